@@ -0,0 +1,136 @@
+//! Picks which attendees of a seating plan a command applies to from the ```--only``` and ```--except```
+//! options, so a single dependency of a big seating plan can be rebuilt or installed on its own.
+use crate::seating_plan::SeatingPlan;
+
+
+/// The attendees a command applies to.
+///
+/// # Fields
+/// * `All` - Every attendee in the seating plan
+/// * `Only` - Only the named attendees
+/// * `Except` - Every attendee apart from the named ones
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum AttendeeFilter {
+    #[default]
+    All,
+    Only(Vec<String>),
+    Except(Vec<String>),
+}
+
+impl AttendeeFilter {
+
+    /// Creates the filter from the names passed to ```--only``` and ```--except```.
+    ///
+    /// # Arguments
+    /// * `only` - The names passed to ```--only```
+    /// * `except` - The names passed to ```--except```
+    ///
+    /// # Returns
+    /// * `Result<AttendeeFilter, String>` - The filter or an error if both options were passed
+    pub fn from_options(only: Vec<String>, except: Vec<String>) -> Result<AttendeeFilter, String> {
+        match (only.is_empty(), except.is_empty()) {
+            (true, true) => Ok(AttendeeFilter::All),
+            (false, true) => Ok(AttendeeFilter::Only(only)),
+            (true, false) => Ok(AttendeeFilter::Except(except)),
+            (false, false) => Err("--only and --except can not be used together".to_string())
+        }
+    }
+
+    /// Checks every name in the filter is an attendee of the seating plan.
+    ///
+    /// # Arguments
+    /// * `seating_plan` - The seating plan the filter is applied to
+    ///
+    /// # Returns
+    /// * `Result<(), String>` - An error listing the unknown names and the valid names
+    pub fn validate(&self, seating_plan: &SeatingPlan) -> Result<(), String> {
+        let names = match self {
+            AttendeeFilter::All => return Ok(()),
+            AttendeeFilter::Only(names) | AttendeeFilter::Except(names) => names
+        };
+        let valid: Vec<&str> = seating_plan.attendees.iter().map(|dependency| dependency.name.as_str()).collect();
+        let unknown: Vec<&str> = names.iter()
+                                      .map(|name| name.as_str())
+                                      .filter(|name| !valid.contains(name))
+                                      .collect();
+        match unknown.is_empty() {
+            true => Ok(()),
+            false => Err(format!("{} not in the seating plan, valid names are {}", unknown.join(", "), valid.join(", ")))
+        }
+    }
+
+    /// Checks whether the filter includes an attendee.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the attendee
+    ///
+    /// # Returns
+    /// * `bool` - Whether the command applies to the attendee
+    pub fn includes(&self, name: &str) -> bool {
+        match self {
+            AttendeeFilter::All => true,
+            AttendeeFilter::Only(names) => names.iter().any(|only| only == name),
+            AttendeeFilter::Except(names) => !names.iter().any(|except| except == name)
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::dependency::Dependency;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn seating_plan() -> SeatingPlan {
+        SeatingPlan {
+            attendees: ["auth", "billing", "search"].iter()
+                                                    .map(|name| Dependency { name: name.to_string(), ..Default::default() })
+                                                    .collect(),
+            venue: "venue".to_string(),
+            command_defaults: None,
+            restart_policy: None,
+            compose_command: None,
+            engine: None,
+            max_docker_ops: None,
+            max_git_ops: None,
+            clone_depth: None,
+            auth: None,
+            git_protocol: None
+        }
+    }
+
+    #[test]
+    fn test_from_options() {
+        assert_eq!(AttendeeFilter::from_options(Vec::new(), Vec::new()), Ok(AttendeeFilter::All));
+        assert_eq!(AttendeeFilter::from_options(names(&["auth"]), Vec::new()), Ok(AttendeeFilter::Only(names(&["auth"]))));
+        assert_eq!(AttendeeFilter::from_options(Vec::new(), names(&["auth"])), Ok(AttendeeFilter::Except(names(&["auth"]))));
+        assert_eq!(
+            AttendeeFilter::from_options(names(&["auth"]), names(&["billing"])),
+            Err("--only and --except can not be used together".to_string())
+        );
+    }
+
+    #[test]
+    fn test_includes() {
+        assert!(AttendeeFilter::All.includes("auth"));
+        assert!(AttendeeFilter::Only(names(&["auth", "search"])).includes("search"));
+        assert!(!AttendeeFilter::Only(names(&["auth", "search"])).includes("billing"));
+        assert!(!AttendeeFilter::Except(names(&["auth"])).includes("auth"));
+        assert!(AttendeeFilter::Except(names(&["auth"])).includes("billing"));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(AttendeeFilter::All.validate(&seating_plan()), Ok(()));
+        assert_eq!(AttendeeFilter::Except(names(&["billing"])).validate(&seating_plan()), Ok(()));
+        assert_eq!(
+            AttendeeFilter::Only(names(&["auth", "payments", "emails"])).validate(&seating_plan()),
+            Err("payments, emails not in the seating plan, valid names are auth, billing, search".to_string())
+        );
+    }
+}
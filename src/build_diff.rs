@@ -0,0 +1,143 @@
+//! Previews the Dockerfile copies made when preparing builds without writing anything. Each copy is compared
+//! against the Dockerfile currently in the build root and reported as up to date, a new file, or a unified diff.
+use similar::TextDiff;
+use std::io::ErrorKind;
+
+use crate::file_handler::CoreFileHandle;
+use crate::wedding_invite::BuildFileCopy;
+
+
+/// What preparing a build file would do to the Dockerfile in the build root.
+///
+/// # Fields
+/// * `UpToDate` - The Dockerfile in the build root is already the Dockerfile for the CPU
+/// * `WouldCreate` - There is no Dockerfile in the build root
+/// * `Changed` - The unified diff from the Dockerfile in the build root to the Dockerfile for the CPU
+#[derive(Debug, PartialEq)]
+pub enum BuildFileChange {
+    UpToDate,
+    WouldCreate,
+    Changed(String),
+}
+
+
+/// Compares the Dockerfile for the CPU against the Dockerfile in the build root.
+///
+/// # Arguments
+/// * `copy` - The Dockerfile copy that preparing the build would make
+/// * `handle` - The file handle used to read both Dockerfiles
+///
+/// # Returns
+/// * `io::Result<BuildFileChange>` - The change or an error if the Dockerfile for the CPU could not be read
+pub fn diff_build_file(copy: &BuildFileCopy, handle: &dyn CoreFileHandle) -> std::io::Result<BuildFileChange> {
+    let new_contents = handle.read_to_string(&copy.from)?;
+    let old_contents = match handle.read_to_string(&copy.to) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(BuildFileChange::WouldCreate),
+        Err(error) => return Err(error)
+    };
+    if old_contents == new_contents {
+        return Ok(BuildFileChange::UpToDate)
+    }
+    let diff = TextDiff::from_lines(&old_contents, &new_contents)
+        .unified_diff()
+        .header(&copy.to.to_string_lossy(), &copy.from.to_string_lossy())
+        .to_string();
+    Ok(BuildFileChange::Changed(diff))
+}
+
+/// Renders the change of a Dockerfile copy for printing.
+///
+/// # Arguments
+/// * `name` - The name of the dependency the Dockerfile belongs to
+/// * `copy` - The Dockerfile copy that preparing the build would make
+/// * `change` - What the copy would do to the Dockerfile in the build root
+///
+/// # Returns
+/// * `String` - The rendered change ending in a new line
+pub fn render_change(name: &str, copy: &BuildFileCopy, change: &BuildFileChange) -> String {
+    let to = copy.to.to_string_lossy();
+    let from = copy.from.to_string_lossy();
+    match change {
+        BuildFileChange::UpToDate => format!("{}: {} is up to date\n", name, to),
+        BuildFileChange::WouldCreate => format!("{}: would create {} from {}\n", name, to, from),
+        BuildFileChange::Changed(diff) => format!("{}: would update {} from {}\n{}", name, to, from, diff)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::file_handler::MockCoreFileHandle;
+    use mockall::predicate::eq;
+    use std::path::{Path, PathBuf};
+
+    fn test_copy() -> BuildFileCopy {
+        BuildFileCopy {
+            from: PathBuf::from("/venue/auth/builds/Dockerfile.x86_64"),
+            to: PathBuf::from("/venue/auth/Dockerfile")
+        }
+    }
+
+    fn mock_handle(existing: Option<&'static str>) -> MockCoreFileHandle {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_read_to_string()
+            .with(eq(Path::new("/venue/auth/builds/Dockerfile.x86_64")))
+            .returning(|_| Ok("FROM rust:1.70\nWORKDIR /app\nRUN cargo build --release\n".to_string()));
+        mock_handle.expect_read_to_string()
+            .with(eq(Path::new("/venue/auth/Dockerfile")))
+            .returning(move |_| match existing {
+                Some(contents) => Ok(contents.to_string()),
+                None => Err(std::io::Error::new(ErrorKind::NotFound, "not found"))
+            });
+        mock_handle.expect_copy().times(0);
+        mock_handle.expect_write().times(0);
+        mock_handle
+    }
+
+    #[test]
+    fn test_diff_build_file_up_to_date() {
+        let handle = mock_handle(Some("FROM rust:1.70\nWORKDIR /app\nRUN cargo build --release\n"));
+        let change = diff_build_file(&test_copy(), &handle).unwrap();
+        assert_eq!(change, BuildFileChange::UpToDate);
+        assert_eq!(render_change("auth", &test_copy(), &change), "auth: /venue/auth/Dockerfile is up to date\n");
+    }
+
+    #[test]
+    fn test_diff_build_file_would_create() {
+        let handle = mock_handle(None);
+        let change = diff_build_file(&test_copy(), &handle).unwrap();
+        assert_eq!(change, BuildFileChange::WouldCreate);
+        assert_eq!(
+            render_change("auth", &test_copy(), &change),
+            "auth: would create /venue/auth/Dockerfile from /venue/auth/builds/Dockerfile.x86_64\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_build_file_changed() {
+        let handle = mock_handle(Some("FROM rust:1.70\nWORKDIR /app\nRUN cargo build\n"));
+        let change = diff_build_file(&test_copy(), &handle).unwrap();
+        assert_eq!(
+            render_change("auth", &test_copy(), &change),
+            "auth: would update /venue/auth/Dockerfile from /venue/auth/builds/Dockerfile.x86_64\n\
+             --- /venue/auth/Dockerfile\n\
+             +++ /venue/auth/builds/Dockerfile.x86_64\n\
+             @@ -1,3 +1,3 @@\n \
+             FROM rust:1.70\n \
+             WORKDIR /app\n\
+             -RUN cargo build\n\
+             +RUN cargo build --release\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_build_file_missing_source() {
+        let mut handle = MockCoreFileHandle::new();
+        handle.expect_read_to_string()
+            .returning(|_| Err(std::io::Error::new(ErrorKind::NotFound, "not found")));
+        assert!(diff_build_file(&test_copy(), &handle).is_err());
+    }
+}
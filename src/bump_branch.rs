@@ -0,0 +1,283 @@
+//! Moves the attendees of a seating plan from one branch to another, such as from ```develop``` to
+//! ```release/1.4``` at release time. The seating plan file is rewritten as text so only the ```branch```
+//! values change, the comments, key order, and quoting of the rest of the file are left as they are. The
+//! rewritten file is parsed again and compared with the original so a layout the rewrite does not understand
+//! is refused instead of being written.
+use crate::seating_plan::SeatingPlan;
+
+
+/// The result of moving the attendees of a seating plan to another branch.
+///
+/// # Fields
+/// * `contents` - The rewritten contents of the seating plan file
+/// * `changed` - The names of the attendees that were moved in the order of the seating plan
+#[derive(Debug, PartialEq)]
+pub struct BranchBump {
+    pub contents: String,
+    pub changed: Vec<String>,
+}
+
+
+/// A ```key: value``` line of an attendee in the seating plan.
+///
+/// # Fields
+/// * `line` - The index of the line in the file
+/// * `value_start` - The byte offset the value starts at in the line
+/// * `value_end` - The byte offset the value ends at in the line, before any comment
+/// * `value` - The value without quotes
+struct AttendeeField {
+    line: usize,
+    value_start: usize,
+    value_end: usize,
+    value: String,
+}
+
+
+/// The ```name``` and ```branch``` lines of an attendee in the seating plan.
+#[derive(Default)]
+struct AttendeeLines {
+    name: Option<AttendeeField>,
+    branch: Option<AttendeeField>,
+}
+
+
+/// Gets the number of spaces a line is indented by.
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+
+/// Reads a ```key: value``` field from a line of an attendee.
+///
+/// # Arguments
+/// * `line` - The line of the seating plan
+/// * `index` - The index of the line in the file
+///
+/// # Returns
+/// * `Option<(String, AttendeeField)>` - The key and the field or ```None``` if the line is not a ```key: value``` line
+fn read_field(line: &str, index: usize) -> Option<(String, AttendeeField)> {
+    let mut start = indent(line);
+    if line[start..].starts_with("- ") {
+        start += 2 + indent(&line[start + 2..]);
+    }
+    let (key, rest) = line[start..].split_once(':')?;
+    let value_start = start + key.len() + 1 + indent(rest);
+    let raw = &line[value_start..];
+    let (value, length) = match raw.chars().next() {
+        Some(quote) if quote == '"' || quote == '\'' => {
+            let end = raw[1..].find(quote)? + 2;
+            (raw[1..end - 1].to_string(), end)
+        },
+        _ => {
+            let value = raw.split(" #").next().unwrap_or("").trim_end();
+            (value.to_string(), value.len())
+        }
+    };
+    Some((key.trim().to_string(), AttendeeField { line: index, value_start, value_end: value_start + length, value }))
+}
+
+
+/// Finds the ```name``` and ```branch``` lines of every attendee in the block style ```attendees``` list.
+///
+/// # Arguments
+/// * `lines` - The lines of the seating plan
+///
+/// # Returns
+/// * `Vec<AttendeeLines>` - The lines of each attendee in the order of the seating plan
+fn find_attendees(lines: &[&str]) -> Vec<AttendeeLines> {
+    let mut attendees = Vec::new();
+    let mut in_attendees = false;
+    let mut item_indent = None;
+
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue
+        }
+        if indent(line) == 0 && !trimmed.starts_with("- ") {
+            in_attendees = trimmed.starts_with("attendees:");
+            continue
+        }
+        if !in_attendees {
+            continue
+        }
+        if trimmed.starts_with("- ") && item_indent.is_none_or(|item_indent| item_indent == indent(line)) {
+            item_indent = Some(indent(line));
+            attendees.push(AttendeeLines::default());
+        }
+        let attendee = match attendees.last_mut() {
+            Some(attendee) => attendee,
+            None => continue
+        };
+        if let Some((key, field)) = read_field(line, index) {
+            match key.as_str() {
+                "name" => attendee.name = Some(field),
+                "branch" => attendee.branch = Some(field),
+                _ => {}
+            }
+        }
+    }
+    attendees
+}
+
+
+/// Renders a branch as a YAML value, keeping the quotes of the value it replaces.
+///
+/// # Arguments
+/// * `old` - The value being replaced as it is written in the file
+/// * `branch` - The new branch
+///
+/// # Returns
+/// * `String` - The branch as it should be written in the file
+fn render_branch(old: &str, branch: &str) -> String {
+    match old.chars().next() {
+        Some('\'') => format!("'{}'", branch.replace('\'', "''")),
+        Some('"') => format!("\"{}\"", branch.replace('\\', "\\\\").replace('"', "\\\"")),
+        _ => match serde_yaml::from_str::<String>(branch) {
+            Ok(parsed) if parsed == branch => branch.to_string(),
+            _ => format!("\"{}\"", branch.replace('\\', "\\\\").replace('"', "\\\""))
+        }
+    }
+}
+
+
+/// Moves the attendees on the ```from``` branch to the ```to``` branch in the contents of a seating plan file.
+///
+/// # Arguments
+/// * `contents` - The contents of the seating plan file
+/// * `file_path` - The path the contents were read from, used in error messages
+/// * `from` - The branch the attendees are moved from
+/// * `to` - The branch the attendees are moved to
+/// * `only` - The names of the attendees to move, empty for every attendee on the ```from``` branch
+///
+/// # Returns
+/// * `Result<BranchBump, String>` - The rewritten contents and the attendees that moved or an error message
+pub fn bump_branches(contents: &str, file_path: &str, from: &str, to: &str, only: &[String]) -> Result<BranchBump, String> {
+    let mut expected = SeatingPlan::parse(contents, file_path)?;
+    for name in only {
+        if !expected.attendees.iter().any(|dependency| &dependency.name == name) {
+            return Err(format!("{} is not an attendee in {}", name, file_path))
+        }
+    }
+    let mut changed = Vec::new();
+    for dependency in expected.attendees.iter_mut() {
+        let selected = only.is_empty() || only.contains(&dependency.name);
+        if selected && dependency.branch.as_deref() == Some(from) {
+            dependency.branch = Some(to.to_string());
+            changed.push(dependency.name.clone());
+        }
+    }
+
+    let mut lines: Vec<String> = contents.split('\n').map(String::from).collect();
+    let line_refs: Vec<&str> = contents.split('\n').collect();
+    for attendee in find_attendees(&line_refs) {
+        let (name, branch) = match (&attendee.name, &attendee.branch) {
+            (Some(name), Some(branch)) => (name, branch),
+            _ => continue
+        };
+        if !changed.contains(&name.value) || branch.value != from {
+            continue
+        }
+        let line = &mut lines[branch.line];
+        let rendered = render_branch(&line[branch.value_start..branch.value_end], to);
+        line.replace_range(branch.value_start..branch.value_end, &rendered);
+    }
+    let rewritten = lines.join("\n");
+
+    match SeatingPlan::parse(&rewritten, file_path) {
+        Ok(seating_plan) if seating_plan == expected => Ok(BranchBump { contents: rewritten, changed }),
+        _ => Err(format!("Could not rewrite the branches in {} without changing the rest of the file, edit it by hand", file_path))
+    }
+}
+
+
+/// Renders the attendees that were moved to another branch.
+///
+/// # Arguments
+/// * `changed` - The names of the attendees that were moved
+/// * `from` - The branch the attendees were moved from
+/// * `to` - The branch the attendees were moved to
+///
+/// # Returns
+/// * `String` - A line for each attendee that was moved or a line saying nothing was on the ```from``` branch
+pub fn render_bump_report(changed: &[String], from: &str, to: &str) -> String {
+    if changed.is_empty() {
+        return format!("No attendees are on branch {}\n", from)
+    }
+    changed.iter()
+           .map(|name| format!("{}: {} -> {}\n", name, from, to))
+           .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::fs;
+
+    static PLAN_PATH: &str = "tests/bump_branch_seating_plan.yml";
+
+    fn plan() -> String {
+        fs::read_to_string(PLAN_PATH).unwrap()
+    }
+
+    #[test]
+    fn test_bump_branches() {
+        let bump = bump_branches(&plan(), PLAN_PATH, "develop", "release/1.4", &[]).unwrap();
+        assert_eq!(bump.contents, fs::read_to_string("tests/bump_branch_seating_plan_bumped.yml").unwrap());
+        assert_eq!(bump.changed, vec!["auth".to_string(), "billing".to_string(), "search".to_string()]);
+    }
+
+    #[test]
+    fn test_bump_branches_only() {
+        let bump = bump_branches(&plan(), PLAN_PATH, "develop", "release/1.4", &["billing".to_string(), "emails".to_string()]).unwrap();
+        assert_eq!(bump.changed, vec!["billing".to_string()]);
+
+        let seating_plan = SeatingPlan::parse(&bump.contents, PLAN_PATH).unwrap();
+        let branches: Vec<Option<&str>> = seating_plan.attendees.iter().map(|dependency| dependency.branch.as_deref()).collect();
+        assert_eq!(branches, vec![Some("develop"), Some("release/1.4"), Some("main"), None, Some("develop")]);
+        assert_eq!(bump.contents.lines().count(), plan().lines().count());
+    }
+
+    #[test]
+    fn test_bump_branches_unknown_attendee() {
+        assert_eq!(
+            bump_branches(&plan(), PLAN_PATH, "develop", "release/1.4", &["payments".to_string()]),
+            Err("payments is not an attendee in tests/bump_branch_seating_plan.yml".to_string())
+        );
+    }
+
+    #[test]
+    fn test_bump_branches_nothing_to_move() {
+        let bump = bump_branches(&plan(), PLAN_PATH, "hotfix", "release/1.4", &[]).unwrap();
+        assert_eq!(bump.contents, plan());
+        assert!(bump.changed.is_empty());
+    }
+
+    #[test]
+    fn test_bump_branches_flow_style() {
+        let contents = "attendees: [{name: auth, url: https://example.com/auth, branch: develop}]\nvenue: ./venue\n";
+        assert_eq!(
+            bump_branches(contents, "plan.yml", "develop", "main", &[]),
+            Err("Could not rewrite the branches in plan.yml without changing the rest of the file, edit it by hand".to_string())
+        );
+    }
+
+    #[test]
+    fn test_render_branch() {
+        assert_eq!(render_branch("develop", "release/1.4"), "release/1.4");
+        assert_eq!(render_branch("'develop'", "release/1.4"), "'release/1.4'");
+        assert_eq!(render_branch("\"develop\"", "release/1.4"), "\"release/1.4\"");
+        assert_eq!(render_branch("develop", "#1"), "\"#1\"");
+    }
+
+    #[test]
+    fn test_render_bump_report() {
+        assert_eq!(
+            render_bump_report(&["auth".to_string(), "billing".to_string()], "develop", "release/1.4"),
+            "auth: develop -> release/1.4\nbilling: develop -> release/1.4\n"
+        );
+        assert_eq!(render_bump_report(&[], "develop", "release/1.4"), "No attendees are on branch develop\n");
+    }
+}
@@ -0,0 +1,46 @@
+//! Defines the clock used for timing out commands and timestamping usage stats so the passing of time can be
+//! mocked in tests.
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+
+/// Defines the interface for reading the time and waiting.
+#[mockall::automock]
+pub trait Clock {
+    /// Gets the current time.
+    /// 
+    /// # Returns
+    /// * `Instant` - The current time
+    fn now(&self) -> Instant;
+
+    /// Gets the current wall clock time.
+    /// 
+    /// # Returns
+    /// * `SystemTime` - The current wall clock time
+    fn system_time(&self) -> SystemTime;
+
+    /// Blocks the current thread for a duration.
+    /// 
+    /// # Arguments
+    /// * `duration` - How long to block for
+    fn sleep(&self, duration: Duration);
+}
+
+
+/// The clock backed by the system time.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration)
+    }
+}
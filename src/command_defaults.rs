@@ -0,0 +1,229 @@
+//! Resolves the flags for a command by merging the flags passed in through the command line over the
+//! ```command_defaults``` defined in the seating plan.
+//! ## Example Command Defaults
+//! Below is an example of the ```command_defaults``` section in a seating plan:
+//! ```yaml
+//! command_defaults:
+//!   run:
+//!     detach: true
+//!     wait: true
+//!   build:
+//!     pull: true
+//! ```
+//! Flags passed through the command line always win over the seating plan, and the seating plan wins over
+//! the built in default of ```false```.
+use std::collections::HashMap;
+use std::fmt;
+
+
+/// The flags that can be given a default in the ```command_defaults``` section of the seating plan.
+pub const KNOWN_FLAGS: [&str; 3] = ["detach", "wait", "pull"];
+
+
+/// The ```command_defaults``` section of the seating plan mapping command names to their default flags.
+pub type CommandDefaults = HashMap<String, HashMap<String, bool>>;
+
+
+/// Where the value of a resolved flag came from.
+/// 
+/// # Fields
+/// * `CommandLine` - The flag was passed in through the command line
+/// * `SeatingPlan` - The flag was defined in the ```command_defaults``` of the seating plan
+/// * `BuiltIn` - The flag was not defined anywhere so the built in default was used
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlagSource {
+    CommandLine,
+    SeatingPlan,
+    BuiltIn,
+}
+
+impl fmt::Display for FlagSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FlagSource::CommandLine => write!(f, "command line"),
+            FlagSource::SeatingPlan => write!(f, "seating plan"),
+            FlagSource::BuiltIn => write!(f, "built in default"),
+        }
+    }
+}
+
+
+/// A flag with its effective value and where the value came from.
+/// 
+/// # Fields
+/// * `name` - The name of the flag
+/// * `value` - The effective value of the flag
+/// * `source` - Where the effective value came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedFlag {
+    pub name: String,
+    pub value: bool,
+    pub source: FlagSource,
+}
+
+
+/// The effective flags for a command.
+/// 
+/// # Fields
+/// * `command` - The command the flags were resolved for
+/// * `flags` - The resolved flags in the order of ```KNOWN_FLAGS```
+#[derive(Debug, PartialEq)]
+pub struct ResolvedFlags {
+    pub command: String,
+    pub flags: Vec<ResolvedFlag>,
+}
+
+impl ResolvedFlags {
+
+    /// Gets the effective value of a flag.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the flag
+    /// 
+    /// # Returns
+    /// * `bool` - The value of the flag, ```false``` if the flag is not known
+    pub fn get(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag.name == name && flag.value)
+    }
+
+    /// Renders the flags as a table showing where each value came from.
+    /// 
+    /// # Returns
+    /// * `String` - The rendered table
+    pub fn render(&self) -> String {
+        let mut output = format!("{}:\n", self.command);
+        for flag in &self.flags {
+            output.push_str(&format!("  {:<8} {:<6} ({})\n", flag.name, flag.value, flag.source));
+        }
+        output
+    }
+}
+
+
+/// Merges the command line flags over the command defaults of the seating plan.
+/// 
+/// # Arguments
+/// * `command` - The command to resolve the flags for
+/// * `cli_flags` - The flags explicitly passed in through the command line
+/// * `command_defaults` - The ```command_defaults``` section of the seating plan
+/// 
+/// # Returns
+/// * `ResolvedFlags` - The effective flags for the command
+pub fn resolve_flags(command: &str, cli_flags: &HashMap<String, bool>, 
+                     command_defaults: &Option<CommandDefaults>) -> ResolvedFlags {
+    let plan_flags = command_defaults.as_ref().and_then(|defaults| defaults.get(command));
+    let mut flags = Vec::new();
+
+    for name in KNOWN_FLAGS {
+        let (value, source) = match cli_flags.get(name) {
+            Some(value) => (*value, FlagSource::CommandLine),
+            None => match plan_flags.and_then(|plan_flags| plan_flags.get(name)) {
+                Some(value) => (*value, FlagSource::SeatingPlan),
+                None => (false, FlagSource::BuiltIn)
+            }
+        };
+        flags.push(ResolvedFlag { name: name.to_string(), value, source });
+    }
+    ResolvedFlags { command: command.to_string(), flags }
+}
+
+/// Finds the keys in the command defaults that are not known flags.
+/// 
+/// # Arguments
+/// * `command_defaults` - The ```command_defaults``` section of the seating plan
+/// 
+/// # Returns
+/// * `Vec<String>` - The unknown keys in the form ```command.flag``` sorted alphabetically
+pub fn unknown_keys(command_defaults: &Option<CommandDefaults>) -> Vec<String> {
+    let mut unknown = Vec::new();
+    if let Some(defaults) = command_defaults {
+        for (command, flags) in defaults {
+            for name in flags.keys() {
+                if !KNOWN_FLAGS.contains(&name.as_str()) {
+                    unknown.push(format!("{}.{}", command, name));
+                }
+            }
+        }
+    }
+    unknown.sort();
+    unknown
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    struct ResolveCase {
+        command: &'static str,
+        cli: Vec<(&'static str, bool)>,
+        plan: Option<Vec<(&'static str, bool)>>,
+        flag: &'static str,
+        value: bool,
+        source: FlagSource,
+    }
+
+    fn to_map(flags: &[(&str, bool)]) -> HashMap<String, bool> {
+        flags.iter().map(|(name, value)| (name.to_string(), *value)).collect()
+    }
+
+    fn to_defaults(defaults: Vec<(&str, Vec<(&str, bool)>)>) -> CommandDefaults {
+        defaults.into_iter().map(|(command, flags)| (command.to_string(), to_map(&flags))).collect()
+    }
+
+    #[test]
+    fn test_resolve_flags() {
+        let cases = vec![
+            ResolveCase { command: "run", cli: vec![], plan: None, 
+                          flag: "detach", value: false, source: FlagSource::BuiltIn },
+            ResolveCase { command: "run", cli: vec![], plan: Some(vec![("detach", true)]), 
+                          flag: "detach", value: true, source: FlagSource::SeatingPlan },
+            ResolveCase { command: "run", cli: vec![("detach", true)], plan: None, 
+                          flag: "detach", value: true, source: FlagSource::CommandLine },
+            ResolveCase { command: "run", cli: vec![("detach", false)], plan: Some(vec![("detach", true)]), 
+                          flag: "detach", value: false, source: FlagSource::CommandLine },
+            ResolveCase { command: "run", cli: vec![("detach", true)], plan: Some(vec![("wait", true)]), 
+                          flag: "wait", value: true, source: FlagSource::SeatingPlan },
+            ResolveCase { command: "build", cli: vec![], plan: Some(vec![("pull", true)]), 
+                          flag: "pull", value: false, source: FlagSource::BuiltIn },
+            ResolveCase { command: "run", cli: vec![], plan: Some(vec![("pull", false)]), 
+                          flag: "pull", value: false, source: FlagSource::SeatingPlan },
+        ];
+
+        for case in cases {
+            // the plan defaults in each case are always defined for the run command
+            let defaults = case.plan.map(|plan| to_defaults(vec![("run", plan)]));
+            let resolved = resolve_flags(case.command, &to_map(&case.cli), &defaults);
+            let flag = resolved.flags.iter().find(|flag| flag.name == case.flag).unwrap();
+            assert_eq!(flag.value, case.value, "{} {:?}", case.command, flag);
+            assert_eq!(flag.source, case.source, "{} {:?}", case.command, flag);
+            assert_eq!(resolved.get(case.flag), case.value);
+        }
+    }
+
+    #[test]
+    fn test_unknown_keys() {
+        let cases = vec![
+            (None, vec![]),
+            (Some(vec![("run", vec![("detach", true), ("wait", true)])]), vec![]),
+            (Some(vec![("run", vec![("detatch", true)])]), vec!["run.detatch"]),
+            (Some(vec![("build", vec![("pull", true), ("no_cash", true)]), ("run", vec![("wiat", true)])]), 
+             vec!["build.no_cash", "run.wiat"]),
+        ];
+
+        for (defaults, expected) in cases {
+            assert_eq!(unknown_keys(&defaults.map(to_defaults)), expected);
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let defaults = to_defaults(vec![("run", vec![("wait", true)])]);
+        let resolved = resolve_flags("run", &to_map(&[("detach", true)]), &Some(defaults));
+        assert_eq!(
+            resolved.render(),
+            "run:\n  detach   true   (command line)\n  wait     true   (seating plan)\n  pull     false  (built in default)\n"
+        );
+    }
+}
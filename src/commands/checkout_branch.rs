@@ -1,5 +1,5 @@
 //! This command checks out a branch in a git repository.
-use super::command_runner::CoreRunner;
+use super::command_runner::{check_status, CoreRunner};
 use std::path::Path;
 
 
@@ -34,17 +34,16 @@ impl CheckoutBranchCommand {
         }
     }
 
-    /// Runs the checkout branch command.
+    /// Runs the checkout branch command in the repository without a shell, so the path can hold spaces.
     /// 
     /// # Arguments
     /// * `runner` - The command runner to for the command being run
     /// 
     /// # Returns
-    /// The output of the command
+    /// The output of the command or an error containing the git stderr if the command failed
     pub fn run(&self, runner: &dyn CoreRunner) -> Result<std::process::Output, std::io::Error> {
-        let root_path = Path::new(&self.path_to_repo).join(&self.repo_name).to_string_lossy().to_string();
-        let checkout_cmd = format!("cd {} && git checkout {}", root_path, self.branch_name);
-        runner.run(&checkout_cmd)
+        let root_path = Path::new(&self.path_to_repo).join(&self.repo_name);
+        check_status(runner.run_in_dir(&root_path, "git", &["checkout", &self.branch_name])?)
     }
 }
 
@@ -53,7 +52,6 @@ impl CheckoutBranchCommand {
 mod tests {
 
     use super::*;
-    use mockall::predicate::eq;
     use std::os::unix::process::ExitStatusExt;
     use super::super::command_runner::MockCoreRunner;
     use std::process::Output;
@@ -70,9 +68,12 @@ mod tests {
     fn test_run() {
         let command = CheckoutBranchCommand::new("test_branch".to_string(), "/path/to/repo".to_string(), "test_repo".to_string());
         let mut mock_runner = MockCoreRunner::new();
-        mock_runner.expect_run()
-            .with(eq("cd /path/to/repo/test_repo && git checkout test_branch".to_string()))
-            .returning(|_| {
+        mock_runner.expect_run_in_dir()
+            .withf(|dir, program, args| {
+                dir == Path::new("/path/to/repo/test_repo") && program == "git" && args == ["checkout", "test_branch"]
+            })
+            .times(1)
+            .returning(|_, _, _| {
                 Ok(Output {
                     status: std::process::ExitStatus::from_raw(0),
                     stdout: Vec::new(),
@@ -84,4 +85,21 @@ mod tests {
         mock_runner.checkpoint(); // Ensure all expected calls have been made
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_run_failure() {
+        let command = CheckoutBranchCommand::new("missing_branch".to_string(), "/path/to/repo".to_string(), "test_repo".to_string());
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .returning(|_, _, _| {
+                Ok(Output {
+                    status: std::process::ExitStatus::from_raw(1 << 8),
+                    stdout: Vec::new(),
+                    stderr: b"error: pathspec 'missing_branch' did not match any file(s) known to git".to_vec(),
+                })
+            });
+        let result = command.run(&mock_runner);
+        assert!(result.unwrap_err().to_string().contains("pathspec 'missing_branch' did not match"));
+        mock_runner.checkpoint();
+    }
+
+}
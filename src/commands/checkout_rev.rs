@@ -0,0 +1,138 @@
+//! This command checks out a tag or a commit in a git repository.
+use super::command_runner::{check_status, CoreRunner};
+use std::path::Path;
+
+
+/// A command to checkout a tag or a commit in a repository.
+/// 
+/// # Fields
+/// * `rev` - The tag or commit SHA to checkout
+/// * `is_tag` - Whether the rev is a tag, if ```true``` the tags are fetched before the checkout
+/// * `path_to_repo` - The path to the repository to checkout the rev in
+/// * `repo_name` - The name of the repository to checkout the rev in
+pub struct CheckoutRevCommand {
+    pub rev: String,
+    pub is_tag: bool,
+    pub path_to_repo: String,
+    pub repo_name: String
+}
+
+impl CheckoutRevCommand {
+
+    /// Creates a new CheckoutRevCommand struct.
+    /// 
+    /// # Arguments
+    /// * `rev` - The tag or commit SHA to checkout
+    /// * `is_tag` - Whether the rev is a tag
+    /// * `path_to_repo` - The path to the repository to checkout the rev in
+    /// * `repo_name` - The name of the repository to checkout the rev in
+    /// 
+    /// # Returns
+    /// A new CheckoutRevCommand struct
+    pub fn new(rev: String, is_tag: bool, path_to_repo: String, repo_name: String) -> Self {
+        Self {
+            rev,
+            is_tag,
+            path_to_repo,
+            repo_name
+        }
+    }
+
+    /// Runs the checkout rev command in the repository without a shell, so the path can hold spaces. A tag is
+    /// fetched first so a tag pushed after the clone can be checked out.
+    /// 
+    /// # Arguments
+    /// * `runner` - The command runner to for the command being run
+    /// 
+    /// # Returns
+    /// The output of the command or an error containing the git stderr if the command failed
+    pub fn run(&self, runner: &dyn CoreRunner) -> Result<std::process::Output, std::io::Error> {
+        let root_path = Path::new(&self.path_to_repo).join(&self.repo_name);
+        if !self.is_tag {
+            return check_status(runner.run_in_dir(&root_path, "git", &["checkout", &self.rev])?)
+        }
+        check_status(runner.run_in_dir(&root_path, "git", &["fetch", "--tags"])?)?;
+        let tag = format!("tags/{}", self.rev);
+        check_status(runner.run_in_dir(&root_path, "git", &["checkout", &tag])?)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use mockall::Sequence;
+    use std::os::unix::process::ExitStatusExt;
+    use super::super::command_runner::MockCoreRunner;
+    use std::process::Output;
+
+    fn successful_output() -> Output {
+        Output {
+            status: std::process::ExitStatus::from_raw(0),
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+        }
+    }
+
+    fn expect_commands(dir: &'static str, commands: &[&'static [&'static str]]) -> MockCoreRunner {
+        let mut mock_runner = MockCoreRunner::new();
+        let mut sequence = Sequence::new();
+        for expected in commands.iter().copied() {
+            mock_runner.expect_run_in_dir()
+                .withf(move |path, program, args| path == Path::new(dir) && program == "git" && args == expected)
+                .times(1)
+                .in_sequence(&mut sequence)
+                .returning(|_, _, _| Ok(successful_output()));
+        }
+        mock_runner
+    }
+
+    #[test]
+    fn test_run_tag() {
+        let command = CheckoutRevCommand::new("v1.4.0".to_string(), true, "/path/to/repo".to_string(), "test_repo".to_string());
+        let mut mock_runner = expect_commands(
+            "/path/to/repo/test_repo", &[&["fetch", "--tags"], &["checkout", "tags/v1.4.0"]]
+        );
+        assert!(command.run(&mock_runner).is_ok());
+        mock_runner.checkpoint();
+    }
+
+    #[test]
+    fn test_run_commit() {
+        let command = CheckoutRevCommand::new("3f5e2a1".to_string(), false, "/path/to/repo".to_string(), "test_repo".to_string());
+        let mut mock_runner = expect_commands("/path/to/repo/test_repo", &[&["checkout", "3f5e2a1"]]);
+        assert!(command.run(&mock_runner).is_ok());
+        mock_runner.checkpoint();
+    }
+
+    #[test]
+    fn test_run_path_with_spaces() {
+        let command = CheckoutRevCommand::new("v1.4.0".to_string(), true, "my projects/venue; rm -rf".to_string(), "test_repo".to_string());
+        let mut mock_runner = expect_commands(
+            "my projects/venue; rm -rf/test_repo", &[&["fetch", "--tags"], &["checkout", "tags/v1.4.0"]]
+        );
+        assert!(command.run(&mock_runner).is_ok());
+        mock_runner.checkpoint();
+    }
+
+    #[test]
+    fn test_run_failure() {
+        let command = CheckoutRevCommand::new("v9.9.9".to_string(), true, "/path/to/repo".to_string(), "test_repo".to_string());
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .withf(|_, _, args| args == ["fetch", "--tags"])
+            .returning(|_, _, _| Ok(successful_output()));
+        mock_runner.expect_run_in_dir()
+            .withf(|_, _, args| args == ["checkout", "tags/v9.9.9"])
+            .returning(|_, _, _| {
+                Ok(Output {
+                    status: std::process::ExitStatus::from_raw(1 << 8),
+                    stdout: Vec::new(),
+                    stderr: b"error: pathspec 'tags/v9.9.9' did not match any file(s) known to git".to_vec(),
+                })
+            });
+        assert!(command.run(&mock_runner).unwrap_err().to_string().contains("pathspec 'tags/v9.9.9'"));
+        mock_runner.checkpoint();
+    }
+}
@@ -1,11 +1,23 @@
 //! Defines the implementation of the CoreRunner trait. This trait is used to run commands and docker commands.
-use std::process::{Command, Output, Stdio};
+use std::fmt;
+use std::path::Path;
+use std::process::{Command, ExitStatus, Output, Stdio};
 use std::io::prelude::*;
+use std::os::unix::process::CommandExt;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use crate::clock::SystemClock;
+use crate::compose_file::shell_quote;
+use crate::error::WedpError;
+use crate::logging;
+use super::timeout::{read_in_background, runs_until_stopped, timeout_per_command, wait_with_timeout};
 
 
-/// Defines the interface for running commands and docker commands.
+/// Defines the interface for running commands and docker commands. Runners are shared between the threads
+/// installing the dependencies so they must be ```Send``` and ```Sync```.
 #[mockall::automock]
-pub trait CoreRunner {
+pub trait CoreRunner: Send + Sync {
     /// Runs a command and returns the output.
     /// 
     /// # Arguments
@@ -15,13 +27,166 @@ pub trait CoreRunner {
     /// * `Result<Output, std::io::Error>` - The output of the command or an error
     fn run(&self, command: &String) -> Result<Output, std::io::Error>;
 
-    /// Runs a docker command and loops until stopped printing outputs of the docker command in realtime.
+    /// Runs a program in a directory without a shell, so the directory and the arguments are passed as they are
+    /// however many spaces or shell characters they hold.
     /// 
     /// # Arguments
-    /// * `command` - The command to run on the docker files 
+    /// * `dir` - The directory the program is run in
+    /// * `program` - The program to run
+    /// * `args` - The arguments passed to the program
+    /// 
+    /// # Returns
+    /// * `Result<Output, std::io::Error>` - The output of the program or an error if it could not be run
+    // mockall needs the lifetime of the arguments named to mock the method
+    #[allow(clippy::needless_lifetimes)]
+    fn run_in_dir<'a>(&self, dir: &Path, program: &str, args: &[&'a str]) -> Result<Output, std::io::Error>;
+
+    /// Runs a program in a directory without a shell like ```run_in_dir```, with environment variables set on
+    /// the program only. The environment is never printed or traced, so it can carry secrets that must not show
+    /// up in the arguments of the process.
+    /// 
+    /// # Arguments
+    /// * `dir` - The directory the program is run in
+    /// * `program` - The program to run
+    /// * `args` - The arguments passed to the program
+    /// * `env` - The names and values of the environment variables set on the program
+    /// 
+    /// # Returns
+    /// * `Result<Output, std::io::Error>` - The output of the program or an error if it could not be run
+    // mockall needs the lifetime of the arguments named to mock the method
+    #[allow(clippy::needless_lifetimes)]
+    fn run_in_dir_with_env<'a>(&self, dir: &Path, program: &str, args: &[&'a str], env: &[(String, String)]) -> Result<Output, std::io::Error>;
+
+    /// Runs a docker command without a shell and loops until stopped printing outputs of the docker command in
+    /// realtime.
+    /// 
+    /// # Arguments
+    /// * `command` - The subcommand run on the docker files such as ``` up -d```, split on whitespace so it must
+    ///   only hold fixed words and checked names like a ```ComposeAction```
     /// * `error_message` - The error message to print if the command fails
-    /// * `command_string` - The string to append the output of the command to
-    fn run_docker_command(&self, command: &str, error_message: &str, command_string: &mut String) -> ();
+    /// * `program_args` - The program and the arguments the subcommand is appended to
+    /// 
+    /// # Returns
+    /// * `Result<ExitStatus, std::io::Error>` - The exit status of the docker command or an error if it could not
+    ///   be started or waited on
+    fn run_docker_command(&self, command: &str, error_message: &str, program_args: &ProgramArgs) -> Result<ExitStatus, std::io::Error>;
+
+    /// Runs a command that the user interacts with such as an editor, the command inherits the terminal.
+    /// 
+    /// # Arguments
+    /// * `command` - The command to run
+    /// 
+    /// # Returns
+    /// * `Result<ExitStatus, std::io::Error>` - The exit status of the command or an error if it could not start
+    fn run_interactive(&self, command: &str) -> Result<ExitStatus, std::io::Error>;
+}
+
+/// A program and its arguments, run without a shell so every argument reaches the program as it is however
+/// many spaces or shell characters it holds. It is only rendered as a shell command to print and trace it.
+/// 
+/// # Fields
+/// * `program` - The program to run
+/// * `args` - The arguments passed to the program
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgramArgs {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl ProgramArgs {
+
+    /// Creates a new ProgramArgs struct.
+    /// 
+    /// # Arguments
+    /// * `program` - The program to run
+    /// * `args` - The arguments passed to the program
+    /// 
+    /// # Returns
+    /// A new ProgramArgs struct
+    pub fn new(program: &str, args: Vec<String>) -> Self {
+        ProgramArgs { program: program.to_string(), args }
+    }
+
+    /// Appends a subcommand such as ``` up -d``` split on whitespace.
+    /// 
+    /// # Arguments
+    /// * `command` - The subcommand made of fixed words and checked names
+    /// 
+    /// # Returns
+    /// * `ProgramArgs` - The program with the words of the subcommand appended to the arguments
+    pub fn with_command(&self, command: &str) -> ProgramArgs {
+        let mut program_args = self.clone();
+        program_args.args.extend(command.split_whitespace().map(String::from));
+        program_args
+    }
+
+    /// Borrows the arguments in the form ```run_in_dir``` takes them.
+    /// 
+    /// # Returns
+    /// * `Vec<&str>` - The arguments passed to the program
+    pub fn arg_refs(&self) -> Vec<&str> {
+        self.args.iter().map(String::as_str).collect()
+    }
+}
+
+impl fmt::Display for ProgramArgs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.program)?;
+        self.args.iter().try_for_each(|arg| write!(f, " {}", shell_quote(arg)))
+    }
+}
+
+/// Renders a program run in a directory as the shell command it is equivalent to, for printing and tracing it.
+/// 
+/// # Arguments
+/// * `dir` - The directory the program is run in
+/// * `program` - The program to run
+/// * `args` - The arguments passed to the program
+/// 
+/// # Returns
+/// * `String` - The command in the form ```cd dir && program args``` with the directory and arguments quoted
+pub fn render_in_dir(dir: &Path, program: &str, args: &[&str]) -> String {
+    let mut command = format!("cd {} && {}", shell_quote(&dir.to_string_lossy()), program);
+    for arg in args {
+        command.push(' ');
+        command.push_str(&shell_quote(arg));
+    }
+    command
+}
+
+/// Turns an unsuccessful command output into an error containing the stderr of the command.
+/// 
+/// # Arguments
+/// * `output` - The output of the command
+/// 
+/// # Returns
+/// * `Result<Output, std::io::Error>` - The output if the command succeeded or an error with the stderr text
+pub fn check_status(output: Output) -> Result<Output, std::io::Error> {
+    if output.status.success() {
+        return Ok(output)
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    Err(std::io::Error::other(format!("command failed with {}: {}", output.status, stderr)))
+}
+
+/// Runs a docker command and turns it into an error if it could not be started or did not succeed.
+/// 
+/// # Arguments
+/// * `runner` - The runner the command is run with
+/// * `command` - The command to run on the docker files
+/// * `error_message` - The start of the error if the command fails
+/// * `program_args` - The docker-compose command the command is appended to
+/// 
+/// # Returns
+/// * `Result<(), WedpError>` - A ```DockerCommand``` error with the exit status of the command if it did not succeed
+pub fn run_docker_checked(runner: &dyn CoreRunner, command: &str, error_message: &str, program_args: &ProgramArgs) -> Result<(), WedpError> {
+    let command_string = program_args.with_command(command).to_string();
+    let reason = match runner.run_docker_command(command, error_message, program_args) {
+        Ok(status) if status.success() => return Ok(()),
+        Ok(status) => format!("{} exited with {}", command_string, status),
+        Err(error) => error.to_string()
+    };
+    Err(WedpError::DockerCommand { message: error_message.to_string(), command: command_string, reason })
 }
 
 /// Main implementation for the CoreRunner trait. This struct should be passed into functions that need to run commands.
@@ -90,57 +255,185 @@ impl CoreRunner for CommandRunner {
     /// # Returns
     /// * `Result<Output, std::io::Error>` - The output of the command
     fn run(&self, command: &String) -> Result<Output, std::io::Error> {
-        Command::new("sh").arg("-c").arg(command).output()
+        logging::command(command);
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        output_with_timeout(shell)
+    }
+
+    /// Runs a program in a directory without a shell.
+    /// 
+    /// # Arguments
+    /// * `dir` - The directory the program is run in
+    /// * `program` - The program to run
+    /// * `args` - The arguments passed to the program
+    /// 
+    /// # Returns
+    /// * `Result<Output, std::io::Error>` - The output of the program
+    fn run_in_dir(&self, dir: &Path, program: &str, args: &[&str]) -> Result<Output, std::io::Error> {
+        logging::command(&render_in_dir(dir, program, args));
+        let mut command = Command::new(program);
+        command.args(args).current_dir(dir);
+        output_with_timeout(command)
+    }
+
+    /// Runs a program in a directory without a shell with environment variables set on the program only.
+    /// 
+    /// # Arguments
+    /// * `dir` - The directory the program is run in
+    /// * `program` - The program to run
+    /// * `args` - The arguments passed to the program
+    /// * `env` - The names and values of the environment variables set on the program
+    /// 
+    /// # Returns
+    /// * `Result<Output, std::io::Error>` - The output of the program
+    fn run_in_dir_with_env(&self, dir: &Path, program: &str, args: &[&str], env: &[(String, String)]) -> Result<Output, std::io::Error> {
+        logging::command(&render_in_dir(dir, program, args));
+        let mut command = Command::new(program);
+        command.args(args).current_dir(dir).envs(env.iter().map(|(name, value)| (name, value)));
+        output_with_timeout(command)
     }
 
-    /// Runs a docker command and loops until stopped printing outputs of the docker command in realtime.
+    /// Runs a docker command without a shell and loops until stopped printing outputs of the docker command in
+    /// realtime.
     /// 
     /// # Arguments
     /// * `command` - The command to run on the docker files
     /// * `error_message` - The error message to print if the command fails
-    /// * `command_string` - The string to append the output of the command to
-    fn run_docker_command(&self, command: &str, error_message: &str, command_string: &mut String) {
-        command_string.push_str(command);
-
-        let mut command = Command::new("bash").arg("-c")
-                                                                     .arg(command_string)
-                                                                     .stdout(Stdio::piped())
-                                                                     .stderr(Stdio::piped()).spawn()
-                                                                     .expect(error_message);
-        let stdout = command.stdout.take().unwrap();
-        let stderr = command.stderr.take().unwrap();
-        let mut stdout_reader = std::io::BufReader::new(stdout).lines();
-        let mut stderr_reader = std::io::BufReader::new(stderr).lines();
-
-        loop {
-            let mut output = String::new();
-            if let Some(line) = stdout_reader.next() {
-                let unwrapped_line = line.unwrap();
-                println!("{}", &unwrapped_line);
-                output.push_str(&unwrapped_line);
-            }
-            if let Some(line) = stderr_reader.next() {
-                let unwrapped_line = line.unwrap();
-                println!("{}", &unwrapped_line);
-                output.push_str(&unwrapped_line);
-            }
-    
-            if output.is_empty() {
-                break;
-            } else {
-                println!("{}", output);
-            }
+    /// * `program_args` - The program and the arguments the command is appended to
+    /// 
+    /// # Returns
+    /// * `ExitStatus` - The exit status of the docker command
+    fn run_docker_command(&self, command: &str, error_message: &str, program_args: &ProgramArgs) -> Result<ExitStatus, std::io::Error> {
+        let timeout = timeout_per_command().filter(|_| !runs_until_stopped(command));
+        let program_args = program_args.with_command(command);
+        logging::command(&program_args.to_string());
+        stream_command(&program_args, timeout, error_message, std::io::stdout())
+    }
+
+    /// Runs a command that the user interacts with such as an editor, the command inherits the terminal.
+    /// 
+    /// # Arguments
+    /// * `command` - The command to run
+    /// 
+    /// # Returns
+    /// * `Result<ExitStatus, std::io::Error>` - The exit status of the command or an error if it could not start
+    fn run_interactive(&self, command: &str) -> Result<ExitStatus, std::io::Error> {
+        logging::command(command);
+        Command::new("sh").arg("-c").arg(command)
+                          .stdin(Stdio::inherit())
+                          .stdout(Stdio::inherit())
+                          .stderr(Stdio::inherit())
+                          .status()
+    }
+}
+
+
+/// Runs a command and collects its output, killing it once it runs past the ```--timeout``` of each command.
+/// Commands with a timeout run in their own process group so the whole group can be killed.
+/// 
+/// # Arguments
+/// * `command` - The command to run
+/// 
+/// # Returns
+/// * `Result<Output, std::io::Error>` - The output of the command or an error if it could not be started or timed out
+fn output_with_timeout(mut command: Command) -> Result<Output, std::io::Error> {
+    let timeout = match timeout_per_command() {
+        Some(timeout) => timeout,
+        None => return command.output()
+    };
+    let mut child = command.process_group(0)
+                           .stdout(Stdio::piped())
+                           .stderr(Stdio::piped()).spawn()?;
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+    let status = wait_with_timeout(&mut child, timeout, &SystemClock)?;
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default()
+    })
+}
+
+
+/// Runs a command forwarding each line of its stdout and stderr to the output as soon as it arrives, only
+/// returning once the command has exited and both pipes have been read to the end. Commands with a timeout run
+/// in their own process group so the whole group can be killed, the others stay in the process group of wedp so
+/// Ctrl+C reaches them.
+/// 
+/// # Arguments
+/// * `program_args` - The program to run and its arguments
+/// * `timeout` - The longest the command is allowed to run for, ```None``` to wait until it exits
+/// * `error_message` - The error message to print if the command times out
+/// * `output` - Where the lines of the command are written
+/// 
+/// # Returns
+/// * `Result<ExitStatus, std::io::Error>` - The exit status of the command or an error if it could not be
+///   started or waited on
+fn stream_command<W: Write + Send + 'static>(program_args: &ProgramArgs, timeout: Option<Duration>, error_message: &str, output: W) -> Result<ExitStatus, std::io::Error> {
+    let mut command = Command::new(&program_args.program);
+    command.args(&program_args.args).stdout(Stdio::piped()).stderr(Stdio::piped());
+    if timeout.is_some() {
+        command.process_group(0);
+    }
+    let mut child = command.spawn()?;
+    let output = Arc::new(Mutex::new(output));
+    let stdout = forward_in_background(child.stdout.take(), output.clone());
+    let stderr = forward_in_background(child.stderr.take(), output);
+
+    if let Some(timeout) = timeout {
+        if let Err(error) = wait_with_timeout(&mut child, timeout, &SystemClock) {
+            eprintln!("{}: {}", error_message, error);
         }
     }
+    let status = child.wait();
+    let _ = stdout.join();
+    let _ = stderr.join();
+    status
 }
 
 
+/// Forwards each line of a pipe of a child process to the output as it arrives on another thread. The output
+/// is shared by the stdout and stderr of the child process so a line is always written whole, and lines that
+/// are not valid UTF-8 are written lossily rather than stopping the reading.
+/// 
+/// # Arguments
+/// * `pipe` - The stdout or stderr of the child process
+/// * `output` - Where the lines are written
+/// 
+/// # Returns
+/// * `JoinHandle<()>` - The handle to join once the child process has finished
+fn forward_in_background<R, W>(pipe: Option<R>, output: Arc<Mutex<W>>) -> JoinHandle<()>
+where
+    R: Read + Send + 'static,
+    W: Write + Send + 'static
+{
+    thread::spawn(move || {
+        let mut reader = match pipe {
+            Some(pipe) => std::io::BufReader::new(pipe),
+            None => return
+        };
+        let mut line = Vec::new();
+        while let Ok(read) = reader.read_until(b'\n', &mut line) {
+            if read == 0 {
+                break
+            }
+            let text = String::from_utf8_lossy(&line);
+            let mut output = output.lock().unwrap();
+            let _ = writeln!(output, "{}", text.trim_end_matches(['\n', '\r']));
+            let _ = output.flush();
+            line.clear();
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::dry_run::SharedOutput;
     use std::os::unix::process::ExitStatusExt;
-    use mockall::predicate::{eq, ne};
+    use mockall::predicate::{always, eq, ne};
 
     fn run_command(command: &String, runner: &dyn CoreRunner) -> Result<Output, std::io::Error> {
         runner.run(command)
@@ -173,4 +466,107 @@ mod tests {
         assert!(result.is_err());
         mock_runner.checkpoint(); // Ensure all expected calls have been made
     }
+
+    fn shell(script: &str) -> ProgramArgs {
+        ProgramArgs::new("sh", vec!["-c".to_string(), script.to_string()])
+    }
+
+    #[test]
+    fn test_stream_command_forwards_both_streams() {
+        let output = SharedOutput::default();
+        let script = "for i in 1 2 3; do echo out $i; sleep 0.05; echo err $i >&2; sleep 0.05; done; \
+                      for i in $(seq 1 500); do echo bulk $i >&2; done; echo done; exit 3";
+        let status = stream_command(&shell(script), None, "failed", output.clone()).unwrap();
+
+        assert_eq!(status.code(), Some(3));
+        let contents = output.contents();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(&lines[..6], &["out 1", "err 1", "out 2", "err 2", "out 3", "err 3"]);
+        let bulk: Vec<String> = (1..=500).map(|i| format!("bulk {}", i)).collect();
+        assert_eq!(lines.iter().filter(|line| line.starts_with("bulk ")).copied().collect::<Vec<&str>>(), bulk);
+        assert!(lines.contains(&"done"));
+        assert_eq!(lines.len(), 507);
+    }
+
+    #[test]
+    fn test_stream_command_with_timeout() {
+        let output = SharedOutput::default();
+        let status = stream_command(&shell("echo started; sleep 5; echo finished"), Some(Duration::from_millis(200)), "failed", output.clone()).unwrap();
+        assert!(!status.success());
+        assert_eq!(output.contents(), "started\n");
+    }
+
+    #[test]
+    fn test_run_in_dir_with_spaces() {
+        let dir = std::env::temp_dir().join(format!("wedp run in dir; {}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let expected = dir.canonicalize().unwrap();
+        let output = CommandRunner.run_in_dir(&dir, "pwd", &[]).unwrap();
+        let echoed = CommandRunner.run_in_dir(&dir, "echo", &["$HOME", "a b"]).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim_end(), expected.to_string_lossy());
+        assert_eq!(String::from_utf8_lossy(&echoed.stdout), "$HOME a b\n");
+    }
+
+    #[test]
+    fn test_program_args() {
+        let program_args = ProgramArgs::new("docker-compose", vec!["-f".to_string(), "my venue/$(id).yml".to_string()]);
+        let with_command = program_args.with_command(" up -d");
+        assert_eq!(with_command.arg_refs(), vec!["-f", "my venue/$(id).yml", "up", "-d"]);
+        assert_eq!(with_command.to_string(), "docker-compose -f 'my venue/$(id).yml' up -d");
+        assert_eq!(program_args.with_command(""), program_args);
+    }
+
+    #[test]
+    fn test_render_in_dir() {
+        assert_eq!(render_in_dir(Path::new("some/repo"), "git", &["checkout", "main"]), "cd some/repo && git checkout main");
+        assert_eq!(
+            render_in_dir(Path::new("my projects/venue"), "git", &["clone", "a b"]),
+            "cd 'my projects/venue' && git clone 'a b'"
+        );
+    }
+
+    #[test]
+    fn test_run_docker_checked() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_docker_command()
+            .with(eq(" up"), eq("failed to run"), always())
+            .returning(|_, _, _| Ok(ExitStatus::from_raw(1 << 8)));
+        mock_runner.expect_run_docker_command()
+            .with(eq(" down"), always(), always())
+            .returning(|_, _, _| Ok(ExitStatus::from_raw(0)));
+        mock_runner.expect_run_docker_command()
+            .with(eq(" build"), always(), always())
+            .returning(|_, _, _| Err(std::io::Error::from(std::io::ErrorKind::NotFound)));
+
+        let compose = ProgramArgs::new("docker-compose", vec!["-f".to_string(), "base.yml".to_string()]);
+        let error = run_docker_checked(&mock_runner, " up", "failed to run", &compose).unwrap_err();
+        assert!(matches!(&error, WedpError::DockerCommand { command, .. } if command == "docker-compose -f base.yml up"), "{:?}", error);
+        assert_eq!(error.to_string(), "failed to run: docker-compose -f base.yml up exited with exit status: 1");
+        let compose = ProgramArgs::new("docker-compose", Vec::new());
+        assert!(run_docker_checked(&mock_runner, " down", "failed to tear down", &compose).is_ok());
+        let error = run_docker_checked(&mock_runner, " build", "failed to build", &compose).unwrap_err();
+        assert_eq!(error.to_string(), "failed to build: entity not found");
+    }
+
+    #[test]
+    fn test_check_status() {
+        let success = Output {
+            status: ExitStatus::from_raw(0),
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+        };
+        assert!(check_status(success).is_ok());
+
+        let failure = Output {
+            status: ExitStatus::from_raw(128 << 8),
+            stdout: Vec::new(),
+            stderr: b"fatal: repository not found\n".to_vec(),
+        };
+        let error = check_status(failure).unwrap_err().to_string();
+        assert!(error.contains("fatal: repository not found"));
+        assert!(error.contains("128"));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,88 @@
+//! This command packs the installed dependencies of the venue into an archive for offline installs.
+use super::command_runner::{check_status, CoreRunner};
+use crate::compose_file::shell_quote;
+
+
+/// A command to pack the working trees of dependencies in the venue into a ```.tar.gz``` archive. Each 
+/// dependency is a top level directory of the archive so it can be extracted on its own. The ```.git``` 
+/// directories are left out so the archive only holds the checked out files.
+///
+/// # Fields
+/// * `archive` - The path the ```.tar.gz``` archive is written to
+/// * `path_to_venue` - The path to the venue holding the dependencies
+/// * `repo_names` - The names of the dependencies to pack
+pub struct CreateArchiveCommand {
+    pub archive: String,
+    pub path_to_venue: String,
+    pub repo_names: Vec<String>
+}
+
+impl CreateArchiveCommand {
+
+    /// Creates a new CreateArchiveCommand struct.
+    ///
+    /// # Arguments
+    /// * `archive` - The path the ```.tar.gz``` archive is written to
+    /// * `path_to_venue` - The path to the venue holding the dependencies
+    /// * `repo_names` - The names of the dependencies to pack
+    ///
+    /// # Returns
+    /// A new CreateArchiveCommand struct
+    pub fn new(archive: String, path_to_venue: String, repo_names: Vec<String>) -> Self {
+        Self {
+            archive,
+            path_to_venue,
+            repo_names
+        }
+    }
+
+    /// Runs the archive command.
+    ///
+    /// # Arguments
+    /// * `runner` - The command runner to for the command being run
+    ///
+    /// # Returns
+    /// The output of the command or an error containing the tar stderr if the command failed
+    pub fn run(&self, runner: &dyn CoreRunner) -> Result<std::process::Output, std::io::Error> {
+        let repo_names: Vec<String> = self.repo_names.iter().map(|name| shell_quote(name)).collect();
+        let archive_cmd = format!(
+            "tar -czf {} --exclude=.git -C {} {}",
+            shell_quote(&self.archive), shell_quote(&self.path_to_venue), repo_names.join(" ")
+        );
+        check_status(runner.run(&archive_cmd)?)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use mockall::predicate::eq;
+    use std::os::unix::process::ExitStatusExt;
+    use super::super::command_runner::MockCoreRunner;
+    use std::process::Output;
+
+    #[test]
+    fn test_run() {
+        let command = CreateArchiveCommand::new(
+            "/offline/vendor.tar.gz".to_string(),
+            "/path/to/venue".to_string(),
+            vec!["auth".to_string(), "billing".to_string()]
+        );
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run()
+            .with(eq("tar -czf /offline/vendor.tar.gz --exclude=.git -C /path/to/venue auth billing".to_string()))
+            .times(1)
+            .returning(|_| {
+                Ok(Output {
+                    status: std::process::ExitStatus::from_raw(0),
+                    stdout: Vec::new(),
+                    stderr: Vec::new(),
+                })
+            });
+        let result = command.run(&mock_runner);
+        assert!(result.is_ok());
+        mock_runner.checkpoint();
+    }
+}
@@ -0,0 +1,96 @@
+//! This command gets the branch that is currently checked out in a git repository.
+use super::command_runner::CoreRunner;
+use std::path::Path;
+
+
+/// A command to get the current branch of a repository.
+/// 
+/// # Fields
+/// * `path_to_repo` - The path to the directory holding the repository
+/// * `repo_name` - The name of the repository to get the current branch of
+pub struct CurrentBranchCommand {
+    pub path_to_repo: String,
+    pub repo_name: String
+}
+
+impl CurrentBranchCommand {
+
+    /// Creates a new CurrentBranchCommand struct.
+    /// 
+    /// # Arguments
+    /// * `path_to_repo` - The path to the directory holding the repository
+    /// * `repo_name` - The name of the repository to get the current branch of
+    /// 
+    /// # Returns
+    /// A new CurrentBranchCommand struct
+    pub fn new(path_to_repo: String, repo_name: String) -> Self {
+        Self {
+            path_to_repo,
+            repo_name
+        }
+    }
+
+    /// Runs the current branch command in the repository without a shell, so the path can hold spaces.
+    /// 
+    /// # Arguments
+    /// * `runner` - The command runner to for the command being run
+    /// 
+    /// # Returns
+    /// The name of the current branch, ```HEAD``` if the repository is in a detached state
+    pub fn run(&self, runner: &dyn CoreRunner) -> Result<String, std::io::Error> {
+        let root_path = Path::new(&self.path_to_repo).join(&self.repo_name);
+        let output = runner.run_in_dir(&root_path, "git", &["rev-parse", "--abbrev-ref", "HEAD"])?;
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use super::super::command_runner::MockCoreRunner;
+    use std::process::Output;
+
+    #[test]
+    fn test_new() {
+        let command = CurrentBranchCommand::new("/path/to/repo".to_string(), "test_repo".to_string());
+        assert_eq!(command.path_to_repo, "/path/to/repo");
+        assert_eq!(command.repo_name, "test_repo");
+    }
+
+    fn expect_rev_parse(dir: &'static str) -> MockCoreRunner {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .withf(move |path, program, args| {
+                path == Path::new(dir) && program == "git" && args == ["rev-parse", "--abbrev-ref", "HEAD"]
+            })
+            .times(1)
+            .returning(|_, _, _| {
+                Ok(Output {
+                    status: std::process::ExitStatus::from_raw(0),
+                    stdout: b"development\n".to_vec(),
+                    stderr: Vec::new(),
+                })
+            });
+        mock_runner
+    }
+
+    #[test]
+    fn test_run() {
+        let command = CurrentBranchCommand::new("/path/to/repo".to_string(), "test_repo".to_string());
+        let mut mock_runner = expect_rev_parse("/path/to/repo/test_repo");
+        let result = command.run(&mock_runner);
+        assert_eq!(result.unwrap(), "development");
+        mock_runner.checkpoint();
+    }
+
+    #[test]
+    fn test_run_path_with_spaces() {
+        let command = CurrentBranchCommand::new("my projects/venue; rm -rf".to_string(), "test_repo".to_string());
+        let mut mock_runner = expect_rev_parse("my projects/venue; rm -rf/test_repo");
+        assert_eq!(command.run(&mock_runner).unwrap(), "development");
+        mock_runner.checkpoint();
+    }
+}
@@ -0,0 +1,99 @@
+//! This command gets the commit that is currently checked out in a git repository.
+use super::command_runner::{check_status, CoreRunner};
+use std::path::Path;
+
+
+/// A command to get the full hash of the commit checked out in a repository.
+/// 
+/// # Fields
+/// * `path_to_repo` - The path to the directory holding the repository
+/// * `repo_name` - The name of the repository to get the current commit of
+pub struct CurrentCommitCommand {
+    pub path_to_repo: String,
+    pub repo_name: String
+}
+
+impl CurrentCommitCommand {
+
+    /// Creates a new CurrentCommitCommand struct.
+    /// 
+    /// # Arguments
+    /// * `path_to_repo` - The path to the directory holding the repository
+    /// * `repo_name` - The name of the repository to get the current commit of
+    /// 
+    /// # Returns
+    /// A new CurrentCommitCommand struct
+    pub fn new(path_to_repo: String, repo_name: String) -> Self {
+        Self {
+            path_to_repo,
+            repo_name
+        }
+    }
+
+    /// Runs the current commit command in the repository without a shell, so the path can hold spaces.
+    /// 
+    /// # Arguments
+    /// * `runner` - The command runner to for the command being run
+    /// 
+    /// # Returns
+    /// The full hash of the checked out commit or an error containing the git stderr if the command failed
+    pub fn run(&self, runner: &dyn CoreRunner) -> Result<String, std::io::Error> {
+        let root_path = Path::new(&self.path_to_repo).join(&self.repo_name);
+        let output = check_status(runner.run_in_dir(&root_path, "git", &["rev-parse", "HEAD"])?)?;
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use super::super::command_runner::MockCoreRunner;
+    use std::process::Output;
+
+    fn expect_rev_parse(dir: &'static str) -> MockCoreRunner {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .withf(move |path, program, args| path == Path::new(dir) && program == "git" && args == ["rev-parse", "HEAD"])
+            .times(1)
+            .returning(|_, _, _| {
+                Ok(Output {
+                    status: std::process::ExitStatus::from_raw(0),
+                    stdout: b"3f2c1a9e8b7d6c5f4e3d2c1b0a9f8e7d6c5b4a39\n".to_vec(),
+                    stderr: Vec::new(),
+                })
+            });
+        mock_runner
+    }
+
+    #[test]
+    fn test_run() {
+        let command = CurrentCommitCommand::new("/path/to/repo".to_string(), "test_repo".to_string());
+        let mock_runner = expect_rev_parse("/path/to/repo/test_repo");
+        assert_eq!(command.run(&mock_runner).unwrap(), "3f2c1a9e8b7d6c5f4e3d2c1b0a9f8e7d6c5b4a39");
+    }
+
+    #[test]
+    fn test_run_path_with_spaces() {
+        let command = CurrentCommitCommand::new("my projects/venue; rm -rf".to_string(), "test_repo".to_string());
+        let mock_runner = expect_rev_parse("my projects/venue; rm -rf/test_repo");
+        assert_eq!(command.run(&mock_runner).unwrap(), "3f2c1a9e8b7d6c5f4e3d2c1b0a9f8e7d6c5b4a39");
+    }
+
+    #[test]
+    fn test_run_not_a_repo() {
+        let command = CurrentCommitCommand::new("/path/to/repo".to_string(), "test_repo".to_string());
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .returning(|_, _, _| {
+                Ok(Output {
+                    status: std::process::ExitStatus::from_raw(128 << 8),
+                    stdout: Vec::new(),
+                    stderr: b"fatal: not a git repository".to_vec(),
+                })
+            });
+        assert!(command.run(&mock_runner).unwrap_err().to_string().contains("not a git repository"));
+    }
+}
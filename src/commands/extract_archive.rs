@@ -0,0 +1,103 @@
+//! This command extracts a dependency from a vendored archive made by ```wedp vendor```.
+use super::command_runner::{check_status, CoreRunner};
+use crate::compose_file::shell_quote;
+
+
+/// A command to extract the working tree of a single dependency from a vendored archive into the venue.
+///
+/// # Fields
+/// * `archive` - The path to the ```.tar.gz``` archive
+/// * `path_to_venue` - The path to the venue the dependency is extracted into
+/// * `repo_name` - The name of the dependency, the top level directory of the dependency in the archive
+pub struct ExtractArchiveCommand {
+    pub archive: String,
+    pub path_to_venue: String,
+    pub repo_name: String
+}
+
+impl ExtractArchiveCommand {
+
+    /// Creates a new ExtractArchiveCommand struct.
+    ///
+    /// # Arguments
+    /// * `archive` - The path to the ```.tar.gz``` archive
+    /// * `path_to_venue` - The path to the venue the dependency is extracted into
+    /// * `repo_name` - The name of the dependency, the top level directory of the dependency in the archive
+    ///
+    /// # Returns
+    /// A new ExtractArchiveCommand struct
+    pub fn new(archive: String, path_to_venue: String, repo_name: String) -> Self {
+        Self {
+            archive,
+            path_to_venue,
+            repo_name
+        }
+    }
+
+    /// Runs the extract command.
+    ///
+    /// # Arguments
+    /// * `runner` - The command runner to for the command being run
+    ///
+    /// # Returns
+    /// The output of the command or an error containing the tar stderr if the command failed
+    pub fn run(&self, runner: &dyn CoreRunner) -> Result<std::process::Output, std::io::Error> {
+        let venue = shell_quote(&self.path_to_venue);
+        let extract_cmd = format!(
+            "mkdir -p {} && tar -xzf {} -C {} {}",
+            venue, shell_quote(&self.archive), venue, shell_quote(&self.repo_name)
+        );
+        check_status(runner.run(&extract_cmd)?)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use mockall::predicate::eq;
+    use std::os::unix::process::ExitStatusExt;
+    use super::super::command_runner::MockCoreRunner;
+    use std::process::Output;
+
+    #[test]
+    fn test_run() {
+        let command = ExtractArchiveCommand::new(
+            "/offline/wedp vendor.tar.gz".to_string(), "/path/to/venue".to_string(), "test_repo".to_string()
+        );
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run()
+            .with(eq("mkdir -p /path/to/venue && tar -xzf '/offline/wedp vendor.tar.gz' -C /path/to/venue test_repo".to_string()))
+            .times(1)
+            .returning(|_| {
+                Ok(Output {
+                    status: std::process::ExitStatus::from_raw(0),
+                    stdout: Vec::new(),
+                    stderr: Vec::new(),
+                })
+            });
+        let result = command.run(&mock_runner);
+        assert!(result.is_ok());
+        mock_runner.checkpoint();
+    }
+
+    #[test]
+    fn test_run_missing_dependency() {
+        let command = ExtractArchiveCommand::new(
+            "vendor.tar.gz".to_string(), "/path/to/venue".to_string(), "test_repo".to_string()
+        );
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run()
+            .returning(|_| {
+                Ok(Output {
+                    status: std::process::ExitStatus::from_raw(2 << 8),
+                    stdout: Vec::new(),
+                    stderr: b"tar: test_repo: Not found in archive".to_vec(),
+                })
+            });
+        let result = command.run(&mock_runner);
+        assert!(result.unwrap_err().to_string().contains("Not found in archive"));
+        mock_runner.checkpoint();
+    }
+}
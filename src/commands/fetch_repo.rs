@@ -0,0 +1,152 @@
+//! This command fetches the latest changes from the remote of a git repository.
+use super::command_runner::{check_status, CoreRunner};
+use crate::git_auth::{run_git, GitConfigEntry};
+use std::path::Path;
+
+
+/// A command to fetch the latest changes into a repository that has already been cloned.
+///
+/// # Fields
+/// * `path_to_repo` - The path to the directory holding the repository
+/// * `repo_name` - The name of the repository to fetch into
+/// * `depth` - The number of commits to fetch to keep a shallow clone shallow, ```None``` fetches everything
+/// * `auth_config` - The git config sending the access token of a private repository, ```None``` sends no token
+pub struct FetchRepoCommand {
+    pub path_to_repo: String,
+    pub repo_name: String,
+    pub depth: Option<u32>,
+    pub auth_config: Option<GitConfigEntry>
+}
+
+impl FetchRepoCommand {
+
+    /// Creates a new FetchRepoCommand struct.
+    ///
+    /// # Arguments
+    /// * `path_to_repo` - The path to the directory holding the repository
+    /// * `repo_name` - The name of the repository to fetch into
+    /// * `depth` - The number of commits to fetch to keep a shallow clone shallow, ```None``` fetches everything
+    ///
+    /// # Returns
+    /// A new FetchRepoCommand struct
+    pub fn new(path_to_repo: String, repo_name: String, depth: Option<u32>) -> Self {
+        Self {
+            path_to_repo,
+            repo_name,
+            depth,
+            auth_config: None
+        }
+    }
+
+    /// Sets the git config the access token is sent with, set through the environment of git so it is
+    /// not stored in the clone or shown in the arguments of git.
+    ///
+    /// # Arguments
+    /// * `auth_config` - The ```http.<url>.extraHeader``` config, ```None``` sends no token
+    ///
+    /// # Returns
+    /// The command sending the token
+    pub fn with_auth_config(self, auth_config: Option<GitConfigEntry>) -> Self {
+        Self { auth_config, ..self }
+    }
+
+    /// Runs the fetch command in the repository without a shell, so the path can hold spaces. The remote is set to track every branch first, so a clone made with
+    /// ```--single-branch``` can still move to a branch it did not clone.
+    ///
+    /// # Arguments
+    /// * `runner` - The command runner to for the command being run
+    ///
+    /// # Returns
+    /// The output of the command or an error containing the git stderr if the command failed
+    pub fn run(&self, runner: &dyn CoreRunner) -> Result<std::process::Output, std::io::Error> {
+        let root_path = Path::new(&self.path_to_repo).join(&self.repo_name);
+        check_status(runner.run_in_dir(&root_path, "git", &["remote", "set-branches", "origin", "*"])?)?;
+        let depth = self.depth.map(|depth| depth.to_string());
+        let mut args = Vec::new();
+        args.extend(["fetch", "--tags"]);
+        if let Some(depth) = &depth {
+            args.extend(["--depth", depth]);
+        }
+        check_status(run_git(runner, &root_path, &args, self.auth_config.as_ref())?)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use mockall::Sequence;
+    use std::os::unix::process::ExitStatusExt;
+    use super::super::command_runner::MockCoreRunner;
+    use std::process::Output;
+
+    fn successful_output() -> Output {
+        Output {
+            status: std::process::ExitStatus::from_raw(0),
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+        }
+    }
+
+    fn expect_fetch(dir: &'static str, fetch: &'static [&'static str]) -> MockCoreRunner {
+        let mut mock_runner = MockCoreRunner::new();
+        let mut sequence = Sequence::new();
+        mock_runner.expect_run_in_dir()
+            .withf(move |path, program, args| {
+                path == Path::new(dir) && program == "git" && args == ["remote", "set-branches", "origin", "*"]
+            })
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _, _| Ok(successful_output()));
+        mock_runner.expect_run_in_dir()
+            .withf(move |path, program, args| path == Path::new(dir) && program == "git" && args == fetch)
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _, _| Ok(successful_output()));
+        mock_runner
+    }
+
+    #[test]
+    fn test_run() {
+        let command = FetchRepoCommand::new("/path/to/repo".to_string(), "test_repo".to_string(), None);
+        let mut mock_runner = expect_fetch("/path/to/repo/test_repo", &["fetch", "--tags"]);
+        let result = command.run(&mock_runner);
+        assert!(result.is_ok());
+        mock_runner.checkpoint();
+    }
+
+    #[test]
+    fn test_run_shallow() {
+        let command = FetchRepoCommand::new("/path/to/repo".to_string(), "test_repo".to_string(), Some(1));
+        let mut mock_runner = expect_fetch("/path/to/repo/test_repo", &["fetch", "--tags", "--depth", "1"]);
+        let result = command.run(&mock_runner);
+        assert!(result.is_ok());
+        mock_runner.checkpoint();
+    }
+
+    #[test]
+    fn test_run_path_with_spaces() {
+        let command = FetchRepoCommand::new("my projects/venue; rm -rf".to_string(), "test_repo".to_string(), None);
+        let mut mock_runner = expect_fetch("my projects/venue; rm -rf/test_repo", &["fetch", "--tags"]);
+        assert!(command.run(&mock_runner).is_ok());
+        mock_runner.checkpoint();
+    }
+
+    #[test]
+    fn test_run_failure() {
+        let command = FetchRepoCommand::new("/path/to/repo".to_string(), "test_repo".to_string(), None);
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .returning(|_, _, _| {
+                Ok(Output {
+                    status: std::process::ExitStatus::from_raw(128 << 8),
+                    stdout: Vec::new(),
+                    stderr: b"fatal: unable to access remote".to_vec(),
+                })
+            });
+        let result = command.run(&mock_runner);
+        assert!(result.unwrap_err().to_string().contains("fatal: unable to access remote"));
+        mock_runner.checkpoint();
+    }
+}
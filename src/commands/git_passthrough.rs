@@ -0,0 +1,99 @@
+//! This command runs an arbitrary git command in a cloned repository streaming the output.
+use super::command_runner::{CoreRunner, ProgramArgs};
+use std::path::Path;
+use std::process::ExitStatus;
+
+
+/// A command to run git with arguments passed through from the command line in a repository.
+///
+/// # Fields
+/// * `path_to_repo` - The path to the directory holding the repository
+/// * `repo_name` - The name of the repository to run git in
+/// * `args` - The arguments passed to git
+pub struct GitPassthroughCommand {
+    pub path_to_repo: String,
+    pub repo_name: String,
+    pub args: Vec<String>
+}
+
+impl GitPassthroughCommand {
+
+    /// Creates a new GitPassthroughCommand struct.
+    ///
+    /// # Arguments
+    /// * `path_to_repo` - The path to the directory holding the repository
+    /// * `repo_name` - The name of the repository to run git in
+    /// * `args` - The arguments passed to git
+    ///
+    /// # Returns
+    /// A new GitPassthroughCommand struct
+    pub fn new(path_to_repo: String, repo_name: String, args: Vec<String>) -> Self {
+        Self {
+            path_to_repo,
+            repo_name,
+            args
+        }
+    }
+
+    /// Renders git run in the repository with the arguments passed through, each one kept as a single argument.
+    ///
+    /// # Returns
+    /// * `ProgramArgs` - The git program with ```-C``` set to the repository followed by the arguments
+    pub fn program_args(&self) -> ProgramArgs {
+        let root_path = Path::new(&self.path_to_repo).join(&self.repo_name).to_string_lossy().to_string();
+        let args = ["-C".to_string(), root_path].into_iter().chain(self.args.iter().cloned()).collect();
+        ProgramArgs::new("git", args)
+    }
+
+    /// Runs the git command printing the output of git in realtime.
+    ///
+    /// # Arguments
+    /// * `runner` - The command runner to for the command being run
+    ///
+    /// # Returns
+    /// * `Result<ExitStatus, std::io::Error>` - The exit status of git or an error if it could not be started
+    pub fn run(&self, runner: &dyn CoreRunner) -> Result<ExitStatus, std::io::Error> {
+        runner.run_docker_command("", "failed to run git", &self.program_args())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use super::super::command_runner::MockCoreRunner;
+
+    #[test]
+    fn test_run() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_docker_command()
+            .withf(|command, error_message, program_args| {
+                command.is_empty() && error_message == "failed to run git" && program_args.program == "git"
+                    && program_args.arg_refs() == ["-C", "./venue/auth", "log", "--oneline", "-n", "5", "feature/my branch"]
+            })
+            .times(1)
+            .returning(|_, _, _| Ok(ExitStatus::from_raw(0)));
+        let args = vec!["log", "--oneline", "-n", "5", "feature/my branch"];
+        let command = GitPassthroughCommand::new(
+            "./venue".to_string(),
+            "auth".to_string(),
+            args.into_iter().map(String::from).collect()
+        );
+
+        let status = command.run(&mock_runner).unwrap();
+        assert!(status.success());
+        mock_runner.checkpoint();
+    }
+
+    #[test]
+    fn test_run_exit_status() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_docker_command()
+            .returning(|_, _, _| Ok(ExitStatus::from_raw(1 << 8)));
+        let command = GitPassthroughCommand::new("./venue".to_string(), "auth".to_string(), vec!["fetch".to_string()]);
+
+        assert_eq!(command.run(&mock_runner).unwrap().code(), Some(1));
+    }
+}
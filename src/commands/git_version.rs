@@ -0,0 +1,63 @@
+//! This command checks git is installed by reading its version.
+use super::command_runner::{check_status, CoreRunner};
+
+
+/// A command to read the version of git, used to find out whether git is installed before running the checks
+/// that need it.
+pub struct GitVersionCommand;
+
+impl GitVersionCommand {
+
+    /// Runs the git version command.
+    /// 
+    /// # Arguments
+    /// * `runner` - The command runner to for the command being run
+    /// 
+    /// # Returns
+    /// The version printed by git or an error if git could not be run
+    pub fn run(&self, runner: &dyn CoreRunner) -> Result<String, std::io::Error> {
+        let output = check_status(runner.run(&"git --version".to_string())?)?;
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use mockall::predicate::eq;
+    use std::os::unix::process::ExitStatusExt;
+    use super::super::command_runner::MockCoreRunner;
+    use std::process::Output;
+
+    #[test]
+    fn test_run() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run()
+            .with(eq("git --version".to_string()))
+            .returning(|_| {
+                Ok(Output {
+                    status: std::process::ExitStatus::from_raw(0),
+                    stdout: b"git version 2.43.0\n".to_vec(),
+                    stderr: Vec::new(),
+                })
+            });
+        assert_eq!(GitVersionCommand.run(&mock_runner).unwrap(), "git version 2.43.0");
+        mock_runner.checkpoint();
+    }
+
+    #[test]
+    fn test_run_not_installed() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run()
+            .returning(|_| {
+                Ok(Output {
+                    status: std::process::ExitStatus::from_raw(127 << 8),
+                    stdout: Vec::new(),
+                    stderr: b"sh: 1: git: not found".to_vec(),
+                })
+            });
+        assert!(GitVersionCommand.run(&mock_runner).is_err());
+    }
+}
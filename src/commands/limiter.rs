@@ -0,0 +1,353 @@
+//! Bounds how many docker and git commands run at the same time across the whole of wedp. Parallel installs
+//! and builds are free to use as many threads as they like, every git or docker command they run waits for a
+//! free slot before it is spawned so the docker daemon and the git remotes never see more than the limit. The
+//! limits come from ```--max-docker-ops``` and ```--max-git-ops```, falling back to ```max_docker_ops``` and
+//! ```max_git_ops``` in the seating plan.
+use std::path::Path;
+use std::process::{ExitStatus, Output};
+use std::sync::{Condvar, Mutex, OnceLock};
+
+use super::command_runner::{render_in_dir, CoreRunner, ProgramArgs};
+
+
+static OPERATION_LIMITS: OnceLock<OperationLimits> = OnceLock::new();
+
+
+/// Sets the limits passed in through the command line for the rest of the process. Only the first call has an
+/// effect.
+///
+/// # Arguments
+/// * `limits` - The limits, a ```None``` limit falls back to the seating plan
+pub fn set_operation_limits(limits: OperationLimits) {
+    let _ = OPERATION_LIMITS.set(limits);
+}
+
+/// Gets the limits passed in through the command line.
+///
+/// # Returns
+/// * `OperationLimits` - The limits, unlimited if none were passed
+pub fn operation_limits() -> OperationLimits {
+    OPERATION_LIMITS.get().copied().unwrap_or_default()
+}
+
+
+/// The most docker and git commands allowed to run at the same time.
+///
+/// # Fields
+/// * `docker` - The most docker commands at the same time, ```None``` for no limit
+/// * `git` - The most git commands at the same time, ```None``` for no limit
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct OperationLimits {
+    pub docker: Option<usize>,
+    pub git: Option<usize>,
+}
+
+impl OperationLimits {
+
+    /// Fills in the limits that are not set from other limits.
+    ///
+    /// # Arguments
+    /// * `fallback` - The limits used where these have none, such as the limits of the seating plan
+    ///
+    /// # Returns
+    /// * `OperationLimits` - The merged limits
+    pub fn or(self, fallback: OperationLimits) -> OperationLimits {
+        OperationLimits { docker: self.docker.or(fallback.docker), git: self.git.or(fallback.git) }
+    }
+
+    /// Checks if neither kind of command is limited.
+    ///
+    /// # Returns
+    /// * `bool` - True if there are no limits
+    pub fn is_unlimited(&self) -> bool {
+        self.docker.is_none() && self.git.is_none()
+    }
+}
+
+
+/// The kind of external command a shell command runs.
+///
+/// # Fields
+/// * `Docker` - Talks to the container engine through docker, docker-compose, or podman
+/// * `Git` - Talks to a git remote or repository
+#[derive(Debug, PartialEq)]
+pub enum OperationKind {
+    Docker,
+    Git,
+}
+
+
+/// Works out the kind of a shell command from the programs it runs, such as ```cd venue && git fetch```.
+///
+/// # Arguments
+/// * `command` - The shell command
+///
+/// # Returns
+/// * `Option<OperationKind>` - The kind or ```None``` if the command runs neither git nor docker
+pub fn operation_kind(command: &str) -> Option<OperationKind> {
+    let programs: Vec<&str> = command.split(['&', '|', ';'])
+                                     .filter_map(|part| part.split_whitespace().next())
+                                     .collect();
+    if programs.iter().any(|program| program.starts_with("docker") || program.starts_with("podman")) {
+        return Some(OperationKind::Docker)
+    }
+    match programs.contains(&"git") {
+        true => Some(OperationKind::Git),
+        false => None
+    }
+}
+
+
+/// A counting semaphore handing out a fixed number of permits.
+///
+/// # Fields
+/// * `available` - The permits not handed out
+/// * `released` - Wakes the threads waiting for a permit when one is handed back
+pub struct Semaphore {
+    available: Mutex<usize>,
+    released: Condvar,
+}
+
+impl Semaphore {
+
+    /// Creates a new Semaphore struct.
+    ///
+    /// # Arguments
+    /// * `permits` - The number of permits
+    ///
+    /// # Returns
+    /// A new Semaphore struct
+    pub fn new(permits: usize) -> Self {
+        Semaphore { available: Mutex::new(permits), released: Condvar::new() }
+    }
+
+    /// Waits for a permit, the permit is handed back when the guard is dropped.
+    ///
+    /// # Returns
+    /// * `Permit` - The guard holding the permit
+    pub fn acquire(&self) -> Permit<'_> {
+        let mut available = self.available.lock().unwrap();
+        while *available == 0 {
+            available = self.released.wait(available).unwrap();
+        }
+        *available -= 1;
+        Permit { semaphore: self }
+    }
+}
+
+
+/// A permit of a semaphore, handed back when dropped.
+///
+/// # Fields
+/// * `semaphore` - The semaphore the permit came from
+pub struct Permit<'a> {
+    semaphore: &'a Semaphore,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        *self.semaphore.available.lock().unwrap() += 1;
+        self.semaphore.released.notify_one();
+    }
+}
+
+
+/// A runner that waits for a free slot before passing each git or docker command on to the runner it wraps.
+/// Interactive commands such as editors are never limited.
+///
+/// # Fields
+/// * `runner` - The runner the commands are passed on to
+/// * `docker` - The slots for docker commands, ```None``` for no limit
+/// * `git` - The slots for git commands, ```None``` for no limit
+pub struct LimitedRunner {
+    runner: Box<dyn CoreRunner>,
+    docker: Option<Semaphore>,
+    git: Option<Semaphore>,
+}
+
+impl LimitedRunner {
+
+    /// Creates a new LimitedRunner struct.
+    ///
+    /// # Arguments
+    /// * `runner` - The runner the commands are passed on to
+    /// * `limits` - The most docker and git commands allowed to run at the same time
+    ///
+    /// # Returns
+    /// A new LimitedRunner struct
+    pub fn new(runner: Box<dyn CoreRunner>, limits: OperationLimits) -> Self {
+        LimitedRunner {
+            runner,
+            docker: limits.docker.map(Semaphore::new),
+            git: limits.git.map(Semaphore::new),
+        }
+    }
+
+    /// Gets the slots a command has to wait for.
+    ///
+    /// # Arguments
+    /// * `kind` - The kind of the command
+    ///
+    /// # Returns
+    /// * `Option<&Semaphore>` - The slots or ```None``` if the kind is not limited
+    fn slots(&self, kind: Option<OperationKind>) -> Option<&Semaphore> {
+        match kind {
+            Some(OperationKind::Docker) => self.docker.as_ref(),
+            Some(OperationKind::Git) => self.git.as_ref(),
+            None => None
+        }
+    }
+}
+
+impl CoreRunner for LimitedRunner {
+
+    fn run(&self, command: &String) -> Result<Output, std::io::Error> {
+        let _permit = self.slots(operation_kind(command)).map(Semaphore::acquire);
+        self.runner.run(command)
+    }
+
+    fn run_in_dir(&self, dir: &Path, program: &str, args: &[&str]) -> Result<Output, std::io::Error> {
+        let _permit = self.slots(operation_kind(&render_in_dir(dir, program, args))).map(Semaphore::acquire);
+        self.runner.run_in_dir(dir, program, args)
+    }
+
+    fn run_in_dir_with_env(&self, dir: &Path, program: &str, args: &[&str], env: &[(String, String)]) -> Result<Output, std::io::Error> {
+        let _permit = self.slots(operation_kind(&render_in_dir(dir, program, args))).map(Semaphore::acquire);
+        self.runner.run_in_dir_with_env(dir, program, args, env)
+    }
+
+    fn run_docker_command(&self, command: &str, error_message: &str, program_args: &ProgramArgs) -> Result<ExitStatus, std::io::Error> {
+        let _permit = self.slots(Some(OperationKind::Docker)).map(Semaphore::acquire);
+        self.runner.run_docker_command(command, error_message, program_args)
+    }
+
+    fn run_interactive(&self, command: &str) -> Result<ExitStatus, std::io::Error> {
+        self.runner.run_interactive(command)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    /// A runner counting how many commands run at the same time, each command takes a little while.
+    #[derive(Default)]
+    struct CountingRunner {
+        running: AtomicUsize,
+        most: AtomicUsize,
+    }
+
+    impl CountingRunner {
+        fn operation(&self) -> ExitStatus {
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.most.fetch_max(running, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(50));
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            ExitStatus::from_raw(0)
+        }
+    }
+
+    impl CoreRunner for Arc<CountingRunner> {
+        fn run(&self, _command: &String) -> Result<Output, std::io::Error> {
+            Ok(Output { status: self.operation(), stdout: Vec::new(), stderr: Vec::new() })
+        }
+
+        fn run_in_dir(&self, _dir: &Path, _program: &str, _args: &[&str]) -> Result<Output, std::io::Error> {
+            Ok(Output { status: self.operation(), stdout: Vec::new(), stderr: Vec::new() })
+        }
+
+        fn run_in_dir_with_env(&self, dir: &Path, program: &str, args: &[&str], _env: &[(String, String)]) -> Result<Output, std::io::Error> {
+            self.run_in_dir(dir, program, args)
+        }
+
+        fn run_docker_command(&self, _command: &str, _error_message: &str, _program_args: &ProgramArgs) -> Result<ExitStatus, std::io::Error> {
+            Ok(self.operation())
+        }
+
+        fn run_interactive(&self, _command: &str) -> Result<ExitStatus, std::io::Error> {
+            Ok(self.operation())
+        }
+    }
+
+    /// Runs a command on each of 8 threads at once and returns the most that ran at the same time.
+    fn most_at_once(limits: OperationLimits, run: fn(&LimitedRunner)) -> usize {
+        let counting = Arc::new(CountingRunner::default());
+        let runner = LimitedRunner::new(Box::new(counting.clone()), limits);
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| run(&runner));
+            }
+        });
+        counting.most.load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn test_limits_docker_commands() {
+        let limits = OperationLimits { docker: Some(2), git: None };
+        let most = most_at_once(limits, |runner| {
+            runner.run_docker_command(" ps", "failed", &ProgramArgs::new("docker-compose", Vec::new())).unwrap();
+        });
+        assert!(most <= 2, "{} ran at once", most);
+        let most = most_at_once(limits, |runner| {
+            runner.run(&"docker-compose -f base.yml ps --format json".to_string()).unwrap();
+        });
+        assert!(most <= 2, "{} ran at once", most);
+    }
+
+    #[test]
+    fn test_limits_git_commands() {
+        let limits = OperationLimits { docker: None, git: Some(3) };
+        let most = most_at_once(limits, |runner| {
+            runner.run(&"cd venue && git fetch origin".to_string()).unwrap();
+        });
+        assert!(most <= 3, "{} ran at once", most);
+        let most = most_at_once(OperationLimits { docker: None, git: Some(1) }, |runner| {
+            runner.run(&"git ls-remote --heads https://github.com/org/auth".to_string()).unwrap();
+        });
+        assert!(most <= 1, "{} ran at once", most);
+    }
+
+    #[test]
+    fn test_unlimited_commands() {
+        let limits = OperationLimits { docker: Some(1), git: Some(1) };
+        let most = most_at_once(limits, |runner| {
+            runner.run(&"cp a b".to_string()).unwrap();
+        });
+        assert!(most > 1, "the commands never overlapped");
+        let most = most_at_once(limits, |runner| {
+            runner.run_interactive("vim wedding_invite.yml").unwrap();
+        });
+        assert!(most > 1, "the commands never overlapped");
+        let most = most_at_once(OperationLimits::default(), |runner| {
+            runner.run(&"cd venue && git fetch origin".to_string()).unwrap();
+        });
+        assert!(most > 1, "the commands never overlapped");
+    }
+
+    #[test]
+    fn test_operation_kind() {
+        assert_eq!(operation_kind("cd venue && git clone https://github.com/org/auth"), Some(OperationKind::Git));
+        assert_eq!(operation_kind("git ls-remote --heads https://github.com/org/auth"), Some(OperationKind::Git));
+        assert_eq!(operation_kind("docker compose version"), Some(OperationKind::Docker));
+        assert_eq!(operation_kind("docker-compose -f base.yml ps"), Some(OperationKind::Docker));
+        assert_eq!(operation_kind("podman-compose -f base.yml up -d"), Some(OperationKind::Docker));
+        assert_eq!(operation_kind("tar -czf vendor.tar.gz auth"), None);
+        assert_eq!(operation_kind("echo git"), None);
+    }
+
+    #[test]
+    fn test_limits_or() {
+        let cli = OperationLimits { docker: Some(2), git: None };
+        let plan = OperationLimits { docker: Some(4), git: Some(3) };
+        assert_eq!(cli.or(plan), OperationLimits { docker: Some(2), git: Some(3) });
+        assert!(OperationLimits::default().is_unlimited());
+        assert!(!plan.is_unlimited());
+    }
+}
@@ -1,4 +1,22 @@
 //! Handles the interaction with the program and the command line.
 pub mod checkout_branch;
+pub mod checkout_rev;
 pub mod clone_repo;
 pub mod command_runner;
+pub mod create_archive;
+pub mod current_branch;
+pub mod current_commit;
+pub mod extract_archive;
+pub mod fetch_repo;
+pub mod git_passthrough;
+pub mod git_version;
+pub mod limiter;
+pub mod list_remote_heads;
+pub mod pull_repo;
+pub mod sparse_checkout;
+pub mod sparse_clone;
+pub mod stash_changes;
+pub mod timeout;
+pub mod trace;
+pub mod update_submodules;
+pub mod working_tree_status;
@@ -0,0 +1,122 @@
+//! This command fast forwards the checked out branch of a git repository to its remote.
+use super::command_runner::{check_status, CoreRunner};
+use crate::git_auth::{run_git, GitConfigEntry};
+use std::path::Path;
+
+
+/// A command to fast forward the checked out branch of a repository. The pull fails instead of merging if
+/// the local branch has diverged from the remote so local commits are never merged by accident.
+///
+/// # Fields
+/// * `path_to_repo` - The path to the directory holding the repository
+/// * `repo_name` - The name of the repository to pull in
+/// * `auth_config` - The git config sending the access token of a private repository, ```None``` sends no token
+pub struct PullRepoCommand {
+    pub path_to_repo: String,
+    pub repo_name: String,
+    pub auth_config: Option<GitConfigEntry>
+}
+
+impl PullRepoCommand {
+
+    /// Creates a new PullRepoCommand struct.
+    ///
+    /// # Arguments
+    /// * `path_to_repo` - The path to the directory holding the repository
+    /// * `repo_name` - The name of the repository to pull in
+    ///
+    /// # Returns
+    /// A new PullRepoCommand struct
+    pub fn new(path_to_repo: String, repo_name: String) -> Self {
+        Self {
+            path_to_repo,
+            repo_name,
+            auth_config: None
+        }
+    }
+
+    /// Sets the git config the access token is sent with, set through the environment of git so it is
+    /// not stored in the clone or shown in the arguments of git.
+    ///
+    /// # Arguments
+    /// * `auth_config` - The ```http.<url>.extraHeader``` config, ```None``` sends no token
+    ///
+    /// # Returns
+    /// The command sending the token
+    pub fn with_auth_config(self, auth_config: Option<GitConfigEntry>) -> Self {
+        Self { auth_config, ..self }
+    }
+
+    /// Runs the pull command in the repository without a shell, so the path can hold spaces.
+    ///
+    /// # Arguments
+    /// * `runner` - The command runner to for the command being run
+    ///
+    /// # Returns
+    /// The output of the command or an error containing the git stderr if the command failed
+    pub fn run(&self, runner: &dyn CoreRunner) -> Result<std::process::Output, std::io::Error> {
+        let root_path = Path::new(&self.path_to_repo).join(&self.repo_name);
+        let mut args = Vec::new();
+        args.extend(["pull", "--ff-only"]);
+        check_status(run_git(runner, &root_path, &args, self.auth_config.as_ref())?)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use super::super::command_runner::MockCoreRunner;
+    use std::process::Output;
+
+    fn expect_pull(dir: &'static str) -> MockCoreRunner {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .withf(move |path, program, args| path == Path::new(dir) && program == "git" && args == ["pull", "--ff-only"])
+            .times(1)
+            .returning(|_, _, _| {
+                Ok(Output {
+                    status: std::process::ExitStatus::from_raw(0),
+                    stdout: Vec::new(),
+                    stderr: Vec::new(),
+                })
+            });
+        mock_runner
+    }
+
+    #[test]
+    fn test_run() {
+        let command = PullRepoCommand::new("/path/to/repo".to_string(), "test_repo".to_string());
+        let mut mock_runner = expect_pull("/path/to/repo/test_repo");
+        let result = command.run(&mock_runner);
+        assert!(result.is_ok());
+        mock_runner.checkpoint();
+    }
+
+    #[test]
+    fn test_run_path_with_spaces() {
+        let command = PullRepoCommand::new("my projects/venue; rm -rf".to_string(), "test_repo".to_string());
+        let mut mock_runner = expect_pull("my projects/venue; rm -rf/test_repo");
+        assert!(command.run(&mock_runner).is_ok());
+        mock_runner.checkpoint();
+    }
+
+    #[test]
+    fn test_run_diverged() {
+        let command = PullRepoCommand::new("/path/to/repo".to_string(), "test_repo".to_string());
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .returning(|_, _, _| {
+                Ok(Output {
+                    status: std::process::ExitStatus::from_raw(128 << 8),
+                    stdout: Vec::new(),
+                    stderr: b"fatal: Not possible to fast-forward, aborting.".to_vec(),
+                })
+            });
+        let result = command.run(&mock_runner);
+        assert!(result.unwrap_err().to_string().contains("Not possible to fast-forward"));
+        mock_runner.checkpoint();
+    }
+}
@@ -0,0 +1,116 @@
+//! This command limits the working tree of a git repository to a set of directories with a cone mode sparse
+//! checkout.
+use super::command_runner::{check_status, CoreRunner};
+use std::path::Path;
+
+
+/// The wedding invite is always added to the sparse checkout so the dependency can still be read.
+const WEDDING_INVITE: &str = "wedding_invite.yml";
+
+
+/// A command to set the directories of a repository that are checked out.
+///
+/// # Fields
+/// * `path_to_repo` - The path to the directory holding the repository
+/// * `repo_name` - The name of the repository to set the sparse checkout of
+/// * `paths` - The directories to check out relative to the root of the repository
+pub struct SparseCheckoutCommand {
+    pub path_to_repo: String,
+    pub repo_name: String,
+    pub paths: Vec<String>
+}
+
+impl SparseCheckoutCommand {
+
+    /// Creates a new SparseCheckoutCommand struct.
+    ///
+    /// # Arguments
+    /// * `path_to_repo` - The path to the directory holding the repository
+    /// * `repo_name` - The name of the repository to set the sparse checkout of
+    /// * `paths` - The directories to check out relative to the root of the repository
+    ///
+    /// # Returns
+    /// A new SparseCheckoutCommand struct
+    pub fn new(path_to_repo: String, repo_name: String, paths: Vec<String>) -> Self {
+        Self {
+            path_to_repo,
+            repo_name,
+            paths
+        }
+    }
+
+    /// Runs the sparse checkout command in the repository without a shell, so the path and the directories from the
+    /// seating plan can hold spaces.
+    ///
+    /// # Arguments
+    /// * `runner` - The command runner to for the command being run
+    ///
+    /// # Returns
+    /// The output of the command or an error containing the git stderr if the command failed
+    pub fn run(&self, runner: &dyn CoreRunner) -> Result<std::process::Output, std::io::Error> {
+        let root_path = Path::new(&self.path_to_repo).join(&self.repo_name);
+        check_status(runner.run_in_dir(&root_path, "git", &["sparse-checkout", "init", "--cone"])?)?;
+        let mut args = vec!["sparse-checkout", "set"];
+        args.extend(self.paths.iter().map(String::as_str));
+        args.push(WEDDING_INVITE);
+        check_status(runner.run_in_dir(&root_path, "git", &args)?)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use mockall::Sequence;
+    use std::os::unix::process::ExitStatusExt;
+    use super::super::command_runner::MockCoreRunner;
+    use std::process::Output;
+
+    fn expect_sparse_checkout(dir: &'static str, set: &'static [&'static str]) -> MockCoreRunner {
+        let mut mock_runner = MockCoreRunner::new();
+        let mut sequence = Sequence::new();
+        for expected in [&["sparse-checkout", "init", "--cone"][..], set] {
+            mock_runner.expect_run_in_dir()
+                .withf(move |path, program, args| path == Path::new(dir) && program == "git" && args == expected)
+                .times(1)
+                .in_sequence(&mut sequence)
+                .returning(|_, _, _| {
+                    Ok(Output {
+                        status: std::process::ExitStatus::from_raw(0),
+                        stdout: Vec::new(),
+                        stderr: Vec::new(),
+                    })
+                });
+        }
+        mock_runner
+    }
+
+    #[test]
+    fn test_run() {
+        let command = SparseCheckoutCommand::new(
+            "venue".to_string(),
+            "monorepo".to_string(),
+            vec!["services/auth".to_string(), "shared libs".to_string()]
+        );
+        let mut mock_runner = expect_sparse_checkout(
+            "venue/monorepo", &["sparse-checkout", "set", "services/auth", "shared libs", "wedding_invite.yml"]
+        );
+        assert!(command.run(&mock_runner).is_ok());
+        mock_runner.checkpoint();
+    }
+
+    #[test]
+    fn test_run_path_with_spaces() {
+        let command = SparseCheckoutCommand::new(
+            "my projects/venue; rm -rf".to_string(),
+            "monorepo".to_string(),
+            vec!["services/auth".to_string()]
+        );
+        let mut mock_runner = expect_sparse_checkout(
+            "my projects/venue; rm -rf/monorepo", &["sparse-checkout", "set", "services/auth", "wedding_invite.yml"]
+        );
+        assert!(command.run(&mock_runner).is_ok());
+        mock_runner.checkpoint();
+    }
+}
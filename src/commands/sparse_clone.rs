@@ -0,0 +1,157 @@
+//! This command clones a git repository without checking anything out, so a sparse checkout can be set up
+//! before the working tree is written.
+use super::command_runner::{check_status, CoreRunner};
+use crate::git_auth::{run_git, GitConfigEntry};
+use super::trace::{redact_output, secrets};
+use std::path::Path;
+
+
+/// A command to clone a git repository without its file contents or a working tree.
+///
+/// # Fields
+/// * `repo_url` - The URL of the repository to clone
+/// * `path_to_repo` - The local path to where the repository should be cloned to
+/// * `depth` - The number of commits to clone for a shallow clone, ```None``` clones the full history
+/// * `branch` - The branch or tag to clone, ```None``` clones the default branch of the remote
+/// * `auth_config` - The git config sending the access token of a private repository, ```None``` sends no token
+pub struct SparseCloneCommand {
+    pub repo_url: String,
+    pub path_to_repo: String,
+    pub depth: Option<u32>,
+    pub branch: Option<String>,
+    pub auth_config: Option<GitConfigEntry>
+}
+
+
+impl SparseCloneCommand {
+
+    /// Creates a new SparseCloneCommand struct.
+    ///
+    /// # Arguments
+    /// * `repo_url` - The URL of the repository to clone
+    /// * `path_to_repo` - The path to the repository to clone
+    /// * `depth` - The number of commits to clone for a shallow clone, ```None``` clones the full history
+    /// * `branch` - The branch or tag to clone, ```None``` clones the default branch of the remote
+    ///
+    /// # Returns
+    /// A new SparseCloneCommand struct
+    pub fn new(repo_url: String, path_to_repo: String, depth: Option<u32>, branch: Option<String>) -> Self {
+        Self {
+            repo_url,
+            path_to_repo,
+            depth,
+            branch,
+            auth_config: None
+        }
+    }
+
+    /// Sets the git config the access token is sent with, set through the environment of git so it is
+    /// not stored in the clone or shown in the arguments of git.
+    ///
+    /// # Arguments
+    /// * `auth_config` - The ```http.<url>.extraHeader``` config, ```None``` sends no token
+    ///
+    /// # Returns
+    /// The command sending the token
+    pub fn with_auth_config(self, auth_config: Option<GitConfigEntry>) -> Self {
+        Self { auth_config, ..self }
+    }
+
+    /// Runs the sparse clone command in the venue without a shell, so the path can hold spaces. The file contents
+    /// are fetched when they are checked out with ```--filter=blob:none``` so only the files in the sparse checkout
+    /// are downloaded. The secrets such as the token of the auth config are redacted from the output and the error.
+    ///
+    /// # Arguments
+    /// * `runner` - The command runner to for the command being run
+    ///
+    /// # Returns
+    /// The output of the command or an error containing the git stderr if the command failed
+    pub fn run(&self, runner: &dyn CoreRunner) -> Result<std::process::Output, std::io::Error> {
+        let depth = self.depth.map(|depth| depth.to_string());
+        let mut args = Vec::new();
+        args.extend(["clone", "--filter=blob:none", "--no-checkout"]);
+        if let Some(depth) = &depth {
+            args.extend(["--depth", depth]);
+        }
+        if let Some(branch) = &self.branch {
+            args.extend(["--branch", branch, "--single-branch"]);
+        }
+        args.push(&self.repo_url);
+        let output = run_git(runner, Path::new(&self.path_to_repo), &args, self.auth_config.as_ref())?;
+        check_status(redact_output(output, &secrets()))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use super::super::command_runner::MockCoreRunner;
+    use std::process::Output;
+
+    static REPO_URL: &str = "https://github.com/yellow-bird-consult/monorepo";
+
+    fn expect_command(dir: &'static str, expected: &'static [&'static str], code: i32) -> MockCoreRunner {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .withf(move |path, program, args| path == Path::new(dir) && program == "git" && args == expected)
+            .times(1)
+            .returning(move |_, _, _| {
+                Ok(Output {
+                    status: std::process::ExitStatus::from_raw(code << 8),
+                    stdout: Vec::new(),
+                    stderr: b"fatal: repository not found".to_vec(),
+                })
+            });
+        mock_runner
+    }
+
+    #[test]
+    fn test_run() {
+        let command = SparseCloneCommand::new(REPO_URL.to_string(), "venue".to_string(), None, None);
+        let mut mock_runner = expect_command(
+            "venue", &["clone", "--filter=blob:none", "--no-checkout", "https://github.com/yellow-bird-consult/monorepo"], 0
+        );
+        assert!(command.run(&mock_runner).is_ok());
+        mock_runner.checkpoint();
+    }
+
+    #[test]
+    fn test_run_with_depth() {
+        let command = SparseCloneCommand::new(REPO_URL.to_string(), "venue".to_string(), Some(1), Some("main".to_string()));
+        let mut mock_runner = expect_command(
+            "venue",
+            &[
+                "clone", "--filter=blob:none", "--no-checkout", "--depth", "1", "--branch", "main", "--single-branch",
+                "https://github.com/yellow-bird-consult/monorepo"
+            ],
+            0
+        );
+        assert!(command.run(&mock_runner).is_ok());
+        mock_runner.checkpoint();
+    }
+
+    #[test]
+    fn test_run_path_with_spaces() {
+        let command = SparseCloneCommand::new(REPO_URL.to_string(), "my projects/venue; rm -rf".to_string(), None, None);
+        let mut mock_runner = expect_command(
+            "my projects/venue; rm -rf",
+            &["clone", "--filter=blob:none", "--no-checkout", "https://github.com/yellow-bird-consult/monorepo"],
+            0
+        );
+        assert!(command.run(&mock_runner).is_ok());
+        mock_runner.checkpoint();
+    }
+
+    #[test]
+    fn test_run_failure() {
+        let command = SparseCloneCommand::new(REPO_URL.to_string(), "venue".to_string(), None, None);
+        let mut mock_runner = expect_command(
+            "venue", &["clone", "--filter=blob:none", "--no-checkout", "https://github.com/yellow-bird-consult/monorepo"], 128
+        );
+        assert!(command.run(&mock_runner).unwrap_err().to_string().contains("fatal: repository not found"));
+        mock_runner.checkpoint();
+    }
+}
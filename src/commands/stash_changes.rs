@@ -0,0 +1,80 @@
+//! This command stashes the local changes in the working tree of a git repository.
+use super::command_runner::{check_status, CoreRunner};
+use std::path::Path;
+
+
+/// The message the changes stashed by wedp are saved under so they can be found with ```git stash list```.
+pub const STASH_MESSAGE: &str = "wedp update";
+
+
+/// A command to stash the modified, staged, and untracked files of a repository, leaving a clean working tree.
+/// 
+/// # Fields
+/// * `path_to_repo` - The path to the directory holding the repository
+/// * `repo_name` - The name of the repository to stash the changes of
+pub struct StashChangesCommand {
+    pub path_to_repo: String,
+    pub repo_name: String
+}
+
+impl StashChangesCommand {
+
+    /// Creates a new StashChangesCommand struct.
+    /// 
+    /// # Arguments
+    /// * `path_to_repo` - The path to the directory holding the repository
+    /// * `repo_name` - The name of the repository to stash the changes of
+    /// 
+    /// # Returns
+    /// A new StashChangesCommand struct
+    pub fn new(path_to_repo: String, repo_name: String) -> Self {
+        Self {
+            path_to_repo,
+            repo_name
+        }
+    }
+
+    /// Runs the stash command in the repository without a shell.
+    /// 
+    /// # Arguments
+    /// * `runner` - The command runner to for the command being run
+    /// 
+    /// # Returns
+    /// The output of the command or an error containing the git stderr if the command failed
+    pub fn run(&self, runner: &dyn CoreRunner) -> Result<std::process::Output, std::io::Error> {
+        let root_path = Path::new(&self.path_to_repo).join(&self.repo_name);
+        check_status(runner.run_in_dir(&root_path, "git", &["stash", "push", "--include-untracked", "--message", STASH_MESSAGE])?)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use super::super::command_runner::MockCoreRunner;
+    use std::process::Output;
+
+    #[test]
+    fn test_run() {
+        let command = StashChangesCommand::new("/path/to/repo".to_string(), "test_repo".to_string());
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .withf(|dir, program, args| {
+                dir == Path::new("/path/to/repo/test_repo") && program == "git" 
+                    && args == ["stash", "push", "--include-untracked", "--message", "wedp update"]
+            })
+            .times(1)
+            .returning(|_, _, _| {
+                Ok(Output {
+                    status: std::process::ExitStatus::from_raw(0),
+                    stdout: Vec::new(),
+                    stderr: Vec::new(),
+                })
+            });
+        assert!(command.run(&mock_runner).is_ok());
+        mock_runner.checkpoint();
+    }
+
+}
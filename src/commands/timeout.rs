@@ -0,0 +1,174 @@
+//! Enforces the ```--timeout-per-command``` safety net. Once a timeout is set every shell invocation run 
+//! through the ```CommandRunner``` is killed if it runs for longer than the timeout. Foreground 
+//! ```docker-compose up``` and ```docker-compose logs -f``` commands are excluded as they are expected to run
+//! until stopped.
+use std::io::Read;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::OnceLock;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::clock::Clock;
+
+
+/// How often a running command is checked to see if it has finished.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+static TIMEOUT_PER_COMMAND: OnceLock<Duration> = OnceLock::new();
+
+
+/// Sets the timeout applied to every command for the rest of the process. Only the first call has an effect.
+/// 
+/// # Arguments
+/// * `timeout` - The longest any single command is allowed to run for
+pub fn set_timeout_per_command(timeout: Duration) {
+    let _ = TIMEOUT_PER_COMMAND.set(timeout);
+}
+
+/// Gets the timeout applied to every command.
+/// 
+/// # Returns
+/// * `Option<Duration>` - The timeout or ```None``` if commands are not timed out
+pub fn timeout_per_command() -> Option<Duration> {
+    TIMEOUT_PER_COMMAND.get().copied()
+}
+
+/// Checks if a docker command runs until the user stops it, these are never timed out and stay in the process
+/// group of wedp so Ctrl+C reaches them.
+/// 
+/// # Arguments
+/// * `command` - The docker-compose subcommand such as ``` up -d```
+/// 
+/// # Returns
+/// * `bool` - True if the command is ```up``` without ```-d``` or ```--detach```, or ```logs``` with ```-f```
+pub fn runs_until_stopped(command: &str) -> bool {
+    let mut parts = command.split_whitespace();
+    match parts.next() {
+        Some("up") => !parts.any(|part| part == "-d" || part == "--detach"),
+        Some("logs") => parts.any(|part| part == "-f" || part == "--follow"),
+        _ => false
+    }
+}
+
+/// Waits for a child process to finish, killing it if it runs for longer than the timeout.
+/// 
+/// # Arguments
+/// * `child` - The running child process
+/// * `timeout` - The longest the child process is allowed to run for
+/// * `clock` - The clock used to measure how long the child process has been running
+/// 
+/// # Returns
+/// * `Result<ExitStatus, std::io::Error>` - The exit status or a ```TimedOut``` error if the child process was killed
+pub fn wait_with_timeout(child: &mut Child, timeout: Duration, clock: &dyn Clock) -> Result<ExitStatus, std::io::Error> {
+    let start = clock.now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status)
+        }
+        if clock.now().duration_since(start) >= timeout {
+            kill_process_group(child);
+            child.kill()?;
+            child.wait()?;
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("command timed out after {}s", timeout.as_secs())
+            ))
+        }
+        clock.sleep(POLL_INTERVAL);
+    }
+}
+
+/// Kills every process in the process group of a child process so commands such as ```cd repo && git clone```
+/// do not leave the git process running after the shell is killed. The child process must have been spawned
+/// with ```process_group(0)```.
+/// 
+/// # Arguments
+/// * `child` - The child process leading the process group
+fn kill_process_group(child: &Child) {
+    let _ = Command::new("kill")
+        .arg("-KILL")
+        .arg("--")
+        .arg(format!("-{}", child.id()))
+        .stderr(Stdio::null())
+        .status();
+}
+
+/// Reads a pipe of a child process to the end on another thread so the child process never blocks on a full pipe.
+/// 
+/// # Arguments
+/// * `pipe` - The stdout or stderr of the child process
+/// 
+/// # Returns
+/// * `JoinHandle<Vec<u8>>` - The handle to join to get everything written to the pipe
+pub fn read_in_background<R: Read + Send + 'static>(pipe: Option<R>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::clock::MockClock;
+    use std::cell::Cell;
+    use std::os::unix::process::CommandExt;
+    use std::time::Instant;
+
+    fn spawn(command: &str) -> Child {
+        Command::new("sh").arg("-c").arg(command)
+            .process_group(0)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn().unwrap()
+    }
+
+    #[test]
+    fn test_slow_command_is_killed() {
+        let start = Instant::now();
+        let ticks = Cell::new(0);
+        let mut clock = MockClock::new();
+        clock.expect_now().returning(move || {
+            ticks.set(ticks.get() + 1);
+            start + Duration::from_secs(10 * (ticks.get() - 1))
+        });
+        clock.expect_sleep().returning(|_| ());
+        let mut child = spawn("sleep 30");
+
+        let error = wait_with_timeout(&mut child, Duration::from_secs(15), &clock).unwrap_err();
+
+        assert_eq!(error.kind(), std::io::ErrorKind::TimedOut);
+        assert_eq!(error.to_string(), "command timed out after 15s");
+        assert!(child.try_wait().unwrap().is_some());
+        assert!(start.elapsed() < Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_fast_command_succeeds() {
+        let start = Instant::now();
+        let mut clock = MockClock::new();
+        clock.expect_now().returning(move || start);
+        clock.expect_sleep().returning(thread::sleep);
+        let mut child = spawn("exit 0");
+
+        let status = wait_with_timeout(&mut child, Duration::from_secs(15), &clock).unwrap();
+
+        assert!(status.success());
+    }
+
+    #[test]
+    fn test_runs_until_stopped() {
+        assert!(runs_until_stopped(" up"));
+        assert!(!runs_until_stopped(" up -d"));
+        assert!(!runs_until_stopped(" up --detach --wait"));
+        assert!(!runs_until_stopped(" build --no-cache"));
+        assert!(!runs_until_stopped(" down"));
+        assert!(runs_until_stopped(" logs -f --tail=100 auth_db"));
+        assert!(!runs_until_stopped(" logs auth_db"));
+    }
+}
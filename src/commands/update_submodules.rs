@@ -0,0 +1,115 @@
+//! This command initializes and updates the submodules in a git repository.
+use super::command_runner::CoreRunner;
+use std::path::Path;
+
+
+/// A command to initialize and update the submodules of a repository.
+/// 
+/// # Fields
+/// * `path_to_repo` - The path to the directory holding the repository
+/// * `repo_name` - The name of the repository to update the submodules in
+/// * `depth` - The number of commits to fetch for each submodule, set for shallow clones
+pub struct UpdateSubmodulesCommand {
+    pub path_to_repo: String,
+    pub repo_name: String,
+    pub depth: Option<u32>
+}
+
+impl UpdateSubmodulesCommand {
+
+    /// Creates a new UpdateSubmodulesCommand struct.
+    /// 
+    /// # Arguments
+    /// * `path_to_repo` - The path to the directory holding the repository
+    /// * `repo_name` - The name of the repository to update the submodules in
+    /// * `depth` - The number of commits to fetch for each submodule, set for shallow clones
+    /// 
+    /// # Returns
+    /// A new UpdateSubmodulesCommand struct
+    pub fn new(path_to_repo: String, repo_name: String, depth: Option<u32>) -> Self {
+        Self {
+            path_to_repo,
+            repo_name,
+            depth
+        }
+    }
+
+    /// Runs the update submodules command in the repository without a shell, so the path can hold spaces.
+    /// 
+    /// # Arguments
+    /// * `runner` - The command runner to for the command being run
+    /// 
+    /// # Returns
+    /// The output of the command
+    pub fn run(&self, runner: &dyn CoreRunner) -> Result<std::process::Output, std::io::Error> {
+        let root_path = Path::new(&self.path_to_repo).join(&self.repo_name);
+        let depth = self.depth.map(|depth| depth.to_string());
+        let mut args = vec!["submodule", "update", "--init", "--recursive"];
+        if let Some(depth) = &depth {
+            args.extend(["--depth", depth]);
+        }
+        runner.run_in_dir(&root_path, "git", &args)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use super::super::command_runner::MockCoreRunner;
+    use std::process::Output;
+
+    #[test]
+    fn test_new() {
+        let command = UpdateSubmodulesCommand::new("/path/to/repo".to_string(), "test_repo".to_string(), None);
+        assert_eq!(command.path_to_repo, "/path/to/repo");
+        assert_eq!(command.repo_name, "test_repo");
+    }
+
+    fn expect_update(dir: &'static str, expected: &'static [&'static str]) -> MockCoreRunner {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .withf(move |path, program, args| path == Path::new(dir) && program == "git" && args == expected)
+            .times(1)
+            .returning(|_, _, _| {
+                Ok(Output {
+                    status: std::process::ExitStatus::from_raw(0),
+                    stdout: Vec::new(),
+                    stderr: Vec::new(),
+                })
+            });
+        mock_runner
+    }
+
+    #[test]
+    fn test_run() {
+        let command = UpdateSubmodulesCommand::new("/path/to/repo".to_string(), "test_repo".to_string(), None);
+        let mut mock_runner = expect_update("/path/to/repo/test_repo", &["submodule", "update", "--init", "--recursive"]);
+        let result = command.run(&mock_runner);
+        assert!(result.is_ok());
+        mock_runner.checkpoint();
+    }
+
+    #[test]
+    fn test_run_with_depth() {
+        let command = UpdateSubmodulesCommand::new("/path/to/repo".to_string(), "test_repo".to_string(), Some(1));
+        let mut mock_runner = expect_update(
+            "/path/to/repo/test_repo", &["submodule", "update", "--init", "--recursive", "--depth", "1"]
+        );
+        let result = command.run(&mock_runner);
+        assert!(result.is_ok());
+        mock_runner.checkpoint();
+    }
+
+    #[test]
+    fn test_run_path_with_spaces() {
+        let command = UpdateSubmodulesCommand::new("my projects/venue; rm -rf".to_string(), "test_repo".to_string(), None);
+        let mut mock_runner = expect_update(
+            "my projects/venue; rm -rf/test_repo", &["submodule", "update", "--init", "--recursive"]
+        );
+        assert!(command.run(&mock_runner).is_ok());
+        mock_runner.checkpoint();
+    }
+}
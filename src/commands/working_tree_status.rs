@@ -0,0 +1,96 @@
+//! This command lists the local changes in the working tree of a git repository.
+use super::command_runner::{check_status, CoreRunner};
+use std::path::Path;
+
+
+/// A command to list the modified, staged, and untracked files of a repository in the porcelain format.
+/// 
+/// # Fields
+/// * `path_to_repo` - The path to the directory holding the repository
+/// * `repo_name` - The name of the repository to list the changes of
+pub struct WorkingTreeStatusCommand {
+    pub path_to_repo: String,
+    pub repo_name: String
+}
+
+impl WorkingTreeStatusCommand {
+
+    /// Creates a new WorkingTreeStatusCommand struct.
+    /// 
+    /// # Arguments
+    /// * `path_to_repo` - The path to the directory holding the repository
+    /// * `repo_name` - The name of the repository to list the changes of
+    /// 
+    /// # Returns
+    /// A new WorkingTreeStatusCommand struct
+    pub fn new(path_to_repo: String, repo_name: String) -> Self {
+        Self {
+            path_to_repo,
+            repo_name
+        }
+    }
+
+    /// Runs the status command in the repository without a shell.
+    /// 
+    /// # Arguments
+    /// * `runner` - The command runner to for the command being run
+    /// 
+    /// # Returns
+    /// A line for each changed file, empty if the working tree is clean, or an error containing the git stderr 
+    /// if the command failed
+    pub fn run(&self, runner: &dyn CoreRunner) -> Result<Vec<String>, std::io::Error> {
+        let root_path = Path::new(&self.path_to_repo).join(&self.repo_name);
+        let output = check_status(runner.run_in_dir(&root_path, "git", &["status", "--porcelain"])?)?;
+        Ok(String::from_utf8_lossy(&output.stdout).lines()
+                  .filter(|line| !line.trim().is_empty())
+                  .map(String::from)
+                  .collect())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use super::super::command_runner::MockCoreRunner;
+    use std::process::Output;
+
+    #[test]
+    fn test_run() {
+        let command = WorkingTreeStatusCommand::new("/path/to/repo".to_string(), "test_repo".to_string());
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .withf(|dir, program, args| {
+                dir == Path::new("/path/to/repo/test_repo") && program == "git" && args == ["status", "--porcelain"]
+            })
+            .times(1)
+            .returning(|_, _, _| {
+                Ok(Output {
+                    status: std::process::ExitStatus::from_raw(0),
+                    stdout: b" M Dockerfile\n?? notes.txt\n".to_vec(),
+                    stderr: Vec::new(),
+                })
+            });
+        let result = command.run(&mock_runner).unwrap();
+        assert_eq!(result, vec![" M Dockerfile".to_string(), "?? notes.txt".to_string()]);
+        mock_runner.checkpoint();
+    }
+
+    #[test]
+    fn test_run_clean() {
+        let command = WorkingTreeStatusCommand::new("/path/to/repo".to_string(), "test_repo".to_string());
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .returning(|_, _, _| {
+                Ok(Output {
+                    status: std::process::ExitStatus::from_raw(0),
+                    stdout: Vec::new(),
+                    stderr: Vec::new(),
+                })
+            });
+        assert!(command.run(&mock_runner).unwrap().is_empty());
+    }
+
+}
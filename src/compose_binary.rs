@@ -0,0 +1,220 @@
+//! Resolves the command used to run docker compose. Newer Docker installs only ship the compose v2 plugin,
+//! run as ```docker compose```, while older ones only have the standalone ```docker-compose``` binary. The
+//! command can be set with the ```WEDP_COMPOSE``` environment variable or the ```compose_command``` of the
+//! seating plan, otherwise ```docker compose version``` is probed and ```docker-compose``` is the fallback.
+//! The container engine can also be picked with the ```engine``` of the seating plan or ```--engine```, which
+//! runs the compose files through podman instead of docker.
+use serde::{Deserialize, Serialize};
+
+use crate::commands::command_runner::CoreRunner;
+
+
+/// The environment variable overriding the compose command.
+pub const COMPOSE_ENV: &str = "WEDP_COMPOSE";
+
+/// The compose v2 plugin of the docker CLI.
+pub const COMPOSE_V2: &str = "docker compose";
+
+/// The standalone compose binary.
+pub const COMPOSE_V1: &str = "docker-compose";
+
+/// The compose subcommand of podman 4.7 and newer.
+pub const PODMAN_COMPOSE: &str = "podman compose";
+
+/// The standalone podman-compose binary for older versions of podman.
+pub const PODMAN_COMPOSE_V1: &str = "podman-compose";
+
+/// The names of the container engines accepted by ```engine``` and ```--engine```.
+pub const ENGINES: [&str; 3] = ["docker", "docker-compose-v2", "podman"];
+
+
+/// The container engine the compose files are run with.
+///
+/// # Fields
+/// * `Docker` - Docker with the standalone ```docker-compose``` binary
+/// * `DockerComposeV2` - Docker with the compose v2 plugin run as ```docker compose```
+/// * `Podman` - Podman with ```podman compose``` or the standalone ```podman-compose``` binary
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ContainerEngine {
+    Docker,
+    DockerComposeV2,
+    Podman,
+}
+
+impl ContainerEngine {
+
+    /// Gets the container engine from its name.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the engine such as ```podman```
+    ///
+    /// # Returns
+    /// * `Result<ContainerEngine, String>` - The engine or an error if the name is not one of the ```ENGINES```
+    pub fn from_name(name: &str) -> Result<ContainerEngine, String> {
+        match name {
+            "docker" => Ok(ContainerEngine::Docker),
+            "docker-compose-v2" => Ok(ContainerEngine::DockerComposeV2),
+            "podman" => Ok(ContainerEngine::Podman),
+            _ => Err(format!("engine {} is not one of {}", name, ENGINES.join(", ")))
+        }
+    }
+
+    /// Gets the compose command of the engine. Podman only has the ```podman compose``` subcommand from
+    /// version 4.7 so it is probed with ```podman compose version``` and ```podman-compose``` is the fallback.
+    ///
+    /// # Arguments
+    /// * `runner` - The runner used to probe for ```podman compose```
+    ///
+    /// # Returns
+    /// * `String` - The compose command of the engine
+    pub fn compose_command(&self, runner: &dyn CoreRunner) -> String {
+        match self {
+            ContainerEngine::Docker => COMPOSE_V1.to_string(),
+            ContainerEngine::DockerComposeV2 => COMPOSE_V2.to_string(),
+            ContainerEngine::Podman => probe(PODMAN_COMPOSE, PODMAN_COMPOSE_V1, runner)
+        }
+    }
+}
+
+
+/// Probes for a compose subcommand by running its ```version``` command.
+///
+/// # Arguments
+/// * `command` - The compose subcommand to probe for
+/// * `fallback` - The command used if the probe fails
+/// * `runner` - The runner used to run the probe
+///
+/// # Returns
+/// * `String` - The subcommand if its version could be read, otherwise the fallback
+fn probe(command: &str, fallback: &str, runner: &dyn CoreRunner) -> String {
+    match runner.run(&format!("{} version", command)) {
+        Ok(output) if output.status.success() => command.to_string(),
+        _ => fallback.to_string()
+    }
+}
+
+
+/// Checks a compose command only holds words of the characters found in paths and binary names, as it is
+/// rendered into every docker compose command without quoting.
+///
+/// # Arguments
+/// * `command` - The compose command from the seating plan or the environment
+///
+/// # Returns
+/// * `Result<(), String>` - An error if the command is empty or holds any other characters
+pub fn validate_compose_command(command: &str) -> Result<(), String> {
+    let allowed = |character: char| character.is_ascii_alphanumeric() || " ._/-".contains(character);
+    match !command.trim().is_empty() && !command.trim_start().starts_with('-') && command.chars().all(allowed) {
+        true => Ok(()),
+        false => Err(format!("compose_command {} is not a valid command", command))
+    }
+}
+
+
+/// Resolves the compose command. An explicit command wins over the engine, and if neither is set the compose
+/// v2 plugin is used when ```docker compose version``` succeeds.
+///
+/// # Arguments
+/// * `command` - The explicit compose command from ```WEDP_COMPOSE``` or the seating plan
+/// * `engine` - The container engine from ```--engine``` or the seating plan
+/// * `runner` - The runner used to probe for the compose subcommands
+///
+/// # Returns
+/// * `Result<String, String>` - The compose command or an error if the explicit command is not valid
+pub fn resolve_compose_command(command: Option<String>, engine: Option<ContainerEngine>, runner: &dyn CoreRunner) -> Result<String, String> {
+    if let Some(command) = command.filter(|command| !command.is_empty()) {
+        validate_compose_command(&command)?;
+        return Ok(command.trim().to_string())
+    }
+    match engine {
+        Some(engine) => Ok(engine.compose_command(runner)),
+        None => Ok(probe(COMPOSE_V2, COMPOSE_V1, runner))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::commands::command_runner::MockCoreRunner;
+    use mockall::predicate::eq;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::{ExitStatus, Output};
+
+    fn probe(command: &str, code: i32) -> MockCoreRunner {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run()
+            .with(eq(format!("{} version", command)))
+            .times(1)
+            .returning(move |_| Ok(Output { status: ExitStatus::from_raw(code << 8), stdout: Vec::new(), stderr: Vec::new() }));
+        mock_runner
+    }
+
+    fn no_probe() -> MockCoreRunner {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run().times(0);
+        mock_runner
+    }
+
+    #[test]
+    fn test_resolve_probes_compose_v2() {
+        assert_eq!(resolve_compose_command(None, None, &probe("docker compose", 0)), Ok("docker compose".to_string()));
+    }
+
+    #[test]
+    fn test_resolve_falls_back_to_docker_compose() {
+        assert_eq!(resolve_compose_command(None, None, &probe("docker compose", 1)), Ok("docker-compose".to_string()));
+
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run().returning(|_| Err(std::io::Error::from(std::io::ErrorKind::NotFound)));
+        assert_eq!(resolve_compose_command(None, None, &mock_runner), Ok("docker-compose".to_string()));
+    }
+
+    #[test]
+    fn test_resolve_command_override() {
+        assert_eq!(
+            resolve_compose_command(Some("/usr/local/bin/podman-compose".to_string()), Some(ContainerEngine::Docker), &no_probe()),
+            Ok("/usr/local/bin/podman-compose".to_string())
+        );
+        assert_eq!(
+            resolve_compose_command(Some("docker compose; rm -rf /".to_string()), None, &no_probe()),
+            Err("compose_command docker compose; rm -rf / is not a valid command".to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_engine_matrix() {
+        let cases = [
+            (ContainerEngine::Docker, no_probe(), "docker-compose"),
+            (ContainerEngine::DockerComposeV2, no_probe(), "docker compose"),
+            (ContainerEngine::Podman, probe("podman compose", 0), "podman compose"),
+            (ContainerEngine::Podman, probe("podman compose", 125), "podman-compose"),
+        ];
+        for (engine, mock_runner, expected) in cases {
+            assert_eq!(resolve_compose_command(None, Some(engine), &mock_runner), Ok(expected.to_string()), "{:?}", engine);
+        }
+    }
+
+    #[test]
+    fn test_engine_from_name() {
+        assert_eq!(ContainerEngine::from_name("docker"), Ok(ContainerEngine::Docker));
+        assert_eq!(ContainerEngine::from_name("docker-compose-v2"), Ok(ContainerEngine::DockerComposeV2));
+        assert_eq!(ContainerEngine::from_name("podman"), Ok(ContainerEngine::Podman));
+        assert_eq!(
+            ContainerEngine::from_name("containerd"),
+            Err("engine containerd is not one of docker, docker-compose-v2, podman".to_string())
+        );
+        assert_eq!(serde_yaml::from_str::<ContainerEngine>("docker-compose-v2").unwrap(), ContainerEngine::DockerComposeV2);
+    }
+
+    #[test]
+    fn test_validate_compose_command() {
+        assert!(validate_compose_command("docker compose").is_ok());
+        assert!(validate_compose_command("docker-compose").is_ok());
+        for command in ["", " ", "-f x.yml", "docker compose $(id)", "docker-compose`id`", "docker-compose\nid"] {
+            assert!(validate_compose_command(command).is_err(), "{:?}", command);
+        }
+    }
+}
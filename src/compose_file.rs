@@ -0,0 +1,452 @@
+//! Compose files are the docker-compose files that are passed to docker-compose with the ```-f``` flag. The
+//! files are collected as structured data and only rendered into a listing at the edge. Docker-compose is run
+//! without a shell: every path is a separate argument of a ```ProgramArgs``` and the docker-compose subcommand
+//! is always a ```ComposeAction```. Any new value read from a seating plan or wedding invite that ends up in a
+//! docker-compose command, such as profiles or project names, must also be a separate argument or checked like
+//! ```ServiceName```.
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+use crate::commands::command_runner::ProgramArgs;
+use crate::file_handler::CoreFileHandle;
+
+
+/// A reference to a docker-compose file and the attendee it came from.
+/// 
+/// # Fields
+/// * `attendee` - The name of the attendee the file belongs to, ```local``` for the local wedding invite
+/// * `path` - The path to the docker-compose file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ComposeFileRef {
+    pub attendee: String,
+    pub path: String,
+}
+
+impl ComposeFileRef {
+
+    /// Creates a new ComposeFileRef struct.
+    /// 
+    /// # Arguments
+    /// * `attendee` - The name of the attendee the file belongs to
+    /// * `path` - The path to the docker-compose file
+    /// 
+    /// # Returns
+    /// A new ComposeFileRef struct
+    pub fn new(attendee: &str, path: String) -> Self {
+        ComposeFileRef { attendee: attendee.to_string(), path }
+    }
+}
+
+
+/// The number of lines of each container printed before following its logs.
+pub const LOGS_TAIL: usize = 100;
+
+
+/// A service name that has been checked to only contain the characters docker-compose allows in a service name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceName(String);
+
+impl ServiceName {
+
+    /// Creates a new ServiceName struct.
+    /// 
+    /// # Arguments
+    /// * `service` - The service name to check
+    /// 
+    /// # Returns
+    /// * `Result<ServiceName, String>` - The service name or an error if it is not valid
+    pub fn new(service: &str) -> Result<Self, String> {
+        let valid_characters = service.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.');
+        if service.is_empty() || !valid_characters || service.starts_with('-') {
+            return Err(format!("{} is not a valid service name", service))
+        }
+        Ok(ServiceName(service.to_string()))
+    }
+}
+
+impl fmt::Display for ServiceName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+
+/// The flags appended to an ```up``` action by ```--force-recreate```, replacing the containers of a compose
+/// project started from another location and removing the ones this location does not define.
+pub const FORCE_RECREATE: &str = " --force-recreate --remove-orphans";
+
+
+/// The docker-compose subcommands wedp runs. Each action renders to a fixed suffix so nothing read from a 
+/// seating plan or wedding invite can add flags to the command, the only value that is not fixed is a
+/// ```ServiceName``` which is checked when it is created.
+/// 
+/// # Fields
+/// * `Up` - Runs the containers in the foreground
+/// * `UpDetached` - Runs the containers in the background
+/// * `UpDetachedWait` - Runs the containers in the background and waits for them to be running or healthy
+/// * `UpAbortOnExit` - Runs the containers in the foreground and stops them all when any container exits
+/// * `Down` - Tears down the containers
+/// * `Build` - Builds the images, pulling the latest base images if ```pull``` is set and without the build cache
+///   if ```no_cache``` is set
+/// * `Ps` - Lists the containers of the project
+/// * `PsJson` - Lists the containers of the project as JSON, compose v2 only
+/// * `ConfigQuiet` - Validates the merged compose files only printing the problems
+/// * `Logs` - Prints the last ```tail``` lines of the services, or all containers if there are none, and keeps 
+///   following them if ```follow``` is set. Following starts from the last ```LOGS_TAIL``` lines if ```tail``` is not set
+#[derive(Debug, Clone, PartialEq)]
+pub enum ComposeAction {
+    Up,
+    UpDetached,
+    UpDetachedWait,
+    UpAbortOnExit(ServiceName),
+    Down,
+    Build { pull: bool, no_cache: bool },
+    Ps,
+    PsJson,
+    ConfigQuiet,
+    Logs { follow: bool, tail: Option<usize>, services: Vec<ServiceName> },
+}
+
+impl fmt::Display for ComposeAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ComposeAction::Up => write!(f, " up"),
+            ComposeAction::UpDetached => write!(f, " up -d"),
+            ComposeAction::UpDetachedWait => write!(f, " up -d --wait"),
+            ComposeAction::UpAbortOnExit(service) => write!(f, " up --abort-on-container-exit --exit-code-from {}", service),
+            ComposeAction::Down => write!(f, " down"),
+            ComposeAction::Build { pull, no_cache } => {
+                write!(f, " build")?;
+                if *pull {
+                    write!(f, " --pull")?;
+                }
+                if *no_cache {
+                    write!(f, " --no-cache")?;
+                }
+                Ok(())
+            },
+            ComposeAction::Ps => write!(f, " ps"),
+            ComposeAction::PsJson => write!(f, " ps --format json"),
+            ComposeAction::ConfigQuiet => write!(f, " config -q"),
+            ComposeAction::Logs { follow, tail, services } => {
+                match (follow, tail) {
+                    (true, tail) => write!(f, " logs -f --tail={}", tail.unwrap_or(LOGS_TAIL))?,
+                    (false, Some(tail)) => write!(f, " logs --tail={}", tail)?,
+                    (false, None) => write!(f, " logs")?
+                }
+                services.iter().try_for_each(|service| write!(f, " {}", service))
+            },
+        }
+    }
+}
+
+impl ComposeAction {
+
+    /// Creates the ```Logs``` action checking every service name.
+    /// 
+    /// # Arguments
+    /// * `follow` - Whether to keep following the logs until interrupted
+    /// * `tail` - The number of lines to print of each container, ```None``` for the default
+    /// * `services` - The services to print the logs of, empty for all of them
+    /// 
+    /// # Returns
+    /// * `Result<ComposeAction, String>` - The action or an error naming the first service name that is not valid
+    pub fn logs(follow: bool, tail: Option<usize>, services: &[String]) -> Result<ComposeAction, String> {
+        let services = services.iter()
+                               .map(|service| ServiceName::new(service))
+                               .collect::<Result<Vec<ServiceName>, String>>()?;
+        Ok(ComposeAction::Logs { follow, tail, services })
+    }
+}
+
+
+/// Quotes a value for a POSIX shell. Values made up of characters that are safe in a shell are left as they 
+/// are, anything else is wrapped in single quotes so it is always passed as a single argument.
+/// 
+/// # Arguments
+/// * `value` - The value to quote
+/// 
+/// # Returns
+/// * `String` - The value that is safe to put into a shell command
+pub fn shell_quote(value: &str) -> String {
+    let safe = !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || "_-./:@+=,%".contains(c));
+    match safe {
+        true => value.to_string(),
+        false => format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+
+/// Puts together the docker-compose command for the compose files. Each path is a separate argument that is
+/// never read by a shell, so a path from a seating plan or wedding invite can never add flags or commands, and
+/// a path starting with ```-``` is prefixed with ```./``` so it is not read as a flag.
+/// 
+/// # Arguments
+/// * `compose` - The command docker compose is run with, such as ```docker compose```, split on whitespace
+/// * `files` - The compose files to pass to docker-compose
+/// 
+/// # Returns
+/// * `ProgramArgs` - The docker-compose program with a ```-f``` flag for each file
+pub fn compose_args(compose: &str, files: &[ComposeFileRef]) -> ProgramArgs {
+    let mut words = compose.split_whitespace();
+    let program = words.next().unwrap_or_default();
+    let mut args: Vec<String> = words.map(String::from).collect();
+    for file in files {
+        let path = match file.path.starts_with('-') {
+            true => format!("./{}", file.path),
+            false => file.path.clone()
+        };
+        args.push("-f".to_string());
+        args.push(path);
+    }
+    ProgramArgs::new(program, args)
+}
+
+/// Makes the paths of the compose files absolute and removes any duplicate paths keeping the first occurrence.
+/// 
+/// # Arguments
+/// * `files` - The compose files to resolve
+/// * `working_directory` - The directory relative paths are resolved against
+/// 
+/// # Returns
+/// * `Vec<ComposeFileRef>` - The resolved compose files
+pub fn resolve_files(files: Vec<ComposeFileRef>, working_directory: &Path) -> Vec<ComposeFileRef> {
+    let mut resolved: Vec<ComposeFileRef> = Vec::new();
+    for file in files {
+        let path = working_directory.join(&file.path);
+        let absolute_path = std::path::absolute(&path).unwrap_or(path).to_string_lossy().to_string();
+        if !resolved.iter().any(|existing| existing.path == absolute_path) {
+            resolved.push(ComposeFileRef::new(&file.attendee, absolute_path));
+        }
+    }
+    resolved
+}
+
+/// Renders the compose files as one path per line.
+/// 
+/// # Arguments
+/// * `files` - The compose files to render
+/// 
+/// # Returns
+/// * `String` - The paths of the compose files
+pub fn render_lines(files: &[ComposeFileRef]) -> String {
+    files.iter().map(|file| format!("{}\n", file.path)).collect()
+}
+
+/// Renders the compose files as a JSON array with the attendee of each file.
+/// 
+/// # Arguments
+/// * `files` - The compose files to render
+/// 
+/// # Returns
+/// * `String` - The JSON array of compose files
+pub fn render_json(files: &[ComposeFileRef]) -> String {
+    serde_json::to_string_pretty(files).unwrap()
+}
+
+
+/// Checks every compose file exists before any docker process is started, so a runner file that was renamed
+/// in a wedding invite is reported with the attendee it came from instead of as a docker-compose error.
+/// 
+/// # Arguments
+/// * `files` - The compose files to check
+/// * `file_handle` - The file handle used to check the files exist
+/// 
+/// # Returns
+/// * `Vec<ComposeFileRef>` - The compose files that do not exist in the order they were passed in
+pub fn missing_files(files: &[ComposeFileRef], file_handle: &dyn CoreFileHandle) -> Vec<ComposeFileRef> {
+    files.iter().filter(|file| !file_handle.exists(Path::new(&file.path))).cloned().collect()
+}
+
+/// Renders the compose files that do not exist with the attendee of each file.
+/// 
+/// # Arguments
+/// * `missing` - The compose files that do not exist
+/// 
+/// # Returns
+/// * `String` - A line saying how many files are missing followed by one line per file
+pub fn render_missing_files(missing: &[ComposeFileRef]) -> String {
+    let mut rendered = match missing.len() {
+        1 => "1 runner file does not exist:".to_string(),
+        count => format!("{} runner files do not exist:", count)
+    };
+    for file in missing {
+        rendered.push_str(&format!("\n  {}: {}", file.attendee, file.path));
+    }
+    rendered
+}
+
+
+/// Reads the services defined in each compose file in the order the files are passed to docker-compose.
+/// 
+/// # Arguments
+/// * `files` - The compose files to read the services from
+/// * `file_handle` - The file handle used to read the compose files
+/// 
+/// # Returns
+/// * `Result<Vec<(String, String)>, String>` - The name of each service and the attendee of the file defining it,
+///   or an error if a file can not be read or parsed
+pub fn read_services(files: &[ComposeFileRef], file_handle: &dyn CoreFileHandle) -> Result<Vec<(String, String)>, String> {
+    let mut services = Vec::new();
+    for file in files {
+        let contents = file_handle.read_to_string(Path::new(&file.path))
+                                  .map_err(|error| format!("Could not read {}: {}", file.path, error))?;
+        let compose: serde_yaml::Value = serde_yaml::from_str(&contents)
+                                  .map_err(|error| format!("Could not parse {}: {}", file.path, error))?;
+        if let Some(defined) = compose.get("services").and_then(|defined| defined.as_mapping()) {
+            let names = defined.keys().filter_map(|name| name.as_str());
+            services.extend(names.map(|name| (name.to_string(), file.attendee.clone())));
+        }
+    }
+    Ok(services)
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::file_handler::MockCoreFileHandle;
+
+    fn test_files() -> Vec<ComposeFileRef> {
+        vec![
+            ComposeFileRef::new("auth", "./venue/auth/base.yml".to_string()),
+            ComposeFileRef::new("auth", "/absolute/auth/database.yml".to_string()),
+            ComposeFileRef::new("billing", "./venue/auth/base.yml".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_missing_files() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_exists()
+            .returning(|path| path != Path::new("/absolute/auth/database.yml"));
+        let missing = missing_files(&test_files(), &mock_handle);
+        assert_eq!(missing, vec![ComposeFileRef::new("auth", "/absolute/auth/database.yml".to_string())]);
+        assert_eq!(render_missing_files(&missing), "1 runner file does not exist:\n  auth: /absolute/auth/database.yml");
+
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_exists().returning(|_| false);
+        assert_eq!(
+            render_missing_files(&missing_files(&test_files(), &mock_handle)),
+            "3 runner files do not exist:\n  auth: ./venue/auth/base.yml\n  auth: /absolute/auth/database.yml\n  billing: ./venue/auth/base.yml"
+        );
+    }
+
+    #[test]
+    fn test_compose_args() {
+        let compose = compose_args("docker compose", &test_files());
+        assert_eq!(compose.program, "docker");
+        assert_eq!(
+            compose.arg_refs(),
+            vec!["compose", "-f", "./venue/auth/base.yml", "-f", "/absolute/auth/database.yml", "-f", "./venue/auth/base.yml"]
+        );
+        assert_eq!(
+            compose_args("docker-compose", &test_files()).to_string(),
+            "docker-compose -f ./venue/auth/base.yml -f /absolute/auth/database.yml -f ./venue/auth/base.yml"
+        );
+    }
+
+    #[test]
+    fn test_resolve_files() {
+        let resolved = resolve_files(test_files(), Path::new("/work"));
+        assert_eq!(resolved, vec![
+            ComposeFileRef::new("auth", "/work/venue/auth/base.yml".to_string()),
+            ComposeFileRef::new("auth", "/absolute/auth/database.yml".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_render_lines() {
+        let resolved = resolve_files(test_files(), Path::new("/work"));
+        assert_eq!(render_lines(&resolved), "/work/venue/auth/base.yml\n/absolute/auth/database.yml\n");
+    }
+
+    #[test]
+    fn test_render_json() {
+        let files = vec![ComposeFileRef::new("auth", "/work/venue/auth/base.yml".to_string())];
+        assert_eq!(
+            render_json(&files),
+            "[\n  {\n    \"attendee\": \"auth\",\n    \"path\": \"/work/venue/auth/base.yml\"\n  }\n]"
+        );
+    }
+
+    static HOSTILE_VALUES: [&str; 14] = [
+        "foo --rm -v /:/host",
+        "$(rm -rf /)",
+        "`rm -rf /`",
+        "base.yml; rm -rf /",
+        "base.yml && echo pwned",
+        "base.yml | tee /etc/passwd",
+        "it's.yml",
+        "\"quoted\".yml",
+        "line\nbreak.yml",
+        "-rf",
+        "with space.yml",
+        "${HOME}/base.yml",
+        "*.yml",
+        "back\\slash.yml",
+    ];
+
+    #[test]
+    fn test_compose_action_suffixes() {
+        assert_eq!(ComposeAction::Up.to_string(), " up");
+        assert_eq!(ComposeAction::UpDetached.to_string(), " up -d");
+        assert_eq!(ComposeAction::UpDetachedWait.to_string(), " up -d --wait");
+        assert_eq!(ComposeAction::Down.to_string(), " down");
+        assert_eq!(ComposeAction::Build { pull: false, no_cache: false }.to_string(), " build");
+        assert_eq!(ComposeAction::Build { pull: true, no_cache: false }.to_string(), " build --pull");
+        assert_eq!(ComposeAction::Build { pull: false, no_cache: true }.to_string(), " build --no-cache");
+        assert_eq!(ComposeAction::Build { pull: true, no_cache: true }.to_string(), " build --pull --no-cache");
+        assert_eq!(ComposeAction::Ps.to_string(), " ps");
+        assert_eq!(ComposeAction::PsJson.to_string(), " ps --format json");
+        assert_eq!(ComposeAction::ConfigQuiet.to_string(), " config -q");
+        assert_eq!(ComposeAction::logs(false, None, &[]).unwrap().to_string(), " logs");
+        assert_eq!(ComposeAction::logs(true, None, &[]).unwrap().to_string(), " logs -f --tail=100");
+        assert_eq!(ComposeAction::logs(true, Some(20), &[]).unwrap().to_string(), " logs -f --tail=20");
+        assert_eq!(ComposeAction::logs(false, Some(5), &[]).unwrap().to_string(), " logs --tail=5");
+        assert_eq!(
+            ComposeAction::logs(true, None, &["auth_db".to_string(), "auth".to_string()]).unwrap().to_string(),
+            " logs -f --tail=100 auth_db auth"
+        );
+        assert_eq!(ComposeAction::logs(false, None, &["-t".to_string()]), Err("-t is not a valid service name".to_string()));
+        assert_eq!(
+            ComposeAction::UpAbortOnExit(ServiceName::new("test_runner").unwrap()).to_string(),
+            " up --abort-on-container-exit --exit-code-from test_runner"
+        );
+    }
+
+    #[test]
+    fn test_service_name() {
+        assert!(ServiceName::new("auth_db").is_ok());
+        assert!(ServiceName::new("auth-db.1").is_ok());
+        assert!(ServiceName::new("").is_err());
+        assert!(ServiceName::new("-d").is_err());
+        for value in HOSTILE_VALUES {
+            assert_eq!(ServiceName::new(value).unwrap_err(), format!("{} is not a valid service name", value));
+        }
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("./venue/auth/base.yml"), "./venue/auth/base.yml");
+        assert_eq!(shell_quote("with space.yml"), "'with space.yml'");
+        assert_eq!(shell_quote("it's.yml"), "'it'\\''s.yml'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn test_compose_args_keep_hostile_paths_as_single_arguments() {
+        for value in HOSTILE_VALUES {
+            let files = vec![ComposeFileRef::new("auth", value.to_string())];
+            let compose = compose_args("docker-compose", &files).with_command(&ComposeAction::Up.to_string());
+            let expected = match value.starts_with('-') {
+                true => format!("./{}", value),
+                false => value.to_string()
+            };
+            assert_eq!(compose.program, "docker-compose", "{}", value);
+            assert_eq!(compose.arg_refs(), vec!["-f", expected.as_str(), "up"], "{}", value);
+        }
+    }
+}
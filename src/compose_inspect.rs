@@ -0,0 +1,507 @@
+//! Inspects the docker-compose files of the attendees without a docker daemon for ```wedp check --no-daemon```.
+//! This is a static check of what wedp can see in the files: the services are defined, every service has an
+//! image or a build context that exists, the env files exist, the ports are written correctly, and the host
+//! paths of bind mounts exist. It is not a substitute for ```docker-compose config```, which also merges the
+//! files, interpolates variables, and checks the full compose schema. Values that use ```${VAR}``` are skipped
+//! as they are only known once compose interpolates them. Relative paths are resolved from the directory of
+//! the file they are in.
+use serde_yaml::Value;
+use std::path::Path;
+
+use crate::compose_file::ComposeFileRef;
+use crate::file_handler::CoreFileHandle;
+
+
+/// The protocols a port can be published with.
+pub const PORT_PROTOCOLS: [&str; 3] = ["tcp", "udp", "sctp"];
+
+
+/// A docker-compose file that has been read and parsed.
+///
+/// # Fields
+/// * `path` - The path to the file
+/// * `compose` - The parsed contents of the file
+#[derive(Debug)]
+pub struct ComposeDocument {
+    pub path: String,
+    pub compose: Value,
+}
+
+impl ComposeDocument {
+
+    /// Parses the contents of a docker-compose file.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the file
+    /// * `contents` - The contents of the file
+    ///
+    /// # Returns
+    /// * `Result<ComposeDocument, String>` - The document or an error if the file is not valid YAML
+    pub fn parse(path: &str, contents: &str) -> Result<ComposeDocument, String> {
+        let compose = serde_yaml::from_str(contents).map_err(|error| format!("{}: could not parse: {}", path, error))?;
+        Ok(ComposeDocument { path: path.to_string(), compose })
+    }
+
+    /// Gets the services defined in the file in the order they are written.
+    ///
+    /// # Returns
+    /// * `Vec<(String, &Value)>` - The name and definition of each service
+    pub fn services(&self) -> Vec<(String, &Value)> {
+        match self.compose.get("services").and_then(|services| services.as_mapping()) {
+            Some(services) => services.iter()
+                                      .map(|(name, service)| (scalar(name).unwrap_or_default(), service))
+                                      .collect(),
+            None => Vec::new()
+        }
+    }
+
+    /// Resolves a path in the file from the directory of the file.
+    ///
+    /// # Arguments
+    /// * `path` - The path as written in the file
+    ///
+    /// # Returns
+    /// * `std::path::PathBuf` - The path to check on disk
+    fn resolve(&self, path: &str) -> std::path::PathBuf {
+        Path::new(&self.path).parent().unwrap_or(Path::new("")).join(path)
+    }
+
+    /// Formats a problem with a service of the file.
+    fn problem(&self, service: &str, message: String) -> String {
+        format!("{}: service {}: {}", self.path, service, message)
+    }
+}
+
+
+/// Reads and checks the docker-compose files.
+///
+/// # Arguments
+/// * `files` - The docker-compose files in the order they are passed to docker-compose
+/// * `file_handle` - The file handle used to read the files
+///
+/// # Returns
+/// * `Vec<String>` - Every problem found, empty if the files passed
+pub fn inspect_compose_files(files: &[ComposeFileRef], file_handle: &dyn CoreFileHandle) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut documents = Vec::new();
+    for file in files {
+        let parsed = file_handle.read_to_string(Path::new(&file.path))
+            .map_err(|error| format!("{}: could not read: {}", file.path, error))
+            .and_then(|contents| ComposeDocument::parse(&file.path, &contents));
+        match parsed {
+            Ok(document) => documents.push(document),
+            Err(problem) => problems.push(problem)
+        }
+    }
+    for document in &documents {
+        problems.extend(check_services(document));
+        problems.extend(check_build_contexts(document));
+        problems.extend(check_env_files(document));
+        problems.extend(check_ports(document));
+        problems.extend(check_bind_mounts(document));
+    }
+    problems.extend(check_images(&documents));
+    problems
+}
+
+
+/// Checks the file defines services and every service is a mapping.
+///
+/// # Arguments
+/// * `document` - The docker-compose file
+///
+/// # Returns
+/// * `Vec<String>` - The problems with the services
+pub fn check_services(document: &ComposeDocument) -> Vec<String> {
+    let services = match document.compose.get("services") {
+        Some(Value::Mapping(services)) if !services.is_empty() => services,
+        Some(Value::Mapping(_)) | Some(Value::Null) | None => return vec![format!("{}: defines no services", document.path)],
+        Some(_) => return vec![format!("{}: services must be a mapping of service names", document.path)]
+    };
+    services.iter()
+            .filter(|(_, service)| !service.is_mapping())
+            .map(|(name, _)| document.problem(&scalar(name).unwrap_or_default(), "must be a mapping".to_string()))
+            .collect()
+}
+
+
+/// Checks every service has an ```image``` or a ```build``` in at least one of the files, as an override
+/// file may only add to a service defined in an earlier file. Services that use ```extends``` are skipped.
+///
+/// # Arguments
+/// * `documents` - The docker-compose files in the order they are passed to docker-compose
+///
+/// # Returns
+/// * `Vec<String>` - A problem for every service that has neither
+pub fn check_images(documents: &[ComposeDocument]) -> Vec<String> {
+    let mut first_seen: Vec<(String, &ComposeDocument)> = Vec::new();
+    let mut resolvable: Vec<String> = Vec::new();
+    for document in documents {
+        for (name, service) in document.services() {
+            if !first_seen.iter().any(|(seen, _)| *seen == name) {
+                first_seen.push((name.clone(), document));
+            }
+            let image = service.get("image").and_then(scalar).is_some_and(|image| !image.is_empty());
+            if image || service.get("build").is_some() || service.get("extends").is_some() {
+                resolvable.push(name);
+            }
+        }
+    }
+    first_seen.into_iter()
+              .filter(|(name, _)| !resolvable.contains(name))
+              .map(|(name, document)| document.problem(&name, "has no image or build".to_string()))
+              .collect()
+}
+
+
+/// Checks the ```build``` context of every service exists. Contexts that are URLs of git repositories are
+/// fetched by docker and skipped.
+///
+/// # Arguments
+/// * `document` - The docker-compose file
+///
+/// # Returns
+/// * `Vec<String>` - A problem for every build context that does not exist
+pub fn check_build_contexts(document: &ComposeDocument) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (name, service) in document.services() {
+        let context = match service.get("build") {
+            Some(Value::Mapping(build)) => build.get("context").and_then(scalar).unwrap_or_else(|| ".".to_string()),
+            Some(build) => match scalar(build) {
+                Some(context) => context,
+                None => {
+                    problems.push(document.problem(&name, "build must be a path or a mapping".to_string()));
+                    continue
+                }
+            },
+            None => continue
+        };
+        let remote = context.contains("://") || context.starts_with("git@");
+        if remote || is_interpolated(&context) {
+            continue
+        }
+        if !document.resolve(&context).is_dir() {
+            problems.push(document.problem(&name, format!("build context {} does not exist", context)));
+        }
+    }
+    problems
+}
+
+
+/// Checks the ```env_file``` of every service exists. Entries marked ```required: false``` are skipped.
+///
+/// # Arguments
+/// * `document` - The docker-compose file
+///
+/// # Returns
+/// * `Vec<String>` - A problem for every env file that does not exist
+pub fn check_env_files(document: &ComposeDocument) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (name, service) in document.services() {
+        let entries = match service.get("env_file") {
+            Some(Value::Sequence(entries)) => entries.iter().collect(),
+            Some(entry) => vec![entry],
+            None => continue
+        };
+        for entry in entries {
+            let (path, required) = match entry {
+                Value::Mapping(entry) => (
+                    entry.get("path").and_then(scalar),
+                    entry.get("required").and_then(Value::as_bool).unwrap_or(true)
+                ),
+                entry => (scalar(entry), true)
+            };
+            let path = match path {
+                Some(path) => path,
+                None => {
+                    problems.push(document.problem(&name, "env_file entries must be a path or have a path".to_string()));
+                    continue
+                }
+            };
+            if required && !is_interpolated(&path) && !document.resolve(&path).is_file() {
+                problems.push(document.problem(&name, format!("env_file {} does not exist", path)));
+            }
+        }
+    }
+    problems
+}
+
+
+/// Checks the ```ports``` of every service are written in the short ```[IP:][HOST:]CONTAINER[/PROTOCOL]```
+/// syntax or the long syntax with a ```target```.
+///
+/// # Arguments
+/// * `document` - The docker-compose file
+///
+/// # Returns
+/// * `Vec<String>` - A problem for every port that is not valid
+pub fn check_ports(document: &ComposeDocument) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (name, service) in document.services() {
+        let ports = match service.get("ports") {
+            Some(Value::Sequence(ports)) => ports,
+            Some(_) => {
+                problems.push(document.problem(&name, "ports must be a list".to_string()));
+                continue
+            },
+            None => continue
+        };
+        for port in ports {
+            let checked = match port {
+                Value::Mapping(port) => check_long_port(port),
+                port => match scalar(port) {
+                    Some(port) => check_short_port(&port),
+                    None => Err("ports must be strings, numbers, or mappings".to_string())
+                }
+            };
+            if let Err(message) = checked {
+                problems.push(document.problem(&name, message));
+            }
+        }
+    }
+    problems
+}
+
+
+/// Checks the host path of every bind mount in ```volumes``` exists. Short syntax sources starting with
+/// ```/``` or ```.``` are bind mounts, anything else is a named volume. Paths under ```~``` are skipped.
+///
+/// # Arguments
+/// * `document` - The docker-compose file
+///
+/// # Returns
+/// * `Vec<String>` - A problem for every bind mount whose host path does not exist
+pub fn check_bind_mounts(document: &ComposeDocument) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (name, service) in document.services() {
+        let volumes = match service.get("volumes").and_then(Value::as_sequence) {
+            Some(volumes) => volumes,
+            None => continue
+        };
+        for volume in volumes {
+            let source = match volume {
+                Value::Mapping(volume) if volume.get("type").and_then(scalar).as_deref() == Some("bind") => {
+                    volume.get("source").and_then(scalar)
+                },
+                Value::Mapping(_) => None,
+                volume => scalar(volume).and_then(|volume| {
+                    let (source, _) = volume.split_once(':')?;
+                    (source.starts_with('/') || source.starts_with('.')).then(|| source.to_string())
+                })
+            };
+            let source = match source {
+                Some(source) if !source.starts_with('~') && !is_interpolated(&source) => source,
+                _ => continue
+            };
+            if !document.resolve(&source).exists() {
+                problems.push(document.problem(&name, format!("bind mount source {} does not exist", source)));
+            }
+        }
+    }
+    problems
+}
+
+
+/// Checks a port in the short syntax such as ```8080```, ```127.0.0.1:8080:80```, or ```9000-9001:9000-9001/udp```.
+///
+/// # Arguments
+/// * `port` - The port as written in the file
+///
+/// # Returns
+/// * `Result<(), String>` - An error describing why the port is not valid
+fn check_short_port(port: &str) -> Result<(), String> {
+    if is_interpolated(port) {
+        return Ok(())
+    }
+    let invalid = |reason: &str| Err(format!("port {} {}", port, reason));
+    let (mapping, protocol) = match port.split_once('/') {
+        Some((mapping, protocol)) => (mapping, Some(protocol)),
+        None => (port, None)
+    };
+    if let Some(protocol) = protocol {
+        if !PORT_PROTOCOLS.contains(&protocol) {
+            return invalid(&format!("has protocol {}, expected one of {}", protocol, PORT_PROTOCOLS.join(", ")))
+        }
+    }
+    // an IPv6 host address is in brackets, everything after it is the host and container port
+    let ports = match mapping.strip_prefix('[') {
+        Some(rest) => match rest.split_once("]:") {
+            Some((_, ports)) => ports,
+            None => return invalid("has an unclosed IPv6 address")
+        },
+        None => mapping
+    };
+    let parts: Vec<&str> = ports.split(':').collect();
+    let (host, container) = match (mapping.starts_with('['), parts.as_slice()) {
+        (false, [container]) => (None, *container),
+        (true, [host, container]) | (false, [host, container]) => (Some(*host), *container),
+        (false, [_, host, container]) => (Some(*host), *container),
+        _ => return invalid("has too many parts")
+    };
+    if !is_port_range(container) {
+        return invalid(&format!("has container port {}, expected a port or range from 1 to 65535", container))
+    }
+    match host {
+        Some(host) if !host.is_empty() && !is_port_range(host) => {
+            invalid(&format!("has host port {}, expected a port or range from 1 to 65535", host))
+        },
+        _ => Ok(())
+    }
+}
+
+
+/// Checks a port in the long syntax has a valid ```target``` and ```published``` port.
+///
+/// # Arguments
+/// * `port` - The port mapping
+///
+/// # Returns
+/// * `Result<(), String>` - An error describing why the port is not valid
+fn check_long_port(port: &serde_yaml::Mapping) -> Result<(), String> {
+    let target = match port.get("target").and_then(scalar) {
+        Some(target) => target,
+        None => return Err("port mappings must have a target".to_string())
+    };
+    if !is_interpolated(&target) && !is_port_range(&target) {
+        return Err(format!("port target {} is not a port from 1 to 65535", target))
+    }
+    match port.get("published").and_then(scalar) {
+        Some(published) if !is_interpolated(&published) && !is_port_range(&published) => {
+            Err(format!("port published {} is not a port or range from 1 to 65535", published))
+        },
+        _ => Ok(())
+    }
+}
+
+
+/// Checks a value is a port or a range of ports such as ```9000-9005```.
+fn is_port_range(value: &str) -> bool {
+    let is_port = |port: &str| port.parse::<u16>().is_ok_and(|port| port > 0);
+    match value.split_once('-') {
+        Some((start, end)) => is_port(start) && is_port(end) && start.parse::<u16>().ok() <= end.parse::<u16>().ok(),
+        None => is_port(value)
+    }
+}
+
+
+/// Checks whether a value is only known once compose interpolates the variables in it.
+fn is_interpolated(value: &str) -> bool {
+    value.contains('$')
+}
+
+
+/// Reads a string or number from the YAML as a string.
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        _ => None
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::file_handler::FileHandle;
+
+    const FIXTURES: &str = "tests/compose_inspect";
+
+    fn document(name: &str) -> ComposeDocument {
+        let path = format!("{}/{}", FIXTURES, name);
+        ComposeDocument::parse(&path, &std::fs::read_to_string(&path).unwrap()).unwrap()
+    }
+
+    fn problem(file: &str, service: &str, message: &str) -> String {
+        format!("{}/{}: service {}: {}", FIXTURES, file, service, message)
+    }
+
+    #[test]
+    fn test_valid_file_has_no_problems() {
+        let files = vec![ComposeFileRef::new("auth", format!("{}/valid.yml", FIXTURES))];
+        assert_eq!(inspect_compose_files(&files, &FileHandle{}), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_check_services() {
+        assert_eq!(check_services(&document("valid.yml")), Vec::<String>::new());
+        assert_eq!(check_services(&document("no_services.yml")), vec![format!("{}/no_services.yml: defines no services", FIXTURES)]);
+        assert_eq!(check_services(&document("invalid.yml")), vec![problem("invalid.yml", "broken", "must be a mapping")]);
+    }
+
+    #[test]
+    fn test_check_images() {
+        assert_eq!(
+            check_images(&[document("invalid.yml")]),
+            vec![problem("invalid.yml", "no_image", "has no image or build"), problem("invalid.yml", "broken", "has no image or build")]
+        );
+        // an override file only adding to a service is fine once an earlier file gives it an image
+        assert_eq!(check_images(&[document("valid.yml"), document("override.yml")]), Vec::<String>::new());
+        assert_eq!(
+            check_images(&[document("override.yml")]),
+            vec![problem("override.yml", "web", "has no image or build")]
+        );
+    }
+
+    #[test]
+    fn test_check_build_contexts() {
+        assert_eq!(check_build_contexts(&document("valid.yml")), Vec::<String>::new());
+        assert_eq!(check_build_contexts(&document("invalid.yml")), vec![
+            problem("invalid.yml", "missing_context", "build context ./missing does not exist"),
+            problem("invalid.yml", "missing_long_context", "build context ../nowhere does not exist"),
+        ]);
+    }
+
+    #[test]
+    fn test_check_env_files() {
+        assert_eq!(check_env_files(&document("valid.yml")), Vec::<String>::new());
+        assert_eq!(check_env_files(&document("invalid.yml")), vec![
+            problem("invalid.yml", "missing_env", "env_file missing.env does not exist"),
+            problem("invalid.yml", "missing_env", "env_file ./also_missing.env does not exist"),
+        ]);
+    }
+
+    #[test]
+    fn test_check_ports() {
+        assert_eq!(check_ports(&document("valid.yml")), Vec::<String>::new());
+        assert_eq!(check_ports(&document("invalid.yml")), vec![
+            problem("invalid.yml", "bad_ports", "port 80:http has container port http, expected a port or range from 1 to 65535"),
+            problem("invalid.yml", "bad_ports", "port 70000:80 has host port 70000, expected a port or range from 1 to 65535"),
+            problem("invalid.yml", "bad_ports", "port 8080:80/tpc has protocol tpc, expected one of tcp, udp, sctp"),
+            problem("invalid.yml", "bad_ports", "port 1:2:3:4 has too many parts"),
+            problem("invalid.yml", "bad_ports", "port 9005-9000:80 has host port 9005-9000, expected a port or range from 1 to 65535"),
+            problem("invalid.yml", "bad_ports", "port mappings must have a target"),
+        ]);
+    }
+
+    #[test]
+    fn test_check_short_port() {
+        for port in ["80", "8080:80", "127.0.0.1:8080:80", "127.0.0.1::80", "[::1]:6001:6001", "9000-9001:9000-9001/udp", "${PORT}:80"] {
+            assert_eq!(check_short_port(port), Ok(()), "{}", port);
+        }
+        assert!(check_short_port("0").is_err());
+        assert!(check_short_port("[::1:6001:6001").is_err());
+    }
+
+    #[test]
+    fn test_check_bind_mounts() {
+        assert_eq!(check_bind_mounts(&document("valid.yml")), Vec::<String>::new());
+        assert_eq!(check_bind_mounts(&document("invalid.yml")), vec![
+            problem("invalid.yml", "missing_bind", "bind mount source ./missing_data does not exist"),
+            problem("invalid.yml", "missing_bind", "bind mount source /wedp/does/not/exist does not exist"),
+        ]);
+    }
+
+    #[test]
+    fn test_inspect_reports_unreadable_and_unparsable_files() {
+        let files = vec![
+            ComposeFileRef::new("auth", format!("{}/absent.yml", FIXTURES)),
+            ComposeFileRef::new("auth", format!("{}/not_yaml.yml", FIXTURES)),
+        ];
+        let problems = inspect_compose_files(&files, &FileHandle{});
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].starts_with(&format!("{}/absent.yml: could not read: ", FIXTURES)), "{}", problems[0]);
+        assert!(problems[1].starts_with(&format!("{}/not_yaml.yml: could not parse: ", FIXTURES)), "{}", problems[1]);
+    }
+}
@@ -0,0 +1,225 @@
+//! Shows the state of the containers of the dependencies. The containers are listed with
+//! ```docker-compose ps --format json``` and each service is matched to the dependency whose compose file
+//! defines it, so the table can say which dependency a container belongs to.
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::commands::command_runner::{check_status, CoreRunner};
+use crate::compose_file::{compose_args, read_services, ComposeAction, ComposeFileRef};
+use crate::file_handler::CoreFileHandle;
+
+
+/// A port published by a container in the output of ```docker-compose ps --format json```.
+#[derive(Debug, Deserialize)]
+struct Publisher {
+    #[serde(rename = "URL", default)]
+    url: String,
+    #[serde(rename = "TargetPort", default)]
+    target_port: u16,
+    #[serde(rename = "PublishedPort", default)]
+    published_port: u16,
+    #[serde(rename = "Protocol", default)]
+    protocol: String,
+}
+
+/// A container in the output of ```docker-compose ps --format json```.
+#[derive(Debug, Deserialize)]
+struct PsEntry {
+    #[serde(rename = "Service")]
+    service: String,
+    #[serde(rename = "State", default)]
+    state: String,
+    #[serde(rename = "Publishers", default)]
+    publishers: Option<Vec<Publisher>>,
+}
+
+
+/// The state of the container of a service.
+///
+/// # Fields
+/// * `service` - The name of the service
+/// * `attendee` - The dependency whose compose file defines the service, ```-``` if no compose file does
+/// * `state` - The state of the container such as ```running``` or ```exited```
+/// * `ports` - The ports of the container, published ports as ```HOST:PORT->PORT/PROTOCOL```
+#[derive(Debug, PartialEq)]
+pub struct ContainerStatus {
+    pub service: String,
+    pub attendee: String,
+    pub state: String,
+    pub ports: Vec<String>,
+}
+
+
+/// Renders the ports of a container. The IPv6 duplicates of IPv4 ports are dropped.
+///
+/// # Arguments
+/// * `publishers` - The ports of the container
+///
+/// # Returns
+/// * `Vec<String>` - The rendered ports
+fn render_ports(publishers: &[Publisher]) -> Vec<String> {
+    let mut ports = Vec::new();
+    for publisher in publishers.iter().filter(|publisher| publisher.url != "::") {
+        let port = match publisher.published_port {
+            0 => format!("{}/{}", publisher.target_port, publisher.protocol),
+            published_port => format!("{}:{}->{}/{}", publisher.url, published_port, publisher.target_port, publisher.protocol)
+        };
+        if !ports.contains(&port) {
+            ports.push(port);
+        }
+    }
+    ports
+}
+
+
+/// Parses the output of ```docker-compose ps --format json``` and matches each container to its dependency.
+/// Compose writes either a single JSON array or one JSON object per line depending on its version.
+///
+/// # Arguments
+/// * `output` - The stdout of ```docker-compose ps --format json```
+/// * `services` - The name of each service and the attendee defining it from ```read_services```
+///
+/// # Returns
+/// * `Result<Vec<ContainerStatus>, String>` - The containers in the order their services are defined or an error if the output is not JSON
+pub fn parse_ps_output(output: &str, services: &[(String, String)]) -> Result<Vec<ContainerStatus>, String> {
+    let output = output.trim();
+    let entries: Vec<PsEntry> = match output.starts_with('[') {
+        true => serde_json::from_str(output).map_err(|error| error.to_string()),
+        false => output.lines()
+                       .filter(|line| !line.trim().is_empty())
+                       .map(|line| serde_json::from_str(line).map_err(|error| error.to_string()))
+                       .collect()
+    }.map_err(|error| format!("Could not read the output of docker-compose ps, wedp ps needs compose v2: {}", error))?;
+
+    let position = |service: &str| services.iter().position(|(name, _)| name == service).unwrap_or(services.len());
+    let mut statuses: Vec<ContainerStatus> = entries.into_iter().map(|entry| {
+        let attendee = services.iter()
+                               .find(|(name, _)| name == &entry.service)
+                               .map(|(_, attendee)| attendee.clone())
+                               .unwrap_or_else(|| "-".to_string());
+        ContainerStatus {
+            ports: render_ports(entry.publishers.as_deref().unwrap_or_default()),
+            service: entry.service,
+            attendee,
+            state: entry.state,
+        }
+    }).collect();
+    statuses.sort_by(|a, b| position(&a.service).cmp(&position(&b.service)).then(a.service.cmp(&b.service)));
+    Ok(statuses)
+}
+
+
+/// Lists the containers of the compose files with the dependency each one belongs to.
+///
+/// # Arguments
+/// * `compose` - The command docker compose is run with
+/// * `files` - The compose files the containers were started from
+/// * `command_runner` - The runner used to run ```docker-compose ps```
+/// * `file_handle` - The file handle used to read the services from the compose files
+///
+/// # Returns
+/// * `Result<Vec<ContainerStatus>, String>` - The state of each container or an error message
+pub fn compose_ps(compose: &str, files: &[ComposeFileRef], command_runner: &dyn CoreRunner, file_handle: &dyn CoreFileHandle) -> Result<Vec<ContainerStatus>, String> {
+    let services = read_services(files, file_handle)?;
+    let command = compose_args(compose, files).with_command(&ComposeAction::PsJson.to_string());
+    let output = command_runner.run_in_dir(Path::new("."), &command.program, &command.arg_refs())
+                               .and_then(check_status)
+                               .map_err(|error| error.to_string())?;
+    parse_ps_output(&String::from_utf8_lossy(&output.stdout), &services)
+}
+
+
+/// Renders the state of the containers as a table.
+///
+/// # Arguments
+/// * `statuses` - The state of each container
+///
+/// # Returns
+/// * `String` - The rendered table
+pub fn render_ps_table(statuses: &[ContainerStatus]) -> String {
+    let mut table = format!("{:<20} {:<20} {:<10} {}\n", "SERVICE", "DEPENDENCY", "STATE", "PORTS");
+    for status in statuses {
+        let row = format!("{:<20} {:<20} {:<10} {}", status.service, status.attendee, status.state, status.ports.join(", "));
+        table.push_str(row.trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::commands::command_runner::MockCoreRunner;
+    use crate::file_handler::MockCoreFileHandle;
+    use mockall::predicate::eq;
+    use std::os::unix::process::ExitStatusExt;
+    use std::path::PathBuf;
+    use std::process::{ExitStatus, Output};
+
+    static PS_LINES: &str = concat!(
+        r#"{"Name":"venue-auth_db-1","Service":"auth_db","State":"running","Publishers":[{"URL":"0.0.0.0","TargetPort":5432,"PublishedPort":5432,"Protocol":"tcp"},{"URL":"::","TargetPort":5432,"PublishedPort":5432,"Protocol":"tcp"}]}"#, "\n",
+        r#"{"Name":"venue-auth-1","Service":"auth","State":"exited","Publishers":[{"URL":"","TargetPort":8080,"PublishedPort":0,"Protocol":"tcp"}]}"#, "\n",
+        r#"{"Name":"venue-mailhog-1","Service":"mailhog","State":"running","Publishers":null}"#, "\n",
+    );
+
+    fn services() -> Vec<(String, String)> {
+        vec![
+            ("auth".to_string(), "auth".to_string()),
+            ("auth_db".to_string(), "auth".to_string()),
+            ("api".to_string(), "local".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_parse_ps_output() {
+        assert_eq!(
+            parse_ps_output(PS_LINES, &services()),
+            Ok(vec![
+                ContainerStatus { service: "auth".to_string(), attendee: "auth".to_string(), state: "exited".to_string(), ports: vec!["8080/tcp".to_string()] },
+                ContainerStatus { service: "auth_db".to_string(), attendee: "auth".to_string(), state: "running".to_string(), ports: vec!["0.0.0.0:5432->5432/tcp".to_string()] },
+                ContainerStatus { service: "mailhog".to_string(), attendee: "-".to_string(), state: "running".to_string(), ports: Vec::new() },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_ps_output_array() {
+        let output = r#"[{"Service":"api","State":"running"},{"Service":"auth","State":"running"}]"#;
+        let statuses = parse_ps_output(output, &services()).unwrap();
+        assert_eq!(statuses.iter().map(|status| status.attendee.as_str()).collect::<Vec<_>>(), vec!["auth", "local"]);
+        assert_eq!(parse_ps_output("", &services()), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn test_parse_ps_output_compose_v1() {
+        let output = "Name   Command   State   Ports\n------------------------------\n";
+        assert!(parse_ps_output(output, &services()).unwrap_err().contains("wedp ps needs compose v2"));
+    }
+
+    #[test]
+    fn test_compose_ps() {
+        let files = vec![ComposeFileRef::new("auth", "venue/auth/base.yml".to_string())];
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_read_to_string()
+            .with(eq(PathBuf::from("venue/auth/base.yml")))
+            .returning(|_| Ok("services:\n  auth:\n    image: auth\n  auth_db:\n    image: postgres\n".to_string()));
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .withf(|dir, program, args| {
+                dir == Path::new(".") && program == "docker-compose" && args == ["-f", "venue/auth/base.yml", "ps", "--format", "json"]
+            })
+            .times(1)
+            .returning(|_, _, _| Ok(Output { status: ExitStatus::from_raw(0), stdout: PS_LINES.as_bytes().to_vec(), stderr: Vec::new() }));
+
+        let statuses = compose_ps("docker-compose", &files, &mock_runner, &mock_handle).unwrap();
+        assert_eq!(
+            render_ps_table(&statuses),
+            "SERVICE              DEPENDENCY           STATE      PORTS\n\
+             auth                 auth                 exited     8080/tcp\n\
+             auth_db              auth                 running    0.0.0.0:5432->5432/tcp\n\
+             mailhog              -                    running\n"
+        );
+    }
+}
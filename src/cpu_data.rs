@@ -1,5 +1,12 @@
-//! Gets the data about the CPU when running the program. 
+//! Gets the data about the CPU when running the program. The CPU the Dockerfiles are picked for can be 
+//! overridden with ```--arch``` or the ```WEDP_ARCH``` environment variable, for example to build the 
+//! ```x86_64``` images on an ```aarch64``` host that runs them under emulation.
 use std::env::consts::ARCH;
+use std::fmt;
+
+
+/// The environment variable that overrides the CPU type when ```--arch``` is not passed.
+pub const ARCH_ENV: &str = "WEDP_ARCH";
 
 
 /// This enum represents the different CPU types that are supported by the `wedp` tool.
@@ -17,6 +24,7 @@ use std::env::consts::ARCH;
 /// * `Riscv64` - The riscv64 CPU type
 /// * `S390x` - The s390x CPU type
 /// * `Sparc64` - The sparc64 CPU type
+#[derive(Debug, PartialEq)]
 pub enum CpuType {
     X86,
     X86_64,
@@ -37,43 +45,115 @@ impl CpuType {
     /// Get the current CPU type
     ///
     /// # Returns
-    /// * The current CPU type
-    pub fn get() -> Self {
-        match ARCH {
-            "x86" => CpuType::X86,
-            "x86_64" => CpuType::X86_64,
-            "arm" => CpuType::Arm,
-            "aarch64" => CpuType::Aarch64,
-            "m68k" => CpuType::M68k,
-            "mips" => CpuType::Mips,
-            "mips64" => CpuType::Mips64,
-            "powerpc" => CpuType::Powerpc,
-            "powerpc64" => CpuType::Powerpc64,
-            "riscv64" => CpuType::Riscv64,
-            "s390x" => CpuType::S390x,
-            "sparc64" => CpuType::Sparc64,
-            _ => panic!("Unsupported CPU type: {}", ARCH)
-        }
+    /// * `Result<CpuType, String>` - The current CPU type or an error if wedp does not support the CPU type
+    pub fn get() -> Result<Self, String> {
+        CpuType::from_arch(ARCH)
     }
 
-    /// Convert the CPU type to a string.
+    /// Get the CPU type of an architecture name.
+    ///
+    /// # Arguments
+    /// * `arch` - The name of the architecture as in ```std::env::consts::ARCH```
     ///
     /// # Returns
-    /// * The string representation of the CPU type
-    pub fn to_string(self) -> String {
-        match self {
-            CpuType::X86 => "x86".to_string(),
-            CpuType::X86_64 => "x86_64".to_string(),
-            CpuType::Arm => "arm".to_string(),
-            CpuType::Aarch64 => "aarch64".to_string(),
-            CpuType::M68k => "m68k".to_string(),
-            CpuType::Mips => "mips".to_string(),
-            CpuType::Mips64 => "mips64".to_string(),
-            CpuType::Powerpc => "powerpc".to_string(),
-            CpuType::Powerpc64 => "powerpc64".to_string(),
-            CpuType::Riscv64 => "riscv64".to_string(),
-            CpuType::S390x => "s390x".to_string(),
-            CpuType::Sparc64 => "sparc64".to_string(),
+    /// * `Result<CpuType, String>` - The CPU type or an error if wedp does not support the architecture
+    pub fn from_arch(arch: &str) -> Result<Self, String> {
+        match arch {
+            "x86" => Ok(CpuType::X86),
+            "x86_64" => Ok(CpuType::X86_64),
+            "arm" => Ok(CpuType::Arm),
+            "aarch64" => Ok(CpuType::Aarch64),
+            "m68k" => Ok(CpuType::M68k),
+            "mips" => Ok(CpuType::Mips),
+            "mips64" => Ok(CpuType::Mips64),
+            "powerpc" => Ok(CpuType::Powerpc),
+            "powerpc64" => Ok(CpuType::Powerpc64),
+            "riscv64" => Ok(CpuType::Riscv64),
+            "s390x" => Ok(CpuType::S390x),
+            "sparc64" => Ok(CpuType::Sparc64),
+            _ => Err(format!(
+                "Unsupported CPU type: {}, the supported CPU types are x86, x86_64, arm, aarch64, m68k, mips, \
+                 mips64, powerpc, powerpc64, riscv64, s390x and sparc64",
+                arch
+            ))
         }
     }
-}
\ No newline at end of file
+}
+
+/// Picks the CPU type the Dockerfiles are picked for, ```--arch``` wins over ```WEDP_ARCH```. An empty 
+/// ```WEDP_ARCH``` is treated as not set.
+///
+/// # Arguments
+/// * `flag` - The value of ```--arch```, already checked by the command line
+/// * `env_value` - The value of ```WEDP_ARCH```
+///
+/// # Returns
+/// * `Result<Option<String>, String>` - The CPU type, ```None``` for the CPU type of the host, or an error if 
+///   ```WEDP_ARCH``` is not a CPU type wedp supports
+pub fn arch_override(flag: Option<&str>, env_value: Option<String>) -> Result<Option<String>, String> {
+    if let Some(arch) = flag {
+        return Ok(Some(arch.to_string()))
+    }
+    match env_value.filter(|arch| !arch.is_empty()) {
+        Some(arch) => CpuType::from_arch(&arch).map(|_| Some(arch)).map_err(|error| format!("{} in {}", error, ARCH_ENV)),
+        None => Ok(None)
+    }
+}
+
+impl fmt::Display for CpuType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            CpuType::X86 => "x86",
+            CpuType::X86_64 => "x86_64",
+            CpuType::Arm => "arm",
+            CpuType::Aarch64 => "aarch64",
+            CpuType::M68k => "m68k",
+            CpuType::Mips => "mips",
+            CpuType::Mips64 => "mips64",
+            CpuType::Powerpc => "powerpc",
+            CpuType::Powerpc64 => "powerpc64",
+            CpuType::Riscv64 => "riscv64",
+            CpuType::S390x => "s390x",
+            CpuType::Sparc64 => "sparc64",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_from_arch() {
+        assert_eq!(CpuType::from_arch("aarch64").unwrap().to_string(), "aarch64");
+        assert_eq!(CpuType::from_arch(ARCH).unwrap().to_string(), ARCH);
+    }
+
+    #[test]
+    fn test_display_reuses_value() {
+        let cpu_type = CpuType::Aarch64;
+        assert_eq!(cpu_type.to_string(), "aarch64");
+        assert_eq!(format!("Dockerfile.{}", cpu_type), "Dockerfile.aarch64");
+        assert_eq!(cpu_type, CpuType::Aarch64);
+    }
+
+    #[test]
+    fn test_arch_override() {
+        assert_eq!(arch_override(Some("x86_64"), Some("aarch64".to_string())), Ok(Some("x86_64".to_string())));
+        assert_eq!(arch_override(None, Some("aarch64".to_string())), Ok(Some("aarch64".to_string())));
+        assert_eq!(arch_override(None, Some(String::new())), Ok(None));
+        assert_eq!(arch_override(None, None), Ok(None));
+        let error = arch_override(None, Some("amd64".to_string())).unwrap_err();
+        assert!(error.starts_with("Unsupported CPU type: amd64, the supported CPU types are x86, x86_64"), "{}", error);
+        assert!(error.ends_with("sparc64 in WEDP_ARCH"), "{}", error);
+    }
+
+    #[test]
+    fn test_from_arch_unsupported() {
+        let error = CpuType::from_arch("loongarch64").err().unwrap();
+        assert!(error.starts_with("Unsupported CPU type: loongarch64, the supported CPU types are x86, x86_64"));
+    }
+}
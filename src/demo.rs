@@ -0,0 +1,303 @@
+//! Generates a self-contained demo of wedp into a directory. The demo has two tiny dependency repos that are
+//! initialised as local git repos, each with a wedding invite and a compose file running the ```hello-world```
+//! image, and a seating plan that clones them through ```file://``` URLs so nothing has to be pushed anywhere.
+//! All of the files are written through the file handle and the git commands are run through the command
+//! runner so ```--dry-run``` prints what the demo would create.
+use std::path::{Path, PathBuf};
+
+use crate::commands::command_runner::{check_status, CoreRunner};
+use crate::compose_file::shell_quote;
+use crate::file_handler::CoreFileHandle;
+
+
+/// The names of the dependency repos created by the demo.
+pub const DEMO_DEPENDENCIES: [&str; 2] = ["greeter", "listener"];
+
+/// The file marking a directory as a generated demo so ```--cleanup``` never removes anything else.
+pub const DEMO_MARKER_FILE: &str = ".wedp-demo";
+
+/// The wedding invite of each demo dependency.
+const DEMO_WEDDING_INVITE: &str = "\
+build_root: \".\"
+build_files:
+  x86_64: build/Dockerfile.x86_64
+  aarch64: build/Dockerfile.aarch64
+runner_files:
+  - runner_files/base.yml
+";
+
+
+/// A demo of wedp generated into a directory.
+///
+/// # Fields
+/// * `directory` - The absolute path to the directory the demo is generated into
+pub struct Demo {
+    pub directory: PathBuf,
+}
+
+impl Demo {
+
+    /// Creates a new Demo struct.
+    ///
+    /// # Arguments
+    /// * `directory` - The absolute path to the directory the demo is generated into
+    ///
+    /// # Returns
+    /// A new Demo struct
+    pub fn new(directory: PathBuf) -> Self {
+        Demo { directory }
+    }
+
+    /// Writes the demo dependency repos and the seating plan and commits each repo with git.
+    ///
+    /// # Arguments
+    /// * `runner` - The command runner used to run the git commands
+    /// * `handle` - The file handle used to write the files
+    ///
+    /// # Returns
+    /// * `Result<(), String>` - An error if the directory is not empty or a file or git command failed
+    pub fn generate(&self, runner: &dyn CoreRunner, handle: &dyn CoreFileHandle) -> Result<(), String> {
+        let marker = self.directory.join(DEMO_MARKER_FILE);
+        let is_empty = self.directory.read_dir().map(|mut entries| entries.next().is_none()).unwrap_or(true);
+        if !is_empty && !marker.exists() {
+            return Err(format!("{} is not empty and is not a wedp demo", self.directory.to_string_lossy()))
+        }
+        write_file(handle, &marker, "Generated by wedp demo, remove with wedp demo --cleanup\n")?;
+
+        for name in DEMO_DEPENDENCIES {
+            let repo = self.repos_directory().join(name);
+            write_file(handle, &repo.join("wedding_invite.yml"), DEMO_WEDDING_INVITE)?;
+            write_file(handle, &repo.join("runner_files").join("base.yml"), &demo_compose_file(name))?;
+            for cpu_type in ["x86_64", "aarch64"] {
+                let dockerfile = repo.join("build").join(format!("Dockerfile.{}", cpu_type));
+                write_file(handle, &dockerfile, "FROM hello-world\n")?;
+            }
+            let commit_command = format!(
+                "cd {} && git init -q && git checkout -q -b main && git add -A && \
+                 git -c user.name=wedp -c user.email=demo@wedp.invalid commit -q -m 'Add the {} demo dependency'",
+                shell_quote(&repo.to_string_lossy()),
+                name
+            );
+            let output = runner.run(&commit_command).map_err(|error| error.to_string())?;
+            check_status(output).map_err(|error| format!("Failed to commit the {} demo repo: {}", name, error))?;
+        }
+        write_file(handle, &self.seating_plan_path(), &self.seating_plan())
+    }
+
+    /// Removes the demo directory if it was generated by ```wedp demo```.
+    ///
+    /// # Arguments
+    /// * `handle` - The file handle used to remove the directory
+    ///
+    /// # Returns
+    /// * `Result<(), String>` - An error if the directory is not a demo or could not be removed
+    pub fn cleanup(&self, handle: &dyn CoreFileHandle) -> Result<(), String> {
+        let directory = self.directory.to_string_lossy();
+        if handle.read_to_string(&self.directory.join(DEMO_MARKER_FILE)).is_err() {
+            return Err(format!("{} is not a wedp demo, not removing it", directory))
+        }
+        handle.remove_directory(&self.directory)
+              .map_err(|error| format!("Failed to remove {}: {}", directory, error))
+    }
+
+    /// Renders the commands to try out the demo.
+    ///
+    /// # Returns
+    /// * `String` - The commands ending in a new line
+    pub fn instructions(&self) -> String {
+        let directory = shell_quote(&self.directory.to_string_lossy());
+        format!(
+            "Created a wedp demo in {directory}, try the following commands:\n\n\
+             \x20   cd {directory}\n\
+             \x20   wedp setup\n\
+             \x20   wedp install\n\
+             \x20   wedp status\n\
+             \x20   wedp compose-files\n\
+             \x20   wedp run\n\
+             \x20   wedp teardown\n\n\
+             Remove the demo with:\n\n\
+             \x20   wedp demo --cleanup {directory}\n"
+        )
+    }
+
+    /// Renders the seating plan of the demo. The venue is absolute so the plan works from any directory.
+    ///
+    /// # Returns
+    /// * `String` - The seating plan yml
+    fn seating_plan(&self) -> String {
+        let mut seating_plan = "attendees:\n".to_string();
+        for name in DEMO_DEPENDENCIES {
+            let url = format!("file://{}", self.repos_directory().join(name).to_string_lossy());
+            seating_plan.push_str(&format!("  - name: {}\n    url: {}\n    branch: main\n", name, url));
+        }
+        seating_plan.push_str(&format!("venue: {}\n", self.directory.join("venue").to_string_lossy()));
+        seating_plan
+    }
+
+    /// Gets the path to the seating plan of the demo.
+    ///
+    /// # Returns
+    /// * `PathBuf` - The path to the ```wedding_planner.yml``` in the demo directory
+    pub fn seating_plan_path(&self) -> PathBuf {
+        self.directory.join("wedding_planner.yml")
+    }
+
+    /// Gets the directory holding the demo dependency repos.
+    ///
+    /// # Returns
+    /// * `PathBuf` - The path to the repos directory
+    fn repos_directory(&self) -> PathBuf {
+        self.directory.join("repos")
+    }
+}
+
+
+/// Renders the compose file of a demo dependency.
+///
+/// # Arguments
+/// * `name` - The name of the demo dependency
+///
+/// # Returns
+/// * `String` - The compose file yml
+fn demo_compose_file(name: &str) -> String {
+    format!("services:\n  {}:\n    image: hello-world\n", name)
+}
+
+
+/// Writes a file creating the directory it is in first.
+///
+/// # Arguments
+/// * `handle` - The file handle used to write the file
+/// * `path` - The path to the file
+/// * `contents` - The contents of the file
+///
+/// # Returns
+/// * `Result<(), String>` - An error if the directory or the file could not be written
+fn write_file(handle: &dyn CoreFileHandle, path: &Path, contents: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        handle.create_directory_if_not_exists(parent)
+              .map_err(|error| format!("Failed to create {}: {}", parent.to_string_lossy(), error))?;
+    }
+    handle.write(path, contents).map_err(|error| format!("Failed to write {}: {}", path.to_string_lossy(), error))
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::commands::command_runner::MockCoreRunner;
+    use crate::file_handler::MockCoreFileHandle;
+    use crate::runner_file::RunnerFile;
+    use crate::seating_plan::SeatingPlan;
+    use crate::wedding_invite::WeddingInvite;
+    use mockall::predicate::eq;
+    use std::collections::HashMap;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::{ExitStatus, Output};
+    use std::sync::{Arc, Mutex};
+
+    static DEMO_DIRECTORY: &str = "/tmp/missing_wedp_demo";
+
+    fn successful_output() -> Output {
+        Output { status: ExitStatus::from_raw(0), stdout: Vec::new(), stderr: Vec::new() }
+    }
+
+    fn recording_handle(written: Arc<Mutex<HashMap<PathBuf, String>>>) -> MockCoreFileHandle {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_create_directory_if_not_exists().returning(|_| Ok(()));
+        mock_handle.expect_write().returning(move |path, contents| {
+            written.lock().unwrap().insert(path.to_path_buf(), contents.to_string());
+            Ok(())
+        });
+        mock_handle
+    }
+
+    #[test]
+    fn test_generate() {
+        let written = Arc::new(Mutex::new(HashMap::new()));
+        let mock_handle = recording_handle(written.clone());
+        let mut mock_runner = MockCoreRunner::new();
+        for name in DEMO_DEPENDENCIES {
+            mock_runner.expect_run()
+                .with(eq(format!(
+                    "cd /tmp/missing_wedp_demo/repos/{} && git init -q && git checkout -q -b main && git add -A && \
+                     git -c user.name=wedp -c user.email=demo@wedp.invalid commit -q -m 'Add the {} demo dependency'",
+                    name, name
+                )))
+                .times(1)
+                .returning(|_| Ok(successful_output()));
+        }
+        let demo = Demo::new(PathBuf::from(DEMO_DIRECTORY));
+
+        demo.generate(&mock_runner, &mock_handle).unwrap();
+
+        let written = written.lock().unwrap();
+        assert_eq!(written.len(), 10);
+        let seating_plan = SeatingPlan::parse(&written[&demo.seating_plan_path()], DEMO_DIRECTORY).unwrap();
+        assert_eq!(seating_plan.venue, "/tmp/missing_wedp_demo/venue");
+        assert_eq!(seating_plan.attendees[1].name, "listener");
+        assert_eq!(seating_plan.attendees[1].url, "file:///tmp/missing_wedp_demo/repos/listener");
+        let invite_path = PathBuf::from("/tmp/missing_wedp_demo/repos/greeter/wedding_invite.yml");
+        let invite: WeddingInvite = serde_yaml::from_str(&written[&invite_path]).unwrap();
+        assert_eq!(invite.runner_files, vec![RunnerFile::from("runner_files/base.yml")]);
+        assert_eq!(
+            written[&PathBuf::from("/tmp/missing_wedp_demo/repos/greeter/runner_files/base.yml")],
+            "services:\n  greeter:\n    image: hello-world\n"
+        );
+    }
+
+    #[test]
+    fn test_generate_git_failure() {
+        let mock_handle = recording_handle(Arc::new(Mutex::new(HashMap::new())));
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run().times(1).returning(|_| Ok(Output {
+            status: ExitStatus::from_raw(128 << 8),
+            stdout: Vec::new(),
+            stderr: b"fatal: not a git repository".to_vec()
+        }));
+        let demo = Demo::new(PathBuf::from(DEMO_DIRECTORY));
+
+        let error = demo.generate(&mock_runner, &mock_handle).unwrap_err();
+        assert!(error.starts_with("Failed to commit the greeter demo repo: command failed with exit status: 128"));
+    }
+
+    #[test]
+    fn test_generate_refuses_non_empty_directory() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_write().times(0);
+        let demo = Demo::new(PathBuf::from("./tests/valid_repo"));
+
+        assert_eq!(
+            demo.generate(&MockCoreRunner::new(), &mock_handle),
+            Err("./tests/valid_repo is not empty and is not a wedp demo".to_string())
+        );
+    }
+
+    #[test]
+    fn test_cleanup() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_read_to_string()
+            .with(eq(Path::new("/tmp/missing_wedp_demo/.wedp-demo")))
+            .returning(|_| Ok("Generated by wedp demo".to_string()));
+        mock_handle.expect_remove_directory()
+            .with(eq(Path::new(DEMO_DIRECTORY)))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        assert!(Demo::new(PathBuf::from(DEMO_DIRECTORY)).cleanup(&mock_handle).is_ok());
+    }
+
+    #[test]
+    fn test_cleanup_refuses_other_directories() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_read_to_string()
+            .returning(|_| Err(std::io::Error::new(std::io::ErrorKind::NotFound, "not found")));
+        mock_handle.expect_remove_directory().times(0);
+
+        assert_eq!(
+            Demo::new(PathBuf::from("/home/user")).cleanup(&mock_handle),
+            Err("/home/user is not a wedp demo, not removing it".to_string())
+        );
+    }
+}
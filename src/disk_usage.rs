@@ -0,0 +1,156 @@
+//! Reports how much disk the dependencies cloned into the venue take up, so it is clear which of them to
+//! clean up when the disk fills up. The sizes are read through a ```CoreFileHandle```.
+use std::io::ErrorKind;
+use std::path::Path;
+
+use crate::file_handler::CoreFileHandle;
+
+
+/// The units sizes are rendered in, each 1024 times the one before.
+const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+
+/// The disk used by a dependency in the venue.
+///
+/// # Fields
+/// * `name` - The name of the dependency
+/// * `bytes` - The total size of the files in the directory of the dependency
+#[derive(Debug, PartialEq)]
+pub struct DependencySize {
+    pub name: String,
+    pub bytes: u64,
+}
+
+
+/// Sums the size of the directory of each dependency in the venue. Dependencies that are not installed are
+/// left out.
+///
+/// # Arguments
+/// * `names` - The names of the dependencies
+/// * `venue` - The path to the venue
+/// * `handle` - The file handle the sizes are read with
+///
+/// # Returns
+/// * `Result<Vec<DependencySize>, String>` - The sizes largest first, or an error if a directory could not be
+///   read
+pub fn venue_disk_usage(names: &[&str], venue: &Path, handle: &dyn CoreFileHandle) -> Result<Vec<DependencySize>, String> {
+    let mut sizes = Vec::new();
+    for name in names {
+        let path = venue.join(name);
+        match handle.dir_size(&path) {
+            Ok(bytes) => sizes.push(DependencySize { name: name.to_string(), bytes }),
+            Err(error) if error.kind() == ErrorKind::NotFound => continue,
+            Err(error) => return Err(format!("Could not read the size of {}: {}", path.to_string_lossy(), error))
+        }
+    }
+    sizes.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+    Ok(sizes)
+}
+
+
+/// Renders a size in bytes in the largest unit it has at least one of.
+///
+/// # Arguments
+/// * `bytes` - The size in bytes
+///
+/// # Returns
+/// * `String` - The size such as ```512 B``` or ```1.5 GiB```
+pub fn format_size(bytes: u64) -> String {
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{} B", bytes),
+        _ => format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+
+/// Renders the disk usage of the dependencies as a table with the total at the bottom.
+///
+/// # Arguments
+/// * `sizes` - The disk used by each dependency, in the order they are listed
+///
+/// # Returns
+/// * `String` - The rendered table
+pub fn render_du_table(sizes: &[DependencySize]) -> String {
+    let mut table = format!("{:<20} {}\n", "DEPENDENCY", "SIZE");
+    for size in sizes {
+        table.push_str(&format!("{:<20} {}\n", size.name, format_size(size.bytes)));
+    }
+    let total = sizes.iter().map(|size| size.bytes).sum();
+    table.push_str(&format!("{:<20} {}\n", "TOTAL", format_size(total)));
+    table
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::file_handler::MockCoreFileHandle;
+    use mockall::predicate::eq;
+    use std::path::PathBuf;
+
+    fn sizes_handle() -> MockCoreFileHandle {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_dir_size().returning(|path| match path.to_string_lossy().as_ref() {
+            "venue/auth" => Ok(2048),
+            "venue/billing" => Ok(5 * 1024 * 1024 * 1024),
+            "venue/search" => Ok(300 * 1024 * 1024),
+            "venue/mail" => Ok(2048),
+            _ => Err(std::io::Error::from(ErrorKind::NotFound))
+        });
+        mock_handle
+    }
+
+    #[test]
+    fn test_venue_disk_usage() {
+        let mock_handle = sizes_handle();
+        let sizes = venue_disk_usage(&["auth", "billing", "missing", "search", "mail"], Path::new("venue"), &mock_handle).unwrap();
+        let sizes: Vec<(&str, u64)> = sizes.iter().map(|size| (size.name.as_str(), size.bytes)).collect();
+        assert_eq!(sizes, vec![
+            ("billing", 5 * 1024 * 1024 * 1024),
+            ("search", 300 * 1024 * 1024),
+            ("auth", 2048),
+            ("mail", 2048),
+        ]);
+    }
+
+    #[test]
+    fn test_venue_disk_usage_unreadable() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_dir_size()
+            .with(eq(PathBuf::from("venue/auth")))
+            .returning(|_| Err(std::io::Error::from(ErrorKind::PermissionDenied)));
+        assert!(venue_disk_usage(&["auth"], Path::new("venue"), &mock_handle).unwrap_err()
+                    .starts_with("Could not read the size of venue/auth: "));
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(300 * 1024 * 1024), "300.0 MiB");
+        assert_eq!(format_size(5 * 1024 * 1024 * 1024), "5.0 GiB");
+        assert_eq!(format_size(2048 * 1024 * 1024 * 1024 * 1024), "2048.0 TiB");
+    }
+
+    #[test]
+    fn test_render_du_table() {
+        let mock_handle = sizes_handle();
+        let sizes = venue_disk_usage(&["auth", "search"], Path::new("venue"), &mock_handle).unwrap();
+        assert_eq!(
+            render_du_table(&sizes),
+            "DEPENDENCY           SIZE\n\
+             search               300.0 MiB\n\
+             auth                 2.0 KiB\n\
+             TOTAL                300.0 MiB\n"
+        );
+        assert_eq!(render_du_table(&[]), "DEPENDENCY           SIZE\nTOTAL                0 B\n");
+    }
+}
@@ -0,0 +1,385 @@
+//! Finds the problems ```wedp doctor``` reports and fixes the ones that are safe to fix with ```--fix```. The
+//! safe ones are housekeeping around the venue, a venue that does not exist, a venue without a ```.gitignore```,
+//! override files generated by a run that is no longer recorded, and Dockerfiles ```dress build``` copied into
+//! the build roots of the working directory. Each fix only creates the venue, writes its ```.gitignore```, or
+//! removes a file wedp generated, so nothing else is ever touched. Every check and fix goes through the file
+//! handle, a file is looked up through its metadata rather than ```exists``` so a dry run still finds it.
+use std::path::{Path, PathBuf};
+
+use crate::file_handler::CoreFileHandle;
+use crate::image_arch;
+use crate::paths::join_normalised;
+use crate::restart_policy;
+use crate::run_state::RunState;
+use crate::wedding_invite::{BuildFileCopy, WeddingInvite};
+
+
+/// The ```.gitignore``` written into the venue, the clones are repositories of their own.
+const VENUE_GITIGNORE: &str = "# Generated by wedp doctor --fix, the venue holds clones of other repositories.\n*\n";
+
+/// The generated files a fix may remove, anything else is refused.
+const GENERATED_FILES: [&str; 3] = [restart_policy::OVERRIDE_FILE, image_arch::OVERRIDE_FILE, "Dockerfile"];
+
+
+/// How a finding is fixed by ```--fix```.
+///
+/// # Fields
+/// * `CreateVenue` - Creates the venue directory
+/// * `IgnoreVenue` - Writes a ```.gitignore``` ignoring everything into the venue
+/// * `RemoveGenerated` - Removes a file wedp generated
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fix {
+    CreateVenue(PathBuf),
+    IgnoreVenue(PathBuf),
+    RemoveGenerated(PathBuf),
+}
+
+/// A problem found by ```wedp doctor```.
+///
+/// # Fields
+/// * `message` - What is wrong
+/// * `fix` - How ```--fix``` fixes it, ```None``` if it has to be fixed by hand
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub message: String,
+    pub fix: Option<Fix>,
+}
+
+impl Finding {
+
+    /// Creates a finding that has to be fixed by hand.
+    ///
+    /// # Arguments
+    /// * `message` - What is wrong
+    ///
+    /// # Returns
+    /// A new Finding struct without a fix
+    pub fn manual(message: String) -> Self {
+        Finding { message, fix: None }
+    }
+}
+
+
+/// Checks the venue exists.
+///
+/// # Arguments
+/// * `venue` - The path to the venue
+/// * `handle` - The file handle used to look the venue up
+///
+/// # Returns
+/// * `Option<Finding>` - A finding if the venue does not exist
+pub fn check_venue(venue: &Path, handle: &dyn CoreFileHandle) -> Option<Finding> {
+    match handle.modified(venue) {
+        Ok(_) => None,
+        Err(_) => Some(Finding {
+            message: format!("venue {} does not exist", venue.to_string_lossy()),
+            fix: Some(Fix::CreateVenue(venue.to_path_buf()))
+        })
+    }
+}
+
+/// Checks the venue has a ```.gitignore``` so the clones do not show up in the git status of a repository the
+/// venue is inside of. A venue that does not exist is reported as well, its fix runs after the venue is created.
+///
+/// # Arguments
+/// * `venue` - The path to the venue
+/// * `handle` - The file handle used to look the ```.gitignore``` up
+///
+/// # Returns
+/// * `Option<Finding>` - A finding if the venue has no ```.gitignore```
+pub fn check_venue_gitignore(venue: &Path, handle: &dyn CoreFileHandle) -> Option<Finding> {
+    let gitignore = venue.join(".gitignore");
+    match handle.modified(&gitignore) {
+        Ok(_) => None,
+        Err(_) => Some(Finding {
+            message: format!("venue {} has no .gitignore, its clones show up in git status", venue.to_string_lossy()),
+            fix: Some(Fix::IgnoreVenue(gitignore))
+        })
+    }
+}
+
+/// Checks for override files in the venue that the last recorded run did not start with, left by a run that
+/// crashed or ran in the foreground. The override files are generated again by every run.
+///
+/// # Arguments
+/// * `venue` - The path to the venue
+/// * `run_state` - The compose files the dependencies were last started with
+/// * `handle` - The file handle used to look the override files up
+///
+/// # Returns
+/// * `Vec<Finding>` - A finding for every leftover override file
+pub fn check_overrides(venue: &Path, run_state: &RunState, handle: &dyn CoreFileHandle) -> Vec<Finding> {
+    [restart_policy::OVERRIDE_FILE, image_arch::OVERRIDE_FILE].iter()
+        .map(|name| join_normalised(venue, &[name]))
+        .filter(|path| handle.modified(path).is_ok())
+        .filter(|path| !run_state.compose_files.iter().any(|file| Path::new(&file.path) == path))
+        .map(|path| Finding {
+            message: format!("{} is left over from a run that is no longer running", path.to_string_lossy()),
+            fix: Some(Fix::RemoveGenerated(path))
+        })
+        .collect()
+}
+
+/// Checks for Dockerfiles ```dress build``` copied into the build roots of the local wedding invite and did not
+/// remove. A Dockerfile is only reported if it is the same as the build file it would be copied from.
+///
+/// # Arguments
+/// * `invite` - The wedding invite of the working directory
+/// * `working_directory` - The directory the wedding invite is in
+/// * `arch` - The CPU type the Dockerfiles are picked for, ```None``` uses the CPU type of the host
+/// * `handle` - The file handle used to read the Dockerfiles
+///
+/// # Returns
+/// * `Vec<Finding>` - A finding for every Dockerfile left in a build root
+pub fn check_local_dockerfiles(invite: &WeddingInvite, working_directory: &str, arch: Option<&str>, handle: &dyn CoreFileHandle) -> Vec<Finding> {
+    let copies = [
+        invite.build_file_copy(working_directory, "", arch),
+        invite.init_build_file_copy(working_directory, "", arch)
+    ];
+    copies.into_iter()
+        .filter_map(|copy| copy.ok().flatten())
+        .filter(|copy| is_copied(copy, handle))
+        .map(|copy| Finding {
+            message: format!(
+                "{} is a copy of {} left by dress build", copy.to.to_string_lossy(), copy.from.to_string_lossy()
+            ),
+            fix: Some(Fix::RemoveGenerated(copy.to))
+        })
+        .collect()
+}
+
+/// Checks a Dockerfile in a build root is the same as the build file it is copied from.
+///
+/// # Arguments
+/// * `copy` - The build file and where it is copied to
+/// * `handle` - The file handle used to read both files
+///
+/// # Returns
+/// * `bool` - True if both files can be read and are the same
+fn is_copied(copy: &BuildFileCopy, handle: &dyn CoreFileHandle) -> bool {
+    match (handle.read_to_string(&copy.from), handle.read_to_string(&copy.to)) {
+        (Ok(from), Ok(to)) => from == to,
+        _ => false
+    }
+}
+
+/// Creates the venue directory.
+///
+/// # Arguments
+/// * `venue` - The path to the venue
+/// * `handle` - The file handle used to create the directory
+///
+/// # Returns
+/// * `Result<(), String>` - An error if the directory could not be created
+pub fn create_venue(venue: &Path, handle: &dyn CoreFileHandle) -> Result<(), String> {
+    handle.create_directory_if_not_exists(venue)
+          .map_err(|error| format!("Could not create {}: {}", venue.to_string_lossy(), error))
+}
+
+/// Writes a ```.gitignore``` ignoring everything into the venue.
+///
+/// # Arguments
+/// * `gitignore` - The path to the ```.gitignore``` in the venue
+/// * `handle` - The file handle used to write the file
+///
+/// # Returns
+/// * `Result<(), String>` - An error if the file could not be written
+pub fn ignore_venue(gitignore: &Path, handle: &dyn CoreFileHandle) -> Result<(), String> {
+    handle.write(gitignore, VENUE_GITIGNORE)
+          .map_err(|error| format!("Could not write {}: {}", gitignore.to_string_lossy(), error))
+}
+
+/// Removes a file wedp generated, a file with any other name is refused.
+///
+/// # Arguments
+/// * `path` - The path to the generated file
+/// * `handle` - The file handle used to remove the file
+///
+/// # Returns
+/// * `Result<(), String>` - An error if the file is not one wedp generates or could not be removed
+pub fn remove_generated(path: &Path, handle: &dyn CoreFileHandle) -> Result<(), String> {
+    let generated = path.file_name()
+                        .and_then(|name| name.to_str())
+                        .map(|name| GENERATED_FILES.contains(&name))
+                        .unwrap_or(false);
+    if !generated {
+        return Err(format!("{} is not a file wedp generates, remove it by hand", path.to_string_lossy()))
+    }
+    handle.remove(path).map_err(|error| format!("Could not remove {}: {}", path.to_string_lossy(), error))
+}
+
+/// Applies the fix of a finding.
+///
+/// # Arguments
+/// * `fix` - The fix to apply
+/// * `handle` - The file handle the fix goes through
+///
+/// # Returns
+/// * `Result<(), String>` - An error if the fix could not be applied
+pub fn apply(fix: &Fix, handle: &dyn CoreFileHandle) -> Result<(), String> {
+    match fix {
+        Fix::CreateVenue(venue) => create_venue(venue, handle),
+        Fix::IgnoreVenue(gitignore) => ignore_venue(gitignore, handle),
+        Fix::RemoveGenerated(path) => remove_generated(path, handle)
+    }
+}
+
+/// Renders the findings for ```wedp doctor```, one per line with the ones ```--fix``` can fix marked.
+///
+/// # Arguments
+/// * `findings` - The findings to render
+///
+/// # Returns
+/// * `String` - The findings, an empty string if there are none
+pub fn render_findings(findings: &[Finding]) -> String {
+    findings.iter()
+        .map(|finding| match finding.fix {
+            Some(_) => format!("{} (fixable)\n", finding.message),
+            None => format!("{}\n", finding.message)
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::compose_file::ComposeFileRef;
+    use crate::file_handler::MockCoreFileHandle;
+    use mockall::predicate::eq;
+    use std::collections::HashMap;
+    use std::io::{Error, ErrorKind};
+    use std::time::SystemTime;
+
+    fn handle_with(existing: Vec<&'static str>) -> MockCoreFileHandle {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_modified()
+            .returning(move |path| match existing.iter().any(|existing| Path::new(existing) == path) {
+                true => Ok(SystemTime::now()),
+                false => Err(Error::from(ErrorKind::NotFound))
+            });
+        mock_handle
+    }
+
+    #[test]
+    fn test_check_venue() {
+        assert_eq!(check_venue(Path::new("venue"), &handle_with(vec!["venue"])), None);
+        assert_eq!(
+            check_venue(Path::new("venue"), &handle_with(vec![])),
+            Some(Finding { message: "venue venue does not exist".to_string(), fix: Some(Fix::CreateVenue(PathBuf::from("venue"))) })
+        );
+    }
+
+    #[test]
+    fn test_check_venue_gitignore() {
+        assert_eq!(check_venue_gitignore(Path::new("venue"), &handle_with(vec!["venue", "venue/.gitignore"])), None);
+        assert_eq!(
+            check_venue_gitignore(Path::new("venue"), &handle_with(vec!["venue"])).unwrap().fix,
+            Some(Fix::IgnoreVenue(PathBuf::from("venue/.gitignore")))
+        );
+    }
+
+    #[test]
+    fn test_check_overrides() {
+        let handle = handle_with(vec!["venue/wedp_restart_override.yml", "venue/wedp_image_arch_override.yml"]);
+        let findings = check_overrides(Path::new("venue"), &RunState::default(), &handle);
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].message, "venue/wedp_restart_override.yml is left over from a run that is no longer running");
+        assert_eq!(findings[0].fix, Some(Fix::RemoveGenerated(PathBuf::from("venue/wedp_restart_override.yml"))));
+
+        let running = RunState {
+            remote: false,
+            compose_files: vec![ComposeFileRef::new("restart_policy", "venue/wedp_restart_override.yml".to_string())]
+        };
+        let findings = check_overrides(Path::new("venue"), &running, &handle);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].fix, Some(Fix::RemoveGenerated(PathBuf::from("venue/wedp_image_arch_override.yml"))));
+    }
+
+    #[test]
+    fn test_check_local_dockerfiles() {
+        let mut build_files = HashMap::new();
+        build_files.insert("default".to_string(), "builds/Dockerfile".to_string());
+        let invite = WeddingInvite {
+            build_files: Some(build_files),
+            build_root: "server".to_string(),
+            init_build: None,
+            runner_files: Vec::new(),
+            remote_runner_files: None,
+            build_lock: None,
+            dev_runner_files: None,
+            remote_image_arch_suffixes: None
+        };
+        let contents = HashMap::from([
+            (PathBuf::from("project/builds/Dockerfile"), "FROM rust\n"),
+            (PathBuf::from("project/server/Dockerfile"), "FROM rust\n"),
+        ]);
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_read_to_string()
+            .returning(move |path| contents.get(path).map(|contents| contents.to_string()).ok_or(Error::from(ErrorKind::NotFound)));
+        let findings = check_local_dockerfiles(&invite, "project", None, &mock_handle);
+        assert_eq!(findings, vec![Finding {
+            message: "project/server/Dockerfile is a copy of project/builds/Dockerfile left by dress build".to_string(),
+            fix: Some(Fix::RemoveGenerated(PathBuf::from("project/server/Dockerfile")))
+        }]);
+
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_read_to_string()
+            .returning(|path| match path == Path::new("project/builds/Dockerfile") {
+                true => Ok("FROM rust\n".to_string()),
+                false => Ok("FROM rust\nRUN make\n".to_string())
+            });
+        assert!(check_local_dockerfiles(&invite, "project", None, &mock_handle).is_empty());
+    }
+
+    #[test]
+    fn test_create_venue() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_create_directory_if_not_exists()
+            .with(eq(PathBuf::from("venue")))
+            .times(1)
+            .returning(|_| Ok(()));
+        assert_eq!(create_venue(Path::new("venue"), &mock_handle), Ok(()));
+    }
+
+    #[test]
+    fn test_ignore_venue() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_write()
+            .with(eq(PathBuf::from("venue/.gitignore")), eq(VENUE_GITIGNORE))
+            .times(1)
+            .returning(|_, _| Ok(()));
+        assert_eq!(ignore_venue(Path::new("venue/.gitignore"), &mock_handle), Ok(()));
+    }
+
+    #[test]
+    fn test_remove_generated() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_remove()
+            .with(eq(PathBuf::from("venue/wedp_restart_override.yml")))
+            .times(1)
+            .returning(|_| Ok(()));
+        assert_eq!(remove_generated(Path::new("venue/wedp_restart_override.yml"), &mock_handle), Ok(()));
+
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_remove().times(0);
+        assert_eq!(
+            remove_generated(Path::new("venue/auth/wedding_invite.yml"), &mock_handle),
+            Err("venue/auth/wedding_invite.yml is not a file wedp generates, remove it by hand".to_string())
+        );
+    }
+
+    #[test]
+    fn test_render_findings() {
+        let findings = vec![
+            Finding::manual("auth: build root server is not a directory".to_string()),
+            Finding { message: "venue venue does not exist".to_string(), fix: Some(Fix::CreateVenue(PathBuf::from("venue"))) },
+        ];
+        assert_eq!(
+            render_findings(&findings),
+            "auth: build root server is not a directory\nvenue venue does not exist (fixable)\n"
+        );
+        assert_eq!(render_findings(&[]), "");
+    }
+}
@@ -1,115 +1,258 @@
 //! Runs the seating plan and the wedding invite of the repo running wedding planner.
-use crate::runner::Runner;
+use std::fmt;
+use std::path::Path;
+use crate::attendee_filter::AttendeeFilter;
+use crate::logging;
+use crate::runner::{check_install_reports, installed_names, logs_exit_code, render_install_summary, Runner};
+use crate::clock::{Clock, SystemClock};
+use crate::compose_binary::ContainerEngine;
+use crate::error::WedpError;
+use crate::runner_file::included_paths;
 use crate::wedding_invite::WeddingInvite;
-use crate::file_handler::FileHandle;
-use crate::commands::command_runner::{CommandRunner, CoreRunner};
+use crate::wedp_ignore::{IgnoreFileHandle, WedpIgnore};
+use crate::compose_file::{compose_args, ComposeAction, ComposeFileRef};
+use crate::container_status::{compose_ps, render_ps_table, ContainerStatus};
+use crate::commands::command_runner::{run_docker_checked, ProgramArgs};
+
+
+/// The attendee name given to the compose files of the local wedding invite.
+pub const LOCAL_ATTENDEE: &str = "local";
+
+
+/// The command line options of the dress subcommands.
+/// 
+/// # Fields
+/// * `detach` - Whether the run commands should run the dependencies in the background
+/// * `dry_run` - Whether to print the commands and file operations instead of running them
+/// * `jobs` - The number of dependencies to install at the same time
+/// * `force` - Whether install should clone dependencies that are already cloned again
+/// * `arch` - The CPU type to pick the Dockerfiles for, ```None``` uses the CPU type of the host
+/// * `engine` - The container engine to run the dependencies with, ```None``` uses the seating plan setting
+/// * `compose_command` - The compose command to run instead of resolving it, ```None``` resolves it as usual
+/// * `skip_missing` - Whether runner files that do not exist are left out with a warning instead of failing the command
+/// * `remote` - Whether logs and ps should read the containers started from the remote runner files
+/// * `follow` - Whether logs should keep following the logs until interrupted
+/// * `tail` - The number of lines logs prints of each container, ```None``` for the default
+/// * `services` - The services logs should print the logs of, empty for all of them
+/// * `archive` - The archive install should extract the dependencies from, ```None``` clones them
+/// * `filter` - The attendees of the seating plan the command applies to, the local invite is always included
+/// * `pull` - Whether build and remotebuild should pull the latest base images before building
+/// * `no_cache` - Whether build and remotebuild should build the images without the build cache
+#[derive(Debug, Default)]
+pub struct DressOptions {
+    pub detach: bool,
+    pub dry_run: bool,
+    pub jobs: usize,
+    pub force: bool,
+    pub arch: Option<String>,
+    pub engine: Option<ContainerEngine>,
+    pub compose_command: Option<String>,
+    pub skip_missing: bool,
+    pub remote: bool,
+    pub follow: bool,
+    pub tail: Option<usize>,
+    pub services: Vec<String>,
+    pub archive: Option<String>,
+    pub filter: AttendeeFilter,
+    pub pull: bool,
+    pub no_cache: bool,
+}
+
+
+/// The dress subcommands that ```dress_rehearsal_factory``` can run.
+pub const DRESS_COMMANDS: [&str; 11] = [
+    "build", "remotebuild", "run", "devrun", "remoterun", "install", "logs", "ps", "teardown", "remoteteardown", "setup"
+];
+
+
+/// The ways a dress subcommand can fail.
+/// 
+/// # Variants
+/// * `Config` - The seating plan, the wedding invite, or the ```.wedpignore``` could not be loaded
+/// * `UnknownCommand` - The command is not one of the ```DRESS_COMMANDS```
+/// * `Operation` - The command ran but one or more of its steps failed
+#[derive(Debug, PartialEq)]
+pub enum DressRehearsalError {
+    Config(String),
+    UnknownCommand(String),
+    Operation(String),
+}
+
+impl fmt::Display for DressRehearsalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DressRehearsalError::Config(message) => write!(f, "{}", message),
+            DressRehearsalError::UnknownCommand(command) => write!(f, "{} not supported", command),
+            DressRehearsalError::Operation(message) => write!(f, "{}", message)
+        }
+    }
+}
+
+/// Lets main exit with the code of a failed dress rehearsal, a config that could not be loaded exits as an
+/// invalid config.
+impl From<DressRehearsalError> for WedpError {
+    fn from(error: DressRehearsalError) -> Self {
+        match error {
+            DressRehearsalError::Config(_) | DressRehearsalError::UnknownCommand(_) => WedpError::InvalidConfig(error.to_string()),
+            DressRehearsalError::Operation(message) => WedpError::Failed(message)
+        }
+    }
+}
 
 
 /// constructs the ```DressRehearsal``` struct and runs the command passed in.
 /// 
 /// # Arguments
-/// * `command` - The command to run
-/// * `seating_plan_path` - The path to the seating plan file
+/// * `command` - The dress subcommand to run
+/// * `options` - The command line options of the dress subcommand
+/// * `seating_plan_paths` - The paths to the seating plan files, merged in order if there is more than one
 /// * `wedding_invite_path` - The path to the wedding invite file
 /// * `working_directory` - The path to the working directory
-pub fn dress_rehearsal_factory(command: String, seating_plan_path: String, wedding_invite_path: String, working_directory: String) {
-    let file_handle = FileHandle{};
-
-    let dress_rehearsal = match DressRehearsal::new(seating_plan_path.clone(), wedding_invite_path.clone(), &working_directory) {
-        Ok(dress_rehearsal) => dress_rehearsal,
-        Err(error) => {
-            println!("{} for seating plan path: {} wedding invite path: {} working dir {}", error, seating_plan_path, wedding_invite_path, working_directory);
-            return;
-        }
+/// 
+/// # Returns
+/// * `Result<(), DressRehearsalError>` - An error if the command is unknown, the config could not be loaded, or a step failed
+pub fn dress_rehearsal_factory(command: String, options: DressOptions, seating_plan_paths: Vec<String>, wedding_invite_path: String, working_directory: String) -> Result<(), DressRehearsalError> {
+    if !DRESS_COMMANDS.contains(&command.as_str()) {
+        return Err(DressRehearsalError::UnknownCommand(command))
+    }
+    let runner = match options.dry_run {
+        true => Runner::new_dry_run(seating_plan_paths.clone()),
+        false => Runner::new(seating_plan_paths.clone())
     };
+    let mut dress_rehearsal = runner
+        .and_then(|runner| DressRehearsal::with_runner(runner, wedding_invite_path.clone(), &working_directory))
+        .map_err(|error| DressRehearsalError::Config(format!(
+            "{} for seating plan path: {} wedding invite path: {} working dir {}", 
+            error, seating_plan_paths.join(", "), wedding_invite_path, working_directory
+        )))?;
+    dress_rehearsal.runner = dress_rehearsal.runner.with_arch(options.arch.clone())
+                                                   .with_engine(options.engine)
+                                                   .with_compose_command(options.compose_command.clone())
+                                                   .with_skip_missing(options.skip_missing)
+                                                   .with_filter(options.filter.clone())
+                                                   .map_err(DressRehearsalError::Config)?;
+    let arch = options.arch.as_deref();
+    let ignore = WedpIgnore::from_directory(&working_directory).map_err(DressRehearsalError::Config)?;
+    let file_handle = IgnoreFileHandle::new(dress_rehearsal.runner.file_handle.as_ref(), ignore);
+    let mut failures = Vec::new();
+
     match command.as_ref() {
 
-        "dressbuild" => {
-            match dress_rehearsal.wedding_invite.prepare_build_file(&working_directory, &"".to_string(), &file_handle) {
+        "build" | "remotebuild" => {
+            match dress_rehearsal.wedding_invite.prepare_build_file(&working_directory, "", arch, &file_handle) {
                 Ok(_) => {
-                    println!("local wedding invite prepared build")
+                    logging::info("local wedding invite prepared build")
                 },
                 Err(error) => {
-                    println!("local wedding invite failed to prepare build: {}", error);
+                    failures.push(format!("local wedding invite failed to prepare build: {}", error));
                 }
             };
-            match dress_rehearsal.wedding_invite.prepare_init_build_file(&working_directory, &"".to_string(), &file_handle) {
+            match dress_rehearsal.wedding_invite.prepare_init_build_file(&working_directory, "", arch, &file_handle) {
                 Ok(_) => {
-                    println!("local wedding invite prepared init build")
+                    logging::info("local wedding invite prepared init build")
                 },
                 Err(error) => {
-                    println!("local wedding invite failed to prepare init build: {}", error);
+                    failures.push(format!("local wedding invite failed to prepare init build: {}", error));
                 }
             };
-            dress_rehearsal.build_dependencies();
-        },
-        "dressremotebuild" => {
-            match dress_rehearsal.wedding_invite.prepare_build_file(&working_directory, &"".to_string(), &file_handle) {
-                Ok(_) => {
-                    println!("local wedding invite prepared build")
-                },
-                Err(error) => {
-                    println!("local wedding invite failed to prepare build: {}", error);
-                }
+            let start = SystemClock.now();
+            let built = match command.as_ref() {
+                "build" => dress_rehearsal.build_dependencies(options.pull, options.no_cache),
+                _ => dress_rehearsal.build_remote_dependencies(options.pull, options.no_cache)
             };
-            match dress_rehearsal.wedding_invite.prepare_init_build_file(&working_directory, &"".to_string(), &file_handle) {
-                Ok(_) => {
-                    println!("local wedding invite prepared init build")
-                },
-                Err(error) => {
-                    println!("local wedding invite failed to prepare init build: {}", error);
-                }
-            };
-            dress_rehearsal.build_remote_dependencies();
+            if let Err(error) = built {
+                failures.push(error);
+            }
+            dress_rehearsal.runner.record_usage(|stats, names| stats.record_build(names, SystemClock.now().duration_since(start)));
         },
-        "dressrun" => {
-            dress_rehearsal.run_dependencies();
+        "run" => {
+            dress_rehearsal.runner.record_usage(|stats, names| stats.record_run(names, &SystemClock));
+            let ran = match options.detach {
+                true => dress_rehearsal.run_dependencies_background(),
+                false => dress_rehearsal.run_dependencies()
+            };
+            if let Err(error) = ran {
+                failures.push(error);
+            }
         },
-        "dressdevrun" => {
-            dress_rehearsal.run_dev_dependencies();
+        "devrun" => {
+            dress_rehearsal.runner.record_usage(|stats, names| stats.record_run(names, &SystemClock));
+            if let Err(error) = dress_rehearsal.run_dev_dependencies() {
+                failures.push(error);
+            }
         },
-        "dressrun-d" => {
-            dress_rehearsal.run_dependencies_background();
+        "remoterun" => {
+            dress_rehearsal.runner.record_usage(|stats, names| stats.record_run(names, &SystemClock));
+            let ran = match options.detach {
+                true => dress_rehearsal.run_remote_dependencies_background(),
+                false => dress_rehearsal.run_remote_dependencies()
+            };
+            if let Err(error) = ran {
+                failures.push(error);
+            }
         },
-        "dressremoterun" => {
-            dress_rehearsal.run_remote_dependencies();
+        "install" => {
+            let cwd = Path::new(&dress_rehearsal.working_directory);
+            let reports = match &options.archive {
+                Some(archive) => dress_rehearsal.runner.install_from_archive(archive, options.jobs, options.force, cwd),
+                None => dress_rehearsal.runner.install_dependencies(options.jobs, options.force, cwd)
+            };
+            dress_rehearsal.runner.record_usage(|stats, _| stats.record_installs(&installed_names(&reports)));
+            print!("{}", render_install_summary(&reports));
+            if let Err(error) = check_install_reports(&reports) {
+                failures.push(error);
+            }
         },
-        "dressremoterun-d" => {
-            dress_rehearsal.run_remote_dependencies_background();
+        "logs" => {
+            match dress_rehearsal.logs(options.remote, options.follow, options.tail, &options.services) {
+                Ok(0) => {},
+                Ok(code) => failures.push(format!("docker-compose logs exited with {}", code)),
+                Err(error) => failures.push(error)
+            }
         },
-        "dressinstall" => {
-            dress_rehearsal.runner.install_dependencies();
+        "ps" => {
+            match dress_rehearsal.ps(options.remote) {
+                Ok(statuses) => print!("{}", render_ps_table(&statuses)),
+                Err(error) => failures.push(error)
+            }
         },
-        "dressteardown" => {
-            dress_rehearsal.teardown_dependencies();
+        "teardown" => {
+            if let Err(error) = dress_rehearsal.teardown_dependencies() {
+                failures.push(error);
+            }
         },
-        "dressremoteteardown" => {
-            dress_rehearsal.teardown_remote_dependencies();
-            match dress_rehearsal.wedding_invite.delete_build_file(&working_directory, &"".to_string(), &file_handle){
+        "remoteteardown" => {
+            if let Err(error) = dress_rehearsal.teardown_remote_dependencies() {
+                failures.push(error);
+            }
+            match dress_rehearsal.wedding_invite.delete_build_file(&working_directory, "", &file_handle){
                 Ok(_) => {
-                    println!("local wedding invite deleted build")
+                    logging::info("local wedding invite deleted build")
                 },
                 Err(error) => {
-                    println!("local wedding invite failed to delete build: {}", error);
+                    failures.push(format!("local wedding invite failed to delete build: {}", error));
                 }
             };
-            match dress_rehearsal.wedding_invite.delete_init_build_file(&working_directory, &"".to_string(), &file_handle) {
+            match dress_rehearsal.wedding_invite.delete_init_build_file(&working_directory, "", &file_handle) {
                 Ok(_) => {
-                    println!("local wedding invite deleted init build")
+                    logging::info("local wedding invite deleted init build")
                 },
                 Err(error) => {
-                    println!("local wedding invite failed to delete init build: {}", error);
+                    failures.push(format!("local wedding invite failed to delete init build: {}", error));
                 }
             };
         },
-        "dresssetup" => {
-            dress_rehearsal.runner.create_venue();
-        }
-        _ => {
-            println!("{} not supported", command);
+        "setup" => {
+            if let Err(error) = dress_rehearsal.runner.create_venue() {
+                failures.push(format!("Failed to create venue: {}", error));
+            }
         }
+        _ => unreachable!("the command is checked against DRESS_COMMANDS")
+    }
+    match failures.is_empty() {
+        true => Ok(()),
+        false => Err(DressRehearsalError::Operation(failures.join("\n")))
     }
-
 }
 
 
@@ -130,118 +273,456 @@ impl DressRehearsal {
     /// The constructor for the DressRehearsal struct.
     /// 
     /// # Arguments
-    /// * `seating_plan_path` - The path to the seating plan file for the repo running wedding planner
+    /// * `seating_plan_paths` - The paths to the seating plan files for the repo running wedding planner, merged 
+    ///   in order if there is more than one
     /// * `wedding_invite_path` - The path to the wedding invite file for the repo running wedding planner
     /// * `working_directory` - The working directory of the repo running local invite docker files
     /// 
     /// # Returns
-    /// * `Result<DressRehearsal, String>` - The DressRehearsal struct or an error message
-    pub fn new(seating_plan_path: String, wedding_invite_path: String, working_directory: &String) -> Result<DressRehearsal, String> {
-        let runner = match Runner::new(seating_plan_path){
-            Ok(runner) => runner,
-            Err(error) => return Err(error)
-        };
-        let wedding_invite = match WeddingInvite::from_file(wedding_invite_path) {
-            Ok(wedding_invite) => wedding_invite,
-            Err(error) => return Err(error)
-        };
-        Ok(DressRehearsal{runner, wedding_invite, working_directory: working_directory.clone()})
+    /// * `Result<DressRehearsal, WedpError>` - The DressRehearsal struct or the error loading the seating plans or wedding invite
+    pub fn new(seating_plan_paths: Vec<String>, wedding_invite_path: String, working_directory: &str) -> Result<DressRehearsal, WedpError> {
+        let runner = Runner::new(seating_plan_paths)?;
+        DressRehearsal::with_runner(runner, wedding_invite_path, working_directory)
     }
 
-    /// Gets the docker-compose command for the dependencies in the seating plan and local wedding invite.
+    /// The constructor for the DressRehearsal struct running the seating plan with the runner passed in.
     /// 
     /// # Arguments
-    /// * `remote` - Whether the command is for remote dependencies
+    /// * `runner` - The runner with the seating plan for the repo running wedding planner
+    /// * `wedding_invite_path` - The path to the wedding invite file for the repo running wedding planner
+    /// * `working_directory` - The working directory of the repo running local invite docker files
     /// 
     /// # Returns
-    /// * `String` - The docker-compose command
-    fn get_compose_file_command(&self, remote: bool) -> String {
-        let mut command_string = self.runner.get_compose_file_command(remote);
+    /// * `Result<DressRehearsal, WedpError>` - The DressRehearsal struct or the error loading the wedding invite
+    pub fn with_runner(runner: Runner, wedding_invite_path: String, working_directory: &str) -> Result<DressRehearsal, WedpError> {
+        let wedding_invite = WeddingInvite::from_file(wedding_invite_path)?;
+        Ok(DressRehearsal{runner, wedding_invite, working_directory: working_directory.to_string()})
+    }
 
-        for file in &self.wedding_invite.runner_files {
-            command_string.push_str(&format!("-f {}/{} ", self.working_directory, file));
+    /// Limits the attendees of the seating plan that are run alongside the local invite.
+    /// 
+    /// # Arguments
+    /// * `filter` - The attendees the commands apply to
+    /// 
+    /// # Returns
+    /// * `Result<DressRehearsal, WedpError>` - The DressRehearsal struct or an error listing the valid attendee 
+    ///   names if the filter names an attendee that is not in the seating plan
+    pub fn with_filter(self, filter: AttendeeFilter) -> Result<DressRehearsal, WedpError> {
+        let runner = self.runner.with_filter(filter).map_err(WedpError::InvalidConfig)?;
+        Ok(DressRehearsal { runner, ..self })
+    }
+
+    /// Gets the docker-compose files for the dependencies in the seating plan and local wedding invite.
+    /// 
+    /// # Arguments
+    /// * `remote` - Whether the files are for remote dependencies
+    /// 
+    /// # Returns
+    /// * `Result<Vec<ComposeFileRef>, String>` - The docker-compose files with the dependency each file came from
+    ///   or an error listing the dependencies whose wedding invite could not be read
+    pub fn get_compose_files(&self, remote: bool) -> Result<Vec<ComposeFileRef>, String> {
+        let mut compose_files = self.runner.get_compose_files(remote)?;
+
+        for file in included_paths(&self.wedding_invite.runner_files) {
+            compose_files.push(ComposeFileRef::new(LOCAL_ATTENDEE, format!("{}/{}", self.working_directory, file)));
         }
-        return command_string;
+        Ok(compose_files)
     }
 
-    /// Gets the docker-compose command for the dependencies in the seating plan and local wedding invite for dev mode.
+    /// Gets the docker-compose files for the dependencies in the seating plan and local wedding invite for dev mode.
     /// 
     /// # Returns
-    /// * `String` - The docker-compose command
-    fn get_compose_file_command_dev(&self) -> String {
-        let mut command_string = self.runner.get_compose_file_command(false);
+    /// * `Result<Vec<ComposeFileRef>, String>` - The docker-compose files with the dependency each file came from
+    ///   or an error listing the dependencies whose wedding invite could not be read
+    pub fn get_compose_files_dev(&self) -> Result<Vec<ComposeFileRef>, String> {
+        let mut compose_files = self.runner.get_compose_files(false)?;
 
-        match &self.wedding_invite.dev_runner_files {
-            Some(dev_runner_files) => {
-                for file in dev_runner_files {
-                    command_string.push_str(&format!("-f {}/{} ", self.working_directory, file));
-                }
-            },
-            None => {}
+        if let Some(dev_runner_files) = &self.wedding_invite.dev_runner_files {
+            for file in included_paths(dev_runner_files) {
+                compose_files.push(ComposeFileRef::new(LOCAL_ATTENDEE, format!("{}/{}", self.working_directory, file)));
+            }
         }
-        return command_string;
+        Ok(compose_files)
+    }
+
+    /// Gets the docker-compose command for the dependencies in the seating plan and local wedding invite.
+    /// 
+    /// # Arguments
+    /// * `remote` - Whether the command is for remote dependencies
+    /// 
+    /// # Returns
+    /// * `Result<ProgramArgs, String>` - The docker-compose command or an error listing the wedding invites or runner 
+    ///   files that do not exist
+    fn get_compose_file_command(&self, remote: bool) -> Result<ProgramArgs, String> {
+        let compose_files = self.runner.existing_compose_files(self.get_compose_files(remote)?)?;
+        Ok(compose_args(self.runner.compose_command(), &compose_files))
+    }
+
+    /// Gets the docker-compose command for running the dependencies and local invite, including the override 
+    /// file setting the ```restart_policy``` of the seating plan on every service if there is one and, for remote
+    /// runs, the override file picking the per-arch image tags.
+    /// 
+    /// # Arguments
+    /// * `compose_files` - The compose files to run
+    /// * `remote` - Whether the compose files are for remote dependencies
+    /// 
+    /// # Returns
+    /// * `Result<ProgramArgs, String>` - The docker-compose command or an error if a runner file does not exist or an 
+    ///   override file could not be generated
+    fn get_run_command(&self, compose_files: Vec<ComposeFileRef>, remote: bool) -> Result<ProgramArgs, String> {
+        let compose_files = self.runner.existing_compose_files(compose_files)?;
+        let compose_files = match remote {
+            true => self.runner.with_image_arch_override(compose_files)?,
+            false => compose_files
+        };
+        let compose_files = self.runner.with_restart_override(compose_files)?;
+        Ok(compose_args(self.runner.compose_command(), &compose_files))
     }
 
     /// Tears down the dependencies that are running.
-    pub fn teardown_dependencies(&self) {
-        let command_runner = CommandRunner {};
-        let mut command_string = self.get_compose_file_command(false);
-        command_runner.run_docker_command(" down", "failed to tear down", &mut command_string);
+    /// 
+    /// # Returns
+    /// * `Result<(), String>` - An error if the docker-compose command could not be put together or did not succeed
+    pub fn teardown_dependencies(&self) -> Result<(), String> {
+        let compose = self.get_compose_file_command(false)?;
+        run_docker_checked(self.runner.command_runner.as_ref(), &ComposeAction::Down.to_string(), "failed to tear down", &compose).map_err(String::from)
     }
 
     /// Tears down the remote dependencies that are running.
-    pub fn teardown_remote_dependencies(&self) {
-        let command_runner = CommandRunner {};
-        let mut command_string = self.get_compose_file_command(true);
-        command_runner.run_docker_command(" down", "failed to tear down", &mut command_string);
+    /// 
+    /// # Returns
+    /// * `Result<(), String>` - An error if the docker-compose command could not be put together or did not succeed
+    pub fn teardown_remote_dependencies(&self) -> Result<(), String> {
+        let compose = self.get_compose_file_command(true)?;
+        run_docker_checked(self.runner.command_runner.as_ref(), &ComposeAction::Down.to_string(), "failed to tear down", &compose).map_err(String::from)
     }
 
     /// Builds the dependencies that are needed to run. 
-    pub fn build_dependencies(&self) {
-        let command_runner = CommandRunner {};
-        let mut command_string = self.get_compose_file_command(false);
-        command_runner.run_docker_command(" build --no-cache", "failed to build", &mut command_string);
+    /// 
+    /// # Arguments
+    /// * `pull` - If true the latest versions of the base images are pulled before building
+    /// * `no_cache` - If true the images are built without the build cache
+    /// 
+    /// # Returns
+    /// * `Result<(), String>` - An error if the docker-compose command could not be put together or did not succeed
+    pub fn build_dependencies(&self, pull: bool, no_cache: bool) -> Result<(), String> {
+        let compose = self.get_compose_file_command(false)?;
+        let command = ComposeAction::Build { pull, no_cache };
+        run_docker_checked(self.runner.command_runner.as_ref(), &command.to_string(), "failed to build", &compose).map_err(String::from)
     }
 
     /// Builds the remote dependencies.
-    pub fn build_remote_dependencies(&self) {
-        let command_runner = CommandRunner {};
-        let mut command_string = self.get_compose_file_command(true);
-        command_runner.run_docker_command(" build --no-cache", "failed to build remote dependencies", &mut command_string);
+    /// 
+    /// # Arguments
+    /// * `pull` - If true the latest versions of the base images are pulled before building
+    /// * `no_cache` - If true the images are built without the build cache
+    /// 
+    /// # Returns
+    /// * `Result<(), String>` - An error if the docker-compose command could not be put together or did not succeed
+    pub fn build_remote_dependencies(&self, pull: bool, no_cache: bool) -> Result<(), String> {
+        let compose = self.get_compose_file_command(true)?;
+        let command = ComposeAction::Build { pull, no_cache };
+        run_docker_checked(self.runner.command_runner.as_ref(), &command.to_string(), "failed to build remote dependencies", &compose).map_err(String::from)
     }
 
     /// Runs the dependencies defined.
-    pub fn run_dependencies(&self) {
-        let command_runner = CommandRunner {};
-        let mut command_string = self.get_compose_file_command(false);
-        command_runner.run_docker_command(" up", "failed to run dependencies", &mut command_string);
+    /// 
+    /// # Returns
+    /// * `Result<(), String>` - An error if the docker-compose command could not be put together or did not succeed
+    pub fn run_dependencies(&self) -> Result<(), String> {
+        let compose = self.get_run_command(self.get_compose_files(false)?, false)?;
+        run_docker_checked(self.runner.command_runner.as_ref(), &ComposeAction::Up.to_string(), "failed to run dependencies", &compose).map_err(String::from)
     }
 
     /// Runs the dependencies defined in the background.
-    pub fn run_dependencies_background(&self) {
-        let command_runner = CommandRunner {};
-        let mut command_string = self.get_compose_file_command(false);
-        command_runner.run_docker_command(" up -d", "failed to run dependencies in the background", &mut command_string);
+    /// 
+    /// # Returns
+    /// * `Result<(), String>` - An error if the docker-compose command could not be put together or did not succeed
+    pub fn run_dependencies_background(&self) -> Result<(), String> {
+        let compose = self.get_run_command(self.get_compose_files(false)?, false)?;
+        run_docker_checked(self.runner.command_runner.as_ref(), &ComposeAction::UpDetached.to_string(), "failed to run dependencies in the background", &compose).map_err(String::from)
     }
 
     /// Runs the remote dependencies defined.
-    pub fn run_remote_dependencies(&self) {
-        let command_runner = CommandRunner {};
-        let mut command_string = self.get_compose_file_command(true);
-        command_runner.run_docker_command(" up", "failed to run remote dependencies", &mut command_string);
+    /// 
+    /// # Returns
+    /// * `Result<(), String>` - An error if the docker-compose command could not be put together or did not succeed
+    pub fn run_remote_dependencies(&self) -> Result<(), String> {
+        let compose = self.get_run_command(self.get_compose_files(true)?, true)?;
+        run_docker_checked(self.runner.command_runner.as_ref(), &ComposeAction::Up.to_string(), "failed to run remote dependencies", &compose).map_err(String::from)
     }
 
     /// Runs the remote dependencies defined in the background.
-    pub fn run_remote_dependencies_background(&self) {
-        let command_runner = CommandRunner {};
-        let mut command_string = self.get_compose_file_command(true);
-        command_runner.run_docker_command(" up -d", "failed to run remote dependencies in the background", &mut command_string);
+    /// 
+    /// # Returns
+    /// * `Result<(), String>` - An error if the docker-compose command could not be put together or did not succeed
+    pub fn run_remote_dependencies_background(&self) -> Result<(), String> {
+        let compose = self.get_run_command(self.get_compose_files(true)?, true)?;
+        run_docker_checked(self.runner.command_runner.as_ref(), &ComposeAction::UpDetached.to_string(), "failed to run remote dependencies in the background", &compose).map_err(String::from)
+    }
+
+    /// Prints the logs of the dependency and local invite containers.
+    /// 
+    /// # Arguments
+    /// * `remote` - Whether the remote runner files were used to run the dependencies
+    /// * `follow` - Whether to keep following the logs until interrupted
+    /// * `tail` - The number of lines to print of each container, ```None``` for the default
+    /// * `services` - The services to print the logs of, empty for all of them
+    /// 
+    /// # Returns
+    /// * `Result<i32, String>` - The exit code of docker-compose or an error if a service name is not valid or a
+    ///   runner file does not exist
+    pub fn logs(&self, remote: bool, follow: bool, tail: Option<usize>, services: &[String]) -> Result<i32, String> {
+        let command = ComposeAction::logs(follow, tail, services)?;
+        let command_runner = self.runner.command_runner.as_ref();
+        let compose = self.get_compose_file_command(remote)?;
+        let status = command_runner.run_docker_command(&command.to_string(), "failed to get logs", &compose)
+            .map_err(|error| format!("failed to get logs: {}", error))?;
+        Ok(logs_exit_code(status, follow))
+    }
+
+    /// Gets the state of the dependency and local invite containers.
+    /// 
+    /// # Arguments
+    /// * `remote` - Whether the remote runner files were used to run the dependencies
+    /// 
+    /// # Returns
+    /// * `Result<Vec<ContainerStatus>, String>` - The state of each container with the dependency it belongs to,
+    ///   or an error if a runner file does not exist
+    pub fn ps(&self, remote: bool) -> Result<Vec<ContainerStatus>, String> {
+        let compose_files = self.runner.existing_compose_files(self.get_compose_files(remote)?)?;
+        compose_ps(self.runner.compose_command(), &compose_files, self.runner.command_runner.as_ref(), self.runner.file_handle.as_ref())
     }
 
     /// Runs the dependencies defined in dev mode.
-    pub fn run_dev_dependencies(&self) {
-        let command_runner = CommandRunner {};
-        let mut command_string = self.get_compose_file_command_dev();
-        command_runner.run_docker_command(" up", "failed to run dependencies in dev mode", &mut command_string);
+    /// 
+    /// # Returns
+    /// * `Result<(), String>` - An error if the docker-compose command could not be put together or did not succeed
+    pub fn run_dev_dependencies(&self) -> Result<(), String> {
+        let compose = self.get_run_command(self.get_compose_files_dev()?, false)?;
+        run_docker_checked(self.runner.command_runner.as_ref(), &ComposeAction::Up.to_string(), "failed to run dependencies in dev mode", &compose).map_err(String::from)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::attendee_filter::AttendeeFilter;
+    use crate::dependency::Dependency;
+    use crate::seating_plan::SeatingPlan;
+    use crate::commands::command_runner::MockCoreRunner;
+    use crate::file_handler::MockCoreFileHandle;
+    use crate::dry_run::{DryRunRunner, SharedOutput};
+    use crate::runner_file::RunnerFile;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::{ExitStatus, Output};
+
+    /// Builds a file handle where every compose file exists.
+    fn existing_files_handle() -> MockCoreFileHandle {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_exists().returning(|_| true);
+        mock_handle
+    }
+
+    fn test_dress_rehearsal() -> DressRehearsal {
+        let mut wedding_invite = WeddingInvite::from_file("./tests/test_repo/wedding_invite.yml".to_string()).unwrap();
+        wedding_invite.dev_runner_files = Some(vec![RunnerFile::from("runner_files/dev.yml")]);
+        DressRehearsal {
+            runner: Runner {
+                seating_plan: SeatingPlan {
+                    attendees: vec![
+                        Dependency {
+                            name: "test_repo".to_string(),
+                            url: "https://github.com/yellow-bird-consult/wedding_planner".to_string(),
+                            branch: Some("master".to_string()),
+                            ..Default::default()
+                        }
+                    ],
+                    venue: "./tests".to_string(),
+                    command_defaults: None,
+                    restart_policy: None,
+                    compose_command: None,
+                    engine: None,
+                    max_docker_ops: None,
+                    max_git_ops: None,
+                    clone_depth: None,
+                    auth: None,
+                    git_protocol: None
+                },
+                command_runner: Box::new(MockCoreRunner::new()),
+                file_handle: Box::new(existing_files_handle()),
+                arch: None,
+                filter: AttendeeFilter::All,
+                engine: None,
+                probe_runner: Box::new(MockCoreRunner::new()),
+                compose: std::sync::OnceLock::from("docker-compose".to_string()),
+                skip_missing: false,
+                force_recreate: false,
+                git_available: std::sync::OnceLock::from(true)
+            },
+            wedding_invite,
+            working_directory: "/work".to_string()
+        }
+    }
+
+    #[test]
+    fn test_get_compose_files() {
+        let dress_rehearsal = test_dress_rehearsal();
+        assert_eq!(
+            dress_rehearsal.get_compose_files(false).unwrap(),
+            vec![
+                ComposeFileRef::new("test_repo", "tests/test_repo/runner_files/base.yml".to_string()),
+                ComposeFileRef::new("test_repo", "tests/test_repo/runner_files/database.yml".to_string()),
+                ComposeFileRef::new("local", "/work/runner_files/base.yml".to_string()),
+                ComposeFileRef::new("local", "/work/runner_files/database.yml".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_with_filter() {
+        let mut dress_rehearsal = test_dress_rehearsal();
+        dress_rehearsal.runner.seating_plan.attendees.push(Dependency {
+            name: "valid_repo".to_string(),
+            url: "https://github.com/yellow-bird-consult/valid_repo".to_string(),
+            branch: Some("main".to_string()),
+            ..Default::default()
+        });
+        let dress_rehearsal = dress_rehearsal.with_filter(AttendeeFilter::Only(vec!["valid_repo".to_string()])).unwrap();
+        assert_eq!(
+            dress_rehearsal.get_compose_file_command(false).unwrap().to_string(),
+            "docker-compose -f tests/valid_repo/runner_files/base.yml -f /work/runner_files/base.yml -f /work/runner_files/database.yml"
+        );
+
+        let error = test_dress_rehearsal().with_filter(AttendeeFilter::Only(vec!["auth".to_string()])).err().unwrap();
+        assert!(matches!(&error, WedpError::InvalidConfig(message) if message == "auth not in the seating plan, valid names are test_repo"));
+    }
+
+    #[test]
+    fn test_get_compose_files_dev() {
+        let dress_rehearsal = test_dress_rehearsal();
+        assert_eq!(
+            dress_rehearsal.get_compose_files_dev().unwrap(),
+            vec![
+                ComposeFileRef::new("test_repo", "tests/test_repo/runner_files/base.yml".to_string()),
+                ComposeFileRef::new("test_repo", "tests/test_repo/runner_files/database.yml".to_string()),
+                ComposeFileRef::new("local", "/work/runner_files/dev.yml".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dry_run_run_dependencies() {
+        let output = SharedOutput::default();
+        let mut dress_rehearsal = test_dress_rehearsal();
+        dress_rehearsal.runner.command_runner = Box::new(DryRunRunner::new(Box::new(output.clone())));
+
+        dress_rehearsal.run_dependencies_background().unwrap();
+        dress_rehearsal.run_dev_dependencies().unwrap();
+
+        assert_eq!(
+            output.contents(),
+            "DRY-RUN: docker-compose -f tests/test_repo/runner_files/base.yml -f tests/test_repo/runner_files/database.yml \
+             -f /work/runner_files/base.yml -f /work/runner_files/database.yml up -d\n\
+             DRY-RUN: docker-compose -f tests/test_repo/runner_files/base.yml -f tests/test_repo/runner_files/database.yml \
+             -f /work/runner_files/dev.yml up\n"
+        );
+    }
+
+    #[test]
+    fn test_dry_run_build_dependencies() {
+        let output = SharedOutput::default();
+        let mut dress_rehearsal = test_dress_rehearsal();
+        dress_rehearsal.runner.command_runner = Box::new(DryRunRunner::new(Box::new(output.clone())));
+
+        dress_rehearsal.build_dependencies(false, false).unwrap();
+        dress_rehearsal.build_dependencies(false, true).unwrap();
+        dress_rehearsal.build_dependencies(true, true).unwrap();
+
+        let compose = "docker-compose -f tests/test_repo/runner_files/base.yml -f tests/test_repo/runner_files/database.yml \
+                       -f /work/runner_files/base.yml -f /work/runner_files/database.yml";
+        assert_eq!(
+            output.contents(),
+            format!(
+                "DRY-RUN: {compose} build\nDRY-RUN: {compose} build --no-cache\nDRY-RUN: {compose} build --pull --no-cache\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_dry_run_logs() {
+        let output = SharedOutput::default();
+        let mut dress_rehearsal = test_dress_rehearsal();
+        dress_rehearsal.runner.command_runner = Box::new(DryRunRunner::new(Box::new(output.clone())));
+
+        assert_eq!(dress_rehearsal.logs(false, true, None, &["auth_db".to_string()]), Ok(0));
+        assert!(dress_rehearsal.logs(false, false, None, &["auth db".to_string()]).is_err());
+
+        assert_eq!(
+            output.contents(),
+            "DRY-RUN: docker-compose -f tests/test_repo/runner_files/base.yml -f tests/test_repo/runner_files/database.yml \
+             -f /work/runner_files/base.yml -f /work/runner_files/database.yml logs -f --tail=100 auth_db\n"
+        );
+    }
+
+    #[test]
+    fn test_ps_includes_local_invite() {
+        let mut dress_rehearsal = test_dress_rehearsal();
+        let mut mock_handle = existing_files_handle();
+        mock_handle.expect_read_to_string()
+            .returning(|path| match path.starts_with("/work") {
+                true => Ok("services:\n  api:\n    build: .\n".to_string()),
+                false => Ok("services:\n  test_db:\n    image: postgres\n".to_string())
+            });
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .withf(|_, _, args| args.ends_with(&["ps", "--format", "json"]))
+            .returning(|_, _, _| Ok(Output {
+                status: ExitStatus::from_raw(0),
+                stdout: b"{\"Service\":\"api\",\"State\":\"running\"}\n{\"Service\":\"test_db\",\"State\":\"running\"}\n".to_vec(),
+                stderr: Vec::new()
+            }));
+        dress_rehearsal.runner.command_runner = Box::new(mock_runner);
+        dress_rehearsal.runner.file_handle = Box::new(mock_handle);
+
+        let statuses = dress_rehearsal.ps(false).unwrap();
+        let attendees: Vec<(&str, &str)> = statuses.iter().map(|status| (status.service.as_str(), status.attendee.as_str())).collect();
+        assert_eq!(attendees, vec![("test_db", "test_repo"), ("api", "local")]);
+    }
+
+    #[test]
+    fn test_factory_unknown_command() {
+        let result = dress_rehearsal_factory(
+            "rehearse".to_string(),
+            DressOptions::default(),
+            vec!["./tests/seating_plan.yml".to_string()],
+            "./tests/test_repo/wedding_invite.yml".to_string(),
+            "./tests/test_repo".to_string()
+        );
+        assert_eq!(result, Err(DressRehearsalError::UnknownCommand("rehearse".to_string())));
+        assert_eq!(result.unwrap_err().to_string(), "rehearse not supported");
+    }
+
+    #[test]
+    fn test_factory_config_failure() {
+        let options = DressOptions { dry_run: true, ..Default::default() };
+        let result = dress_rehearsal_factory(
+            "build".to_string(),
+            options,
+            vec!["./tests/missing_seating_plan.yml".to_string()],
+            "./tests/test_repo/wedding_invite.yml".to_string(),
+            "./tests/test_repo".to_string()
+        );
+        match result {
+            Err(DressRehearsalError::Config(message)) => {
+                assert!(message.ends_with(
+                    "for seating plan path: ./tests/missing_seating_plan.yml wedding invite path: \
+                     ./tests/test_repo/wedding_invite.yml working dir ./tests/test_repo"
+                ));
+            },
+            other => panic!("expected a config error, got {:?}", other)
+        }
     }
 }
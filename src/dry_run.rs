@@ -0,0 +1,268 @@
+//! The ```--dry-run``` implementations of the command runner and the file handle. Every git and docker command
+//! and every file operation that would change the file system is printed prefixed with ```DRY-RUN:``` instead
+//! of being run. Reading files still goes to the file system so the commands printed match a real run.
+use std::fs;
+use std::io::Write;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Output};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::commands::command_runner::{render_in_dir, CoreRunner, ProgramArgs};
+use crate::commands::trace::{redact, secrets};
+use crate::file_handler::{walk_dir_size, CoreFileHandle};
+
+
+/// The prefix of every line printed by a dry run.
+pub const DRY_RUN_PREFIX: &str = "DRY-RUN:";
+
+
+/// Writes a line of dry run output with the secrets redacted.
+///
+/// # Arguments
+/// * `output` - Where the dry run output is written to
+/// * `line` - The command or file operation that would have been run
+fn print_dry_run(output: &Mutex<Box<dyn Write + Send>>, line: &str) {
+    if let Ok(mut output) = output.lock() {
+        let _ = writeln!(output, "{} {}", DRY_RUN_PREFIX, redact(line, &secrets()));
+    }
+}
+
+
+/// A command runner that prints the commands instead of running them.
+///
+/// # Fields
+/// * `output` - Where the commands are printed to
+pub struct DryRunRunner {
+    pub output: Mutex<Box<dyn Write + Send>>,
+}
+
+impl DryRunRunner {
+
+    /// Creates a new DryRunRunner struct.
+    ///
+    /// # Arguments
+    /// * `output` - Where the commands are printed to
+    ///
+    /// # Returns
+    /// A new DryRunRunner struct
+    pub fn new(output: Box<dyn Write + Send>) -> Self {
+        DryRunRunner { output: Mutex::new(output) }
+    }
+
+    /// Creates a new DryRunRunner struct that prints to stdout.
+    ///
+    /// # Returns
+    /// A new DryRunRunner struct
+    pub fn stdout() -> Self {
+        DryRunRunner::new(Box::new(std::io::stdout()))
+    }
+}
+
+impl CoreRunner for DryRunRunner {
+
+    fn run(&self, command: &String) -> Result<Output, std::io::Error> {
+        print_dry_run(&self.output, command);
+        Ok(Output {
+            status: ExitStatus::from_raw(0),
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+        })
+    }
+
+    fn run_in_dir(&self, dir: &Path, program: &str, args: &[&str]) -> Result<Output, std::io::Error> {
+        self.run(&render_in_dir(dir, program, args))
+    }
+
+    fn run_in_dir_with_env(&self, dir: &Path, program: &str, args: &[&str], _env: &[(String, String)]) -> Result<Output, std::io::Error> {
+        self.run_in_dir(dir, program, args)
+    }
+
+    fn run_docker_command(&self, command: &str, _error_message: &str, program_args: &ProgramArgs) -> Result<ExitStatus, std::io::Error> {
+        print_dry_run(&self.output, &program_args.with_command(command).to_string());
+        Ok(ExitStatus::from_raw(0))
+    }
+
+    fn run_interactive(&self, command: &str) -> Result<ExitStatus, std::io::Error> {
+        print_dry_run(&self.output, command);
+        Ok(ExitStatus::from_raw(0))
+    }
+}
+
+
+/// A file handle that prints the file operations that would change the file system instead of performing them.
+///
+/// # Fields
+/// * `output` - Where the file operations are printed to
+pub struct DryRunFileHandle {
+    pub output: Mutex<Box<dyn Write + Send>>,
+}
+
+impl DryRunFileHandle {
+
+    /// Creates a new DryRunFileHandle struct.
+    ///
+    /// # Arguments
+    /// * `output` - Where the file operations are printed to
+    ///
+    /// # Returns
+    /// A new DryRunFileHandle struct
+    pub fn new(output: Box<dyn Write + Send>) -> Self {
+        DryRunFileHandle { output: Mutex::new(output) }
+    }
+
+    /// Creates a new DryRunFileHandle struct that prints to stdout.
+    ///
+    /// # Returns
+    /// A new DryRunFileHandle struct
+    pub fn stdout() -> Self {
+        DryRunFileHandle::new(Box::new(std::io::stdout()))
+    }
+}
+
+impl CoreFileHandle for DryRunFileHandle {
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<u64, std::io::Error> {
+        print_dry_run(&self.output, &format!("cp {} {}", from.to_string_lossy(), to.to_string_lossy()));
+        Ok(0)
+    }
+
+    fn remove(&self, path: &Path) -> Result<(), std::io::Error> {
+        print_dry_run(&self.output, &format!("rm {}", path.to_string_lossy()));
+        Ok(())
+    }
+
+    fn remove_directory(&self, path: &Path) -> Result<(), std::io::Error> {
+        print_dry_run(&self.output, &format!("rm -r {}", path.to_string_lossy()));
+        Ok(())
+    }
+
+    fn create_directory_if_not_exists(&self, path: &Path) -> Result<(), std::io::Error> {
+        print_dry_run(&self.output, &format!("mkdir -p {}", path.to_string_lossy()));
+        Ok(())
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String, std::io::Error> {
+        fs::read_to_string(path)
+    }
+
+    fn write(&self, path: &Path, _contents: &str) -> Result<(), std::io::Error> {
+        print_dry_run(&self.output, &format!("write {}", path.to_string_lossy()));
+        Ok(())
+    }
+
+    fn append(&self, path: &Path, _contents: &str) -> Result<(), std::io::Error> {
+        print_dry_run(&self.output, &format!("append {}", path.to_string_lossy()));
+        Ok(())
+    }
+
+    fn modified(&self, path: &Path) -> Result<SystemTime, std::io::Error> {
+        fs::metadata(path)?.modified()
+    }
+
+    /// Treats every path as existing so a dry run prints the full docker commands even before the 
+    /// dependencies are installed.
+    fn exists(&self, _path: &Path) -> bool {
+        true
+    }
+
+    fn exists_no_follow(&self, path: &Path) -> bool {
+        fs::symlink_metadata(path).is_ok()
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf, std::io::Error> {
+        fs::read_link(path)
+    }
+
+    fn dir_size(&self, path: &Path) -> Result<u64, std::io::Error> {
+        walk_dir_size(path)
+    }
+
+    fn symlink(&self, target: &Path, link: &Path) -> Result<(), std::io::Error> {
+        print_dry_run(&self.output, &format!("ln -s {} {}", target.to_string_lossy(), link.to_string_lossy()));
+        Ok(())
+    }
+
+    fn copy_directory(&self, from: &Path, to: &Path) -> Result<(), std::io::Error> {
+        print_dry_run(&self.output, &format!("cp -R {} {}", from.to_string_lossy(), to.to_string_lossy()));
+        Ok(())
+    }
+}
+
+
+/// A writer that can be shared with a dry run runner or file handle so tests can read what was printed.
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub struct SharedOutput(pub std::sync::Arc<Mutex<Vec<u8>>>);
+
+#[cfg(test)]
+impl SharedOutput {
+    pub fn contents(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+#[cfg(test)]
+impl Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_run() {
+        let output = SharedOutput::default();
+        let runner = DryRunRunner::new(Box::new(output.clone()));
+
+        let result = runner.run(&"cd venue && git clone git@github.com:org/repo.git".to_string()).unwrap();
+
+        assert!(result.status.success());
+        assert_eq!(output.contents(), "DRY-RUN: cd venue && git clone git@github.com:org/repo.git\n");
+    }
+
+    #[test]
+    fn test_run_docker_command() {
+        let output = SharedOutput::default();
+        let runner = DryRunRunner::new(Box::new(output.clone()));
+        let compose = ProgramArgs::new("docker-compose", ["-f", "a.yml", "-f", "b.yml"].map(String::from).to_vec());
+
+        let status = runner.run_docker_command(" up -d", "failed to run", &compose).unwrap();
+
+        assert!(status.success());
+        assert_eq!(output.contents(), "DRY-RUN: docker-compose -f a.yml -f b.yml up -d\n");
+    }
+
+    #[test]
+    fn test_file_handle() {
+        let output = SharedOutput::default();
+        let handle = DryRunFileHandle::new(Box::new(output.clone()));
+
+        handle.copy(Path::new("/venue/auth/builds/Dockerfile.x86_64"), Path::new("/venue/auth/Dockerfile")).unwrap();
+        handle.remove(Path::new("/venue/auth/Dockerfile")).unwrap();
+        handle.remove_directory(Path::new("/venue/auth")).unwrap();
+        handle.create_directory_if_not_exists(Path::new("/venue")).unwrap();
+        handle.write(Path::new("/cache/plan.json"), "{}").unwrap();
+
+        assert_eq!(
+            output.contents(),
+            "DRY-RUN: cp /venue/auth/builds/Dockerfile.x86_64 /venue/auth/Dockerfile\n\
+             DRY-RUN: rm /venue/auth/Dockerfile\n\
+             DRY-RUN: rm -r /venue/auth\n\
+             DRY-RUN: mkdir -p /venue\n\
+             DRY-RUN: write /cache/plan.json\n"
+        );
+        assert!(!Path::new("/venue").exists());
+    }
+}
@@ -0,0 +1,224 @@
+//! Opens the wedding invite of an installed dependency in the editor of the user and checks it once the editor
+//! exits. If the wedding invite no longer parses or fails validation the problems are printed and the user is
+//! asked whether to open the editor again so the invite can be fixed straight away.
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use crate::commands::command_runner::CoreRunner;
+use crate::compose_file::shell_quote;
+use crate::wedding_invite::WeddingInvite;
+
+
+/// Picks the editor to open the wedding invite with, ```$VISUAL``` first and then ```$EDITOR```.
+///
+/// # Arguments
+/// * `visual` - The value of ```$VISUAL```
+/// * `editor` - The value of ```$EDITOR```
+///
+/// # Returns
+/// * `Result<String, String>` - The editor command or an error if neither variable is set
+pub fn resolve_editor(visual: Option<String>, editor: Option<String>) -> Result<String, String> {
+    [visual, editor].into_iter()
+        .flatten()
+        .map(|value| value.trim().to_string())
+        .find(|value| !value.is_empty())
+        .ok_or_else(|| "Neither $VISUAL nor $EDITOR is set, set one of them to the editor to use with wedp edit".to_string())
+}
+
+
+/// Checks that wedp is attached to a terminal the editor can take over.
+///
+/// # Arguments
+/// * `interactive` - Whether stdin and stdout are terminals
+/// * `path` - The path to the wedding invite being edited
+///
+/// # Returns
+/// * `Result<(), String>` - An error naming the file to edit directly if there is no terminal
+pub fn check_interactive(interactive: bool, path: &Path) -> Result<(), String> {
+    match interactive {
+        true => Ok(()),
+        false => Err(format!(
+            "wedp edit needs an interactive terminal to open an editor, edit {} directly and run wedp validate instead",
+            path.to_string_lossy()
+        ))
+    }
+}
+
+
+/// Parses and validates a wedding invite.
+///
+/// # Arguments
+/// * `path` - The path to the ```wedding_invite.yml```
+///
+/// # Returns
+/// * `Vec<String>` - A message for every problem found, empty if the wedding invite is valid
+pub fn check_wedding_invite(path: &Path) -> Vec<String> {
+    let wedding_invite = match WeddingInvite::from_file(path.to_string_lossy().to_string()) {
+        Ok(wedding_invite) => wedding_invite,
+        Err(error) => return vec![error.to_string()]
+    };
+    match path.parent() {
+        Some(invite_directory) => wedding_invite.validate(invite_directory),
+        None => Vec::new()
+    }
+}
+
+
+/// Asks the user whether to open the editor again, anything but ```n``` or ```no``` opens it again.
+///
+/// # Arguments
+/// * `input` - Where the answer is read from
+/// * `output` - Where the question is written to
+///
+/// # Returns
+/// * `bool` - Whether to open the editor again, false if the input has ended
+pub fn ask_to_reopen(input: &mut dyn BufRead, output: &mut dyn Write) -> bool {
+    let _ = write!(output, "Open the wedding invite again to fix it? [Y/n] ");
+    let _ = output.flush();
+    let mut answer = String::new();
+    match input.read_line(&mut answer) {
+        Ok(0) | Err(_) => {
+            let _ = writeln!(output);
+            false
+        },
+        Ok(_) => !matches!(answer.trim().to_lowercase().as_str(), "n" | "no")
+    }
+}
+
+
+/// Opens the wedding invite in the editor until it is valid or the user stops editing.
+///
+/// # Arguments
+/// * `path` - The path to the ```wedding_invite.yml```
+/// * `editor` - The editor command from ```resolve_editor```
+/// * `runner` - The command runner used to start the editor
+/// * `reopen` - Asks whether to open the editor again after problems were found
+///
+/// # Returns
+/// * `Result<(), String>` - An error if the editor failed or the wedding invite was left with problems
+pub fn edit_wedding_invite(path: &Path, editor: &str, runner: &dyn CoreRunner, reopen: &mut dyn FnMut() -> bool) -> Result<(), String> {
+    let command = format!("{} {}", editor, shell_quote(&path.to_string_lossy()));
+    loop {
+        let status = runner.run_interactive(&command)
+                           .map_err(|error| format!("Failed to start {}: {}", editor, error))?;
+        if !status.success() {
+            return Err(format!("{} exited with {}, the wedding invite was not checked", editor, status))
+        }
+        let problems = check_wedding_invite(path);
+        if problems.is_empty() {
+            println!("{} is valid", path.to_string_lossy());
+            return Ok(())
+        }
+        for problem in &problems {
+            println!("{}: {}", path.to_string_lossy(), problem);
+        }
+        if !reopen() {
+            return Err(format!("{} has {} problem(s)", path.to_string_lossy(), problems.len()))
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::commands::command_runner::MockCoreRunner;
+    use mockall::predicate::eq;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+    use std::sync::{Arc, Mutex};
+
+    fn fixture(name: &str, contents: &str) -> std::path::PathBuf {
+        let directory = std::env::temp_dir().join("wedp_edit_tests").join(name);
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("wedding_invite.yml");
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    /// A runner standing in for the editor, each run writes the next version of the file.
+    fn scripted_editor(path: std::path::PathBuf, versions: Vec<&'static str>) -> MockCoreRunner {
+        let versions = Arc::new(Mutex::new(versions.into_iter()));
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_interactive()
+            .with(eq(format!("vim {}", path.to_string_lossy())))
+            .returning(move |_| {
+                let contents = versions.lock().unwrap().next().expect("the editor was opened too many times");
+                std::fs::write(&path, contents).unwrap();
+                Ok(ExitStatus::from_raw(0))
+            });
+        mock_runner
+    }
+
+    #[test]
+    fn test_resolve_editor() {
+        assert_eq!(resolve_editor(Some("code --wait".to_string()), Some("vim".to_string())), Ok("code --wait".to_string()));
+        assert_eq!(resolve_editor(Some(" ".to_string()), Some("vim".to_string())), Ok("vim".to_string()));
+        assert_eq!(resolve_editor(None, Some("nano".to_string())), Ok("nano".to_string()));
+        assert!(resolve_editor(None, None).unwrap_err().starts_with("Neither $VISUAL nor $EDITOR is set"));
+    }
+
+    #[test]
+    fn test_check_interactive() {
+        assert!(check_interactive(true, Path::new("venue/auth/wedding_invite.yml")).is_ok());
+        assert_eq!(
+            check_interactive(false, Path::new("venue/auth/wedding_invite.yml")),
+            Err("wedp edit needs an interactive terminal to open an editor, edit venue/auth/wedding_invite.yml \
+                 directly and run wedp validate instead".to_string())
+        );
+    }
+
+    #[test]
+    fn test_ask_to_reopen() {
+        let mut output = Vec::new();
+        assert!(ask_to_reopen(&mut "\n".as_bytes(), &mut output));
+        assert!(ask_to_reopen(&mut "y\n".as_bytes(), &mut output));
+        assert!(!ask_to_reopen(&mut "No\n".as_bytes(), &mut output));
+        assert!(!ask_to_reopen(&mut "".as_bytes(), &mut output));
+        assert!(String::from_utf8(output).unwrap().starts_with("Open the wedding invite again to fix it? [Y/n] "));
+    }
+
+    #[test]
+    fn test_edit_valid_first_time() {
+        let path = fixture("valid", "");
+        let mock_runner = scripted_editor(path.clone(), vec!["build_root: \".\"\nrunner_files: []\n"]);
+
+        let result = edit_wedding_invite(&path, "vim", &mock_runner, &mut || panic!("should not ask to reopen"));
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn test_edit_reopens_until_valid() {
+        let path = fixture("reopen", "");
+        let mock_runner = scripted_editor(path.clone(), vec![
+            "build_root: [\n",
+            "build_root: missing_directory\nrunner_files: []\n",
+            "build_root: \".\"\nrunner_files: []\n",
+        ]);
+        let mut asked = 0;
+
+        let result = edit_wedding_invite(&path, "vim", &mock_runner, &mut || { asked += 1; true });
+        assert_eq!(result, Ok(()));
+        assert_eq!(asked, 2);
+    }
+
+    #[test]
+    fn test_edit_gives_up() {
+        let path = fixture("give_up", "");
+        let mock_runner = scripted_editor(path.clone(), vec!["build_root: missing_directory\nrunner_files: []\n"]);
+
+        let result = edit_wedding_invite(&path, "vim", &mock_runner, &mut || false);
+        assert_eq!(result, Err(format!("{} has 1 problem(s)", path.to_string_lossy())));
+        assert_eq!(check_wedding_invite(&path), vec!["build_root missing_directory is not a directory".to_string()]);
+    }
+
+    #[test]
+    fn test_edit_editor_fails() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_interactive().returning(|_| Ok(ExitStatus::from_raw(1 << 8)));
+
+        let result = edit_wedding_invite(Path::new("/missing/wedding_invite.yml"), "vim", &mock_runner, &mut || true);
+        assert_eq!(result, Err("vim exited with exit status: 1, the wedding invite was not checked".to_string()));
+    }
+}
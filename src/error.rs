@@ -0,0 +1,181 @@
+//! The errors of wedp. Keeping the kind of error lets callers tell a missing file apart from a file that could
+//! not be parsed or a git or docker command that failed, while the ```Display``` of each error is the message
+//! printed to the user and ```exit_code``` is the code wedp exits with.
+use std::io;
+use thiserror::Error;
+
+
+/// The exit code for a git or docker command that failed, or any other error.
+pub const EXIT_FAILURE: i32 = 1;
+
+/// The exit code for a file that holds a value wedp does not accept, following ```EX_DATAERR``` of sysexits.
+pub const EXIT_INVALID_CONFIG: i32 = 65;
+
+/// The exit code for a file that does not exist, following ```EX_NOINPUT``` of sysexits.
+pub const EXIT_NOT_FOUND: i32 = 66;
+
+/// The exit code for a file that exists but could not be read, following ```EX_IOERR``` of sysexits.
+pub const EXIT_IO: i32 = 74;
+
+
+/// The ways wedp can fail.
+///
+/// # Fields
+/// * `Io` - A file could not be read, with the path and the underlying error
+/// * `ConfigNotFound` - A file that is needed does not exist, with its path
+/// * `ConfigParse` - A file is not valid YAML for what it holds, with the path and the parser message
+/// * `InvalidConfig` - A file was parsed but holds a value wedp does not accept, with the message
+/// * `MissingBuildFile` - The wedding invite of a dependency has no Dockerfile for the CPU type and no default
+/// * `UnsupportedCpu` - The CPU type is not one wedp can build for, with the message
+/// * `GitCommand` - A git command failed, with the command and its stderr
+/// * `DockerCommand` - A docker command could not be run or exited unsuccessfully, with what it was doing, the
+///   command, and why it failed
+/// * `Failed` - A step that still reports its error as a message failed, with the message
+#[derive(Debug, Error)]
+pub enum WedpError {
+    #[error("Could not open file: {source} for {path}")]
+    Io { path: String, source: io::Error },
+    #[error("{0} does not exist")]
+    ConfigNotFound(String),
+    #[error("Could not parse file: {message} for {path}")]
+    ConfigParse { path: String, message: String },
+    #[error("{0}")]
+    InvalidConfig(String),
+    #[error("No build file for CPU type: {cpu_type} and no default in the wedding invite of {dependency}")]
+    MissingBuildFile { cpu_type: String, dependency: String },
+    #[error("{0}")]
+    UnsupportedCpu(String),
+    #[error("{command} failed: {stderr}")]
+    GitCommand { command: String, stderr: String },
+    #[error("{message}: {reason}")]
+    DockerCommand { message: String, command: String, reason: String },
+    #[error("{0}")]
+    Failed(String),
+}
+
+impl WedpError {
+
+    /// Turns the error reading a file into ```ConfigNotFound``` if the file does not exist and ```Io``` otherwise.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the file that was read
+    /// * `source` - The error reading the file
+    ///
+    /// # Returns
+    /// * `WedpError` - The error for the file
+    pub fn from_read(path: String, source: io::Error) -> Self {
+        match source.kind() {
+            io::ErrorKind::NotFound => WedpError::ConfigNotFound(path),
+            _ => WedpError::Io { path, source }
+        }
+    }
+
+    /// Gets the code wedp exits with for the error, so scripts can tell a missing or invalid config apart from
+    /// a command that failed.
+    ///
+    /// # Returns
+    /// * `i32` - The exit code
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            WedpError::ConfigNotFound(_) => EXIT_NOT_FOUND,
+            WedpError::Io { .. } => EXIT_IO,
+            WedpError::ConfigParse { .. } | WedpError::InvalidConfig(_) | WedpError::MissingBuildFile { .. }
+                | WedpError::UnsupportedCpu(_) => EXIT_INVALID_CONFIG,
+            WedpError::GitCommand { .. } | WedpError::DockerCommand { .. } | WedpError::Failed(_) => EXIT_FAILURE
+        }
+    }
+}
+
+/// Lets the functions that still return ```Result<_, String>``` pass the error on with ```?```.
+impl From<WedpError> for String {
+    fn from(error: WedpError) -> Self {
+        error.to_string()
+    }
+}
+
+/// Lets the file operations that return ```io::Result``` pass the error on with ```?```, keeping the kind of
+/// the underlying IO error.
+impl From<WedpError> for io::Error {
+    fn from(error: WedpError) -> Self {
+        match error {
+            WedpError::Io { source, .. } => source,
+            WedpError::ConfigNotFound(_) => io::Error::new(io::ErrorKind::NotFound, error.to_string()),
+            WedpError::UnsupportedCpu(_) => io::Error::new(io::ErrorKind::Unsupported, error.to_string()),
+            _ => io::Error::other(error.to_string())
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn test_display() {
+        let error = WedpError::Io {
+            path: "plan.yml".to_string(),
+            source: io::Error::new(io::ErrorKind::PermissionDenied, "Permission denied")
+        };
+        assert_eq!(error.to_string(), "Could not open file: Permission denied for plan.yml");
+        assert!(error.source().is_some());
+
+        let error = WedpError::ConfigParse { path: "plan.yml".to_string(), message: "missing field `venue`".to_string() };
+        assert_eq!(error.to_string(), "Could not parse file: missing field `venue` for plan.yml");
+        assert!(error.source().is_none());
+
+        assert_eq!(WedpError::ConfigNotFound("venue/auth/wedding_invite.yml".to_string()).to_string(), "venue/auth/wedding_invite.yml does not exist");
+        assert_eq!(
+            WedpError::MissingBuildFile { cpu_type: "aarch64".to_string(), dependency: "auth".to_string() }.to_string(),
+            "No build file for CPU type: aarch64 and no default in the wedding invite of auth"
+        );
+        assert_eq!(
+            WedpError::GitCommand { command: "git fetch".to_string(), stderr: "fatal: not a git repository".to_string() }.to_string(),
+            "git fetch failed: fatal: not a git repository"
+        );
+        assert_eq!(
+            WedpError::DockerCommand {
+                message: "Failed to build dependencies".to_string(),
+                command: "docker-compose build".to_string(),
+                reason: "docker-compose build exited with exit status: 2".to_string()
+            }.to_string(),
+            "Failed to build dependencies: docker-compose build exited with exit status: 2"
+        );
+    }
+
+    #[test]
+    fn test_from_read() {
+        let error = WedpError::from_read("plan.yml".to_string(), io::Error::new(io::ErrorKind::NotFound, "No such file or directory"));
+        assert!(matches!(&error, WedpError::ConfigNotFound(path) if path == "plan.yml"));
+        let error = WedpError::from_read("plan.yml".to_string(), io::Error::new(io::ErrorKind::PermissionDenied, "denied"));
+        assert!(matches!(&error, WedpError::Io { source, .. } if source.kind() == io::ErrorKind::PermissionDenied));
+    }
+
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(WedpError::ConfigNotFound("plan.yml".to_string()).exit_code(), EXIT_NOT_FOUND);
+        assert_eq!(WedpError::from_read("plan.yml".to_string(), io::Error::other("denied")).exit_code(), EXIT_IO);
+        assert_eq!(WedpError::ConfigParse { path: "plan.yml".to_string(), message: String::new() }.exit_code(), EXIT_INVALID_CONFIG);
+        assert_eq!(WedpError::InvalidConfig("bad".to_string()).exit_code(), EXIT_INVALID_CONFIG);
+        assert_eq!(WedpError::GitCommand { command: "git pull".to_string(), stderr: String::new() }.exit_code(), EXIT_FAILURE);
+        assert_eq!(WedpError::Failed("clone failed".to_string()).exit_code(), EXIT_FAILURE);
+    }
+
+    #[test]
+    fn test_into_io_error() {
+        let error: io::Error = WedpError::Io {
+            path: "plan.yml".to_string(),
+            source: io::Error::new(io::ErrorKind::PermissionDenied, "denied")
+        }.into();
+        assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+
+        let error: io::Error = WedpError::ConfigNotFound("plan.yml".to_string()).into();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        let error: io::Error = WedpError::UnsupportedCpu("sparc is not supported".to_string()).into();
+        assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+        let error: io::Error = WedpError::MissingBuildFile { cpu_type: "aarch64".to_string(), dependency: "auth".to_string() }.into();
+        assert_eq!(error.to_string(), "No build file for CPU type: aarch64 and no default in the wedding invite of auth");
+    }
+}
@@ -1,18 +1,43 @@
 //! the file handler is for managing the interface for basic file operations.
 use std::fs;
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 
-/// The core file handle trait for actions on files.
+/// The core file handle trait for actions on files. File handles are shared between the threads installing
+/// the dependencies so they must be ```Send``` and ```Sync```.
 #[mockall::automock]
-pub trait CoreFileHandle {
+pub trait CoreFileHandle: Send + Sync {
 
     fn copy(&self, from: &Path, to: &Path) -> Result<u64, std::io::Error>;
 
     fn remove(&self, path: &Path) -> Result<(), std::io::Error>;
 
+    fn remove_directory(&self, path: &Path) -> Result<(), std::io::Error>;
+
     fn create_directory_if_not_exists(&self, path: &Path) -> Result<(), std::io::Error>;
 
+    fn read_to_string(&self, path: &Path) -> Result<String, std::io::Error>;
+
+    fn write(&self, path: &Path, contents: &str) -> Result<(), std::io::Error>;
+
+    fn append(&self, path: &Path, contents: &str) -> Result<(), std::io::Error>;
+
+    fn modified(&self, path: &Path) -> Result<SystemTime, std::io::Error>;
+
+    fn exists(&self, path: &Path) -> bool;
+
+    fn exists_no_follow(&self, path: &Path) -> bool;
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf, std::io::Error>;
+
+    fn dir_size(&self, path: &Path) -> Result<u64, std::io::Error>;
+
+    fn symlink(&self, target: &Path, link: &Path) -> Result<(), std::io::Error>;
+
+    fn copy_directory(&self, from: &Path, to: &Path) -> Result<(), std::io::Error>;
+
 }
 
 
@@ -44,6 +69,17 @@ impl CoreFileHandle for FileHandle {
         fs::remove_file(path)
     }
 
+    /// Removes a directory and everything in it from the file system.
+    /// 
+    /// # Arguments
+    /// * `path` - The path to the directory to remove
+    /// 
+    /// # Returns
+    /// * `Result<(), std::io::Error>` - An error if the directory could not be removed
+    fn remove_directory(&self, path: &Path) -> Result<(), std::io::Error> {
+        fs::remove_dir_all(path)
+    }
+
     /// Creates a directory if it does not already exist.
     /// 
     /// # Arguments
@@ -58,5 +94,167 @@ impl CoreFileHandle for FileHandle {
         Ok(())
     }
 
+    /// Reads the contents of a file into a string.
+    /// 
+    /// # Arguments
+    /// * `path` - The path to the file to read
+    /// 
+    /// # Returns
+    /// * `Result<String, std::io::Error>` - The contents of the file or an error
+    fn read_to_string(&self, path: &Path) -> Result<String, std::io::Error> {
+        fs::read_to_string(path)
+    }
+
+    /// Writes a string to a file, replacing the file if it already exists.
+    /// 
+    /// # Arguments
+    /// * `path` - The path to the file to write
+    /// * `contents` - The contents to write to the file
+    /// 
+    /// # Returns
+    /// * `Result<(), std::io::Error>` - An error if the file could not be written
+    fn write(&self, path: &Path, contents: &str) -> Result<(), std::io::Error> {
+        fs::write(path, contents)
+    }
+
+    /// Appends a string to a file, creating the file if it does not exist.
+    /// 
+    /// # Arguments
+    /// * `path` - The path to the file to append to
+    /// * `contents` - The contents to add to the end of the file
+    /// 
+    /// # Returns
+    /// * `Result<(), std::io::Error>` - An error if the file could not be written
+    fn append(&self, path: &Path, contents: &str) -> Result<(), std::io::Error> {
+        fs::OpenOptions::new().create(true).append(true).open(path)?.write_all(contents.as_bytes())
+    }
+
+    /// Gets the last modification time of a file.
+    /// 
+    /// # Arguments
+    /// * `path` - The path to the file
+    /// 
+    /// # Returns
+    /// * `Result<SystemTime, std::io::Error>` - The last modification time or an error
+    fn modified(&self, path: &Path) -> Result<SystemTime, std::io::Error> {
+        fs::metadata(path)?.modified()
+    }
+
+    /// Checks whether a file or directory exists.
+    /// 
+    /// # Arguments
+    /// * `path` - The path to check
+    /// 
+    /// # Returns
+    /// * `bool` - Whether something exists at the path
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    /// Checks whether a file, directory, or link exists without following a link, so a broken link exists.
+    /// 
+    /// # Arguments
+    /// * `path` - The path to check
+    /// 
+    /// # Returns
+    /// * `bool` - Whether something exists at the path
+    fn exists_no_follow(&self, path: &Path) -> bool {
+        fs::symlink_metadata(path).is_ok()
+    }
+
+    /// Reads where a symbolic link points to.
+    /// 
+    /// # Arguments
+    /// * `path` - The path to the link
+    /// 
+    /// # Returns
+    /// * `Result<PathBuf, std::io::Error>` - The path the link points to or an error if the path is not a link
+    fn read_link(&self, path: &Path) -> Result<PathBuf, std::io::Error> {
+        fs::read_link(path)
+    }
+
+    /// Sums the sizes of the files in a directory and all of its subdirectories.
+    /// 
+    /// # Arguments
+    /// * `path` - The path to the directory
+    /// 
+    /// # Returns
+    /// * `Result<u64, std::io::Error>` - The total size in bytes or an error if the directory could not be read
+    fn dir_size(&self, path: &Path) -> Result<u64, std::io::Error> {
+        walk_dir_size(path)
+    }
+
+    /// Creates a symbolic link.
+    /// 
+    /// # Arguments
+    /// * `target` - The path the link points to
+    /// * `link` - The path of the link
+    /// 
+    /// # Returns
+    /// * `Result<(), std::io::Error>` - An error if the link could not be created
+    fn symlink(&self, target: &Path, link: &Path) -> Result<(), std::io::Error> {
+        std::os::unix::fs::symlink(target, link)
+    }
+
+    /// Copies a directory and everything in it.
+    /// 
+    /// # Arguments
+    /// * `from` - The path to the directory to copy
+    /// * `to` - The path the copy is made at, it must not exist yet
+    /// 
+    /// # Returns
+    /// * `Result<(), std::io::Error>` - An error if something could not be copied
+    fn copy_directory(&self, from: &Path, to: &Path) -> Result<(), std::io::Error> {
+        copy_dir_all(from, to)
+    }
+
 }
 
+
+/// Sums the sizes of the files under a path. Symbolic links are counted as links and never followed so a link
+/// pointing out of the directory is not counted twice.
+/// 
+/// # Arguments
+/// * `path` - The path to the file or directory
+/// 
+/// # Returns
+/// * `Result<u64, std::io::Error>` - The total size in bytes or an error if something could not be read
+pub fn walk_dir_size(path: &Path) -> Result<u64, std::io::Error> {
+    let metadata = fs::symlink_metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len())
+    }
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        size += walk_dir_size(&entry?.path())?;
+    }
+    Ok(size)
+}
+
+/// Copies a directory and everything in it. Symbolic links are copied as links and never followed, so a link
+/// pointing out of the directory does not pull what it points to into the copy.
+/// 
+/// # Arguments
+/// * `from` - The path to the directory to copy
+/// * `to` - The path the copy is made at
+/// 
+/// # Returns
+/// * `Result<(), std::io::Error>` - An error if something could not be read or written
+pub fn copy_dir_all(from: &Path, to: &Path) -> Result<(), std::io::Error> {
+    fs::create_dir(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            std::os::unix::fs::symlink(fs::read_link(entry.path())?, &target)?;
+        }
+        else if file_type.is_dir() {
+            copy_dir_all(&entry.path(), &target)?;
+        }
+        else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
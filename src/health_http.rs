@@ -0,0 +1,297 @@
+//! Waits for the HTTP health endpoint of a dependency after ```run -d --wait```. Compose only knows whether a
+//! container is running or its healthcheck passes, for a web service the real readiness signal is the status
+//! of an endpoint such as ```/health```. The endpoint is polled with a minimal HTTP/1.1 client over a plain
+//! TCP connection, so only ```http://``` URLs are supported.
+//! ```yaml
+//!     health_http:
+//!       url: http://localhost:{host_port}/health
+//!       expect_status: 200
+//!       timeout: 60
+//! ```
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use crate::clock::Clock;
+
+
+/// The status expected from the endpoint if ```expect_status``` is not set.
+pub const DEFAULT_EXPECT_STATUS: u16 = 200;
+
+/// How long to keep polling the endpoint if ```timeout``` is not set.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long to wait between polls of the endpoint.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long a single request may take to connect and respond.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+
+/// The HTTP health endpoint of a dependency.
+///
+/// # Fields
+/// * `url` - The ```http://``` URL polled, ```{host_port}``` is replaced with the host port of ```open_port```
+/// * `expect_status` - The status the endpoint returns once the dependency is ready, ```200``` if not set
+/// * `timeout` - The seconds to keep polling before giving up, ```60``` if not set
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HealthHttp {
+    pub url: String,
+    pub expect_status: Option<u16>,
+    pub timeout: Option<u64>,
+}
+
+impl HealthHttp {
+
+    /// Gets the status the endpoint returns once the dependency is ready.
+    ///
+    /// # Returns
+    /// * `u16` - The ```expect_status``` or ```200```
+    pub fn expected_status(&self) -> u16 {
+        self.expect_status.unwrap_or(DEFAULT_EXPECT_STATUS)
+    }
+
+    /// Gets how long to keep polling the endpoint.
+    ///
+    /// # Returns
+    /// * `Duration` - The ```timeout``` or 60 seconds
+    pub fn wait_timeout(&self) -> Duration {
+        self.timeout.map(Duration::from_secs).unwrap_or(DEFAULT_TIMEOUT)
+    }
+}
+
+
+/// The parts of an ```http://``` URL needed to send a request.
+///
+/// # Fields
+/// * `host` - The host name or address
+/// * `port` - The port, ```80``` if the URL has none
+/// * `path` - The path and query, ```/``` if the URL has none
+#[derive(Debug, PartialEq)]
+pub struct HttpTarget {
+    pub host: String,
+    pub port: u16,
+    pub path: String,
+}
+
+impl HttpTarget {
+
+    /// Parses an ```http://``` URL.
+    ///
+    /// # Arguments
+    /// * `url` - The URL of the health endpoint
+    ///
+    /// # Returns
+    /// * `Result<HttpTarget, String>` - The target or an error if the URL is not a valid ```http://``` URL
+    pub fn parse(url: &str) -> Result<HttpTarget, String> {
+        let rest = match url.strip_prefix("http://") {
+            Some(rest) => rest,
+            None => return Err(format!("health_http url {} must start with http://", url))
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(index) => (&rest[..index], &rest[index..]),
+            None => (rest, "/")
+        };
+        // the colons of an IPv6 address are inside the brackets, only a colon after them starts the port
+        let (host, port) = match authority.rfind(':') {
+            Some(index) if !authority[index..].contains(']') => {
+                let port = authority[index + 1..].parse::<u16>()
+                                                  .map_err(|_| format!("health_http url {} has an invalid port", url))?;
+                (&authority[..index], port)
+            },
+            _ => (authority, 80)
+        };
+        if host.is_empty() {
+            return Err(format!("health_http url {} has no host", url))
+        }
+        Ok(HttpTarget { host: host.to_string(), port, path: path.to_string() })
+    }
+}
+
+
+/// Sends a ```GET``` request and reads the status of the response.
+///
+/// # Arguments
+/// * `target` - The endpoint to request
+/// * `timeout` - How long connecting, writing, and reading may each take
+///
+/// # Returns
+/// * `std::io::Result<u16>` - The status of the response or an error if the endpoint could not be reached
+pub fn get_status(target: &HttpTarget, timeout: Duration) -> std::io::Result<u16> {
+    let address = (target.host.trim_start_matches('[').trim_end_matches(']'), target.port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, format!("could not resolve {}", target.host)))?;
+    let mut stream = TcpStream::connect_timeout(&address, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}:{}\r\nUser-Agent: wedp\r\nConnection: close\r\n\r\n",
+        target.path, target.host, target.port
+    );
+    stream.write_all(request.as_bytes())?;
+
+    let mut response = Vec::new();
+    let mut buffer = [0; 256];
+    while !response.contains(&b'\n') {
+        let read = stream.read(&mut buffer)?;
+        if read == 0 {
+            break
+        }
+        response.extend_from_slice(&buffer[..read]);
+    }
+    let status_line = String::from_utf8_lossy(&response).lines().next().unwrap_or("").to_string();
+    parse_status_line(&status_line).ok_or_else(|| std::io::Error::new(
+        std::io::ErrorKind::InvalidData, format!("not an HTTP response: {:?}", status_line)
+    ))
+}
+
+
+/// Reads the status from the first line of an HTTP response such as ```HTTP/1.1 200 OK```.
+///
+/// # Arguments
+/// * `line` - The status line of the response
+///
+/// # Returns
+/// * `Option<u16>` - The status or ```None``` if the line is not an HTTP status line
+pub fn parse_status_line(line: &str) -> Option<u16> {
+    let mut parts = line.split_whitespace();
+    match parts.next() {
+        Some(version) if version.starts_with("HTTP/") => parts.next()?.parse::<u16>().ok(),
+        _ => None
+    }
+}
+
+
+/// Polls an endpoint until it returns the expected status or the timeout passes.
+///
+/// # Arguments
+/// * `url` - The ```http://``` URL of the endpoint with any ```{host_port}``` already replaced
+/// * `health` - The expected status and timeout
+/// * `interval` - How long to wait between polls
+/// * `clock` - The clock the timeout is measured with
+///
+/// # Returns
+/// * `Result<u32, String>` - The number of requests sent or an error with the last response if it timed out
+pub fn wait_for_status(url: &str, health: &HealthHttp, interval: Duration, clock: &dyn Clock) -> Result<u32, String> {
+    let target = HttpTarget::parse(url)?;
+    let expected = health.expected_status();
+    let timeout = health.wait_timeout();
+    let start = clock.now();
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let last = match get_status(&target, REQUEST_TIMEOUT) {
+            Ok(status) if status == expected => return Ok(attempts),
+            Ok(status) => format!("got {}", status),
+            Err(error) => error.to_string()
+        };
+        if clock.now().duration_since(start) >= timeout {
+            return Err(format!(
+                "{} did not return {} within {}s, last attempt: {}", url, expected, timeout.as_secs(), last
+            ))
+        }
+        clock.sleep(interval);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::clock::SystemClock;
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Serves each response in turn to one connection each on a random local port.
+    fn serve(responses: Vec<&'static str>) -> (String, thread::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (format!("http://127.0.0.1:{}/health", port), handle)
+    }
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(
+            HttpTarget::parse("http://localhost:8080/health?deep=1"),
+            Ok(HttpTarget { host: "localhost".to_string(), port: 8080, path: "/health?deep=1".to_string() })
+        );
+        assert_eq!(
+            HttpTarget::parse("http://example.com"),
+            Ok(HttpTarget { host: "example.com".to_string(), port: 80, path: "/".to_string() })
+        );
+        assert_eq!(HttpTarget::parse("http://[::1]:8080/").unwrap(), HttpTarget { host: "[::1]".to_string(), port: 8080, path: "/".to_string() });
+        assert_eq!(HttpTarget::parse("http://[::1]/").unwrap().port, 80);
+        assert_eq!(HttpTarget::parse("https://localhost/health"), Err("health_http url https://localhost/health must start with http://".to_string()));
+        assert_eq!(HttpTarget::parse("http://localhost:http/"), Err("health_http url http://localhost:http/ has an invalid port".to_string()));
+        assert!(HttpTarget::parse("http:///health").is_err());
+    }
+
+    #[test]
+    fn test_parse_status_line() {
+        assert_eq!(parse_status_line("HTTP/1.1 200 OK"), Some(200));
+        assert_eq!(parse_status_line("HTTP/1.0 503 Service Unavailable\r"), Some(503));
+        assert_eq!(parse_status_line("SSH-2.0-OpenSSH_9.6"), None);
+        assert_eq!(parse_status_line(""), None);
+    }
+
+    #[test]
+    fn test_health_http_defaults() {
+        let health: HealthHttp = serde_yaml::from_str("url: http://localhost/health\n").unwrap();
+        assert_eq!(health.expected_status(), 200);
+        assert_eq!(health.wait_timeout(), Duration::from_secs(60));
+        let health: HealthHttp = serde_yaml::from_str("url: http://localhost/health\nexpect_status: 204\ntimeout: 5\n").unwrap();
+        assert_eq!(health.expected_status(), 204);
+        assert_eq!(health.wait_timeout(), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_wait_succeeds_after_retries() {
+        let (url, server) = serve(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok",
+        ]);
+        let health = HealthHttp { url: url.clone(), timeout: Some(10), ..Default::default() };
+        assert_eq!(wait_for_status(&url, &health, Duration::from_millis(10), &SystemClock), Ok(3));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_wait_for_other_status() {
+        let (url, server) = serve(vec!["HTTP/1.1 204 No Content\r\n\r\n"]);
+        let health = HealthHttp { url: url.clone(), expect_status: Some(204), timeout: Some(10) };
+        assert_eq!(wait_for_status(&url, &health, Duration::from_millis(10), &SystemClock), Ok(1));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_wait_times_out() {
+        let (url, server) = serve(vec!["HTTP/1.1 503 Service Unavailable\r\n\r\n"]);
+        let health = HealthHttp { url: url.clone(), timeout: Some(0), ..Default::default() };
+        assert_eq!(
+            wait_for_status(&url, &health, Duration::from_millis(10), &SystemClock),
+            Err(format!("{} did not return 200 within 0s, last attempt: got 503", url))
+        );
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_wait_times_out_when_nothing_listens() {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let url = format!("http://127.0.0.1:{}/health", port);
+        let health = HealthHttp { url: url.clone(), timeout: Some(0), ..Default::default() };
+        let error = wait_for_status(&url, &health, Duration::from_millis(10), &SystemClock).unwrap_err();
+        assert!(error.starts_with(&format!("{} did not return 200 within 0s, last attempt: ", url)), "{}", error);
+    }
+}
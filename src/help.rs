@@ -0,0 +1,458 @@
+//! The long help of every subcommand, shown after the flags by ```wedp <command> --help```. Each command has
+//! at least one example invocation and, where the command reads them, the part of the seating plan or wedding
+//! invite it uses. The examples and snippets are constants so the tests can parse every example with the
+//! command line and every snippet as a seating plan or wedding invite, keeping the help in step with wedp.
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+
+/// A seating plan with two attendees, one on a branch and one pinned to a release tag.
+pub const PLAN_BASIC: &str = "\
+attendees:
+  - name: institution
+    url: https://github.com/yellow-bird-consult/institution.git
+    branch: infrastructure
+  - name: billing
+    url: git@github.com:yellow-bird-consult/billing.git
+    rev: v1.4.0
+venue: ~/work/venue
+";
+
+/// A seating plan setting the defaults of ```run``` and waiting on the health of a web service.
+pub const PLAN_RUN: &str = "\
+attendees:
+  - name: institution
+    url: https://github.com/yellow-bird-consult/institution.git
+    branch: infrastructure
+    open_port: web:8080
+    health_http:
+      url: http://localhost:{host_port}/health
+      expect_status: 200
+      timeout: 30
+venue: ./sandbox/services/
+command_defaults:
+  run:
+    detach: true
+    wait: true
+restart_policy: unless-stopped
+";
+
+/// A seating plan cloning shallow with submodules and limiting the git and docker commands run at once.
+pub const PLAN_INSTALL: &str = "\
+attendees:
+  - name: institution
+    url: https://github.com/yellow-bird-consult/institution.git
+    branch: main
+    submodules: true
+    clone_depth: 1
+venue: ${HOME}/services
+max_git_ops: 4
+max_docker_ops: 2
+";
+
+/// A seating plan building with the defaults of ```build``` and running with podman.
+pub const PLAN_BUILD: &str = "\
+attendees:
+  - name: institution
+    url: https://github.com/yellow-bird-consult/institution.git
+    branch: infrastructure
+venue: ./sandbox/services/
+command_defaults:
+  build:
+    pull: true
+engine: podman
+";
+
+/// A wedding invite with runner files for local builds, one only included when ```WEDP_DEBUG``` is set.
+pub const INVITE_BASIC: &str = "\
+build_root: \".\"
+build_files:
+  x86_64: builds/Dockerfile.x86_64
+  aarch64: builds/Dockerfile.aarch64
+runner_files:
+  - runner_files/base.yml
+  - path: runner_files/debug.yml
+    when: \"${WEDP_DEBUG}\"
+";
+
+/// A wedding invite with runner files for the images pulled from a registry.
+pub const INVITE_REMOTE: &str = "\
+build_root: \".\"
+runner_files:
+  - runner_files/base.yml
+remote_runner_files:
+  - runner_files/remote.yml
+";
+
+/// A wedding invite with runner files for running the repo in dev mode.
+pub const INVITE_DEV: &str = "\
+build_root: \".\"
+runner_files:
+  - runner_files/base.yml
+dev_runner_files:
+  - runner_files/base.yml
+  - runner_files/dev.yml
+";
+
+
+/// A YAML file wedp reads that is shown in the help of a command.
+///
+/// # Fields
+/// * `SeatingPlan` - A seating plan passed with ```-f```
+/// * `WeddingInvite` - The ```wedding_invite.yml``` in the root of a dependency or the current directory
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Snippet {
+    SeatingPlan(&'static str),
+    WeddingInvite(&'static str),
+}
+
+
+/// The long help of a subcommand.
+///
+/// # Fields
+/// * `command` - The subcommand, nested subcommands are separated by a space such as ```dress run```
+/// * `examples` - Invocations of the subcommand, each one starting with ```wedp```
+/// * `snippet` - The part of a seating plan or wedding invite the subcommand reads, if there is one worth showing
+pub struct CommandHelp {
+    pub command: &'static str,
+    pub examples: &'static [&'static str],
+    pub snippet: Option<Snippet>,
+}
+
+
+/// The long help of every subcommand.
+pub const COMMAND_HELP: &[CommandHelp] = &[
+    CommandHelp {
+        command: "build",
+        examples: &["wedp build -f seating_plan.yml", "wedp build --pull --only institution -f seating_plan.yml"],
+        snippet: Some(Snippet::SeatingPlan(PLAN_BUILD)),
+    },
+    CommandHelp {
+        command: "remotebuild",
+        examples: &["wedp remotebuild -f seating_plan.yml", "wedp remotebuild --pull --only institution -f seating_plan.yml"],
+        snippet: Some(Snippet::WeddingInvite(INVITE_REMOTE)),
+    },
+    CommandHelp {
+        command: "run",
+        examples: &[
+            "wedp run -f seating_plan.yml",
+            "wedp run -d --wait --then-logs -f seating_plan.yml",
+            "wedp run --abort-on-exit tests -f seating_plan.yml",
+        ],
+        snippet: Some(Snippet::SeatingPlan(PLAN_RUN)),
+    },
+    CommandHelp {
+        command: "remoterun",
+        examples: &["wedp remoterun -d -f seating_plan.yml", "wedp remoterun --except billing -f seating_plan.yml"],
+        snippet: Some(Snippet::WeddingInvite(INVITE_REMOTE)),
+    },
+    CommandHelp {
+        command: "install",
+        examples: &[
+            "wedp install -f seating_plan.yml",
+            "wedp install --force --jobs 4 --verify-refs -f seating_plan.yml",
+            "wedp install --archive wedp-vendor.tar.gz -f seating_plan.yml",
+            "wedp install --locked -f seating_plan.yml",
+        ],
+        snippet: Some(Snippet::SeatingPlan(PLAN_INSTALL)),
+    },
+    CommandHelp {
+        command: "lock",
+        examples: &["wedp lock -f seating_plan.yml", "wedp lock --only institution -f seating_plan.yml"],
+        snippet: Some(Snippet::SeatingPlan(PLAN_BASIC)),
+    },
+    CommandHelp {
+        command: "update",
+        examples: &[
+            "wedp update -f seating_plan.yml",
+            "wedp update --force --only institution -f seating_plan.yml",
+        ],
+        snippet: Some(Snippet::SeatingPlan(PLAN_BASIC)),
+    },
+    CommandHelp {
+        command: "vendor",
+        examples: &["wedp vendor -o wedp-vendor.tar.gz -f seating_plan.yml"],
+        snippet: Some(Snippet::SeatingPlan(PLAN_BASIC)),
+    },
+    CommandHelp {
+        command: "logs",
+        examples: &["wedp logs -f seating_plan.yml", "wedp logs --no-follow --tail 20 auth_db -f seating_plan.yml"],
+        snippet: None,
+    },
+    CommandHelp {
+        command: "ps",
+        examples: &["wedp ps -f seating_plan.yml", "wedp ps --remote --raw -f seating_plan.yml"],
+        snippet: None,
+    },
+    CommandHelp {
+        command: "stats",
+        examples: &["wedp stats -f seating_plan.yml", "wedp stats --json -f seating_plan.yml"],
+        snippet: None,
+    },
+    CommandHelp {
+        command: "du",
+        examples: &["wedp du -f seating_plan.yml"],
+        snippet: None,
+    },
+    CommandHelp {
+        command: "teardown",
+        examples: &["wedp teardown -f seating_plan.yml", "wedp teardown --except billing --remove-venue -f seating_plan.yml"],
+        snippet: None,
+    },
+    CommandHelp {
+        command: "remoteteardown",
+        examples: &["wedp remoteteardown -f seating_plan.yml"],
+        snippet: Some(Snippet::WeddingInvite(INVITE_REMOTE)),
+    },
+    CommandHelp {
+        command: "clean",
+        examples: &["wedp clean -f seating_plan.yml", "wedp clean --all -f seating_plan.yml"],
+        snippet: None,
+    },
+    CommandHelp {
+        command: "setup",
+        examples: &["wedp setup -f seating_plan.yml"],
+        snippet: Some(Snippet::SeatingPlan(PLAN_BASIC)),
+    },
+    CommandHelp {
+        command: "status",
+        examples: &["wedp status -f seating_plan.yml"],
+        snippet: None,
+    },
+    CommandHelp {
+        command: "demo",
+        examples: &["wedp demo", "wedp demo --cleanup wedp-demo"],
+        snippet: None,
+    },
+    CommandHelp {
+        command: "init",
+        examples: &["wedp init invite", "wedp init plan --force"],
+        snippet: None,
+    },
+    CommandHelp {
+        command: "git",
+        examples: &["wedp git institution -f seating_plan.yml -- log --oneline -n 5"],
+        snippet: None,
+    },
+    CommandHelp {
+        command: "edit",
+        examples: &["wedp edit institution -f seating_plan.yml"],
+        snippet: Some(Snippet::WeddingInvite(INVITE_BASIC)),
+    },
+    CommandHelp {
+        command: "bump-branch",
+        examples: &[
+            "wedp bump-branch --from main --to release/1.4 -f seating_plan.yml",
+            "wedp bump-branch --from main --to release/1.4 --only institution --checkout -f seating_plan.yml",
+        ],
+        snippet: Some(Snippet::SeatingPlan(PLAN_BASIC)),
+    },
+    CommandHelp {
+        command: "validate",
+        examples: &["wedp validate -f seating_plan.yml", "wedp validate --no-verify-refs -f seating_plan.yml"],
+        snippet: Some(Snippet::SeatingPlan(PLAN_BASIC)),
+    },
+    CommandHelp {
+        command: "doctor",
+        examples: &["wedp doctor -f seating_plan.yml", "wedp doctor --fix -f seating_plan.yml"],
+        snippet: Some(Snippet::SeatingPlan(PLAN_BASIC)),
+    },
+    CommandHelp {
+        command: "check",
+        examples: &["wedp check -f seating_plan.yml", "wedp check --no-daemon --remote -f seating_plan.yml"],
+        snippet: Some(Snippet::WeddingInvite(INVITE_BASIC)),
+    },
+    CommandHelp {
+        command: "prepare",
+        examples: &["wedp prepare -f seating_plan.yml", "wedp --arch aarch64 prepare --diff -f seating_plan.yml"],
+        snippet: Some(Snippet::WeddingInvite(INVITE_BASIC)),
+    },
+    CommandHelp {
+        command: "compose-files",
+        examples: &["wedp compose-files -f seating_plan.yml", "wedp compose-files --remote --json -f seating_plan.yml"],
+        snippet: Some(Snippet::WeddingInvite(INVITE_BASIC)),
+    },
+    CommandHelp {
+        command: "config",
+        examples: &["wedp config show -f seating_plan.yml"],
+        snippet: Some(Snippet::SeatingPlan(PLAN_RUN)),
+    },
+    CommandHelp {
+        command: "config show",
+        examples: &["wedp config show run -f seating_plan.yml", "wedp config show run --detach -f seating_plan.yml"],
+        snippet: Some(Snippet::SeatingPlan(PLAN_RUN)),
+    },
+    CommandHelp {
+        command: "dress",
+        examples: &["wedp dress run -f seating_plan.yml"],
+        snippet: Some(Snippet::WeddingInvite(INVITE_BASIC)),
+    },
+    CommandHelp {
+        command: "dress build",
+        examples: &["wedp dress build -f seating_plan.yml"],
+        snippet: Some(Snippet::WeddingInvite(INVITE_BASIC)),
+    },
+    CommandHelp {
+        command: "dress remotebuild",
+        examples: &["wedp dress remotebuild -f seating_plan.yml"],
+        snippet: Some(Snippet::WeddingInvite(INVITE_REMOTE)),
+    },
+    CommandHelp {
+        command: "dress run",
+        examples: &["wedp dress run -f seating_plan.yml", "wedp dress run -d --only auth -f seating_plan.yml"],
+        snippet: Some(Snippet::WeddingInvite(INVITE_BASIC)),
+    },
+    CommandHelp {
+        command: "dress devrun",
+        examples: &["wedp dress devrun -f seating_plan.yml"],
+        snippet: Some(Snippet::WeddingInvite(INVITE_DEV)),
+    },
+    CommandHelp {
+        command: "dress remoterun",
+        examples: &["wedp dress remoterun -d -f seating_plan.yml"],
+        snippet: Some(Snippet::WeddingInvite(INVITE_REMOTE)),
+    },
+    CommandHelp {
+        command: "dress install",
+        examples: &["wedp dress install --jobs 4 -f seating_plan.yml"],
+        snippet: Some(Snippet::SeatingPlan(PLAN_INSTALL)),
+    },
+    CommandHelp {
+        command: "dress logs",
+        examples: &["wedp dress logs -f seating_plan.yml", "wedp dress logs --no-follow web -f seating_plan.yml"],
+        snippet: None,
+    },
+    CommandHelp {
+        command: "dress ps",
+        examples: &["wedp dress ps -f seating_plan.yml"],
+        snippet: None,
+    },
+    CommandHelp {
+        command: "dress teardown",
+        examples: &["wedp dress teardown -f seating_plan.yml"],
+        snippet: None,
+    },
+    CommandHelp {
+        command: "dress remoteteardown",
+        examples: &["wedp dress remoteteardown -f seating_plan.yml"],
+        snippet: None,
+    },
+    CommandHelp {
+        command: "dress setup",
+        examples: &["wedp dress setup -f seating_plan.yml"],
+        snippet: Some(Snippet::SeatingPlan(PLAN_BASIC)),
+    },
+    CommandHelp {
+        command: "dress compose-files",
+        examples: &["wedp dress compose-files --dev -f seating_plan.yml"],
+        snippet: Some(Snippet::WeddingInvite(INVITE_DEV)),
+    },
+];
+
+
+/// Renders the long help of a subcommand: the examples followed by the snippet, each line indented.
+///
+/// # Arguments
+/// * `help` - The long help of the subcommand
+///
+/// # Returns
+/// * `String` - The text shown after the flags
+pub fn render_after_help(help: &CommandHelp) -> String {
+    let mut rendered = String::from("EXAMPLES:\n");
+    for example in help.examples {
+        rendered.push_str(&format!("    {}\n", example));
+    }
+    let (title, snippet) = match help.snippet {
+        Some(Snippet::SeatingPlan(snippet)) => ("SEATING PLAN", snippet),
+        Some(Snippet::WeddingInvite(snippet)) => ("WEDDING INVITE", snippet),
+        None => return rendered
+    };
+    rendered.push_str(&format!("\n{}:\n", title));
+    for line in snippet.lines() {
+        rendered.push_str(&format!("    {}\n", line));
+    }
+    rendered
+}
+
+
+/// Gets the long help of a subcommand, rendered once for the rest of the process.
+///
+/// # Arguments
+/// * `command` - The subcommand, nested subcommands are separated by a space such as ```dress run```
+///
+/// # Returns
+/// * `&'static str` - The text shown after the flags, empty if the subcommand has no long help
+pub fn after_help(command: &str) -> &'static str {
+    static RENDERED: OnceLock<HashMap<&'static str, String>> = OnceLock::new();
+    RENDERED.get_or_init(|| COMMAND_HELP.iter().map(|help| (help.command, render_after_help(help))).collect())
+            .get(command)
+            .map(String::as_str)
+            .unwrap_or_default()
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::seating_plan::SeatingPlan;
+    use crate::wedding_invite::WeddingInvite;
+    use std::fs;
+
+    /// Writes a snippet into a temporary file so it is read the way wedp reads the real files.
+    fn write_snippet(name: &str, snippet: &str) -> String {
+        let directory = std::env::temp_dir().join("wedp_help_tests");
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join(name);
+        fs::write(&path, snippet).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_snippets_parse() {
+        for (index, help) in COMMAND_HELP.iter().enumerate() {
+            match help.snippet {
+                Some(Snippet::SeatingPlan(snippet)) => {
+                    let path = write_snippet(&format!("seating_plan_{}.yml", index), snippet);
+                    if let Err(error) = SeatingPlan::from_file(path) {
+                        panic!("the seating plan of {} does not parse: {}", help.command, error);
+                    }
+                },
+                Some(Snippet::WeddingInvite(snippet)) => {
+                    let path = write_snippet(&format!("wedding_invite_{}.yml", index), snippet);
+                    if let Err(error) = WeddingInvite::from_file(path) {
+                        panic!("the wedding invite of {} does not parse: {}", help.command, error);
+                    }
+                },
+                None => {}
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_command_has_an_example() {
+        for help in COMMAND_HELP {
+            assert!(!help.examples.is_empty(), "{} has no example", help.command);
+            for example in help.examples {
+                assert!(example.starts_with(&format!("wedp {}", help.command)) || example.starts_with("wedp --"), "{}", example);
+            }
+        }
+    }
+
+    #[test]
+    fn test_render_after_help() {
+        let help = CommandHelp {
+            command: "remoteteardown",
+            examples: &["wedp remoteteardown -f seating_plan.yml"],
+            snippet: Some(Snippet::WeddingInvite(INVITE_REMOTE)),
+        };
+        assert_eq!(
+            render_after_help(&help),
+            "EXAMPLES:\n    wedp remoteteardown -f seating_plan.yml\n\n\
+             WEDDING INVITE:\n    build_root: \".\"\n    runner_files:\n      - runner_files/base.yml\n    \
+             remote_runner_files:\n      - runner_files/remote.yml\n"
+        );
+        assert_eq!(after_help("du"), "EXAMPLES:\n    wedp du -f seating_plan.yml\n");
+        assert_eq!(after_help("not-a-command"), "");
+    }
+}
@@ -0,0 +1,228 @@
+//! Picks the per-arch tag of the images in remote runner files for images that are published as separate
+//! ```-amd64``` and ```-arm64``` tags rather than a manifest list. A wedding invite maps each CPU type to the
+//! suffix of its tags with ```remote_image_arch_suffixes```, and an override file is generated into the venue
+//! rewriting the ```image``` of every service in the remote runner files of that attendee to the tag with the
+//! suffix. The compose files of the dependencies are never edited.
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use crate::compose_file::ComposeFileRef;
+use crate::cpu_data::CpuType;
+use crate::file_handler::CoreFileHandle;
+use crate::paths::join_normalised;
+
+
+/// The name of the generated override file in the venue.
+pub const OVERRIDE_FILE: &str = "wedp_image_arch_override.yml";
+
+/// The attendee name given to the generated override file.
+pub const OVERRIDE_ATTENDEE: &str = "image_arch";
+
+
+/// The image set on a single service in the override file.
+#[derive(Serialize)]
+struct ImageService {
+    image: String,
+}
+
+/// The contents of the override file.
+#[derive(Serialize)]
+struct ImageOverride {
+    services: BTreeMap<String, ImageService>,
+}
+
+
+/// Gets the suffix of the image tags for a CPU type.
+///
+/// # Arguments
+/// * `suffixes` - The ```remote_image_arch_suffixes``` of a wedding invite keyed by CPU type
+/// * `cpu_type` - The name of the CPU type the dependencies are run on
+///
+/// # Returns
+/// * `Result<Option<&str>, String>` - The suffix, ```None``` if the CPU type has no suffix so the images are used
+///   as they are written, or an error if a key is not a CPU type wedp knows
+pub fn arch_suffix<'a>(suffixes: &'a HashMap<String, String>, cpu_type: &str) -> Result<Option<&'a str>, String> {
+    for arch in suffixes.keys() {
+        CpuType::from_arch(arch).map_err(|error| format!("remote_image_arch_suffixes: {}", error))?;
+    }
+    Ok(suffixes.get(cpu_type).map(String::as_str))
+}
+
+
+/// Adds a suffix to the tag of an image, an image without a tag gets the suffix on ```latest```. Images pinned
+/// by digest and images that already end with the suffix are kept as they are.
+///
+/// # Arguments
+/// * `image` - The image reference such as ```org/auth:1.4```
+/// * `suffix` - The suffix to add such as ```-arm64```
+///
+/// # Returns
+/// * `String` - The image reference with the suffix
+pub fn suffixed_image(image: &str, suffix: &str) -> String {
+    if image.contains('@') || image.ends_with(suffix) {
+        return image.to_string()
+    }
+    let name_start = image.rfind('/').map(|index| index + 1).unwrap_or(0);
+    match image[name_start..].contains(':') {
+        true => format!("{}{}", image, suffix),
+        false => format!("{}:latest{}", image, suffix)
+    }
+}
+
+
+/// Reads the images of the services in the compose files of the attendees that have a suffix.
+///
+/// # Arguments
+/// * `compose_files` - The compose files docker is about to be run with
+/// * `suffixes` - The suffix of each attendee, attendees that are not in the map are left out
+/// * `file_handle` - The file handle used to read the compose files
+///
+/// # Returns
+/// * `Result<BTreeMap<String, String>, String>` - The suffixed image of each service or an error if a file can
+///   not be read or parsed
+pub fn suffixed_images(compose_files: &[ComposeFileRef], suffixes: &HashMap<String, String>, file_handle: &dyn CoreFileHandle) -> Result<BTreeMap<String, String>, String> {
+    let mut images = BTreeMap::new();
+    for file in compose_files {
+        let suffix = match suffixes.get(&file.attendee) {
+            Some(suffix) => suffix,
+            None => continue
+        };
+        let contents = file_handle.read_to_string(Path::new(&file.path))
+                                  .map_err(|error| format!("Could not read {}: {}", file.path, error))?;
+        let compose: serde_yaml::Value = serde_yaml::from_str(&contents)
+                                  .map_err(|error| format!("Could not parse {}: {}", file.path, error))?;
+        let services = match compose.get("services").and_then(|services| services.as_mapping()) {
+            Some(services) => services,
+            None => continue
+        };
+        for (name, service) in services {
+            if let (Some(name), Some(image)) = (name.as_str(), service.get("image").and_then(|image| image.as_str())) {
+                images.insert(name.to_string(), suffixed_image(image, suffix));
+            }
+        }
+    }
+    Ok(images)
+}
+
+
+/// Renders the override file setting the image of each service.
+///
+/// # Arguments
+/// * `images` - The image of each service
+///
+/// # Returns
+/// * `String` - The YAML contents of the override file
+pub fn render_override(images: &BTreeMap<String, String>) -> String {
+    let services = images.iter()
+                         .map(|(name, image)| (name.clone(), ImageService { image: image.clone() }))
+                         .collect();
+    serde_yaml::to_string(&ImageOverride { services }).unwrap()
+}
+
+
+/// Generates the override file with the suffixed images into the venue and adds it after the compose files.
+///
+/// # Arguments
+/// * `compose_files` - The remote compose files the images are read from
+/// * `suffixes` - The suffix of each attendee, an empty map leaves the compose files as they are
+/// * `venue` - The venue the override file is written into
+/// * `file_handle` - The file handle used to read the compose files and write the override file
+///
+/// # Returns
+/// * `Result<Vec<ComposeFileRef>, String>` - The compose files including the override or an error if it could not be generated
+pub fn with_image_arch_override(compose_files: Vec<ComposeFileRef>, suffixes: &HashMap<String, String>, venue: &Path, file_handle: &dyn CoreFileHandle) -> Result<Vec<ComposeFileRef>, String> {
+    if suffixes.is_empty() {
+        return Ok(compose_files)
+    }
+    let images = suffixed_images(&compose_files, suffixes, file_handle)?;
+    if images.is_empty() {
+        return Ok(compose_files)
+    }
+    let override_path = join_normalised(venue, &[OVERRIDE_FILE]);
+    file_handle.write(&override_path, &render_override(&images))
+               .map_err(|error| format!("Could not write {}: {}", override_path.to_string_lossy(), error))?;
+    let mut compose_files = compose_files;
+    compose_files.push(ComposeFileRef::new(OVERRIDE_ATTENDEE, override_path.to_string_lossy().to_string()));
+    Ok(compose_files)
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::file_handler::MockCoreFileHandle;
+    use mockall::predicate::eq;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn test_files() -> Vec<ComposeFileRef> {
+        vec![
+            ComposeFileRef::new("auth", "tests/image_arch/auth_remote.yml".to_string()),
+            ComposeFileRef::new("billing", "tests/image_arch/billing_remote.yml".to_string()),
+        ]
+    }
+
+    fn fixture_handle() -> MockCoreFileHandle {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_read_to_string()
+            .returning(|path| fs::read_to_string(path));
+        mock_handle
+    }
+
+    fn suffixes(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn test_arch_suffix() {
+        let arch_suffixes = suffixes(&[("x86_64", "-amd64"), ("aarch64", "-arm64")]);
+        assert_eq!(arch_suffix(&arch_suffixes, "aarch64"), Ok(Some("-arm64")));
+        assert_eq!(arch_suffix(&arch_suffixes, "x86_64"), Ok(Some("-amd64")));
+        assert_eq!(arch_suffix(&arch_suffixes, "riscv64"), Ok(None));
+        assert!(arch_suffix(&suffixes(&[("arm64", "-arm64")]), "aarch64").unwrap_err()
+            .starts_with("remote_image_arch_suffixes: Unsupported CPU type: arm64"));
+    }
+
+    #[test]
+    fn test_suffixed_image() {
+        assert_eq!(suffixed_image("org/auth:1.4", "-arm64"), "org/auth:1.4-arm64");
+        assert_eq!(suffixed_image("org/auth", "-arm64"), "org/auth:latest-arm64");
+        assert_eq!(suffixed_image("localhost:5000/auth", "-arm64"), "localhost:5000/auth:latest-arm64");
+        assert_eq!(suffixed_image("localhost:5000/auth:2", "-arm64"), "localhost:5000/auth:2-arm64");
+        assert_eq!(suffixed_image("org/auth:1.4-arm64", "-arm64"), "org/auth:1.4-arm64");
+        assert_eq!(suffixed_image("org/auth@sha256:abc", "-arm64"), "org/auth@sha256:abc");
+    }
+
+    #[test]
+    fn test_suffixed_images() {
+        let images = suffixed_images(&test_files(), &suffixes(&[("auth", "-arm64")]), &fixture_handle()).unwrap();
+        assert_eq!(render_override(&images), fs::read_to_string("tests/image_arch/expected_override.yml").unwrap());
+    }
+
+    #[test]
+    fn test_with_image_arch_override() {
+        let mut mock_handle = fixture_handle();
+        let expected = fs::read_to_string("tests/image_arch/expected_override.yml").unwrap();
+        mock_handle.expect_write()
+            .with(eq(PathBuf::from("venue/wedp_image_arch_override.yml")), eq(expected))
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let files = with_image_arch_override(test_files(), &suffixes(&[("auth", "-arm64")]), Path::new("./venue/"), &mock_handle).unwrap();
+        assert_eq!(files.last(), Some(&ComposeFileRef::new("image_arch", "venue/wedp_image_arch_override.yml".to_string())));
+        assert_eq!(files.len(), 3);
+    }
+
+    #[test]
+    fn test_with_image_arch_override_untouched() {
+        let mut mock_handle = fixture_handle();
+        mock_handle.expect_write().times(0);
+        assert_eq!(with_image_arch_override(test_files(), &HashMap::new(), Path::new("venue"), &mock_handle), Ok(test_files()));
+        assert_eq!(
+            with_image_arch_override(test_files(), &suffixes(&[("payments", "-arm64")]), Path::new("venue"), &mock_handle),
+            Ok(test_files())
+        );
+    }
+}
@@ -0,0 +1,292 @@
+//! Substitutes the environment into the seating plan and the wedding invites before they are parsed, so a
+//! seating plan can hold ```url: https://${GIT_HOST}/team/auth.git``` or ```branch: ${AUTH_BRANCH:-develop}```.
+//! Only the braced ```${NAME}``` and ```${NAME:-default}``` forms are substituted, ```$${NAME}``` is kept as the
+//! literal ```${NAME}```, and a variable that is not set and has no default is an error naming the file, the key,
+//! and the line it is on.
+//!
+//! The ```when``` of a runner file is left as it is, it is evaluated when the runner files are picked and a
+//! variable that is not set there leaves the runner file out. Substitution, and the expansion of the ```venue```
+//! and the ```url``` of each attendee, is turned off for the rest of the process with ```--no-interpolate``` to
+//! see the files as they are written.
+use std::env;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::error::WedpError;
+use crate::file_handler::CoreFileHandle;
+
+
+/// The keys whose values are never substituted.
+const SKIPPED_KEYS: [&str; 1] = ["when"];
+
+static INTERPOLATE: OnceLock<bool> = OnceLock::new();
+
+
+/// Sets whether the seating plan and the wedding invites are substituted for the rest of the process. Only the
+/// first call has an effect.
+///
+/// # Arguments
+/// * `interpolate` - If false the files are parsed as they are written
+pub fn set_interpolate(interpolate: bool) {
+    let _ = INTERPOLATE.set(interpolate);
+}
+
+/// Checks if the environment is substituted into the seating plan and the wedding invites, this also covers
+/// the expansion of the ```venue``` and the ```url``` of each attendee.
+///
+/// # Returns
+/// * `bool` - False if ```--no-interpolate``` is passed
+pub fn interpolating() -> bool {
+    INTERPOLATE.get().copied().unwrap_or(true)
+}
+
+/// Substitutes the environment of the process into the contents of a file unless ```--no-interpolate``` is passed.
+///
+/// # Arguments
+/// * `contents` - The YAML contents of the file
+/// * `path` - The path the contents were read from, used in error messages
+///
+/// # Returns
+/// * `Result<String, WedpError>` - The substituted contents or an error naming a variable that is not set
+pub fn interpolate_file(contents: &str, path: &str) -> Result<String, WedpError> {
+    match interpolating() {
+        true => interpolate(contents, path, &|name| env::var(name).ok()),
+        false => Ok(contents.to_string())
+    }
+}
+
+/// Reads a file through a file handle and substitutes the environment into it unless ```--no-interpolate``` is
+/// passed.
+///
+/// # Arguments
+/// * `path` - The path to the file
+/// * `handle` - The file handle the file is read with
+///
+/// # Returns
+/// * `Result<String, WedpError>` - The substituted contents or the error reading or substituting the file
+pub fn read_interpolated(path: &str, handle: &dyn CoreFileHandle) -> Result<String, WedpError> {
+    let contents = handle.read_to_string(Path::new(path))
+                         .map_err(|source| WedpError::from_read(path.to_string(), source))?;
+    interpolate_file(&contents, path)
+}
+
+
+/// Substitutes the ```${NAME}``` and ```${NAME:-default}``` variables of YAML contents line by line. Comment lines
+/// and the values of the skipped keys are kept as they are.
+///
+/// # Arguments
+/// * `contents` - The YAML contents to substitute
+/// * `path` - The path the contents were read from, used in error messages
+/// * `lookup` - Reads an environment variable, ```None``` if it is not set
+///
+/// # Returns
+/// * `Result<String, WedpError>` - The substituted contents or an error naming the first variable that is not set
+pub fn interpolate(contents: &str, path: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String, WedpError> {
+    let lines: Vec<&str> = contents.split_inclusive('\n').collect();
+    let mut interpolated = String::with_capacity(contents.len());
+    for (index, line) in lines.iter().enumerate() {
+        let key = line_key(&lines, index);
+        if line.trim_start().starts_with('#') || key.is_some_and(|key| SKIPPED_KEYS.contains(&key)) {
+            interpolated.push_str(line);
+            continue
+        }
+        let substituted = substitute(line, lookup).map_err(|name| WedpError::InvalidConfig(format!(
+            "{} is not set for {} at line {} of {}", name, key.unwrap_or("the value"), index + 1, path
+        )))?;
+        interpolated.push_str(&substituted);
+    }
+    Ok(interpolated)
+}
+
+
+/// Replaces the ```${NAME}``` and ```${NAME:-default}``` variables of a line with their values. The default is
+/// used when the variable is not set or is empty.
+///
+/// # Arguments
+/// * `line` - The line to substitute
+/// * `lookup` - Reads an environment variable, ```None``` if it is not set
+///
+/// # Returns
+/// * `Result<String, String>` - The substituted line or the name of the first variable that is not set
+fn substitute(line: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut substituted = String::new();
+    let mut rest = line;
+    while let Some(start) = rest.find("${") {
+        if rest[..start].ends_with('$') {
+            substituted.push_str(&rest[..start - 1]);
+            substituted.push_str("${");
+            rest = &rest[start + 2..];
+            continue
+        }
+        substituted.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = match after.find('}') {
+            Some(end) => end,
+            None => {
+                substituted.push_str("${");
+                rest = after;
+                continue
+            }
+        };
+        let (name, default) = match after[..end].split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (&after[..end], None)
+        };
+        if !is_variable_name(name) {
+            substituted.push_str("${");
+            rest = after;
+            continue
+        }
+        let value = match (lookup(name).filter(|value| !value.is_empty() || default.is_none()), default) {
+            (Some(value), _) => value,
+            (None, Some(default)) => default.to_string(),
+            (None, None) => return Err(name.to_string())
+        };
+        substituted.push_str(&value);
+        rest = &after[end + 1..];
+    }
+    substituted.push_str(rest);
+    Ok(substituted)
+}
+
+
+/// Checks if a string is a name an environment variable can have.
+///
+/// # Arguments
+/// * `name` - The name to check
+///
+/// # Returns
+/// * `bool` - True if the name is letters, digits, and underscores and does not start with a digit
+fn is_variable_name(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+
+/// Finds the key a line belongs to, which is the key written on the line or, for a list item or a continued
+/// value, the closest key above it that is indented less.
+///
+/// # Arguments
+/// * `lines` - The lines of the YAML contents
+/// * `index` - The index of the line
+///
+/// # Returns
+/// * `Option<&str>` - The key or ```None``` if the line is not under a key
+fn line_key<'a>(lines: &[&'a str], index: usize) -> Option<&'a str> {
+    let indent = |line: &str| line.len() - line.trim_start().len();
+    if let Some(key) = written_key(lines[index]) {
+        return Some(key)
+    }
+    let mut limit = indent(lines[index]);
+    for line in lines[..index].iter().rev() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') || indent(line) >= limit {
+            continue
+        }
+        match written_key(line) {
+            Some(key) => return Some(key),
+            None => limit = indent(line)
+        }
+    }
+    None
+}
+
+
+/// Gets the key written on a line such as ```branch: main``` or ```- name: auth```.
+///
+/// # Arguments
+/// * `line` - The line to read the key from
+///
+/// # Returns
+/// * `Option<&str>` - The key or ```None``` if there is no key on the line
+fn written_key(line: &str) -> Option<&str> {
+    let trimmed = line.trim();
+    let unlisted = trimmed.strip_prefix("- ").unwrap_or(trimmed).trim_start();
+    let (key, value) = unlisted.split_once(':')?;
+    match (value.is_empty() || value.starts_with(' ')) && !key.is_empty() && !key.contains(' ') && !key.contains('$') {
+        true => Some(key),
+        false => None
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::runner_file::RunnerFile;
+    use crate::seating_plan::SeatingPlan;
+    use crate::wedding_invite::WeddingInvite;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "GIT_HOST" => Some("git.example.com".to_string()),
+            "ENV" => Some("staging".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None
+        }
+    }
+
+    #[test]
+    fn test_substitute() {
+        assert_eq!(substitute("url: https://${GIT_HOST}/auth.git", &lookup), Ok("url: https://git.example.com/auth.git".to_string()));
+        assert_eq!(substitute("branch: ${AUTH_BRANCH:-develop}", &lookup), Ok("branch: develop".to_string()));
+        assert_eq!(substitute("branch: ${EMPTY:-develop}", &lookup), Ok("branch: develop".to_string()));
+        assert_eq!(substitute("branch: ${ENV:-develop}", &lookup), Ok("branch: staging".to_string()));
+        assert_eq!(substitute("value: ${EMPTY}", &lookup), Ok("value: ".to_string()));
+        assert_eq!(substitute("value: $${GIT_HOST} $GIT_HOST", &lookup), Ok("value: ${GIT_HOST} $GIT_HOST".to_string()));
+        assert_eq!(substitute("value: ${GIT_HOST ${1NAME} ${", &lookup), Ok("value: ${GIT_HOST ${1NAME} ${".to_string()));
+        assert_eq!(substitute("url: ${MISSING}/auth.git", &lookup), Err("MISSING".to_string()));
+    }
+
+    #[test]
+    fn test_line_key() {
+        let lines: Vec<&str> = "attendees:\n  - name: auth\n    url: ${X}\nrunner_files:\n  - base.yml\n  - path: debug.yml\n    when: ${X}\n"
+            .split_inclusive('\n').collect();
+        assert_eq!(line_key(&lines, 0), Some("attendees"));
+        assert_eq!(line_key(&lines, 2), Some("url"));
+        assert_eq!(line_key(&lines, 4), Some("runner_files"));
+        assert_eq!(line_key(&lines, 6), Some("when"));
+        assert_eq!(written_key("url: https://host"), Some("url"));
+        assert_eq!(written_key("- https://host/${X}"), None);
+    }
+
+    #[test]
+    fn test_interpolate_seating_plan() {
+        std::env::set_var("WEDP_TEST_GIT_HOST", "git.example.com");
+        std::env::remove_var("WEDP_TEST_AUTH_BRANCH");
+        let contents = std::fs::read_to_string("tests/interpolated_seating_plan.yml").unwrap();
+        let interpolated = interpolate_file(&contents, "tests/interpolated_seating_plan.yml").unwrap();
+        let seating_plan = SeatingPlan::parse(&interpolated, "tests/interpolated_seating_plan.yml").unwrap();
+        assert_eq!(seating_plan.attendees[0].url, "https://git.example.com/team/auth.git");
+        assert_eq!(seating_plan.attendees[0].branch, Some("develop".to_string()));
+        assert_eq!(seating_plan.attendees[1].url, "https://git.example.com/team/billing.git");
+    }
+
+    #[test]
+    fn test_interpolate_wedding_invite() {
+        std::env::set_var("WEDP_TEST_ENV", "staging");
+        let wedding_invite = WeddingInvite::from_file("tests/interpolated_wedding_invite.yml".to_string()).unwrap();
+        assert_eq!(
+            wedding_invite.runner_files,
+            serde_yaml::from_str::<Vec<RunnerFile>>(
+                "- runner_files/base.yml\n- runner_files/staging.yml\n- path: runner_files/debug.yml\n  when: \"${WEDP_DEBUG}\"\n"
+            ).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_undefined_variable() {
+        let contents = "attendees:\n  - name: auth\n    url: https://${GIT_HOST}/auth.git\n    branch: ${AUTH_BRANCH}\nvenue: ./venue\n";
+        assert_eq!(
+            interpolate(contents, "seating_plan.yml", &lookup).map_err(|error| error.to_string()),
+            Err("AUTH_BRANCH is not set for branch at line 4 of seating_plan.yml".to_string())
+        );
+        let contents = "runner_files:\n  - runner_files/${MISSING}.yml\n";
+        assert_eq!(
+            interpolate(contents, "wedding_invite.yml", &lookup).map_err(|error| error.to_string()),
+            Err("MISSING is not set for runner_files at line 2 of wedding_invite.yml".to_string())
+        );
+        let contents = "# uses ${MISSING}\nrunner_files:\n  - path: debug.yml\n    when: \"${MISSING}\"\n";
+        assert_eq!(interpolate(contents, "wedding_invite.yml", &lookup).unwrap(), contents);
+    }
+}
@@ -0,0 +1,219 @@
+//! Records the commit each attendee was installed at in ```wedding_planner.lock``` next to the seating plan, so
+//! CI can install exactly the revisions a developer tested with ```wedp install --locked``` instead of the tips
+//! of the branches. The lockfile is YAML so a change of commit reads as a one line diff in code review.
+//! ```yaml
+//! attendees:
+//! - name: auth
+//!   url: https://github.com/org/auth
+//!   branch: main
+//!   commit: 3f2a9c1d0e8b7a6f5c4d3e2f1a0b9c8d7e6f5a4b
+//! ```
+//! Attendees with a ```path``` are not locked as they are not git clones wedp checks out.
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::dependency::Dependency;
+
+
+/// The name of the lockfile written next to the seating plan.
+pub const LOCK_FILE: &str = "wedding_planner.lock";
+
+
+/// An attendee in the lockfile.
+///
+/// # Fields
+/// * `name` - The name of the attendee
+/// * `url` - The URL the attendee was cloned from
+/// * `branch` - The branch of the attendee in the seating plan
+/// * `commit` - The full hash of the commit the attendee was checked out at
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LockEntry {
+    pub name: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    pub commit: String,
+}
+
+
+/// The commits the attendees of a seating plan are locked to.
+///
+/// # Fields
+/// * `attendees` - The locked attendees in the order of the seating plan
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Lockfile {
+    pub attendees: Vec<LockEntry>,
+}
+
+impl Lockfile {
+
+    /// Parses a lockfile.
+    ///
+    /// # Arguments
+    /// * `contents` - The YAML of the lockfile
+    /// * `path` - The path the lockfile was read from, used in the error message
+    ///
+    /// # Returns
+    /// * `Result<Lockfile, String>` - The lockfile or an error if the YAML is not a lockfile
+    pub fn parse(contents: &str, path: &Path) -> Result<Lockfile, String> {
+        serde_yaml::from_str(contents)
+            .map_err(|error| format!("{} is not a valid lockfile: {}", path.to_string_lossy(), error))
+    }
+
+    /// Renders the lockfile as YAML.
+    ///
+    /// # Returns
+    /// * `String` - The YAML of the lockfile
+    pub fn render(&self) -> String {
+        serde_yaml::to_string(self).unwrap()
+    }
+
+    /// Gets the locked entry of an attendee.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the attendee
+    ///
+    /// # Returns
+    /// * `Option<&LockEntry>` - The entry or ```None``` if the attendee is not locked
+    pub fn entry(&self, name: &str) -> Option<&LockEntry> {
+        self.attendees.iter().find(|entry| entry.name == name)
+    }
+
+    /// Locks attendees to new commits, keeping the entries of the other attendees still in the seating plan so a
+    /// filtered install only moves the attendees it installed.
+    ///
+    /// # Arguments
+    /// * `attendees` - Every attendee of the seating plan in its order
+    /// * `locked` - The new entries
+    ///
+    /// # Returns
+    /// * `Lockfile` - The lockfile in the order of the seating plan without the attendees it no longer has
+    pub fn merge(&self, attendees: &[Dependency], locked: Vec<LockEntry>) -> Lockfile {
+        let entries = attendees.iter().filter_map(|dependency| {
+            locked.iter().find(|entry| entry.name == dependency.name)
+                  .or_else(|| self.entry(&dependency.name))
+                  .cloned()
+        });
+        Lockfile { attendees: entries.collect() }
+    }
+}
+
+
+/// Gets the path of the lockfile of a seating plan, next to the seating plan file.
+///
+/// # Arguments
+/// * `seating_plan` - The path to the seating plan file
+///
+/// # Returns
+/// * `PathBuf` - The path to the lockfile
+pub fn lock_path(seating_plan: &str) -> PathBuf {
+    Path::new(seating_plan).parent().unwrap_or_else(|| Path::new(".")).join(LOCK_FILE)
+}
+
+
+/// Pins an attendee to the commit of its lockfile entry, dropping any other branch, tag, or rev so the commit is
+/// checked out. The ```branch``` is kept only to be recorded when the lockfile is written again.
+///
+/// # Arguments
+/// * `dependency` - The attendee to pin
+/// * `entry` - The entry of the attendee in the lockfile
+///
+/// # Returns
+/// * `Result<(), String>` - An error if the attendee was locked from another url
+pub fn pin(dependency: &mut Dependency, entry: &LockEntry) -> Result<(), String> {
+    if entry.url != dependency.url {
+        return Err(format!(
+            "{} was locked from {} but the seating plan clones {}, run wedp lock to lock the new url",
+            dependency.name, entry.url, dependency.url
+        ))
+    }
+    dependency.commit = Some(entry.commit.clone());
+    dependency.tag = None;
+    dependency.rev = None;
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn entry(name: &str, commit: &str) -> LockEntry {
+        LockEntry {
+            name: name.to_string(),
+            url: format!("https://github.com/org/{}", name),
+            branch: Some("main".to_string()),
+            commit: commit.to_string(),
+        }
+    }
+
+    fn attendee(name: &str) -> Dependency {
+        Dependency {
+            name: name.to_string(),
+            url: format!("https://github.com/org/{}", name),
+            branch: Some("main".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut tagged = entry("billing", "8b04d2e5f6a7b8c9d0e1f2a3b4c5d6e7f8a9b0c1");
+        tagged.branch = None;
+        let lockfile = Lockfile { attendees: vec![entry("auth", "3f2a9c1d0e8b7a6f5c4d3e2f1a0b9c8d7e6f5a4b"), tagged] };
+
+        let rendered = lockfile.render();
+
+        assert_eq!(
+            rendered,
+            "attendees:\n\
+             - name: auth\n  url: https://github.com/org/auth\n  branch: main\n  commit: 3f2a9c1d0e8b7a6f5c4d3e2f1a0b9c8d7e6f5a4b\n\
+             - name: billing\n  url: https://github.com/org/billing\n  commit: 8b04d2e5f6a7b8c9d0e1f2a3b4c5d6e7f8a9b0c1\n"
+        );
+        assert_eq!(Lockfile::parse(&rendered, Path::new(LOCK_FILE)), Ok(lockfile));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        let error = Lockfile::parse("attendees:\n  - name: auth\n", Path::new("plans/wedding_planner.lock")).unwrap_err();
+        assert!(error.starts_with("plans/wedding_planner.lock is not a valid lockfile: "), "{}", error);
+    }
+
+    #[test]
+    fn test_merge() {
+        let old = Lockfile { attendees: vec![entry("auth", "1111111"), entry("removed", "3333333"), entry("billing", "2222222")] };
+        let attendees = vec![attendee("auth"), attendee("billing"), attendee("search")];
+
+        let merged = old.merge(&attendees, vec![entry("search", "5555555"), entry("billing", "4444444")]);
+
+        let commits: Vec<(&str, &str)> = merged.attendees.iter().map(|entry| (entry.name.as_str(), entry.commit.as_str())).collect();
+        assert_eq!(commits, vec![("auth", "1111111"), ("billing", "4444444"), ("search", "5555555")]);
+    }
+
+    #[test]
+    fn test_lock_path() {
+        assert_eq!(lock_path("/repo/plans/seating_plan.yml"), PathBuf::from("/repo/plans/wedding_planner.lock"));
+        assert_eq!(lock_path("seating_plan.yml"), PathBuf::from("wedding_planner.lock"));
+    }
+
+    #[test]
+    fn test_pin() {
+        let mut dependency = attendee("auth");
+        dependency.rev = Some("v1.0.0".to_string());
+        dependency.branch = None;
+        pin(&mut dependency, &entry("auth", "3f2a9c1d0e8b7a6f5c4d3e2f1a0b9c8d7e6f5a4b")).unwrap();
+        assert_eq!(dependency.commit, Some("3f2a9c1d0e8b7a6f5c4d3e2f1a0b9c8d7e6f5a4b".to_string()));
+        assert_eq!(dependency.rev, None);
+
+        let mut moved = attendee("auth");
+        moved.url = "https://github.com/neworg/auth".to_string();
+        assert_eq!(
+            pin(&mut moved, &entry("auth", "1111111")),
+            Err("auth was locked from https://github.com/org/auth but the seating plan clones https://github.com/neworg/auth, \
+                 run wedp lock to lock the new url".to_string())
+        );
+    }
+}
@@ -17,155 +17,1823 @@
 //! ```bash
 //! wedp teardown -f tests/live_test.yml
 //! ```
-use clap::{App, Arg};
+//! We can see the effective flags of each command and where they came from with the following command: 
+//! ```bash
+//! wedp config show run -f tests/live_test.yml
+//! ```
+//! We can run the dependencies alongside the wedding invite of the current directory with the ```dress``` 
+//! subcommands:
+//! ```bash
+//! wedp dress run -f tests/live_test.yml
+//! ```
+//! The old single word commands such as ```run-d``` and ```dressrun``` are still accepted for now and are 
+//! translated to the subcommands with a deprecation warning.
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 
-use std::{env, path::Path};
+use std::{collections::HashMap, env, io::IsTerminal, path::{Path, PathBuf}, process, time::Duration};
 
+mod attendee_filter;
+mod clock;
+mod command_defaults;
+mod build_diff;
+mod bump_branch;
+mod compose_binary;
+mod compose_file;
+mod compose_inspect;
+mod container_status;
 mod cpu_data;
+mod demo;
+mod disk_usage;
+mod doctor;
+mod edit;
+mod git_auth;
+mod health_http;
+mod image_arch;
+mod lockfile;
+mod help;
+mod error;
+mod interpolate;
+mod logging;
 mod dependency;
 mod file_handler;
 mod seating_plan;
+mod plan_cache;
+mod project_location;
+mod remote_refs;
+mod open_url;
+mod paths;
+mod restart_policy;
+mod runner_file;
+mod scaffold;
+mod run_state;
+mod schema;
+mod state;
+mod usage_stats;
+mod wedp_ignore;
+mod yaml_error;
 mod wedding_invite;
+mod vendor_manifest;
 mod runner;
 mod dress_rehearsal;
+mod dry_run;
 mod commands;
 
 use runner::Runner;
-use dress_rehearsal::dress_rehearsal_factory;
+use commands::command_runner::{CommandRunner, CoreRunner};
+use dry_run::{DryRunFileHandle, DryRunRunner};
+use file_handler::{CoreFileHandle, FileHandle};
+use commands::timeout::set_timeout_per_command;
+use commands::trace::{register_secret_env, set_trace_script, traced};
+use logging::LogLevel;
+use commands::limiter::{set_operation_limits, OperationLimits};
+use dress_rehearsal::{dress_rehearsal_factory, DressOptions, DressRehearsal};
+use cpu_data::{arch_override, CpuType, ARCH_ENV};
+use attendee_filter::AttendeeFilter;
+use compose_binary::ContainerEngine;
+use clock::{Clock, SystemClock};
+use error::WedpError;
 
 
-fn main() {
-    let matches = App::new("wedding planner")
+/// The old single word commands mapped to the subcommand arguments that replace them.
+const LEGACY_COMMANDS: [(&str, &[&str]); 13] = [
+    ("run-d", &["run", "-d"]),
+    ("remoterun-d", &["remoterun", "-d"]),
+    ("dressbuild", &["dress", "build"]),
+    ("dressremotebuild", &["dress", "remotebuild"]),
+    ("dressrun", &["dress", "run"]),
+    ("dressdevrun", &["dress", "devrun"]),
+    ("dressrun-d", &["dress", "run", "-d"]),
+    ("dressremoterun", &["dress", "remoterun"]),
+    ("dressremoterun-d", &["dress", "remoterun", "-d"]),
+    ("dressinstall", &["dress", "install"]),
+    ("dressteardown", &["dress", "teardown"]),
+    ("dressremoteteardown", &["dress", "remoteteardown"]),
+    ("dresssetup", &["dress", "setup"]),
+];
+
+
+/// The subcommands that work on the current directory, which use the seating plan in it instead of searching
+/// the directories above it.
+const CURRENT_DIRECTORY_COMMANDS: [&str; 3] = ["init", "demo", "dress"];
+
+
+/// Translates the old single word commands into the subcommand arguments that replace them. Only the first
+/// positional argument is translated so the values of flags are left alone.
+/// 
+/// # Arguments
+/// * `args` - The command line arguments including the binary name
+/// 
+/// # Returns
+/// * `Vec<String>` - The arguments with a legacy command replaced by its subcommand arguments
+fn translate_legacy_args(args: Vec<String>) -> Vec<String> {
+    let mut translated = Vec::new();
+    let mut args = args.into_iter();
+    translated.extend(args.next());
+
+    let mut expecting_value = false;
+    let mut found_command = false;
+
+    for arg in args {
+        if found_command || expecting_value {
+            expecting_value = false;
+            translated.push(arg);
+            continue
+        }
+        if arg.starts_with('-') {
+            expecting_value = arg == "-f" || arg == "--file" || arg == "--timeout-per-command" || arg == "--arch"
+                || arg == "--engine" || arg == "--compose-cmd" || arg == "--max-docker-ops" || arg == "--max-git-ops"
+                || arg == "--trace-script";
+            translated.push(arg);
+            continue
+        }
+        found_command = true;
+        match LEGACY_COMMANDS.iter().find(|(name, _)| *name == arg) {
+            Some((name, replacement)) => {
+                eprintln!("Warning: {} is deprecated, use wedp {} instead", name, replacement.join(" "));
+                translated.extend(replacement.iter().map(|part| part.to_string()));
+            },
+            None => translated.push(arg)
+        }
+    }
+    translated
+}
+
+
+/// Loads the runner for the seating plan.
+/// 
+/// # Arguments
+/// * `paths` - The paths to the seating plan files, merged in order if there is more than one
+/// * `dry_run` - Whether the runner should print the commands and file changes instead of running them
+/// * `arch` - The CPU type to pick the Dockerfiles for, ```None``` uses the CPU type of the host
+/// * `filter` - The attendees the command applies to
+/// * `engine` - The container engine to run the dependencies with, ```None``` uses the seating plan setting
+/// * `compose_command` - The compose command from ```--compose-cmd```, ```None``` resolves it as usual
+/// * `skip_missing` - Whether runner files that do not exist are left out with a warning instead of failing
+/// * `plan_root` - The directory of a seating plan found above the current directory, ```None``` if it is not
+/// 
+/// # Returns
+/// * `Result<Runner, WedpError>` - The runner or the error loading the seating plan
+#[allow(clippy::too_many_arguments)]
+fn load_runner(paths: Vec<String>, dry_run: bool, arch: Option<String>, filter: AttendeeFilter, engine: Option<ContainerEngine>,
+               compose_command: Option<String>, skip_missing: bool, plan_root: Option<&Path>) -> Result<Runner, WedpError> {
+    let runner = match dry_run {
+        true => Runner::new_dry_run(paths)?,
+        false => Runner::new(paths)?
+    };
+    runner.with_plan_root(plan_root)
+          .with_arch(arch)
+          .with_engine(engine)
+          .with_compose_command(compose_command)
+          .with_skip_missing(skip_missing)
+          .with_filter(filter)
+          .map_err(WedpError::InvalidConfig)
+}
+
+
+/// Gets the seating plan to use when ```-f``` is not passed. The current directory and the directories above it
+/// are searched for it, the paths in the seating plan are then read from the directory it is found in with
+/// ```Runner::with_plan_root```. ```init```, ```demo```, and ```dress``` work on the current directory so they
+/// use the seating plan in it without searching.
+/// 
+/// # Arguments
+/// * `command` - The subcommand being run
+/// * `cwd` - The directory wedp was started in
+/// 
+/// # Returns
+/// * `String` - The full path to the seating plan, exits if it is not found
+fn default_plan_path(command: &str, cwd: &str) -> String {
+    let file_name = runner::plan_file_name(env::var(runner::PLAN_FILE_ENV).ok());
+    if CURRENT_DIRECTORY_COMMANDS.contains(&command) {
+        return Path::new(cwd).join(file_name).to_string_lossy().to_string()
+    }
+    runner::find_seating_plan(Path::new(cwd), &file_name)
+        .unwrap_or_else(|error| exit_with(WedpError::InvalidConfig(error)))
+        .to_string_lossy()
+        .to_string()
+}
+
+
+/// Prints an error and exits with the code for its kind, see ```WedpError::exit_code```.
+/// 
+/// # Arguments
+/// * `error` - The error to print
+fn exit_with(error: WedpError) -> ! {
+    eprintln!("{}", error);
+    process::exit(error.exit_code());
+}
+
+
+/// Gets the attendees a command applies to from ```--only``` and ```--except```.
+/// 
+/// # Arguments
+/// * `matches` - The matches of the subcommand
+/// 
+/// # Returns
+/// * `AttendeeFilter` - The filter, every attendee if neither option was passed
+fn get_filter(matches: &ArgMatches) -> AttendeeFilter {
+    let names = |name: &str| -> Vec<String> {
+        matches.values_of(name)
+               .map(|values| values.filter(|value| !value.is_empty()).map(String::from).collect())
+               .unwrap_or_default()
+    };
+    // clap already refuses --only together with --except
+    AttendeeFilter::from_options(names("only"), names("except")).unwrap_or_default()
+}
+
+
+/// Checks the value of ```--timeout-per-command``` is a whole number of seconds above zero.
+/// 
+/// # Arguments
+/// * `value` - The value passed in through the command line
+/// 
+/// # Returns
+/// * `Result<(), String>` - An error message if the value is not a valid timeout
+fn validate_timeout(value: String) -> Result<(), String> {
+    match value.parse::<u64>() {
+        Ok(seconds) if seconds > 0 => Ok(()),
+        _ => Err(format!("{} is not a whole number of seconds above zero", value))
+    }
+}
+
+
+/// Checks the value of ```--jobs``` is a whole number above zero.
+/// 
+/// # Arguments
+/// * `value` - The value passed in through the command line
+/// 
+/// # Returns
+/// * `Result<(), String>` - An error message if the value is not a valid number of jobs
+fn validate_jobs(value: String) -> Result<(), String> {
+    match value.parse::<usize>() {
+        Ok(jobs) if jobs > 0 => Ok(()),
+        _ => Err(format!("{} is not a whole number above zero", value))
+    }
+}
+
+
+/// Gets the number of dependencies to install at the same time.
+/// 
+/// # Arguments
+/// * `matches` - The matches of the install subcommand
+/// 
+/// # Returns
+/// * `usize` - The value of ```--jobs``` or the number of CPUs if it was not passed in
+fn get_jobs(matches: Option<&ArgMatches>) -> usize {
+    match matches.and_then(|matches| matches.value_of("jobs")) {
+        Some(jobs) => jobs.parse().unwrap(),
+        None => runner::default_install_jobs()
+    }
+}
+
+
+/// Gets the services passed to the logs subcommand.
+/// 
+/// # Arguments
+/// * `matches` - The matches of the logs subcommand
+/// 
+/// # Returns
+/// * `Vec<String>` - The services, empty if none were passed in
+fn get_services(matches: &ArgMatches) -> Vec<String> {
+    matches.values_of("services")
+           .map(|services| services.map(String::from).collect())
+           .unwrap_or_default()
+}
+
+
+/// Gets the number of lines of each container ```logs``` prints from ```--tail```.
+/// 
+/// # Arguments
+/// * `matches` - The matches of the logs subcommand
+/// 
+/// # Returns
+/// * `Option<usize>` - The number of lines, ```None``` if ```--tail``` was not passed
+fn get_tail(matches: &ArgMatches) -> Option<usize> {
+    matches.value_of("tail").map(|tail| tail.parse().unwrap())
+}
+
+
+/// Checks the value of ```--tail``` is a whole number of lines.
+/// 
+/// # Arguments
+/// * `value` - The value passed in through the command line
+/// 
+/// # Returns
+/// * `Result<(), String>` - An error message if the value is not a whole number
+fn validate_tail(value: String) -> Result<(), String> {
+    value.parse::<usize>().map(|_| ()).map_err(|_| format!("{} is not a whole number of lines", value))
+}
+
+
+/// Checks the value of ```--arch``` is a CPU type wedp supports.
+/// 
+/// # Arguments
+/// * `value` - The value passed in through the command line
+/// 
+/// # Returns
+/// * `Result<(), String>` - An error message if the CPU type is not supported
+fn validate_arch(value: String) -> Result<(), String> {
+    CpuType::from_arch(&value).map(|_| ())
+}
+
+
+/// Checks the value of ```--engine``` is a container engine wedp supports.
+/// 
+/// # Arguments
+/// * `value` - The value passed in through the command line
+/// 
+/// # Returns
+/// * `Result<(), String>` - An error message if the engine is not supported
+fn validate_engine(value: String) -> Result<(), String> {
+    ContainerEngine::from_name(&value).map(|_| ())
+}
+
+
+/// Checks the value of ```--compose-cmd``` is a command wedp can run docker compose with.
+/// 
+/// # Arguments
+/// * `value` - The value passed in through the command line
+/// 
+/// # Returns
+/// * `Result<(), String>` - An error message if the command is not valid
+fn validate_compose_cmd(value: String) -> Result<(), String> {
+    compose_binary::validate_compose_command(&value)
+}
+
+
+/// Defines the command line interface of the tool.
+/// 
+/// # Returns
+/// * `App` - The clap app with all of the subcommands
+fn build_app() -> App<'static, 'static> {
+    let file_arg = Arg::with_name("file")
+        .takes_value(true)
+        .short("f")
+        .long("file")
+        .use_delimiter(true)
+        .multiple(true)
+        .number_of_values(1)
+        .global(true)
+        .help("The seating plan file, defaults to the closest wedding_planner.yml in the current directory or above \
+               it. Pass it more than once or separate the files with commas to merge several seating plans");
+    let dry_run_arg = Arg::with_name("dry-run")
+        .long("dry-run")
+        .global(true)
+        .help("Prints the git and docker commands and file changes instead of running them");
+    let timeout_arg = Arg::with_name("timeout-per-command")
+        .takes_value(true)
+        .long("timeout-per-command")
+        .value_name("SECS")
+        .global(true)
+        .validator(validate_timeout)
+        .help("Kills any single git or docker command that runs for longer than SECS, foreground runs are excluded");
+    let arch_arg = Arg::with_name("arch")
+        .takes_value(true)
+        .long("arch")
+        .value_name("CPU")
+        .global(true)
+        .validator(validate_arch)
+        .help("Picks the Dockerfiles for CPU instead of the CPU of the host, such as aarch64, overrides WEDP_ARCH");
+    let engine_arg = Arg::with_name("engine")
+        .takes_value(true)
+        .long("engine")
+        .value_name("ENGINE")
+        .global(true)
+        .validator(validate_engine)
+        .help("Runs the dependencies with docker, docker-compose-v2, or podman instead of the engine of the seating plan");
+    let compose_cmd_arg = Arg::with_name("compose-cmd")
+        .takes_value(true)
+        .long("compose-cmd")
+        .value_name("COMMAND")
+        .global(true)
+        .validator(validate_compose_cmd)
+        .help("Runs docker compose with COMMAND such as \"docker compose\", overriding WEDP_COMPOSE and the seating plan");
+    let skip_missing_arg = Arg::with_name("skip-missing")
+        .long("skip-missing")
+        .global(true)
+        .help("Leaves out runner files that do not exist with a warning instead of failing before docker is run");
+    let max_docker_ops_arg = Arg::with_name("max-docker-ops")
+        .takes_value(true)
+        .long("max-docker-ops")
+        .value_name("N")
+        .global(true)
+        .validator(validate_jobs)
+        .help("Runs at most N docker commands at the same time, overriding max_docker_ops of the seating plan");
+    let max_git_ops_arg = Arg::with_name("max-git-ops")
+        .takes_value(true)
+        .long("max-git-ops")
+        .value_name("N")
+        .global(true)
+        .validator(validate_jobs)
+        .help("Runs at most N git commands at the same time, overriding max_git_ops of the seating plan");
+    let no_interpolate_arg = Arg::with_name("no-interpolate")
+        .long("no-interpolate")
+        .global(true)
+        .help("Reads the seating plan and the wedding invites as they are written without substituting ${NAME} variables");
+    let trace_script_arg = Arg::with_name("trace-script")
+        .takes_value(true)
+        .long("trace-script")
+        .value_name("FILE")
+        .global(true)
+        .help("Appends every git and docker command to FILE as a shell script that can be replayed, secrets are redacted");
+    let verbose_arg = Arg::with_name("verbose")
+        .long("verbose")
+        .global(true)
+        .conflicts_with("quiet")
+        .help("Prints every git and docker command before it is run, secrets are redacted");
+    let quiet_arg = Arg::with_name("quiet")
+        .long("quiet")
+        .global(true)
+        .help("Only prints errors and the output of the command, not its progress or warnings");
+    let remove_venue_arg = Arg::with_name("remove-venue")
+        .long("remove-venue")
+        .help("Removes the cloned dependencies from the venue once the containers are torn down");
+    let detach_arg = Arg::with_name("detach")
+        .short("d")
+        .long("detach")
+        .help("Runs the dependencies in the background");
+    let force_recreate_arg = Arg::with_name("force-recreate")
+        .long("force-recreate")
+        .help("Recreates the containers and removes orphans, even if the compose project runs from another location");
+    let then_logs_arg = Arg::with_name("then-logs")
+        .long("then-logs")
+        .help("Follows the logs after starting in the background, Ctrl-C stops following and leaves the dependencies running");
+    let wait_arg = Arg::with_name("wait")
+        .long("wait")
+        .help("Waits for the dependencies to be running or healthy when running in the background");
+    let pull_arg = Arg::with_name("pull")
+        .long("pull")
+        .help("Pulls the latest base images before building");
+    let no_detach_arg = Arg::with_name("no-detach")
+        .long("no-detach")
+        .conflicts_with("detach")
+        .help("Runs the dependencies in the foreground even if the seating plan detaches them");
+    let no_wait_arg = Arg::with_name("no-wait")
+        .long("no-wait")
+        .conflicts_with("wait")
+        .help("Does not wait for the dependencies even if the seating plan waits for them");
+    let no_pull_arg = Arg::with_name("no-pull")
+        .long("no-pull")
+        .conflicts_with("pull")
+        .help("Builds on the base images already pulled even if the seating plan pulls them");
+    let no_cache_arg = Arg::with_name("no-cache")
+        .long("no-cache")
+        .help("Builds the images without the build cache");
+    let jobs_arg = Arg::with_name("jobs")
+        .takes_value(true)
+        .short("j")
+        .long("jobs")
+        .value_name("N")
+        .validator(validate_jobs)
+        .help("Installs up to N dependencies at the same time, defaults to the number of CPUs");
+    let force_arg = Arg::with_name("force")
+        .long("force")
+        .help("Removes dependencies that are already cloned and clones them again instead of fetching");
+    let archive_arg = Arg::with_name("archive")
+        .takes_value(true)
+        .long("archive")
+        .value_name("TARBALL")
+        .help("Extracts the dependencies from an archive made by wedp vendor instead of cloning them");
+    let only_arg = Arg::with_name("only")
+        .takes_value(true)
+        .long("only")
+        .value_name("NAMES")
+        .use_delimiter(true)
+        .multiple(true)
+        .number_of_values(1)
+        .conflicts_with("except")
+        .help("Only applies the command to the named attendees, separated by commas");
+    let except_arg = Arg::with_name("except")
+        .takes_value(true)
+        .long("except")
+        .value_name("NAMES")
+        .use_delimiter(true)
+        .multiple(true)
+        .number_of_values(1)
+        .help("Applies the command to every attendee apart from the named ones, separated by commas");
+    let remote_arg = Arg::with_name("remote")
+        .long("remote")
+        .help("Uses the remote runner files");
+    let no_follow_arg = Arg::with_name("no-follow")
+        .long("no-follow")
+        .help("Prints the logs once and exits with the code of docker-compose instead of following them");
+    let tail_arg = Arg::with_name("tail")
+        .takes_value(true)
+        .long("tail")
+        .value_name("LINES")
+        .validator(validate_tail)
+        .help("The number of lines to print of each container before following, defaults to 100 when following");
+    let services_arg = Arg::with_name("services")
+        .value_name("SERVICE")
+        .multiple(true)
+        .help("The services to print the logs of, defaults to all of them");
+    let json_arg = Arg::with_name("json")
+        .long("json")
+        .help("Prints the files as JSON with the attendee each file came from");
+
+    App::new("wedding planner")
         .version("0.1.0")
         .author("Maxwell Flitton <maxwellflitton@gmail.com>")
         .about("Basic tool for running docker builds from other Github repos")
-        .arg(
-            Arg::with_name("command")
-                .value_name("COMMAND")
-                .required(true)
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(file_arg)
+        .arg(timeout_arg)
+        .arg(dry_run_arg)
+        .arg(arch_arg)
+        .arg(engine_arg)
+        .arg(compose_cmd_arg)
+        .arg(skip_missing_arg)
+        .arg(no_interpolate_arg)
+        .arg(trace_script_arg)
+        .arg(verbose_arg)
+        .arg(quiet_arg)
+        .arg(max_docker_ops_arg)
+        .arg(max_git_ops_arg)
+        .subcommand(SubCommand::with_name("build")
+            .after_help(help::after_help("build"))
+            .about("Builds the dependencies")
+            .arg(only_arg.clone())
+            .arg(except_arg.clone())
+            .arg(pull_arg.clone())
+            .arg(no_pull_arg.clone())
+            .arg(no_cache_arg.clone()))
+        .subcommand(SubCommand::with_name("remotebuild")
+            .after_help(help::after_help("remotebuild"))
+            .about("Builds the dependencies from their remote runner files")
+            .arg(only_arg.clone())
+            .arg(except_arg.clone())
+            .arg(pull_arg.clone())
+            .arg(no_pull_arg.clone())
+            .arg(no_cache_arg.clone()))
+        .subcommand(SubCommand::with_name("run")
+            .after_help(help::after_help("run"))
+            .about("Runs the dependencies")
+            .arg(detach_arg.clone())
+            .arg(no_detach_arg.clone())
+            .arg(wait_arg.clone())
+            .arg(no_wait_arg.clone())
+            .arg(then_logs_arg.clone().conflicts_with("abort-on-exit"))
+            .arg(force_recreate_arg.clone())
+            .arg(only_arg.clone())
+            .arg(except_arg.clone())
+            .arg(Arg::with_name("abort-on-exit")
+                .long("abort-on-exit")
+                .value_name("SERVICE")
+                .takes_value(true)
+                .conflicts_with("detach")
+                .help("Stops all containers when any container exits and exits with the code of SERVICE")))
+        .subcommand(SubCommand::with_name("remoterun")
+            .after_help(help::after_help("remoterun"))
+            .about("Runs the dependencies from their remote images")
+            .arg(only_arg.clone())
+            .arg(except_arg.clone())
+            .arg(detach_arg.clone())
+            .arg(no_detach_arg.clone())
+            .arg(wait_arg.clone())
+            .arg(no_wait_arg.clone())
+            .arg(then_logs_arg.clone())
+            .arg(force_recreate_arg))
+        .subcommand(SubCommand::with_name("install")
+            .after_help(help::after_help("install"))
+            .about("Clones the dependencies into the venue and prepares their builds")
+            .arg(jobs_arg.clone())
+            .arg(only_arg.clone())
+            .arg(except_arg.clone())
+            .arg(force_arg.clone())
+            .arg(Arg::with_name("no-clobber")
+                .long("no-clobber")
+                .conflicts_with("force")
+                .help("Updates dependencies that are already cloned instead of cloning them again, the default without --force"))
+            .arg(Arg::with_name("verify-refs")
+                .long("verify-refs")
+                .conflicts_with("archive")
+                .help("Checks the branch of each dependency exists on its remote before cloning anything"))
+            .arg(Arg::with_name("locked")
+                .long("locked")
+                .conflicts_with("archive")
+                .help("Checks out the commits of wedding_planner.lock instead of the tips of the branches"))
+            .arg(archive_arg.clone()))
+        .subcommand(SubCommand::with_name("lock")
+            .after_help(help::after_help("lock"))
+            .about("Records the commit of each installed dependency in wedding_planner.lock without installing")
+            .arg(only_arg.clone())
+            .arg(except_arg.clone()))
+        .subcommand(SubCommand::with_name("update")
+            .after_help(help::after_help("update"))
+            .about("Fetches, checks out, and fast forwards the dependencies that are already cloned")
+            .arg(jobs_arg.clone())
+            .arg(Arg::with_name("force")
+                .long("force")
+                .help("Stashes the local changes of dependencies instead of skipping them"))
+            .arg(only_arg.clone())
+            .arg(except_arg.clone()))
+        .subcommand(SubCommand::with_name("vendor")
+            .after_help(help::after_help("vendor"))
+            .about("Installs the dependencies and packs them into an archive for installing without network access")
+            .arg(jobs_arg.clone())
+            .arg(Arg::with_name("archive")
+                .value_name("TARBALL")
+                .index(1)
+                .required_unless("output")
+                .conflicts_with("output")
+                .help("The path the .tar.gz archive is written to"))
+            .arg(Arg::with_name("output")
                 .takes_value(true)
+                .short("o")
+                .long("output")
+                .value_name("TARBALL")
+                .help("The path the .tar.gz archive is written to")))
+        .subcommand(SubCommand::with_name("logs")
+            .after_help(help::after_help("logs"))
+            .about("Follows the logs of the dependency containers until interrupted")
+            .arg(no_follow_arg.clone())
+            .arg(tail_arg.clone())
+            .arg(services_arg.clone())
+            .arg(remote_arg.clone()))
+        .subcommand(SubCommand::with_name("ps")
+            .after_help(help::after_help("ps"))
+            .about("Shows the state and ports of the dependency containers")
+            .arg(remote_arg.clone())
+            .arg(Arg::with_name("raw")
+                .long("raw")
+                .help("Streams the output of docker-compose ps as it is instead of the table")))
+        .subcommand(SubCommand::with_name("stats")
+            .after_help(help::after_help("stats"))
+            .about("Shows the local usage stats of the dependencies in the venue, nothing leaves the machine")
+            .arg(Arg::with_name("json")
+                .long("json")
+                .help("Prints the stats as JSON"))
+            .arg(Arg::with_name("reset")
+                .long("reset")
+                .help("Removes the recorded stats")))
+        .subcommand(SubCommand::with_name("du")
+            .after_help(help::after_help("du"))
+            .about("Shows the disk used by each dependency in the venue, largest first"))
+        .subcommand(SubCommand::with_name("teardown")
+            .after_help(help::after_help("teardown"))
+            .about("Tears down the dependency containers")
+            .arg(only_arg.clone())
+            .arg(except_arg.clone())
+            .arg(remove_venue_arg.clone()))
+        .subcommand(SubCommand::with_name("remoteteardown")
+            .after_help(help::after_help("remoteteardown"))
+            .about("Tears down the remote dependency containers")
+            .arg(only_arg.clone())
+            .arg(except_arg.clone())
+            .arg(remove_venue_arg))
+        .subcommand(SubCommand::with_name("clean")
+            .after_help(help::after_help("clean"))
+            .about("Removes the cloned dependencies from the venue, leaving the containers as they are")
+            .arg(only_arg.clone())
+            .arg(except_arg.clone())
+            .arg(Arg::with_name("all")
+                .long("all")
+                .conflicts_with_all(&["only", "except"])
+                .help("Also removes the venue directory once the dependencies are removed")))
+        .subcommand(SubCommand::with_name("setup")
+            .after_help(help::after_help("setup"))
+            .about("Creates the venue directory"))
+        .subcommand(SubCommand::with_name("status")
+            .after_help(help::after_help("status"))
+            .about("Shows which dependencies are installed and on what branch"))
+        .subcommand(SubCommand::with_name("demo")
+            .after_help(help::after_help("demo"))
+            .about("Creates a self-contained demo with two local dependency repos and a seating plan")
+            .arg(Arg::with_name("directory")
+                .value_name("DIR")
+                .index(1)
+                .default_value("wedp-demo")
+                .help("The directory to create the demo in"))
+            .arg(Arg::with_name("cleanup")
+                .long("cleanup")
+                .help("Removes a demo created by wedp demo")))
+        .subcommand(SubCommand::with_name("init")
+            .after_help(help::after_help("init"))
+            .about("Writes a commented starter wedding invite or seating plan")
+            .arg(Arg::with_name("template")
+                .value_name("TEMPLATE")
+                .index(1)
+                .required(true)
+                .possible_values(&["invite", "plan"])
+                .help("The file to write, invite for a wedding invite or plan for a seating plan"))
+            .arg(Arg::with_name("force")
+                .long("force")
+                .help("Overwrites the file if it already exists")))
+        .subcommand(SubCommand::with_name("git")
+            .after_help(help::after_help("git"))
+            .about("Runs a git command in the venue directory of a dependency")
+            .arg(Arg::with_name("dependency")
+                .value_name("NAME")
+                .required(true)
                 .index(1)
-                .help("Input command")
-        )
-        .arg(
-            Arg::with_name("file")
+                .help("The name of the dependency to run git in"))
+            .arg(Arg::with_name("args")
+                .value_name("GIT_ARGS")
+                .multiple(true)
+                .last(true)
+                .help("The arguments passed to git after --")))
+        .subcommand(SubCommand::with_name("edit")
+            .after_help(help::after_help("edit"))
+            .about("Opens the wedding invite of an installed dependency in $VISUAL or $EDITOR and checks it")
+            .arg(Arg::with_name("dependency")
+                .value_name("NAME")
+                .required(true)
+                .index(1)
+                .help("The name of the dependency to edit the wedding invite of")))
+        .subcommand(SubCommand::with_name("bump-branch")
+            .after_help(help::after_help("bump-branch"))
+            .about("Moves the attendees on one branch to another in the seating plan file")
+            .arg(Arg::with_name("from")
+                .long("from")
+                .value_name("BRANCH")
+                .takes_value(true)
+                .required(true)
+                .help("The branch the attendees are moved from"))
+            .arg(Arg::with_name("to")
+                .long("to")
+                .value_name("BRANCH")
                 .takes_value(true)
-                .short("f")
-                .long("file")
-                .help("Optional file argument")
-        )
-        .get_matches();
+                .required(true)
+                .help("The branch the attendees are moved to"))
+            .arg(Arg::with_name("only")
+                .long("only")
+                .value_name("NAME")
+                .takes_value(true)
+                .multiple(true)
+                .help("Only moves the named attendees"))
+            .arg(Arg::with_name("checkout")
+                .long("checkout")
+                .help("Fetches and checks out the new branch of the attendees that moved"))
+            .arg(jobs_arg.clone()))
+        .subcommand(SubCommand::with_name("validate")
+            .after_help(help::after_help("validate"))
+            .about("Checks the seating plan and the wedding invites of the installed dependencies")
+            .arg(Arg::with_name("no-verify-refs")
+                .long("no-verify-refs")
+                .help("Skips checking the branch of each dependency exists on its remote")))
+        .subcommand(SubCommand::with_name("doctor")
+            .after_help(help::after_help("doctor"))
+            .about("Reports the problems of the venue and the dependencies, marking the fixable ones")
+            .arg(Arg::with_name("fix")
+                .long("fix")
+                .help("Fixes the fixable problems and checks again, only the venue and generated files are touched")))
+        .subcommand(SubCommand::with_name("check")
+            .after_help(help::after_help("check"))
+            .about("Checks the docker-compose files of the dependencies with docker-compose config")
+            .arg(remote_arg.clone())
+            .arg(Arg::with_name("no-daemon")
+                .long("no-daemon")
+                .help("Checks the files statically without docker, this is not a substitute for docker-compose config")))
+        .subcommand(SubCommand::with_name("prepare")
+            .after_help(help::after_help("prepare"))
+            .about("Copies the Dockerfile for the CPU into the build root of each dependency")
+            .arg(only_arg.clone())
+            .arg(except_arg.clone())
+            .arg(Arg::with_name("diff")
+                .long("diff")
+                .help("Prints what would change in each build root without writing anything")))
+        .subcommand(SubCommand::with_name("compose-files")
+            .after_help(help::after_help("compose-files"))
+            .about("Prints the docker-compose files that would be used without running anything")
+            .arg(remote_arg.clone())
+            .arg(json_arg.clone()))
+        .subcommand(SubCommand::with_name("config")
+            .after_help(help::after_help("config"))
+            .about("Inspects the configuration of the seating plan")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(SubCommand::with_name("show")
+                .after_help(help::after_help("config show"))
+                .about("Shows the effective flags of a command and where they came from")
+                .arg(Arg::with_name("command")
+                    .value_name("COMMAND")
+                    .index(1)
+                    .help("The command to show the flags for, defaults to all commands with flags"))
+                .arg(detach_arg.clone())
+                .arg(no_detach_arg)
+                .arg(wait_arg.clone())
+                .arg(no_wait_arg)
+                .arg(pull_arg.clone())
+                .arg(no_pull_arg)))
+        .subcommand(SubCommand::with_name("dress")
+            .after_help(help::after_help("dress"))
+            .about("Runs the dependencies alongside the wedding invite in the current directory")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(SubCommand::with_name("build")
+                .after_help(help::after_help("dress build"))
+                .about("Builds the dependencies and the local invite")
+                .arg(only_arg.clone())
+                .arg(except_arg.clone())
+                .arg(pull_arg.clone())
+                .arg(no_cache_arg.clone()))
+            .subcommand(SubCommand::with_name("remotebuild")
+                .after_help(help::after_help("dress remotebuild"))
+                .about("Builds the remote dependencies and the local invite")
+                .arg(only_arg.clone())
+                .arg(except_arg.clone())
+                .arg(pull_arg)
+                .arg(no_cache_arg))
+            .subcommand(SubCommand::with_name("run")
+                .after_help(help::after_help("dress run"))
+                .about("Runs the dependencies and the local invite")
+                .arg(only_arg.clone())
+                .arg(except_arg.clone())
+                .arg(detach_arg.clone()))
+            .subcommand(SubCommand::with_name("devrun")
+                .after_help(help::after_help("dress devrun"))
+                .about("Runs the dependencies and the local invite in dev mode")
+                .arg(only_arg.clone())
+                .arg(except_arg.clone()))
+            .subcommand(SubCommand::with_name("remoterun")
+                .after_help(help::after_help("dress remoterun"))
+                .about("Runs the remote dependencies and the local invite")
+                .arg(only_arg.clone())
+                .arg(except_arg.clone())
+                .arg(detach_arg))
+            .subcommand(SubCommand::with_name("install")
+                .after_help(help::after_help("dress install"))
+                .about("Clones the dependencies into the venue and prepares their builds")
+                .arg(only_arg.clone())
+                .arg(except_arg.clone())
+                .arg(jobs_arg)
+                .arg(force_arg)
+                .arg(archive_arg))
+            .subcommand(SubCommand::with_name("logs")
+                .after_help(help::after_help("dress logs"))
+                .about("Follows the logs of the dependency and local invite containers until interrupted")
+                .arg(no_follow_arg)
+                .arg(tail_arg)
+                .arg(services_arg)
+                .arg(remote_arg.clone()))
+            .subcommand(SubCommand::with_name("ps")
+                .after_help(help::after_help("dress ps"))
+                .about("Shows the state and ports of the dependency and local invite containers")
+                .arg(remote_arg.clone()))
+            .subcommand(SubCommand::with_name("teardown")
+                .after_help(help::after_help("dress teardown"))
+                .about("Tears down the dependency and local invite containers")
+                .arg(only_arg.clone())
+                .arg(except_arg.clone()))
+            .subcommand(SubCommand::with_name("remoteteardown")
+                .after_help(help::after_help("dress remoteteardown"))
+                .about("Tears down the remote dependency and local invite containers")
+                .arg(only_arg.clone())
+                .arg(except_arg.clone()))
+            .subcommand(SubCommand::with_name("setup")
+                .after_help(help::after_help("dress setup"))
+                .about("Creates the venue directory"))
+            .subcommand(SubCommand::with_name("compose-files")
+                .after_help(help::after_help("dress compose-files"))
+                .about("Prints the docker-compose files including the local invite without running anything")
+                .arg(only_arg)
+                .arg(except_arg)
+                .arg(remote_arg.conflicts_with("dev"))
+                .arg(Arg::with_name("dev")
+                    .long("dev")
+                    .help("Uses the dev runner files of the local invite"))
+                .arg(json_arg)))
+}
+
+
+/// Gets the flags that were explicitly passed in through the command line for a subcommand, a flag is
+/// ```true``` if it was passed and ```false``` if its ```--no-``` form was passed.
+/// 
+/// # Arguments
+/// * `matches` - The matches of the subcommand
+/// 
+/// # Returns
+/// * `HashMap<String, bool>` - The flags that were passed in
+fn get_cli_flags(matches: &ArgMatches) -> HashMap<String, bool> {
+    let mut cli_flags = HashMap::new();
+    for flag in command_defaults::KNOWN_FLAGS {
+        if matches.is_present(flag) {
+            cli_flags.insert(flag.to_string(), true);
+        }
+        else if matches.is_present(format!("no-{}", flag)) {
+            cli_flags.insert(flag.to_string(), false);
+        }
+    }
+    cli_flags
+}
+
+
+/// Prints the resolved compose files either one per line or as JSON.
+/// 
+/// # Arguments
+/// * `files` - The compose files to print
+/// * `json` - Whether to print the files as JSON
+/// * `cwd` - The directory relative paths are resolved against
+fn print_compose_files(files: Vec<compose_file::ComposeFileRef>, json: bool, cwd: &str) {
+    let files = compose_file::resolve_files(files, Path::new(cwd));
+    match json {
+        true => println!("{}", compose_file::render_json(&files)),
+        false => print!("{}", compose_file::render_lines(&files))
+    }
+}
+
+
+fn main() {
+    let matches = build_app().get_matches_from(translate_legacy_args(env::args().collect()));
 
     let cwd = env::current_dir().unwrap().to_str().unwrap().to_owned();
-    let command = &matches.values_of_lossy("command").unwrap()[0];
-    let file_name = match &matches.values_of_lossy("file"){
-        Some(file_name) => file_name[0].clone(),
-        None => "wedding_planner.yml".to_owned()
+    let (command, sub_matches) = matches.subcommand();
+    let sub_matches = sub_matches.unwrap();
+    let full_file_paths: Vec<String> = match sub_matches.values_of("file") {
+        Some(file_names) => file_names.filter(|file_name| !file_name.is_empty())
+            .map(|file_name| Path::new(&cwd).join(file_name).as_os_str().to_str().unwrap().to_owned())
+            .collect(),
+        None => vec![default_plan_path(command, &cwd)]
+    };
+    // a seating plan found above the directory wedp was started in reads its paths from its own directory, the
+    // paths passed on the command line are still read from the directory wedp was started in
+    let plan_root = match sub_matches.is_present("file") {
+        true => None,
+        false => Path::new(&full_file_paths[0]).parent()
+                                               .filter(|directory| *directory != Path::new(&cwd))
+                                               .map(Path::to_path_buf)
     };
-    let full_file_path = Path::new(&cwd).join(&file_name).as_os_str().to_str().unwrap().to_owned();
-    println!("Running {} with file {}", command, full_file_path);
+    let full_file_path = full_file_paths.join(", ");
+    let cli_flags = get_cli_flags(sub_matches);
+    let dry_run = sub_matches.is_present("dry-run");
+    let arch = match arch_override(sub_matches.value_of("arch"), env::var(ARCH_ENV).ok()) {
+        Ok(arch) => arch,
+        Err(error) => exit_with(WedpError::UnsupportedCpu(error))
+    };
+    let engine = sub_matches.value_of("engine").map(|engine| ContainerEngine::from_name(engine).unwrap());
+    let compose_command = sub_matches.value_of("compose-cmd").map(String::from);
+    let skip_missing = sub_matches.is_present("skip-missing");
+    let filter = get_filter(sub_matches);
+    if let Some(timeout) = sub_matches.value_of("timeout-per-command") {
+        set_timeout_per_command(Duration::from_secs(timeout.parse().unwrap()));
+    }
+    interpolate::set_interpolate(!sub_matches.is_present("no-interpolate"));
+    let log_level = LogLevel::from_flags(sub_matches.is_present("verbose"), sub_matches.is_present("quiet"));
+    logging::set_log_level(log_level);
+    if log_level == LogLevel::Verbose {
+        register_secret_env();
+    }
+    if let Some(trace_script) = sub_matches.value_of("trace-script") {
+        register_secret_env();
+        set_trace_script(Path::new(&cwd).join(trace_script));
+    }
+    set_operation_limits(OperationLimits {
+        docker: sub_matches.value_of("max-docker-ops").map(|limit| limit.parse().unwrap()),
+        git: sub_matches.value_of("max-git-ops").map(|limit| limit.parse().unwrap())
+    });
+    if logging::enabled(LogLevel::Normal) {
+        eprintln!("Running {} with file {}", command, full_file_path);
+    }
 
-    match command.as_ref() {
+    match command {
 
         "build" => {
-            match Runner::new(full_file_path) {
-                Ok(runner) => runner.build_dependencies(),
-                Err(error) => println!("{}", error)
+            match load_runner(full_file_paths, dry_run, arch, filter, engine, compose_command, skip_missing, plan_root.as_deref()) {
+                Ok(runner) => {
+                    let flags = runner.resolve_flags(command, &cli_flags);
+                    let start = SystemClock.now();
+                    let built = runner.build_dependencies(flags.get("pull"), sub_matches.is_present("no-cache"));
+                    runner.record_usage(|stats, names| stats.record_build(names, SystemClock.now().duration_since(start)));
+                    if let Err(error) = built {
+                        exit_with(error);
+                    }
+                },
+                Err(error) => exit_with(error)
             }
         },
-        "run" => {
-            match Runner::new(full_file_path) {
-                Ok(runner) => runner.run_dependencies(),
-                Err(error) => println!("{}", error)
+        "remotebuild" => {
+            match load_runner(full_file_paths, dry_run, arch, filter, engine, compose_command, skip_missing, plan_root.as_deref()) {
+                Ok(runner) => {
+                    let flags = runner.resolve_flags(command, &cli_flags);
+                    let start = SystemClock.now();
+                    let built = runner.build_remote_dependencies(flags.get("pull"), sub_matches.is_present("no-cache"));
+                    runner.record_usage(|stats, names| stats.record_build(names, SystemClock.now().duration_since(start)));
+                    if let Err(error) = built {
+                        exit_with(error);
+                    }
+                },
+                Err(error) => exit_with(error)
             }
         },
-        "run-d" => {
-            match Runner::new(full_file_path) {
-                Ok(runner) => runner.run_dependencies_background(),
-                Err(error) => println!("{}", error)
+        "run" => {
+            let runner = load_runner(full_file_paths, dry_run, arch, filter, engine, compose_command, skip_missing, plan_root.as_deref())
+                .map(|runner| runner.with_force_recreate(sub_matches.is_present("force-recreate")));
+            match runner {
+                Ok(runner) => {
+                    runner.record_usage(|stats, names| stats.record_run(names, &SystemClock));
+                    if let Some(service) = sub_matches.value_of("abort-on-exit") {
+                        match runner.run_dependencies_abort_on_exit(service, Path::new(&cwd)) {
+                            Ok(code) => process::exit(code),
+                            Err(error) => exit_with(WedpError::Failed(error))
+                        }
+                    }
+                    let flags = runner.resolve_flags(command, &cli_flags);
+                    if sub_matches.is_present("then-logs") && !flags.get("detach") {
+                        eprintln!("--then-logs needs --detach");
+                        process::exit(1);
+                    }
+                    match flags.get("detach") {
+                        true => {
+                            let started = runner.run_dependencies_background(flags.get("wait"), Path::new(&cwd));
+                            if !started {
+                                process::exit(1);
+                            }
+                            if sub_matches.is_present("then-logs") {
+                                process::exit(runner.follow_started_logs(false));
+                            }
+                        },
+                        false => if let Err(error) = runner.run_dependencies(Path::new(&cwd)) {
+                            exit_with(error);
+                        }
+                    }
+                },
+                Err(error) => exit_with(error)
             }
         },
         "remoterun" => {
-            match Runner::new(full_file_path) {
-                Ok(runner) => runner.run_remote_dependencies(),
-                Err(error) => println!("{}", error)
+            let runner = load_runner(full_file_paths, dry_run, arch, filter, engine, compose_command, skip_missing, plan_root.as_deref())
+                .map(|runner| runner.with_force_recreate(sub_matches.is_present("force-recreate")));
+            match runner {
+                Ok(runner) => {
+                    runner.record_usage(|stats, names| stats.record_run(names, &SystemClock));
+                    let flags = runner.resolve_flags(command, &cli_flags);
+                    if sub_matches.is_present("then-logs") && !flags.get("detach") {
+                        eprintln!("--then-logs needs --detach");
+                        process::exit(1);
+                    }
+                    match flags.get("detach") {
+                        true => {
+                            let started = runner.run_remote_dependencies_background(flags.get("wait"), Path::new(&cwd));
+                            if !started {
+                                process::exit(1);
+                            }
+                            if sub_matches.is_present("then-logs") {
+                                process::exit(runner.follow_started_logs(true));
+                            }
+                        },
+                        false => if let Err(error) = runner.run_remote_dependencies(Path::new(&cwd)) {
+                            exit_with(error);
+                        }
+                    }
+                },
+                Err(error) => exit_with(error)
             }
         },
-        "remoterun-d" => {
-            match Runner::new(full_file_path) {
-                Ok(runner) => runner.run_remote_dependencies_background(),
-                Err(error) => println!("{}", error)
+        "install" => {
+            let lock_path = lockfile::lock_path(&full_file_paths[0]);
+            let locked = sub_matches.is_present("locked");
+            let runner = load_runner(full_file_paths, dry_run, arch, filter, engine, compose_command, skip_missing, plan_root.as_deref())
+                .and_then(|runner| match locked {
+                    true => runner.with_lockfile(&lock_path).map_err(WedpError::InvalidConfig),
+                    false => Ok(runner)
+                });
+            match runner {
+                Ok(runner) => {
+                    if sub_matches.is_present("verify-refs") {
+                        let problems = runner.verify_refs();
+                        if !problems.is_empty() {
+                            for problem in &problems {
+                                println!("{}", problem);
+                            }
+                            eprintln!("{} branches do not exist on their remotes, nothing was installed", problems.len());
+                            process::exit(1);
+                        }
+                    }
+                    let jobs = get_jobs(Some(sub_matches));
+                    let force = sub_matches.is_present("force");
+                    let reports = match sub_matches.value_of("archive") {
+                        Some(archive) => runner.install_from_archive(archive, jobs, force, Path::new(&cwd)),
+                        None => runner.install_dependencies(jobs, force, Path::new(&cwd))
+                    };
+                    runner.record_usage(|stats, _| stats.record_installs(&runner::installed_names(&reports)));
+                    print!("{}", runner::render_install_summary(&reports));
+                    if let Err(error) = runner::check_install_reports(&reports) {
+                        exit_with(WedpError::Failed(error));
+                    }
+                    // a dry run clones nothing so there are no commits to lock
+                    if !locked && !dry_run && !sub_matches.is_present("archive") {
+                        if let Err(error) = runner.lock(&lock_path, Path::new(&cwd)) {
+                            exit_with(WedpError::Failed(error));
+                        }
+                    }
+                },
+                Err(error) => exit_with(error)
             }
         },
-        "install" => {
-            match Runner::new(full_file_path) {
-                Ok(runner) => runner.install_dependencies(),
-                Err(error) => println!("{}", error)
+        "lock" => {
+            let lock_path = lockfile::lock_path(&full_file_paths[0]);
+            match load_runner(full_file_paths, dry_run, arch, filter, engine, compose_command, skip_missing, plan_root.as_deref()) {
+                Ok(runner) => match runner.lock(&lock_path, Path::new(&cwd)) {
+                    Ok(lockfile) => println!("Locked {} dependencies in {}", lockfile.attendees.len(), lock_path.to_string_lossy()),
+                    Err(error) => exit_with(WedpError::Failed(error))
+                },
+                Err(error) => exit_with(error)
+            }
+        },
+        "update" => {
+            match load_runner(full_file_paths, dry_run, arch, filter, engine, compose_command, skip_missing, plan_root.as_deref()) {
+                Ok(runner) => {
+                    let reports = runner.update_installed(get_jobs(Some(sub_matches)), sub_matches.is_present("force"), Path::new(&cwd));
+                    print!("{}", runner::render_update_summary(&reports));
+                    if let Err(error) = runner::check_install_reports(&reports) {
+                        exit_with(WedpError::Failed(error));
+                    }
+                },
+                Err(error) => exit_with(error)
+            }
+        },
+        "ps" => {
+            match load_runner(full_file_paths, dry_run, arch, filter, engine, compose_command, skip_missing, plan_root.as_deref()) {
+                Ok(runner) => {
+                    if sub_matches.is_present("raw") {
+                        match runner.ps_dependencies(sub_matches.is_present("remote")) {
+                            Ok(code) => process::exit(code),
+                            Err(error) => exit_with(WedpError::Failed(error))
+                        }
+                    }
+                    match runner.ps(sub_matches.is_present("remote")) {
+                        Ok(statuses) => print!("{}", container_status::render_ps_table(&statuses)),
+                        Err(error) => exit_with(WedpError::Failed(error))
+                    }
+                },
+                Err(error) => exit_with(error)
+            }
+        },
+        "du" => {
+            match load_runner(full_file_paths, dry_run, arch, filter, engine, compose_command, skip_missing, plan_root.as_deref()) {
+                Ok(runner) => match runner.disk_usage() {
+                    Ok(sizes) => print!("{}", disk_usage::render_du_table(&sizes)),
+                    Err(error) => exit_with(WedpError::Failed(error))
+                },
+                Err(error) => exit_with(error)
+            }
+        },
+        "stats" => {
+            match load_runner(full_file_paths, dry_run, arch, filter, engine, compose_command, skip_missing, plan_root.as_deref()) {
+                Ok(runner) => {
+                    let result = match sub_matches.is_present("reset") {
+                        true => runner.reset_usage_stats().map(|_| "Usage stats reset\n".to_string()),
+                        false => runner.usage_stats().map(|stats| match sub_matches.is_present("json") {
+                            true => format!("{}\n", usage_stats::render_stats_json(&stats)),
+                            false => {
+                                let names: Vec<String> = runner.seating_plan.attendees.iter().map(|dependency| dependency.name.clone()).collect();
+                                usage_stats::render_stats_table(&stats, &names)
+                            }
+                        })
+                    };
+                    match result {
+                        Ok(output) => print!("{}", output),
+                        Err(error) => exit_with(WedpError::Failed(error))
+                    }
+                },
+                Err(error) => exit_with(error)
+            }
+        },
+        "vendor" => {
+            match load_runner(full_file_paths, dry_run, arch, filter, engine, compose_command, skip_missing, plan_root.as_deref()) {
+                Ok(runner) => {
+                    let archive = sub_matches.value_of("archive").or(sub_matches.value_of("output")).unwrap();
+                    if let Err(error) = runner.vendor(archive, get_jobs(Some(sub_matches)), Path::new(&cwd)) {
+                        exit_with(WedpError::Failed(error));
+                    }
+                },
+                Err(error) => exit_with(error)
+            }
+        },
+        "logs" => {
+            match load_runner(full_file_paths, dry_run, arch, filter, engine, compose_command, skip_missing, plan_root.as_deref()) {
+                Ok(runner) => {
+                    let follow = !sub_matches.is_present("no-follow");
+                    match runner.logs(sub_matches.is_present("remote"), follow, get_tail(sub_matches), &get_services(sub_matches)) {
+                        Ok(code) => process::exit(code),
+                        Err(error) => exit_with(WedpError::Failed(error))
+                    }
+                },
+                Err(error) => exit_with(error)
             }
         },
-        "teardown" => {
-            match Runner::new(full_file_path) {
-                Ok(runner) => runner.teardown_dependencies(),
-                Err(error) => println!("{}", error)
+        "teardown" | "remoteteardown" => {
+            let runner = match load_runner(full_file_paths, dry_run, arch, filter, engine, compose_command, skip_missing, plan_root.as_deref()) {
+                Ok(runner) => runner,
+                Err(error) => exit_with(error)
+            };
+            let torn_down = match command {
+                "teardown" => runner.teardown_dependencies(),
+                _ => runner.teardown_remote_dependencies()
+            };
+            let torn_down = match torn_down {
+                Ok(torn_down) => torn_down,
+                Err(error) => exit_with(WedpError::Failed(error))
+            };
+            if !sub_matches.is_present("remove-venue") {
+                return
+            }
+            if !torn_down {
+                eprintln!("The containers were not torn down, leaving the venue as it is");
+                process::exit(1);
+            }
+            let errors = runner.remove_venue_dependencies();
+            if !errors.is_empty() {
+                for error in &errors {
+                    eprintln!("{}", error);
+                }
+                process::exit(1);
             }
         },
-        "remoteteardown" => {
-            match Runner::new(full_file_path) {
-                Ok(runner) => runner.teardown_remote_dependencies(),
-                Err(error) => println!("{}", error)
+        "clean" => {
+            let runner = match load_runner(full_file_paths, dry_run, arch, filter, engine, compose_command, skip_missing, plan_root.as_deref()) {
+                Ok(runner) => runner,
+                Err(error) => exit_with(error)
+            };
+            let home = env::var_os("HOME").map(PathBuf::from);
+            if let Err(error) = runner.clean_venue(sub_matches.is_present("all"), Path::new(&cwd), home.as_deref()) {
+                exit_with(WedpError::Failed(error));
             }
         },
         "setup" => {
-            match Runner::new(full_file_path) {
-                Ok(runner) => runner.create_venue(),
-                Err(error) => println!("{}", error)
+            match load_runner(full_file_paths, dry_run, arch, filter, engine, compose_command, skip_missing, plan_root.as_deref()) {
+                Ok(runner) => if let Err(error) = runner.create_venue() {
+                    exit_with(error);
+                },
+                Err(error) => exit_with(error)
+            }
+        },
+        "status" => {
+            match load_runner(full_file_paths, dry_run, arch, filter, engine, compose_command, skip_missing, plan_root.as_deref()) {
+                Ok(runner) => print!("{}", runner::render_status_table(&runner.status_dependencies())),
+                Err(error) => exit_with(error)
+            }
+        },
+        "demo" => {
+            let directory = Path::new(&cwd).join(sub_matches.value_of("directory").unwrap());
+            let demo = demo::Demo::new(directory);
+            let (command_runner, file_handle): (Box<dyn CoreRunner>, Box<dyn CoreFileHandle>) = match dry_run {
+                true => (traced(Box::new(DryRunRunner::stdout())), Box::new(DryRunFileHandle::stdout())),
+                false => (traced(Box::new(CommandRunner)), Box::new(FileHandle {}))
+            };
+            let result = match sub_matches.is_present("cleanup") {
+                true => demo.cleanup(file_handle.as_ref()),
+                false => demo.generate(command_runner.as_ref(), file_handle.as_ref())
+                             .map(|_| print!("{}", demo.instructions()))
+            };
+            if let Err(error) = result {
+                exit_with(WedpError::Failed(error));
+            }
+        },
+        "init" => {
+            let template = scaffold::Template::from_name(sub_matches.value_of("template").unwrap())
+                                               .unwrap_or_else(|error| exit_with(WedpError::InvalidConfig(error)));
+            let file_handle: Box<dyn CoreFileHandle> = match dry_run {
+                true => Box::new(DryRunFileHandle::stdout()),
+                false => Box::new(FileHandle {})
+            };
+            match scaffold::scaffold(template, Path::new(&cwd), sub_matches.is_present("force"), file_handle.as_ref()) {
+                Ok(path) => println!("Created {}", path.to_string_lossy()),
+                Err(error) => exit_with(WedpError::Failed(error))
+            }
+        },
+        "git" => {
+            let name = sub_matches.value_of("dependency").unwrap();
+            let args = sub_matches.values_of("args").map(|args| args.map(String::from).collect()).unwrap_or_default();
+            match load_runner(full_file_paths, dry_run, arch, filter, engine, compose_command, skip_missing, plan_root.as_deref()) {
+                Ok(runner) => match runner.run_git_command(name, args) {
+                    Ok(code) => process::exit(code),
+                    Err(error) => exit_with(WedpError::Failed(error))
+                },
+                Err(error) => exit_with(error)
+            }
+        },
+        "bump-branch" => {
+            let from = sub_matches.value_of("from").unwrap();
+            let to = sub_matches.value_of("to").unwrap();
+            let only: Vec<String> = sub_matches.values_of("only")
+                                               .map(|names| names.map(String::from).collect())
+                                               .unwrap_or_default();
+            if full_file_paths.len() > 1 {
+                exit_with(WedpError::InvalidConfig("bump-branch rewrites a single seating plan, pass only one -f".to_string()));
+            }
+            match load_runner(full_file_paths.clone(), dry_run, arch, AttendeeFilter::All, engine, compose_command, skip_missing, plan_root.as_deref()) {
+                Ok(mut runner) => {
+                    let changed = match runner.bump_branch(&full_file_path, from, to, &only) {
+                        Ok(changed) => changed,
+                        Err(error) => exit_with(WedpError::Failed(error))
+                    };
+                    print!("{}", bump_branch::render_bump_report(&changed, from, to));
+                    if sub_matches.is_present("checkout") && !changed.is_empty() {
+                        let reports = runner.update_dependencies(&changed, get_jobs(Some(sub_matches)), Path::new(&cwd));
+                        print!("{}", runner::render_install_summary(&reports));
+                        if let Err(error) = runner::check_install_reports(&reports) {
+                            exit_with(WedpError::Failed(error));
+                        }
+                    }
+                },
+                Err(error) => exit_with(error)
+            }
+        },
+        "edit" => {
+            let name = sub_matches.value_of("dependency").unwrap();
+            match load_runner(full_file_paths, dry_run, arch, filter, engine, compose_command, skip_missing, plan_root.as_deref()) {
+                Ok(runner) => {
+                    let interactive = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
+                    let result = runner.wedding_invite_path(name).and_then(|path| {
+                        edit::check_interactive(interactive || dry_run, &path)?;
+                        let editor = edit::resolve_editor(env::var("VISUAL").ok(), env::var("EDITOR").ok())?;
+                        edit::edit_wedding_invite(&path, &editor, runner.command_runner.as_ref(), &mut || {
+                            edit::ask_to_reopen(&mut std::io::stdin().lock(), &mut std::io::stdout())
+                        })
+                    });
+                    if let Err(error) = result {
+                        exit_with(WedpError::Failed(error));
+                    }
+                },
+                Err(error) => exit_with(error)
             }
         },
-        _ => {
+        "validate" => {
+            let (runner, mut problems) = match Runner::new_lenient(full_file_paths) {
+                Ok((runner, problems)) => (runner.with_plan_root(plan_root.as_deref()), problems),
+                Err(error) => exit_with(error)
+            };
+            problems.extend(runner.validate());
+            if !sub_matches.is_present("no-verify-refs") {
+                problems.extend(runner.verify_refs());
+            }
+            if !problems.is_empty() {
+                for problem in &problems {
+                    println!("{}", problem);
+                }
+                eprintln!("{} problems found in {}", problems.len(), full_file_path);
+                process::exit(1);
+            }
+            println!("{} is valid", full_file_path);
+        },
+        "doctor" => {
+            let runner = match load_runner(full_file_paths, dry_run, arch, filter, engine, compose_command, skip_missing, plan_root.as_deref()) {
+                Ok(runner) => runner,
+                Err(error) => exit_with(error)
+            };
+            let mut findings = runner.doctor(&cwd);
+            if sub_matches.is_present("fix") {
+                for finding in &findings {
+                    if let Some(fix) = &finding.fix {
+                        match doctor::apply(fix, runner.file_handle.as_ref()) {
+                            Ok(_) => println!("fixed: {}", finding.message),
+                            Err(error) => println!("not fixed: {}: {}", finding.message, error)
+                        }
+                    }
+                }
+                findings = runner.doctor(&cwd);
+            }
+            if !findings.is_empty() {
+                print!("{}", doctor::render_findings(&findings));
+                let fixable = findings.iter().filter(|finding| finding.fix.is_some()).count();
+                eprintln!("{} problems found in {}, {} can be fixed with --fix", findings.len(), full_file_path, fixable);
+                process::exit(1);
+            }
+            println!("No problems found in {}", full_file_path);
+        },
+        "check" => {
+            let runner = match load_runner(full_file_paths.clone(), dry_run, arch, filter, engine, compose_command, skip_missing, plan_root.as_deref()) {
+                Ok(runner) => runner,
+                Err(error) => exit_with(error)
+            };
+            let remote = sub_matches.is_present("remote");
+            if !sub_matches.is_present("no-daemon") {
+                if !runner.check_compose_config(remote) {
+                    process::exit(1);
+                }
+                return
+            }
+            let problems = runner.inspect_compose_files(remote);
+            if !problems.is_empty() {
+                for problem in &problems {
+                    println!("{}", problem);
+                }
+                eprintln!("{} problems found in the compose files of {}", problems.len(), full_file_path);
+                process::exit(1);
+            }
+            println!("The compose files of {} passed the static check", full_file_path);
+        },
+        "prepare" => {
+            match load_runner(full_file_paths, dry_run, arch, filter, engine, compose_command, skip_missing, plan_root.as_deref()) {
+                Ok(runner) => match sub_matches.is_present("diff") {
+                    true => {
+                        let (changes, errors) = runner.diff_build_files(runner.file_handle.as_ref(), Path::new(&cwd));
+                        print!("{}", changes);
+                        if !errors.is_empty() {
+                            exit_with(WedpError::Failed(errors.join("\n")));
+                        }
+                    },
+                    false => if let Err(error) = runner.prepare_build_files(runner.file_handle.as_ref(), Path::new(&cwd)) {
+                        exit_with(WedpError::Failed(error));
+                    }
+                },
+                Err(error) => exit_with(error)
+            }
+        },
+        "compose-files" => {
+            match load_runner(full_file_paths, dry_run, arch, filter, engine, compose_command, skip_missing, plan_root.as_deref()) {
+                Ok(runner) => {
+                    match runner.get_compose_files(sub_matches.is_present("remote")) {
+                        Ok(files) => print_compose_files(files, sub_matches.is_present("json"), &cwd),
+                        Err(error) => exit_with(WedpError::Failed(error))
+                    }
+                },
+                Err(error) => exit_with(error)
+            }
+        },
+        "config" => {
+            let show_matches = sub_matches.subcommand_matches("show").unwrap();
+            let cli_flags = get_cli_flags(show_matches);
+            match load_runner(full_file_paths, dry_run, arch, filter, engine, compose_command, skip_missing, plan_root.as_deref()) {
+                Ok(runner) => {
+                    let commands = match show_matches.value_of("command") {
+                        Some(shown_command) => vec![shown_command],
+                        None => vec!["build", "remotebuild", "run", "remoterun"]
+                    };
+                    for shown_command in commands {
+                        print!("{}", runner.resolve_flags(shown_command, &cli_flags).render());
+                    }
+                },
+                Err(error) => exit_with(error)
+            }
+        },
+        "dress" => {
+            let (dress_command, dress_matches) = sub_matches.subcommand();
+            let detach = dress_matches.map(|dress_matches| dress_matches.is_present("detach")).unwrap_or(false);
             let wedding_invite_file = "wedding_invite.yml";
             let wedding_invite_path = Path::new(&cwd).join(wedding_invite_file).as_os_str().to_str().unwrap().to_owned();
-            dress_rehearsal_factory(command.to_string(), full_file_path, wedding_invite_path, cwd);
-        }
+            if dress_command == "compose-files" {
+                let dress_matches = dress_matches.unwrap();
+                let dress_rehearsal = DressRehearsal::new(full_file_paths, wedding_invite_path, &cwd)
+                    .and_then(|dress_rehearsal| dress_rehearsal.with_filter(get_filter(dress_matches)));
+                match dress_rehearsal {
+                    Ok(dress_rehearsal) => {
+                        let files = match dress_matches.is_present("dev") {
+                            true => dress_rehearsal.get_compose_files_dev(),
+                            false => dress_rehearsal.get_compose_files(dress_matches.is_present("remote"))
+                        };
+                        match files {
+                            Ok(files) => print_compose_files(files, dress_matches.is_present("json"), &cwd),
+                            Err(error) => exit_with(WedpError::Failed(error))
+                        }
+                    },
+                    Err(error) => exit_with(error)
+                }
+                return
+            }
+            let force = dress_matches.map(|dress_matches| dress_matches.is_present("force")).unwrap_or(false);
+            let options = DressOptions {
+                detach,
+                dry_run,
+                jobs: get_jobs(dress_matches),
+                force,
+                arch,
+                engine,
+                compose_command,
+                skip_missing,
+                remote: dress_matches.map(|dress_matches| dress_matches.is_present("remote")).unwrap_or(false),
+                follow: !dress_matches.map(|dress_matches| dress_matches.is_present("no-follow")).unwrap_or(false),
+                tail: dress_matches.and_then(get_tail),
+                services: dress_matches.map(get_services).unwrap_or_default(),
+                archive: dress_matches.and_then(|dress_matches| dress_matches.value_of("archive")).map(String::from),
+                filter: dress_matches.map(get_filter).unwrap_or_default(),
+                pull: dress_matches.map(|dress_matches| dress_matches.is_present("pull")).unwrap_or(false),
+                no_cache: dress_matches.map(|dress_matches| dress_matches.is_present("no-cache")).unwrap_or(false)
+            };
+            if let Err(error) = dress_rehearsal_factory(dress_command.to_string(), options, full_file_paths, wedding_invite_path, cwd) {
+                exit_with(error.into());
+            }
+        },
+        _ => unreachable!("clap rejects unknown subcommands")
     }
 }
 
 
-// test integration
 #[cfg(test)]
 mod main_tests {
 
-    // use assert_cmd::Command;
-    // use predicates::prelude::*;
-    // use std::fs;
-
-    // #[test]
-    // fn dies_no_args() {
-    //     let mut cmd = Command::cargo_bin("wedp").unwrap();
-    //     cmd.assert()
-    //         .failure()
-    //         .stderr(predicate::str::contains("USAGE"));
-    // }
-
-    // #[test]
-    // fn runs() {
-    //     let mut cmd = Command::cargo_bin("wedp").unwrap();
-    //     cmd.arg("hello").assert().success();
-    // }
-    //
-    // #[test]
-    // fn hello1() {
-    //     // let outfile = "./hello1.txt";
-    //     // let expected = fs::read_to_string(outfile).unwrap();
-    //     let mut cmd = Command::cargo_bin("wedp").unwrap();
-    //     cmd.arg("Hello there").assert().success().stdout("Hello there\n\n");
-    // }
-    //
-    // #[test]
-    // fn hello2() {
-    //     // let outfile = "./hello1.txt";
-    //     // let expected = fs::read_to_string(outfile).unwrap();
-    //     let mut cmd = Command::cargo_bin("wedp").unwrap();
-    //     cmd.args(vec!["Hello", "there"]).assert().success().stdout("Hello there\n\n");
-    // }
-
-}
\ No newline at end of file
+    use super::*;
+
+    fn to_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_build_subcommand() {
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "build", "--pull", "-f", "plan.yml"])).unwrap();
+        let (command, sub_matches) = matches.subcommand();
+        let sub_matches = sub_matches.unwrap();
+        assert_eq!(command, "build");
+        assert_eq!(sub_matches.value_of("file"), Some("plan.yml"));
+        assert_eq!(get_cli_flags(sub_matches).get("pull"), Some(&true));
+    }
+
+    #[test]
+    fn test_remotebuild_subcommand() {
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "remotebuild", "--no-cache", "--only", "auth", "-f", "plan.yml"])).unwrap();
+        let (command, sub_matches) = matches.subcommand();
+        let sub_matches = sub_matches.unwrap();
+        assert_eq!(command, "remotebuild");
+        assert!(sub_matches.is_present("no-cache"));
+        assert_eq!(get_filter(sub_matches), AttendeeFilter::Only(vec!["auth".to_string()]));
+    }
+
+    #[test]
+    fn test_run_subcommand() {
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "-f", "plan.yml", "run", "-d", "--wait"])).unwrap();
+        let (command, sub_matches) = matches.subcommand();
+        let cli_flags = get_cli_flags(sub_matches.unwrap());
+        assert_eq!(command, "run");
+        assert_eq!(sub_matches.unwrap().value_of("file"), Some("plan.yml"));
+        assert_eq!(cli_flags.get("detach"), Some(&true));
+        assert_eq!(cli_flags.get("wait"), Some(&true));
+        assert_eq!(cli_flags.get("pull"), None);
+    }
+
+    #[test]
+    fn test_cli_flags_override_command_defaults() {
+        let defaults: command_defaults::CommandDefaults = [
+            ("run".to_string(), [("detach".to_string(), true), ("wait".to_string(), true)].into_iter().collect()),
+            ("build".to_string(), [("pull".to_string(), true)].into_iter().collect()),
+        ].into_iter().collect();
+        let cases: Vec<(&[&str], &str, &str, bool, command_defaults::FlagSource)> = vec![
+            (&["wedp", "run"], "run", "detach", true, command_defaults::FlagSource::SeatingPlan),
+            (&["wedp", "run", "--no-detach"], "run", "detach", false, command_defaults::FlagSource::CommandLine),
+            (&["wedp", "run", "--no-wait"], "run", "wait", false, command_defaults::FlagSource::CommandLine),
+            (&["wedp", "remoterun", "--no-detach"], "run", "detach", false, command_defaults::FlagSource::CommandLine),
+            (&["wedp", "build", "--no-pull"], "build", "pull", false, command_defaults::FlagSource::CommandLine),
+            (&["wedp", "build", "--pull"], "build", "pull", true, command_defaults::FlagSource::CommandLine),
+            (&["wedp", "config", "show", "run", "--no-detach"], "run", "detach", false, command_defaults::FlagSource::CommandLine),
+        ];
+
+        for (args, command, flag, value, source) in cases {
+            let matches = build_app().get_matches_from_safe(to_args(args)).unwrap();
+            let sub_matches = matches.subcommand().1.unwrap();
+            let sub_matches = sub_matches.subcommand_matches("show").unwrap_or(sub_matches);
+            let resolved = command_defaults::resolve_flags(command, &get_cli_flags(sub_matches), &Some(defaults.clone()));
+            let resolved = resolved.flags.iter().find(|resolved| resolved.name == flag).unwrap();
+            assert_eq!((resolved.value, resolved.source), (value, source), "{:?}", args);
+        }
+        assert!(build_app().get_matches_from_safe(to_args(&["wedp", "run", "-d", "--no-detach"])).is_err());
+    }
+
+    #[test]
+    fn test_run_abort_on_exit() {
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "run", "--abort-on-exit", "test_runner"])).unwrap();
+        let run_matches = matches.subcommand_matches("run").unwrap();
+        assert_eq!(run_matches.value_of("abort-on-exit"), Some("test_runner"));
+
+        let result = build_app().get_matches_from_safe(to_args(&["wedp", "run", "-d", "--abort-on-exit", "test_runner"]));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_compose_files_subcommand() {
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "compose-files", "--remote", "--json"])).unwrap();
+        let compose_matches = matches.subcommand_matches("compose-files").unwrap();
+        assert!(compose_matches.is_present("remote"));
+        assert!(compose_matches.is_present("json"));
+
+        let result = build_app().get_matches_from_safe(to_args(&["wedp", "dress", "compose-files", "--remote", "--dev"]));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_timeout_per_command() {
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "--timeout-per-command", "300", "install"])).unwrap();
+        let install_matches = matches.subcommand_matches("install").unwrap();
+        assert_eq!(install_matches.value_of("timeout-per-command"), Some("300"));
+
+        let result = build_app().get_matches_from_safe(to_args(&["wedp", "install", "--timeout-per-command", "0"]));
+        assert!(result.is_err());
+        let result = build_app().get_matches_from_safe(to_args(&["wedp", "install", "--timeout-per-command", "5m"]));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_max_ops() {
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "--max-docker-ops", "2", "install", "--max-git-ops", "4"])).unwrap();
+        let install_matches = matches.subcommand_matches("install").unwrap();
+        assert_eq!(install_matches.value_of("max-docker-ops"), Some("2"));
+        assert_eq!(install_matches.value_of("max-git-ops"), Some("4"));
+
+        let result = build_app().get_matches_from_safe(to_args(&["wedp", "install", "--max-git-ops", "0"]));
+        assert!(result.is_err());
+        assert_eq!(
+            translate_legacy_args(to_args(&["wedp", "--max-docker-ops", "2", "dressrun"])),
+            to_args(&["wedp", "--max-docker-ops", "2", "dress", "run"])
+        );
+    }
+
+    #[test]
+    fn test_validate_subcommand() {
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "validate", "-f", "plan.yml"])).unwrap();
+        assert_eq!(matches.subcommand_name(), Some("validate"));
+
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "validate", "--no-verify-refs"])).unwrap();
+        assert!(matches.subcommand_matches("validate").unwrap().is_present("no-verify-refs"));
+    }
+
+    #[test]
+    fn test_check_subcommand() {
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "check", "--no-daemon", "--remote"])).unwrap();
+        let check_matches = matches.subcommand_matches("check").unwrap();
+        assert!(check_matches.is_present("no-daemon"));
+        assert!(check_matches.is_present("remote"));
+    }
+
+    #[test]
+    fn test_install_verify_refs() {
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "install", "--verify-refs"])).unwrap();
+        assert!(matches.subcommand_matches("install").unwrap().is_present("verify-refs"));
+
+        let result = build_app().get_matches_from_safe(to_args(&["wedp", "install", "--verify-refs", "--archive", "bundle.tar.gz"]));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_git_subcommand() {
+        let args = to_args(&["wedp", "git", "auth", "-f", "plan.yml", "--", "log", "-f", "--oneline"]);
+        let matches = build_app().get_matches_from_safe(translate_legacy_args(args)).unwrap();
+        let git_matches = matches.subcommand_matches("git").unwrap();
+        assert_eq!(git_matches.value_of("dependency"), Some("auth"));
+        assert_eq!(git_matches.value_of("file"), Some("plan.yml"));
+        assert_eq!(git_matches.values_of("args").unwrap().collect::<Vec<&str>>(), vec!["log", "-f", "--oneline"]);
+    }
+
+    #[test]
+    fn test_prepare_subcommand() {
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "prepare", "--diff"])).unwrap();
+        let prepare_matches = matches.subcommand_matches("prepare").unwrap();
+        assert!(prepare_matches.is_present("diff"));
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "--dry-run", "dress", "run", "-d"])).unwrap();
+        let dress_matches = matches.subcommand_matches("dress").unwrap();
+        assert!(dress_matches.is_present("dry-run"));
+        assert!(dress_matches.subcommand_matches("run").unwrap().is_present("dry-run"));
+
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "teardown", "--dry-run"])).unwrap();
+        assert!(matches.subcommand_matches("teardown").unwrap().is_present("dry-run"));
+    }
+
+    #[test]
+    fn test_install_jobs() {
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "install", "--jobs", "4"])).unwrap();
+        assert_eq!(get_jobs(matches.subcommand_matches("install")), 4);
+
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "dress", "install", "-j", "2"])).unwrap();
+        let dress_matches = matches.subcommand_matches("dress").unwrap();
+        assert_eq!(get_jobs(dress_matches.subcommand_matches("install")), 2);
+
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "install"])).unwrap();
+        assert_eq!(get_jobs(matches.subcommand_matches("install")), runner::default_install_jobs());
+
+        let result = build_app().get_matches_from_safe(to_args(&["wedp", "install", "--jobs", "0"]));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_edit() {
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "edit", "auth", "-f", "plan.yml"])).unwrap();
+        assert_eq!(matches.subcommand_matches("edit").unwrap().value_of("dependency"), Some("auth"));
+
+        assert!(build_app().get_matches_from_safe(to_args(&["wedp", "edit"])).is_err());
+    }
+
+    #[test]
+    fn test_install_force() {
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "install", "--force"])).unwrap();
+        assert!(matches.subcommand_matches("install").unwrap().is_present("force"));
+
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "dress", "install", "--force"])).unwrap();
+        let dress_matches = matches.subcommand_matches("dress").unwrap();
+        assert!(dress_matches.subcommand_matches("install").unwrap().is_present("force"));
+
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "install"])).unwrap();
+        assert!(!matches.subcommand_matches("install").unwrap().is_present("force"));
+    }
+
+    #[test]
+    fn test_arch() {
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "prepare", "--arch", "aarch64"])).unwrap();
+        assert_eq!(matches.subcommand_matches("prepare").unwrap().value_of("arch"), Some("aarch64"));
+
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "--arch", "x86_64", "dress", "build"])).unwrap();
+        let dress_matches = matches.subcommand_matches("dress").unwrap();
+        assert_eq!(dress_matches.subcommand_matches("build").unwrap().value_of("arch"), Some("x86_64"));
+
+        let result = build_app().get_matches_from_safe(to_args(&["wedp", "install", "--arch", "amd64"]));
+        assert!(result.unwrap_err().message.contains("Unsupported CPU type: amd64"));
+    }
+
+    #[test]
+    fn test_install_subcommand() {
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "install"])).unwrap();
+        let (command, sub_matches) = matches.subcommand();
+        assert_eq!(command, "install");
+        assert_eq!(sub_matches.unwrap().value_of("file"), None);
+    }
+
+    #[test]
+    fn test_dress_subcommand() {
+        let matches = build_app().get_matches_from_safe(to_args(&["wedp", "dress", "run", "-d"])).unwrap();
+        let dress_matches = matches.subcommand_matches("dress").unwrap();
+        let (dress_command, run_matches) = dress_matches.subcommand();
+        assert_eq!(dress_command, "run");
+        assert!(run_matches.unwrap().is_present("detach"));
+    }
+
+    #[test]
+    fn test_flag_not_supported_by_subcommand() {
+        let result = build_app().get_matches_from_safe(to_args(&["wedp", "install", "--pull"]));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_unknown_subcommand() {
+        let result = build_app().get_matches_from_safe(to_args(&["wedp", "bogus"]));
+        assert_eq!(result.unwrap_err().kind, clap::ErrorKind::UnknownArgument);
+    }
+
+    #[test]
+    fn test_translate_legacy_args() {
+        assert_eq!(
+            translate_legacy_args(to_args(&["wedp", "run-d", "-f", "plan.yml"])),
+            to_args(&["wedp", "run", "-d", "-f", "plan.yml"])
+        );
+        assert_eq!(
+            translate_legacy_args(to_args(&["wedp", "-f", "plan.yml", "dressremoterun-d"])),
+            to_args(&["wedp", "-f", "plan.yml", "dress", "remoterun", "-d"])
+        );
+        assert_eq!(
+            translate_legacy_args(to_args(&["wedp", "build", "-f", "dressbuild"])),
+            to_args(&["wedp", "build", "-f", "dressbuild"])
+        );
+        assert_eq!(
+            translate_legacy_args(to_args(&["wedp", "--timeout-per-command", "60", "dressbuild"])),
+            to_args(&["wedp", "--timeout-per-command", "60", "dress", "build"])
+        );
+        assert_eq!(
+            translate_legacy_args(to_args(&["wedp", "--arch", "aarch64", "dressbuild"])),
+            to_args(&["wedp", "--arch", "aarch64", "dress", "build"])
+        );
+    }
+
+    #[test]
+    fn test_legacy_args_parse() {
+        let matches = build_app().get_matches_from_safe(translate_legacy_args(to_args(&["wedp", "dresssetup"]))).unwrap();
+        let dress_matches = matches.subcommand_matches("dress").unwrap();
+        assert_eq!(dress_matches.subcommand_name(), Some("setup"));
+    }
+
+    /// Gets the help text of a command the way ```--help``` prints it.
+    fn help_text(command: &str) -> String {
+        let mut args = vec!["wedp"];
+        args.extend(command.split_whitespace());
+        args.push("--help");
+        let error = build_app().get_matches_from_safe(to_args(&args)).unwrap_err();
+        assert_eq!(error.kind, clap::ErrorKind::HelpDisplayed);
+        error.message
+    }
+
+    #[test]
+    fn test_help_examples_parse() {
+        for help in help::COMMAND_HELP {
+            for example in help.examples {
+                let matches = match build_app().get_matches_from_safe(to_args(&example.split_whitespace().collect::<Vec<&str>>())) {
+                    Ok(matches) => matches,
+                    Err(error) => panic!("{} does not parse: {}", example, error.message)
+                };
+                let (command, sub_matches) = matches.subcommand();
+                let nested = sub_matches.and_then(|sub_matches| sub_matches.subcommand_name());
+                let parsed = match nested {
+                    Some(nested) => format!("{} {}", command, nested),
+                    None => command.to_string()
+                };
+                assert!(parsed.starts_with(help.command), "{} runs {} instead of {}", example, parsed, help.command);
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_subcommand_has_long_help() {
+        let mut commands = Vec::new();
+        for parent in ["", "config", "dress"] {
+            let text = help_text(parent);
+            let listed = text.split("SUBCOMMANDS:\n").nth(1).unwrap();
+            for line in listed.lines().take_while(|line| line.starts_with("    ")) {
+                let name = line.split_whitespace().next().unwrap();
+                if name != "help" {
+                    commands.push(format!("{} {}", parent, name).trim().to_string());
+                }
+            }
+        }
+        assert!(commands.contains(&"dress devrun".to_string()));
+        for command in commands {
+            let long_help = help::after_help(&command);
+            assert!(!long_help.is_empty(), "{} has no long help", command);
+            assert!(help_text(&command).contains(long_help.trim_end()), "{} --help does not show its long help", command);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! Opens the ```open_url``` of a dependency with the platform launcher once the dependencies are healthy. The
+//! URL can contain ```{host_port}``` which is replaced with the host port docker published for the
+//! ```open_port``` of the dependency, looked up with ```docker-compose port```.
+use crate::compose_file::{shell_quote, ServiceName};
+
+
+/// The placeholder in an ```open_url``` that is replaced with the published host port.
+pub const HOST_PORT_PLACEHOLDER: &str = "{host_port}";
+
+
+/// A published container port in the form ```SERVICE:PORT```.
+///
+/// # Fields
+/// * `service` - The docker-compose service publishing the port
+/// * `port` - The port inside the container
+#[derive(Debug, PartialEq)]
+pub struct OpenPort {
+    pub service: ServiceName,
+    pub port: u16,
+}
+
+impl OpenPort {
+
+    /// Parses an ```open_port``` from the seating plan.
+    ///
+    /// # Arguments
+    /// * `open_port` - The port in the form ```SERVICE:PORT```
+    ///
+    /// # Returns
+    /// * `Result<OpenPort, String>` - The port or an error if it is not in the form ```SERVICE:PORT```
+    pub fn parse(open_port: &str) -> Result<Self, String> {
+        let (service, port) = match open_port.rsplit_once(':') {
+            Some(parts) => parts,
+            None => return Err(format!("open_port {} is not in the form SERVICE:PORT", open_port))
+        };
+        let port = port.parse::<u16>()
+                       .map_err(|_| format!("open_port {} does not end in a port number", open_port))?;
+        Ok(OpenPort { service: ServiceName::new(service)?, port })
+    }
+
+    /// Renders the ```docker-compose port``` arguments that print the host port.
+    ///
+    /// # Returns
+    /// * `String` - The arguments starting with a space
+    pub fn lookup_suffix(&self) -> String {
+        format!(" port {} {}", self.service, self.port)
+    }
+}
+
+
+/// Reads the host port from the output of ```docker-compose port``` such as ```0.0.0.0:49153```.
+///
+/// # Arguments
+/// * `output` - The stdout of ```docker-compose port```
+///
+/// # Returns
+/// * `Option<String>` - The host port or ```None``` if the port is not published
+pub fn parse_host_port(output: &str) -> Option<String> {
+    let port = output.lines().next()?.trim().rsplit(':').next()?;
+    match !port.is_empty() && port != "0" && port.chars().all(|c| c.is_ascii_digit()) {
+        true => Some(port.to_string()),
+        false => None
+    }
+}
+
+
+/// Replaces ```{host_port}``` in a URL.
+///
+/// # Arguments
+/// * `url` - The ```open_url``` of the dependency
+/// * `host_port` - The published host port, ```None``` if the dependency has no ```open_port```
+///
+/// # Returns
+/// * `Result<String, String>` - The URL or an error if the URL needs a host port that is not known
+pub fn expand_open_url(url: &str, host_port: Option<&str>) -> Result<String, String> {
+    if !url.contains(HOST_PORT_PLACEHOLDER) {
+        return Ok(url.to_string())
+    }
+    match host_port {
+        Some(host_port) => Ok(url.replace(HOST_PORT_PLACEHOLDER, host_port)),
+        None => Err(format!("open_url {} uses {} but there is no open_port", url, HOST_PORT_PLACEHOLDER))
+    }
+}
+
+
+/// Renders the command that opens a URL with the launcher of the platform.
+///
+/// # Arguments
+/// * `url` - The URL to open
+///
+/// # Returns
+/// * `String` - The command opening the URL
+pub fn launcher_command(url: &str) -> String {
+    let launcher = match std::env::consts::OS {
+        "macos" => "open",
+        "windows" => "cmd /C start \"\"",
+        _ => "xdg-open"
+    };
+    format!("{} {}", launcher, shell_quote(url))
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_open_port_parse() {
+        let open_port = OpenPort::parse("web:8080").unwrap();
+        assert_eq!(open_port.lookup_suffix(), " port web 8080");
+        assert!(OpenPort::parse("8080").is_err());
+        assert!(OpenPort::parse("web:http").is_err());
+        assert!(OpenPort::parse("web; rm -rf /:80").is_err());
+    }
+
+    #[test]
+    fn test_parse_host_port() {
+        assert_eq!(parse_host_port("0.0.0.0:49153\n"), Some("49153".to_string()));
+        assert_eq!(parse_host_port("[::]:49153\n0.0.0.0:49153\n"), Some("49153".to_string()));
+        assert_eq!(parse_host_port(":0\n"), None);
+        assert_eq!(parse_host_port(""), None);
+    }
+
+    #[test]
+    fn test_expand_open_url() {
+        assert_eq!(expand_open_url("http://localhost:{host_port}/", Some("49153")), Ok("http://localhost:49153/".to_string()));
+        assert_eq!(expand_open_url("http://localhost:3000/", None), Ok("http://localhost:3000/".to_string()));
+        assert_eq!(
+            expand_open_url("http://localhost:{host_port}/", None),
+            Err("open_url http://localhost:{host_port}/ uses {host_port} but there is no open_port".to_string())
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! Lexical clean up of the paths derived from the venue and the wedding invites. Paths such as
+//! ```./venue//auth/./Dockerfile``` are cleaned to ```venue/auth/Dockerfile``` so paths can be compared and
+//! printed consistently. Only the text of the path is changed, nothing is read from the file system so
+//! symlinks are never resolved and ```..``` segments are kept as they are, apart from ```resolve_parents```
+//! which is used to check where a venue points before it is removed.
+//!
+//! Paths written in a seating plan such as ```$HOME/services``` or ```~/work/venue``` are expanded with the
+//! environment before they are used.
+use std::path::{Component, Path, PathBuf};
+
+
+/// Cleans a path by removing ```.``` segments, duplicate separators, and trailing separators.
+///
+/// # Arguments
+/// * `path` - The path to clean
+///
+/// # Returns
+/// * `PathBuf` - The cleaned path, ```.``` if nothing is left of a relative path
+pub fn normalise_path(path: &Path) -> PathBuf {
+    let normalised: PathBuf = path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect();
+    match normalised.as_os_str().is_empty() {
+        true => PathBuf::from("."),
+        false => normalised
+    }
+}
+
+/// Joins paths together and cleans the result.
+///
+/// # Arguments
+/// * `base` - The path the parts are joined onto
+/// * `parts` - The parts to join in order, an absolute part replaces everything before it
+///
+/// # Returns
+/// * `PathBuf` - The cleaned joined path
+pub fn join_normalised(base: &Path, parts: &[&str]) -> PathBuf {
+    let mut path = base.to_path_buf();
+    for part in parts {
+        path.push(part);
+    }
+    normalise_path(&path)
+}
+
+
+/// Cleans a path and removes each ```..``` segment together with the segment before it, for comparing where
+/// a path points before something is removed. A ```..``` at the root stays at the root.
+///
+/// # Arguments
+/// * `path` - The path to resolve
+///
+/// # Returns
+/// * `PathBuf` - The path without ```.``` and ```..``` segments, ```..``` segments at the start of a relative path are kept
+pub fn resolve_parents(path: &Path) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => match resolved.components().next_back() {
+                Some(Component::Normal(_)) => { resolved.pop(); },
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => {},
+                _ => resolved.push(".."),
+            },
+            component => resolved.push(component)
+        }
+    }
+    match resolved.as_os_str().is_empty() {
+        true => PathBuf::from("."),
+        false => resolved
+    }
+}
+
+
+/// Expands a leading ```~``` to ```HOME``` and the ```${NAME}``` and ```$NAME``` variables with the environment.
+/// A ```$``` that does not start a variable name is kept as it is.
+///
+/// # Arguments
+/// * `path` - The path to expand
+/// * `lookup` - Reads an environment variable, ```None``` if it is not set
+///
+/// # Returns
+/// * `Result<String, String>` - The expanded path or an error naming the first variable that is not set
+pub fn expand_path(path: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String, String> {
+    let unset = |name: &str| format!("{} uses {} which is not set", path, name);
+    let rest = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+            let home = lookup("HOME").ok_or_else(|| unset("HOME"))?;
+            return expand_variables(rest, lookup).map(|rest| format!("{}{}", home, rest)).map_err(|name| unset(&name))
+        },
+        _ => path
+    };
+    expand_variables(rest, lookup).map_err(|name| unset(&name))
+}
+
+
+/// Replaces the ```${NAME}``` and ```$NAME``` variables of a string with their values.
+///
+/// # Arguments
+/// * `value` - The string to expand
+/// * `lookup` - Reads an environment variable, ```None``` if it is not set
+///
+/// # Returns
+/// * `Result<String, String>` - The expanded string or the name of the first variable that is not set
+fn expand_variables(value: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String, String> {
+    let is_name = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut expanded = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let (name, remaining) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], &braced[end + 1..]),
+                None => ("", after)
+            },
+            None => {
+                let end = after.find(|c: char| !is_name(c)).unwrap_or(after.len());
+                (&after[..end], &after[end..])
+            }
+        };
+        if name.is_empty() || !name.chars().all(is_name) {
+            expanded.push('$');
+            rest = after;
+            continue
+        }
+        expanded.push_str(&lookup(name).ok_or_else(|| name.to_string())?);
+        rest = remaining;
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn normalise(path: &str) -> String {
+        normalise_path(Path::new(path)).to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_normalise_path_current_directory_segments() {
+        assert_eq!(normalise("./tests/test_repo/./Dockerfile"), "tests/test_repo/Dockerfile");
+        assert_eq!(normalise("tests/././test_repo"), "tests/test_repo");
+        assert_eq!(normalise("/work/./venue"), "/work/venue");
+        assert_eq!(normalise("./Dockerfile"), "Dockerfile");
+    }
+
+    #[test]
+    fn test_normalise_path_separators() {
+        assert_eq!(normalise("tests//test_repo///Dockerfile"), "tests/test_repo/Dockerfile");
+        assert_eq!(normalise("../sandbox/services/"), "../sandbox/services");
+        assert_eq!(normalise("/work/venue//"), "/work/venue");
+        assert_eq!(normalise("//work"), "/work");
+    }
+
+    #[test]
+    fn test_normalise_path_keeps_parent_segments() {
+        assert_eq!(normalise("../sandbox/../services"), "../sandbox/../services");
+        assert_eq!(normalise("./../venue/./"), "../venue");
+    }
+
+    #[test]
+    fn test_normalise_path_empty_and_root() {
+        assert_eq!(normalise("."), ".");
+        assert_eq!(normalise("./"), ".");
+        assert_eq!(normalise(""), ".");
+        assert_eq!(normalise("/"), "/");
+        assert_eq!(normalise("/."), "/");
+    }
+
+    #[test]
+    fn test_normalise_path_is_idempotent() {
+        for path in ["./tests/test_repo/./Dockerfile", "../a//b/", "/", ".", "a/../b"] {
+            let once = normalise(path);
+            assert_eq!(normalise(&once), once);
+        }
+    }
+
+    #[test]
+    fn test_join_normalised() {
+        assert_eq!(join_normalised(Path::new("./tests/"), &["test_repo", ".", "Dockerfile"]), Path::new("tests/test_repo/Dockerfile"));
+        assert_eq!(join_normalised(Path::new("/work/"), &["", "runner_files/base.yml"]), Path::new("/work/runner_files/base.yml"));
+        assert_eq!(join_normalised(Path::new("./venue"), &["/absolute/base.yml"]), Path::new("/absolute/base.yml"));
+    }
+
+    #[test]
+    fn test_resolve_parents() {
+        assert_eq!(resolve_parents(Path::new("/work/project/../venue/./auth")), Path::new("/work/venue/auth"));
+        assert_eq!(resolve_parents(Path::new("/work/..")), Path::new("/"));
+        assert_eq!(resolve_parents(Path::new("/../..")), Path::new("/"));
+        assert_eq!(resolve_parents(Path::new("venue/..")), Path::new("."));
+        assert_eq!(resolve_parents(Path::new("../venue")), Path::new("../venue"));
+    }
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "HOME" => Some("/home/dev".to_string()),
+            "SERVICES" => Some("/srv/services".to_string()),
+            _ => None
+        }
+    }
+
+    #[test]
+    fn test_expand_path() {
+        assert_eq!(expand_path("~/work/venue", &lookup), Ok("/home/dev/work/venue".to_string()));
+        assert_eq!(expand_path("~", &lookup), Ok("/home/dev".to_string()));
+        assert_eq!(expand_path("$HOME/services", &lookup), Ok("/home/dev/services".to_string()));
+        assert_eq!(expand_path("${SERVICES}/venue", &lookup), Ok("/srv/services/venue".to_string()));
+        assert_eq!(expand_path("~/$SERVICES-${HOME}", &lookup), Ok("/home/dev//srv/services-/home/dev".to_string()));
+        assert_eq!(expand_path("./venue", &lookup), Ok("./venue".to_string()));
+    }
+
+    #[test]
+    fn test_expand_path_kept_literally() {
+        assert_eq!(expand_path("~other/venue", &lookup), Ok("~other/venue".to_string()));
+        assert_eq!(expand_path("venue/~", &lookup), Ok("venue/~".to_string()));
+        assert_eq!(expand_path("cost$/venue$", &lookup), Ok("cost$/venue$".to_string()));
+        assert_eq!(expand_path("${unclosed/venue", &lookup), Ok("${unclosed/venue".to_string()));
+    }
+
+    #[test]
+    fn test_expand_path_unset() {
+        assert_eq!(expand_path("$MISSING/venue", &lookup), Err("$MISSING/venue uses MISSING which is not set".to_string()));
+        assert_eq!(expand_path("~/venue", &|_| None), Err("~/venue uses HOME which is not set".to_string()));
+    }
+}
@@ -0,0 +1,331 @@
+//! An optional on-disk cache of parsed seating plans for very large plans. The cache is turned on by setting
+//! the ```WEDP_CACHE_DIR``` environment variable to the directory the cache entries should be stored in.
+//! 
+//! Each entry is keyed by the modification time and a hash of the contents of the seating plan file. If the
+//! modification time is unchanged the seating plan file is not even read, if the modification time changed
+//! but the contents did not the entry is refreshed without parsing, and any edit to the contents re-parses
+//! the seating plan. An entry written with another schema that can not be read is parsed again and replaced.
+//! 
+//! The hash is taken after the environment is substituted into the contents, so a seating plan that uses
+//! ```${NAME}``` variables is always read and only parsed again when the substituted contents change.
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::error::WedpError;
+use crate::file_handler::CoreFileHandle;
+use crate::interpolate::interpolate_file;
+use crate::schema::{read_stamped, stamp, PLAN_CACHE_FILE};
+use crate::seating_plan::SeatingPlan;
+
+
+/// The environment variable that turns on the cache and defines where it is stored.
+pub const CACHE_DIR_ENV: &str = "WEDP_CACHE_DIR";
+
+
+/// A cached seating plan with the key it was cached under.
+/// 
+/// # Fields
+/// * `modified` - The modification time of the seating plan file in nanoseconds since the epoch
+/// * `hash` - The hash of the contents of the seating plan file once the environment is substituted
+/// * `reads_env` - If the contents changed when the environment was substituted, entries written before this was
+///   recorded are treated as reading the environment
+/// * `seating_plan` - The parsed seating plan
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct CacheEntry {
+    modified: u64,
+    hash: u64,
+    #[serde(default = "reads_env_default")]
+    reads_env: bool,
+    seating_plan: SeatingPlan,
+}
+
+
+/// The ```reads_env``` of an entry written before it was recorded.
+fn reads_env_default() -> bool {
+    true
+}
+
+
+/// Loads seating plans through the on-disk cache.
+/// 
+/// # Fields
+/// * `cache_dir` - The directory the cache entries are stored in
+/// * `handle` - The file handle used for all file operations
+pub struct PlanCache<'a> {
+    pub cache_dir: PathBuf,
+    pub handle: &'a dyn CoreFileHandle,
+}
+
+impl<'a> PlanCache<'a> {
+
+    /// Creates a new PlanCache struct.
+    /// 
+    /// # Arguments
+    /// * `cache_dir` - The directory the cache entries are stored in
+    /// * `handle` - The file handle used for all file operations
+    /// 
+    /// # Returns
+    /// A new PlanCache struct
+    pub fn new(cache_dir: PathBuf, handle: &'a dyn CoreFileHandle) -> Self {
+        PlanCache { cache_dir, handle }
+    }
+
+    /// Loads a seating plan, only parsing the YAML if the file or the environment it uses has changed since it 
+    /// was cached.
+    /// 
+    /// # Arguments
+    /// * `file_path` - The path to the seating plan file
+    /// * `parse` - The function that parses the contents of the seating plan file
+    /// 
+    /// # Returns
+    /// * `Result<SeatingPlan, WedpError>` - The seating plan or the error reading or parsing the file
+    pub fn load(&self, file_path: &str, parse: &dyn Fn(&str, &str) -> Result<SeatingPlan, WedpError>) -> Result<SeatingPlan, WedpError> {
+        let modified = match self.handle.modified(Path::new(file_path)) {
+            Ok(modified) => modified.duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0),
+            Err(source) => return Err(WedpError::from_read(file_path.to_string(), source))
+        };
+        let entry_path = self.entry_path(file_path);
+        let cached = self.handle.read_to_string(&entry_path).ok()
+            .and_then(|contents| read_stamped::<CacheEntry>(PLAN_CACHE_FILE, &entry_path.to_string_lossy(), &contents).ok());
+
+        if let Some(entry) = &cached {
+            if entry.modified == modified && !entry.reads_env {
+                return Ok(cached.unwrap().seating_plan)
+            }
+        }
+        let contents = match self.handle.read_to_string(Path::new(file_path)) {
+            Ok(contents) => contents,
+            Err(source) => return Err(WedpError::from_read(file_path.to_string(), source))
+        };
+        let interpolated = interpolate_file(&contents, file_path)?;
+        let reads_env = interpolated != contents;
+        let hash = hash_contents(&interpolated);
+
+        let seating_plan = match cached {
+            Some(entry) if entry.hash == hash => entry.seating_plan,
+            _ => parse(&interpolated, file_path)?
+        };
+        let entry = CacheEntry { modified, hash, reads_env, seating_plan };
+        self.store(&entry_path, &entry);
+        Ok(entry.seating_plan)
+    }
+
+    /// Gets the path of the cache entry for a seating plan file.
+    /// 
+    /// # Arguments
+    /// * `file_path` - The path to the seating plan file
+    /// 
+    /// # Returns
+    /// * `PathBuf` - The path of the cache entry
+    fn entry_path(&self, file_path: &str) -> PathBuf {
+        self.cache_dir.join(format!("{:016x}.json", hash_contents(file_path)))
+    }
+
+    /// Writes a cache entry, warning rather than failing if the cache cannot be written.
+    /// 
+    /// # Arguments
+    /// * `entry_path` - The path of the cache entry
+    /// * `entry` - The entry to write
+    fn store(&self, entry_path: &Path, entry: &CacheEntry) {
+        let result = self.handle.create_directory_if_not_exists(&self.cache_dir)
+            .and_then(|_| self.handle.write(entry_path, &serde_json::to_string(&stamp(entry)).unwrap()));
+        if let Err(error) = result {
+            eprintln!("Warning: could not write seating plan cache {}: {}", entry_path.to_string_lossy(), error);
+        }
+    }
+}
+
+
+/// Hashes a string for the cache key.
+/// 
+/// # Arguments
+/// * `contents` - The string to hash
+/// 
+/// # Returns
+/// * `u64` - The hash of the string
+fn hash_contents(contents: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::cell::Cell;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+    use std::collections::HashMap;
+    use std::time::{Duration, SystemTime};
+
+    static PLAN_PATH: &str = "/plans/wedding_planner.yml";
+
+    /// An in memory file handle that counts how many times the seating plan file is read.
+    struct CountingFileHandle {
+        files: Mutex<HashMap<PathBuf, String>>,
+        modified: AtomicU64,
+        plan_reads: AtomicU32,
+    }
+
+    impl CountingFileHandle {
+        fn new(contents: &str) -> Self {
+            let mut files = HashMap::new();
+            files.insert(PathBuf::from(PLAN_PATH), contents.to_string());
+            CountingFileHandle { files: Mutex::new(files), modified: AtomicU64::new(1), plan_reads: AtomicU32::new(0) }
+        }
+
+        fn edit(&self, contents: &str, modified: u64) {
+            self.files.lock().unwrap().insert(PathBuf::from(PLAN_PATH), contents.to_string());
+            self.modified.store(modified, Ordering::SeqCst);
+        }
+    }
+
+    impl CoreFileHandle for CountingFileHandle {
+        fn copy(&self, _from: &Path, _to: &Path) -> Result<u64, std::io::Error> {
+            unimplemented!()
+        }
+
+        fn remove(&self, _path: &Path) -> Result<(), std::io::Error> {
+            unimplemented!()
+        }
+
+        fn remove_directory(&self, _path: &Path) -> Result<(), std::io::Error> {
+            unimplemented!()
+        }
+
+        fn create_directory_if_not_exists(&self, _path: &Path) -> Result<(), std::io::Error> {
+            Ok(())
+        }
+
+        fn read_to_string(&self, path: &Path) -> Result<String, std::io::Error> {
+            if path == Path::new(PLAN_PATH) {
+                self.plan_reads.fetch_add(1, Ordering::SeqCst);
+            }
+            self.files.lock().unwrap().get(path).cloned()
+                .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "not found"))
+        }
+
+        fn write(&self, path: &Path, contents: &str) -> Result<(), std::io::Error> {
+            self.files.lock().unwrap().insert(path.to_path_buf(), contents.to_string());
+            Ok(())
+        }
+
+        fn append(&self, _path: &Path, _contents: &str) -> Result<(), std::io::Error> {
+            unimplemented!()
+        }
+
+        fn modified(&self, _path: &Path) -> Result<SystemTime, std::io::Error> {
+            Ok(UNIX_EPOCH + Duration::from_nanos(self.modified.load(Ordering::SeqCst)))
+        }
+
+        fn exists(&self, path: &Path) -> bool {
+            self.files.lock().unwrap().contains_key(path)
+        }
+
+        fn exists_no_follow(&self, _path: &Path) -> bool {
+            unimplemented!()
+        }
+
+        fn read_link(&self, _path: &Path) -> Result<PathBuf, std::io::Error> {
+            unimplemented!()
+        }
+
+        fn dir_size(&self, _path: &Path) -> Result<u64, std::io::Error> {
+            unimplemented!()
+        }
+
+        fn symlink(&self, _target: &Path, _link: &Path) -> Result<(), std::io::Error> {
+            unimplemented!()
+        }
+
+        fn copy_directory(&self, _from: &Path, _to: &Path) -> Result<(), std::io::Error> {
+            unimplemented!()
+        }
+    }
+
+    fn plan_yaml(venue: &str) -> String {
+        format!("attendees:\n  - name: auth\n    url: https://example.com/auth.git\n    branch: main\nvenue: {}\n", venue)
+    }
+
+    #[test]
+    fn test_cache_hit_skips_parse() {
+        let handle = CountingFileHandle::new(&plan_yaml("./venue/"));
+        let cache = PlanCache::new(PathBuf::from("/cache"), &handle);
+        let parses = Cell::new(0);
+        let parse = |contents: &str, path: &str| {
+            parses.set(parses.get() + 1);
+            SeatingPlan::parse(contents, path)
+        };
+
+        let first = cache.load(PLAN_PATH, &parse).unwrap();
+        let second = cache.load(PLAN_PATH, &parse).unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(second.venue, "./venue/");
+        assert_eq!(parses.get(), 1);
+        assert_eq!(handle.plan_reads.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_touch_without_edit_skips_parse() {
+        let handle = CountingFileHandle::new(&plan_yaml("./venue/"));
+        let cache = PlanCache::new(PathBuf::from("/cache"), &handle);
+        let parses = Cell::new(0);
+        let parse = |contents: &str, path: &str| {
+            parses.set(parses.get() + 1);
+            SeatingPlan::parse(contents, path)
+        };
+
+        cache.load(PLAN_PATH, &parse).unwrap();
+        handle.edit(&plan_yaml("./venue/"), 2);
+        cache.load(PLAN_PATH, &parse).unwrap();
+
+        assert_eq!(parses.get(), 1);
+        assert_eq!(handle.plan_reads.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_edit_invalidates_cache() {
+        let handle = CountingFileHandle::new(&plan_yaml("./venue/"));
+        let cache = PlanCache::new(PathBuf::from("/cache"), &handle);
+        let parses = Cell::new(0);
+        let parse = |contents: &str, path: &str| {
+            parses.set(parses.get() + 1);
+            SeatingPlan::parse(contents, path)
+        };
+
+        cache.load(PLAN_PATH, &parse).unwrap();
+        handle.edit(&plan_yaml("./other_venue/"), 2);
+        let edited = cache.load(PLAN_PATH, &parse).unwrap();
+
+        assert_eq!(edited.venue, "./other_venue/");
+        assert_eq!(parses.get(), 2);
+    }
+
+    #[test]
+    fn test_environment_change_invalidates_cache() {
+        std::env::set_var("WEDP_TEST_CACHE_BRANCH", "main");
+        let handle = CountingFileHandle::new(
+            "attendees:\n  - name: auth\n    url: https://example.com/auth.git\n    branch: ${WEDP_TEST_CACHE_BRANCH}\nvenue: ./venue/\n"
+        );
+        let cache = PlanCache::new(PathBuf::from("/cache"), &handle);
+        let parses = Cell::new(0);
+        let parse = |contents: &str, path: &str| {
+            parses.set(parses.get() + 1);
+            SeatingPlan::parse(contents, path)
+        };
+
+        assert_eq!(cache.load(PLAN_PATH, &parse).unwrap().attendees[0].branch, Some("main".to_string()));
+        assert_eq!(cache.load(PLAN_PATH, &parse).unwrap().attendees[0].branch, Some("main".to_string()));
+        std::env::set_var("WEDP_TEST_CACHE_BRANCH", "develop");
+        assert_eq!(cache.load(PLAN_PATH, &parse).unwrap().attendees[0].branch, Some("develop".to_string()));
+
+        assert_eq!(parses.get(), 2);
+        assert_eq!(handle.plan_reads.load(Ordering::SeqCst), 3);
+    }
+}
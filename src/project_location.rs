@@ -0,0 +1,320 @@
+//! Detects the compose project of the seating plan already running from another checkout before anything is
+//! started. Compose names the project after the directory of the first compose file, so two checkouts of the
+//! same plan share a project name while passing different absolute ```-f``` paths, and ```up``` from the
+//! second checkout buries the real problem under pages of orphan container warnings. The containers with a
+//! ```com.docker.compose.project``` label are listed once, and the directory and compose files the containers
+//! of the project were started from are compared with the ones about to be used. The compose files are only
+//! read for the project name if any compose project is running at all.
+use std::env;
+use std::path::Path;
+
+use crate::commands::command_runner::{check_status, CoreRunner};
+use crate::compose_file::{resolve_files, shell_quote, ComposeFileRef};
+use crate::file_handler::CoreFileHandle;
+
+
+/// The label compose sets to the project name on every container.
+pub const PROJECT_LABEL: &str = "com.docker.compose.project";
+
+/// The label compose sets to the directory the project was started from.
+pub const WORKING_DIR_LABEL: &str = "com.docker.compose.project.working_dir";
+
+/// The label compose sets to the comma separated compose files the project was started with.
+pub const CONFIG_FILES_LABEL: &str = "com.docker.compose.project.config_files";
+
+/// The environment variable compose reads the project name from before anything else.
+pub const PROJECT_NAME_ENV: &str = "COMPOSE_PROJECT_NAME";
+
+
+/// Where a container of a compose project was started from.
+///
+/// # Fields
+/// * `project` - The name of the compose project the container belongs to
+/// * `working_dir` - The absolute directory of the project
+/// * `config_files` - The absolute paths of the compose files
+#[derive(Debug, PartialEq)]
+pub struct ProjectLocation {
+    pub project: String,
+    pub working_dir: String,
+    pub config_files: Vec<String>,
+}
+
+
+/// Normalises a name the way compose v2 does for project names: lower case, only letters, digits, dashes, and
+/// underscores, starting with a letter or digit.
+///
+/// # Arguments
+/// * `name` - The name such as the directory of the first compose file
+///
+/// # Returns
+/// * `String` - The project name, empty if nothing in the name can be used
+pub fn normalise_project_name(name: &str) -> String {
+    let name: String = name.to_lowercase()
+                           .chars()
+                           .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+                           .collect();
+    name.trim_start_matches(|c: char| !c.is_ascii_alphanumeric()).to_string()
+}
+
+
+/// Works out the name compose gives the project: ```COMPOSE_PROJECT_NAME```, then the top level ```name``` of
+/// the last compose file that sets one, then the directory of the first compose file.
+///
+/// # Arguments
+/// * `files` - The absolute compose files in the order they are passed to compose
+/// * `handle` - The file handle the compose files are read with
+///
+/// # Returns
+/// * `Option<String>` - The project name or ```None``` if there are no compose files
+pub fn project_name(files: &[ComposeFileRef], handle: &dyn CoreFileHandle) -> Option<String> {
+    if let Some(name) = env::var(PROJECT_NAME_ENV).ok().filter(|name| !name.is_empty()) {
+        return Some(normalise_project_name(&name))
+    }
+    let named = files.iter().rev().find_map(|file| {
+        let contents = handle.read_to_string(Path::new(&file.path)).ok()?;
+        let compose: serde_yaml::Value = serde_yaml::from_str(&contents).ok()?;
+        compose.get("name")?.as_str().map(String::from)
+    });
+    if let Some(name) = named {
+        return Some(normalise_project_name(&name))
+    }
+    let directory = Path::new(&files.first()?.path).parent()?.file_name()?.to_string_lossy().to_string();
+    Some(normalise_project_name(&directory))
+}
+
+
+/// Renders the command listing the project of every compose container and where it was started from, one
+/// container per line.
+///
+/// # Arguments
+/// * `engine` - The program that lists the containers such as ```docker```
+///
+/// # Returns
+/// * `String` - The command
+pub fn render_locations_command(engine: &str) -> String {
+    let labels: Vec<String> = [PROJECT_LABEL, WORKING_DIR_LABEL, CONFIG_FILES_LABEL].iter()
+        .map(|label| format!("{{{{.Label \"{}\"}}}}", label))
+        .collect();
+    let format = labels.join("\\t");
+    format!("{} ps -a --filter label={} --format {}", engine, PROJECT_LABEL, shell_quote(&format))
+}
+
+
+/// Reads the locations printed by the command from ```render_locations_command```.
+///
+/// # Arguments
+/// * `output` - The output of the command, a project, a working directory, and the comma separated compose
+///   files per line
+///
+/// # Returns
+/// * `Vec<ProjectLocation>` - The location of each container that has a project and working directory label
+pub fn parse_locations(output: &str) -> Vec<ProjectLocation> {
+    output.lines()
+          .filter_map(|line| {
+              let mut fields = line.split('\t').map(str::trim);
+              let project = fields.next().unwrap_or_default();
+              let working_dir = fields.next().unwrap_or_default();
+              let config_files = fields.next().unwrap_or_default();
+              if project.is_empty() || working_dir.is_empty() {
+                  return None
+              }
+              let config_files = config_files.split(',')
+                                             .map(str::trim)
+                                             .filter(|file| !file.is_empty())
+                                             .map(String::from)
+                                             .collect();
+              Some(ProjectLocation { project: project.to_string(), working_dir: working_dir.to_string(), config_files })
+          })
+          .collect()
+}
+
+
+/// Finds a container of the project that was started from somewhere else. A container is from somewhere else
+/// if its working directory differs, or if none of the compose files it was started with are about to be used.
+///
+/// # Arguments
+/// * `locations` - The locations of the compose containers
+/// * `project` - The name of the project about to be started
+/// * `working_dir` - The absolute directory the project is about to be started from
+/// * `config_files` - The absolute compose files about to be used
+///
+/// # Returns
+/// * `Option<&ProjectLocation>` - The first location that is somewhere else
+pub fn other_location<'a>(locations: &'a [ProjectLocation], project: &str, working_dir: &str, config_files: &[String]) -> Option<&'a ProjectLocation> {
+    locations.iter().filter(|location| location.project == project).find(|location| {
+        let shares_file = location.config_files.iter().any(|file| config_files.contains(file));
+        location.working_dir != working_dir || (!location.config_files.is_empty() && !shares_file)
+    })
+}
+
+
+/// Renders the error for a project that already runs from somewhere else.
+///
+/// # Arguments
+/// * `other` - Where the project is running from
+///
+/// # Returns
+/// * `String` - The error naming the other location and how to resolve it
+pub fn render_other_location(other: &ProjectLocation) -> String {
+    format!(
+        "The compose project {} is already running from {}\n\
+         Run `wedp teardown` in the checkout it was started from, or pass --force-recreate to recreate the \
+         containers from here and remove the orphans",
+        other.project, other.working_dir
+    )
+}
+
+
+/// Checks the compose project is not already running from another location. If the containers can not be
+/// listed, such as when the daemon is down, the check is skipped and compose reports the problem itself.
+///
+/// # Arguments
+/// * `compose` - The command docker compose is run with, the containers are listed with its first word
+/// * `files` - The compose files about to be used
+/// * `cwd` - The directory relative compose file paths are resolved against
+/// * `runner` - The runner the containers are listed with
+/// * `handle` - The file handle the compose files are read with
+///
+/// # Returns
+/// * `Result<(), String>` - An error naming the other location if the project runs from somewhere else
+pub fn check_project_location(compose: &str, files: &[ComposeFileRef], cwd: &Path, runner: &dyn CoreRunner, handle: &dyn CoreFileHandle) -> Result<(), String> {
+    let engine = match compose.split_whitespace().next() {
+        Some("docker") | Some("docker-compose") => "docker",
+        _ => return Ok(())
+    };
+    let output = match runner.run(&render_locations_command(engine)).and_then(check_status) {
+        Ok(output) => output,
+        Err(_) => return Ok(())
+    };
+    let locations = parse_locations(&String::from_utf8_lossy(&output.stdout));
+    if locations.is_empty() {
+        return Ok(())
+    }
+    let files = resolve_files(files.to_vec(), cwd);
+    let project = match project_name(&files, handle) {
+        Some(project) => project,
+        None => return Ok(())
+    };
+    let working_dir = Path::new(&files[0].path).parent().map(|dir| dir.to_string_lossy().to_string()).unwrap_or_default();
+    let config_files: Vec<String> = files.iter().map(|file| file.path.clone()).collect();
+    match other_location(&locations, &project, &working_dir, &config_files) {
+        Some(other) => Err(render_other_location(other)),
+        None => Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::commands::command_runner::MockCoreRunner;
+    use crate::file_handler::MockCoreFileHandle;
+    use mockall::predicate::eq;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::{ExitStatus, Output};
+
+    const LOCATIONS: &str = "auth\t/home/dev/old/venue/auth\t/home/dev/old/venue/auth/base.yml,/home/dev/old/venue/billing/base.yml\n\
+                             billing\t/home/dev/billing\t/home/dev/billing/docker-compose.yml\n";
+
+    fn files() -> Vec<ComposeFileRef> {
+        vec![
+            ComposeFileRef::new("auth", "/home/dev/plan/venue/auth/base.yml".to_string()),
+            ComposeFileRef::new("billing", "/home/dev/plan/venue/billing/base.yml".to_string()),
+        ]
+    }
+
+    fn unnamed_handle() -> MockCoreFileHandle {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_read_to_string().returning(|_| Ok("services: {}\n".to_string()));
+        mock_handle
+    }
+
+    fn listing(stdout: &'static str) -> MockCoreRunner {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run()
+            .with(eq(render_locations_command("docker")))
+            .times(1)
+            .returning(move |_| Ok(Output { status: ExitStatus::from_raw(0), stdout: stdout.as_bytes().to_vec(), stderr: Vec::new() }));
+        mock_runner
+    }
+
+    #[test]
+    fn test_normalise_project_name() {
+        assert_eq!(normalise_project_name("Auth Service"), "authservice");
+        assert_eq!(normalise_project_name("_wedding.planner-2"), "weddingplanner-2");
+        assert_eq!(normalise_project_name("..."), "");
+    }
+
+    #[test]
+    fn test_project_name() {
+        assert_eq!(project_name(&files(), &unnamed_handle()), Some("auth".to_string()));
+        assert_eq!(project_name(&[], &unnamed_handle()), None);
+
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_read_to_string()
+            .with(eq(Path::new("/home/dev/plan/venue/billing/base.yml")))
+            .returning(|_| Ok("name: Wedding\nservices: {}\n".to_string()));
+        assert_eq!(project_name(&files(), &mock_handle), Some("wedding".to_string()));
+    }
+
+    #[test]
+    fn test_render_locations_command() {
+        assert_eq!(
+            render_locations_command("docker"),
+            "docker ps -a --filter label=com.docker.compose.project --format '{{.Label \"com.docker.compose.project\"}}\\t\
+             {{.Label \"com.docker.compose.project.working_dir\"}}\\t{{.Label \"com.docker.compose.project.config_files\"}}'"
+        );
+    }
+
+    #[test]
+    fn test_parse_locations() {
+        let locations = parse_locations(LOCATIONS);
+        assert_eq!(locations.len(), 2);
+        assert_eq!(locations[0], ProjectLocation {
+            project: "auth".to_string(),
+            working_dir: "/home/dev/old/venue/auth".to_string(),
+            config_files: vec!["/home/dev/old/venue/auth/base.yml".to_string(), "/home/dev/old/venue/billing/base.yml".to_string()]
+        });
+        assert_eq!(
+            parse_locations("\t\t\nauth\t\t\nauth\t/srv/auth\t\n"),
+            vec![ProjectLocation { project: "auth".to_string(), working_dir: "/srv/auth".to_string(), config_files: vec![] }]
+        );
+    }
+
+    #[test]
+    fn test_other_location() {
+        let ours: Vec<String> = files().into_iter().map(|file| file.path).collect();
+        let locations = parse_locations(LOCATIONS);
+        assert_eq!(other_location(&locations, "auth", "/home/dev/plan/venue/auth", &ours), Some(&locations[0]));
+        assert_eq!(other_location(&locations, "payments", "/home/dev/plan/venue/auth", &ours), None);
+
+        let same = parse_locations("auth\t/home/dev/plan/venue/auth\t/home/dev/plan/venue/auth/base.yml\n");
+        assert_eq!(other_location(&same, "auth", "/home/dev/plan/venue/auth", &ours), None);
+
+        let moved_files = parse_locations("auth\t/home/dev/plan/venue/auth\t/home/dev/old/venue/auth/base.yml\n");
+        assert_eq!(other_location(&moved_files, "auth", "/home/dev/plan/venue/auth", &ours), Some(&moved_files[0]));
+        assert_eq!(other_location(&[], "auth", "/home/dev/plan/venue/auth", &ours), None);
+    }
+
+    #[test]
+    fn test_check_project_location() {
+        let error = check_project_location("docker compose", &files(), Path::new("/"), &listing(LOCATIONS), &unnamed_handle()).unwrap_err();
+        assert_eq!(
+            error,
+            "The compose project auth is already running from /home/dev/old/venue/auth\n\
+             Run `wedp teardown` in the checkout it was started from, or pass --force-recreate to recreate the \
+             containers from here and remove the orphans"
+        );
+        assert_eq!(check_project_location("docker-compose", &files(), Path::new("/"), &listing(""), &MockCoreFileHandle::new()), Ok(()));
+    }
+
+    #[test]
+    fn test_check_skipped() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run()
+            .returning(|_| Ok(Output { status: ExitStatus::from_raw(1 << 8), stdout: Vec::new(), stderr: b"Cannot connect to the Docker daemon".to_vec() }));
+        assert_eq!(check_project_location("docker compose", &files(), Path::new("/"), &mock_runner, &unnamed_handle()), Ok(()));
+        assert_eq!(check_project_location("podman compose", &files(), Path::new("/"), &MockCoreRunner::new(), &unnamed_handle()), Ok(()));
+    }
+}
@@ -0,0 +1,250 @@
+//! Checks the branches in a seating plan exist on the remotes before anything is cloned, so a typo in a
+//! branch is reported straight away along with the branches it was probably meant to be, instead of after a
+//! full clone. A remote that can not be reached is only a warning as the install may still work later.
+use crate::commands::command_runner::CoreRunner;
+use crate::commands::list_remote_heads::ListRemoteHeadsCommand;
+use crate::git_auth::GitConfigEntry;
+
+
+/// The most branches suggested for a branch that does not exist.
+pub const MAX_SUGGESTIONS: usize = 3;
+
+
+/// The result of checking a branch on the remote.
+///
+/// # Fields
+/// * `Found` - The branch exists on the remote
+/// * `Missing` - The branch does not exist, with the similar branches on the remote closest first
+/// * `Unreachable` - The remote could not be listed, with the error
+#[derive(Debug, PartialEq)]
+pub enum RefCheck {
+    Found,
+    Missing(Vec<String>),
+    Unreachable(String),
+}
+
+
+/// Checks a branch exists on the remote, listing every branch of the remote for suggestions if it does not.
+///
+/// # Arguments
+/// * `url` - The URL of the remote repository
+/// * `auth_config` - The git config sending the access token of a private repository, ```None``` sends no token
+/// * `branch` - The branch to check
+/// * `runner` - The runner for the ```git ls-remote``` commands
+///
+/// # Returns
+/// * `RefCheck` - Whether the branch was found
+pub fn check_branch(url: &str, auth_config: Option<&GitConfigEntry>, branch: &str, runner: &dyn CoreRunner) -> RefCheck {
+    let list = |branch: Option<&str>| {
+        ListRemoteHeadsCommand::new(url.to_string(), branch.map(String::from))
+            .with_auth_config(auth_config.cloned())
+            .run(runner)
+    };
+    let listed = match list(Some(branch)) {
+        Ok(listed) => listed,
+        Err(error) => return RefCheck::Unreachable(error.to_string())
+    };
+    if parse_ls_remote_heads(&listed).iter().any(|head| head == branch) {
+        return RefCheck::Found
+    }
+    match list(None) {
+        Ok(all) => RefCheck::Missing(suggest_branches(branch, &parse_ls_remote_heads(&all))),
+        Err(error) => RefCheck::Unreachable(error.to_string())
+    }
+}
+
+
+/// Reads the branch names from the output of ```git ls-remote --heads```.
+///
+/// # Arguments
+/// * `output` - The output of ```git ls-remote --heads```, one ```<sha>\trefs/heads/<branch>``` per line
+///
+/// # Returns
+/// * `Vec<String>` - The branch names in the order they were listed
+pub fn parse_ls_remote_heads(output: &str) -> Vec<String> {
+    output.lines()
+          .filter_map(|line| line.split_once('\t'))
+          .filter_map(|(_, reference)| reference.trim().strip_prefix("refs/heads/"))
+          .map(String::from)
+          .collect()
+}
+
+
+/// Ranks the branches of a remote by how close they are to a branch that does not exist. A branch is similar
+/// if one name contains the other ignoring case, or if it is within a few edits of the branch, a third of its
+/// length and at least two.
+///
+/// # Arguments
+/// * `branch` - The branch that does not exist
+/// * `heads` - The branches on the remote
+///
+/// # Returns
+/// * `Vec<String>` - Up to ```MAX_SUGGESTIONS``` similar branches, the fewest edits away first
+pub fn suggest_branches(branch: &str, heads: &[String]) -> Vec<String> {
+    let target = branch.to_lowercase();
+    let limit = (target.chars().count() / 3).max(2);
+    let mut ranked: Vec<(usize, &String)> = heads.iter()
+        .map(|head| (edit_distance(&target, &head.to_lowercase()), head))
+        .filter(|(distance, head)| {
+            let head = head.to_lowercase();
+            *distance <= limit || head.contains(&target) || target.contains(&head)
+        })
+        .collect();
+    ranked.sort();
+    ranked.into_iter().take(MAX_SUGGESTIONS).map(|(_, head)| head.clone()).collect()
+}
+
+
+/// Renders the problem for a branch that does not exist on the remote.
+///
+/// # Arguments
+/// * `name` - The name of the attendee
+/// * `branch` - The branch that does not exist
+/// * `url` - The URL of the remote repository
+/// * `suggestions` - The similar branches on the remote
+///
+/// # Returns
+/// * `String` - The problem with the suggestions if there are any
+pub fn render_missing_branch(name: &str, branch: &str, url: &str, suggestions: &[String]) -> String {
+    let problem = format!("{}: branch {} does not exist on {}", name, branch, url);
+    match suggestions.is_empty() {
+        true => problem,
+        false => format!("{}, did you mean {}?", problem, suggestions.join(", "))
+    }
+}
+
+
+/// Counts the single character insertions, deletions, and substitutions to turn one string into another.
+///
+/// # Arguments
+/// * `from` - The first string
+/// * `to` - The second string
+///
+/// # Returns
+/// * `usize` - The Levenshtein distance between the strings
+pub fn edit_distance(from: &str, to: &str) -> usize {
+    let to: Vec<char> = to.chars().collect();
+    let mut previous: Vec<usize> = (0..=to.len()).collect();
+    for (i, from_char) in from.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, to_char) in to.iter().enumerate() {
+            let substitution = previous[j] + usize::from(from_char != *to_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[to.len()]
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::commands::command_runner::MockCoreRunner;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::{ExitStatus, Output};
+
+    const HEADS: &str = "1111111111111111111111111111111111111111\trefs/heads/main\n\
+                         2222222222222222222222222222222222222222\trefs/heads/development\n\
+                         3333333333333333333333333333333333333333\trefs/heads/feature/login\n\
+                         4444444444444444444444444444444444444444\trefs/heads/release-1.4\n";
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn listing(expected: &'static [&'static str], stdout: &'static str, mock_runner: &mut MockCoreRunner) {
+        mock_runner.expect_run_in_dir()
+            .withf(move |_, program, args| program == "git" && args == expected)
+            .times(1)
+            .returning(move |_, _, _| Ok(Output { status: ExitStatus::from_raw(0), stdout: stdout.as_bytes().to_vec(), stderr: Vec::new() }));
+    }
+
+    #[test]
+    fn test_parse_ls_remote_heads() {
+        assert_eq!(parse_ls_remote_heads(HEADS), names(&["main", "development", "feature/login", "release-1.4"]));
+        assert_eq!(parse_ls_remote_heads(""), Vec::<String>::new());
+        assert_eq!(parse_ls_remote_heads("warning: redirecting to https://github.com/org/auth.git/\n"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_suggest_branches() {
+        let heads = parse_ls_remote_heads(HEADS);
+        assert_eq!(suggest_branches("mian", &heads), names(&["main"]));
+        assert_eq!(suggest_branches("develop", &heads), names(&["development"]));
+        assert_eq!(suggest_branches("Feature/Logn", &heads), names(&["feature/login"]));
+        assert_eq!(suggest_branches("payments", &heads), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_suggest_branches_ranked_and_capped() {
+        let heads = names(&["release-1.5", "release-1.4", "release-2.0", "release-1.40", "release"]);
+        assert_eq!(suggest_branches("release-1.4.0", &heads), names(&["release-1.40", "release-1.4", "release-1.5"]));
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("main", "main"), 0);
+        assert_eq!(edit_distance("mian", "main"), 2);
+        assert_eq!(edit_distance("", "main"), 4);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_check_branch() {
+        let mut mock_runner = MockCoreRunner::new();
+        listing(&["ls-remote", "--heads", "--", "https://github.com/org/auth", "main"],
+                "1111111111111111111111111111111111111111\trefs/heads/main\n", &mut mock_runner);
+        assert_eq!(check_branch("https://github.com/org/auth", None, "main", &mock_runner), RefCheck::Found);
+
+        let mut mock_runner = MockCoreRunner::new();
+        listing(&["ls-remote", "--heads", "--", "https://github.com/org/auth", "mian"], "", &mut mock_runner);
+        listing(&["ls-remote", "--heads", "--", "https://github.com/org/auth"], HEADS, &mut mock_runner);
+        assert_eq!(check_branch("https://github.com/org/auth", None, "mian", &mock_runner), RefCheck::Missing(names(&["main"])));
+    }
+
+    #[test]
+    fn test_check_branch_private() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir_with_env()
+            .withf(|_, program, args, env| {
+                program == "git" && args == ["ls-remote", "--heads", "--", "https://github.com/org/auth", "main"]
+                    && env.iter().any(|(name, value)| name.starts_with("GIT_CONFIG_VALUE_") && value == "Authorization: Basic abc")
+            })
+            .times(1)
+            .returning(|_, _, _, _| Ok(Output {
+                status: ExitStatus::from_raw(0),
+                stdout: b"1111111111111111111111111111111111111111\trefs/heads/main\n".to_vec(),
+                stderr: Vec::new()
+            }));
+        let auth_config = GitConfigEntry {
+            key: "http.https://github.com/org/auth.extraHeader".to_string(),
+            value: "Authorization: Basic abc".to_string()
+        };
+        assert_eq!(check_branch("https://github.com/org/auth", Some(&auth_config), "main", &mock_runner), RefCheck::Found);
+    }
+
+    #[test]
+    fn test_check_branch_unreachable() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .returning(|_, _, _| Ok(Output { status: ExitStatus::from_raw(128 << 8), stdout: Vec::new(), stderr: b"fatal: Could not resolve host".to_vec() }));
+        match check_branch("https://github.com/org/auth", None, "main", &mock_runner) {
+            RefCheck::Unreachable(error) => assert!(error.contains("Could not resolve host"), "{}", error),
+            other => panic!("expected the remote to be unreachable, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_render_missing_branch() {
+        assert_eq!(
+            render_missing_branch("auth", "mian", "https://github.com/org/auth", &names(&["main", "maint"])),
+            "auth: branch mian does not exist on https://github.com/org/auth, did you mean main, maint?"
+        );
+        assert_eq!(
+            render_missing_branch("auth", "payments", "https://github.com/org/auth", &[]),
+            "auth: branch payments does not exist on https://github.com/org/auth"
+        );
+    }
+}
@@ -0,0 +1,180 @@
+//! Applies the ```restart_policy``` of a seating plan to every service without editing the compose files of the
+//! dependencies. Compose has no way to set a key on all services at once, so the service names are read from
+//! the compose files and an override file setting ```restart``` on each of them is generated into the venue and
+//! passed to docker-compose after the other files.
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::compose_file::{read_services, ComposeFileRef};
+use crate::file_handler::CoreFileHandle;
+use crate::paths::join_normalised;
+
+
+/// The restart policies docker-compose accepts.
+pub const RESTART_POLICIES: [&str; 4] = ["no", "always", "on-failure", "unless-stopped"];
+
+/// The name of the generated override file in the venue.
+pub const OVERRIDE_FILE: &str = "wedp_restart_override.yml";
+
+/// The attendee name given to the generated override file.
+pub const OVERRIDE_ATTENDEE: &str = "restart_policy";
+
+
+/// The restart policy set on a single service in the override file.
+#[derive(Serialize)]
+struct RestartService {
+    restart: String,
+}
+
+/// The contents of the override file.
+#[derive(Serialize)]
+struct RestartOverride {
+    services: BTreeMap<String, RestartService>,
+}
+
+
+/// Checks a restart policy is one docker-compose accepts.
+///
+/// # Arguments
+/// * `policy` - The restart policy from the seating plan
+///
+/// # Returns
+/// * `Result<(), String>` - An error listing the allowed policies if the policy is not one of them
+pub fn validate_restart_policy(policy: &str) -> Result<(), String> {
+    match RESTART_POLICIES.contains(&policy) {
+        true => Ok(()),
+        false => Err(format!("restart_policy {} is not one of {}", policy, RESTART_POLICIES.join(", ")))
+    }
+}
+
+
+/// Reads the names of the services defined in the compose files.
+///
+/// # Arguments
+/// * `compose_files` - The compose files to read the services from
+/// * `file_handle` - The file handle used to read the compose files
+///
+/// # Returns
+/// * `Result<Vec<String>, String>` - The sorted service names or an error if a file can not be read or parsed
+pub fn service_names(compose_files: &[ComposeFileRef], file_handle: &dyn CoreFileHandle) -> Result<Vec<String>, String> {
+    let mut names: Vec<String> = read_services(compose_files, file_handle)?.into_iter().map(|(name, _)| name).collect();
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
+
+
+/// Renders the override file setting the restart policy on every service.
+///
+/// # Arguments
+/// * `policy` - The restart policy to set
+/// * `services` - The names of the services to set it on
+///
+/// # Returns
+/// * `String` - The YAML contents of the override file
+pub fn render_override(policy: &str, services: &[String]) -> String {
+    let services = services.iter()
+                           .map(|name| (name.clone(), RestartService { restart: policy.to_string() }))
+                           .collect();
+    serde_yaml::to_string(&RestartOverride { services }).unwrap()
+}
+
+
+/// Generates the override file for the restart policy into the venue and adds it after the compose files.
+///
+/// # Arguments
+/// * `compose_files` - The compose files the services are read from
+/// * `policy` - The restart policy from the seating plan, ```None``` leaves the compose files as they are
+/// * `venue` - The venue the override file is written into
+/// * `file_handle` - The file handle used to read the compose files and write the override file
+///
+/// # Returns
+/// * `Result<Vec<ComposeFileRef>, String>` - The compose files including the override or an error if it could not be generated
+pub fn with_restart_override(compose_files: Vec<ComposeFileRef>, policy: Option<&str>, venue: &Path, file_handle: &dyn CoreFileHandle) -> Result<Vec<ComposeFileRef>, String> {
+    let policy = match policy {
+        Some(policy) => policy,
+        None => return Ok(compose_files)
+    };
+    validate_restart_policy(policy)?;
+    let services = service_names(&compose_files, file_handle)?;
+    let override_path = join_normalised(venue, &[OVERRIDE_FILE]);
+    file_handle.write(&override_path, &render_override(policy, &services))
+               .map_err(|error| format!("Could not write {}: {}", override_path.to_string_lossy(), error))?;
+    let mut compose_files = compose_files;
+    compose_files.push(ComposeFileRef::new(OVERRIDE_ATTENDEE, override_path.to_string_lossy().to_string()));
+    Ok(compose_files)
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::file_handler::MockCoreFileHandle;
+    use mockall::predicate::eq;
+    use std::path::PathBuf;
+
+    fn test_files() -> Vec<ComposeFileRef> {
+        vec![
+            ComposeFileRef::new("auth", "venue/auth/base.yml".to_string()),
+            ComposeFileRef::new("auth", "venue/auth/database.yml".to_string()),
+        ]
+    }
+
+    fn mock_compose_files(mock_handle: &mut MockCoreFileHandle) {
+        mock_handle.expect_read_to_string()
+            .with(eq(PathBuf::from("venue/auth/base.yml")))
+            .returning(|_| Ok("services:\n  auth:\n    image: auth\n  auth_db:\n    image: postgres\n".to_string()));
+        mock_handle.expect_read_to_string()
+            .with(eq(PathBuf::from("venue/auth/database.yml")))
+            .returning(|_| Ok("version: \"3\"\nservices:\n  auth_db:\n    ports:\n      - 5432:5432\n".to_string()));
+    }
+
+    #[test]
+    fn test_validate_restart_policy() {
+        for policy in RESTART_POLICIES {
+            assert_eq!(validate_restart_policy(policy), Ok(()));
+        }
+        assert_eq!(
+            validate_restart_policy("sometimes"),
+            Err("restart_policy sometimes is not one of no, always, on-failure, unless-stopped".to_string())
+        );
+    }
+
+    #[test]
+    fn test_service_names() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_compose_files(&mut mock_handle);
+        assert_eq!(service_names(&test_files(), &mock_handle), Ok(vec!["auth".to_string(), "auth_db".to_string()]));
+    }
+
+    #[test]
+    fn test_with_restart_override() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_compose_files(&mut mock_handle);
+        mock_handle.expect_write()
+            .with(
+                eq(PathBuf::from("venue/wedp_restart_override.yml")),
+                eq("services:\n  auth:\n    restart: unless-stopped\n  auth_db:\n    restart: unless-stopped\n")
+            )
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let files = with_restart_override(test_files(), Some("unless-stopped"), Path::new("./venue/"), &mock_handle).unwrap();
+        assert_eq!(files.last(), Some(&ComposeFileRef::new("restart_policy", "venue/wedp_restart_override.yml".to_string())));
+        assert_eq!(files.len(), 3);
+    }
+
+    #[test]
+    fn test_with_restart_override_none() {
+        let mock_handle = MockCoreFileHandle::new();
+        assert_eq!(with_restart_override(test_files(), None, Path::new("venue"), &mock_handle), Ok(test_files()));
+    }
+
+    #[test]
+    fn test_with_restart_override_invalid() {
+        let mock_handle = MockCoreFileHandle::new();
+        assert!(with_restart_override(test_files(), Some("forever"), Path::new("venue"), &mock_handle).is_err());
+    }
+}
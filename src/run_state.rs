@@ -0,0 +1,67 @@
+//! Records which compose files the dependencies were last started in the background with, in the ```.wedp```
+//! directory of the venue, so following the logs afterwards uses the same files as the run even if the
+//! seating plan has been edited or the run was limited with ```--only``` or ```--except```.
+use serde::{Deserialize, Serialize};
+
+use crate::compose_file::ComposeFileRef;
+
+
+/// The name of the run state file in the ```.wedp``` directory of the venue.
+pub const RUN_STATE_FILE: &str = "run.json";
+
+
+/// The compose files the dependencies were last started with.
+///
+/// # Fields
+/// * `remote` - Whether the remote runner files were used
+/// * `compose_files` - The compose files passed to docker-compose, including any generated override
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunState {
+    #[serde(default)]
+    pub remote: bool,
+    #[serde(default)]
+    pub compose_files: Vec<ComposeFileRef>,
+}
+
+impl RunState {
+
+    /// Gets the compose files of the last run if it used the same kind of runner files.
+    ///
+    /// # Arguments
+    /// * `remote` - Whether the remote runner files are wanted
+    ///
+    /// # Returns
+    /// * `Option<Vec<ComposeFileRef>>` - The compose files or ```None``` if nothing matching was started
+    pub fn compose_files_for(self, remote: bool) -> Option<Vec<ComposeFileRef>> {
+        match self.remote == remote && !self.compose_files.is_empty() {
+            true => Some(self.compose_files),
+            false => None
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn state(remote: bool) -> RunState {
+        RunState { remote, compose_files: vec![ComposeFileRef::new("auth", "venue/auth/base.yml".to_string())] }
+    }
+
+    #[test]
+    fn test_compose_files_for() {
+        assert_eq!(state(false).compose_files_for(false), Some(vec![ComposeFileRef::new("auth", "venue/auth/base.yml".to_string())]));
+        assert_eq!(state(false).compose_files_for(true), None);
+        assert_eq!(state(true).compose_files_for(true).map(|files| files.len()), Some(1));
+        assert_eq!(RunState::default().compose_files_for(false), None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let json = serde_json::to_string(&state(true)).unwrap();
+        assert_eq!(json, r#"{"remote":true,"compose_files":[{"attendee":"auth","path":"venue/auth/base.yml"}]}"#);
+        assert_eq!(serde_json::from_str::<RunState>(&json).unwrap(), state(true));
+    }
+}
@@ -1,20 +1,502 @@
 //! The Runner handles all the processes of the dependencies. 
-use std::{env, path::Path};
+use std::{collections::HashMap, env, fmt, path::{Path, PathBuf}, thread};
+use std::process::ExitStatus;
+use std::sync::{Mutex, OnceLock};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
+use crate::error::WedpError;
+use crate::logging;
+use crate::clock::{Clock, SystemClock};
+use crate::health_http::{wait_for_status, POLL_INTERVAL};
+use crate::remote_refs::{check_branch, render_missing_branch, RefCheck};
+use crate::attendee_filter::AttendeeFilter;
+use crate::compose_binary::{resolve_compose_command, validate_compose_command, ContainerEngine, COMPOSE_ENV, COMPOSE_V1};
+use crate::command_defaults::{resolve_flags, unknown_keys, ResolvedFlags};
+use crate::container_status::{compose_ps, ContainerStatus};
+use crate::compose_inspect::inspect_compose_files;
+use crate::compose_file::{missing_files, render_missing_files, compose_args, ComposeAction, ComposeFileRef, ServiceName, FORCE_RECREATE};
 use crate::seating_plan::SeatingPlan;
+use crate::paths::join_normalised;
+use crate::project_location::check_project_location;
+use crate::plan_cache::{PlanCache, CACHE_DIR_ENV};
+use crate::restart_policy::with_restart_override;
+use crate::image_arch::with_image_arch_override;
+use crate::run_state::{RunState, RUN_STATE_FILE};
+use crate::state::VenueState;
+use crate::disk_usage::{venue_disk_usage, DependencySize};
+use crate::usage_stats::{UsageStats, STATS_FILE};
+use crate::vendor_manifest::{build_manifest, render_manifest, MANIFEST_FILE};
+use crate::dependency::{Dependency, GitRef};
+use crate::lockfile::{self, LockEntry, Lockfile};
+use crate::build_diff::{diff_build_file, render_change};
+use crate::bump_branch::bump_branches;
+use crate::doctor::{check_local_dockerfiles, check_overrides, check_venue, check_venue_gitignore, Finding};
+use crate::wedding_invite::{BuildFileCopy, WeddingInvite};
 use crate::commands::command_runner::{
+    check_status,
+    run_docker_checked,
     CoreRunner,
-    CommandRunner
+    CommandRunner,
+    ProgramArgs
 };
-use crate::file_handler::FileHandle;
+use crate::commands::create_archive::CreateArchiveCommand;
+use crate::commands::current_branch::CurrentBranchCommand;
+use crate::commands::git_version::GitVersionCommand;
+use crate::commands::git_passthrough::GitPassthroughCommand;
+use crate::commands::limiter::{operation_limits, LimitedRunner, OperationLimits};
+use crate::commands::trace::traced;
+use crate::file_handler::{CoreFileHandle, FileHandle};
+use crate::dry_run::{DryRunFileHandle, DryRunRunner};
+use crate::open_url::{expand_open_url, launcher_command, parse_host_port, OpenPort, HOST_PORT_PLACEHOLDER};
+
+
+/// The name of the seating plan file searched for when ```-f``` is not passed.
+pub const DEFAULT_PLAN_FILE: &str = "wedding_planner.yml";
+
+/// The environment variable that changes the name of the seating plan file searched for.
+pub const PLAN_FILE_ENV: &str = "WEDP_PLAN_FILE";
+
+
+/// The installation state of a dependency in the venue.
+/// 
+/// # Fields
+/// * `name` - The name of the dependency
+/// * `installed` - Whether the dependency has been cloned into the venue
+/// * `current_branch` - The branch currently checked out, ```None``` if not installed or the branch could not be read
+/// * `expected` - The branch or rev defined in the seating plan
+/// * `mismatch` - Whether the current branch differs from the expected branch
+#[derive(Debug, PartialEq)]
+pub struct DependencyStatus {
+    pub name: String,
+    pub installed: bool,
+    pub current_branch: Option<String>,
+    pub expected: String,
+    pub mismatch: bool,
+}
+
+
+/// What ```preflight``` found in the venue before any compose files are collected.
+/// 
+/// # Fields
+/// * `venue` - The venue of the seating plan as it is written
+/// * `venue_exists` - Whether the venue directory exists
+/// * `selected` - The attendees the command applies to
+/// * `missing` - The selected attendees that are not cloned into the venue
+#[derive(Debug, PartialEq)]
+pub struct PreflightReport {
+    pub venue: String,
+    pub venue_exists: bool,
+    pub selected: Vec<String>,
+    pub missing: Vec<String>,
+}
+
+impl PreflightReport {
+
+    /// Checks if docker can be run, which needs the venue and at least one of the selected attendees. Some 
+    /// attendees missing is left to the commands, which list the missing wedding invites.
+    /// 
+    /// # Returns
+    /// * `bool` - True if the venue exists and holds at least one selected attendee, or no attendee is selected
+    pub fn is_ready(&self) -> bool {
+        self.venue_exists && (self.selected.is_empty() || self.missing.len() < self.selected.len())
+    }
+
+    /// Turns the report into the message telling the user what to run first.
+    /// 
+    /// # Returns
+    /// * `Result<(), String>` - An error naming the venue and the missing attendees if docker can not be run
+    pub fn check(&self) -> Result<(), String> {
+        if self.is_ready() {
+            return Ok(())
+        }
+        let problem = match self.venue_exists {
+            false => format!("venue {} does not exist, run `wedp setup && wedp install` first", self.venue),
+            true => format!("none of the attendees are cloned into the venue {}, run `wedp install` first", self.venue)
+        };
+        Err(format!("{}\nmissing attendees: {}", problem, self.missing.join(", ")))
+    }
+}
+
+
+/// The steps of installing a dependency that ran.
+/// 
+/// # Fields
+/// * `name` - The name of the dependency
+/// * `cloned` - Whether the repository was cloned into the venue
+/// * `fetched` - Whether the latest changes were fetched into a repository that was already cloned
+/// * `pulled` - Whether the checked out branch of a repository that was already cloned was fast forwarded
+/// * `extracted` - Whether the working tree was extracted from a vendored archive instead of cloned
+/// * `linked` - Whether the ```path``` of the dependency was symlinked into the venue instead of cloned
+/// * `copied` - Whether the ```path``` of the dependency was copied into the venue instead of cloned
+/// * `checked_out` - Whether the branch, tag, or commit was checked out
+/// * `submodules_updated` - Whether the submodules were initialized
+/// * `build_file_prepared` - Whether the Dockerfile was copied into the build root
+/// * `init_build_file_prepared` - Whether the init Dockerfile was copied into the init build root
+/// * `stashed` - Whether the local changes of the working tree were stashed before updating
+/// * `skipped` - Whether the update was skipped because the working tree has local changes
+/// * `old_commit` - The commit checked out before updating, ```None``` if it was not read
+/// * `new_commit` - The commit checked out after updating, ```None``` if it was not read
+/// * `failed_step` - The first step that failed, ```None``` if every step succeeded
+/// * `errors` - The errors of the steps that failed
+#[derive(Debug, Default, PartialEq)]
+pub struct InstallReport {
+    pub name: String,
+    pub cloned: bool,
+    pub fetched: bool,
+    pub pulled: bool,
+    pub extracted: bool,
+    pub linked: bool,
+    pub copied: bool,
+    pub checked_out: bool,
+    pub submodules_updated: bool,
+    pub build_file_prepared: bool,
+    pub init_build_file_prepared: bool,
+    pub stashed: bool,
+    pub skipped: bool,
+    pub old_commit: Option<String>,
+    pub new_commit: Option<String>,
+    pub failed_step: Option<InstallStep>,
+    pub errors: Vec<String>,
+}
+
+impl InstallReport {
+
+    /// Creates a new InstallReport struct with no steps run.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the dependency
+    /// 
+    /// # Returns
+    /// A new InstallReport struct
+    pub fn new(name: &str) -> Self {
+        InstallReport { name: name.to_string(), ..Default::default() }
+    }
+
+    /// Records a failed step, keeping the first step that failed.
+    /// 
+    /// # Arguments
+    /// * `step` - The step that failed
+    /// * `error` - The error of the step
+    pub fn fail(&mut self, step: InstallStep, error: String) {
+        self.failed_step.get_or_insert(step);
+        self.errors.push(error);
+    }
+
+    /// Gets where the working tree of the dependency came from.
+    /// 
+    /// # Returns
+    /// * `&str` - How the dependency was put into the venue, ```-``` if it was not
+    fn source(&self) -> &'static str {
+        let sources = [
+            ("cloned", self.cloned),
+            ("fetched", self.fetched),
+            ("extracted", self.extracted),
+            ("linked", self.linked),
+            ("copied", self.copied),
+        ];
+        sources.iter().find(|(_, done)| *done).map_or("-", |(source, _)| source)
+    }
+}
+
+
+/// A step of installing a dependency.
+/// 
+/// # Fields
+/// * `Remove` - Removing the old install with ```--force```
+/// * `Clone` - Cloning the repository
+/// * `Fetch` - Fetching into a repository that is already cloned
+/// * `Extract` - Extracting the dependency from a vendored archive
+/// * `Path` - Linking or copying the ```path``` of the dependency
+/// * `WorkingTree` - Reading the local changes and the commit of the working tree before an update
+/// * `Stash` - Stashing the local changes before an update
+/// * `Checkout` - Checking out the branch, tag, or commit
+/// * `Pull` - Fast forwarding the branch
+/// * `Submodules` - Initializing the submodules
+/// * `WeddingInvite` - Reading the wedding invite
+/// * `BuildFile` - Copying the Dockerfile into the build root
+/// * `InitBuildFile` - Copying the init Dockerfile into the init build root
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InstallStep {
+    Remove,
+    Clone,
+    Fetch,
+    Extract,
+    Path,
+    WorkingTree,
+    Stash,
+    Checkout,
+    Pull,
+    Submodules,
+    WeddingInvite,
+    BuildFile,
+    InitBuildFile,
+}
+
+impl fmt::Display for InstallStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let step = match self {
+            InstallStep::Remove => "remove",
+            InstallStep::Clone => "clone",
+            InstallStep::Fetch => "fetch",
+            InstallStep::Extract => "extract",
+            InstallStep::Path => "path",
+            InstallStep::WorkingTree => "working tree",
+            InstallStep::Stash => "stash",
+            InstallStep::Checkout => "checkout",
+            InstallStep::Pull => "pull",
+            InstallStep::Submodules => "submodules",
+            InstallStep::WeddingInvite => "wedding invite",
+            InstallStep::BuildFile => "build file",
+            InstallStep::InitBuildFile => "init build file",
+        };
+        write!(f, "{}", step)
+    }
+}
+
+
+/// Checks the install reports for failures.
+/// 
+/// # Arguments
+/// * `reports` - The install report of each dependency
+/// 
+/// # Returns
+/// * `Result<(), String>` - An error listing every failed step
+pub fn check_install_reports(reports: &[InstallReport]) -> Result<(), String> {
+    let errors: Vec<&str> = reports.iter()
+        .flat_map(|report| report.errors.iter().map(|error| error.as_str()))
+        .collect();
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors.join("\n"))
+    }
+}
+
+
+/// Renders a summary of the install as a table of the steps that ran for each dependency, followed by a line
+/// counting the dependencies installed and naming the step each failed one failed at.
+/// 
+/// # Arguments
+/// * `reports` - The install report of each dependency
+/// 
+/// # Returns
+/// * `String` - The rendered summary
+pub fn render_install_summary(reports: &[InstallReport]) -> String {
+    let done = |step: bool| if step { "yes" } else { "-" };
+    let mut summary = format!("{:<20} {:<10} {:<10} {:<12} {:<16} {}\n", "NAME", "SOURCE", "CHECKOUT", "BUILD FILE", "INIT BUILD FILE", "RESULT");
+    for report in reports {
+        summary.push_str(&format!(
+            "{:<20} {:<10} {:<10} {:<12} {:<16} {}\n",
+            report.name,
+            report.source(),
+            done(report.checked_out),
+            done(report.build_file_prepared),
+            done(report.init_build_file_prepared),
+            if report.errors.is_empty() { "installed" } else { "failed" }
+        ));
+    }
+    let installed = reports.iter().filter(|report| report.errors.is_empty()).count();
+    let failed: Vec<String> = reports.iter()
+        .filter(|report| !report.errors.is_empty())
+        .map(|report| match report.failed_step {
+            Some(step) => format!("{}: {} error", report.name, step),
+            None => format!("{}: error", report.name)
+        })
+        .collect();
+    match failed.is_empty() {
+        true => summary.push_str(&format!("{} installed\n", installed)),
+        false => summary.push_str(&format!("{} installed, {} failed ({})\n", installed, failed.len(), failed.join(", ")))
+    }
+    summary
+}
+
+
+/// Renders a summary of the update with the old and new commit of each dependency and the number updated.
+/// 
+/// # Arguments
+/// * `reports` - The update report of each dependency
+/// 
+/// # Returns
+/// * `String` - The rendered summary
+pub fn render_update_summary(reports: &[InstallReport]) -> String {
+    let short = |commit: &String| commit.chars().take(7).collect::<String>();
+    let mut summary = String::new();
+    for report in reports {
+        let outcome = match (&report.old_commit, &report.new_commit) {
+            _ if !report.errors.is_empty() => "failed".to_string(),
+            _ if report.skipped => "skipped, the working tree has local changes".to_string(),
+            (Some(old), Some(new)) if old == new => format!("up to date at {}", short(new)),
+            (Some(old), Some(new)) => format!("{} -> {}", short(old), short(new)),
+            _ => "updated".to_string()
+        };
+        let stashed = match report.stashed {
+            true => ", local changes stashed",
+            false => ""
+        };
+        summary.push_str(&format!("{}: {}{}\n", report.name, outcome, stashed));
+    }
+    let updated = reports.iter().filter(|report| report.errors.is_empty() && !report.skipped).count();
+    summary.push_str(&format!("{} of {} dependencies updated\n", updated, reports.len()));
+    summary
+}
+
+
+/// Gets the names of the dependencies that installed without errors.
+/// 
+/// # Arguments
+/// * `reports` - The install report of each dependency
+/// 
+/// # Returns
+/// * `Vec<String>` - The names of the dependencies that were installed
+pub fn installed_names(reports: &[InstallReport]) -> Vec<String> {
+    reports.iter()
+           .filter(|report| report.errors.is_empty())
+           .map(|report| report.name.clone())
+           .collect()
+}
+
+
+/// Gets the name of the seating plan file searched for, ```WEDP_PLAN_FILE``` if it is set.
+/// 
+/// # Arguments
+/// * `plan_file_env` - The value of ```WEDP_PLAN_FILE```
+/// 
+/// # Returns
+/// * `String` - The name of the seating plan file
+pub fn plan_file_name(plan_file_env: Option<String>) -> String {
+    plan_file_env.filter(|name| !name.is_empty()).unwrap_or_else(|| DEFAULT_PLAN_FILE.to_string())
+}
+
+
+/// Searches a directory and then each directory above it for the seating plan file, the way git searches for
+/// ```.git```, so wedp can be run from any subdirectory of a project. The closest seating plan wins.
+/// 
+/// # Arguments
+/// * `start` - The directory the search starts from
+/// * `file_name` - The name of the seating plan file
+/// 
+/// # Returns
+/// * `Result<PathBuf, String>` - The path to the seating plan or an error if no directory up to the root has one
+pub fn find_seating_plan(start: &Path, file_name: &str) -> Result<PathBuf, String> {
+    start.ancestors()
+         .map(|directory| directory.join(file_name))
+         .find(|path| path.is_file())
+         .ok_or_else(|| format!(
+             "No {} found in {} or any directory above it, pass -f to name the seating plan", 
+             file_name, start.to_string_lossy()
+         ))
+}
+
+
+/// Gets the default number of dependencies to install at the same time, the number of CPUs.
+/// 
+/// # Returns
+/// * `usize` - The number of CPUs or 1 if it can not be read
+pub fn default_install_jobs() -> usize {
+    thread::available_parallelism().map(|jobs| jobs.get()).unwrap_or(1)
+}
+
+
+/// Gets the exit code of ```docker-compose logs```. Following logs is stopped with Ctrl+C which kills 
+/// docker-compose with a signal, this counts as a clean exit.
+/// 
+/// # Arguments
+/// * `status` - The exit status of docker-compose
+/// * `follow` - Whether the logs were followed
+/// 
+/// # Returns
+/// * `i32` - The exit code of docker-compose, ```0``` if following was interrupted and ```1``` for any other signal
+pub fn logs_exit_code(status: ExitStatus, follow: bool) -> i32 {
+    match status.code() {
+        Some(code) => code,
+        None if follow => 0,
+        None => 1
+    }
+}
+
+
+/// Copies the Dockerfiles for the CPU into the build root and the init build root of a dependency. The init 
+/// build is prepared even if the dependency has no ```build_files```.
+/// 
+/// # Arguments
+/// * `wedding_invite` - The wedding invite of the dependency
+/// * `full_venue_path` - The full path to the venue
+/// * `arch` - The CPU type to pick the Dockerfiles for, ```None``` uses the CPU type of the host
+/// * `file_handle` - The file handle used to copy the Dockerfiles
+/// * `report` - The install report the prepared builds and errors are recorded in
+fn prepare_builds(wedding_invite: &WeddingInvite, full_venue_path: &str, arch: Option<&str>, file_handle: &dyn CoreFileHandle, report: &mut InstallReport) {
+    match wedding_invite.build_file_copy(full_venue_path, &report.name, arch) {
+        Ok(Some(copy)) => match file_handle.copy(&copy.from, &copy.to) {
+            Ok(_) => report.build_file_prepared = true,
+            Err(error) => report.fail(InstallStep::BuildFile, format!("Failed to prepare build file for {}: {}", report.name, error))
+        },
+        Ok(None) => {},
+        Err(error) => report.fail(InstallStep::BuildFile, format!("Failed to prepare build file for {}: {}", report.name, error))
+    }
+    match wedding_invite.init_build_file_copy(full_venue_path, &report.name, arch) {
+        Ok(Some(copy)) => match file_handle.copy(&copy.from, &copy.to) {
+            Ok(_) => {
+                logging::info(&format!("{}: prepared init build file", report.name));
+                report.init_build_file_prepared = true
+            },
+            Err(error) => report.fail(InstallStep::InitBuildFile, format!("Failed to prepare init build file for {}: {}", report.name, error))
+        },
+        Ok(None) => {},
+        Err(error) => report.fail(InstallStep::InitBuildFile, format!("Failed to prepare init build file for {}: {}", report.name, error))
+    }
+}
+
+
+/// Renders the status of the dependencies as a table.
+/// 
+/// # Arguments
+/// * `statuses` - The status of each dependency
+/// 
+/// # Returns
+/// * `String` - The rendered table
+pub fn render_status_table(statuses: &[DependencyStatus]) -> String {
+    let mut table = format!("{:<20} {:<10} {:<20} {:<20} {}\n", "NAME", "INSTALLED", "CURRENT", "EXPECTED", "MISMATCH");
+    for status in statuses {
+        let row = format!(
+            "{:<20} {:<10} {:<20} {:<20} {}",
+            status.name,
+            if status.installed { "yes" } else { "no" },
+            status.current_branch.as_deref().unwrap_or("-"),
+            status.expected,
+            if status.mismatch { "yes" } else { "" }
+        );
+        table.push_str(row.trim_end());
+        table.push('\n');
+    }
+    table
+}
 
 
 /// Runs the processes for seating plan and thus runs the processes around running dependencies.
 /// 
 /// # Fields 
 /// * `seating_plan` - The seating plan that defines the dependencies to run
+/// * `command_runner` - The runner that runs the git and docker commands
+/// * `file_handle` - The file handle that performs the file operations
+/// * `arch` - The CPU type to pick the Dockerfiles for, ```None``` uses the CPU type of the host
+/// * `filter` - The attendees the compose files are read from and that are installed
+/// * `engine` - The container engine picked with ```--engine```, overriding the seating plan
+/// * `probe_runner` - The runner for read-only probes such as the installed compose commands and the branches on the remotes, which change nothing so they also run for real in a dry run
+/// * `compose` - The command docker compose is run with, resolved the first time it is needed
+/// * `skip_missing` - Whether runner files that do not exist are left out with a warning instead of failing the command
+/// * `force_recreate` - Whether the containers are recreated and orphans removed when started, even if the compose project runs from another location
+/// * `git_available` - Whether git is installed, probed the first time an optional check that needs git runs
 pub struct Runner {
-    pub seating_plan: SeatingPlan
+    pub seating_plan: SeatingPlan,
+    pub command_runner: Box<dyn CoreRunner>,
+    pub file_handle: Box<dyn CoreFileHandle>,
+    pub arch: Option<String>,
+    pub filter: AttendeeFilter,
+    pub engine: Option<ContainerEngine>,
+    pub probe_runner: Box<dyn CoreRunner>,
+    pub compose: OnceLock<String>,
+    pub skip_missing: bool,
+    pub force_recreate: bool,
+    pub git_available: OnceLock<bool>
 }
 
 
@@ -23,174 +505,3670 @@ impl Runner {
     /// The constructor for the Runner struct.
     /// 
     /// # Arguments
-    /// * `path` - The path to the seating plan file
+    /// * `paths` - The paths to the seating plan files, merged in order if there is more than one
+    /// 
+    /// # Returns
+    /// * `Result<Runner, WedpError>` - A Runner struct or the error loading the seating plans
+    pub fn new(paths: Vec<String>) -> Result<Runner, WedpError> {
+        Runner::with_handles(paths, Box::new(CommandRunner {}), Box::new(FileHandle {}))
+    }
+
+    /// The constructor for the Runner struct running the commands and file operations through the runner and 
+    /// file handle passed in. The seating plan cache is also written through the file handle. If the command line
+    /// or the seating plan limits the docker or git commands run at the same time the runner is wrapped to 
+    /// enforce the limits.
+    /// 
+    /// # Arguments
+    /// * `paths` - The paths to the seating plan files, merged in order if there is more than one
+    /// * `command_runner` - The runner for the git and docker commands
+    /// * `file_handle` - The file handle for the file operations
     /// 
     /// # Returns
-    /// * `Runner` - A Runner struct wrapped in a result
-    pub fn new(path: String) -> Result<Runner, String> {
-        match SeatingPlan::from_file(path){
-            Ok(seating_plan) => Ok(Runner{seating_plan}),
-            Err(error) => Err(error)
+    /// * `Result<Runner, WedpError>` - A Runner struct or the error loading or merging the seating plans
+    pub fn with_handles(paths: Vec<String>, command_runner: Box<dyn CoreRunner>, file_handle: Box<dyn CoreFileHandle>) -> Result<Runner, WedpError> {
+        let load = |path: &String| match env::var(CACHE_DIR_ENV) {
+            Ok(cache_dir) => PlanCache::new(PathBuf::from(cache_dir), file_handle.as_ref())
+                .load(path, &SeatingPlan::parse)
+                .and_then(|seating_plan| seating_plan.expand_paths(path)),
+            Err(_) => SeatingPlan::from_file(path.clone())
+        };
+        let (first, others) = paths.split_first()
+            .ok_or_else(|| WedpError::InvalidConfig("no seating plan file was given".to_string()))?;
+        let mut seating_plan = load(first)?;
+        for path in others {
+            seating_plan = seating_plan.merge(load(path)?, path)?;
         }
+        Runner::from_seating_plan(seating_plan, command_runner, file_handle)
     }
 
-    /// Creates the venue directory.
-    pub fn create_venue(&self) {
-        match self.seating_plan.create_venue(&FileHandle{}){
-            Ok(_) => {
-                println!("Created venue directory");
-            },
-            Err(error) => println!("Failed to create venue: {}", error)
+    /// The constructor for the Runner struct used by ```validate```. Unknown keys are left out of the seating 
+    /// plans and returned as problems with the attendees that are listed more than once, the ```url``` that do 
+    /// not look like git remotes, and the plans that could not be merged, so every problem is reported at once.
+    /// 
+    /// # Arguments
+    /// * `paths` - The paths to the seating plan files, merged in order if there is more than one
+    /// 
+    /// # Returns
+    /// * `Result<(Runner, Vec<String>), WedpError>` - A Runner struct and every problem found or the error reading or
+    ///   parsing the first seating plan
+    pub fn new_lenient(paths: Vec<String>) -> Result<(Runner, Vec<String>), WedpError> {
+        let (first, others) = paths.split_first()
+            .ok_or_else(|| WedpError::InvalidConfig("no seating plan file was given".to_string()))?;
+        let (mut seating_plan, mut problems) = SeatingPlan::from_file_lenient(first.clone())?;
+        for path in others {
+            let merged = SeatingPlan::from_file_lenient(path.clone()).and_then(|(other, unknown)| {
+                problems.extend(unknown);
+                seating_plan.clone().merge(other, path)
+            });
+            match merged {
+                Ok(merged) => seating_plan = merged,
+                Err(error) => problems.push(error.to_string())
+            }
+        }
+        problems.extend(seating_plan.problems().into_iter().map(|problem| format!("{}: {}", paths.join(", "), problem)));
+        let runner = Runner::from_seating_plan(seating_plan, Box::new(CommandRunner {}), Box::new(FileHandle {}))?;
+        Ok((runner, problems))
+    }
+
+    /// Builds the Runner struct around a seating plan that is already loaded, wrapping the runner to enforce 
+    /// the limits on the docker and git commands and to record them with ```--trace-script```.
+    /// 
+    /// # Arguments
+    /// * `seating_plan` - The loaded seating plan
+    /// * `command_runner` - The runner for the git and docker commands
+    /// * `file_handle` - The file handle for the file operations
+    /// 
+    /// # Returns
+    /// * `Result<Runner, WedpError>` - A Runner struct or an error if ```WEDP_COMPOSE``` is not a valid command
+    fn from_seating_plan(seating_plan: SeatingPlan, command_runner: Box<dyn CoreRunner>, file_handle: Box<dyn CoreFileHandle>) -> Result<Runner, WedpError> {
+        for key in unknown_keys(&seating_plan.command_defaults) {
+            logging::warn(&format!("unknown key {} in command_defaults, ignoring", key));
+        }
+        if let Ok(command) = env::var(COMPOSE_ENV) {
+            validate_compose_command(&command)
+                .map_err(|error| WedpError::InvalidConfig(format!("{} in {}", error, COMPOSE_ENV)))?;
+        }
+        let limits = operation_limits().or(OperationLimits { docker: seating_plan.max_docker_ops, git: seating_plan.max_git_ops });
+        let command_runner = traced(command_runner);
+        let command_runner: Box<dyn CoreRunner> = match limits.is_unlimited() {
+            true => command_runner,
+            false => Box::new(LimitedRunner::new(command_runner, limits))
         };
+        Ok(Runner{
+            seating_plan,
+            command_runner,
+            file_handle,
+            arch: None,
+            filter: AttendeeFilter::All,
+            engine: None,
+            probe_runner: traced(Box::new(CommandRunner {})),
+            compose: OnceLock::new(),
+            skip_missing: false,
+            force_recreate: false,
+            git_available: OnceLock::new()
+        })
     }
 
-    /// Gets the docker-compose command for the dependencies in the seating plan.
+    /// The constructor for a Runner struct that prints the commands and file operations it would run instead 
+    /// of running them, nothing on the system is changed including the seating plan cache.
     /// 
     /// # Arguments
-    /// * `remote` - If true the remote docker-compose files meaning the docker-compose files that rely on images from Dockerhub
+    /// * `paths` - The paths to the seating plan files, merged in order if there is more than one
     /// 
     /// # Returns
-    /// * `String` - The docker-compose command
+    /// * `Result<Runner, WedpError>` - A Runner struct for a dry run or the error loading the seating plans
+    pub fn new_dry_run(paths: Vec<String>) -> Result<Runner, WedpError> {
+        Runner::with_handles(paths, Box::new(DryRunRunner::stdout()), Box::new(DryRunFileHandle::stdout()))
+    }
+
+    /// Picks the Dockerfiles for a CPU type other than the CPU type of the host.
     /// 
-    /// # Example
-    /// ```
-    /// docker-compose -f venue/dependency1/docker-compose.yml -f venue/dependency2/docker-compose.yml
-    /// ```
-    pub fn get_compose_file_command(&self, remote: bool) -> String {
-        let venue = &self.seating_plan.venue;
-        let mut command_string = "docker-compose ".to_owned();
+    /// # Arguments
+    /// * `arch` - The CPU type to pick the Dockerfiles for, ```None``` uses the CPU type of the host
+    /// 
+    /// # Returns
+    /// * `Runner` - The Runner struct picking the Dockerfiles for the CPU type
+    pub fn with_arch(self, arch: Option<String>) -> Runner {
+        Runner { arch, ..self }
+    }
 
-        for dependency in &self.seating_plan.attendees {
-            let wedding_invite = dependency.get_wedding_invite(&venue).unwrap();
+    /// Picks the container engine instead of the ```engine``` of the seating plan.
+    /// 
+    /// # Arguments
+    /// * `engine` - The container engine to run the dependencies with, ```None``` keeps the seating plan setting
+    /// 
+    /// # Returns
+    /// * `Runner` - The Runner struct running the dependencies with the engine
+    pub fn with_engine(self, engine: Option<ContainerEngine>) -> Runner {
+        Runner { engine, compose: OnceLock::new(), ..self }
+    }
 
-            let files = match remote {
-                true => wedding_invite.get_remote_compose_files(&venue, &dependency.name),
-                false => wedding_invite.get_docker_compose_files(&venue, &dependency.name)
-            };
-            command_string.push_str(&files);
+    /// Runs docker compose with the command instead of resolving it, this overrides ```WEDP_COMPOSE``` and 
+    /// the engine so it must be called after ```with_engine```.
+    /// 
+    /// # Arguments
+    /// * `command` - The compose command such as ```docker compose```, ```None``` resolves the command as usual
+    /// 
+    /// # Returns
+    /// * `Runner` - The Runner struct running docker compose with the command
+    pub fn with_compose_command(self, command: Option<String>) -> Runner {
+        match command {
+            Some(command) => Runner { compose: OnceLock::from(command), ..self },
+            None => self
         }
-        return command_string;
     }
 
-    /// Installs all of the dependencies in the seating plan. 
-    pub fn install_dependencies(&self) {
-        let cwd = env::current_dir().unwrap().to_str().unwrap().to_owned();
-        let venue = &self.seating_plan.venue;
-        let full_venue_path = Path::new(&cwd).join(&venue).to_string_lossy().to_string();
+    /// Leaves out runner files that do not exist with a warning instead of failing the command.
+    /// 
+    /// # Arguments
+    /// * `skip_missing` - Whether to leave out the runner files that do not exist
+    /// 
+    /// # Returns
+    /// * `Runner` - The Runner struct skipping the missing runner files
+    pub fn with_skip_missing(self, skip_missing: bool) -> Runner {
+        Runner { skip_missing, ..self }
+    }
 
-        let command_runner = CommandRunner {};
-        let file_handle = FileHandle {};
+    /// Reads the relative venue and attendee paths from the directory of a seating plan found above the directory
+    /// wedp was started in, see ```SeatingPlan::relative_to```.
+    /// 
+    /// # Arguments
+    /// * `plan_root` - The directory holding the seating plan, ```None``` reads them from the current directory
+    /// 
+    /// # Returns
+    /// * `Runner` - The Runner struct reading the paths from the directory of the seating plan
+    pub fn with_plan_root(self, plan_root: Option<&Path>) -> Runner {
+        match plan_root {
+            Some(root) => Runner { seating_plan: self.seating_plan.relative_to(root), ..self },
+            None => self
+        }
+    }
 
-        for dependency in &self.seating_plan.attendees {
+    /// Recreates the containers and removes the orphans when starting the dependencies instead of refusing to
+    /// start a compose project that is already running from another location.
+    /// 
+    /// # Arguments
+    /// * `force_recreate` - Whether to recreate the containers
+    /// 
+    /// # Returns
+    /// * `Runner` - The Runner struct recreating the containers
+    pub fn with_force_recreate(self, force_recreate: bool) -> Runner {
+        Runner { force_recreate, ..self }
+    }
 
-            if Path::new(&venue).join(&dependency.name).is_dir() == true {
-                std::fs::remove_dir_all(Path::new(&venue).join(&dependency.name)).unwrap();
-            };
-            // download and checkout the dependency
-            match dependency.clone_github_repo(&full_venue_path, &command_runner) {
-                Ok(_) => {
-                    println!("Cloned repo for {}/{}", &full_venue_path, dependency.name);
-                },
-                Err(error) => {
-                    println!("Failed to clone repo for {}: {}", dependency.name, error);
-                    continue
-                }
-            }
-            match dependency.checkout_branch(&full_venue_path, &command_runner){
-                Ok(_) => {
-                    println!("Checked out branch for {}/{} as branch {}", &full_venue_path, dependency.name, dependency.branch);
-                },
-                Err(error) => {
-                    println!("Failed to checkout branch for {} as branch {}: {}", dependency.name, dependency.branch, error);
-                    continue
-                }
-            };
-            let wedding_invite = dependency.get_wedding_invite(&full_venue_path).unwrap();
-
-            // configure the build files for the dependency
-            match wedding_invite.build_files {
-                Some(_) => {
-                    let locked_build = match wedding_invite.build_lock {
-                        Some(unpacked_result) => unpacked_result,
-                        None => false
-                    };
-                    if locked_build == false {
-                        let _ = wedding_invite.prepare_build_file(&full_venue_path, &dependency.name, &file_handle);
-                    }
-                },
-                None => continue
-            }
-            // configure the build files for the dependency's init build
-            match &wedding_invite.init_build {
-                Some(unpacked_init_build) => {
-                    let locked_build = match unpacked_init_build.build_lock {
-                        Some(unpacked_result) => unpacked_result,
-                        None => false
-                    };
-                    if locked_build == false {
-                        match wedding_invite.prepare_init_build_file(&full_venue_path, &dependency.name, &file_handle) {
-                            Ok(_) => {
-                                println!("Prepared init build file for {}", dependency.name);
-                            },
-                            Err(error) => {
-                                println!("Failed to prepare init build file for {}: {}", dependency.name, error);
-                                continue
-                            }
-                        };
-                    }
-                },
-                None => continue
-            }
-        }
+    /// Limits the attendees the runner builds, runs, and installs to the ones the filter includes.
+    /// 
+    /// # Arguments
+    /// * `filter` - The attendees to apply the commands to
+    /// 
+    /// # Returns
+    /// * `Result<Runner, String>` - The Runner struct applying the filter or an error if a name in the filter is not an attendee
+    pub fn with_filter(self, filter: AttendeeFilter) -> Result<Runner, String> {
+        filter.validate(&self.seating_plan)?;
+        Ok(Runner { filter, ..self })
     }
 
-    /// Tears down the dependencies that are running.
-    pub fn teardown_dependencies(&self) {
-        let command_runner = CommandRunner {};
-        let mut command_string = self.get_compose_file_command(false);
-        command_runner.run_docker_command(" down", "failed to tear down", &mut command_string);
+    /// Gets the attendees of the seating plan the filter includes.
+    /// 
+    /// # Returns
+    /// * `Vec<&Dependency>` - The included attendees in the order of the seating plan
+    pub fn selected_attendees(&self) -> Vec<&Dependency> {
+        self.seating_plan.attendees.iter()
+                                   .filter(|dependency| self.filter.includes(&dependency.name))
+                                   .collect()
     }
 
-    /// Tears down the remote dependencies that are running.
-    pub fn teardown_remote_dependencies(&self) {
-        let command_runner = CommandRunner {};
-        let mut command_string = self.get_compose_file_command(true);
-        command_runner.run_docker_command(" down", "failed to tear down", &mut command_string);
+    /// Resolves the flags for a command by merging the command line flags over the command defaults of
+    /// the seating plan.
+    /// 
+    /// # Arguments
+    /// * `command` - The command to resolve the flags for
+    /// * `cli_flags` - The flags explicitly passed in through the command line
+    /// 
+    /// # Returns
+    /// * `ResolvedFlags` - The effective flags for the command
+    pub fn resolve_flags(&self, command: &str, cli_flags: &HashMap<String, bool>) -> ResolvedFlags {
+        resolve_flags(command, cli_flags, &self.seating_plan.command_defaults)
     }
 
-    /// Builds the dependencies that are needed to run. 
-    pub fn build_dependencies(&self) {
-        let command_runner = CommandRunner {};
-        let mut command_string = self.get_compose_file_command(false);
-        command_runner.run_docker_command(" build", "failed to build", &mut command_string);
+    /// Gets the command docker compose is run with the first time it is needed, and reused after that. It is 
+    /// resolved from ```--compose-cmd```, then ```WEDP_COMPOSE```, then ```--engine```, then the ```compose_command``` and the ```engine```
+    /// of the seating plan, and otherwise by probing for ```docker compose```.
+    /// 
+    /// # Returns
+    /// * `&str` - The compose command such as ```docker compose```, ```docker-compose```, or ```podman compose```
+    pub fn compose_command(&self) -> &str {
+        self.compose.get_or_init(|| {
+            let command = env::var(COMPOSE_ENV).ok().filter(|command| !command.is_empty()).or(match self.engine {
+                Some(_) => None,
+                None => self.seating_plan.compose_command.clone()
+            });
+            resolve_compose_command(command, self.engine.or(self.seating_plan.engine), self.probe_runner.as_ref())
+                .unwrap_or_else(|_| COMPOSE_V1.to_string())
+        })
     }
 
-    /// Runs the dependencies defined.
-    pub fn run_dependencies(&self) {
-        let command_runner = CommandRunner {};
-        let mut command_string = self.get_compose_file_command(false);
-        command_runner.run_docker_command(" up", "failed to run", &mut command_string);
+    /// Checks git is installed, so the optional checks that need git such as the checked out branches can be
+    /// skipped when wedp only runs a venue that is already installed. The probe runs once and a missing git is
+    /// only reported the first time.
+    /// 
+    /// # Returns
+    /// * `bool` - Whether git could be run
+    pub fn git_available(&self) -> bool {
+        *self.git_available.get_or_init(|| {
+            let available = GitVersionCommand.run(self.probe_runner.as_ref()).is_ok();
+            if !available {
+                logging::warn("git is not installed, skipping the checks that need git");
+            }
+            available
+        })
     }
 
-    /// Runs the dependencies defined in the background.
-    pub fn run_dependencies_background(&self) {
-        let command_runner = CommandRunner {};
-        let mut command_string = self.get_compose_file_command(false);
-        command_runner.run_docker_command(" up -d", "failed to run", &mut command_string);
+    /// Creates the venue directory.
+    /// 
+    /// # Returns
+    /// * `Result<(), WedpError>` - An ```Io``` error with the venue if the directory could not be created
+    pub fn create_venue(&self) -> Result<(), WedpError> {
+        self.seating_plan.create_venue(self.file_handle.as_ref())
+            .map_err(|source| WedpError::Io { path: self.seating_plan.venue.clone(), source })?;
+        logging::info("Created venue directory");
+        Ok(())
     }
 
-    /// Runs the remote dependencies defined.
-    pub fn run_remote_dependencies(&self) {
-        let command_runner = CommandRunner {};
-        let mut command_string = self.get_compose_file_command(true);
-        command_runner.run_docker_command(" up", "failed to run", &mut command_string);
+    /// Checks the venue and the selected attendees are cloned before docker is run with their compose files. 
+    /// The paths are checked through the file handle, so a dry run always passes.
+    /// 
+    /// # Returns
+    /// * `PreflightReport` - Whether the venue exists and which selected attendees are not cloned into it
+    pub fn preflight(&self) -> PreflightReport {
+        let venue_exists = self.file_handle.exists(&self.seating_plan.venue_path());
+        let selected: Vec<String> = self.selected_attendees().iter().map(|dependency| dependency.name.clone()).collect();
+        let missing = selected.iter()
+            .filter(|name| !venue_exists || !self.file_handle.exists(&self.seating_plan.dependency_path(name)))
+            .cloned()
+            .collect();
+        PreflightReport { venue: self.seating_plan.venue.clone(), venue_exists, selected, missing }
     }
 
-    /// Runs the remote dependencies defined in the background.
-    pub fn run_remote_dependencies_background(&self) {
-        let command_runner = CommandRunner {};
-        let mut command_string = self.get_compose_file_command(true);
-        command_runner.run_docker_command(" up -d", "failed to run", &mut command_string);
+    /// Gets the docker-compose files for the dependencies in the seating plan.
+    /// 
+    /// # Arguments
+    /// * `remote` - If true the remote docker-compose files meaning the docker-compose files that rely on images from Dockerhub
+    /// 
+    /// # Returns
+    /// * `Result<Vec<ComposeFileRef>, String>` - The docker-compose files with the dependency each file came from,
+    ///   or an error if the venue or every attendee is missing, or naming every dependency whose wedding invite
+    ///   is missing or could not be loaded
+    pub fn get_compose_files(&self, remote: bool) -> Result<Vec<ComposeFileRef>, String> {
+        self.preflight().check()?;
+        let venue = &self.seating_plan.venue;
+        let mut compose_files = Vec::new();
+        let mut missing = Vec::new();
+        let mut errors = Vec::new();
+
+        for dependency in self.selected_attendees() {
+            let wedding_invite = match dependency.get_wedding_invite(venue) {
+                Ok(wedding_invite) => wedding_invite,
+                Err(WedpError::ConfigNotFound(_)) => {
+                    missing.push(dependency.name.as_str());
+                    continue
+                },
+                Err(error) => {
+                    errors.push(format!("{}: {}", dependency.name, error));
+                    continue
+                }
+            };
+
+            let files = match remote {
+                true => match wedding_invite.get_remote_compose_files(venue, &dependency.name) {
+                    Ok(files) => files,
+                    Err(_) => {
+                        logging::warn(&format!("{} has no remote runner files, skipping", dependency.name));
+                        continue
+                    }
+                },
+                false => wedding_invite.get_docker_compose_files(venue, &dependency.name)
+            };
+            compose_files.extend(files);
+        }
+        if !missing.is_empty() {
+            errors.insert(0, format!("run `wedp install` first; missing invites for: {}", missing.join(", ")));
+        }
+        match errors.is_empty() {
+            true => Ok(compose_files),
+            false => Err(errors.join("\n"))
+        }
     }
 
+    /// Gets the docker-compose command for the dependencies in the seating plan.
+    /// 
+    /// # Arguments
+    /// * `remote` - If true the remote docker-compose files meaning the docker-compose files that rely on images from Dockerhub
+    /// 
+    /// # Returns
+    /// * `Result<ProgramArgs, String>` - The docker-compose command or an error listing the runner files that do not exist
+    /// 
+    /// # Example
+    /// ```
+    /// docker-compose -f venue/dependency1/docker-compose.yml -f venue/dependency2/docker-compose.yml
+    /// ```
+    pub fn get_compose_file_command(&self, remote: bool) -> Result<ProgramArgs, String> {
+        let compose_files = self.existing_compose_files(self.get_compose_files(remote)?)?;
+        let compose_files = match remote {
+            true => self.with_image_arch_override(compose_files)?,
+            false => compose_files
+        };
+        Ok(compose_args(self.compose_command(), &compose_files))
+    }
+
+    /// Checks the compose files exist before docker is run with them. Missing files fail the command unless
+    /// ```skip_missing``` is set, in which case they are left out with a warning.
+    /// 
+    /// # Arguments
+    /// * `compose_files` - The compose files docker is about to be run with
+    /// 
+    /// # Returns
+    /// * `Result<Vec<ComposeFileRef>, String>` - The files that exist or an error listing every missing file
+    pub fn existing_compose_files(&self, compose_files: Vec<ComposeFileRef>) -> Result<Vec<ComposeFileRef>, String> {
+        let missing = missing_files(&compose_files, self.file_handle.as_ref());
+        if missing.is_empty() {
+            return Ok(compose_files)
+        }
+        if !self.skip_missing {
+            return Err(format!("{}\nFix the wedding invites or pass --skip-missing to leave them out", render_missing_files(&missing)))
+        }
+        logging::warn(&render_missing_files(&missing));
+        Ok(compose_files.into_iter().filter(|file| !missing.contains(file)).collect())
+    }
+
+    /// Gets the docker-compose command for running the dependencies, including the override file setting the
+    /// ```restart_policy``` of the seating plan on every service if there is one.
+    /// 
+    /// # Arguments
+    /// * `remote` - If true the remote docker-compose files meaning the docker-compose files that rely on images from Dockerhub
+    /// * `cwd` - The directory wedp was started in
+    /// 
+    /// # Returns
+    /// * `Result<ProgramArgs, String>` - The docker-compose command or an error if a runner file does not exist, the 
+    ///   override file could not be generated, or the compose project is already running from another location
+    pub fn get_run_command(&self, remote: bool, cwd: &Path) -> Result<ProgramArgs, String> {
+        self.get_run_files(remote, cwd).map(|files| compose_args(self.compose_command(), &files))
+    }
+
+    /// Gets the compose files for running the dependencies, checking the compose project is not already running
+    /// from another location unless ```--force-recreate``` was passed.
+    /// 
+    /// # Arguments
+    /// * `remote` - If true the remote docker-compose files
+    /// * `cwd` - The directory wedp was started in
+    /// 
+    /// # Returns
+    /// * `Result<Vec<ComposeFileRef>, String>` - The compose files including the restart override or an error if
+    ///   a runner file does not exist, the override file could not be generated, or the compose project is
+    ///   already running from another location
+    fn get_run_files(&self, remote: bool, cwd: &Path) -> Result<Vec<ComposeFileRef>, String> {
+        let compose_files = self.existing_compose_files(self.get_compose_files(remote)?)?;
+        let compose_files = match remote {
+            true => self.with_image_arch_override(compose_files)?,
+            false => compose_files
+        };
+        let compose_files = self.with_restart_override(compose_files)?;
+        if !self.force_recreate {
+            check_project_location(
+                self.compose_command(), &compose_files, cwd, self.probe_runner.as_ref(), self.file_handle.as_ref()
+            )?;
+        }
+        Ok(compose_files)
+    }
+
+    /// Renders an ```up``` action, recreating the containers and removing the orphans if ```--force-recreate```
+    /// was passed.
+    /// 
+    /// # Arguments
+    /// * `action` - The action starting the dependencies
+    /// 
+    /// # Returns
+    /// * `String` - The action to append to the docker-compose command
+    fn up_action(&self, action: ComposeAction) -> String {
+        match self.force_recreate {
+            true => format!("{}{}", action, FORCE_RECREATE),
+            false => action.to_string()
+        }
+    }
+
+    /// Gets the compose files the dependencies were last started in the background with, falling back to the
+    /// compose files of the seating plan if nothing was started with the same kind of runner files.
+    /// 
+    /// # Arguments
+    /// * `remote` - Whether the remote runner files were used to run the dependencies
+    /// 
+    /// # Returns
+    /// * `Result<Vec<ComposeFileRef>, String>` - The compose files to pass to docker-compose or an error if the
+    ///   compose files of the seating plan could not be read
+    pub fn get_started_compose_files(&self, remote: bool) -> Result<Vec<ComposeFileRef>, String> {
+        let started = VenueState::new(&self.seating_plan.venue_path(), self.file_handle.as_ref())
+            .load::<RunState>(RUN_STATE_FILE)
+            .ok()
+            .and_then(|state| state.compose_files_for(remote));
+        match started {
+            Some(compose_files) => Ok(compose_files),
+            None => self.get_compose_files(remote)
+        }
+    }
+
+    /// Generates the override file picking the per-arch image tags of the attendees whose wedding invite has
+    /// ```remote_image_arch_suffixes``` for the CPU type of ```--arch``` or the host. Attendees without the map
+    /// are left as they are.
+    /// 
+    /// # Arguments
+    /// * `compose_files` - The remote compose files docker is about to be run with
+    /// 
+    /// # Returns
+    /// * `Result<Vec<ComposeFileRef>, String>` - The compose files including the override or an error if a suffix
+    ///   could not be picked or the override file could not be generated
+    pub fn with_image_arch_override(&self, compose_files: Vec<ComposeFileRef>) -> Result<Vec<ComposeFileRef>, String> {
+        let mut suffixes = HashMap::new();
+        for dependency in self.selected_attendees() {
+            let wedding_invite = match dependency.get_wedding_invite(&self.seating_plan.venue) {
+                Ok(wedding_invite) => wedding_invite,
+                Err(_) => continue
+            };
+            let suffix = wedding_invite.remote_image_suffix(self.arch.as_deref())
+                .map_err(|error| format!("{}: {}", dependency.name, error))?;
+            if let Some(suffix) = suffix {
+                suffixes.insert(dependency.name.clone(), suffix.to_string());
+            }
+        }
+        with_image_arch_override(compose_files, &suffixes, &self.seating_plan.venue_path(), self.file_handle.as_ref())
+    }
+
+    /// Adds the override file setting the ```restart_policy``` of the seating plan to the compose files.
+    /// 
+    /// # Arguments
+    /// * `compose_files` - The compose files the services are read from
+    /// 
+    /// # Returns
+    /// * `Result<Vec<ComposeFileRef>, String>` - The compose files including the override if there is a restart policy
+    pub fn with_restart_override(&self, compose_files: Vec<ComposeFileRef>) -> Result<Vec<ComposeFileRef>, String> {
+        let policy = self.seating_plan.restart_policy.as_deref();
+        with_restart_override(compose_files, policy, &self.seating_plan.venue_path(), self.file_handle.as_ref())
+    }
+
+    /// Installs the dependencies in the seating plan the filter includes. A dependency that fails a step is skipped from 
+    /// that step onwards, the remaining dependencies are still installed. Up to ```jobs``` dependencies are 
+    /// installed at the same time, each one on its own thread. Dependencies that are already cloned are fetched 
+    /// and fast forwarded so local changes in the venue are kept, unless ```force``` is set.
+    /// 
+    /// # Arguments
+    /// * `jobs` - The number of dependencies to install at the same time
+    /// * `force` - Removes dependencies that are already cloned and clones them again
+    /// * `cwd` - The directory wedp was started in, the venue and the ```path``` of dependencies are relative to it
+    /// 
+    /// # Returns
+    /// * `Vec<InstallReport>` - The steps that ran for each dependency in the order of the seating plan
+    pub fn install_dependencies(&self, jobs: usize, force: bool, cwd: &Path) -> Vec<InstallReport> {
+        self.install_attendees(&self.selected_attendees(), jobs, force, None, cwd)
+    }
+
+    /// Installs the dependencies in the seating plan the filter includes from an archive made by ```vendor``` instead of 
+    /// cloning them, nothing is fetched over the network. Dependencies that are already installed are only 
+    /// replaced if ```force``` is set.
+    /// 
+    /// # Arguments
+    /// * `archive` - The path to the archive
+    /// * `jobs` - The number of dependencies to install at the same time
+    /// * `force` - Removes dependencies that are already installed and extracts them again
+    /// * `cwd` - The directory wedp was started in, the archive, the venue, and the ```path``` of dependencies are
+    ///   relative to it
+    /// 
+    /// # Returns
+    /// * `Vec<InstallReport>` - The steps that ran for each dependency in the order of the seating plan
+    pub fn install_from_archive(&self, archive: &str, jobs: usize, force: bool, cwd: &Path) -> Vec<InstallReport> {
+        let archive = join_normalised(cwd, &[archive]).to_string_lossy().to_string();
+        self.install_attendees(&self.selected_attendees(), jobs, force, Some(&archive), cwd)
+    }
+
+    /// Installs the dependencies the filter includes at the revisions in the seating plan and packs their 
+    /// working trees, without the ```.git``` directories, into an archive that ```install_from_archive``` can 
+    /// install from without network access. A manifest of the commit each dependency was vendored at is 
+    /// written into the venue and packed alongside them.
+    /// 
+    /// # Arguments
+    /// * `archive` - The path the archive is written to
+    /// * `jobs` - The number of dependencies to install at the same time
+    /// * `cwd` - The directory wedp was started in, the archive, the venue, and the ```path``` of dependencies are
+    ///   relative to it
+    /// 
+    /// # Returns
+    /// * `Result<(), String>` - An error if a dependency could not be installed or the archive could not be written
+    pub fn vendor(&self, archive: &str, jobs: usize, cwd: &Path) -> Result<(), String> {
+        check_install_reports(&self.install_dependencies(jobs, false, cwd))?;
+
+        let attendees = self.selected_attendees();
+        let full_venue_path = self.seating_plan.full_venue_path(cwd);
+        let manifest = build_manifest(&attendees, &full_venue_path, self.command_runner.as_ref())?;
+        let manifest_path = join_normalised(Path::new(&full_venue_path), &[MANIFEST_FILE]);
+        self.file_handle.write(&manifest_path, &render_manifest(&manifest))
+                        .map_err(|error| format!("Could not write {}: {}", manifest_path.to_string_lossy(), error))?;
+
+        let archive = join_normalised(cwd, &[archive]).to_string_lossy().to_string();
+        let mut names: Vec<String> = attendees.iter().map(|dependency| dependency.name.clone()).collect();
+        names.push(MANIFEST_FILE.to_string());
+        CreateArchiveCommand::new(archive.clone(), full_venue_path, names)
+            .run(self.command_runner.as_ref())
+            .map_err(|error| format!("Failed to write {}: {}", archive, error))?;
+        logging::info(&format!("Vendored {} dependencies into {}", attendees.len(), archive));
+        Ok(())
+    }
+
+    /// Fetches, checks out, and fast forwards the named dependencies, cloning any that are not installed yet.
+    /// 
+    /// # Arguments
+    /// * `names` - The names of the dependencies to update
+    /// * `jobs` - The number of dependencies to update at the same time
+    /// * `cwd` - The directory wedp was started in, the venue and the ```path``` of dependencies are relative to it
+    /// 
+    /// # Returns
+    /// * `Vec<InstallReport>` - The steps that ran for each dependency in the order of the seating plan
+    pub fn update_dependencies(&self, names: &[String], jobs: usize, cwd: &Path) -> Vec<InstallReport> {
+        let attendees: Vec<&Dependency> = self.seating_plan.attendees.iter()
+                                              .filter(|dependency| names.contains(&dependency.name))
+                                              .collect();
+        self.install_attendees(&attendees, jobs, false, None, cwd)
+    }
+
+    /// Fetches, checks out, and fast forwards the dependencies the filter includes that are already cloned into 
+    /// the venue. Nothing is cloned or removed, a dependency that is not cloned yet fails with a message to run
+    /// ```wedp install``` first. A dependency with local changes is skipped with a warning unless ```force``` is
+    /// set, which stashes the changes first. The commit checked out before and after is recorded on each report.
+    /// 
+    /// # Arguments
+    /// * `jobs` - The number of dependencies to update at the same time
+    /// * `force` - Stashes the local changes of a dependency instead of skipping it
+    /// * `cwd` - The directory wedp was started in, the venue and the ```path``` of dependencies are relative to it
+    /// 
+    /// # Returns
+    /// * `Vec<InstallReport>` - The steps that ran for each dependency in the order of the seating plan
+    pub fn update_installed(&self, jobs: usize, force: bool, cwd: &Path) -> Vec<InstallReport> {
+        let full_venue_path = self.seating_plan.full_venue_path(cwd);
+        let (installed, missing): (Vec<&Dependency>, Vec<&Dependency>) = self.selected_attendees()
+            .into_iter()
+            .partition(|dependency| cwd.join(self.seating_plan.dependency_path(&dependency.name)).is_dir());
+        let mut checked = HashMap::new();
+        let mut ready = Vec::new();
+        let mut reports = Vec::new();
+        for dependency in installed {
+            if dependency.source_path(cwd).is_some() {
+                ready.push(dependency);
+                continue
+            }
+            let report = self.check_working_tree(dependency, &full_venue_path, force);
+            match report.skipped || !report.errors.is_empty() {
+                true => reports.push(report),
+                false => {
+                    ready.push(dependency);
+                    checked.insert(dependency.name.clone(), report);
+                }
+            }
+        }
+        for mut report in self.install_attendees(&ready, jobs, false, None, cwd) {
+            if let Some(before) = checked.remove(&report.name) {
+                report.stashed = before.stashed;
+                report.old_commit = before.old_commit;
+                if report.errors.is_empty() {
+                    let dependency = ready.iter().find(|dependency| dependency.name == report.name).unwrap();
+                    report.new_commit = dependency.current_commit(&full_venue_path, self.command_runner.as_ref()).ok()
+                                                  .filter(|commit| !commit.is_empty());
+                }
+            }
+            reports.push(report);
+        }
+        for dependency in missing {
+            let mut report = InstallReport::new(&dependency.name);
+            report.fail(InstallStep::Clone, format!("{} is not cloned into {}, run wedp install first", dependency.name, self.seating_plan.venue));
+            reports.push(report);
+        }
+        let order = |name: &str| self.seating_plan.attendees.iter().position(|dependency| dependency.name == name);
+        reports.sort_by_key(|report| order(&report.name));
+        reports
+    }
+
+    /// Pins the attendees the filter includes to the commits recorded in the lockfile, so installing checks out
+    /// the exact commits instead of the tips of the branches. Attendees with a ```path``` are not locked.
+    /// 
+    /// # Arguments
+    /// * `lock_path` - The path to the lockfile
+    /// 
+    /// # Returns
+    /// * `Result<Self, String>` - The runner with the attendees pinned or an error if the lockfile could not be 
+    ///   read or an attendee is not in it
+    pub fn with_lockfile(mut self, lock_path: &Path) -> Result<Self, String> {
+        let contents = self.file_handle.read_to_string(lock_path)
+            .map_err(|error| format!("Could not read {}: {}, run wedp lock to create it", lock_path.to_string_lossy(), error))?;
+        let lockfile = Lockfile::parse(&contents, lock_path)?;
+        let selected: Vec<String> = self.selected_attendees().iter().map(|dependency| dependency.name.clone()).collect();
+        let mut problems = Vec::new();
+        for dependency in self.seating_plan.attendees.iter_mut() {
+            if !selected.contains(&dependency.name) || dependency.path.is_some() {
+                continue
+            }
+            let pinned = match lockfile.entry(&dependency.name) {
+                Some(entry) => lockfile::pin(dependency, entry),
+                None => Err(format!("{} is not in {}, run wedp lock to add it", dependency.name, lock_path.to_string_lossy()))
+            };
+            if let Err(error) = pinned {
+                problems.push(error);
+            }
+        }
+        match problems.is_empty() {
+            true => Ok(self),
+            false => Err(problems.join("\n"))
+        }
+    }
+
+    /// Records the commit checked out for each attendee the filter includes in the lockfile, keeping the entries
+    /// of the other attendees. Nothing is installed, the attendees must already be cloned into the venue.
+    /// 
+    /// # Arguments
+    /// * `lock_path` - The path to the lockfile
+    /// * `cwd` - The directory wedp is run from
+    /// 
+    /// # Returns
+    /// * `Result<Lockfile, String>` - The lockfile written or an error if a commit could not be read or the 
+    ///   lockfile could not be written
+    pub fn lock(&self, lock_path: &Path, cwd: &Path) -> Result<Lockfile, String> {
+        let full_venue_path = self.seating_plan.full_venue_path(cwd);
+        let mut locked = Vec::new();
+        for dependency in self.selected_attendees() {
+            if dependency.path.is_some() {
+                continue
+            }
+            if !self.seating_plan.dependency_path(&dependency.name).is_dir() {
+                return Err(format!("{} is not cloned into {}, run wedp install first", dependency.name, self.seating_plan.venue))
+            }
+            let commit = dependency.current_commit(&full_venue_path, self.command_runner.as_ref())
+                .map_err(|error| format!("Failed to read the commit of {}: {}", dependency.name, error))?;
+            locked.push(LockEntry {
+                name: dependency.name.clone(),
+                url: dependency.url.clone(),
+                branch: dependency.branch.clone(),
+                commit
+            });
+        }
+        let existing = match self.file_handle.read_to_string(lock_path) {
+            Ok(contents) => Lockfile::parse(&contents, lock_path)?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Lockfile::default(),
+            Err(error) => return Err(format!("Could not read {}: {}", lock_path.to_string_lossy(), error))
+        };
+        let lockfile = existing.merge(&self.seating_plan.attendees, locked);
+        self.file_handle.write(lock_path, &lockfile.render())
+            .map_err(|error| format!("Could not write {}: {}", lock_path.to_string_lossy(), error))?;
+        Ok(lockfile)
+    }
+
+    /// Checks the working tree of a dependency is clean before it is updated, stashing the local changes if 
+    /// ```force``` is set, and reads the commit checked out before the update.
+    /// 
+    /// # Arguments
+    /// * `dependency` - The dependency about to be updated
+    /// * `full_venue_path` - The full path to the venue
+    /// * `force` - Stashes the local changes instead of skipping the dependency
+    /// 
+    /// # Returns
+    /// * `InstallReport` - The report with ```skipped```, ```stashed```, ```old_commit```, or the errors set
+    fn check_working_tree(&self, dependency: &Dependency, full_venue_path: &str, force: bool) -> InstallReport {
+        let command_runner = self.command_runner.as_ref();
+        let mut report = InstallReport::new(&dependency.name);
+        let changes = match dependency.local_changes(full_venue_path, command_runner) {
+            Ok(changes) => changes,
+            Err(error) => {
+                report.fail(InstallStep::WorkingTree, format!("Failed to read the working tree of {}: {}", dependency.name, error));
+                return report
+            }
+        };
+        if !changes.is_empty() && !force {
+            logging::warn(&format!(
+                "{}: skipped, {} files have local changes, commit them or pass --force to stash them", 
+                dependency.name, changes.len()
+            ));
+            report.skipped = true;
+            return report
+        }
+        if !changes.is_empty() {
+            if let Err(error) = dependency.stash(full_venue_path, command_runner) {
+                report.fail(InstallStep::Stash, format!("Failed to stash the local changes of {}: {}", dependency.name, error));
+                return report
+            }
+            logging::warn(&format!("{}: stashed the local changes of {} files, git stash pop restores them", dependency.name, changes.len()));
+            report.stashed = true;
+        }
+        match dependency.current_commit(full_venue_path, command_runner) {
+            Ok(commit) => report.old_commit = Some(commit).filter(|commit| !commit.is_empty()),
+            Err(error) => report.fail(InstallStep::WorkingTree, format!("Failed to read the commit of {}: {}", dependency.name, error))
+        }
+        report
+    }
+
+    /// Installs the dependencies passed in, up to ```jobs``` at the same time.
+    /// 
+    /// # Arguments
+    /// * `attendees` - The dependencies to install
+    /// * `jobs` - The number of dependencies to install at the same time
+    /// * `force` - Removes dependencies that are already cloned and clones them again
+    /// * `archive` - The archive to extract the dependencies from, ```None``` clones them, dependencies with a 
+    ///   ```path``` are always linked or copied
+    /// * `cwd` - The directory wedp was started in, the venue and the ```path``` of dependencies are relative to it
+    /// 
+    /// # Returns
+    /// * `Vec<InstallReport>` - The steps that ran for each dependency in the order passed in
+    fn install_attendees(&self, attendees: &[&Dependency], jobs: usize, force: bool, archive: Option<&str>, cwd: &Path) -> Vec<InstallReport> {
+        let full_venue_path = self.seating_plan.full_venue_path(cwd);
+        let next_index = AtomicUsize::new(0);
+        let reports: Mutex<Vec<Option<InstallReport>>> = Mutex::new(attendees.iter().map(|_| None).collect());
+
+        thread::scope(|scope| {
+            for _ in 0..jobs.clamp(1, attendees.len().max(1)) {
+                scope.spawn(|| loop {
+                    let index = next_index.fetch_add(1, Ordering::SeqCst);
+                    let dependency = match attendees.get(index) {
+                        Some(dependency) => dependency,
+                        None => break
+                    };
+                    let report = match (archive, dependency.source_path(cwd)) {
+                        (_, Some(source)) => self.install_path_dependency(dependency, &full_venue_path, &source, force, self.file_handle.as_ref()),
+                        (Some(archive), None) => self.extract_dependency(dependency, &full_venue_path, archive, force, self.file_handle.as_ref()),
+                        (None, None) => self.install_dependency(dependency, &full_venue_path, force, self.file_handle.as_ref())
+                    };
+                    reports.lock().unwrap()[index] = Some(report);
+                });
+            }
+        });
+        reports.into_inner().unwrap().into_iter().flatten().collect()
+    }
+
+    /// Moves the attendees on the ```from``` branch to the ```to``` branch, rewriting the seating plan file 
+    /// through the file handle and updating the loaded seating plan to match.
+    /// 
+    /// # Arguments
+    /// * `file_path` - The path to the seating plan file the runner was loaded from
+    /// * `from` - The branch the attendees are moved from
+    /// * `to` - The branch the attendees are moved to
+    /// * `only` - The names of the attendees to move, empty for every attendee on the ```from``` branch
+    /// 
+    /// # Returns
+    /// * `Result<Vec<String>, String>` - The names of the attendees that moved or an error message
+    pub fn bump_branch(&mut self, file_path: &str, from: &str, to: &str, only: &[String]) -> Result<Vec<String>, String> {
+        let contents = self.file_handle.read_to_string(Path::new(file_path))
+                           .map_err(|error| format!("Could not open file: {} for {}", error, file_path))?;
+        let bump = bump_branches(&contents, file_path, from, to, only)?;
+        if bump.changed.is_empty() {
+            return Ok(bump.changed)
+        }
+        self.file_handle.write(Path::new(file_path), &bump.contents)
+            .map_err(|error| format!("Could not write {}: {}", file_path, error))?;
+        for dependency in self.seating_plan.attendees.iter_mut() {
+            if bump.changed.contains(&dependency.name) {
+                dependency.branch = Some(to.to_string());
+            }
+        }
+        Ok(bump.changed)
+    }
+
+    /// Clones or fetches, checks out, and prepares the builds of a dependency.
+    /// 
+    /// # Arguments
+    /// * `dependency` - The dependency to install
+    /// * `full_venue_path` - The full path to the venue
+    /// * `force` - Removes the dependency if it is already cloned and clones it again
+    /// * `file_handle` - The file handle used to copy the Dockerfiles
+    /// 
+    /// # Returns
+    /// * `InstallReport` - The steps that ran for the dependency
+    fn install_dependency(&self, dependency: &Dependency, full_venue_path: &String, force: bool, file_handle: &dyn CoreFileHandle) -> InstallReport {
+        let command_runner = self.command_runner.as_ref();
+        let mut report = InstallReport::new(&dependency.name);
+        let dependency_path = self.seating_plan.dependency_path(&dependency.name);
+        let mut existing = dependency_path.is_dir();
+
+        if existing && force {
+            if let Err(error) = file_handle.remove_directory(&dependency_path) {
+                report.fail(InstallStep::Remove, format!("Failed to remove the old clone of {}: {}", dependency.name, error));
+                return report
+            }
+            existing = false;
+        }
+        // download and checkout the dependency
+        if existing {
+            if let Err(error) = dependency.fetch(full_venue_path, command_runner) {
+                report.fail(InstallStep::Fetch, format!("Failed to fetch {}: {}", dependency.name, error));
+                return report
+            }
+            logging::info(&format!("{}: fetched into {}/{}", dependency.name, full_venue_path, dependency.name));
+            report.fetched = true;
+        }
+        else {
+            if let Err(error) = dependency.clone_github_repo(full_venue_path, command_runner) {
+                report.fail(InstallStep::Clone, format!("Failed to clone repo for {}: {}", dependency.name, error));
+                return report
+            }
+            logging::info(&format!("{}: cloned into {}/{}", dependency.name, full_venue_path, dependency.name));
+            report.cloned = true;
+        }
+
+        let git_ref = match dependency.git_ref() {
+            Ok(git_ref) => git_ref,
+            Err(error) => {
+                report.fail(InstallStep::Checkout, error);
+                return report
+            }
+        };
+        let checked_out = match report.cloned {
+            true => dependency.checkout_cloned(full_venue_path, command_runner),
+            false => dependency.checkout(full_venue_path, command_runner).map(|_| ())
+        };
+        if let Err(error) = checked_out {
+            report.fail(InstallStep::Checkout, format!("Failed to checkout {} as {}: {}", dependency.name, git_ref, error));
+            return report
+        }
+        logging::info(&format!("{}: checked out {}", dependency.name, git_ref));
+        report.checked_out = true;
+
+        if existing {
+            match dependency.pull(full_venue_path, command_runner) {
+                Ok(pulled) => report.pulled = pulled,
+                Err(error) => {
+                    report.fail(InstallStep::Pull, format!("Failed to pull {} for {}: {}", git_ref, dependency.name, error));
+                    return report
+                }
+            }
+            if report.pulled {
+                logging::info(&format!("{}: pulled {}", dependency.name, git_ref));
+            }
+        }
+
+        if let Err(error) = dependency.update_submodules(full_venue_path, command_runner) {
+            report.fail(InstallStep::Submodules, format!("Failed to update submodules for {}: {}", dependency.name, error));
+            return report
+        }
+        report.submodules_updated = dependency.submodules;
+
+        self.prepare_dependency_builds(dependency, full_venue_path, file_handle, report)
+    }
+
+    /// Extracts a dependency from a vendored archive and prepares its builds.
+    /// 
+    /// # Arguments
+    /// * `dependency` - The dependency to install
+    /// * `full_venue_path` - The full path to the venue
+    /// * `archive` - The full path to the archive
+    /// * `force` - Removes the dependency if it is already installed and extracts it again
+    /// * `file_handle` - The file handle used to copy the Dockerfiles
+    /// 
+    /// # Returns
+    /// * `InstallReport` - The steps that ran for the dependency
+    fn extract_dependency(&self, dependency: &Dependency, full_venue_path: &String, archive: &str, force: bool, file_handle: &dyn CoreFileHandle) -> InstallReport {
+        let mut report = InstallReport::new(&dependency.name);
+        let dependency_path = self.seating_plan.dependency_path(&dependency.name);
+
+        if dependency_path.is_dir() {
+            if !force {
+                report.fail(InstallStep::Extract, format!("{} is already installed, pass --force to replace it with the archive", dependency.name));
+                return report
+            }
+            if let Err(error) = file_handle.remove_directory(&dependency_path) {
+                report.fail(InstallStep::Remove, format!("Failed to remove the old install of {}: {}", dependency.name, error));
+                return report
+            }
+        }
+        if let Err(error) = dependency.extract(full_venue_path, archive, self.command_runner.as_ref()) {
+            report.fail(InstallStep::Extract, format!("Failed to extract {} from {}: {}", dependency.name, archive, error));
+            return report
+        }
+        logging::info(&format!("{}: extracted from {} into {}/{}", dependency.name, archive, full_venue_path, dependency.name));
+        report.extracted = true;
+
+        self.prepare_dependency_builds(dependency, full_venue_path, file_handle, report)
+    }
+
+    /// Symlinks or copies the ```path``` of a dependency into the venue and prepares its builds, nothing is cloned 
+    /// or checked out. A link that already points at the ```path``` is kept and any other link is replaced. An 
+    /// existing copy is only replaced with ```force```, as is a directory in the way of a link.
+    /// 
+    /// # Arguments
+    /// * `dependency` - The dependency to install
+    /// * `full_venue_path` - The full path to the venue
+    /// * `source` - The full path to the directory of the dependency
+    /// * `force` - Removes whatever is in the venue for the dependency and links or copies it again
+    /// * `file_handle` - The file handle used to link or copy the directory and the Dockerfiles
+    /// 
+    /// # Returns
+    /// * `InstallReport` - The steps that ran for the dependency
+    fn install_path_dependency(&self, dependency: &Dependency, full_venue_path: &String, source: &Path, force: bool, file_handle: &dyn CoreFileHandle) -> InstallReport {
+        let mut report = InstallReport::new(&dependency.name);
+        let dependency_path = Path::new(full_venue_path).join(&dependency.name);
+        if !source.is_dir() {
+            report.fail(InstallStep::Path, format!("path {} of {} is not a directory", source.to_string_lossy(), dependency.name));
+            return report
+        }
+        // a link is cheap to replace, a directory may be a clone with local changes so it is only removed with force
+        let linked_to = file_handle.read_link(&dependency_path).ok();
+        let existing = file_handle.exists_no_follow(&dependency_path);
+        let up_to_date = dependency.link && linked_to.as_deref() == Some(source);
+        let replace = existing && (force || (linked_to.is_some() && !up_to_date));
+        if replace {
+            if let Err(error) = file_handle.remove_directory(&dependency_path) {
+                report.fail(InstallStep::Remove, format!("Failed to remove the old install of {}: {}", dependency.name, error));
+                return report
+            }
+        }
+        let kept = existing && !replace;
+        if kept && dependency.link && !up_to_date {
+            report.fail(InstallStep::Path, format!(
+                "{} is already in {} and is not a link, pass --force to replace it with a link to {}",
+                dependency.name, full_venue_path, source.to_string_lossy()
+            ));
+            return report
+        }
+        let installed = match (kept, dependency.link) {
+            (true, _) => Ok(()),
+            (false, true) => file_handle.symlink(source, &dependency_path),
+            (false, false) => file_handle.copy_directory(source, &dependency_path)
+        };
+        if let Err(error) = installed {
+            report.fail(InstallStep::Path, format!("Failed to put {} into {}: {}", source.to_string_lossy(), full_venue_path, error));
+            return report
+        }
+        let source_name = source.to_string_lossy();
+        match (kept, dependency.link) {
+            (true, true) => logging::info(&format!("{}: already linked to {}", dependency.name, source_name)),
+            (true, false) => logging::info(&format!("{}: keeping the copy in {}, pass --force to copy it again", dependency.name, full_venue_path)),
+            (false, true) => logging::info(&format!("{}: linked {} into {}", dependency.name, source_name, full_venue_path)),
+            (false, false) => logging::info(&format!("{}: copied {} into {}", dependency.name, source_name, full_venue_path))
+        }
+        report.linked = dependency.link;
+        report.copied = !dependency.link && !kept;
+
+        self.prepare_dependency_builds(dependency, full_venue_path, file_handle, report)
+    }
+
+    /// Reads the wedding invite of an installed dependency and copies its Dockerfiles into the build roots.
+    /// 
+    /// # Arguments
+    /// * `dependency` - The dependency to prepare the builds of
+    /// * `full_venue_path` - The full path to the venue
+    /// * `file_handle` - The file handle used to copy the Dockerfiles
+    /// * `report` - The install report of the dependency
+    /// 
+    /// # Returns
+    /// * `InstallReport` - The install report including the prepared builds
+    fn prepare_dependency_builds(&self, dependency: &Dependency, full_venue_path: &String, file_handle: &dyn CoreFileHandle, mut report: InstallReport) -> InstallReport {
+        let wedding_invite = match dependency.get_wedding_invite(full_venue_path) {
+            Ok(wedding_invite) => wedding_invite,
+            Err(error) => {
+                report.fail(InstallStep::WeddingInvite, format!("Failed to read wedding invite for {}: {}", dependency.name, error));
+                return report
+            }
+        };
+        prepare_builds(&wedding_invite, full_venue_path, self.arch.as_deref(), file_handle, &mut report);
+        report
+    }
+
+    /// Gets the Dockerfile copies that preparing the builds of a dependency would make.
+    /// 
+    /// # Arguments
+    /// * `venue_path` - The full path to the venue
+    /// * `dependency` - The dependency to get the copies for
+    /// 
+    /// # Returns
+    /// * `Result<Vec<BuildFileCopy>, String>` - The build and init build copies or an error if the invite could not be read
+    fn build_file_copies(&self, venue_path: &String, dependency: &Dependency) -> Result<Vec<BuildFileCopy>, String> {
+        let wedding_invite = dependency.get_wedding_invite(venue_path)?;
+        let build_copy = wedding_invite.build_file_copy(venue_path, &dependency.name, self.arch.as_deref()).map_err(|e| e.to_string())?;
+        let init_build_copy = wedding_invite.init_build_file_copy(venue_path, &dependency.name, self.arch.as_deref()).map_err(|e| e.to_string())?;
+        Ok(build_copy.into_iter().chain(init_build_copy).collect())
+    }
+
+    /// Copies the Dockerfile for the CPU into the build roots of every installed dependency the filter includes. A
+    /// dependency that fails does not stop the others from being prepared.
+    /// 
+    /// # Arguments
+    /// * `handle` - The file handle used to copy the Dockerfiles
+    /// * `cwd` - The directory wedp was started in, the venue is relative to it
+    /// 
+    /// # Returns
+    /// * `Result<(), String>` - An error listing every invite that could not be read and every copy that failed
+    pub fn prepare_build_files(&self, handle: &dyn CoreFileHandle, cwd: &Path) -> Result<(), String> {
+        let full_venue_path = self.seating_plan.full_venue_path(cwd);
+        let mut errors = Vec::new();
+
+        for dependency in self.selected_attendees() {
+            let copies = match self.build_file_copies(&full_venue_path, dependency) {
+                Ok(copies) => copies,
+                Err(error) => {
+                    errors.push(format!("Failed to prepare build files for {}: {}", dependency.name, error));
+                    continue
+                }
+            };
+            for copy in copies {
+                match handle.copy(&copy.from, &copy.to) {
+                    Ok(_) => logging::info(&format!("Prepared {} for {}", copy.to.to_string_lossy(), dependency.name)),
+                    Err(error) => errors.push(format!("Failed to prepare {} for {}: {}", copy.to.to_string_lossy(), dependency.name, error))
+                }
+            }
+        }
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors.join("\n"))
+        }
+    }
+
+    /// Compares the Dockerfile for the CPU against the Dockerfile in the build root of every dependency the
+    /// filter includes without writing anything.
+    /// 
+    /// # Arguments
+    /// * `handle` - The file handle used to read the Dockerfiles
+    /// * `cwd` - The directory wedp was started in, the venue is relative to it
+    /// 
+    /// # Returns
+    /// * `(String, Vec<String>)` - The changes of each dependency in the order of the seating plan, and the
+    ///   dependencies whose invite or Dockerfile could not be read
+    pub fn diff_build_files(&self, handle: &dyn CoreFileHandle, cwd: &Path) -> (String, Vec<String>) {
+        let full_venue_path = self.seating_plan.full_venue_path(cwd);
+        let mut output = String::new();
+        let mut errors = Vec::new();
+
+        for dependency in self.selected_attendees() {
+            let copies = match self.build_file_copies(&full_venue_path, dependency) {
+                Ok(copies) => copies,
+                Err(error) => {
+                    errors.push(format!("{}: {}", dependency.name, error));
+                    continue
+                }
+            };
+            for copy in copies {
+                match diff_build_file(&copy, handle) {
+                    Ok(change) => output.push_str(&render_change(&dependency.name, &copy, &change)),
+                    Err(error) => errors.push(format!("{}: could not read {}: {}", dependency.name, copy.from.to_string_lossy(), error))
+                }
+            }
+        }
+        (output, errors)
+    }
+
+    /// Finds the problems reported by ```wedp doctor```, the problems ```validate``` reports followed by the
+    /// housekeeping of the venue and the working directory that ```--fix``` can fix.
+    /// 
+    /// # Arguments
+    /// * `working_directory` - The directory wedp is run from, its wedding invite is checked for Dockerfiles
+    ///   left by ```dress build``` if it has one
+    /// 
+    /// # Returns
+    /// * `Vec<Finding>` - Every problem found
+    pub fn doctor(&self, working_directory: &str) -> Vec<Finding> {
+        let handle = self.file_handle.as_ref();
+        let venue = self.seating_plan.venue_path();
+        let mut findings: Vec<Finding> = self.validate().into_iter().map(Finding::manual).collect();
+        findings.extend(check_venue(&venue, handle));
+        findings.extend(check_venue_gitignore(&venue, handle));
+        match VenueState::new(&venue, handle).load::<RunState>(RUN_STATE_FILE) {
+            Ok(run_state) => findings.extend(check_overrides(&venue, &run_state, handle)),
+            Err(error) => findings.push(Finding::manual(error))
+        }
+        let invite_path = join_normalised(Path::new(working_directory), &["wedding_invite.yml"]);
+        if handle.modified(&invite_path).is_ok() {
+            match WeddingInvite::from_handle(invite_path.to_string_lossy().to_string(), handle) {
+                Ok(invite) => findings.extend(check_local_dockerfiles(&invite, working_directory, self.arch.as_deref(), handle)),
+                Err(error) => findings.push(Finding::manual(error.to_string()))
+            }
+        }
+        findings
+    }
+
+    /// Checks the wedding invite of every installed dependency without running any git or docker commands.
+    /// Every unknown key of a wedding invite is reported and the rest of it is still checked. Dependencies that
+    /// are not installed are skipped.
+    /// 
+    /// # Returns
+    /// * `Vec<String>` - A message for every problem found, each prefixed with the name of the dependency
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut checked = Vec::new();
+
+        for dependency in &self.seating_plan.attendees {
+            let invite_path = self.seating_plan.dependency_path(&dependency.name);
+            if checked.contains(&dependency.name) || !invite_path.is_dir() {
+                continue
+            }
+            checked.push(dependency.name.clone());
+            let invite_file = invite_path.join("wedding_invite.yml").to_string_lossy().to_string();
+            let wedding_invite = match &dependency.invite {
+                Some(invite) => Ok((invite.clone(), Vec::new())),
+                None => WeddingInvite::from_file_lenient(invite_file.clone())
+            };
+            match wedding_invite {
+                Ok((wedding_invite, unknown)) => {
+                    problems.extend(unknown.into_iter().map(|problem| format!("{}: {}: {}", dependency.name, invite_file, problem)));
+                    problems.extend(
+                        wedding_invite.validate(&invite_path).into_iter()
+                            .map(|problem| format!("{}: {}", dependency.name, problem))
+                    );
+                    if let Some(sparse_paths) = &dependency.sparse_paths {
+                        problems.extend(
+                            wedding_invite.sparse_problems(sparse_paths).into_iter()
+                                .map(|problem| format!("{}: {}", dependency.name, problem))
+                        );
+                    }
+                },
+                Err(error) => problems.push(format!("{}: {}", dependency.name, error))
+            }
+        }
+        problems
+    }
+
+    /// Checks the docker-compose files of the attendees the filter includes with ```docker-compose config```.
+    /// 
+    /// # Arguments
+    /// * `remote` - If true the remote docker-compose files are checked
+    /// 
+    /// # Returns
+    /// * `bool` - Whether docker-compose accepted the files
+    pub fn check_compose_config(&self, remote: bool) -> bool {
+        let compose = match self.get_compose_file_command(remote) {
+            Ok(compose) => compose,
+            Err(error) => {
+                logging::error(&error.to_string());
+                return false
+            }
+        };
+        run_docker_checked(
+            self.command_runner.as_ref(), &ComposeAction::ConfigQuiet.to_string(), "compose files are not valid", &compose
+        ).map_err(|error| logging::error(&error.to_string())).is_ok()
+    }
+
+    /// Statically checks the docker-compose files of the attendees the filter includes without a docker
+    /// daemon, see ```compose_inspect``` for what is checked.
+    /// 
+    /// # Arguments
+    /// * `remote` - If true the remote docker-compose files are checked
+    /// 
+    /// # Returns
+    /// * `Vec<String>` - Every problem found in the files
+    pub fn inspect_compose_files(&self, remote: bool) -> Vec<String> {
+        match self.get_compose_files(remote) {
+            Ok(compose_files) => inspect_compose_files(&compose_files, self.file_handle.as_ref()),
+            Err(error) => vec![error]
+        }
+    }
+
+    /// Checks the branch of every attendee the filter includes exists on its remote with ```git ls-remote```, 
+    /// before anything is cloned. Attendees pinned with a ```rev``` and attendees with a ```path``` are skipped. A remote that can not be 
+    /// reached is printed as a warning and not counted as a problem. The remote is listed at the URL it is cloned from, with the
+    /// token of ```auth``` for a private repository.
+    /// 
+    /// # Returns
+    /// * `Vec<String>` - A message for every branch that does not exist, with the similar branches on the remote
+    pub fn verify_refs(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !self.git_available() {
+            return problems
+        }
+        for dependency in self.selected_attendees().into_iter().filter(|dependency| dependency.path.is_none()) {
+            let branch = match dependency.git_ref() {
+                Ok(GitRef::Branch(branch)) => branch,
+                _ => continue
+            };
+            let auth_config = match dependency.auth_config() {
+                Ok(auth_config) => auth_config,
+                Err(error) => {
+                    logging::warn(&format!("could not check branch {} of {}: {}", branch, dependency.name, error));
+                    continue
+                }
+            };
+            match check_branch(&dependency.clone_url(), auth_config.as_ref(), &branch, self.probe_runner.as_ref()) {
+                RefCheck::Found => {},
+                RefCheck::Missing(suggestions) => problems.push(
+                    render_missing_branch(&dependency.name, &branch, &dependency.url, &suggestions)
+                ),
+                RefCheck::Unreachable(error) => logging::warn(&format!(
+                    "could not check branch {} of {}: {}", branch, dependency.name, error
+                ))
+            }
+        }
+        problems
+    }
+
+    /// Gets the installation state of each dependency in the venue.
+    /// 
+    /// # Returns
+    /// * `Vec<DependencyStatus>` - The status of each dependency in the order of the seating plan
+    pub fn status_dependencies(&self) -> Vec<DependencyStatus> {
+        let venue = &self.seating_plan.venue;
+        let mut statuses = Vec::new();
+
+        for dependency in &self.seating_plan.attendees {
+            let installed = self.seating_plan.dependency_path(&dependency.name).is_dir();
+            let current_branch = match installed && self.git_available() {
+                true => CurrentBranchCommand::new(venue.clone(), dependency.name.clone())
+                    .run(self.command_runner.as_ref())
+                    .ok()
+                    .filter(|branch| !branch.is_empty()),
+                false => None
+            };
+            let (expected, mismatch) = match dependency.git_ref() {
+                Ok(GitRef::Branch(branch)) => {
+                    let mismatch = current_branch.as_ref().map(|current| current != &branch).unwrap_or(false);
+                    (branch, mismatch)
+                },
+                Ok(GitRef::Tag(rev)) | Ok(GitRef::Commit(rev)) => (rev, false),
+                Err(_) => ("-".to_string(), false)
+            };
+            statuses.push(DependencyStatus {
+                name: dependency.name.clone(),
+                installed,
+                current_branch,
+                expected,
+                mismatch
+            });
+        }
+        statuses
+    }
+
+    /// Runs git with the arguments passed in inside the venue directory of a dependency.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the dependency to run git in
+    /// * `args` - The arguments passed to git
+    /// 
+    /// # Returns
+    /// * `Result<i32, String>` - The exit code of git or an error if the dependency is not defined or not cloned
+    pub fn run_git_command(&self, name: &str, args: Vec<String>) -> Result<i32, String> {
+        self.installed_dependency_path(name)?;
+        if !self.git_available() {
+            return Err(format!("git is needed to run git in {}", name))
+        }
+        let command = GitPassthroughCommand::new(self.seating_plan.venue.clone(), name.to_string(), args);
+        let status = command.run(self.command_runner.as_ref()).map_err(|error| format!("failed to run git: {}", error))?;
+        Ok(status.code().unwrap_or(1))
+    }
+
+    /// Gets the path to the wedding invite of a dependency that is cloned into the venue.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the dependency
+    /// 
+    /// # Returns
+    /// * `Result<PathBuf, String>` - The path to the ```wedding_invite.yml``` or an error if the dependency is not defined or not cloned
+    pub fn wedding_invite_path(&self, name: &str) -> Result<PathBuf, String> {
+        Ok(self.installed_dependency_path(name)?.join("wedding_invite.yml"))
+    }
+
+    /// Gets the directory of a dependency checking it is in the seating plan and cloned into the venue.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the dependency
+    /// 
+    /// # Returns
+    /// * `Result<PathBuf, String>` - The directory of the dependency or an error if it is not defined or not cloned
+    fn installed_dependency_path(&self, name: &str) -> Result<PathBuf, String> {
+        if !self.seating_plan.attendees.iter().any(|dependency| dependency.name == name) {
+            return Err(format!("{} is not a dependency in the seating plan", name))
+        }
+        let dependency_path = self.seating_plan.dependency_path(name);
+        if !dependency_path.is_dir() {
+            return Err(format!("{} is not cloned into {}, run wedp install first", name, self.seating_plan.venue))
+        }
+        Ok(dependency_path)
+    }
+
+    /// Tears down the dependencies that are running.
+    /// 
+    /// # Returns
+    /// * `Result<bool, String>` - Whether the containers were torn down or an error if the docker-compose 
+    ///   command could not be put together or started
+    pub fn teardown_dependencies(&self) -> Result<bool, String> {
+        self.teardown(false)
+    }
+
+    /// Tears down the remote dependencies that are running.
+    /// 
+    /// # Returns
+    /// * `Result<bool, String>` - Whether the containers were torn down or an error if the docker-compose 
+    ///   command could not be put together or started
+    pub fn teardown_remote_dependencies(&self) -> Result<bool, String> {
+        self.teardown(true)
+    }
+
+    /// Tears down the containers of the local or remote runner files.
+    /// 
+    /// # Arguments
+    /// * `remote` - Whether to use the remote runner files
+    /// 
+    /// # Returns
+    /// * `Result<bool, String>` - Whether docker-compose succeeded or an error if the docker-compose command
+    ///   could not be put together or started
+    fn teardown(&self, remote: bool) -> Result<bool, String> {
+        let compose = self.get_compose_file_command(remote)?;
+        let status = self.command_runner.run_docker_command(&ComposeAction::Down.to_string(), "failed to tear down", &compose)
+            .map_err(|error| format!("failed to tear down: {}", error))?;
+        Ok(status.success())
+    }
+
+    /// Removes the clone of every attendee the filter includes from the venue, for resetting the venue to 
+    /// nothing after the containers are torn down. Only directories directly inside the venue are removed.
+    /// 
+    /// # Returns
+    /// * `Vec<String>` - An error for every dependency that could not be removed
+    pub fn remove_venue_dependencies(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for dependency in self.selected_attendees() {
+            let dependency_path = match self.seating_plan.removable_dependency_path(&dependency.name) {
+                Ok(dependency_path) => dependency_path,
+                Err(error) => {
+                    errors.push(error);
+                    continue
+                }
+            };
+            if !self.file_handle.exists(&dependency_path) {
+                continue
+            }
+            match self.file_handle.remove_directory(&dependency_path) {
+                Ok(_) => logging::info(&format!("{}: removed {}", dependency.name, dependency_path.to_string_lossy())),
+                Err(error) => errors.push(format!("Failed to remove {}: {}", dependency.name, error))
+            }
+        }
+        errors
+    }
+
+    /// Removes the clone of every attendee the filter includes from the venue and, with ```all```, the venue 
+    /// itself. Nothing is removed if the venue resolves to the root, the home directory, the directory wedp is 
+    /// run from, or a directory above it.
+    /// 
+    /// # Arguments
+    /// * `all` - If true the venue directory is removed once the dependencies are removed
+    /// * `cwd` - The directory wedp is run from
+    /// * `home` - The home directory of the user, ```None``` if it is not known
+    /// 
+    /// # Returns
+    /// * `Result<(), String>` - An error if the venue is not safe to clean or anything could not be removed
+    pub fn clean_venue(&self, all: bool, cwd: &Path, home: Option<&Path>) -> Result<(), String> {
+        let venue_path = self.seating_plan.removable_venue_path(cwd, home)?;
+        let errors = self.remove_venue_dependencies();
+        if !errors.is_empty() {
+            return Err(errors.join("\n"))
+        }
+        if !all || !self.file_handle.exists(&venue_path) {
+            return Ok(())
+        }
+        self.file_handle.remove_directory(&venue_path)
+            .map_err(|error| format!("Failed to remove the venue {}: {}", venue_path.to_string_lossy(), error))?;
+        logging::info(&format!("removed the venue {}", venue_path.to_string_lossy()));
+        Ok(())
+    }
+
+    /// Builds the dependencies that are needed to run. 
+    /// 
+    /// # Arguments
+    /// * `pull` - If true the latest versions of the base images are pulled before building
+    /// * `no_cache` - If true the images are built without the build cache
+    /// 
+    /// # Returns
+    /// * `Result<(), WedpError>` - An error if the docker-compose command could not be put together or did not succeed
+    pub fn build_dependencies(&self, pull: bool, no_cache: bool) -> Result<(), WedpError> {
+        let compose = self.get_compose_file_command(false).map_err(WedpError::InvalidConfig)?;
+        let command = ComposeAction::Build { pull, no_cache };
+        run_docker_checked(self.command_runner.as_ref(), &command.to_string(), "failed to build", &compose)
+    }
+
+    /// Builds the dependencies from their remote runner files.
+    /// 
+    /// # Arguments
+    /// * `pull` - If true the latest versions of the base images are pulled before building
+    /// * `no_cache` - If true the images are built without the build cache
+    /// 
+    /// # Returns
+    /// * `Result<(), WedpError>` - An error if the docker-compose command could not be put together or did not succeed
+    pub fn build_remote_dependencies(&self, pull: bool, no_cache: bool) -> Result<(), WedpError> {
+        let compose = self.get_compose_file_command(true).map_err(WedpError::InvalidConfig)?;
+        let command = ComposeAction::Build { pull, no_cache };
+        run_docker_checked(self.command_runner.as_ref(), &command.to_string(), "failed to build remote dependencies", &compose)
+    }
+
+    /// Runs the dependencies defined.
+    /// 
+    /// # Arguments
+    /// * `cwd` - The directory wedp was started in
+    /// 
+    /// # Returns
+    /// * `Result<(), WedpError>` - An error if the docker-compose command could not be put together or did not succeed
+    pub fn run_dependencies(&self, cwd: &Path) -> Result<(), WedpError> {
+        let compose = self.get_run_command(false, cwd).map_err(WedpError::InvalidConfig)?;
+        run_docker_checked(self.command_runner.as_ref(), &self.up_action(ComposeAction::Up), "failed to run", &compose)
+    }
+
+    /// Runs the dependencies defined in the background.
+    /// 
+    /// # Arguments
+    /// * `wait` - If true the command waits for the services to be running or healthy before returning
+    /// * `cwd` - The directory wedp was started in
+    /// 
+    /// # Returns
+    /// * `bool` - Whether the dependencies were started
+    pub fn run_dependencies_background(&self, wait: bool, cwd: &Path) -> bool {
+        self.start_detached(false, wait, cwd)
+    }
+
+    /// Runs the dependencies defined and stops all of them as soon as any container exits, exiting with the
+    /// exit code of the service passed in.
+    /// 
+    /// # Arguments
+    /// * `service` - The service whose exit code is returned
+    /// * `cwd` - The directory wedp was started in
+    /// 
+    /// # Returns
+    /// * `Result<i32, String>` - The exit code of the service or an error if the service name is not valid
+    pub fn run_dependencies_abort_on_exit(&self, service: &str, cwd: &Path) -> Result<i32, String> {
+        let command = ComposeAction::UpAbortOnExit(ServiceName::new(service)?);
+        let command_runner = self.command_runner.as_ref();
+        let compose = self.get_run_command(false, cwd)?;
+        let status = command_runner.run_docker_command(&self.up_action(command), "failed to run", &compose)
+            .map_err(|error| format!("failed to run: {}", error))?;
+        Ok(status.code().unwrap_or(1))
+    }
+
+    /// Runs the remote dependencies defined.
+    /// 
+    /// # Arguments
+    /// * `cwd` - The directory wedp was started in
+    /// 
+    /// # Returns
+    /// * `Result<(), WedpError>` - An error if the docker-compose command could not be put together or did not succeed
+    pub fn run_remote_dependencies(&self, cwd: &Path) -> Result<(), WedpError> {
+        let compose = self.get_run_command(true, cwd).map_err(WedpError::InvalidConfig)?;
+        run_docker_checked(self.command_runner.as_ref(), &self.up_action(ComposeAction::Up), "failed to run", &compose)
+    }
+
+    /// Runs the remote dependencies defined in the background.
+    /// 
+    /// # Arguments
+    /// * `wait` - If true the command waits for the services to be running or healthy before returning
+    /// * `cwd` - The directory wedp was started in
+    /// 
+    /// # Returns
+    /// * `bool` - Whether the dependencies were started
+    pub fn run_remote_dependencies_background(&self, wait: bool, cwd: &Path) -> bool {
+        self.start_detached(true, wait, cwd)
+    }
+
+    /// Starts the dependencies in the background and records the compose files they were started with so 
+    /// following the logs afterwards uses the same files.
+    /// 
+    /// # Arguments
+    /// * `remote` - Whether to use the remote runner files
+    /// * `wait` - If true the command waits for the services to be running or healthy before returning
+    /// * `cwd` - The directory wedp was started in
+    /// 
+    /// # Returns
+    /// * `bool` - Whether the dependencies were started
+    fn start_detached(&self, remote: bool, wait: bool, cwd: &Path) -> bool {
+        let compose_files = match self.get_run_files(remote, cwd) {
+            Ok(compose_files) => compose_files,
+            Err(error) => {
+                logging::error(&error.to_string());
+                return false
+            }
+        };
+        let command = match wait {
+            true => ComposeAction::UpDetachedWait,
+            false => ComposeAction::UpDetached
+        };
+        let compose = compose_args(self.compose_command(), &compose_files);
+        if let Err(error) = run_docker_checked(self.command_runner.as_ref(), &self.up_action(command), "failed to run", &compose) {
+            logging::error(&error.to_string());
+            return false
+        }
+        let state = RunState { remote, compose_files };
+        if let Err(error) = VenueState::new(&self.seating_plan.venue_path(), self.file_handle.as_ref()).save(RUN_STATE_FILE, &state) {
+            logging::warn(&format!("could not record the started compose files: {}", error));
+        }
+        self.show_started(remote);
+        if wait {
+            if !self.wait_for_health_http(remote, &SystemClock) {
+                return false
+            }
+            self.open_urls(remote);
+        }
+        true
+    }
+
+    /// Follows the logs of the dependencies that were just started in the background. Interrupting the logs 
+    /// only stops following them, the dependencies keep running.
+    /// 
+    /// # Arguments
+    /// * `remote` - Whether the remote runner files were used to run the dependencies
+    /// 
+    /// # Returns
+    /// * `i32` - The exit code of docker-compose, ```0``` if following was interrupted and ```1``` if the compose 
+    ///   files could not be read
+    pub fn follow_started_logs(&self, remote: bool) -> i32 {
+        let teardown = match remote {
+            true => "wedp remoteteardown",
+            false => "wedp teardown"
+        };
+        logging::info(&format!("Press Ctrl-C to stop following the logs, the dependencies keep running until {}", teardown));
+        let compose_files = match self.get_started_compose_files(remote) {
+            Ok(compose_files) => compose_files,
+            Err(error) => {
+                logging::error(&error.to_string());
+                return 1
+            }
+        };
+        let compose = compose_args(self.compose_command(), &compose_files);
+        let command = ComposeAction::Logs { follow: true, tail: None, services: Vec::new() };
+        let status = match self.command_runner.run_docker_command(&command.to_string(), "failed to get logs", &compose) {
+            Ok(status) => status,
+            Err(error) => {
+                logging::error(&format!("failed to get logs: {}", error));
+                return 1
+            }
+        };
+        logging::info(&format!("Stopped following the logs, the dependencies are still running, stop them with: {}", teardown));
+        logs_exit_code(status, true)
+    }
+
+    /// Prints the logs of the containers of the dependencies, read from the compose files the dependencies
+    /// were last started in the background with.
+    /// 
+    /// # Arguments
+    /// * `remote` - Whether the remote runner files were used to run the dependencies
+    /// * `follow` - Whether to keep following the logs until interrupted
+    /// * `tail` - The number of lines to print of each container, ```None``` for the default
+    /// * `services` - The services to print the logs of, empty for all of them
+    /// 
+    /// # Returns
+    /// * `Result<i32, String>` - The exit code of docker-compose or an error if a service name is not valid or a
+    ///   runner file does not exist
+    pub fn logs(&self, remote: bool, follow: bool, tail: Option<usize>, services: &[String]) -> Result<i32, String> {
+        let command = ComposeAction::logs(follow, tail, services)?;
+        let compose_files = self.existing_compose_files(self.get_started_compose_files(remote)?)?;
+        let compose = compose_args(self.compose_command(), &compose_files);
+        let status = self.command_runner.run_docker_command(&command.to_string(), "failed to get logs", &compose)
+            .map_err(|error| format!("failed to get logs: {}", error))?;
+        Ok(logs_exit_code(status, follow))
+    }
+
+    /// Streams the output of ```docker-compose ps``` for the containers of the dependencies as it is, which also
+    /// works with compose versions that can not print the containers as JSON.
+    /// 
+    /// # Arguments
+    /// * `remote` - Whether the remote runner files were used to run the dependencies
+    /// 
+    /// # Returns
+    /// * `Result<i32, String>` - The exit code of docker-compose or an error if a runner file does not exist
+    pub fn ps_dependencies(&self, remote: bool) -> Result<i32, String> {
+        let compose = self.get_compose_file_command(remote)?;
+        let status = self.command_runner.run_docker_command(&ComposeAction::Ps.to_string(), "failed to list containers", &compose)
+            .map_err(|error| format!("failed to list containers: {}", error))?;
+        Ok(status.code().unwrap_or(1))
+    }
+
+    /// Gets the state of the containers of the dependencies.
+    /// 
+    /// # Arguments
+    /// * `remote` - Whether the remote runner files were used to run the dependencies
+    /// 
+    /// # Returns
+    /// * `Result<Vec<ContainerStatus>, String>` - The state of each container with the dependency it belongs to,
+    ///   or an error if a runner file does not exist
+    pub fn ps(&self, remote: bool) -> Result<Vec<ContainerStatus>, String> {
+        let compose_files = self.existing_compose_files(self.get_compose_files(remote)?)?;
+        compose_ps(self.compose_command(), &compose_files, self.command_runner.as_ref(), self.file_handle.as_ref())
+    }
+
+    /// Gets the disk used by each dependency the filter includes that is installed in the venue.
+    /// 
+    /// # Returns
+    /// * `Result<Vec<DependencySize>, String>` - The sizes largest first or an error if a directory could not be read
+    pub fn disk_usage(&self) -> Result<Vec<DependencySize>, String> {
+        let names: Vec<&str> = self.selected_attendees().iter().map(|dependency| dependency.name.as_str()).collect();
+        venue_disk_usage(&names, &self.seating_plan.venue_path(), self.file_handle.as_ref())
+    }
+
+    /// Loads the usage stats of the venue.
+    /// 
+    /// # Returns
+    /// * `Result<UsageStats, String>` - The usage stats, empty if nothing has been recorded yet
+    pub fn usage_stats(&self) -> Result<UsageStats, String> {
+        VenueState::new(&self.seating_plan.venue_path(), self.file_handle.as_ref()).load(STATS_FILE)
+    }
+
+    /// Removes the usage stats of the venue.
+    /// 
+    /// # Returns
+    /// * `Result<(), String>` - An error if the stats file could not be removed
+    pub fn reset_usage_stats(&self) -> Result<(), String> {
+        VenueState::new(&self.seating_plan.venue_path(), self.file_handle.as_ref()).remove(STATS_FILE)
+    }
+
+    /// Updates the usage stats of the venue. Failing to record the stats only prints a warning so it never 
+    /// fails the command being recorded, and a venue that does not exist is not created to hold them.
+    /// 
+    /// # Arguments
+    /// * `update` - Updates the stats, called with the names of the attendees the filter includes
+    pub fn record_usage<F: FnOnce(&mut UsageStats, &[String])>(&self, update: F) {
+        if !self.file_handle.exists(&self.seating_plan.venue_path()) {
+            return
+        }
+        let state = VenueState::new(&self.seating_plan.venue_path(), self.file_handle.as_ref());
+        let names: Vec<String> = self.selected_attendees().iter().map(|dependency| dependency.name.clone()).collect();
+        let result = state.load(STATS_FILE).and_then(|mut stats: UsageStats| {
+            update(&mut stats, &names);
+            state.save(STATS_FILE, &stats)
+        });
+        if let Err(error) = result {
+            logging::warn(&format!("could not record usage stats: {}", error));
+        }
+    }
+
+    /// Lists the containers that were started in the background and how to follow their logs.
+    /// 
+    /// # Arguments
+    /// * `remote` - Whether the remote runner files were used to run the dependencies
+    fn show_started(&self, remote: bool) {
+        if let Err(error) = self.ps_dependencies(remote) {
+            logging::error(&error.to_string());
+            return
+        }
+        match remote {
+            true => logging::info("Follow the logs with: wedp logs --remote"),
+            false => logging::info("Follow the logs with: wedp logs")
+        }
+    }
+
+    /// Polls the ```health_http``` endpoint of every dependency that has one until it returns the expected 
+    /// status. Every endpoint is waited for even if an earlier one timed out so all of the failures are reported.
+    /// 
+    /// # Arguments
+    /// * `remote` - Whether the remote runner files were used to run the dependencies
+    /// * `clock` - The clock the timeouts are measured with
+    /// 
+    /// # Returns
+    /// * `bool` - Whether every endpoint returned the expected status
+    fn wait_for_health_http(&self, remote: bool, clock: &dyn Clock) -> bool {
+        let mut healthy = true;
+        for dependency in &self.seating_plan.attendees {
+            let health = match &dependency.health_http {
+                Some(health) => health,
+                None => continue
+            };
+            let result = self.expand_open_url(dependency, &health.url, remote)
+                .and_then(|url| wait_for_status(&url, health, POLL_INTERVAL, clock).map(|_| url));
+            match result {
+                Ok(url) => logging::info(&format!("{}: {} returned {}", dependency.name, url, health.expected_status())),
+                Err(error) => {
+                    logging::error(&format!("{}: health check failed: {}", dependency.name, error));
+                    healthy = false
+                }
+            }
+        }
+        healthy
+    }
+
+    /// Opens the ```open_url``` of every dependency that has one with the platform launcher. A URL that can 
+    /// not be opened is reported and the rest are still opened.
+    /// 
+    /// # Arguments
+    /// * `remote` - Whether the remote runner files were used to run the dependencies
+    fn open_urls(&self, remote: bool) {
+        for dependency in &self.seating_plan.attendees {
+            let url = match &dependency.open_url {
+                Some(url) => url,
+                None => continue
+            };
+            let opened = self.expand_open_url(dependency, url, remote)
+                .and_then(|url| {
+                    let output = self.command_runner.run(&launcher_command(&url)).map_err(|error| error.to_string())?;
+                    check_status(output).map_err(|error| error.to_string())?;
+                    Ok(url)
+                });
+            match opened {
+                Ok(url) => logging::info(&format!("{}: opened {}", dependency.name, url)),
+                Err(error) => logging::error(&format!("{}: failed to open {}: {}", dependency.name, url, error))
+            }
+        }
+    }
+
+    /// Replaces ```{host_port}``` in the ```open_url``` of a dependency with the host port published for 
+    /// its ```open_port```.
+    /// 
+    /// # Arguments
+    /// * `dependency` - The dependency the URL belongs to
+    /// * `url` - The ```open_url``` of the dependency
+    /// * `remote` - Whether the remote runner files were used to run the dependencies
+    /// 
+    /// # Returns
+    /// * `Result<String, String>` - The URL to open or an error if the host port could not be found
+    fn expand_open_url(&self, dependency: &Dependency, url: &str, remote: bool) -> Result<String, String> {
+        let open_port = match (&dependency.open_port, url.contains(HOST_PORT_PLACEHOLDER)) {
+            (Some(open_port), true) => OpenPort::parse(open_port)?,
+            _ => return expand_open_url(url, None)
+        };
+        let lookup = self.get_compose_file_command(remote)?.with_command(&open_port.lookup_suffix());
+        let output = self.command_runner.run_in_dir(Path::new("."), &lookup.program, &lookup.arg_refs())
+                                        .map_err(|error| error.to_string())?;
+        let output = check_status(output).map_err(|error| error.to_string())?;
+        match parse_host_port(&String::from_utf8_lossy(&output.stdout)) {
+            Some(host_port) => expand_open_url(url, Some(&host_port)),
+            None => Err(format!("{}:{} is not published", open_port.service, open_port.port))
+        }
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::project_location::render_locations_command;
+    use crate::commands::command_runner::{render_in_dir, MockCoreRunner};
+    use crate::file_handler::MockCoreFileHandle;
+    use crate::dry_run::SharedOutput;
+    use crate::health_http::HealthHttp;
+    use mockall::predicate::{always, eq};
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::{ExitStatus, Output};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn test_runner() -> Runner {
+        test_runner_with(MockCoreRunner::new())
+    }
+
+    /// Builds a file handle where every compose file exists.
+    fn existing_files_handle() -> MockCoreFileHandle {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_exists().returning(|_| true);
+        mock_handle
+    }
+
+    /// Builds a probe runner where no compose project is running.
+    fn no_projects_probe() -> MockCoreRunner {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run()
+            .with(eq(render_locations_command("docker")))
+            .returning(|_| Ok(Output { status: ExitStatus::from_raw(0), stdout: Vec::new(), stderr: Vec::new() }));
+        mock_runner
+    }
+
+    fn test_runner_with(command_runner: MockCoreRunner) -> Runner {
+        Runner {
+            seating_plan: SeatingPlan {
+                attendees: vec![
+                    Dependency {
+                        name: "test_repo".to_string(),
+                        url: "https://github.com/yellow-bird-consult/wedding_planner".to_string(),
+                        branch: Some("master".to_string()),
+                        ..Default::default()
+                    }
+                ],
+                venue: "./tests".to_string(),
+                command_defaults: None,
+                restart_policy: None,
+                compose_command: None,
+                engine: None,
+                max_docker_ops: None,
+                max_git_ops: None,
+                clone_depth: None,
+                auth: None,
+                git_protocol: None
+            },
+            command_runner: Box::new(command_runner),
+            file_handle: Box::new(existing_files_handle()),
+            arch: None,
+            filter: AttendeeFilter::All,
+            engine: None,
+            probe_runner: Box::new(no_projects_probe()),
+            compose: OnceLock::from(COMPOSE_V1.to_string()),
+            skip_missing: false,
+            force_recreate: false,
+            git_available: OnceLock::from(true)
+        }
+    }
+
+    #[test]
+    fn test_get_compose_file_command() {
+        let runner = test_runner();
+        assert_eq!(
+            runner.get_compose_file_command(false).unwrap().to_string(),
+            "docker-compose -f tests/test_repo/runner_files/base.yml -f tests/test_repo/runner_files/database.yml"
+        );
+    }
+
+    /// Builds a file handle where the test repo is cloned but only its base runner file exists.
+    fn missing_database_handle() -> MockCoreFileHandle {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_exists()
+            .returning(|path| ["tests", "tests/test_repo", "tests/test_repo/runner_files/base.yml"].iter().any(|existing| path == Path::new(existing)));
+        mock_handle
+    }
+
+    /// Builds a file handle where only the given paths exist.
+    fn existing_paths_handle(paths: &'static [&'static str]) -> MockCoreFileHandle {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_exists()
+            .returning(move |path| paths.iter().any(|existing| path == Path::new(existing)));
+        mock_handle
+    }
+
+    #[test]
+    fn test_preflight() {
+        let mut runner = test_runner();
+        runner.seating_plan.attendees.push(Dependency { name: "billing".to_string(), ..Default::default() });
+
+        runner.file_handle = Box::new(existing_paths_handle(&["tests", "tests/test_repo"]));
+        let report = runner.preflight();
+        assert_eq!(report, PreflightReport {
+            venue: "./tests".to_string(),
+            venue_exists: true,
+            selected: vec!["test_repo".to_string(), "billing".to_string()],
+            missing: vec!["billing".to_string()],
+        });
+        assert_eq!(report.check(), Ok(()));
+
+        runner.file_handle = Box::new(existing_paths_handle(&["tests"]));
+        assert_eq!(
+            runner.preflight().check(),
+            Err("none of the attendees are cloned into the venue ./tests, run `wedp install` first\nmissing attendees: test_repo, billing".to_string())
+        );
+
+        runner.file_handle = Box::new(existing_paths_handle(&[]));
+        let report = runner.preflight();
+        assert!(!report.venue_exists);
+        assert_eq!(
+            report.check(),
+            Err("venue ./tests does not exist, run `wedp setup && wedp install` first\nmissing attendees: test_repo, billing".to_string())
+        );
+
+        runner.filter = AttendeeFilter::from_options(Vec::new(), vec!["test_repo".to_string(), "billing".to_string()]).unwrap();
+        runner.file_handle = Box::new(existing_paths_handle(&["tests"]));
+        assert!(runner.preflight().is_ready());
+    }
+
+    #[test]
+    fn test_run_dependencies_missing_venue() {
+        let mut runner = test_runner();
+        runner.file_handle = Box::new(existing_paths_handle(&[]));
+
+        let error = runner.run_dependencies(&env::current_dir().unwrap()).unwrap_err().to_string();
+
+        assert!(error.contains("venue ./tests does not exist, run `wedp setup && wedp install` first"), "{}", error);
+    }
+
+    #[test]
+    fn test_get_compose_file_command_missing_file() {
+        let mut runner = test_runner();
+        runner.file_handle = Box::new(missing_database_handle());
+        assert_eq!(
+            runner.get_compose_file_command(false),
+            Err("1 runner file does not exist:\n  test_repo: tests/test_repo/runner_files/database.yml\n\
+                 Fix the wedding invites or pass --skip-missing to leave them out".to_string())
+        );
+    }
+
+    #[test]
+    fn test_missing_file_fails_before_docker_runs() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_docker_command().times(0);
+        let mut runner = test_runner_with(mock_runner);
+        let mut mock_handle = missing_database_handle();
+        mock_handle.expect_read_to_string()
+            .returning(|_| Err(std::io::Error::from(std::io::ErrorKind::NotFound)));
+        runner.file_handle = Box::new(mock_handle);
+
+        assert!(runner.teardown_dependencies().is_err());
+        assert!(runner.run_dependencies(&env::current_dir().unwrap()).is_err());
+        assert!(!runner.run_dependencies_background(false, &env::current_dir().unwrap()));
+        assert!(runner.logs(false, false, None, &[]).is_err());
+    }
+
+    #[test]
+    fn test_teardown_then_remove_venue() {
+        let mut sequence = mockall::Sequence::new();
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_docker_command()
+            .with(eq(" down"), always(), always())
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _, _| Ok(ExitStatus::from_raw(0)));
+        let mut runner = test_runner_with(mock_runner);
+        let mut mock_handle = existing_files_handle();
+        mock_handle.expect_remove_directory()
+            .with(eq(PathBuf::from("tests/test_repo")))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(()));
+        runner.file_handle = Box::new(mock_handle);
+
+        assert_eq!(runner.teardown_dependencies(), Ok(true));
+        assert_eq!(runner.remove_venue_dependencies(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_remove_venue_refuses_paths_outside_the_venue() {
+        let mut runner = test_runner();
+        runner.seating_plan.attendees[0].name = "../test_repo".to_string();
+        let mut mock_handle = existing_files_handle();
+        mock_handle.expect_remove_directory().times(0);
+        runner.file_handle = Box::new(mock_handle);
+
+        assert_eq!(
+            runner.remove_venue_dependencies(),
+            vec!["../test_repo is not a directory inside the venue ./tests, refusing to remove it".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_clean_venue() {
+        let mut runner = test_runner();
+        let mut mock_handle = existing_files_handle();
+        mock_handle.expect_remove_directory()
+            .with(eq(PathBuf::from("tests/test_repo")))
+            .times(1)
+            .returning(|_| Ok(()));
+        mock_handle.expect_remove_directory()
+            .with(eq(PathBuf::from("tests")))
+            .times(0);
+        runner.file_handle = Box::new(mock_handle);
+
+        assert_eq!(runner.clean_venue(false, Path::new("/work"), Some(Path::new("/home/dev"))), Ok(()));
+    }
+
+    #[test]
+    fn test_clean_venue_all() {
+        let mut sequence = mockall::Sequence::new();
+        let mut runner = test_runner();
+        let mut mock_handle = existing_files_handle();
+        for path in ["tests/test_repo", "tests"] {
+            mock_handle.expect_remove_directory()
+                .with(eq(PathBuf::from(path)))
+                .times(1)
+                .in_sequence(&mut sequence)
+                .returning(|_| Ok(()));
+        }
+        runner.file_handle = Box::new(mock_handle);
+
+        assert_eq!(runner.clean_venue(true, Path::new("/work"), None), Ok(()));
+    }
+
+    #[test]
+    fn test_clean_venue_refuses_dangerous_venues() {
+        for venue in ["/", ".", "..", "/home/dev"] {
+            let mut runner = test_runner();
+            runner.seating_plan.venue = venue.to_string();
+            let mut mock_handle = existing_files_handle();
+            mock_handle.expect_remove_directory().times(0);
+            runner.file_handle = Box::new(mock_handle);
+
+            let error = runner.clean_venue(true, Path::new("/work/project"), Some(Path::new("/home/dev"))).unwrap_err();
+            assert!(error.ends_with("refusing to remove it"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_clean_venue_keeps_the_venue_when_a_dependency_fails() {
+        let mut runner = test_runner();
+        let mut mock_handle = existing_files_handle();
+        mock_handle.expect_remove_directory()
+            .with(eq(PathBuf::from("tests/test_repo")))
+            .times(1)
+            .returning(|_| Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "Permission denied")));
+        mock_handle.expect_remove_directory()
+            .with(eq(PathBuf::from("tests")))
+            .times(0);
+        runner.file_handle = Box::new(mock_handle);
+
+        assert_eq!(
+            runner.clean_venue(true, Path::new("/work"), None),
+            Err("Failed to remove test_repo: Permission denied".to_string())
+        );
+    }
+
+    #[test]
+    fn test_get_compose_file_command_skip_missing() {
+        let mut runner = test_runner().with_skip_missing(true);
+        runner.file_handle = Box::new(missing_database_handle());
+        assert_eq!(
+            runner.get_compose_file_command(false).unwrap().to_string(),
+            "docker-compose -f tests/test_repo/runner_files/base.yml"
+        );
+    }
+
+    #[test]
+    fn test_git_available_probe() {
+        let mut probe = MockCoreRunner::new();
+        probe.expect_run()
+            .with(eq("git --version".to_string()))
+            .times(1)
+            .returning(|_| Ok(Output { status: ExitStatus::from_raw(127 << 8), stdout: Vec::new(), stderr: b"git: not found".to_vec() }));
+        let mut runner = test_runner_with(MockCoreRunner::new());
+        runner.probe_runner = Box::new(probe);
+        runner.git_available = OnceLock::new();
+
+        assert!(!runner.git_available());
+        assert_eq!(runner.verify_refs(), Vec::<String>::new());
+        assert_eq!(runner.run_git_command("test_repo", vec!["status".to_string()]), Err("git is needed to run git in test_repo".to_string()));
+
+        let mut probe = MockCoreRunner::new();
+        probe.expect_run()
+            .with(eq("git --version".to_string()))
+            .returning(|_| Ok(Output { status: ExitStatus::from_raw(0), stdout: b"git version 2.43.0\n".to_vec(), stderr: Vec::new() }));
+        runner.probe_runner = Box::new(probe);
+        runner.git_available = OnceLock::new();
+        assert!(runner.git_available());
+    }
+
+    #[test]
+    fn test_status_dependencies_without_git() {
+        let mut runner = test_runner_with(MockCoreRunner::new());
+        runner.git_available = OnceLock::from(false);
+
+        assert_eq!(runner.status_dependencies(), vec![
+            DependencyStatus {
+                name: "test_repo".to_string(),
+                installed: true,
+                current_branch: None,
+                expected: "master".to_string(),
+                mismatch: false
+            }
+        ]);
+    }
+
+    #[test]
+    fn test_status_dependencies() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .withf(|dir, program, args| {
+                dir == Path::new("./tests/test_repo") && program == "git" && args == ["rev-parse", "--abbrev-ref", "HEAD"]
+            })
+            .times(1)
+            .returning(|_, _, _| {
+                Ok(Output {
+                    status: ExitStatus::from_raw(0),
+                    stdout: b"development\n".to_vec(),
+                    stderr: Vec::new(),
+                })
+            });
+        let mut runner = test_runner_with(mock_runner);
+        runner.seating_plan.attendees.push(Dependency {
+            name: "not_installed".to_string(),
+            url: "https://github.com/yellow-bird-consult/not_installed".to_string(),
+            branch: Some("main".to_string()),
+            ..Default::default()
+        });
+
+        let statuses = runner.status_dependencies();
+        assert_eq!(statuses, vec![
+            DependencyStatus {
+                name: "test_repo".to_string(),
+                installed: true,
+                current_branch: Some("development".to_string()),
+                expected: "master".to_string(),
+                mismatch: true
+            },
+            DependencyStatus {
+                name: "not_installed".to_string(),
+                installed: false,
+                current_branch: None,
+                expected: "main".to_string(),
+                mismatch: false
+            },
+        ]);
+        assert_eq!(
+            render_status_table(&statuses),
+            "NAME                 INSTALLED  CURRENT              EXPECTED             MISMATCH\n\
+             test_repo            yes        development          master               yes\n\
+             not_installed        no         -                    main\n"
+        );
+    }
+
+    #[test]
+    fn test_run_git_command() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_docker_command()
+            .withf(|command, _, program_args| {
+                command.is_empty() && program_args.to_string() == "git -C ./tests/test_repo log --oneline -n 1"
+            })
+            .times(1)
+            .returning(|_, _, _| Ok(ExitStatus::from_raw(0)));
+        let runner = test_runner_with(mock_runner);
+        let args = vec!["log".to_string(), "--oneline".to_string(), "-n".to_string(), "1".to_string()];
+
+        assert_eq!(runner.run_git_command("test_repo", args), Ok(0));
+    }
+
+    #[test]
+    fn test_run_git_command_not_cloned() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_docker_command().times(0);
+        let mut runner = test_runner_with(mock_runner);
+        runner.seating_plan.attendees.push(Dependency {
+            name: "not_installed".to_string(),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            runner.run_git_command("not_installed", vec!["status".to_string()]),
+            Err("not_installed is not cloned into ./tests, run wedp install first".to_string())
+        );
+        assert_eq!(
+            runner.run_git_command("missing", vec!["status".to_string()]),
+            Err("missing is not a dependency in the seating plan".to_string())
+        );
+    }
+
+    #[test]
+    fn test_wedding_invite_path() {
+        let mut runner = test_runner();
+        runner.seating_plan.attendees.push(Dependency {
+            name: "not_installed".to_string(),
+            ..Default::default()
+        });
+
+        assert_eq!(runner.wedding_invite_path("test_repo"), Ok(PathBuf::from("tests/test_repo/wedding_invite.yml")));
+        assert_eq!(
+            runner.wedding_invite_path("not_installed"),
+            Err("not_installed is not cloned into ./tests, run wedp install first".to_string())
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut runner = test_runner();
+        runner.seating_plan.attendees.push(Dependency {
+            name: "valid_repo".to_string(),
+            ..Default::default()
+        });
+        runner.seating_plan.attendees.push(Dependency {
+            name: "not_installed".to_string(),
+            ..Default::default()
+        });
+
+        let problems = runner.validate();
+
+        assert_eq!(problems.len(), 7);
+        assert!(problems.iter().all(|problem| problem.starts_with("test_repo: ")));
+        assert_eq!(problems[0], "test_repo: build_files.aarch64 build/Dockerfile.aarch64 does not exist");
+    }
+
+    #[test]
+    fn test_validate_unknown_invite_keys() {
+        let mut runner = test_runner();
+        runner.seating_plan = SeatingPlan::from_file_lenient("tests/validate/seating_plan.yml".to_string()).unwrap().0;
+
+        let problems = runner.validate();
+
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert!(
+            problems[0].starts_with("typo_repo: tests/validate/typo_repo/wedding_invite.yml: unknown field `remote_runer_files` at line 4 column 1"),
+            "{}", problems[0]
+        );
+    }
+
+    #[test]
+    fn test_validate_sparse_paths() {
+        let mut runner = test_runner();
+        let without_sparse_paths = runner.validate();
+        runner.seating_plan.attendees[0].sparse_paths = Some(vec!["build".to_string(), "database".to_string()]);
+
+        let problems = runner.validate();
+
+        assert_eq!(problems[..without_sparse_paths.len()], without_sparse_paths[..]);
+        assert_eq!(problems[without_sparse_paths.len()..], [
+            "test_repo: runner_files runner_files/base.yml is outside the sparse_paths build, database".to_string(),
+            "test_repo: runner_files runner_files/database.yml is outside the sparse_paths build, database".to_string(),
+        ]);
+    }
+
+    fn dry_run_runner(output: &SharedOutput) -> Runner {
+        let mut runner = test_runner();
+        runner.command_runner = Box::new(DryRunRunner::new(Box::new(output.clone())));
+        runner.file_handle = Box::new(DryRunFileHandle::new(Box::new(output.clone())));
+        runner
+    }
+
+    #[test]
+    fn test_dry_run_docker_commands() {
+        let output = SharedOutput::default();
+        let runner = dry_run_runner(&output);
+
+        runner.teardown_dependencies().unwrap();
+        runner.run_dependencies_background(false, &env::current_dir().unwrap());
+        runner.build_dependencies(true, false).unwrap();
+        runner.build_dependencies(false, true).unwrap();
+        runner.build_dependencies(true, true).unwrap();
+
+        let compose = "docker-compose -f tests/test_repo/runner_files/base.yml -f tests/test_repo/runner_files/database.yml";
+        assert_eq!(
+            output.contents(),
+            format!(
+                "DRY-RUN: {compose} down\n\
+                 DRY-RUN: {compose} up -d\n\
+                 DRY-RUN: mkdir -p tests/.wedp\n\
+                 DRY-RUN: write tests/.wedp/run.json\n\
+                 DRY-RUN: {compose} ps\n\
+                 DRY-RUN: {compose} build --pull\n\
+                 DRY-RUN: {compose} build --no-cache\n\
+                 DRY-RUN: {compose} build --pull --no-cache\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_build_remote_dependencies() {
+        let output = SharedOutput::default();
+        let mut runner = dry_run_runner(&output);
+        runner.seating_plan.attendees[0].name = "image_arch".to_string();
+        runner.arch = Some("x86_64".to_string());
+
+        runner.build_remote_dependencies(true, false).unwrap();
+
+        assert_eq!(
+            output.contents(),
+            "DRY-RUN: write tests/wedp_image_arch_override.yml\n\
+             DRY-RUN: docker-compose -f tests/image_arch/auth_remote.yml -f tests/wedp_image_arch_override.yml build --pull\n"
+        );
+    }
+
+    #[test]
+    fn test_dry_run_install() {
+        let output = SharedOutput::default();
+        let mut runner = dry_run_runner(&output);
+        runner.seating_plan.attendees[0].name = "not_cloned".to_string();
+        runner.seating_plan.attendees[0].url = "git@github.com:yellow-bird-consult/not_cloned.git".to_string();
+        let venue = env::current_dir().unwrap().join("tests").to_string_lossy().to_string();
+
+        let reports = runner.install_dependencies(1, false, &env::current_dir().unwrap());
+
+        assert!(reports[0].cloned);
+        assert!(reports[0].checked_out);
+        assert_eq!(
+            output.contents(),
+            format!(
+                "DRY-RUN: cd {venue} && git clone --branch master --single-branch git@github.com:yellow-bird-consult/not_cloned.git\n\
+                 DRY-RUN: cd {venue}/not_cloned && git rev-parse --abbrev-ref HEAD\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_dry_run_install_existing() {
+        let output = SharedOutput::default();
+        let runner = dry_run_runner(&output);
+        let venue = env::current_dir().unwrap().join("tests").to_string_lossy().to_string();
+
+        let reports = runner.install_dependencies(1, false, &env::current_dir().unwrap());
+
+        assert!(reports[0].fetched);
+        assert!(reports[0].pulled);
+        assert!(!reports[0].cloned);
+        assert!(output.contents().starts_with(&format!(
+            "DRY-RUN: cd {venue}/test_repo && git remote set-branches origin '*'\n\
+             DRY-RUN: cd {venue}/test_repo && git fetch --tags\n\
+             DRY-RUN: cd {venue}/test_repo && git checkout master\n\
+             DRY-RUN: cd {venue}/test_repo && git pull --ff-only\n\
+             DRY-RUN: cp "
+        )));
+    }
+
+    #[test]
+    fn test_dry_run_install_existing_force() {
+        let output = SharedOutput::default();
+        let runner = dry_run_runner(&output);
+        let venue = env::current_dir().unwrap().join("tests").to_string_lossy().to_string();
+
+        let reports = runner.install_dependencies(1, true, &env::current_dir().unwrap());
+
+        assert!(reports[0].cloned);
+        assert!(!reports[0].fetched);
+        assert!(output.contents().starts_with(&format!(
+            "DRY-RUN: rm -r tests/test_repo\n\
+             DRY-RUN: cd {venue} && git clone --branch master --single-branch https://github.com/yellow-bird-consult/wedding_planner\n\
+             DRY-RUN: cd {venue}/test_repo && git rev-parse --abbrev-ref HEAD\n\
+             DRY-RUN: cp "
+        )));
+    }
+
+    #[test]
+    fn test_get_compose_files() {
+        let runner = test_runner();
+        assert_eq!(
+            runner.get_compose_files(false).unwrap(),
+            vec![
+                ComposeFileRef::new("test_repo", "tests/test_repo/runner_files/base.yml".to_string()),
+                ComposeFileRef::new("test_repo", "tests/test_repo/runner_files/database.yml".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_compose_files_inline_invite() {
+        let mut runner = test_runner();
+        runner.seating_plan = SeatingPlan::from_file("tests/inline_invite_seating_plan.yml".to_string()).unwrap();
+        assert_eq!(
+            runner.get_compose_files(false).unwrap(),
+            vec![ComposeFileRef::new("test_repo", "tests/test_repo/runner_files/base.yml".to_string())]
+        );
+        let problems = runner.validate();
+        assert!(!problems.is_empty());
+        assert!(problems.iter().all(|problem| !problem.contains("init_build")), "{:?}", problems);
+    }
+
+    #[test]
+    fn test_get_compose_files_missing_invites() {
+        let venue = std::env::temp_dir().join("wedp_runner_tests").join("empty_venue");
+        std::fs::create_dir_all(&venue).unwrap();
+        let mut runner = test_runner();
+        runner.seating_plan.venue = venue.to_string_lossy().to_string();
+        runner.seating_plan.attendees = ["auth", "billing"].iter().map(|name| Dependency {
+            name: name.to_string(),
+            url: format!("https://github.com/org/{}", name),
+            ..Default::default()
+        }).collect();
+        assert_eq!(
+            runner.get_compose_files(false),
+            Err("run `wedp install` first; missing invites for: auth, billing".to_string())
+        );
+        assert!(runner.get_compose_file_command(false).is_err());
+    }
+
+    /// Writes a lockfile locking ```test_repo``` and ```not_cloned``` into a temporary directory.
+    fn test_lockfile(fixture: &str, entries: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join("wedp_runner_tests").join(fixture);
+        std::fs::create_dir_all(&root).unwrap();
+        let lockfile = Lockfile {
+            attendees: entries.iter().map(|(name, commit)| LockEntry {
+                name: name.to_string(),
+                url: "https://github.com/yellow-bird-consult/wedding_planner".to_string(),
+                branch: Some("master".to_string()),
+                commit: commit.to_string()
+            }).collect()
+        };
+        let lock_path = root.join(lockfile::LOCK_FILE);
+        std::fs::write(&lock_path, lockfile.render()).unwrap();
+        lock_path
+    }
+
+    #[test]
+    fn test_install_locked() {
+        let lock_path = test_lockfile("locked", &[
+            ("test_repo", "3f2a9c1d0e8b7a6f5c4d3e2f1a0b9c8d7e6f5a4b"),
+            ("not_cloned", "8b04d2e5f6a7b8c9d0e1f2a3b4c5d6e7f8a9b0c1"),
+        ]);
+        let output = SharedOutput::default();
+        let mut runner = dry_run_runner(&output);
+        runner.seating_plan.attendees.push(Dependency {
+            name: "not_cloned".to_string(),
+            url: "https://github.com/yellow-bird-consult/wedding_planner".to_string(),
+            branch: Some("master".to_string()),
+            ..Default::default()
+        });
+        let venue = env::current_dir().unwrap().join("tests").to_string_lossy().to_string();
+        let runner = runner.with_lockfile(&lock_path).unwrap();
+
+        let reports = runner.install_dependencies(1, false, &env::current_dir().unwrap());
+
+        assert!(!reports[0].pulled && reports[1].cloned, "{:?}", reports);
+        let contents = output.contents();
+        assert!(contents.starts_with(&format!(
+            "DRY-RUN: cd {venue}/test_repo && git remote set-branches origin '*'\n\
+             DRY-RUN: cd {venue}/test_repo && git fetch --tags\n\
+             DRY-RUN: cd {venue}/test_repo && git checkout 3f2a9c1d0e8b7a6f5c4d3e2f1a0b9c8d7e6f5a4b\n"
+        )), "{}", contents);
+        assert!(contents.contains(&format!(
+            "DRY-RUN: cd {venue} && git clone https://github.com/yellow-bird-consult/wedding_planner\n\
+             DRY-RUN: cd {venue}/not_cloned && git checkout 8b04d2e5f6a7b8c9d0e1f2a3b4c5d6e7f8a9b0c1\n"
+        )), "{}", contents);
+        assert!(!contents.contains("git pull"), "{}", contents);
+    }
+
+    #[test]
+    fn test_with_lockfile_missing_attendee() {
+        let lock_path = test_lockfile("locked_missing", &[("test_repo", "3f2a9c1d0e8b7a6f5c4d3e2f1a0b9c8d7e6f5a4b")]);
+        let mut runner = test_runner();
+        runner.file_handle = Box::new(FileHandle {});
+        runner.seating_plan.attendees.push(Dependency {
+            name: "auth".to_string(),
+            url: "https://github.com/org/auth".to_string(),
+            ..Default::default()
+        });
+
+        let error = runner.with_lockfile(&lock_path).err().unwrap();
+        assert_eq!(error, format!("auth is not in {}, run wedp lock to add it", lock_path.to_string_lossy()));
+
+        let mut runner = test_runner();
+        runner.file_handle = Box::new(FileHandle {});
+        let error = runner.with_lockfile(&lock_path.with_file_name("missing.lock")).err().unwrap();
+        assert!(error.starts_with("Could not read ") && error.ends_with(", run wedp lock to create it"), "{}", error);
+    }
+
+    #[test]
+    fn test_lock() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .withf(|dir, _, args| dir == Path::new("/work/tests/test_repo") && args == ["rev-parse", "HEAD"])
+            .times(1)
+            .returning(|_, _, _| Ok(Output {
+                status: ExitStatus::from_raw(0),
+                stdout: b"3f2a9c1d0e8b7a6f5c4d3e2f1a0b9c8d7e6f5a4b\n".to_vec(),
+                stderr: Vec::new()
+            }));
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_read_to_string()
+            .with(eq(PathBuf::from("plans/wedding_planner.lock")))
+            .returning(|_| Err(std::io::Error::from(std::io::ErrorKind::NotFound)));
+        mock_handle.expect_write()
+            .withf(|path, contents| {
+                path == Path::new("plans/wedding_planner.lock") && contents == "attendees:\n\
+                 - name: test_repo\n  url: https://github.com/yellow-bird-consult/wedding_planner\n  branch: master\n  \
+                 commit: 3f2a9c1d0e8b7a6f5c4d3e2f1a0b9c8d7e6f5a4b\n"
+            })
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let mut runner = test_runner_with(mock_runner);
+        runner.file_handle = Box::new(mock_handle);
+
+        let lockfile = runner.lock(Path::new("plans/wedding_planner.lock"), Path::new("/work")).unwrap();
+        assert_eq!(lockfile.attendees.len(), 1);
+
+        runner.seating_plan.venue = "./tests/missing_venue".to_string();
+        assert_eq!(
+            runner.lock(Path::new("plans/wedding_planner.lock"), Path::new("/work")).err(),
+            Some("test_repo is not cloned into ./tests/missing_venue, run wedp install first".to_string())
+        );
+    }
+
+    fn filtered_runner(filter: AttendeeFilter) -> Result<Runner, String> {
+        let mut runner = test_runner();
+        runner.seating_plan.attendees.push(Dependency {
+            name: "valid_repo".to_string(),
+            url: "https://github.com/yellow-bird-consult/valid_repo".to_string(),
+            ..Default::default()
+        });
+        runner.with_filter(filter)
+    }
+
+    #[test]
+    fn test_get_compose_file_command_filtered() {
+        let runner = filtered_runner(AttendeeFilter::All).unwrap();
+        assert_eq!(
+            runner.get_compose_file_command(false).unwrap().to_string(),
+            "docker-compose -f tests/test_repo/runner_files/base.yml -f tests/test_repo/runner_files/database.yml \
+             -f tests/valid_repo/runner_files/base.yml"
+        );
+
+        let runner = filtered_runner(AttendeeFilter::Only(vec!["valid_repo".to_string()])).unwrap();
+        assert_eq!(runner.get_compose_file_command(false).unwrap().to_string(), "docker-compose -f tests/valid_repo/runner_files/base.yml");
+
+        let runner = filtered_runner(AttendeeFilter::Except(vec!["valid_repo".to_string()])).unwrap();
+        assert_eq!(
+            runner.get_compose_file_command(false).unwrap().to_string(),
+            "docker-compose -f tests/test_repo/runner_files/base.yml -f tests/test_repo/runner_files/database.yml"
+        );
+    }
+
+    #[test]
+    fn test_with_filter_unknown_attendee() {
+        assert_eq!(
+            filtered_runner(AttendeeFilter::Except(vec!["auth".to_string()])).err(),
+            Some("auth not in the seating plan, valid names are test_repo, valid_repo".to_string())
+        );
+    }
+
+    /// Builds a runner installing from a fixture of a local directory holding a dependency, with a Dockerfile
+    /// for each CPU and an empty venue.
+    fn path_dependency_runner(fixture: &str, link: bool) -> (Runner, PathBuf, PathBuf) {
+        let root = std::env::temp_dir().join("wedp_runner_tests").join(fixture);
+        let _ = std::fs::remove_dir_all(&root);
+        let source = root.join("auth_service");
+        let venue = root.join("venue");
+        std::fs::create_dir_all(source.join("runner_files")).unwrap();
+        std::fs::create_dir_all(&venue).unwrap();
+        std::fs::write(
+            source.join("wedding_invite.yml"),
+            "build_root: .\nbuild_files:\n  x86_64: Dockerfile.local\n  aarch64: Dockerfile.local\nrunner_files:\n  - runner_files/base.yml\n"
+        ).unwrap();
+        std::fs::write(source.join("Dockerfile.local"), "FROM scratch\n").unwrap();
+        std::fs::write(source.join("runner_files/base.yml"), "services: {}\n").unwrap();
+
+        let mut runner = test_runner();
+        runner.file_handle = Box::new(FileHandle {});
+        runner.seating_plan.venue = venue.to_string_lossy().to_string();
+        runner.seating_plan.attendees = vec![Dependency {
+            name: "auth".to_string(),
+            path: Some(source.to_string_lossy().to_string()),
+            link,
+            ..Default::default()
+        }];
+        (runner, source, venue)
+    }
+
+    #[test]
+    fn test_install_path_dependency_linked() {
+        let (runner, source, venue) = path_dependency_runner("linked_path", true);
+
+        let reports = runner.install_dependencies(1, false, &env::current_dir().unwrap());
+        assert_eq!(reports[0].errors, Vec::<String>::new());
+        assert!(reports[0].linked && !reports[0].copied && !reports[0].cloned && !reports[0].checked_out);
+        assert!(reports[0].build_file_prepared);
+        assert_eq!(std::fs::read_link(venue.join("auth")).unwrap(), source);
+        assert!(source.join("Dockerfile").is_file());
+        assert_eq!(
+            runner.get_compose_files(false).unwrap(),
+            vec![ComposeFileRef::new("auth", venue.join("auth/runner_files/base.yml").to_string_lossy().to_string())]
+        );
+
+        let reports = runner.install_dependencies(1, false, &env::current_dir().unwrap());
+        assert_eq!(reports[0].errors, Vec::<String>::new());
+        assert_eq!(std::fs::read_link(venue.join("auth")).unwrap(), source);
+
+        std::fs::remove_file(venue.join("auth")).unwrap();
+        std::fs::create_dir(venue.join("auth")).unwrap();
+        let reports = runner.install_dependencies(1, false, &env::current_dir().unwrap());
+        assert_eq!(reports[0].errors, vec![format!(
+            "auth is already in {} and is not a link, pass --force to replace it with a link to {}",
+            venue.to_string_lossy(), source.to_string_lossy()
+        )]);
+        assert!(runner.install_dependencies(1, true, &env::current_dir().unwrap())[0].errors.is_empty());
+        assert_eq!(std::fs::read_link(venue.join("auth")).unwrap(), source);
+    }
+
+    #[test]
+    fn test_install_path_dependency_copied() {
+        let (runner, source, venue) = path_dependency_runner("copied_path", false);
+
+        let reports = runner.install_dependencies(1, false, &env::current_dir().unwrap());
+        assert_eq!(reports[0].errors, Vec::<String>::new());
+        assert!(reports[0].copied && !reports[0].linked && reports[0].build_file_prepared);
+        assert!(std::fs::symlink_metadata(venue.join("auth")).unwrap().is_dir());
+        assert!(venue.join("auth/Dockerfile").is_file());
+        assert!(!source.join("Dockerfile").exists());
+        assert_eq!(std::fs::read_to_string(venue.join("auth/runner_files/base.yml")).unwrap(), "services: {}\n");
+        assert_eq!(
+            runner.get_compose_files(false).unwrap(),
+            vec![ComposeFileRef::new("auth", venue.join("auth/runner_files/base.yml").to_string_lossy().to_string())]
+        );
+
+        std::fs::write(source.join("runner_files/base.yml"), "services:\n  auth: {}\n").unwrap();
+        let reports = runner.install_dependencies(1, false, &env::current_dir().unwrap());
+        assert!(reports[0].errors.is_empty() && !reports[0].copied);
+        assert_eq!(std::fs::read_to_string(venue.join("auth/runner_files/base.yml")).unwrap(), "services: {}\n");
+
+        assert!(runner.install_dependencies(1, true, &env::current_dir().unwrap())[0].copied);
+        assert_eq!(std::fs::read_to_string(venue.join("auth/runner_files/base.yml")).unwrap(), "services:\n  auth: {}\n");
+    }
+
+    /// Builds a runner installing a path dependency from the ```tests``` directory into ```/work/venue``` with the
+    /// file handle passed in, its wedding invite is embedded so no file is read from the venue.
+    fn mocked_path_dependency_runner(file_handle: MockCoreFileHandle, link: bool) -> (Runner, PathBuf) {
+        let source = env::current_dir().unwrap().join("tests");
+        let mut runner = test_runner();
+        runner.file_handle = Box::new(file_handle);
+        runner.seating_plan.venue = "/work/venue".to_string();
+        runner.seating_plan.attendees = vec![Dependency {
+            name: "auth".to_string(),
+            path: Some(source.to_string_lossy().to_string()),
+            link,
+            invite: Some(serde_yaml::from_str("build_root: .\nrunner_files: []\n").unwrap()),
+            ..Default::default()
+        }];
+        (runner, source)
+    }
+
+    #[test]
+    fn test_install_path_dependency_replaces_stale_link() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_read_link()
+            .with(eq(Path::new("/work/venue/auth")))
+            .returning(|_| Ok(PathBuf::from("/old/auth")));
+        mock_handle.expect_exists_no_follow().with(eq(Path::new("/work/venue/auth"))).returning(|_| true);
+        mock_handle.expect_remove_directory().with(eq(Path::new("/work/venue/auth"))).times(1).returning(|_| Ok(()));
+        let source = env::current_dir().unwrap().join("tests");
+        mock_handle.expect_symlink()
+            .withf(move |target, link| target == source && link == Path::new("/work/venue/auth"))
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let (runner, _) = mocked_path_dependency_runner(mock_handle, true);
+
+        let reports = runner.install_dependencies(1, false, Path::new("/work"));
+        assert_eq!(reports[0].errors, Vec::<String>::new());
+        assert!(reports[0].linked);
+    }
+
+    #[test]
+    fn test_install_path_dependency_keeps_directory_without_force() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_read_link()
+            .returning(|_| Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "not a link")));
+        mock_handle.expect_exists_no_follow().returning(|_| true);
+        mock_handle.expect_remove_directory().times(0);
+        mock_handle.expect_symlink().times(0);
+        mock_handle.expect_copy_directory().times(0);
+        let (runner, source) = mocked_path_dependency_runner(mock_handle, true);
+
+        let reports = runner.install_dependencies(1, false, Path::new("/work"));
+        assert_eq!(reports[0].errors, vec![format!(
+            "auth is already in /work/venue and is not a link, pass --force to replace it with a link to {}",
+            source.to_string_lossy()
+        )]);
+    }
+
+    #[test]
+    fn test_install_path_dependency_keeps_copy_without_force() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_read_link()
+            .returning(|_| Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "not a link")));
+        mock_handle.expect_exists_no_follow().returning(|_| true);
+        mock_handle.expect_remove_directory().times(0);
+        mock_handle.expect_copy_directory().times(0);
+        let (runner, _) = mocked_path_dependency_runner(mock_handle, false);
+
+        let reports = runner.install_dependencies(1, false, Path::new("/work"));
+        assert!(reports[0].errors.is_empty() && !reports[0].copied && !reports[0].linked);
+    }
+
+    #[test]
+    fn test_install_dependencies_filtered() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .withf(|_, program, args| program == "git" && args.contains(&"https://github.com/yellow-bird-consult/valid_repo"))
+            .times(1)
+            .returning(|_, _, _| Ok(Output { status: ExitStatus::from_raw(128 << 8), stdout: Vec::new(), stderr: b"fatal: offline".to_vec() }));
+        let mut runner = test_runner_with(mock_runner);
+        runner.seating_plan.venue = "./tests/missing_venue".to_string();
+        runner.seating_plan.attendees.push(Dependency {
+            name: "valid_repo".to_string(),
+            url: "https://github.com/yellow-bird-consult/valid_repo".to_string(),
+            ..Default::default()
+        });
+        let runner = runner.with_filter(AttendeeFilter::Only(vec!["valid_repo".to_string()])).unwrap();
+
+        let reports = runner.install_dependencies(1, false, &env::current_dir().unwrap());
+        assert_eq!(reports.iter().map(|report| report.name.as_str()).collect::<Vec<_>>(), vec!["valid_repo"]);
+    }
+
+    #[test]
+    fn test_update_installed_skips_missing() {
+        let mut runner = test_runner();
+        runner.seating_plan.venue = "./tests/missing_venue".to_string();
+
+        let reports = runner.update_installed(2, false, &env::current_dir().unwrap());
+        assert_eq!(reports.len(), 1);
+        assert!(!reports[0].cloned);
+        assert_eq!(reports[0].errors, vec!["test_repo is not cloned into ./tests/missing_venue, run wedp install first".to_string()]);
+    }
+
+    /// Builds a runner for updating ```tests/test_repo``` where ```git status``` lists the changes passed in.
+    fn local_changes_runner(changes: &'static str) -> MockCoreRunner {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .withf(|dir, _, args| dir.ends_with("tests/test_repo") && args == ["status", "--porcelain"])
+            .times(1)
+            .returning(move |_, _, _| Ok(Output { status: ExitStatus::from_raw(0), stdout: changes.as_bytes().to_vec(), stderr: Vec::new() }));
+        mock_runner
+    }
+
+    #[test]
+    fn test_update_installed_skips_local_changes() {
+        let runner = test_runner_with(local_changes_runner(" M build/Dockerfile.x86_64\n?? notes.txt\n"));
+
+        let reports = runner.update_installed(1, false, &env::current_dir().unwrap());
+
+        assert!(reports[0].skipped);
+        assert!(!reports[0].fetched && reports[0].errors.is_empty());
+        assert_eq!(render_update_summary(&reports), "test_repo: skipped, the working tree has local changes\n0 of 1 dependencies updated\n");
+    }
+
+    #[test]
+    fn test_update_installed_force_stashes_local_changes() {
+        let mut mock_runner = local_changes_runner("?? notes.txt\n");
+        mock_runner.expect_run_in_dir()
+            .withf(|_, _, args| args == ["stash", "push", "--include-untracked", "--message", "wedp update"])
+            .times(1)
+            .returning(|_, _, _| Ok(Output { status: ExitStatus::from_raw(0), stdout: Vec::new(), stderr: Vec::new() }));
+        mock_runner.expect_run_in_dir()
+            .withf(|_, _, args| args == ["checkout", "master"])
+            .times(1)
+            .returning(|_, _, _| Ok(Output { status: ExitStatus::from_raw(0), stdout: Vec::new(), stderr: Vec::new() }));
+        let commits = AtomicUsize::new(0);
+        mock_runner.expect_run_in_dir()
+            .withf(|_, _, args| args == ["rev-parse", "HEAD"])
+            .times(2)
+            .returning(move |_, _, _| {
+                let commit = ["1111111aaaa", "2222222bbbb"][commits.fetch_add(1, Ordering::SeqCst)];
+                Ok(Output { status: ExitStatus::from_raw(0), stdout: format!("{}\n", commit).into_bytes(), stderr: Vec::new() })
+            });
+        mock_runner.expect_run_in_dir()
+            .withf(|_, _, args| args[0] == "remote" || args[0] == "fetch" || args == ["pull", "--ff-only"])
+            .times(3)
+            .returning(|_, _, _| Ok(Output { status: ExitStatus::from_raw(0), stdout: Vec::new(), stderr: Vec::new() }));
+        let mut runner = test_runner_with(mock_runner);
+        runner.file_handle = Box::new(DryRunFileHandle::new(Box::new(SharedOutput::default())));
+
+        let reports = runner.update_installed(1, true, &env::current_dir().unwrap());
+
+        assert!(reports[0].stashed && reports[0].pulled, "{:?}", reports[0]);
+        assert_eq!(reports[0].old_commit, Some("1111111aaaa".to_string()));
+        assert_eq!(reports[0].new_commit, Some("2222222bbbb".to_string()));
+        assert_eq!(render_update_summary(&reports), "test_repo: 1111111 -> 2222222, local changes stashed\n1 of 1 dependencies updated\n");
+    }
+
+    #[test]
+    fn test_render_update_summary() {
+        let mut current = InstallReport::new("auth");
+        current.old_commit = Some("abcdef0123".to_string());
+        current.new_commit = Some("abcdef0123".to_string());
+        let mut failed = InstallReport::new("billing");
+        failed.errors.push("Failed to pull master for billing: diverged".to_string());
+        let linked = InstallReport::new("search");
+
+        assert_eq!(
+            render_update_summary(&[current, failed, linked]),
+            "auth: up to date at abcdef0\nbilling: failed\nsearch: updated\n2 of 3 dependencies updated\n"
+        );
+    }
+
+    fn probe_runner(command: &'static str, code: i32) -> MockCoreRunner {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run()
+            .with(eq(format!("{} version", command)))
+            .times(1)
+            .returning(move |_| Ok(Output { status: ExitStatus::from_raw(code << 8), stdout: Vec::new(), stderr: Vec::new() }));
+        mock_runner
+    }
+
+    fn ls_remote(mock_runner: &mut MockCoreRunner, expected: &'static [&'static str], status: i32, stdout: &'static str) {
+        mock_runner.expect_run_in_dir()
+            .withf(move |_, program, args| program == "git" && args == expected)
+            .times(1)
+            .returning(move |_, _, _| Ok(Output { status: ExitStatus::from_raw(status << 8), stdout: stdout.as_bytes().to_vec(), stderr: Vec::new() }));
+    }
+
+    #[test]
+    fn test_create_venue_fails() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_create_directory_if_not_exists()
+            .returning(|_| Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "Permission denied")));
+        let mut runner = test_runner();
+        runner.file_handle = Box::new(mock_handle);
+
+        let error = runner.create_venue().unwrap_err();
+        assert_eq!(error.exit_code(), crate::error::EXIT_IO);
+        assert_eq!(error.to_string(), format!("Could not open file: Permission denied for {}", runner.seating_plan.venue));
+    }
+
+    #[test]
+    fn test_verify_refs() {
+        let mut runner = test_runner();
+        runner.seating_plan.attendees.push(Dependency {
+            name: "pinned".to_string(),
+            url: "https://github.com/org/pinned".to_string(),
+            rev: Some("v1.4.0".to_string()),
+            ..Default::default()
+        });
+        runner.seating_plan.attendees.push(Dependency {
+            name: "auth".to_string(),
+            url: "https://github.com/org/auth".to_string(),
+            branch: Some("mian".to_string()),
+            ..Default::default()
+        });
+        let mut mock_runner = MockCoreRunner::new();
+        ls_remote(&mut mock_runner, &["ls-remote", "--heads", "--", "https://github.com/yellow-bird-consult/wedding_planner", "master"], 0,
+                  "1111111111111111111111111111111111111111\trefs/heads/master\n");
+        ls_remote(&mut mock_runner, &["ls-remote", "--heads", "--", "https://github.com/org/auth", "mian"], 0, "");
+        ls_remote(&mut mock_runner, &["ls-remote", "--heads", "--", "https://github.com/org/auth"], 0,
+                  "1111111111111111111111111111111111111111\trefs/heads/main\n2222222222222222222222222222222222222222\trefs/heads/development\n");
+        runner.probe_runner = Box::new(mock_runner);
+
+        assert_eq!(runner.verify_refs(), vec!["auth: branch mian does not exist on https://github.com/org/auth, did you mean main?".to_string()]);
+    }
+
+    #[test]
+    fn test_verify_refs_lists_the_clone_url_with_the_token() {
+        std::env::set_var("WEDP_TEST_VERIFY_TOKEN", "verify-secret");
+        let mut runner = test_runner();
+        runner.seating_plan.attendees = vec![
+            Dependency {
+                name: "private".to_string(),
+                url: "git@github.com:org/private.git".to_string(),
+                branch: Some("main".to_string()),
+                auth: Some(crate::git_auth::GitAuth { token_env: "WEDP_TEST_VERIFY_TOKEN".to_string() }),
+                git_protocol: Some(crate::git_auth::GitProtocol::Https),
+                ..Default::default()
+            }
+        ];
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir_with_env()
+            .withf(|_, program, args, env| {
+                program == "git" && args == ["ls-remote", "--heads", "--", "https://github.com/org/private.git", "main"]
+                    && env.iter().any(|(name, value)| name.starts_with("GIT_CONFIG_KEY_") && value == "http.https://github.com/org/private.git.extraHeader")
+            })
+            .times(1)
+            .returning(|_, _, _, _| Ok(Output {
+                status: ExitStatus::from_raw(0),
+                stdout: b"1111111111111111111111111111111111111111\trefs/heads/main\n".to_vec(),
+                stderr: Vec::new()
+            }));
+        runner.probe_runner = Box::new(mock_runner);
+
+        assert_eq!(runner.verify_refs(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_verify_refs_unreachable_is_a_warning() {
+        let mut runner = test_runner();
+        let mut mock_runner = MockCoreRunner::new();
+        ls_remote(&mut mock_runner, &["ls-remote", "--heads", "--", "https://github.com/yellow-bird-consult/wedding_planner", "master"], 128, "");
+        runner.probe_runner = Box::new(mock_runner);
+
+        assert_eq!(runner.verify_refs(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_compose_command_probed_once() {
+        let mut runner = test_runner();
+        runner.probe_runner = Box::new(probe_runner("docker compose", 0));
+        runner.compose = OnceLock::new();
+
+        if env::var(COMPOSE_ENV).is_err() {
+            assert_eq!(
+                runner.get_compose_file_command(false).unwrap().to_string(),
+                "docker compose -f tests/test_repo/runner_files/base.yml -f tests/test_repo/runner_files/database.yml"
+            );
+            assert_eq!(runner.compose_command(), "docker compose");
+        }
+    }
+
+    #[test]
+    fn test_compose_command_from_seating_plan() {
+        let mut runner = test_runner();
+        runner.compose = OnceLock::new();
+        runner.seating_plan.compose_command = Some("podman-compose".to_string());
+
+        if env::var(COMPOSE_ENV).is_err() {
+            assert_eq!(runner.compose_command(), "podman-compose");
+        }
+    }
+
+    #[test]
+    fn test_compose_command_engine_matrix() {
+        let cases = [
+            (ContainerEngine::Docker, MockCoreRunner::new(), "docker-compose -f "),
+            (ContainerEngine::DockerComposeV2, MockCoreRunner::new(), "docker compose -f "),
+            (ContainerEngine::Podman, probe_runner("podman compose", 0), "podman compose -f "),
+            (ContainerEngine::Podman, probe_runner("podman compose", 127), "podman-compose -f "),
+        ];
+        if env::var(COMPOSE_ENV).is_ok() {
+            return
+        }
+        for (engine, mock_runner, prefix) in cases {
+            let mut from_plan = test_runner();
+            from_plan.probe_runner = Box::new(mock_runner);
+            from_plan.seating_plan.engine = Some(engine);
+            from_plan.compose = OnceLock::new();
+            let command = from_plan.get_compose_file_command(false).unwrap().to_string();
+            assert!(command.starts_with(prefix), "{:?}: {}", engine, command);
+        }
+    }
+
+    #[test]
+    fn test_compose_command_engine_flag_overrides_seating_plan() {
+        let mut runner = test_runner();
+        runner.seating_plan.compose_command = Some("docker-compose".to_string());
+        runner.seating_plan.engine = Some(ContainerEngine::Docker);
+        runner.probe_runner = Box::new(probe_runner("podman compose", 0));
+        let runner = runner.with_engine(Some(ContainerEngine::Podman));
+
+        if env::var(COMPOSE_ENV).is_err() {
+            assert_eq!(runner.compose_command(), "podman compose");
+            assert!(runner.get_compose_file_command(false).unwrap().to_string().starts_with("podman compose -f "));
+        }
+    }
+
+    #[test]
+    fn test_compose_command_flag_overrides_everything() {
+        let mut runner = test_runner();
+        runner.seating_plan.compose_command = Some("podman-compose".to_string());
+        let runner = runner.with_engine(Some(ContainerEngine::Podman))
+                           .with_compose_command(Some("docker compose".to_string()));
+
+        assert_eq!(runner.compose_command(), "docker compose");
+        assert_eq!(
+            runner.get_compose_file_command(false).unwrap().to_string(),
+            "docker compose -f tests/test_repo/runner_files/base.yml -f tests/test_repo/runner_files/database.yml"
+        );
+        assert_eq!(test_runner().with_compose_command(None).compose_command(), "docker-compose");
+    }
+
+    #[test]
+    fn test_get_compose_file_command_remote_skips_missing_remote_files() {
+        let runner = test_runner();
+        assert_eq!(runner.get_compose_file_command(true).unwrap().to_string(), "docker-compose");
+    }
+
+    /// Builds a file handle that keeps the run state in memory so it can be read back after being written.
+    fn run_state_handle() -> MockCoreFileHandle {
+        let run_state = Arc::new(std::sync::Mutex::new(None::<String>));
+        let written = run_state.clone();
+        let mut mock_handle = existing_files_handle();
+        mock_handle.expect_create_directory_if_not_exists()
+            .with(eq(PathBuf::from("tests/.wedp")))
+            .returning(|_| Ok(()));
+        mock_handle.expect_write()
+            .withf(|path, _| path == Path::new("tests/.wedp/run.json"))
+            .returning(move |_, contents| {
+                *written.lock().unwrap() = Some(contents.to_string());
+                Ok(())
+            });
+        mock_handle.expect_read_to_string()
+            .with(eq(PathBuf::from("tests/.wedp/run.json")))
+            .returning(move |_| run_state.lock().unwrap().clone().ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotFound)));
+        mock_handle
+    }
+
+    fn open_url_runner(mock_runner: MockCoreRunner) -> Runner {
+        let mut runner = test_runner_with(mock_runner);
+        runner.file_handle = Box::new(run_state_handle());
+        runner.seating_plan.attendees[0].open_url = Some("http://localhost:{host_port}/health".to_string());
+        runner.seating_plan.attendees[0].open_port = Some("web:8080".to_string());
+        runner
+    }
+
+    #[test]
+    fn test_run_dependencies_background_opens_url() {
+        let compose = "docker-compose -f tests/test_repo/runner_files/base.yml -f tests/test_repo/runner_files/database.yml";
+        let mut sequence = mockall::Sequence::new();
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_docker_command()
+            .with(eq(" up -d --wait"), always(), always())
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _, _| Ok(ExitStatus::from_raw(0)));
+        mock_runner.expect_run_docker_command()
+            .with(eq(" ps"), always(), always())
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _, _| Ok(ExitStatus::from_raw(0)));
+        mock_runner.expect_run_in_dir()
+            .withf(move |_, program, args| format!("{} {}", program, args.join(" ")) == format!("{} port web 8080", compose))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _, _| Ok(Output {
+                status: ExitStatus::from_raw(0),
+                stdout: b"0.0.0.0:49153\n".to_vec(),
+                stderr: Vec::new(),
+            }));
+        mock_runner.expect_run()
+            .with(eq(launcher_command("http://localhost:49153/health")))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(Output {
+                status: ExitStatus::from_raw(0),
+                stdout: Vec::new(),
+                stderr: Vec::new(),
+            }));
+        let runner = open_url_runner(mock_runner);
+
+        runner.run_dependencies_background(true, &env::current_dir().unwrap());
+    }
+
+    #[test]
+    fn test_run_dependencies_background_skips_url_without_healthy_wait() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_docker_command()
+            .with(eq(" up -d"), always(), always())
+            .times(1)
+            .returning(|_, _, _| Ok(ExitStatus::from_raw(0)));
+        mock_runner.expect_run_docker_command()
+            .with(eq(" ps"), always(), always())
+            .times(1)
+            .returning(|_, _, _| Ok(ExitStatus::from_raw(0)));
+        mock_runner.expect_run_docker_command()
+            .with(eq(" up -d --wait"), always(), always())
+            .times(1)
+            .returning(|_, _, _| Ok(ExitStatus::from_raw(1 << 8)));
+        mock_runner.expect_run().times(0);
+        let runner = open_url_runner(mock_runner);
+
+        runner.run_dependencies_background(false, &env::current_dir().unwrap());
+        runner.run_dependencies_background(true, &env::current_dir().unwrap());
+    }
+
+    #[test]
+    fn test_wait_for_health_http() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            use std::io::{Read, Write};
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0; 1024]);
+            stream.write_all(b"HTTP/1.1 204 No Content\r\n\r\n").unwrap();
+        });
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .withf(|dir, program, args| {
+                dir == Path::new(".") && program == "docker-compose"
+                    && args == ["-f", "tests/test_repo/runner_files/base.yml", "-f", "tests/test_repo/runner_files/database.yml", "port", "web", "8080"]
+            })
+            .times(1)
+            .returning(move |_, _, _| Ok(Output {
+                status: ExitStatus::from_raw(0),
+                stdout: format!("0.0.0.0:{}\n", port).into_bytes(),
+                stderr: Vec::new(),
+            }));
+        let mut runner = test_runner_with(mock_runner);
+        runner.seating_plan.attendees[0].open_port = Some("web:8080".to_string());
+        runner.seating_plan.attendees[0].health_http = Some(HealthHttp {
+            url: "http://127.0.0.1:{host_port}/health".to_string(),
+            expect_status: Some(204),
+            timeout: Some(5)
+        });
+
+        assert!(runner.wait_for_health_http(false, &SystemClock));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_wait_for_health_http_not_published() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .times(1)
+            .returning(|_, _, _| Ok(Output { status: ExitStatus::from_raw(0), stdout: Vec::new(), stderr: Vec::new() }));
+        let mut runner = test_runner_with(mock_runner);
+        runner.seating_plan.attendees[0].open_port = Some("web:8080".to_string());
+        runner.seating_plan.attendees[0].health_http = Some(HealthHttp {
+            url: "http://localhost:{host_port}/health".to_string(),
+            ..Default::default()
+        });
+
+        assert!(!runner.wait_for_health_http(false, &SystemClock));
+    }
+
+    #[test]
+    fn test_logs() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_docker_command()
+            .with(eq(" logs -f --tail=100"), eq("failed to get logs"), always())
+            .times(1)
+            .returning(|_, _, _| Ok(ExitStatus::from_raw(2)));
+        mock_runner.expect_run_docker_command()
+            .with(eq(" logs test_runner"), eq("failed to get logs"), always())
+            .times(1)
+            .returning(|_, _, _| Ok(ExitStatus::from_raw(1 << 8)));
+        let mut runner = test_runner_with(mock_runner);
+        runner.file_handle = Box::new(run_state_handle());
+
+        assert_eq!(runner.logs(false, true, None, &[]), Ok(0));
+        assert_eq!(runner.logs(false, false, None, &["test_runner".to_string()]), Ok(1));
+        assert_eq!(runner.logs(false, true, None, &["$(id)".to_string()]), Err("$(id) is not a valid service name".to_string()));
+    }
+
+    #[test]
+    fn test_run_background_then_logs() {
+        let compose = "docker-compose -f tests/test_repo/runner_files/base.yml -f tests/test_repo/runner_files/database.yml";
+        let mut sequence = mockall::Sequence::new();
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_docker_command()
+            .withf(move |command, _, program_args| command == " up -d" && program_args.to_string() == compose)
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _, _| Ok(ExitStatus::from_raw(0)));
+        mock_runner.expect_run_docker_command()
+            .with(eq(" ps"), always(), always())
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _, _| Ok(ExitStatus::from_raw(0)));
+        mock_runner.expect_run_docker_command()
+            .withf(move |command, error_message, program_args| {
+                command == " logs -f --tail=100" && error_message == "failed to get logs" && program_args.to_string() == compose
+            })
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _, _| Ok(ExitStatus::from_raw(2)));
+        let mut runner = test_runner_with(mock_runner);
+        runner.file_handle = Box::new(run_state_handle());
+
+        assert!(runner.run_dependencies_background(false, &env::current_dir().unwrap()));
+        // the logs follow the files the dependencies were started with even if the seating plan changes
+        runner.seating_plan.attendees.push(Dependency { name: "valid_repo".to_string(), ..Default::default() });
+        assert_eq!(runner.follow_started_logs(false), 0);
+    }
+
+    #[test]
+    fn test_run_background_failed_records_nothing() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_docker_command()
+            .with(eq(" up -d"), always(), always())
+            .times(1)
+            .returning(|_, _, _| Ok(ExitStatus::from_raw(1 << 8)));
+        let mut mock_handle = existing_files_handle();
+        mock_handle.expect_write().times(0);
+        let mut runner = test_runner_with(mock_runner);
+        runner.file_handle = Box::new(mock_handle);
+
+        assert!(!runner.run_dependencies_background(false, &env::current_dir().unwrap()));
+    }
+
+    #[test]
+    fn test_logs_exit_code() {
+        assert_eq!(logs_exit_code(ExitStatus::from_raw(3 << 8), true), 3);
+        assert_eq!(logs_exit_code(ExitStatus::from_raw(2), true), 0);
+        assert_eq!(logs_exit_code(ExitStatus::from_raw(9), false), 1);
+    }
+
+    #[test]
+    fn test_run_dependencies_abort_on_exit() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_docker_command()
+            .with(eq(" up --abort-on-container-exit --exit-code-from test_runner"), always(), always())
+            .times(1)
+            .returning(|_, _, _| Ok(ExitStatus::from_raw(3 << 8)));
+        let runner = test_runner_with(mock_runner);
+
+        assert_eq!(runner.run_dependencies_abort_on_exit("test_runner", &env::current_dir().unwrap()), Ok(3));
+    }
+
+    #[test]
+    fn test_run_dependencies_restart_policy() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_docker_command()
+            .withf(|command, _, program_args| {
+                command == " up" && program_args.arg_refs().ends_with(&["-f", "tests/wedp_restart_override.yml"])
+            })
+            .times(1)
+            .returning(|_, _, _| Ok(ExitStatus::from_raw(0)));
+        let mut mock_handle = existing_files_handle();
+        mock_handle.expect_read_to_string()
+            .returning(|_| Ok("services:\n  test_runner:\n    image: test_runner\n".to_string()));
+        mock_handle.expect_write()
+            .with(eq(PathBuf::from("tests/wedp_restart_override.yml")), eq("services:\n  test_runner:\n    restart: always\n"))
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let mut runner = test_runner_with(mock_runner);
+        runner.file_handle = Box::new(mock_handle);
+        runner.seating_plan.restart_policy = Some("always".to_string());
+
+        runner.run_dependencies(&env::current_dir().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_bump_branch() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_read_to_string()
+            .with(eq(PathBuf::from("plan.yml")))
+            .returning(|_| Ok("attendees:\n  - name: test_repo\n    url: https://example.com/test_repo\n    branch: master\nvenue: ./tests\n".to_string()));
+        mock_handle.expect_write()
+            .with(
+                eq(PathBuf::from("plan.yml")),
+                eq("attendees:\n  - name: test_repo\n    url: https://example.com/test_repo\n    branch: release/1.4\nvenue: ./tests\n")
+            )
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let mut runner = test_runner();
+        runner.file_handle = Box::new(mock_handle);
+
+        assert_eq!(runner.bump_branch("plan.yml", "master", "release/1.4", &[]), Ok(vec!["test_repo".to_string()]));
+        assert_eq!(runner.seating_plan.attendees[0].branch, Some("release/1.4".to_string()));
+        assert_eq!(runner.bump_branch("plan.yml", "develop", "release/1.4", &[]), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn test_run_dependencies_failed() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_docker_command()
+            .with(eq(" up"), always(), always())
+            .times(1)
+            .returning(|_, _, _| Ok(ExitStatus::from_raw(1 << 8)));
+        mock_runner.expect_run_docker_command()
+            .with(eq(" build --pull"), always(), always())
+            .times(1)
+            .returning(|_, _, _| Err(std::io::Error::from(std::io::ErrorKind::NotFound)));
+        let runner = test_runner_with(mock_runner);
+
+        let error = runner.run_dependencies(&env::current_dir().unwrap()).unwrap_err();
+        assert!(matches!(&error, WedpError::DockerCommand { command, .. } if command.ends_with(" up")), "{:?}", error);
+        assert_eq!(error.to_string(), format!("failed to run: {} up exited with exit status: 1", runner.get_compose_file_command(false).unwrap()));
+        let error = runner.build_dependencies(true, false).unwrap_err();
+        assert!(matches!(&error, WedpError::DockerCommand { reason, .. } if reason == "entity not found"), "{:?}", error);
+    }
+
+    #[test]
+    fn test_run_dependencies_other_location() {
+        let mut probe = MockCoreRunner::new();
+        probe.expect_run()
+            .with(eq(render_locations_command("docker")))
+            .returning(|_| Ok(Output {
+                status: ExitStatus::from_raw(0),
+                stdout: b"runner_files\t/old/checkout/venue/test_repo/runner_files\t/old/checkout/venue/test_repo/runner_files/base.yml\n".to_vec(),
+                stderr: Vec::new(),
+            }));
+        let mut mock_handle = existing_files_handle();
+        mock_handle.expect_read_to_string().returning(|_| Ok("services: {}\n".to_string()));
+        let mut runner = test_runner_with(MockCoreRunner::new());
+        runner.probe_runner = Box::new(probe);
+        runner.file_handle = Box::new(mock_handle);
+
+        let error = runner.run_dependencies(&env::current_dir().unwrap()).unwrap_err().to_string();
+        assert!(error.starts_with("The compose project runner_files is already running from /old/checkout/venue/test_repo/runner_files\n"), "{}", error);
+        assert!(!runner.run_dependencies_background(false, &env::current_dir().unwrap()));
+
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_docker_command()
+            .with(eq(" up --force-recreate --remove-orphans"), always(), always())
+            .times(1)
+            .returning(|_, _, _| Ok(ExitStatus::from_raw(0)));
+        runner.command_runner = Box::new(mock_runner);
+        let runner = runner.with_force_recreate(true);
+        runner.run_dependencies(&env::current_dir().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_run_dependencies_location_resolved_against_cwd() {
+        let mut probe = MockCoreRunner::new();
+        probe.expect_run()
+            .with(eq(render_locations_command("docker")))
+            .returning(|_| Ok(Output {
+                status: ExitStatus::from_raw(0),
+                stdout: b"runner_files\t/srv/plan/tests/test_repo/runner_files\t/srv/plan/tests/test_repo/runner_files/base.yml\n".to_vec(),
+                stderr: Vec::new(),
+            }));
+        let mut mock_handle = existing_files_handle();
+        mock_handle.expect_read_to_string().returning(|_| Ok("services: {}\n".to_string()));
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_docker_command()
+            .with(eq(" up"), always(), always())
+            .times(1)
+            .returning(|_, _, _| Ok(ExitStatus::from_raw(0)));
+        let mut runner = test_runner_with(mock_runner);
+        runner.probe_runner = Box::new(probe);
+        runner.file_handle = Box::new(mock_handle);
+
+        runner.run_dependencies(Path::new("/srv/plan")).unwrap();
+        let error = runner.run_dependencies(Path::new("/home/dev/plan")).unwrap_err().to_string();
+        assert!(error.starts_with("The compose project runner_files is already running from /srv/plan/"), "{}", error);
+    }
+
+    #[test]
+    fn test_run_dependencies_abort_on_exit_invalid_service() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_docker_command().times(0);
+        let runner = test_runner_with(mock_runner);
+
+        assert_eq!(
+            runner.run_dependencies_abort_on_exit("tests --rm", &env::current_dir().unwrap()),
+            Err("tests --rm is not a valid service name".to_string())
+        );
+    }
+
+    #[test]
+    fn test_install_dependencies_records_clone_failure() {
+        let mut command_runner = MockCoreRunner::new();
+        command_runner.expect_run_in_dir()
+            .times(1)
+            .returning(|_, _, _| {
+                Ok(Output {
+                    status: ExitStatus::from_raw(128 << 8),
+                    stdout: Vec::new(),
+                    stderr: b"fatal: Authentication failed".to_vec(),
+                })
+            });
+        let mut runner = test_runner_with(command_runner);
+        runner.seating_plan.venue = "./tests/missing_venue".to_string();
+        runner.seating_plan.attendees[0].name = "missing_repo".to_string();
+
+        let reports = runner.install_dependencies(1, false, &env::current_dir().unwrap());
+        assert_eq!(reports.len(), 1);
+        assert!(!reports[0].cloned);
+        assert!(!reports[0].checked_out);
+        let error = check_install_reports(&reports).unwrap_err();
+        assert!(error.starts_with("Failed to clone repo for missing_repo"));
+        assert!(error.contains("fatal: Authentication failed"));
+    }
+
+    #[test]
+    fn test_install_from_archive_extracts_into_venue() {
+        let cwd = env::current_dir().unwrap().to_string_lossy().to_string();
+        let mut command_runner = MockCoreRunner::new();
+        for name in ["auth", "billing"] {
+            command_runner.expect_run()
+                .with(eq(format!(
+                    "mkdir -p {cwd}/tests/archive_venue && tar -xzf {cwd}/vendor.tar.gz -C {cwd}/tests/archive_venue {name}"
+                )))
+                .times(1)
+                .returning(|_| Ok(Output { status: ExitStatus::from_raw(0), stdout: Vec::new(), stderr: Vec::new() }));
+        }
+        let mut runner = test_runner_with(command_runner);
+        runner.seating_plan.venue = "./tests/archive_venue".to_string();
+        runner.seating_plan.attendees[0].name = "auth".to_string();
+        runner.seating_plan.attendees.push(Dependency { name: "billing".to_string(), ..Default::default() });
+
+        let reports = runner.install_from_archive("vendor.tar.gz", 1, false, Path::new(&cwd));
+        assert_eq!(reports.iter().map(|report| report.name.as_str()).collect::<Vec<_>>(), vec!["auth", "billing"]);
+        assert!(reports.iter().all(|report| report.extracted && !report.cloned && !report.checked_out));
+    }
+
+    #[test]
+    fn test_install_from_archive_keeps_installed_without_force() {
+        let mut command_runner = MockCoreRunner::new();
+        command_runner.expect_run().times(0);
+        let runner = test_runner_with(command_runner);
+
+        let reports = runner.install_from_archive("vendor.tar.gz", 1, false, &env::current_dir().unwrap());
+        assert_eq!(
+            check_install_reports(&reports),
+            Err("test_repo is already installed, pass --force to replace it with the archive".to_string())
+        );
+    }
+
+    /// A command runner that sleeps on every command, records the most commands running at the same time, 
+    /// and fails to clone ```billing```. The mocked runner is not used as it runs one call at a time.
+    struct SlowRunner {
+        in_flight: AtomicUsize,
+        max_in_flight: Arc<AtomicUsize>,
+    }
+
+    impl CoreRunner for SlowRunner {
+        fn run(&self, command: &String) -> Result<Output, std::io::Error> {
+            let running = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(running, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(50));
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            let failed = command.ends_with("billing.git");
+            Ok(Output {
+                status: ExitStatus::from_raw(if failed { 128 << 8 } else { 0 }),
+                stdout: Vec::new(),
+                stderr: if failed { b"fatal: repository not found".to_vec() } else { Vec::new() },
+            })
+        }
+
+        fn run_in_dir(&self, dir: &Path, program: &str, args: &[&str]) -> Result<Output, std::io::Error> {
+            self.run(&render_in_dir(dir, program, args))
+        }
+
+        fn run_in_dir_with_env(&self, dir: &Path, program: &str, args: &[&str], _env: &[(String, String)]) -> Result<Output, std::io::Error> {
+            self.run_in_dir(dir, program, args)
+        }
+
+        fn run_docker_command(&self, _command: &str, _error_message: &str, _program_args: &ProgramArgs) -> Result<ExitStatus, std::io::Error> {
+            unimplemented!()
+        }
+
+        fn run_interactive(&self, _command: &str) -> Result<ExitStatus, std::io::Error> {
+            unimplemented!()
+        }
+    }
+
+    /// Builds a runner with six dependencies that are not cloned using the ```SlowRunner```.
+    fn parallel_install_runner(max_in_flight: Arc<AtomicUsize>) -> Runner {
+        let mut runner = test_runner();
+        runner.command_runner = Box::new(SlowRunner { in_flight: AtomicUsize::new(0), max_in_flight });
+        runner.seating_plan.venue = "./tests/missing_venue".to_string();
+        runner.seating_plan.attendees = ["auth", "billing", "orders", "search", "emails", "gateway"].iter()
+            .map(|name| Dependency {
+                name: name.to_string(),
+                url: format!("https://github.com/yellow-bird-consult/{}.git", name),
+                branch: Some("main".to_string()),
+                ..Default::default()
+            })
+            .collect();
+        runner
+    }
+
+    #[test]
+    fn test_install_dependencies_in_parallel() {
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let runner = parallel_install_runner(max_in_flight.clone());
+
+        let reports = runner.install_dependencies(3, false, &env::current_dir().unwrap());
+
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 3);
+        let names: Vec<&str> = reports.iter().map(|report| report.name.as_str()).collect();
+        assert_eq!(names, vec!["auth", "billing", "orders", "search", "emails", "gateway"]);
+        for report in &reports {
+            assert_eq!(report.cloned, report.name != "billing");
+            assert_eq!(report.checked_out, report.name != "billing");
+        }
+        let error = check_install_reports(&reports).unwrap_err();
+        assert!(error.contains("Failed to clone repo for billing"));
+        assert!(error.contains("fatal: repository not found"));
+    }
+
+    #[test]
+    fn test_install_dependencies_one_job() {
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let runner = parallel_install_runner(max_in_flight.clone());
+
+        let reports = runner.install_dependencies(1, false, &env::current_dir().unwrap());
+
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 1);
+        assert_eq!(reports.len(), 6);
+    }
+
+    #[test]
+    fn test_render_install_summary() {
+        let mut installed = InstallReport::new("auth");
+        installed.cloned = true;
+        installed.checked_out = true;
+        installed.build_file_prepared = true;
+        let mut failed = InstallReport::new("billing");
+        failed.fetched = true;
+        failed.fail(InstallStep::Checkout, "Failed to checkout billing as branch main: pathspec 'main' did not match".to_string());
+        failed.fail(InstallStep::BuildFile, "Failed to prepare build file for billing: not found".to_string());
+        let mut linked = InstallReport::new("search");
+        linked.linked = true;
+
+        assert_eq!(
+            render_install_summary(&[installed, failed, linked]),
+            "NAME                 SOURCE     CHECKOUT   BUILD FILE   INIT BUILD FILE  RESULT\n\
+             auth                 cloned     yes        yes          -                installed\n\
+             billing              fetched    -          -            -                failed\n\
+             search               linked     -          -            -                installed\n\
+             2 installed, 1 failed (billing: checkout error)\n"
+        );
+        assert_eq!(render_install_summary(&[InstallReport::new("auth")]).lines().last(), Some("1 installed"));
+    }
+
+    #[test]
+    fn test_install_report_failed_step() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .returning(|_, _, _| Ok(Output { status: ExitStatus::from_raw(128 << 8), stdout: Vec::new(), stderr: b"fatal: repository not found".to_vec() }));
+        let mut runner = test_runner_with(mock_runner);
+        runner.seating_plan.venue = "./tests/missing_venue".to_string();
+
+        let reports = runner.install_dependencies(1, false, &env::current_dir().unwrap());
+
+        assert_eq!(reports[0].failed_step, Some(InstallStep::Clone));
+        assert!(!reports[0].cloned && !reports[0].checked_out);
+        assert!(check_install_reports(&reports).unwrap_err().contains("fatal: repository not found"));
+        assert!(render_install_summary(&reports).ends_with("0 installed, 1 failed (test_repo: clone error)\n"));
+    }
+
+    #[test]
+    fn test_prepare_build_files_with_arch() {
+        let venue = env::current_dir().unwrap().join("tests").to_string_lossy().to_string();
+        let for_arch = |arch: &'static str| {
+            let mut mock_handle = MockCoreFileHandle::new();
+            mock_handle.expect_copy()
+                .with(
+                    eq(PathBuf::from(format!("{}/valid_repo/build/Dockerfile.{}", venue, arch))),
+                    eq(PathBuf::from(format!("{}/valid_repo/Dockerfile", venue)))
+                )
+                .times(1)
+                .returning(|_, _| Ok(0));
+            let mut runner = test_runner().with_arch(Some(arch.to_string()));
+            runner.seating_plan.attendees[0].name = "valid_repo".to_string();
+            assert_eq!(runner.prepare_build_files(&mock_handle, &env::current_dir().unwrap()), Ok(()));
+            mock_handle.checkpoint();
+        };
+
+        for_arch("aarch64");
+        for_arch("x86_64");
+    }
+
+    #[test]
+    fn test_prepare_builds_init_build_without_build_files() {
+        let wedding_invite = WeddingInvite::from_file("./tests/init_only_repo/wedding_invite.yml".to_string()).unwrap();
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_copy()
+            .with(
+                eq(Path::new("tests/init_only_repo/database/build/Dockerfile.init")), 
+                eq(Path::new("tests/init_only_repo/database/Dockerfile"))
+            )
+            .times(1)
+            .returning(|_, _| Ok(0));
+        let mut report = InstallReport::new("init_only_repo");
+
+        prepare_builds(&wedding_invite, "./tests", None, &mock_handle, &mut report);
+
+        assert_eq!(report, InstallReport {
+            name: "init_only_repo".to_string(),
+            init_build_file_prepared: true,
+            ..Default::default()
+        });
+        mock_handle.checkpoint();
+    }
+
+    #[test]
+    fn test_find_seating_plan() {
+        let root = std::env::temp_dir().join(format!("wedp_find_plan_{}", std::process::id()));
+        let nested = root.join("services").join("auth").join("src");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(root.join("wedding_planner.yml"), "attendees: []\nvenue: ./venue\n").unwrap();
+
+        assert_eq!(find_seating_plan(&nested, "wedding_planner.yml"), Ok(root.join("wedding_planner.yml")));
+        assert_eq!(find_seating_plan(&root, "wedding_planner.yml"), Ok(root.join("wedding_planner.yml")));
+
+        std::fs::write(root.join("services").join("wedding_planner.yml"), "attendees: []\nvenue: ./venue\n").unwrap();
+        assert_eq!(find_seating_plan(&nested, "wedding_planner.yml"), Ok(root.join("services").join("wedding_planner.yml")));
+
+        std::fs::create_dir_all(nested.join("wedp_missing_plan.yml")).unwrap();
+        let missing = find_seating_plan(&nested, "wedp_missing_plan.yml");
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(
+            missing,
+            Err(format!("No wedp_missing_plan.yml found in {} or any directory above it, pass -f to name the seating plan", nested.to_string_lossy()))
+        );
+    }
+
+    #[test]
+    fn test_plan_file_name() {
+        assert_eq!(plan_file_name(None), "wedding_planner.yml");
+        assert_eq!(plan_file_name(Some(String::new())), "wedding_planner.yml");
+        assert_eq!(plan_file_name(Some("seating_plan.yml".to_string())), "seating_plan.yml");
+    }
+
+    #[test]
+    fn test_check_install_reports() {
+        let mut failed = InstallReport::new("auth");
+        failed.errors.push("Failed to clone repo for auth: not found".to_string());
+        let reports = vec![InstallReport::new("database"), failed];
+
+        assert_eq!(check_install_reports(&reports[..1]), Ok(()));
+        assert_eq!(check_install_reports(&reports), Err("Failed to clone repo for auth: not found".to_string()));
+    }
+
+    #[test]
+    fn test_diff_build_files() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_read_to_string()
+            .returning(|path| match path.ends_with("Dockerfile") {
+                true => Err(std::io::Error::new(std::io::ErrorKind::NotFound, "not found")),
+                false => Ok("FROM rust:1.70\n".to_string())
+            });
+        mock_handle.expect_copy().times(0);
+        let mut runner = test_runner();
+        runner.seating_plan.attendees.push(Dependency {
+            name: "not_installed".to_string(),
+            ..Default::default()
+        });
+
+        let (output, errors) = runner.diff_build_files(&mock_handle, &env::current_dir().unwrap());
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("test_repo: would create "));
+        assert!(lines[0].contains("/tests/test_repo/Dockerfile from "));
+        assert!(lines[1].contains("/tests/test_repo/database/Dockerfile from "));
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("not_installed: "));
+        assert!(errors[0].ends_with("does not exist"));
+
+        let runner = runner.with_filter(AttendeeFilter::Only(vec!["not_installed".to_string()])).unwrap();
+        let (output, errors) = runner.diff_build_files(&mock_handle, &env::current_dir().unwrap());
+        assert_eq!(output, "");
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn test_prepare_build_files_reports_failures() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_copy()
+            .returning(|_, _| Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "Permission denied")));
+        let mut runner = test_runner();
+        runner.seating_plan.attendees.push(Dependency {
+            name: "not_installed".to_string(),
+            ..Default::default()
+        });
+
+        let error = runner.prepare_build_files(&mock_handle, &env::current_dir().unwrap()).unwrap_err();
+        let lines: Vec<&str> = error.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("Failed to prepare ") && lines[0].ends_with("/tests/test_repo/Dockerfile for test_repo: Permission denied"));
+        assert!(lines[2].starts_with("Failed to prepare build files for not_installed: "));
+
+        let runner = runner.with_filter(AttendeeFilter::Except(vec!["not_installed".to_string()])).unwrap();
+        assert_eq!(runner.prepare_build_files(&mock_handle, &env::current_dir().unwrap()).unwrap_err().lines().count(), 2);
+    }
+
+    #[test]
+    fn test_record_usage() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_exists().with(eq(PathBuf::from("tests"))).returning(|_| true);
+        mock_handle.expect_read_to_string()
+            .with(eq(PathBuf::from("tests/.wedp/stats.json")))
+            .returning(|_| Ok("{\"attendees\": {\"test_repo\": {\"installs\": 1}}}".to_string()));
+        mock_handle.expect_create_directory_if_not_exists()
+            .with(eq(PathBuf::from("tests/.wedp")))
+            .times(1)
+            .returning(|_| Ok(()));
+        mock_handle.expect_write()
+            .withf(|path, contents| path == Path::new("tests/.wedp/stats.json") && contents.contains("\"installs\": 2"))
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let mut runner = test_runner();
+        runner.file_handle = Box::new(mock_handle);
+
+        runner.record_usage(|stats, names| stats.record_installs(names));
+    }
+
+    #[test]
+    fn test_record_usage_corrupt_stats() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_exists().returning(|_| true);
+        mock_handle.expect_read_to_string().returning(|_| Ok("not json".to_string()));
+        mock_handle.expect_write().times(0);
+        let mut runner = test_runner();
+        runner.file_handle = Box::new(mock_handle);
+
+        runner.record_usage(|stats, names| stats.record_installs(names));
+        assert!(runner.usage_stats().unwrap_err().starts_with("Could not parse tests/.wedp/stats.json"));
+    }
+
+    #[test]
+    fn test_record_usage_missing_venue() {
+        let mut runner = test_runner();
+        runner.file_handle = Box::new(existing_paths_handle(&[]));
+
+        runner.record_usage(|stats, names| stats.record_run(names, &SystemClock));
+    }
 }
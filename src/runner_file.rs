@@ -0,0 +1,170 @@
+//! The entries of the ```runner_files``` of a wedding invite. An entry is either the path to a compose file or
+//! an object with a ```when``` condition, so a compose file such as ```debug.yml``` is only passed to
+//! docker-compose when an environment variable is set:
+//! ```yaml
+//! runner_files:
+//!   - runner_files/base.yml
+//!   - path: runner_files/debug.yml
+//!     when: "${WEDP_DEBUG}"
+//! ```
+//! The ```${NAME}``` variables in the condition are replaced with the environment, unset variables being empty,
+//! and the file is included if the result is truthy, meaning anything apart from an empty string, ```0```,
+//! ```false```, ```no```, or ```off```.
+use serde::{Deserialize, Serialize};
+
+
+/// The values a condition is false for, compared ignoring case.
+pub const FALSY_VALUES: [&str; 5] = ["", "0", "false", "no", "off"];
+
+
+/// A compose file in the runner files of a wedding invite.
+///
+/// # Fields
+/// * `Path` - The path to a compose file that is always included
+/// * `Conditional` - The path to a compose file with the condition it is included under
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RunnerFile {
+    Path(String),
+    Conditional {
+        path: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        when: Option<String>
+    },
+}
+
+impl RunnerFile {
+
+    /// Gets the path to the compose file relative to the wedding invite.
+    ///
+    /// # Returns
+    /// * `&str` - The path to the compose file
+    pub fn path(&self) -> &str {
+        match self {
+            RunnerFile::Path(path) | RunnerFile::Conditional { path, .. } => path
+        }
+    }
+
+    /// Checks whether the compose file is included by evaluating its condition.
+    ///
+    /// # Arguments
+    /// * `lookup` - Reads an environment variable, ```None``` if it is not set
+    ///
+    /// # Returns
+    /// * `bool` - Whether the compose file is included, always ```true``` without a condition
+    pub fn is_included(&self, lookup: &dyn Fn(&str) -> Option<String>) -> bool {
+        match self {
+            RunnerFile::Conditional { when: Some(condition), .. } => is_truthy(&expand_variables(condition, lookup)),
+            _ => true
+        }
+    }
+}
+
+impl From<&str> for RunnerFile {
+    fn from(path: &str) -> Self {
+        RunnerFile::Path(path.to_string())
+    }
+}
+
+
+/// Gets the paths of the runner files whose conditions hold in the environment of the process.
+///
+/// # Arguments
+/// * `files` - The runner files of the wedding invite
+///
+/// # Returns
+/// * `Vec<&str>` - The paths of the included files in order
+pub fn included_paths(files: &[RunnerFile]) -> Vec<&str> {
+    let lookup = |name: &str| std::env::var(name).ok();
+    files.iter().filter(|file| file.is_included(&lookup)).map(RunnerFile::path).collect()
+}
+
+
+/// Replaces the ```${NAME}``` variables in a condition with their values. A ```${``` without a closing brace
+/// is kept as it is.
+///
+/// # Arguments
+/// * `condition` - The condition of the runner file
+/// * `lookup` - Reads an environment variable, ```None``` if it is not set
+///
+/// # Returns
+/// * `String` - The condition with the variables replaced
+fn expand_variables(condition: &str, lookup: &dyn Fn(&str) -> Option<String>) -> String {
+    let mut expanded = String::new();
+    let mut rest = condition;
+    while let Some(start) = rest.find("${") {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break
+        };
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&lookup(&rest[start + 2..end]).unwrap_or_default());
+        rest = &rest[end + 1..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+
+/// Checks whether an expanded condition is truthy.
+///
+/// # Arguments
+/// * `value` - The condition with the variables replaced
+///
+/// # Returns
+/// * `bool` - ```false``` for the ```FALSY_VALUES```, ```true``` for anything else
+fn is_truthy(value: &str) -> bool {
+    let value = value.trim();
+    !FALSY_VALUES.iter().any(|falsy| falsy.eq_ignore_ascii_case(value))
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn conditional(when: &str) -> RunnerFile {
+        RunnerFile::Conditional { path: "runner_files/debug.yml".to_string(), when: Some(when.to_string()) }
+    }
+
+    fn debug_env(value: Option<&'static str>) -> impl Fn(&str) -> Option<String> {
+        move |name: &str| match name {
+            "WEDP_DEBUG" => value.map(String::from),
+            _ => None
+        }
+    }
+
+    #[test]
+    fn test_parse_both_forms() {
+        let files: Vec<RunnerFile> = serde_yaml::from_str(
+            "- runner_files/base.yml\n- path: runner_files/debug.yml\n  when: \"${WEDP_DEBUG}\"\n- path: runner_files/extra.yml\n"
+        ).unwrap();
+        assert_eq!(files, vec![
+            RunnerFile::from("runner_files/base.yml"),
+            conditional("${WEDP_DEBUG}"),
+            RunnerFile::Conditional { path: "runner_files/extra.yml".to_string(), when: None },
+        ]);
+        let paths: Vec<&str> = files.iter().map(RunnerFile::path).collect();
+        assert_eq!(paths, vec!["runner_files/base.yml", "runner_files/debug.yml", "runner_files/extra.yml"]);
+    }
+
+    #[test]
+    fn test_is_included() {
+        let file = conditional("${WEDP_DEBUG}");
+        assert!(file.is_included(&debug_env(Some("1"))));
+        assert!(file.is_included(&debug_env(Some("yes"))));
+        assert!(!file.is_included(&debug_env(None)));
+        for value in ["", "0", "false", "FALSE", "no", "off", " 0 "] {
+            assert!(!file.is_included(&debug_env(Some(value))), "{:?}", value);
+        }
+        assert!(RunnerFile::from("runner_files/base.yml").is_included(&debug_env(None)));
+    }
+
+    #[test]
+    fn test_expand_variables() {
+        assert_eq!(expand_variables("${WEDP_DEBUG}-${MISSING}", &debug_env(Some("1"))), "1-");
+        assert_eq!(expand_variables("plain", &debug_env(None)), "plain");
+        assert_eq!(expand_variables("${WEDP_DEBUG", &debug_env(Some("1"))), "${WEDP_DEBUG");
+    }
+}
@@ -0,0 +1,202 @@
+//! Scaffolds a starter wedding invite or seating plan into a directory for ```wedp init```. The templates are
+//! commented so a new repo can be filled in without looking up the keys, and they are written through the file
+//! handle so ```--dry-run``` prints the file that would be created. An existing file is never overwritten
+//! unless ```--force``` is passed.
+use std::path::{Path, PathBuf};
+
+use crate::file_handler::CoreFileHandle;
+
+
+/// The starter wedding invite, it parses as it is written.
+const INVITE_TEMPLATE: &str = "\
+# The wedding invite tells wedp how to build and run this repo when a seating plan lists it as an attendee.
+
+# The directory the Dockerfile for the CPU type is copied into before building.
+build_root: \".\"
+
+# The Dockerfile for each CPU type, copied into the build root as Dockerfile.
+build_files:
+  x86_64: builds/Dockerfile.x86_64
+  aarch64: builds/Dockerfile.aarch64
+
+# The docker-compose files that run this repo. A file can be included only when a condition holds.
+runner_files:
+  - runner_files/base.yml
+#  - path: runner_files/debug.yml
+#    when: \"${WEDP_DEBUG}\"
+
+# The docker-compose files that run the images pulled from a registry with wedp remoterun.
+#remote_runner_files:
+#  - runner_files/remote.yml
+";
+
+/// The starter seating plan, it parses as it is written.
+const PLAN_TEMPLATE: &str = "\
+# The seating plan lists the repos this project needs to run and where wedp clones them.
+
+attendees:
+  # Each attendee is cloned from its url and checked out on its branch. Pin an attendee with tag, commit,
+  # or rev instead of a branch.
+  - name: auth
+    url: https://github.com/your-org/auth.git
+    branch: main
+
+# The directory the attendees are cloned into.
+venue: ./sandbox/services
+
+# The flags passed to a command when they are not given on the command line.
+#command_defaults:
+#  run:
+#    detach: true
+#    wait: true
+";
+
+
+/// The kind of file ```wedp init``` scaffolds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Template {
+    Invite,
+    Plan,
+}
+
+impl Template {
+
+    /// Gets the template for the name passed to ```wedp init```.
+    ///
+    /// # Arguments
+    /// * `name` - Either ```invite``` or ```plan```
+    ///
+    /// # Returns
+    /// * `Result<Template, String>` - The template or an error if the name is not a template
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "invite" => Ok(Template::Invite),
+            "plan" => Ok(Template::Plan),
+            _ => Err(format!("{} is not a template, pass invite or plan", name))
+        }
+    }
+
+    /// Gets the name of the file the template is written to, the seating plan uses the name wedp reads when
+    /// no ```-f``` is passed.
+    ///
+    /// # Returns
+    /// * `&str` - The file name
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Template::Invite => "wedding_invite.yml",
+            Template::Plan => "wedding_planner.yml"
+        }
+    }
+
+    /// Gets the contents of the template.
+    ///
+    /// # Returns
+    /// * `&str` - The commented yml
+    pub fn contents(&self) -> &'static str {
+        match self {
+            Template::Invite => INVITE_TEMPLATE,
+            Template::Plan => PLAN_TEMPLATE
+        }
+    }
+}
+
+
+/// Writes a template into a directory. The existing file is looked up through its metadata rather than
+/// ```exists``` so a dry run still refuses to overwrite it.
+///
+/// # Arguments
+/// * `template` - The template to write
+/// * `directory` - The directory the file is written into
+/// * `force` - Whether to overwrite a file that already exists
+/// * `handle` - The file handle used to write the file
+///
+/// # Returns
+/// * `Result<PathBuf, String>` - The path to the written file or an error if it exists or could not be written
+pub fn scaffold(template: Template, directory: &Path, force: bool, handle: &dyn CoreFileHandle) -> Result<PathBuf, String> {
+    let path = directory.join(template.file_name());
+    if !force && handle.modified(&path).is_ok() {
+        return Err(format!("{} already exists, pass --force to overwrite it", path.to_string_lossy()))
+    }
+    handle.write(&path, template.contents())
+          .map_err(|error| format!("Failed to write {}: {}", path.to_string_lossy(), error))?;
+    Ok(path)
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::file_handler::MockCoreFileHandle;
+    use crate::seating_plan::SeatingPlan;
+    use crate::wedding_invite::WeddingInvite;
+    use mockall::predicate::eq;
+    use std::io::{Error, ErrorKind};
+    use std::time::SystemTime;
+
+    fn template_handle(template: Template) -> MockCoreFileHandle {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_read_to_string()
+            .returning(move |_| Ok(template.contents().to_string()));
+        mock_handle
+    }
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(Template::from_name("invite"), Ok(Template::Invite));
+        assert_eq!(Template::from_name("plan"), Ok(Template::Plan));
+        assert_eq!(Template::from_name("venue"), Err("venue is not a template, pass invite or plan".to_string()));
+    }
+
+    #[test]
+    fn test_invite_template_parses() {
+        let invite = WeddingInvite::from_handle("wedding_invite.yml".to_string(), &template_handle(Template::Invite)).unwrap();
+        assert_eq!(invite.build_root, ".");
+        assert_eq!(invite.build_files.unwrap().len(), 2);
+        assert_eq!(invite.runner_files.len(), 1);
+        assert_eq!(invite.remote_runner_files, None);
+    }
+
+    #[test]
+    fn test_plan_template_parses() {
+        let plan = SeatingPlan::from_handle("/project/wedding_planner.yml".to_string(), &template_handle(Template::Plan)).unwrap();
+        assert_eq!(plan.attendees.len(), 1);
+        assert_eq!(plan.attendees[0].name, "auth");
+        assert_eq!(plan.attendees[0].branch, Some("main".to_string()));
+        assert_eq!(plan.venue, "./sandbox/services");
+        assert!(plan.problems().is_empty());
+    }
+
+    #[test]
+    fn test_scaffold() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_modified()
+            .with(eq(PathBuf::from("/project/wedding_invite.yml")))
+            .returning(|_| Err(Error::from(ErrorKind::NotFound)));
+        mock_handle.expect_write()
+            .with(eq(PathBuf::from("/project/wedding_invite.yml")), eq(INVITE_TEMPLATE))
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let path = scaffold(Template::Invite, Path::new("/project"), false, &mock_handle);
+        assert_eq!(path, Ok(PathBuf::from("/project/wedding_invite.yml")));
+    }
+
+    #[test]
+    fn test_scaffold_existing_file() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_modified().returning(|_| Ok(SystemTime::now()));
+        mock_handle.expect_write().times(0);
+        assert_eq!(
+            scaffold(Template::Plan, Path::new("/project"), false, &mock_handle),
+            Err("/project/wedding_planner.yml already exists, pass --force to overwrite it".to_string())
+        );
+
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_modified().returning(|_| Ok(SystemTime::now()));
+        mock_handle.expect_write()
+            .with(eq(PathBuf::from("/project/wedding_planner.yml")), eq(PLAN_TEMPLATE))
+            .times(1)
+            .returning(|_, _| Ok(()));
+        assert!(scaffold(Template::Plan, Path::new("/project"), true, &mock_handle).is_ok());
+    }
+}
@@ -0,0 +1,255 @@
+//! Stamps the files wedp generates with the version of wedp that wrote them and the schema of their format, so
+//! a file written by one version is never misread by another. Files of a newer schema are refused with an
+//! error asking for wedp to be upgraded, files of an older schema are brought up to date by the migrations
+//! registered for them, and files of the same schema are read whichever version of wedp wrote them.
+//! ```json
+//! {
+//!   "wedp_version": "0.1.0",
+//!   "schema": 1,
+//!   "remote": false,
+//!   ...
+//! }
+//! ```
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::run_state::RUN_STATE_FILE;
+use crate::usage_stats::STATS_FILE;
+use crate::vendor_manifest::MANIFEST_FILE;
+
+
+/// The version of wedp stamped into the files it writes.
+pub const WEDP_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The schema of the files this version of wedp writes, bumped whenever the format of a file changes.
+pub const CURRENT_SCHEMA: u32 = 1;
+
+/// The schema of the files written before files were stamped.
+pub const UNSTAMPED_SCHEMA: u32 = 0;
+
+/// The field the version of wedp is stamped in.
+pub const VERSION_FIELD: &str = "wedp_version";
+
+/// The field the schema is stamped in.
+pub const SCHEMA_FIELD: &str = "schema";
+
+/// The name the seating plan cache entries are migrated under as each entry is named after a hash.
+pub const PLAN_CACHE_FILE: &str = "plan_cache";
+
+
+/// Brings a file from one schema to the next.
+///
+/// # Fields
+/// * `file` - The name of the file the migration is for
+/// * `from` - The schema the migration reads, it writes the schema after it
+/// * `migrate` - Rewrites the contents of the file without the stamp fields
+pub struct Migration {
+    pub file: &'static str,
+    pub from: u32,
+    pub migrate: fn(Value) -> Result<Value, String>,
+}
+
+
+/// The migrations of every generated file. The layout of every file was unchanged when the stamps were added
+/// so the unstamped files are read as they are.
+pub const MIGRATIONS: [Migration; 4] = [
+    Migration { file: RUN_STATE_FILE, from: UNSTAMPED_SCHEMA, migrate: unchanged },
+    Migration { file: STATS_FILE, from: UNSTAMPED_SCHEMA, migrate: unchanged },
+    Migration { file: MANIFEST_FILE, from: UNSTAMPED_SCHEMA, migrate: unchanged },
+    Migration { file: PLAN_CACHE_FILE, from: UNSTAMPED_SCHEMA, migrate: unchanged },
+];
+
+
+/// The contents of a generated file with the stamp written before them.
+///
+/// # Fields
+/// * `wedp_version` - The version of wedp writing the file
+/// * `schema` - The schema of the file
+/// * `contents` - The contents of the file, flattened next to the stamp
+#[derive(Serialize)]
+pub struct Stamped<'a, T: Serialize> {
+    pub wedp_version: &'static str,
+    pub schema: u32,
+    #[serde(flatten)]
+    pub contents: &'a T,
+}
+
+
+/// Stamps the contents of a file with the current version and schema.
+///
+/// # Arguments
+/// * `contents` - The contents of the file, they must serialize to a JSON object
+///
+/// # Returns
+/// * `Stamped<T>` - The contents with the stamp, ready to be serialized
+pub fn stamp<T: Serialize>(contents: &T) -> Stamped<'_, T> {
+    Stamped { wedp_version: WEDP_VERSION, schema: CURRENT_SCHEMA, contents }
+}
+
+
+/// Reads a stamped file, migrating it if it was written with an older schema.
+///
+/// # Arguments
+/// * `file` - The name of the file the migrations are registered under
+/// * `path` - The path of the file for the errors
+/// * `contents` - The contents of the file
+///
+/// # Returns
+/// * `Result<T, String>` - The contents or an error if the file could not be parsed, is of a newer schema, or
+///   can not be migrated
+pub fn read_stamped<T: DeserializeOwned>(file: &str, path: &str, contents: &str) -> Result<T, String> {
+    read_with(file, path, contents, &MIGRATIONS)
+}
+
+
+/// Reads a stamped file with the given migrations.
+///
+/// # Arguments
+/// * `file` - The name of the file the migrations are registered under
+/// * `path` - The path of the file for the errors
+/// * `contents` - The contents of the file
+/// * `migrations` - The migrations to bring an older schema up to date with
+///
+/// # Returns
+/// * `Result<T, String>` - The contents or an error if the file could not be parsed, is of a newer schema, or
+///   can not be migrated
+pub fn read_with<T: DeserializeOwned>(file: &str, path: &str, contents: &str, migrations: &[Migration]) -> Result<T, String> {
+    let parse_error = |error: String| format!("Could not parse {}: {}", path, error);
+    let mut value: Value = serde_json::from_str(contents).map_err(|error| parse_error(error.to_string()))?;
+    let (version, schema) = match value.as_object_mut() {
+        Some(object) => {
+            let version = object.remove(VERSION_FIELD).and_then(|version| version.as_str().map(String::from));
+            let schema = match object.remove(SCHEMA_FIELD) {
+                Some(schema) => schema.as_u64()
+                                      .and_then(|schema| u32::try_from(schema).ok())
+                                      .ok_or_else(|| parse_error(format!("{} must be a whole number", SCHEMA_FIELD)))?,
+                None => UNSTAMPED_SCHEMA
+            };
+            (version, schema)
+        },
+        None => (None, UNSTAMPED_SCHEMA)
+    };
+    if schema > CURRENT_SCHEMA {
+        return Err(format!(
+            "{} was written by wedp {} with schema {} but wedp {} only reads up to schema {}, upgrade wedp to read it",
+            path, version.as_deref().unwrap_or("unknown"), schema, WEDP_VERSION, CURRENT_SCHEMA
+        ))
+    }
+    for from in schema..CURRENT_SCHEMA {
+        let migration = migrations.iter()
+            .find(|migration| migration.file == file && migration.from == from)
+            .ok_or_else(|| format!(
+                "{} has schema {} which wedp {} can not migrate, remove it to have wedp regenerate it",
+                path, from, WEDP_VERSION
+            ))?;
+        value = (migration.migrate)(value).map_err(|error| format!("Could not migrate {} from schema {}: {}", path, from, error))?;
+    }
+    serde_json::from_value(value).map_err(|error| parse_error(error.to_string()))
+}
+
+
+/// Leaves the contents of a file whose layout did not change between schemas as they are.
+///
+/// # Arguments
+/// * `value` - The contents of the file
+///
+/// # Returns
+/// * `Result<Value, String>` - The same contents
+fn unchanged(value: Value) -> Result<Value, String> {
+    Ok(value)
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::compose_file::ComposeFileRef;
+    use crate::run_state::RunState;
+
+    fn run_state() -> RunState {
+        RunState { remote: true, compose_files: vec![ComposeFileRef::new("auth", "venue/auth/base.yml".to_string())] }
+    }
+
+    /// Renames the ```files``` of a fabricated schema 0 run state to ```compose_files```.
+    fn rename_files(mut value: Value) -> Result<Value, String> {
+        let object = value.as_object_mut().ok_or("the run state is not an object")?;
+        let files = object.remove("files").ok_or("the run state has no files")?;
+        object.insert("compose_files".to_string(), files);
+        Ok(value)
+    }
+
+    #[test]
+    fn test_stamp() {
+        let json = serde_json::to_string(&stamp(&run_state())).unwrap();
+        assert_eq!(json, format!(
+            r#"{{"wedp_version":"{}","schema":{},"remote":true,"compose_files":[{{"attendee":"auth","path":"venue/auth/base.yml"}}]}}"#,
+            WEDP_VERSION, CURRENT_SCHEMA
+        ));
+        assert_eq!(read_stamped::<RunState>(RUN_STATE_FILE, "run.json", &json), Ok(run_state()));
+    }
+
+    #[test]
+    fn test_read_unstamped() {
+        let json = r#"{"remote":true,"compose_files":[{"attendee":"auth","path":"venue/auth/base.yml"}]}"#;
+        assert_eq!(read_stamped::<RunState>(RUN_STATE_FILE, "run.json", json), Ok(run_state()));
+    }
+
+    #[test]
+    fn test_read_older_version_same_schema() {
+        let json = r#"{"wedp_version":"0.0.1","schema":1,"remote":true,"compose_files":[{"attendee":"auth","path":"venue/auth/base.yml"}]}"#;
+        assert_eq!(read_stamped::<RunState>(RUN_STATE_FILE, "run.json", json), Ok(run_state()));
+    }
+
+    #[test]
+    fn test_read_newer_schema() {
+        let json = r#"{"wedp_version":"9.0.0","schema":99,"remote":true,"compose_files":[]}"#;
+        assert_eq!(
+            read_stamped::<RunState>(RUN_STATE_FILE, "venue/.wedp/run.json", json),
+            Err(format!(
+                "venue/.wedp/run.json was written by wedp 9.0.0 with schema 99 but wedp {} only reads up to schema {}, \
+                 upgrade wedp to read it", WEDP_VERSION, CURRENT_SCHEMA
+            ))
+        );
+    }
+
+    #[test]
+    fn test_migrate_older_schema() {
+        let migrations = [Migration { file: RUN_STATE_FILE, from: 0, migrate: rename_files }];
+        let json = r#"{"remote":true,"files":[{"attendee":"auth","path":"venue/auth/base.yml"}]}"#;
+        assert_eq!(read_with::<RunState>(RUN_STATE_FILE, "run.json", json, &migrations), Ok(run_state()));
+
+        let json = r#"{"wedp_version":"0.0.1","schema":0,"remote":true,"compose_files":[]}"#;
+        assert_eq!(
+            read_with::<RunState>(RUN_STATE_FILE, "run.json", json, &migrations),
+            Err("Could not migrate run.json from schema 0: the run state has no files".to_string())
+        );
+    }
+
+    #[test]
+    fn test_migration_missing() {
+        let json = r#"{"remote":true,"compose_files":[]}"#;
+        assert_eq!(
+            read_with::<RunState>(RUN_STATE_FILE, "run.json", json, &[]),
+            Err(format!("run.json has schema 0 which wedp {} can not migrate, remove it to have wedp regenerate it", WEDP_VERSION))
+        );
+    }
+
+    #[test]
+    fn test_read_invalid_schema() {
+        let json = r#"{"schema":"one","remote":true}"#;
+        assert_eq!(
+            read_stamped::<RunState>(RUN_STATE_FILE, "run.json", json),
+            Err("Could not parse run.json: schema must be a whole number".to_string())
+        );
+        assert!(read_stamped::<RunState>(RUN_STATE_FILE, "run.json", "{").unwrap_err().starts_with("Could not parse run.json: "));
+    }
+
+    #[test]
+    fn test_every_file_migrates_from_unstamped() {
+        for file in [RUN_STATE_FILE, STATS_FILE, MANIFEST_FILE, PLAN_CACHE_FILE] {
+            assert!(MIGRATIONS.iter().any(|migration| migration.file == file && migration.from == UNSTAMPED_SCHEMA), "{}", file);
+        }
+    }
+}
@@ -6,21 +6,43 @@
 //!  - name: John Doe
 //!    url: http://example.com/john-doe
 //!    branch: development
-//!    local_run_config_file: ../sandbox/local_service_configs/jane-doe.yml
-//!    remote_run_config_file: ../sandbox/remote_service_configs/jane-doe.yml
 //!  - name: Jane Doe
 //!    url: http://example.com/jane-doe
-//!    branch: development
-//!    local_run_config_file: ../sandbox/local_service_configs/jane-doe.yml
-//!    remote_run_config_file: ../sandbox/remote_service_configs/jane-doe.yml
+//!    rev: v1.4.0
 //!
 //!venue: ../sandbox/services/
+//!
+//!command_defaults:
+//!  run:
+//!    detach: true
+//!    wait: true
+//!
+//!restart_policy: unless-stopped
+//!
+//!compose_command: docker compose
+//!
+//!engine: podman
+//!
+//!clone_depth: 1
+//!
+//!auth:
+//!  token_env: GH_TOKEN
+//!
+//!git_protocol: https
 //! ```
 use serde::{Deserialize, Serialize};
 use serde_yaml::{self};
-use std::fs::File;
-use std::path::Path;
-use crate::file_handler::CoreFileHandle;
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+use crate::file_handler::{CoreFileHandle, FileHandle};
+use crate::paths::{expand_path, join_normalised, normalise_path, resolve_parents};
+use crate::command_defaults::CommandDefaults;
+use crate::restart_policy::validate_restart_policy;
+use crate::compose_binary::{validate_compose_command, ContainerEngine};
+use crate::error::WedpError;
+use crate::git_auth::{GitAuth, GitProtocol};
+use crate::interpolate::{interpolating, read_interpolated};
+use crate::yaml_error::{describe_yaml_error, strip_unknown_fields, with_unknown_fields};
 
 use crate::dependency::Dependency;
 
@@ -30,32 +52,240 @@ use crate::dependency::Dependency;
 /// # Fields
 /// * `attendees` - A vector of ```Dependency``` structs
 /// * `venue` - The directory where all docker-compose files for local services will be run
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// * `command_defaults` - The default flags for each command, overridden by flags passed in through the command line
+/// * `restart_policy` - The ```restart``` policy set on every service when the dependencies are run
+/// * `compose_command` - The command docker compose is run with, ```None``` detects it
+/// * `engine` - The container engine the dependencies are run with, ```None``` detects the docker compose command
+/// * `max_docker_ops` - The most docker commands run at the same time, ```None``` for no limit
+/// * `max_git_ops` - The most git commands run at the same time, ```None``` for no limit
+/// * `clone_depth` - The ```clone_depth``` of every attendee that does not set its own, ```None``` clones the full history
+/// * `auth` - The ```auth``` of every attendee that does not set its own
+/// * `git_protocol` - The ```git_protocol``` of every attendee that does not set its own
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
 pub struct SeatingPlan {
     pub attendees: Vec<Dependency>,
     pub venue: String,
+    pub command_defaults: Option<CommandDefaults>,
+    pub restart_policy: Option<String>,
+    pub compose_command: Option<String>,
+    pub engine: Option<ContainerEngine>,
+    pub max_docker_ops: Option<usize>,
+    pub max_git_ops: Option<usize>,
+    pub clone_depth: Option<u32>,
+    pub auth: Option<GitAuth>,
+    pub git_protocol: Option<GitProtocol>,
 }
 
 
 impl SeatingPlan {
 
-    /// Creates a new SeatingPlan struct from a YAML file.
+    /// Creates a new SeatingPlan struct from a YAML file on disk, substituting the environment into it first.
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the YAML file
+    ///
+    /// # Returns
+    /// * `Result<SeatingPlan, WedpError>` - A ```SeatingPlan``` struct or the error reading, parsing, or 
+    ///   expanding the file
+    pub fn from_file(file_path: String) -> Result<SeatingPlan, WedpError> {
+        SeatingPlan::from_handle(file_path, &FileHandle {})
+    }
+
+    /// Creates a new SeatingPlan struct from a YAML file read through a file handle, substituting the 
+    /// environment into it first.
     ///
     /// # Arguments
     /// * `file_path` - The path to the YAML file
+    /// * `handle` - The file handle the file is read with
+    ///
+    /// # Returns
+    /// * `Result<SeatingPlan, WedpError>` - A ```SeatingPlan``` struct or the error reading, parsing, or 
+    ///   expanding the file
+    pub fn from_handle(file_path: String, handle: &dyn CoreFileHandle) -> Result<SeatingPlan, WedpError> {
+        let contents = read_interpolated(&file_path, handle)?;
+        SeatingPlan::parse(&contents, &file_path)?.expand_paths(&file_path)
+    }
+
+    /// Creates a new SeatingPlan struct from a YAML file for ```validate```, leaving out the keys it does not 
+    /// know so every unknown key is reported and the rest of the seating plan can still be checked. The values 
+    /// are not checked, they are reported by ```problems```.
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the YAML file
+    ///
+    /// # Returns
+    /// * `Result<(SeatingPlan, Vec<String>), WedpError>` - The ```SeatingPlan``` without the unknown keys and a 
+    ///   problem for every unknown key, or the error reading, parsing, or expanding the file
+    pub fn from_file_lenient(file_path: String) -> Result<(SeatingPlan, Vec<String>), WedpError> {
+        let contents = read_interpolated(&file_path, &FileHandle {})?;
+        let (contents, unknown) = strip_unknown_fields::<SeatingPlan>(&contents);
+        let seating_plan = SeatingPlan::deserialize(&contents, &file_path)
+            .map_err(|error| with_unknown_fields(error, &unknown))?
+            .expand_paths(&file_path)?;
+        let problems = unknown.into_iter().map(|problem| format!("{}: {}", file_path, problem)).collect();
+        Ok((seating_plan, problems))
+    }
+
+    /// Checks every value parsing refuses, and what parsing does not check, that every attendee has its own name
+    /// and a ```url``` that looks like a git remote or a ```path``` to a directory.
     ///
     /// # Returns
-    /// * `Result<SeatingPlan, String>` - A ```SeatingPlan``` struct or an error message
-    pub fn from_file(file_path: String) -> Result<SeatingPlan, String> {
-        let file = match File::open(&file_path) {
-            Ok(f) => f,
-            Err(e) => return Err(format!("Could not open file: {} for {}", e, file_path))
-        };
-        let seating_plan: SeatingPlan = match serde_yaml::from_reader(file) {
-            Ok(s) => s,
-            Err(e) => return Err(format!("Could not parse file: {} for {}", e, file_path))
-        };
-        Ok(seating_plan)
+    /// * `Vec<String>` - A message for every problem found, empty if the seating plan is valid
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut seen = HashSet::new();
+        for dependency in &self.attendees {
+            if !seen.insert(dependency.name.as_str()) {
+                problems.push(format!("attendee {} is listed more than once", dependency.name));
+            }
+            match &dependency.path {
+                Some(path) if !Path::new(path).is_dir() => problems.push(format!("path {} of {} is not a directory", path, dependency.name)),
+                Some(_) => {},
+                None if dependency.url.is_empty() => {},
+                None => problems.extend(dependency.check_url().err())
+            }
+        }
+        problems.extend(self.value_problems());
+        problems
+    }
+
+    /// Expands the environment variables and a leading ```~``` in the ```venue``` and the ```url``` and ```path```
+    /// of each attendee, as a ```url``` can also be the path to a local repository. This is applied after parsing 
+    /// rather than in ```parse``` so a cached seating plan is expanded with the environment of every run. Nothing
+    /// is expanded with ```--no-interpolate```.
+    ///
+    /// # Arguments
+    /// * `file_path` - The path the seating plan was read from, used in error messages
+    ///
+    /// # Returns
+    /// * `Result<SeatingPlan, WedpError>` - The expanded ```SeatingPlan``` or an error naming a variable that is not set
+    pub fn expand_paths(self, file_path: &str) -> Result<SeatingPlan, WedpError> {
+        if !interpolating() {
+            return Ok(self)
+        }
+        let lookup = |name: &str| std::env::var(name).ok();
+        let invalid = |field: String, e: String| WedpError::InvalidConfig(format!("{} {} for {}", field, e, file_path));
+        let venue = expand_path(&self.venue, &lookup).map_err(|e| invalid("venue".to_string(), e))?;
+        let mut attendees = Vec::new();
+        for dependency in self.attendees {
+            let url = expand_path(&dependency.url, &lookup).map_err(|e| invalid(format!("url of {}", dependency.name), e))?;
+            let path = match &dependency.path {
+                Some(path) => Some(expand_path(path, &lookup).map_err(|e| invalid(format!("path of {}", dependency.name), e))?),
+                None => None
+            };
+            attendees.push(Dependency { url, path, ..dependency });
+        }
+        Ok(SeatingPlan { attendees, venue, ..self })
+    }
+
+    /// Creates a new SeatingPlan struct from the YAML contents of a seating plan file.
+    ///
+    /// # Arguments
+    /// * `contents` - The YAML contents of the seating plan
+    /// * `file_path` - The path the contents were read from, used in error messages
+    ///
+    /// # Returns
+    /// * `Result<SeatingPlan, WedpError>` - A ```SeatingPlan``` struct or the error parsing or validating the contents
+    pub fn parse(contents: &str, file_path: &str) -> Result<SeatingPlan, WedpError> {
+        let seating_plan = SeatingPlan::deserialize(contents, file_path)?.with_attendee_defaults();
+        match seating_plan.value_problems().into_iter().next() {
+            Some(problem) => Err(WedpError::InvalidConfig(format!("{} for {}", problem, file_path))),
+            None => Ok(seating_plan)
+        }
+    }
+
+    /// Sets the ```clone_depth```, ```auth```, and ```git_protocol``` of the seating plan on every attendee that
+    /// does not set its own, so the defaults only apply to the attendees of the file they are written in when
+    /// seating plans are merged.
+    ///
+    /// # Returns
+    /// * `SeatingPlan` - The seating plan with the defaults applied to its attendees
+    fn with_attendee_defaults(mut self) -> SeatingPlan {
+        for dependency in self.attendees.iter_mut() {
+            dependency.clone_depth = dependency.clone_depth.or(self.clone_depth);
+            dependency.auth = dependency.auth.clone().or_else(|| self.auth.clone());
+            dependency.git_protocol = dependency.git_protocol.or(self.git_protocol);
+        }
+        self
+    }
+
+    /// Deserializes the YAML contents of a seating plan file without checking the values.
+    ///
+    /// # Arguments
+    /// * `contents` - The YAML contents of the seating plan
+    /// * `file_path` - The path the contents were read from, used in error messages
+    ///
+    /// # Returns
+    /// * `Result<SeatingPlan, WedpError>` - A ```SeatingPlan``` struct or the error parsing the contents
+    fn deserialize(contents: &str, file_path: &str) -> Result<SeatingPlan, WedpError> {
+        serde_yaml::from_str(contents)
+            .map_err(|e| WedpError::ConfigParse { path: file_path.to_string(), message: describe_yaml_error(&e, contents) })
+    }
+
+    /// Checks the values parsing refuses, such as an attendee with both a ```url``` and a ```path```, an attendee
+    /// cloned from a ```url``` without a ```branch``` or a ```rev```, or a ```restart_policy``` docker does not know.
+    ///
+    /// # Returns
+    /// * `Vec<String>` - A message for every value that is refused, empty if all of them are accepted
+    fn value_problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = self.attendees.iter().filter_map(|dependency| dependency.check_source().err()).collect();
+        problems.extend(self.attendees.iter()
+            .filter(|dependency| dependency.path.is_none())
+            .filter_map(|dependency| dependency.git_ref().err()));
+        if let Some(Err(problem)) = self.restart_policy.as_ref().map(|policy| validate_restart_policy(policy)) {
+            problems.push(problem);
+        }
+        if let Some(Err(problem)) = self.compose_command.as_ref().map(|command| validate_compose_command(command)) {
+            problems.push(problem);
+        }
+        for (key, limit) in [("max_docker_ops", self.max_docker_ops), ("max_git_ops", self.max_git_ops)] {
+            if limit == Some(0) {
+                problems.push(format!("{} must be at least 1", key));
+            }
+        }
+        for dependency in self.attendees.iter().filter(|dependency| dependency.clone_depth == Some(0)) {
+            problems.push(format!("clone_depth of {} must be at least 1", dependency.name));
+        }
+        problems
+    }
+
+    /// Merges the seating plan of another file into this one, so a project can split its attendees across 
+    /// several seating plans. The attendees are added after the attendees of this plan and both plans must use 
+    /// the same venue. A setting such as ```restart_policy``` may be set in either plan but not to different values.
+    ///
+    /// # Arguments
+    /// * `other` - The seating plan to merge in
+    /// * `other_path` - The path the other seating plan was read from, used in error messages
+    ///
+    /// # Returns
+    /// * `Result<SeatingPlan, WedpError>` - The merged ```SeatingPlan``` or an error naming the attendee in both 
+    ///   plans, or the venue or setting they disagree on
+    pub fn merge(self, other: SeatingPlan, other_path: &str) -> Result<SeatingPlan, WedpError> {
+        let invalid = |e: String| WedpError::InvalidConfig(format!("{} in {}", e, other_path));
+        if self.venue_path() != other.venue_path() {
+            return Err(invalid(format!("venue {} does not match the venue {} of the other seating plans", other.venue, self.venue)))
+        }
+        let mut attendees = self.attendees;
+        for dependency in other.attendees {
+            if attendees.iter().any(|attendee| attendee.name == dependency.name) {
+                return Err(invalid(format!("attendee {} is already in another seating plan", dependency.name)))
+            }
+            attendees.push(dependency);
+        }
+        Ok(SeatingPlan {
+            attendees,
+            venue: self.venue,
+            command_defaults: merge_setting("command_defaults", self.command_defaults, other.command_defaults).map_err(invalid)?,
+            restart_policy: merge_setting("restart_policy", self.restart_policy, other.restart_policy).map_err(invalid)?,
+            compose_command: merge_setting("compose_command", self.compose_command, other.compose_command).map_err(invalid)?,
+            engine: merge_setting("engine", self.engine, other.engine).map_err(invalid)?,
+            max_docker_ops: merge_setting("max_docker_ops", self.max_docker_ops, other.max_docker_ops).map_err(invalid)?,
+            max_git_ops: merge_setting("max_git_ops", self.max_git_ops, other.max_git_ops).map_err(invalid)?,
+            clone_depth: merge_setting("clone_depth", self.clone_depth, other.clone_depth).map_err(invalid)?,
+            auth: merge_setting("auth", self.auth, other.auth).map_err(invalid)?,
+            git_protocol: merge_setting("git_protocol", self.git_protocol, other.git_protocol).map_err(invalid)?
+        })
     }
 
     /// Creates a venue directory if the venue is not already present. 
@@ -67,8 +297,110 @@ impl SeatingPlan {
     /// * `Result<(), std::io::Error>` - An error if the directory could not be created
     pub fn create_venue(&self, file_handler: &dyn CoreFileHandle) -> Result<(), std::io::Error> {
         println!("Creating venue directory");
-        let venue_path = Path::new(&self.venue);
-        file_handler.create_directory_if_not_exists(venue_path)
+        file_handler.create_directory_if_not_exists(&self.venue_path())
+    }
+
+    /// Gets the venue without ```.``` segments, duplicate separators, or trailing slashes.
+    /// 
+    /// # Returns
+    /// * `PathBuf` - The cleaned path to the venue
+    pub fn venue_path(&self) -> PathBuf {
+        normalise_path(Path::new(&self.venue))
+    }
+
+    /// Joins the relative venue and attendee paths onto the directory of a seating plan that was found above the
+    /// directory wedp was started in, so they are read from the directory of the seating plan as they would be
+    /// if wedp was started there.
+    /// 
+    /// # Arguments
+    /// * `root` - The directory holding the seating plan
+    /// 
+    /// # Returns
+    /// * `SeatingPlan` - The seating plan with its venue and the ```path``` of its attendees under ```root```
+    pub fn relative_to(self, root: &Path) -> SeatingPlan {
+        let venue = join_normalised(root, &[&self.venue]).to_string_lossy().to_string();
+        let attendees = self.attendees.into_iter().map(|dependency| Dependency {
+            path: dependency.path.map(|path| join_normalised(root, &[&path]).to_string_lossy().to_string()),
+            ..dependency
+        }).collect();
+        SeatingPlan { attendees, venue, ..self }
+    }
+
+    /// Gets the venue joined onto the directory wedp is run from.
+    /// 
+    /// # Arguments
+    /// * `cwd` - The directory wedp is run from
+    /// 
+    /// # Returns
+    /// * `String` - The cleaned full path to the venue
+    pub fn full_venue_path(&self, cwd: &Path) -> String {
+        join_normalised(cwd, &[&self.venue]).to_string_lossy().to_string()
+    }
+
+    /// Gets the directory a dependency is cloned into in the venue.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the dependency
+    /// 
+    /// # Returns
+    /// * `PathBuf` - The cleaned path to the dependency
+    pub fn dependency_path(&self, name: &str) -> PathBuf {
+        join_normalised(Path::new(&self.venue), &[name])
+    }
+
+    /// Gets the directory of a dependency in the venue for removing it. The name must be a single plain path 
+    /// segment so the directory is always directly inside the venue, a name such as ```..``` or ```../auth``` 
+    /// never reaches a directory outside of it.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the dependency
+    /// 
+    /// # Returns
+    /// * `Result<PathBuf, String>` - The path to the dependency or an error if it would be outside the venue
+    pub fn removable_dependency_path(&self, name: &str) -> Result<PathBuf, String> {
+        let mut components = Path::new(name).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(_)), None) => Ok(self.dependency_path(name)),
+            _ => Err(format!("{} is not a directory inside the venue {}, refusing to remove it", name, self.venue))
+        }
+    }
+
+    /// Gets the venue for removing it as a whole. A venue that is the root, the home directory, the directory
+    /// wedp is run from, or any directory above it is refused as removing it would remove far more than the
+    /// dependencies.
+    /// 
+    /// # Arguments
+    /// * `cwd` - The directory wedp is run from
+    /// * `home` - The home directory of the user, ```None``` if it is not known
+    /// 
+    /// # Returns
+    /// * `Result<PathBuf, String>` - The path to the venue or an error if removing it would be dangerous
+    pub fn removable_venue_path(&self, cwd: &Path, home: Option<&Path>) -> Result<PathBuf, String> {
+        let venue = resolve_parents(&cwd.join(&self.venue));
+        let dangerous = venue.parent().is_none()
+            || resolve_parents(cwd).starts_with(&venue)
+            || home.map(|home| resolve_parents(home) == venue).unwrap_or(false);
+        match dangerous {
+            true => Err(format!("venue {} resolves to {}, refusing to remove it", self.venue, venue.to_string_lossy())),
+            false => Ok(self.venue_path())
+        }
+    }
+}
+
+
+/// Merges a setting of two seating plans, keeping the value of whichever plan sets it.
+///
+/// # Arguments
+/// * `key` - The key of the setting, used in the error
+/// * `setting` - The value of the setting in the first plan
+/// * `other` - The value of the setting in the other plan
+///
+/// # Returns
+/// * `Result<Option<T>, String>` - The merged setting or an error if the plans set it to different values
+fn merge_setting<T: PartialEq>(key: &str, setting: Option<T>, other: Option<T>) -> Result<Option<T>, String> {
+    match (setting, other) {
+        (Some(setting), Some(other)) if setting != other => Err(format!("{} differs from the {} of the other seating plans", key, key)),
+        (setting, other) => Ok(setting.or(other))
     }
 }
 
@@ -79,7 +411,9 @@ mod tests {
 
     use super::*;
     use crate::file_handler::MockCoreFileHandle;
+    use crate::runner_file::RunnerFile;
     use mockall::predicate::eq;
+    use std::collections::HashMap;
 
     #[test]
     fn test_from_file() {
@@ -91,7 +425,8 @@ mod tests {
                 Dependency {
                     name: "institution".to_string(),
                     url: "https://github.com/yellow-bird-consult/institution.git".to_string(),
-                    branch: "infrastructure".to_string(),
+                    branch: Some("infrastructure".to_string()),
+                    ..Default::default()
                 },
             ]
         );
@@ -100,6 +435,274 @@ mod tests {
             seating_plan.venue,
             "./sandbox/services/".to_string()
         );
+        assert_eq!(seating_plan.command_defaults, None);
+    }
+
+    #[test]
+    fn test_from_handle() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_read_to_string()
+            .with(eq(Path::new("plan.yml")))
+            .times(1)
+            .returning(|_| Ok("attendees:\n  - name: auth\n    url: git@github.com:org/auth.git\n    tag: v2.0.0\nvenue: ./venue\n".to_string()));
+        mock_handle.expect_read_to_string()
+            .with(eq(Path::new("missing.yml")))
+            .returning(|_| Err(std::io::Error::new(std::io::ErrorKind::NotFound, "not found")));
+        mock_handle.expect_read_to_string()
+            .with(eq(Path::new("typo.yml")))
+            .returning(|_| Ok("attendees: []\nvenue: ./venue\nengin: podman\n".to_string()));
+
+        let seating_plan = SeatingPlan::from_handle("plan.yml".to_string(), &mock_handle).unwrap();
+        assert_eq!(seating_plan.attendees[0].tag, Some("v2.0.0".to_string()));
+        assert_eq!(seating_plan.venue, "./venue");
+        assert!(matches!(
+            SeatingPlan::from_handle("missing.yml".to_string(), &mock_handle),
+            Err(WedpError::ConfigNotFound(path)) if path == "missing.yml"
+        ));
+        assert!(matches!(
+            SeatingPlan::from_handle("typo.yml".to_string(), &mock_handle),
+            Err(WedpError::ConfigParse { path, message }) if path == "typo.yml" && message.contains("did you mean `engine`?")
+        ));
+    }
+
+    #[test]
+    fn test_from_file_command_defaults() {
+        let seating_plan = SeatingPlan::from_file("tests/seating_plan.yml".to_string()).unwrap();
+
+        let mut run_defaults = HashMap::new();
+        run_defaults.insert("detach".to_string(), true);
+        run_defaults.insert("wait".to_string(), true);
+        let mut build_defaults = HashMap::new();
+        build_defaults.insert("pull".to_string(), true);
+        let mut expected = HashMap::new();
+        expected.insert("run".to_string(), run_defaults);
+        expected.insert("build".to_string(), build_defaults);
+
+        assert_eq!(seating_plan.command_defaults, Some(expected));
+    }
+
+    #[test]
+    fn test_from_file_clone_depth() {
+        let seating_plan = SeatingPlan::from_file("tests/seating_plan.yml".to_string()).unwrap();
+        assert_eq!(seating_plan.attendees[0].clone_depth, None);
+        assert_eq!(seating_plan.attendees[1].clone_depth, Some(1));
+        assert_eq!(seating_plan.attendees[1].url, "git@example.com:jane-doe.git");
+
+        let contents = "attendees:\n  - name: auth\n    url: https://github.com/org/auth.git\n    branch: main\n  \
+                        - name: billing\n    url: https://github.com/org/billing.git\n    branch: main\n    clone_depth: 10\n\
+                        venue: ./services\nclone_depth: 1\n";
+        let seating_plan = SeatingPlan::parse(contents, "wedding_planner.yml").unwrap();
+        assert_eq!(seating_plan.attendees[0].clone_depth, Some(1));
+        assert_eq!(seating_plan.attendees[1].clone_depth, Some(10));
+
+        let contents = contents.replace("clone_depth: 1\n", "clone_depth: 0\n");
+        assert!(matches!(
+            SeatingPlan::parse(&contents, "wedding_planner.yml"),
+            Err(WedpError::InvalidConfig(message)) if message == "clone_depth of auth must be at least 1 for wedding_planner.yml"
+        ));
+    }
+
+    #[test]
+    fn test_parse_auth_and_git_protocol() {
+        let contents = "attendees:\n  - name: auth\n    url: https://github.com/org/auth.git\n    branch: main\n  \
+                        - name: billing\n    url: https://gitlab.com/org/billing.git\n    branch: main\n    \
+                        auth:\n      token_env: GITLAB_TOKEN\n    git_protocol: ssh\n\
+                        venue: ./services\nauth:\n  token_env: GH_TOKEN\ngit_protocol: https\n";
+        let seating_plan = SeatingPlan::parse(contents, "wedding_planner.yml").unwrap();
+        assert_eq!(seating_plan.attendees[0].auth, Some(GitAuth { token_env: "GH_TOKEN".to_string() }));
+        assert_eq!(seating_plan.attendees[0].git_protocol, Some(GitProtocol::Https));
+        assert_eq!(seating_plan.attendees[1].auth, Some(GitAuth { token_env: "GITLAB_TOKEN".to_string() }));
+        assert_eq!(seating_plan.attendees[1].git_protocol, Some(GitProtocol::Ssh));
+
+        let error = SeatingPlan::parse("attendees: []\nvenue: ./venue\ngit_protocol: ftp\n", "wedding_planner.yml").unwrap_err();
+        assert!(error.to_string().contains("unknown variant `ftp`, expected `ssh` or `https`"), "{}", error);
+        let error = SeatingPlan::parse("attendees: []\nvenue: ./venue\ngit_protocol: ssh\n", "seating_plan.yml").unwrap()
+            .merge(SeatingPlan::parse("attendees: []\nvenue: ./venue\ngit_protocol: https\n", "seating_plan.yml").unwrap(), "data.yml")
+            .unwrap_err();
+        assert_eq!(error.to_string(), "git_protocol differs from the git_protocol of the other seating plans in data.yml");
+    }
+
+    #[test]
+    fn test_parse_path_dependency() {
+        let contents = "attendees:\n  - name: auth\n    path: ../auth_service\n    link: true\nvenue: ./services\n";
+        let seating_plan = SeatingPlan::parse(contents, "wedding_planner.yml").unwrap();
+        assert_eq!(seating_plan.attendees[0].path, Some("../auth_service".to_string()));
+        assert!(seating_plan.attendees[0].link);
+        assert_eq!(seating_plan.attendees[0].url, "");
+        assert_eq!(seating_plan.problems(), vec!["path ../auth_service of auth is not a directory".to_string()]);
+
+        let both = "attendees:\n  - name: auth\n    url: https://github.com/org/auth.git\n    branch: main\n    path: ./tests\nvenue: ./services\n";
+        assert_eq!(
+            SeatingPlan::parse(both, "wedding_planner.yml").unwrap_err().to_string(),
+            WedpError::InvalidConfig(
+                "auth has a url and a path, a path dependency is not cloned so only one can be given for wedding_planner.yml".to_string()
+            ).to_string()
+        );
+        let neither = "attendees:\n  - name: auth\n    branch: main\nvenue: ./services\n";
+        assert!(matches!(
+            SeatingPlan::parse(neither, "wedding_planner.yml"),
+            Err(WedpError::InvalidConfig(message)) if message == "auth needs a url or a path for wedding_planner.yml"
+        ));
+        let link_without_path = "attendees:\n  - name: auth\n    url: https://github.com/org/auth.git\n    branch: main\n    link: true\nvenue: ./services\n";
+        assert!(matches!(
+            SeatingPlan::parse(link_without_path, "wedding_planner.yml"),
+            Err(WedpError::InvalidConfig(message)) if message == "auth sets link without a path, only a path can be linked for wedding_planner.yml"
+        ));
+    }
+
+    #[test]
+    fn test_from_file_inline_invite() {
+        let seating_plan = SeatingPlan::from_file("tests/inline_invite_seating_plan.yml".to_string()).unwrap();
+        let invite = seating_plan.attendees[0].invite.as_ref().unwrap();
+        assert_eq!(invite.build_root, ".");
+        assert_eq!(invite.build_files.as_ref().unwrap()["aarch64"], "build/Dockerfile.aarch64");
+        assert_eq!(invite.runner_files, vec![
+            RunnerFile::from("runner_files/base.yml"),
+            RunnerFile::Conditional { path: "runner_files/database.yml".to_string(), when: Some("${WEDP_INLINE_DATABASE}".to_string()) },
+        ]);
+
+        let contents = "attendees:\n  - name: auth\n    url: https://github.com/org/auth.git\n    branch: main\n    \
+                        invite:\n      build_root: .\n      runner_file:\n        - base.yml\nvenue: ./sandbox\n";
+        let error = SeatingPlan::parse(contents, "plan.yml").unwrap_err().to_string();
+        assert!(error.contains("unknown field `runner_file`"), "{}", error);
+    }
+
+    #[test]
+    fn test_parse_compose_command() {
+        let contents = "attendees: []\nvenue: ./venue\ncompose_command: docker compose\n";
+        let seating_plan = SeatingPlan::parse(contents, "seating_plan.yml").unwrap();
+        assert_eq!(seating_plan.compose_command, Some("docker compose".to_string()));
+
+        let contents = "attendees: []\nvenue: ./venue\ncompose_command: docker-compose && id\n";
+        assert_eq!(
+            SeatingPlan::parse(contents, "seating_plan.yml").map_err(|error| error.to_string()),
+            Err("compose_command docker-compose && id is not a valid command for seating_plan.yml".to_string())
+        );
+    }
+
+    #[test]
+    fn test_expand_paths() {
+        std::env::set_var("WEDP_TEST_SERVICES", "/srv/services");
+        let contents = "attendees:\n  - name: auth\n    url: ${WEDP_TEST_SERVICES}/auth.git\n    branch: main\nvenue: $WEDP_TEST_SERVICES/venue\n";
+        let seating_plan = SeatingPlan::parse(contents, "seating_plan.yml").unwrap();
+        assert_eq!(seating_plan.venue, "$WEDP_TEST_SERVICES/venue");
+
+        let seating_plan = seating_plan.expand_paths("seating_plan.yml").unwrap();
+        assert_eq!(seating_plan.venue, "/srv/services/venue");
+        assert_eq!(seating_plan.attendees[0].url, "/srv/services/auth.git");
+
+        let contents = "attendees: []\nvenue: ${WEDP_TEST_UNSET}/venue\n";
+        assert_eq!(
+            SeatingPlan::parse(contents, "seating_plan.yml").unwrap().expand_paths("seating_plan.yml").map_err(|error| error.to_string()),
+            Err("venue ${WEDP_TEST_UNSET}/venue uses WEDP_TEST_UNSET which is not set for seating_plan.yml".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_engine() {
+        let contents = "attendees: []\nvenue: ./venue\nengine: podman\n";
+        let seating_plan = SeatingPlan::parse(contents, "seating_plan.yml").unwrap();
+        assert_eq!(seating_plan.engine, Some(ContainerEngine::Podman));
+
+        let contents = "attendees: []\nvenue: ./venue\nengine: containerd\n";
+        let error = SeatingPlan::parse(contents, "seating_plan.yml").unwrap_err().to_string();
+        assert!(error.starts_with("Could not parse file: engine: unknown variant `containerd`"), "{}", error);
+    }
+
+    #[test]
+    fn test_parse_operation_limits() {
+        let contents = "attendees: []\nvenue: ./venue\nmax_docker_ops: 2\nmax_git_ops: 4\n";
+        let seating_plan = SeatingPlan::parse(contents, "seating_plan.yml").unwrap();
+        assert_eq!((seating_plan.max_docker_ops, seating_plan.max_git_ops), (Some(2), Some(4)));
+
+        let contents = "attendees: []\nvenue: ./venue\nmax_git_ops: 0\n";
+        assert_eq!(
+            SeatingPlan::parse(contents, "seating_plan.yml").map_err(|error| error.to_string()),
+            Err("max_git_ops must be at least 1 for seating_plan.yml".to_string())
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let core = SeatingPlan::from_file("tests/merge_core_seating_plan.yml".to_string()).unwrap();
+        let data = SeatingPlan::from_file("tests/merge_data_seating_plan.yml".to_string()).unwrap();
+        let seating_plan = core.merge(data, "tests/merge_data_seating_plan.yml").unwrap();
+        let names: Vec<&str> = seating_plan.attendees.iter().map(|attendee| attendee.name.as_str()).collect();
+        assert_eq!(names, vec!["auth", "billing", "postgres", "redis"]);
+        assert_eq!(seating_plan.venue, "./venue");
+        assert_eq!(seating_plan.restart_policy, Some("unless-stopped".to_string()));
+    }
+
+    #[test]
+    fn test_merge_conflicts() {
+        let plan = |contents: &str| SeatingPlan::parse(contents, "seating_plan.yml").unwrap();
+        let core = "attendees:\n  - name: auth\n    url: https://github.com/org/auth\n    branch: main\nvenue: ./venue\nengine: docker\n";
+
+        let error = plan(core).merge(plan(core), "data.yml").unwrap_err();
+        assert!(matches!(&error, WedpError::InvalidConfig(message) if message == "attendee auth is already in another seating plan in data.yml"));
+
+        let error = plan(core).merge(plan("attendees: []\nvenue: ./other\n"), "data.yml").unwrap_err();
+        assert_eq!(error.to_string(), "venue ./other does not match the venue ./venue of the other seating plans in data.yml");
+
+        let error = plan(core).merge(plan("attendees: []\nvenue: ./venue\nengine: podman\n"), "data.yml").unwrap_err();
+        assert_eq!(error.to_string(), "engine differs from the engine of the other seating plans in data.yml");
+        assert!(plan(core).merge(plan("attendees: []\nvenue: venue\nengine: docker\n"), "data.yml").is_ok());
+    }
+
+    #[test]
+    fn test_parse_restart_policy() {
+        let contents = "attendees: []\nvenue: ./venue\nrestart_policy: unless-stopped\n";
+        let seating_plan = SeatingPlan::parse(contents, "seating_plan.yml").unwrap();
+        assert_eq!(seating_plan.restart_policy, Some("unless-stopped".to_string()));
+
+        let contents = "attendees: []\nvenue: ./venue\nrestart_policy: sometimes\n";
+        assert_eq!(
+            SeatingPlan::parse(contents, "seating_plan.yml").map_err(|error| error.to_string()),
+            Err("restart_policy sometimes is not one of no, always, on-failure, unless-stopped for seating_plan.yml".to_string())
+        );
+    }
+
+    #[test]
+    fn test_from_file_branch_and_rev() {
+        let result = SeatingPlan::from_file("tests/seating_plan_branch_and_rev.yml".to_string());
+        assert!(matches!(result, Err(WedpError::InvalidConfig(_))));
+        assert_eq!(
+            result.map_err(|error| error.to_string()),
+            Err("institution has a rev and a branch, tag, or commit, a rev can only be given on its own for tests/seating_plan_branch_and_rev.yml".to_string())
+        );
+    }
+
+    #[test]
+    fn test_from_file_lenient() {
+        let (seating_plan, unknown) = SeatingPlan::from_file_lenient("tests/validate/seating_plan.yml".to_string()).unwrap();
+        assert_eq!(unknown.len(), 2, "{:?}", unknown);
+        assert!(unknown[0].starts_with("tests/validate/seating_plan.yml: attendees[0]: unknown field `brnch` at line 4 column 5"), "{}", unknown[0]);
+        assert!(unknown[1].starts_with("tests/validate/seating_plan.yml: unknown field `restart_polcy` at line 12 column 1"), "{}", unknown[1]);
+        assert_eq!(
+            seating_plan.problems(),
+            vec![
+                "attendee typo_repo is listed more than once".to_string(),
+                "url billing service of billing does not look like a git remote, expected a URL such as \
+                 https://github.com/org/repo.git, git@github.com:org/repo.git, or a path to a local repository".to_string(),
+                "typo_repo needs a branch, tag, commit, or rev".to_string(),
+            ]
+        );
+        assert!(matches!(SeatingPlan::from_file("tests/validate/seating_plan.yml".to_string()), Err(WedpError::ConfigParse { .. })));
+    }
+
+    #[test]
+    fn test_problems() {
+        let mut seating_plan = SeatingPlan::from_file("tests/live_test.yml".to_string()).unwrap();
+        assert_eq!(seating_plan.problems(), Vec::<String>::new());
+        seating_plan.restart_policy = Some("sometimes".to_string());
+        seating_plan.max_git_ops = Some(0);
+        assert_eq!(
+            seating_plan.problems(),
+            vec![
+                "restart_policy sometimes is not one of no, always, on-failure, unless-stopped".to_string(),
+                "max_git_ops must be at least 1".to_string(),
+            ]
+        );
     }
 
     #[test]
@@ -107,7 +710,7 @@ mod tests {
         let seating_plan = SeatingPlan::from_file("tests/live_test.yml".to_string()).unwrap();
 
         let mut mock_handle = MockCoreFileHandle::new();
-        let venue_path = Path::new("./sandbox/services/");
+        let venue_path = Path::new("sandbox/services");
 
         mock_handle.expect_create_directory_if_not_exists()
             .with(eq(venue_path))
@@ -119,4 +722,67 @@ mod tests {
         assert!(result.is_ok());
         mock_handle.checkpoint(); 
     }
+
+    #[test]
+    fn test_derived_paths() {
+        let mut seating_plan = SeatingPlan::from_file("tests/live_test.yml".to_string()).unwrap();
+        assert_eq!(seating_plan.venue_path(), PathBuf::from("sandbox/services"));
+        assert_eq!(seating_plan.dependency_path("auth"), PathBuf::from("sandbox/services/auth"));
+        assert_eq!(seating_plan.full_venue_path(Path::new("/work/")), "/work/sandbox/services");
+
+        seating_plan.venue = "/venue//./services/".to_string();
+        assert_eq!(seating_plan.dependency_path("auth"), PathBuf::from("/venue/services/auth"));
+        assert_eq!(seating_plan.full_venue_path(Path::new("/work")), "/venue/services");
+    }
+
+    #[test]
+    fn test_removable_dependency_path() {
+        let seating_plan = SeatingPlan::from_file("tests/live_test.yml".to_string()).unwrap();
+        assert_eq!(seating_plan.removable_dependency_path("auth"), Ok(PathBuf::from("sandbox/services/auth")));
+        for name in ["..", "../auth", "auth/..", "/etc", "", ".", "auth/nested"] {
+            assert_eq!(
+                seating_plan.removable_dependency_path(name),
+                Err(format!("{} is not a directory inside the venue {}, refusing to remove it", name, seating_plan.venue)),
+                "{}", name
+            );
+        }
+    }
+
+    #[test]
+    fn test_relative_to() {
+        let mut seating_plan = SeatingPlan::from_file("tests/live_test.yml".to_string()).unwrap();
+        seating_plan.attendees[0].path = Some("./local/auth".to_string());
+        seating_plan.attendees.push(Dependency { name: "billing".to_string(), path: Some("/srv/billing".to_string()), ..Default::default() });
+
+        let seating_plan = seating_plan.relative_to(Path::new("/work/project"));
+
+        assert_eq!(seating_plan.venue, "/work/project/sandbox/services");
+        assert_eq!(seating_plan.attendees[0].path, Some("/work/project/local/auth".to_string()));
+        assert_eq!(seating_plan.attendees[1].path, Some("/srv/billing".to_string()));
+        assert_eq!(
+            seating_plan.removable_venue_path(Path::new("/work/project/sandbox/services/auth"), None),
+            Err("venue /work/project/sandbox/services resolves to /work/project/sandbox/services, refusing to remove it".to_string())
+        );
+    }
+
+    #[test]
+    fn test_removable_venue_path() {
+        let mut seating_plan = SeatingPlan::from_file("tests/live_test.yml".to_string()).unwrap();
+        let cwd = Path::new("/work/project");
+        let home = Some(Path::new("/home/dev"));
+        assert_eq!(seating_plan.removable_venue_path(cwd, home), Ok(PathBuf::from("sandbox/services")));
+        seating_plan.venue = "../venue".to_string();
+        assert_eq!(seating_plan.removable_venue_path(cwd, home), Ok(PathBuf::from("../venue")));
+        for (venue, resolved) in [
+            (".", "/work/project"), ("./", "/work/project"), ("..", "/work"), ("sandbox/../..", "/work"),
+            ("/", "/"), ("/../..", "/"), ("/home/dev/", "/home/dev"), ("../../home/dev", "/home/dev")
+        ] {
+            seating_plan.venue = venue.to_string();
+            assert_eq!(
+                seating_plan.removable_venue_path(cwd, home),
+                Err(format!("venue {} resolves to {}, refusing to remove it", venue, resolved)),
+                "{}", venue
+            );
+        }
+    }
 }
@@ -0,0 +1,144 @@
+//! Persists the state wedp keeps about a venue in the ```.wedp``` directory of the venue. Each kind of state is
+//! a JSON file in the directory, read and written through a ```CoreFileHandle``` so a dry run changes nothing.
+//! Every file is stamped with the version of wedp and the schema it was written with, see ```schema```.
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::file_handler::CoreFileHandle;
+use crate::schema::{read_stamped, stamp};
+
+
+/// The directory in the venue the state is kept in.
+pub const STATE_DIR: &str = ".wedp";
+
+
+/// The state files of a venue.
+///
+/// # Fields
+/// * `directory` - The ```.wedp``` directory of the venue
+/// * `handle` - The file handle used to read and write the state files
+pub struct VenueState<'a> {
+    pub directory: PathBuf,
+    handle: &'a dyn CoreFileHandle,
+}
+
+impl<'a> VenueState<'a> {
+
+    /// Creates a new VenueState struct.
+    ///
+    /// # Arguments
+    /// * `venue` - The path to the venue
+    /// * `handle` - The file handle used to read and write the state files
+    ///
+    /// # Returns
+    /// A new VenueState struct
+    pub fn new(venue: &Path, handle: &'a dyn CoreFileHandle) -> Self {
+        VenueState { directory: venue.join(STATE_DIR), handle }
+    }
+
+    /// Loads a state file, a file that has not been written yet loads as the default value and a file written
+    /// with an older schema is migrated.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the state file
+    ///
+    /// # Returns
+    /// * `Result<T, String>` - The state or an error if the file could not be read, parsed, or migrated, or was
+    ///   written by a newer version of wedp
+    pub fn load<T: DeserializeOwned + Default>(&self, name: &str) -> Result<T, String> {
+        let path = self.directory.join(name);
+        match self.handle.read_to_string(&path) {
+            Ok(contents) => read_stamped(name, &path.to_string_lossy(), &contents),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(T::default()),
+            Err(error) => Err(format!("Could not read {}: {}", path.to_string_lossy(), error))
+        }
+    }
+
+    /// Writes a state file stamped with the current version and schema, creating the ```.wedp``` directory if
+    /// needed.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the state file
+    /// * `state` - The state to write
+    ///
+    /// # Returns
+    /// * `Result<(), String>` - An error if the file could not be written
+    pub fn save<T: Serialize>(&self, name: &str, state: &T) -> Result<(), String> {
+        let path = self.directory.join(name);
+        let contents = serde_json::to_string_pretty(&stamp(state)).map_err(|error| error.to_string())?;
+        self.handle.create_directory_if_not_exists(&self.directory)
+            .and_then(|_| self.handle.write(&path, &contents))
+            .map_err(|error| format!("Could not write {}: {}", path.to_string_lossy(), error))
+    }
+
+    /// Removes a state file so it loads as the default value again.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the state file
+    ///
+    /// # Returns
+    /// * `Result<(), String>` - An error if the file exists but could not be removed
+    pub fn remove(&self, name: &str) -> Result<(), String> {
+        let path = self.directory.join(name);
+        match self.handle.remove(&path) {
+            Ok(_) => Ok(()),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(()),
+            Err(error) => Err(format!("Could not remove {}: {}", path.to_string_lossy(), error))
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::file_handler::MockCoreFileHandle;
+    use mockall::predicate::eq;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_load_missing() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_read_to_string()
+            .with(eq(PathBuf::from("venue/.wedp/stats.json")))
+            .returning(|_| Err(std::io::Error::from(ErrorKind::NotFound)));
+        let state = VenueState::new(Path::new("venue"), &mock_handle);
+        assert_eq!(state.load::<BTreeMap<String, u64>>("stats.json"), Ok(BTreeMap::new()));
+    }
+
+    #[test]
+    fn test_load_corrupt() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_read_to_string().returning(|_| Ok("{".to_string()));
+        let state = VenueState::new(Path::new("venue"), &mock_handle);
+        assert!(state.load::<BTreeMap<String, u64>>("stats.json").unwrap_err().starts_with("Could not parse venue/.wedp/stats.json"));
+    }
+
+    #[test]
+    fn test_save() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_create_directory_if_not_exists()
+            .with(eq(PathBuf::from("venue/.wedp")))
+            .times(1)
+            .returning(|_| Ok(()));
+        mock_handle.expect_write()
+            .with(eq(PathBuf::from("venue/.wedp/stats.json")), eq(format!("{{\n  \"wedp_version\": \"{}\",\n  \"schema\": 1,\n  \"auth\": 2\n}}", crate::schema::WEDP_VERSION)))
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let state = VenueState::new(Path::new("venue"), &mock_handle);
+        let mut counters = BTreeMap::new();
+        counters.insert("auth".to_string(), 2u64);
+        assert_eq!(state.save("stats.json", &counters), Ok(()));
+    }
+
+    #[test]
+    fn test_remove_missing() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_remove().returning(|_| Err(std::io::Error::from(ErrorKind::NotFound)));
+        let state = VenueState::new(Path::new("venue"), &mock_handle);
+        assert_eq!(state.remove("stats.json"), Ok(()));
+    }
+}
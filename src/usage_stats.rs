@@ -0,0 +1,227 @@
+//! Keeps usage stats for the attendees of a venue so developers can see which dependencies they actually use
+//! and how long they take to build. The stats are only written to the ```.wedp``` directory of the venue,
+//! nothing is ever sent anywhere.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::clock::Clock;
+
+
+/// The name of the stats file in the ```.wedp``` directory of the venue.
+pub const STATS_FILE: &str = "stats.json";
+
+
+/// The usage stats of a single attendee.
+///
+/// # Fields
+/// * `installs` - The number of times the attendee was installed successfully
+/// * `last_run` - When the attendee was last run in seconds since the Unix epoch
+/// * `last_build_ms` - How long the last build of the attendee took in milliseconds
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttendeeStats {
+    #[serde(default)]
+    pub installs: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_run: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_build_ms: Option<u64>,
+}
+
+
+/// The usage stats of every attendee of a venue.
+///
+/// # Fields
+/// * `attendees` - The stats of each attendee by name
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageStats {
+    #[serde(default)]
+    pub attendees: BTreeMap<String, AttendeeStats>,
+}
+
+impl UsageStats {
+
+    /// Counts an install for each of the attendees.
+    ///
+    /// # Arguments
+    /// * `names` - The names of the attendees that were installed successfully
+    pub fn record_installs(&mut self, names: &[String]) {
+        for name in names {
+            self.attendees.entry(name.clone()).or_default().installs += 1;
+        }
+    }
+
+    /// Sets the last run of each of the attendees to now.
+    ///
+    /// # Arguments
+    /// * `names` - The names of the attendees that were run
+    /// * `clock` - The clock the current time is read from
+    pub fn record_run(&mut self, names: &[String], clock: &dyn Clock) {
+        let now = clock.system_time().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        for name in names {
+            self.attendees.entry(name.clone()).or_default().last_run = Some(now);
+        }
+    }
+
+    /// Sets the last build duration of each of the attendees.
+    ///
+    /// # Arguments
+    /// * `names` - The names of the attendees that were built
+    /// * `duration` - How long the build took
+    pub fn record_build(&mut self, names: &[String], duration: Duration) {
+        for name in names {
+            self.attendees.entry(name.clone()).or_default().last_build_ms = Some(duration.as_millis() as u64);
+        }
+    }
+}
+
+
+/// Renders seconds since the Unix epoch as a UTC date and time.
+///
+/// # Arguments
+/// * `seconds` - The seconds since the Unix epoch
+///
+/// # Returns
+/// * `String` - The date and time as ```YYYY-MM-DD HH:MM:SS UTC```
+pub fn format_timestamp(seconds: u64) -> String {
+    let days = (seconds / 86400) as i64;
+    let time = seconds % 86400;
+
+    // converts the days since the epoch to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let shifted = days + 719468;
+    let era = shifted.div_euclid(146097);
+    let day_of_era = shifted.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC", year, month, day, time / 3600, time % 3600 / 60, time % 60)
+}
+
+
+/// Renders a build duration in milliseconds.
+///
+/// # Arguments
+/// * `milliseconds` - The build duration in milliseconds
+///
+/// # Returns
+/// * `String` - The duration in seconds under a minute, otherwise in minutes and seconds
+pub fn format_duration(milliseconds: u64) -> String {
+    match milliseconds < 60_000 {
+        true => format!("{:.1}s", milliseconds as f64 / 1000.0),
+        false => format!("{}m {:02}s", milliseconds / 60_000, milliseconds % 60_000 / 1000)
+    }
+}
+
+
+/// Renders the usage stats as a table. The attendees of the seating plan are listed in order even if they
+/// have never been used, followed by any attendees that have stats but are no longer in the seating plan.
+///
+/// # Arguments
+/// * `stats` - The usage stats of the venue
+/// * `names` - The names of the attendees in the seating plan
+///
+/// # Returns
+/// * `String` - The rendered table
+pub fn render_stats_table(stats: &UsageStats, names: &[String]) -> String {
+    let mut rows: Vec<&String> = names.iter().collect();
+    rows.extend(stats.attendees.keys().filter(|name| !names.contains(name)));
+
+    let mut table = format!("{:<20} {:<10} {:<24} {}\n", "DEPENDENCY", "INSTALLS", "LAST RUN", "LAST BUILD");
+    for name in rows {
+        let attendee = stats.attendees.get(name).cloned().unwrap_or_default();
+        let last_run = attendee.last_run.map(format_timestamp).unwrap_or_else(|| "-".to_string());
+        let last_build = attendee.last_build_ms.map(format_duration).unwrap_or_else(|| "-".to_string());
+        table.push_str(&format!("{:<20} {:<10} {:<24} {}\n", name, attendee.installs, last_run, last_build));
+    }
+    table
+}
+
+
+/// Renders the usage stats as JSON.
+///
+/// # Arguments
+/// * `stats` - The usage stats of the venue
+///
+/// # Returns
+/// * `String` - The stats as pretty printed JSON
+pub fn render_stats_json(stats: &UsageStats) -> String {
+    serde_json::to_string_pretty(stats).unwrap()
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::clock::MockClock;
+    use std::time::SystemTime;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_record() {
+        let mut clock = MockClock::new();
+        clock.expect_system_time().returning(|| UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+
+        let mut stats = UsageStats::default();
+        stats.record_installs(&names(&["auth", "billing"]));
+        stats.record_installs(&names(&["auth"]));
+        stats.record_run(&names(&["auth"]), &clock);
+        stats.record_build(&names(&["billing"]), Duration::from_millis(83_250));
+
+        assert_eq!(stats.attendees["auth"], AttendeeStats { installs: 2, last_run: Some(1_700_000_000), last_build_ms: None });
+        assert_eq!(stats.attendees["billing"], AttendeeStats { installs: 1, last_run: None, last_build_ms: Some(83_250) });
+    }
+
+    #[test]
+    fn test_record_run_before_epoch() {
+        let mut clock = MockClock::new();
+        clock.expect_system_time().returning(|| SystemTime::UNIX_EPOCH - Duration::from_secs(5));
+        let mut stats = UsageStats::default();
+        stats.record_run(&names(&["auth"]), &clock);
+        assert_eq!(stats.attendees["auth"].last_run, Some(0));
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29 00:00:00 UTC");
+        assert_eq!(format_timestamp(1_700_000_000), "2023-11-14 22:13:20 UTC");
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(4_300), "4.3s");
+        assert_eq!(format_duration(83_250), "1m 23s");
+    }
+
+    #[test]
+    fn test_render_stats_table() {
+        let mut stats = UsageStats::default();
+        stats.attendees.insert("auth".to_string(), AttendeeStats { installs: 2, last_run: Some(1_700_000_000), last_build_ms: Some(83_250) });
+        stats.attendees.insert("payments".to_string(), AttendeeStats { installs: 1, ..Default::default() });
+
+        assert_eq!(
+            render_stats_table(&stats, &names(&["billing", "auth"])),
+            "DEPENDENCY           INSTALLS   LAST RUN                 LAST BUILD\n\
+             billing              0          -                        -\n\
+             auth                 2          2023-11-14 22:13:20 UTC  1m 23s\n\
+             payments             1          -                        -\n"
+        );
+    }
+
+    #[test]
+    fn test_render_stats_json() {
+        let mut stats = UsageStats::default();
+        stats.attendees.insert("auth".to_string(), AttendeeStats { installs: 2, last_run: Some(1_700_000_000), last_build_ms: None });
+        let json = render_stats_json(&stats);
+        assert_eq!(json, "{\n  \"attendees\": {\n    \"auth\": {\n      \"installs\": 2,\n      \"last_run\": 1700000000\n    }\n  }\n}");
+        assert_eq!(serde_json::from_str::<UsageStats>(&json).unwrap(), stats);
+    }
+}
@@ -0,0 +1,168 @@
+//! Builds the manifest packed into a vendored archive recording the commit each attendee was vendored at, so an
+//! air-gapped install can be traced back to the exact revisions of the repositories even though the archive
+//! holds no ```.git``` directories.
+use serde::{Deserialize, Serialize};
+
+use crate::commands::command_runner::CoreRunner;
+use crate::commands::current_commit::CurrentCommitCommand;
+use crate::dependency::Dependency;
+use crate::schema::stamp;
+
+
+/// The name of the manifest written into the venue and packed into the archive.
+pub const MANIFEST_FILE: &str = "wedp-vendor-manifest.json";
+
+
+/// An attendee in the manifest of a vendored archive.
+///
+/// # Fields
+/// * `name` - The name of the attendee
+/// * `url` - The URL the attendee was cloned from
+/// * `branch` - The branch of the attendee in the seating plan
+/// * `rev` - The tag or commit of the attendee in the seating plan
+/// * `commit` - The full hash of the commit the attendee was vendored at
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub name: String,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+    pub commit: String,
+}
+
+
+/// The manifest of a vendored archive.
+///
+/// # Fields
+/// * `attendees` - The attendees in the archive in the order of the seating plan
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct VendorManifest {
+    pub attendees: Vec<ManifestEntry>,
+}
+
+
+/// Builds the manifest by reading the commit checked out for each attendee in the venue.
+///
+/// # Arguments
+/// * `attendees` - The attendees being vendored
+/// * `full_venue_path` - The full path to the venue the attendees are cloned into
+/// * `runner` - The runner used to read the checked out commits
+///
+/// # Returns
+/// * `Result<VendorManifest, String>` - The manifest or an error if a commit could not be read
+pub fn build_manifest(attendees: &[&Dependency], full_venue_path: &str, runner: &dyn CoreRunner) -> Result<VendorManifest, String> {
+    let mut entries = Vec::new();
+    for dependency in attendees {
+        let commit = CurrentCommitCommand::new(full_venue_path.to_string(), dependency.name.clone())
+            .run(runner)
+            .map_err(|error| format!("Failed to read the commit of {}: {}", dependency.name, error))?;
+        entries.push(ManifestEntry {
+            name: dependency.name.clone(),
+            url: dependency.url.clone(),
+            branch: dependency.branch.clone(),
+            rev: dependency.rev.clone(),
+            commit,
+        });
+    }
+    Ok(VendorManifest { attendees: entries })
+}
+
+
+/// Renders the manifest as JSON stamped with the current version and schema.
+///
+/// # Arguments
+/// * `manifest` - The manifest of the vendored archive
+///
+/// # Returns
+/// * `String` - The manifest as pretty printed JSON
+pub fn render_manifest(manifest: &VendorManifest) -> String {
+    serde_json::to_string_pretty(&stamp(manifest)).unwrap()
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::commands::command_runner::MockCoreRunner;
+    use crate::schema::{read_stamped, WEDP_VERSION};
+    use std::os::unix::process::ExitStatusExt;
+    use std::path::Path;
+    use std::process::{ExitStatus, Output};
+
+    fn attendees() -> Vec<Dependency> {
+        vec![
+            Dependency {
+                name: "auth".to_string(),
+                url: "https://github.com/org/auth".to_string(),
+                branch: Some("main".to_string()),
+                ..Default::default()
+            },
+            Dependency {
+                name: "billing".to_string(),
+                url: "https://github.com/org/billing".to_string(),
+                rev: Some("v1.4.0".to_string()),
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn expect_commit(mock_runner: &mut MockCoreRunner, name: &str, commit: &'static str) {
+        let dir = Path::new("/venue").join(name);
+        mock_runner.expect_run_in_dir()
+            .withf(move |path, program, args| path == dir && program == "git" && args == ["rev-parse", "HEAD"])
+            .times(1)
+            .returning(move |_, _, _| Ok(Output { status: ExitStatus::from_raw(0), stdout: format!("{}\n", commit).into_bytes(), stderr: Vec::new() }));
+    }
+
+    #[test]
+    fn test_build_manifest() {
+        let mut mock_runner = MockCoreRunner::new();
+        expect_commit(&mut mock_runner, "auth", "1111111111111111111111111111111111111111");
+        expect_commit(&mut mock_runner, "billing", "2222222222222222222222222222222222222222");
+        let attendees = attendees();
+        let attendees: Vec<&Dependency> = attendees.iter().collect();
+
+        let manifest = build_manifest(&attendees, "/venue", &mock_runner).unwrap();
+        let commits: Vec<(&str, &str)> = manifest.attendees.iter()
+                                                 .map(|entry| (entry.name.as_str(), entry.commit.as_str()))
+                                                 .collect();
+        assert_eq!(commits, vec![
+            ("auth", "1111111111111111111111111111111111111111"),
+            ("billing", "2222222222222222222222222222222222222222"),
+        ]);
+        assert_eq!(manifest.attendees[1].rev, Some("v1.4.0".to_string()));
+    }
+
+    #[test]
+    fn test_build_manifest_missing_commit() {
+        let mut mock_runner = MockCoreRunner::new();
+        mock_runner.expect_run_in_dir()
+            .returning(|_, _, _| Ok(Output { status: ExitStatus::from_raw(128 << 8), stdout: Vec::new(), stderr: b"fatal: not a git repository".to_vec() }));
+        let attendees = attendees();
+        let attendees: Vec<&Dependency> = attendees.iter().collect();
+        assert!(build_manifest(&attendees, "/venue", &mock_runner).unwrap_err().starts_with("Failed to read the commit of auth: "));
+    }
+
+    #[test]
+    fn test_render_manifest() {
+        let manifest = VendorManifest {
+            attendees: vec![ManifestEntry {
+                name: "auth".to_string(),
+                url: "https://github.com/org/auth".to_string(),
+                branch: Some("main".to_string()),
+                rev: None,
+                commit: "1111111111111111111111111111111111111111".to_string(),
+            }]
+        };
+        let rendered = render_manifest(&manifest);
+        assert_eq!(
+            rendered,
+            format!("{{\n  \"wedp_version\": \"{}\",\n  \"schema\": 1,\n  \"attendees\": [\n    {{\n      \"name\": \"auth\",\n      \"url\": \"https://github.com/org/auth\",\n      \
+             \"branch\": \"main\",\n      \"commit\": \"1111111111111111111111111111111111111111\"\n    }}\n  ]\n}}", WEDP_VERSION)
+        );
+        assert_eq!(read_stamped::<VendorManifest>(MANIFEST_FILE, MANIFEST_FILE, &rendered), Ok(manifest));
+    }
+}
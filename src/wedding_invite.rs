@@ -8,9 +8,12 @@
 //! runner_files:
 //!   - runner_files/base.yml
 //!   - runner_files/database.yml
+//!   - path: runner_files/debug.yml
+//!     when: "${WEDP_DEBUG}"
 //! build_files:
 //!   x86_64: builds/Dockerfile.x86_64
 //!   aarch64: builds/Dockerfile.aarch64
+//!   default: builds/Dockerfile
 //! init_build:
 //!   build_files:
 //!     x86_64: builds/Dockerfile.x86_64
@@ -19,19 +22,31 @@
 //! ```
 use serde::{Deserialize, Serialize};
 use serde_yaml::{self};
-use std::fs::File;
 use std::collections::HashMap;
-use std::path::Path;
-use crate::file_handler::CoreFileHandle;
+use std::path::{Path, PathBuf};
+use crate::file_handler::{CoreFileHandle, FileHandle};
+use crate::compose_file::ComposeFileRef;
+use crate::cpu_data::CpuType;
+use crate::error::WedpError;
+use crate::image_arch::arch_suffix;
+use crate::interpolate::read_interpolated;
+use crate::paths::{join_normalised, resolve_parents};
+use crate::runner_file::{included_paths, RunnerFile};
+use crate::yaml_error::{describe_yaml_error, strip_unknown_fields, with_unknown_fields};
+
+
+/// The key of ```build_files``` used for a CPU type that has no Dockerfile of its own.
+pub const DEFAULT_BUILD_FILE: &str = "default";
 
 
 /// A struct to hold the local data around a build for an init pod.
 ///
 /// # Fields
-/// * `build_files` - A map of Dockerfiles relating to CPU information
+/// * `build_files` - A map of Dockerfiles relating to CPU information, with a ```default``` for any other CPU type
 /// * `build_root` - The root of the build (where the Dockerfile needs to be to run)
 /// * `build_lock` - Whether to lock the build to a specific CPU architecture, if ```true``` the CPU will not be checked and the Dockerfile will not be moved
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
 pub struct InitBuild {
     pub build_files: HashMap<String, String>,
     pub build_root: String,
@@ -46,77 +61,135 @@ pub struct TestBuild {
 }
 
 
+/// A Dockerfile that is copied into a build root before building.
+///
+/// # Fields
+/// * `from` - The Dockerfile for the CPU type
+/// * `to` - The Dockerfile in the build root that is overwritten
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildFileCopy {
+    pub from: PathBuf,
+    pub to: PathBuf
+}
+
+
 /// A struct to hold the local data around a build.
 ///
 /// # Fields
-/// * `build_files` - A map of Dockerfiles relating to CPU information
+/// * `build_files` - A map of Dockerfiles relating to CPU information, with a ```default``` for any other CPU type
 /// * `build_root` - The root of the build (where the Dockerfile needs to be to run)
 /// * `package_file` - The location of the docker-compose file to run the build
 /// * `init_build` - The location of the data needed for an init pod build
-/// * `runner_files` - The location of the docker-compose files to run the build
+/// * `runner_files` - The location of the docker-compose files to run the build, each optionally included only ```when``` a condition holds
 /// * `remote_runner_files` - The location of the docker-compose files to run the build from a remote dockerhub repository
 /// * `build_lock` - Whether to lock the build to a specific CPU architecture, if ```true``` the CPU will not be checked and the Dockerfile will not be moved
 /// * `dev_runner_files` - The location of the docker-compose files to run the build in development mode
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// * `remote_image_arch_suffixes` - The suffix of the image tags for each CPU type, for remote images published as
+///   separate tags per CPU type rather than a manifest list
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
 pub struct WeddingInvite {
     pub build_files: Option<HashMap<String, String>>,
     pub build_root: String,
     pub init_build: Option<InitBuild>,
-    pub runner_files: Vec<String>,
-    pub remote_runner_files: Option<Vec<String>>,
+    pub runner_files: Vec<RunnerFile>,
+    pub remote_runner_files: Option<Vec<RunnerFile>>,
     pub build_lock: Option<bool>,
-    pub dev_runner_files: Option<Vec<String>>,
+    pub dev_runner_files: Option<Vec<RunnerFile>>,
+    pub remote_image_arch_suffixes: Option<HashMap<String, String>>,
 }
 
 
 impl WeddingInvite {
 
-    /// Create a new WeddingInvite struct from a file
+    /// Create a new WeddingInvite struct from a file on disk, substituting the environment into it first
     ///
     /// # Arguments
     /// * `path` - The path to the file to read
     ///
     /// # Returns
-    /// * `Result<WeddingInvite, String>` - A WeddingInvite struct or an error message
-    pub fn from_file(path: String) -> Result<Self, String> {
-        let file = match File::open(&path) {
-            Ok(f) => f,
-            Err(e) => return Err(format!("Could not open file: {} for {}", e, path))
-        };
-        let invite_data: WeddingInvite = match serde_yaml::from_reader(file) {
+    /// * `Result<WeddingInvite, WedpError>` - A WeddingInvite struct or the error reading, substituting, or parsing the file
+    pub fn from_file(path: String) -> Result<Self, WedpError> {
+        WeddingInvite::from_handle(path, &FileHandle {})
+    }
+
+    /// Create a new WeddingInvite struct from a file read through a file handle, substituting the environment 
+    /// into it first
+    ///
+    /// # Arguments
+    /// * `path` - The path to the file to read
+    /// * `handle` - The file handle the file is read with
+    ///
+    /// # Returns
+    /// * `Result<WeddingInvite, WedpError>` - A WeddingInvite struct or the error reading, substituting, or parsing the file
+    pub fn from_handle(path: String, handle: &dyn CoreFileHandle) -> Result<Self, WedpError> {
+        let contents = read_interpolated(&path, handle)?;
+        let invite_data: WeddingInvite = match serde_yaml::from_str(&contents) {
             Ok(ld) => ld,
-            Err(e) => return Err(format!("Could not read values: {} for {}", e, path))
+            Err(e) => return Err(WedpError::ConfigParse { path, message: describe_yaml_error(&e, &contents) })
         };
         Ok(invite_data)
     }
 
+    /// Create a new WeddingInvite struct from a file for ```validate```, leaving out the keys it does not know so
+    /// every unknown key is reported and the rest of the wedding invite can still be checked
+    ///
+    /// # Arguments
+    /// * `path` - The path to the file to read
+    ///
+    /// # Returns
+    /// * `Result<(WeddingInvite, Vec<String>), WedpError>` - The WeddingInvite without the unknown keys and a 
+    ///   problem for every unknown key, or the error reading, substituting, or parsing the file
+    pub fn from_file_lenient(path: String) -> Result<(Self, Vec<String>), WedpError> {
+        let contents = read_interpolated(&path, &FileHandle {})?;
+        let (contents, unknown) = strip_unknown_fields::<WeddingInvite>(&contents);
+        match serde_yaml::from_str(&contents) {
+            Ok(invite_data) => Ok((invite_data, unknown)),
+            Err(e) => Err(with_unknown_fields(WedpError::ConfigParse { path, message: describe_yaml_error(&e, &contents) }, &unknown))
+        }
+    }
+
     /// Copies the correct Dockerfile to the build root.
     ///
     /// # Arguments
     /// * `venue_path` - The path to the venue directory where all the dependencies are stored
     /// * `name` - The name of the dependency in the venue directory
+    /// * `arch` - The CPU type to pick the Dockerfile for, ```None``` uses the CPU type of the host
     /// * `handle` - A FileHandle struct to handle the copying of the build file
     /// 
     /// # Returns
     /// * `io::Result<u64>` - The number of bytes copied
-    pub fn prepare_build_file(&self, venue_path: &String, name: &String, handle: &dyn CoreFileHandle) -> std::io::Result<u64> {
-        if let Some(lock) = self.build_lock {
-            if lock == true {
-                return Ok(0)
-            }
+    pub fn prepare_build_file(&self, venue_path: &str, name: &str, arch: Option<&str>, handle: &dyn CoreFileHandle) -> std::io::Result<u64> {
+        match self.build_file_copy(venue_path, name, arch)? {
+            Some(copy) => handle.copy(&copy.from, &copy.to),
+            None => Ok(0)
+        }
+    }
+
+    /// Gets the Dockerfile for the CPU and where it would be copied to in the build root.
+    /// 
+    /// # Arguments
+    /// * `venue_path` - The path to the venue directory where all the dependencies are stored
+    /// * `name` - The name of the dependency in the venue directory
+    /// * `arch` - The CPU type to pick the Dockerfile for, ```None``` uses the CPU type of the host
+    /// 
+    /// # Returns
+    /// * `Result<Option<BuildFileCopy>, WedpError>` - The copy or ```None``` if the build is locked or there are no build files
+    pub fn build_file_copy(&self, venue_path: &str, name: &str, arch: Option<&str>) -> Result<Option<BuildFileCopy>, WedpError> {
+        if self.build_lock == Some(true) {
+            return Ok(None)
         }
-        let invite_path = Path::new(&venue_path).join(&name).to_string_lossy().to_string();
-        let cpu_type = super::cpu_data::CpuType::get().to_string();
-        let files_map = self.build_files.as_ref().unwrap();
-        let build_file_path = match files_map.get(&cpu_type){
-            Some(p) => p,
-            None => return Err(std::io::Error::new(std::io::ErrorKind::Other, 
-                format!("No build file for CPU type: {}", cpu_type)))
+        let files_map = match &self.build_files {
+            Some(files_map) => files_map,
+            None => return Ok(None)
         };
-        let build_path = Path::new(&invite_path).join(build_file_path);
-        let build_root_path = Path::new(&invite_path).join(&self.build_root)
-                                                                    .join("Dockerfile");
-        handle.copy(&build_path, &build_root_path)
+        let invite_path = invite_path(venue_path, name);
+        let cpu_type = build_cpu_type(arch, name)?;
+        let build_file_path = get_build_file(files_map, &cpu_type, name)?;
+        Ok(Some(BuildFileCopy {
+            from: join_normalised(&invite_path, &[build_file_path]),
+            to: join_normalised(&invite_path, &[&self.build_root, "Dockerfile"])
+        }))
     }
 
     /// Deletes the Dockerfile from the build root.
@@ -128,15 +201,13 @@ impl WeddingInvite {
     /// 
     /// # Returns
     /// * `io::Result<()>` - An empty result or an error
-    pub fn delete_build_file(&self, venue_path: &String, name: &String, handle: &dyn CoreFileHandle) -> Result<(), std::io::Error> {
+    pub fn delete_build_file(&self, venue_path: &str, name: &str, handle: &dyn CoreFileHandle) -> Result<(), std::io::Error> {
         if let Some(lock) = self.build_lock {
             if lock == true {
                 return Ok(())
             }
         }
-        let invite_path = Path::new(&venue_path).join(&name).to_string_lossy().to_string();
-        let build_root_path = Path::new(&invite_path).join(&self.build_root)
-                                                                    .join("Dockerfile");
+        let build_root_path = join_normalised(&invite_path(venue_path, name), &[&self.build_root, "Dockerfile"]);
         handle.remove(&build_root_path)
     }
 
@@ -145,32 +216,42 @@ impl WeddingInvite {
     /// # Arguments
     /// * `venue_path` - The path to the venue where all dependencies are stored
     /// * `name` - The name of the repository where we can prepare the init build
+    /// * `arch` - The CPU type to pick the Dockerfile for, ```None``` uses the CPU type of the host
     /// * `handle` - A FileHandle struct to handle the copying of the build file
     /// 
     /// # Returns
     /// * `io::Result<u64>` - The number of bytes copied
-    pub fn prepare_init_build_file(&self, venue_path: &String, name: &String, handle: &dyn CoreFileHandle) -> std::io::Result<u64> {
-
-        if None == self.init_build {
-            return Ok(0)
-        }
-        if let Some(lock) = self.init_build.as_ref().unwrap().build_lock {
-            if lock == true {
-                return Ok(0)
-            }
+    pub fn prepare_init_build_file(&self, venue_path: &str, name: &str, arch: Option<&str>, handle: &dyn CoreFileHandle) -> std::io::Result<u64> {
+        match self.init_build_file_copy(venue_path, name, arch)? {
+            Some(copy) => handle.copy(&copy.from, &copy.to),
+            None => Ok(0)
         }
-        let invite_path = Path::new(&venue_path).join(&name).to_string_lossy().to_string();
-        let cpu_type = super::cpu_data::CpuType::get().to_string();
+    }
 
-        let build_file_path = match self.init_build.as_ref().unwrap().build_files.get(&cpu_type){
-            Some(p) => p,
-            None => panic!("No build file for CPU type: {}", &cpu_type)
+    /// Gets the init Dockerfile for the CPU and where it would be copied to in the init build root.
+    /// 
+    /// # Arguments
+    /// * `venue_path` - The path to the venue where all dependencies are stored
+    /// * `name` - The name of the repository where we can prepare the init build
+    /// * `arch` - The CPU type to pick the Dockerfile for, ```None``` uses the CPU type of the host
+    /// 
+    /// # Returns
+    /// * `Result<Option<BuildFileCopy>, WedpError>` - The copy or ```None``` if there is no init build or it is locked
+    pub fn init_build_file_copy(&self, venue_path: &str, name: &str, arch: Option<&str>) -> Result<Option<BuildFileCopy>, WedpError> {
+        let init_build = match &self.init_build {
+            Some(init_build) => init_build,
+            None => return Ok(None)
         };
-
-        let build_path = Path::new(&invite_path).join(build_file_path);
-        let build_root_path = Path::new(&invite_path).join(&self.init_build.as_ref().unwrap().build_root)
-                                                                    .join("Dockerfile");
-        handle.copy(&build_path, &build_root_path)
+        if init_build.build_lock == Some(true) {
+            return Ok(None)
+        }
+        let invite_path = invite_path(venue_path, name);
+        let cpu_type = build_cpu_type(arch, name)?;
+        let build_file_path = get_build_file(&init_build.build_files, &cpu_type, name)?;
+        Ok(Some(BuildFileCopy {
+            from: join_normalised(&invite_path, &[build_file_path]),
+            to: join_normalised(&invite_path, &[&init_build.build_root, "Dockerfile"])
+        }))
     }
 
     /// Deletes the Dockerfile from the init build root.
@@ -179,7 +260,7 @@ impl WeddingInvite {
     /// * `venue_path` - The path to the venue where all dependencies are stored
     /// * `name` - The name of the repository where we can prepare the init build
     /// * `handle` - A FileHandle struct to handle the removing of the build file
-    pub fn delete_init_build_file(&self, venue_path: &String, name: &String, handle: &dyn CoreFileHandle) -> Result<(), std::io::Error> {
+    pub fn delete_init_build_file(&self, venue_path: &str, name: &str, handle: &dyn CoreFileHandle) -> Result<(), std::io::Error> {
         if None == self.init_build {
             return Ok(())
         }
@@ -188,41 +269,254 @@ impl WeddingInvite {
                 return Ok(())
             }
         }
-        let invite_path = Path::new(&venue_path).join(&name).to_string_lossy().to_string();
-        let build_root_path = Path::new(&invite_path).join(&self.init_build.as_ref().unwrap().build_root)
-                                                                    .join("Dockerfile");
+        let build_root = &self.init_build.as_ref().unwrap().build_root;
+        let build_root_path = join_normalised(&invite_path(venue_path, name), &[build_root, "Dockerfile"]);
         handle.remove(&build_root_path)
     }
 
-    /// Gets the docker-compose files command string.
+    /// Checks that the build roots are directories and that every build file and runner file exists.
     /// 
     /// # Arguments
-    /// * `venue_path` - The path to the venue where all dependencies are stored
-    /// * `name` - The name of the repository where we can run the images
+    /// * `invite_path` - The path to the directory holding the wedding invite
     /// 
     /// # Returns
-    /// * `String` - The docker-compose files command string
-    pub fn get_docker_compose_files(&self, venue_path: &String, name: &String) -> String {
-        let invite_path = Path::new(&venue_path).join(&name).to_string_lossy().to_string();
-        let mut files_string = String::new();
+    /// * `Vec<String>` - A message for every problem found, empty if the wedding invite is valid
+    pub fn validate(&self, invite_path: &Path) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        check_build_root(invite_path, "build_root", &self.build_root, &mut problems);
+        if let Some(build_files) = &self.build_files {
+            check_build_files(invite_path, "build_files", build_files, &mut problems);
+        }
+        if let Some(init_build) = &self.init_build {
+            check_build_root(invite_path, "init_build.build_root", &init_build.build_root, &mut problems);
+            check_build_files(invite_path, "init_build.build_files", &init_build.build_files, &mut problems);
+        }
         for file in &self.runner_files {
-            files_string.push_str(&format!("-f {}/{} ", &invite_path, file));
+            if !invite_path.join(file.path()).is_file() {
+                problems.push(format!("runner_files {} does not exist", file.path()));
+            }
+        }
+        problems
+    }
+
+    /// Checks that the build roots, build files, and runner files are checked out by the sparse checkout of the 
+    /// dependency. A cone mode sparse checkout has every file in the listed directories and the files directly 
+    /// inside their parent directories, so a build root above a listed directory is kept.
+    /// 
+    /// # Arguments
+    /// * `sparse_paths` - The ```sparse_paths``` of the dependency in the seating plan
+    /// 
+    /// # Returns
+    /// * `Vec<String>` - A message for every path that is not checked out
+    pub fn sparse_problems(&self, sparse_paths: &[String]) -> Vec<String> {
+        let mut paths: Vec<(String, &str, bool)> = vec![("build_root".to_string(), &self.build_root, true)];
+        let mut build_files: Vec<(String, &HashMap<String, String>)> = Vec::new();
+        if let Some(files) = &self.build_files {
+            build_files.push(("build_files".to_string(), files));
         }
-        files_string
+        if let Some(init_build) = &self.init_build {
+            paths.push(("init_build.build_root".to_string(), &init_build.build_root, true));
+            build_files.push(("init_build.build_files".to_string(), &init_build.build_files));
+        }
+        for (field, files) in build_files {
+            let mut cpu_types: Vec<&String> = files.keys().collect();
+            cpu_types.sort();
+            for cpu_type in cpu_types {
+                paths.push((format!("{}.{}", field, cpu_type), &files[cpu_type], false));
+            }
+        }
+        let runner_files = [
+            ("runner_files", Some(&self.runner_files)),
+            ("remote_runner_files", self.remote_runner_files.as_ref()),
+            ("dev_runner_files", self.dev_runner_files.as_ref()),
+        ];
+        for (field, files) in runner_files {
+            for file in files.into_iter().flatten() {
+                paths.push((field.to_string(), file.path(), false));
+            }
+        }
+        paths.into_iter()
+             .filter(|(_, path, is_dir)| !in_sparse_checkout(path, *is_dir, sparse_paths))
+             .map(|(field, path, _)| format!("{} {} is outside the sparse_paths {}", field, path, sparse_paths.join(", ")))
+             .collect()
+    }
+
+    /// Gets the docker-compose files of the dependency whose conditions hold. The paths are returned as they are,
+    /// ```compose_args``` passes each of them as a separate argument so a venue with spaces or shell characters is passed whole.
+    /// 
+    /// # Arguments
+    /// * `venue_path` - The path to the venue where all dependencies are stored
+    /// * `name` - The name of the repository where we can run the images
+    /// 
+    /// # Returns
+    /// * `Vec<ComposeFileRef>` - The docker-compose files
+    pub fn get_docker_compose_files(&self, venue_path: &str, name: &str) -> Vec<ComposeFileRef> {
+        let invite_path = invite_path(venue_path, name);
+        included_paths(&self.runner_files).into_iter()
+            .map(|file| ComposeFileRef::new(name, join_normalised(&invite_path, &[file]).to_string_lossy().to_string()))
+            .collect()
     }
 
-    /// Gets the docker-compose files command string that run remote images.
+    /// Gets the docker-compose files of the dependency that run remote images whose conditions hold.
     /// 
     /// # Arguments
     /// * `venue_path` - The path to the venue where all dependencies are stored
     /// * `name` - The name of the repository where we can run the remote images
-    pub fn get_remote_compose_files(&self, venue_path: &String, name: &String) -> String {
-        let invite_path = Path::new(&venue_path).join(&name).to_string_lossy().to_string();
-        let mut files_string = String::new();
-        for file in self.remote_runner_files.as_ref().unwrap() {
-            files_string.push_str(&format!("-f {}/{} ", &invite_path, file));
+    /// 
+    /// # Returns
+    /// * `Result<Vec<ComposeFileRef>, String>` - The docker-compose files or an error if there are no remote runner files
+    pub fn get_remote_compose_files(&self, venue_path: &str, name: &str) -> Result<Vec<ComposeFileRef>, String> {
+        let remote_runner_files = match &self.remote_runner_files {
+            Some(files) => files,
+            None => return Err(format!("{} has no remote_runner_files", name))
+        };
+        let invite_path = invite_path(venue_path, name);
+        Ok(included_paths(remote_runner_files).into_iter()
+            .map(|file| ComposeFileRef::new(name, join_normalised(&invite_path, &[file]).to_string_lossy().to_string()))
+            .collect())
+    }
+
+    /// Gets the suffix of the remote image tags for the CPU type the dependencies are run on.
+    /// 
+    /// # Arguments
+    /// * `arch` - The CPU type passed in with ```--arch```, ```None``` uses the CPU type of the host
+    /// 
+    /// # Returns
+    /// * `Result<Option<&str>, WedpError>` - The suffix, ```None``` if the images are used as they are written, or 
+    ///   an error if the CPU type or a key of ```remote_image_arch_suffixes``` is not supported
+    pub fn remote_image_suffix(&self, arch: Option<&str>) -> Result<Option<&str>, WedpError> {
+        let suffixes = match &self.remote_image_arch_suffixes {
+            Some(suffixes) => suffixes,
+            None => return Ok(None)
+        };
+        arch_suffix(suffixes, &current_cpu_type(arch)?).map_err(WedpError::InvalidConfig)
+    }
+}
+
+
+/// Gets the cleaned path to the directory of a dependency in the venue.
+/// 
+/// # Arguments
+/// * `venue_path` - The path to the venue where all dependencies are stored
+/// * `name` - The name of the dependency in the venue directory
+/// 
+/// # Returns
+/// * `PathBuf` - The path to the dependency without ```.``` segments or trailing slashes
+fn invite_path(venue_path: &str, name: &str) -> PathBuf {
+    join_normalised(Path::new(venue_path), &[name])
+}
+
+
+/// Checks that a build root is a directory.
+/// 
+/// # Arguments
+/// * `invite_path` - The path to the directory holding the wedding invite
+/// * `field` - The name of the field the build root came from
+/// * `build_root` - The build root relative to the wedding invite
+/// * `problems` - The problems found so far
+fn check_build_root(invite_path: &Path, field: &str, build_root: &str, problems: &mut Vec<String>) {
+    if !invite_path.join(build_root).is_dir() {
+        problems.push(format!("{} {} is not a directory", field, build_root));
+    }
+}
+
+
+/// Checks that the Dockerfile of every CPU type exists, in CPU type order so the problems are reported in
+/// the same order every time.
+/// 
+/// # Arguments
+/// * `invite_path` - The path to the directory holding the wedding invite
+/// * `field` - The name of the field the build files came from
+/// * `build_files` - A map of Dockerfiles relating to CPU information
+/// * `problems` - The problems found so far
+fn check_build_files(invite_path: &Path, field: &str, build_files: &HashMap<String, String>, problems: &mut Vec<String>) {
+    let mut cpu_types: Vec<&String> = build_files.keys().collect();
+    cpu_types.sort();
+    for cpu_type in cpu_types {
+        let build_file = &build_files[cpu_type];
+        if !invite_path.join(build_file).is_file() {
+            problems.push(format!("{}.{} {} does not exist", field, cpu_type, build_file));
         }
-        files_string
+    }
+}
+
+
+/// Checks whether a path is checked out by a cone mode sparse checkout, a directory is checked out if it is 
+/// inside a sparse path or holds one and a file is checked out if the directory it is in is.
+/// 
+/// # Arguments
+/// * `path` - The path relative to the root of the repository
+/// * `is_dir` - Whether the path is a directory
+/// * `sparse_paths` - The directories of the sparse checkout
+/// 
+/// # Returns
+/// * `bool` - Whether the path is checked out
+fn in_sparse_checkout(path: &str, is_dir: bool, sparse_paths: &[String]) -> bool {
+    let path = resolve_parents(Path::new(path));
+    let directory = match is_dir {
+        true => path.as_path(),
+        false => path.parent().unwrap_or(Path::new(""))
+    };
+    if directory.as_os_str().is_empty() || directory == Path::new(".") {
+        return true
+    }
+    sparse_paths.iter()
+                .map(|sparse_path| resolve_parents(Path::new(sparse_path)))
+                .any(|sparse_path| directory.starts_with(&sparse_path) || sparse_path.starts_with(directory))
+}
+
+
+/// Gets the CPU type the build files are picked for.
+/// 
+/// # Arguments
+/// * `arch` - The CPU type passed in with ```--arch```, ```None``` uses the CPU type of the host
+/// 
+/// # Returns
+/// * `Result<String, WedpError>` - The name of the CPU type or an error if wedp does not support the CPU
+fn current_cpu_type(arch: Option<&str>) -> Result<String, WedpError> {
+    let cpu_type = match arch {
+        Some(arch) => CpuType::from_arch(arch),
+        None => CpuType::get()
+    };
+    match cpu_type {
+        Ok(cpu_type) => Ok(cpu_type.to_string()),
+        Err(error) => Err(WedpError::UnsupportedCpu(error))
+    }
+}
+
+/// Gets the CPU type a dependency picks its Dockerfile for. Only called once a Dockerfile is needed so a 
+/// locked build runs on a CPU type wedp does not support.
+/// 
+/// # Arguments
+/// * `arch` - The CPU type passed in with ```--arch```, ```None``` uses the CPU type of the host
+/// * `dependency` - The name of the dependency the Dockerfile is picked for
+/// 
+/// # Returns
+/// * `Result<String, WedpError>` - The name of the CPU type or an error naming the dependency if wedp does not 
+///   support the CPU
+fn build_cpu_type(arch: Option<&str>, dependency: &str) -> Result<String, WedpError> {
+    current_cpu_type(arch).map_err(|error| WedpError::UnsupportedCpu(format!(
+        "{}, {} needs it to pick a Dockerfile, set build_lock: true in its wedding invite to build it as it is",
+        error, dependency
+    )))
+}
+
+/// Gets the Dockerfile for a CPU type from a map of build files, falling back to the ```default``` key when the
+/// CPU type is not in the map.
+/// 
+/// # Arguments
+/// * `build_files` - A map of Dockerfiles relating to CPU information
+/// * `cpu_type` - The CPU type to get the Dockerfile for
+/// * `dependency` - The name of the dependency the build files are from, used in the error
+/// 
+/// # Returns
+/// * `Result<&String, WedpError>` - The path to the Dockerfile or an error if there is no Dockerfile for the CPU 
+///   type and no default
+fn get_build_file<'a>(build_files: &'a HashMap<String, String>, cpu_type: &str, dependency: &str) -> Result<&'a String, WedpError> {
+    match build_files.get(cpu_type).or_else(|| build_files.get(DEFAULT_BUILD_FILE)) {
+        Some(p) => Ok(p),
+        None => Err(WedpError::MissingBuildFile { cpu_type: cpu_type.to_string(), dependency: dependency.to_string() })
     }
 }
 
@@ -231,9 +525,37 @@ impl WeddingInvite {
 mod local_data_tests {
     
     use super::*;
+    use crate::compose_file::compose_args;
     use crate::file_handler::MockCoreFileHandle;
     use mockall::predicate::eq;
 
+    #[test]
+    fn test_remote_image_suffix() {
+        let wedding_invite = WeddingInvite::from_file("./tests/image_arch/wedding_invite.yml".to_string()).unwrap();
+        assert_eq!(wedding_invite.remote_image_suffix(Some("aarch64")).unwrap(), Some("-arm64"));
+        assert_eq!(wedding_invite.remote_image_suffix(Some("x86_64")).unwrap(), Some("-amd64"));
+        assert_eq!(wedding_invite.remote_image_suffix(Some("riscv64")).unwrap(), None);
+        assert!(matches!(wedding_invite.remote_image_suffix(Some("sparc")), Err(WedpError::UnsupportedCpu(_))));
+
+        let wedding_invite = WeddingInvite::from_file("./tests/test_repo/wedding_invite.yml".to_string()).unwrap();
+        assert_eq!(wedding_invite.remote_image_suffix(Some("aarch64")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_from_handle() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_read_to_string()
+            .with(eq(Path::new("auth/wedding_invite.yml")))
+            .times(1)
+            .returning(|_| Ok("build_root: \".\"\nrunner_files:\n  - runner_files/base.yml\n".to_string()));
+
+        let wedding_invite = WeddingInvite::from_handle("auth/wedding_invite.yml".to_string(), &mock_handle).unwrap();
+        assert_eq!(wedding_invite.build_root, ".");
+        assert_eq!(wedding_invite.runner_files, vec![RunnerFile::Path("runner_files/base.yml".to_string())]);
+        assert_eq!(wedding_invite.build_files, None);
+        mock_handle.checkpoint();
+    }
+
     #[test]
     fn test_from_file() {
         let mut normal_builds = HashMap::new();
@@ -255,10 +577,62 @@ mod local_data_tests {
         }));
     }
 
+    #[test]
+    fn test_validate() {
+        let wedding_invite = WeddingInvite::from_file("./tests/valid_repo/wedding_invite.yml".to_string()).unwrap();
+        assert_eq!(wedding_invite.validate(Path::new("./tests/valid_repo")), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_validate_missing_files() {
+        let wedding_invite = WeddingInvite::from_file("./tests/test_repo/wedding_invite.yml".to_string()).unwrap();
+        assert_eq!(
+            wedding_invite.validate(Path::new("./tests/test_repo")),
+            vec![
+                "build_files.aarch64 build/Dockerfile.aarch64 does not exist".to_string(),
+                "build_files.x86_64 build/Dockerfile.x86_64 does not exist".to_string(),
+                "init_build.build_root database is not a directory".to_string(),
+                "init_build.build_files.aarch64 database/build/Dockerfile.init.arch does not exist".to_string(),
+                "init_build.build_files.x86_64 database/build/Dockerfile.init does not exist".to_string(),
+                "runner_files runner_files/base.yml does not exist".to_string(),
+                "runner_files runner_files/database.yml does not exist".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_file_anchor() {
+        let ld = WeddingInvite::from_file("./tests/wedding_invite_anchors.yml".to_string()).unwrap();
+        assert_eq!(ld.init_build.unwrap().build_files, ld.build_files.unwrap());
+    }
+
+    #[test]
+    fn test_from_file_dangling_alias() {
+        let ld = WeddingInvite::from_file("./tests/wedding_invite_dangling_alias.yml".to_string());
+        assert!(matches!(ld, Err(WedpError::ConfigParse { .. })));
+        assert_eq!(
+            ld.map_err(|error| error.to_string()),
+            Err("Could not parse file: alias *init_builds at line 6 column 16 refers to an undefined anchor \
+                 &init_builds for ./tests/wedding_invite_dangling_alias.yml".to_string())
+        );
+    }
+
     #[test]
     fn test_from_file_missing() {
         let ld = WeddingInvite::from_file("./tests/wedding_invite_missing.yml".to_string());
-        assert!(ld.is_err());
+        assert!(matches!(ld, Err(WedpError::ConfigNotFound(path)) if path == "./tests/wedding_invite_missing.yml"));
+    }
+
+    #[test]
+    fn test_from_file_lenient() {
+        let (wedding_invite, unknown) = WeddingInvite::from_file_lenient("./tests/validate/typo_repo/wedding_invite.yml".to_string()).unwrap();
+        assert_eq!(wedding_invite.remote_runner_files, None);
+        assert_eq!(unknown.len(), 1);
+        assert!(unknown[0].starts_with("unknown field `remote_runer_files` at line 4 column 1, did you mean `remote_runner_files`?"), "{}", unknown[0]);
+
+        let (wedding_invite, unknown) = WeddingInvite::from_file_lenient("./tests/valid_repo/wedding_invite.yml".to_string()).unwrap();
+        assert_eq!(wedding_invite.runner_files.len(), 1);
+        assert_eq!(unknown, Vec::<String>::new());
     }
 
     #[test]
@@ -272,8 +646,8 @@ mod local_data_tests {
         wedding_invite.build_files = Some(normal_builds);
 
         let mut mock_handle = MockCoreFileHandle::new();
-        let from_path = Path::new("./tests/test_repo/build/Dockerfile.aarch64");
-        let to_path = Path::new("./tests/test_repo/./Dockerfile");
+        let from_path = Path::new("tests/test_repo/build/Dockerfile.aarch64");
+        let to_path = Path::new("tests/test_repo/Dockerfile");
 
         mock_handle.expect_copy()
             .with(eq(from_path), eq(to_path))
@@ -281,18 +655,124 @@ mod local_data_tests {
                 Ok(0)
             });
         let result = wedding_invite.prepare_build_file(
-            &"./tests".to_string(), &"test_repo".to_string(), 
+            "./tests", "test_repo", None,
             &mock_handle);
         assert!(result.is_ok());
         mock_handle.checkpoint(); 
     }
 
+    #[test]
+    fn test_prepare_build_file_arch() {
+        let mut wedding_invite = WeddingInvite::from_file("./tests/test_repo/wedding_invite.yml".to_string()).unwrap();
+        wedding_invite.build_files = Some(HashMap::from([
+            ("x86_64".to_string(), "build/Dockerfile.x86_64".to_string()),
+            ("aarch64".to_string(), "build/Dockerfile.aarch64".to_string()),
+        ]));
+
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_copy()
+            .with(eq(Path::new("tests/test_repo/build/Dockerfile.x86_64")), eq(Path::new("tests/test_repo/Dockerfile")))
+            .times(1)
+            .returning(|_, _| Ok(0));
+        mock_handle.expect_copy()
+            .with(eq(Path::new("tests/test_repo/database/build/Dockerfile.init.arch")), eq(Path::new("tests/test_repo/database/Dockerfile")))
+            .times(1)
+            .returning(|_, _| Ok(0));
+
+        let venue = "./tests".to_string();
+        let name = "test_repo".to_string();
+        assert!(wedding_invite.prepare_build_file(&venue, &name, Some("x86_64"), &mock_handle).is_ok());
+        assert!(wedding_invite.prepare_init_build_file(&venue, &name, Some("aarch64"), &mock_handle).is_ok());
+        let error = wedding_invite.prepare_build_file(&venue, &name, Some("amd64"), &mock_handle).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::Unsupported);
+        mock_handle.checkpoint();
+    }
+
+    #[test]
+    fn test_build_file_copy_arch_override() {
+        let mut builds = HashMap::new();
+        builds.insert("x86_64".to_string(), "build/Dockerfile.x86_64".to_string());
+        builds.insert("aarch64".to_string(), "build/Dockerfile.aarch64".to_string());
+        let mut wedding_invite = WeddingInvite::from_file("./tests/test_repo/wedding_invite.yml".to_string()).unwrap();
+        wedding_invite.build_files = Some(builds);
+
+        let copy = wedding_invite.build_file_copy("./tests", "test_repo", Some("aarch64")).unwrap().unwrap();
+        assert_eq!(copy.from, Path::new("tests/test_repo/build/Dockerfile.aarch64"));
+        let copy = wedding_invite.build_file_copy("./tests", "test_repo", Some("x86_64")).unwrap().unwrap();
+        assert_eq!(copy.from, Path::new("tests/test_repo/build/Dockerfile.x86_64"));
+
+        let error = wedding_invite.build_file_copy("./tests", "test_repo", Some("sparc")).unwrap_err();
+        assert!(matches!(error, WedpError::UnsupportedCpu(_)), "{:?}", error);
+    }
+
+    #[test]
+    fn test_sparse_problems() {
+        let wedding_invite = WeddingInvite::from_file("./tests/test_repo/wedding_invite.yml".to_string()).unwrap();
+        let sparse_paths = |paths: &[&str]| paths.iter().map(|path| path.to_string()).collect::<Vec<String>>();
+
+        assert!(wedding_invite.sparse_problems(&sparse_paths(&["build", "database/", "./runner_files"])).is_empty());
+        assert!(wedding_invite.sparse_problems(&sparse_paths(&["build", "database/build", "runner_files"])).is_empty());
+        assert_eq!(wedding_invite.sparse_problems(&sparse_paths(&["build", "database/build"])), vec![
+            "runner_files runner_files/base.yml is outside the sparse_paths build, database/build".to_string(),
+            "runner_files runner_files/database.yml is outside the sparse_paths build, database/build".to_string(),
+        ]);
+        assert_eq!(wedding_invite.sparse_problems(&sparse_paths(&["runner_files"])), vec![
+            "init_build.build_root database is outside the sparse_paths runner_files".to_string(),
+            "build_files.aarch64 build/Dockerfile.aarch64 is outside the sparse_paths runner_files".to_string(),
+            "build_files.x86_64 build/Dockerfile.x86_64 is outside the sparse_paths runner_files".to_string(),
+            "init_build.build_files.aarch64 database/build/Dockerfile.init.arch is outside the sparse_paths runner_files".to_string(),
+            "init_build.build_files.x86_64 database/build/Dockerfile.init is outside the sparse_paths runner_files".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_build_file_copy_default() {
+        let wedding_invite = WeddingInvite::from_file("./tests/default_build_repo/wedding_invite.yml".to_string()).unwrap();
+        for arch in ["x86_64", "aarch64", "riscv64"] {
+            let copy = wedding_invite.build_file_copy("./tests", "default_build_repo", Some(arch)).unwrap().unwrap();
+            assert_eq!(copy.from, Path::new("tests/default_build_repo/build/Dockerfile"));
+            assert_eq!(copy.to, Path::new("tests/default_build_repo/Dockerfile"));
+        }
+        let copy = wedding_invite.init_build_file_copy("./tests", "default_build_repo", Some("x86_64")).unwrap().unwrap();
+        assert_eq!(copy.from, Path::new("tests/default_build_repo/database/build/Dockerfile.init"));
+        let copy = wedding_invite.init_build_file_copy("./tests", "default_build_repo", Some("aarch64")).unwrap().unwrap();
+        assert_eq!(copy.from, Path::new("tests/default_build_repo/database/build/Dockerfile.init.arch"));
+
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_copy()
+            .with(eq(Path::new("tests/default_build_repo/build/Dockerfile")), eq(Path::new("tests/default_build_repo/Dockerfile")))
+            .times(1)
+            .returning(|_, _| Ok(0));
+        let venue = "./tests".to_string();
+        let name = "default_build_repo".to_string();
+        assert!(wedding_invite.prepare_build_file(&venue, &name, Some("aarch64"), &mock_handle).is_ok());
+    }
+
+    #[test]
+    fn test_build_file_copy_unsupported_arch() {
+        let mut wedding_invite = WeddingInvite::from_file("./tests/test_repo/wedding_invite.yml".to_string()).unwrap();
+        let error = wedding_invite.build_file_copy("./tests", "test_repo", Some("loongarch64")).unwrap_err().to_string();
+        assert!(error.starts_with("Unsupported CPU type: loongarch64"), "{}", error);
+        assert!(error.contains(", test_repo needs it to pick a Dockerfile, set build_lock: true"), "{}", error);
+        let error = wedding_invite.init_build_file_copy("./tests", "test_repo", Some("loongarch64")).unwrap_err();
+        assert!(matches!(error, WedpError::UnsupportedCpu(_)), "{:?}", error);
+
+        wedding_invite.build_lock = Some(true);
+        wedding_invite.init_build.as_mut().unwrap().build_lock = Some(true);
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_copy().times(0);
+        let venue = "./tests".to_string();
+        let name = "test_repo".to_string();
+        assert_eq!(wedding_invite.prepare_build_file(&venue, &name, Some("loongarch64"), &mock_handle).unwrap(), 0);
+        assert_eq!(wedding_invite.prepare_init_build_file(&venue, &name, Some("loongarch64"), &mock_handle).unwrap(), 0);
+    }
+
     #[test]
     fn test_delete_build_file() {
         let wedding_invite = WeddingInvite::from_file("./tests/test_repo/wedding_invite.yml".to_string()).unwrap();
 
         let mut mock_handle = MockCoreFileHandle::new();
-        let to_path = Path::new("./tests/test_repo/./Dockerfile");
+        let to_path = Path::new("tests/test_repo/Dockerfile");
 
         mock_handle.expect_remove()
             .with(eq(to_path))
@@ -300,7 +780,7 @@ mod local_data_tests {
                 Ok(())
             });
         let result = wedding_invite.delete_build_file(
-            &"./tests".to_string(), &"test_repo".to_string(), 
+            "./tests", "test_repo", 
             &mut mock_handle);
         assert!(result.is_ok());
         mock_handle.checkpoint(); 
@@ -320,8 +800,8 @@ mod local_data_tests {
         });
 
         let mut mock_handle = MockCoreFileHandle::new();
-        let from_path = Path::new("./tests/test_repo/database/build/Dockerfile.aarch64");
-        let to_path = Path::new("./tests/test_repo/database/Dockerfile");
+        let from_path = Path::new("tests/test_repo/database/build/Dockerfile.aarch64");
+        let to_path = Path::new("tests/test_repo/database/Dockerfile");
 
         mock_handle.expect_copy()
             .with(eq(from_path), eq(to_path))
@@ -329,12 +809,28 @@ mod local_data_tests {
                 Ok(0)
             });
         let result = wedding_invite.prepare_init_build_file(
-            &"./tests/".to_string(), &"test_repo".to_string(), 
+            "./tests/", "test_repo", None,
             &mut mock_handle);
         assert!(result.is_ok());
         mock_handle.checkpoint(); 
     }
 
+    #[test]
+    fn test_get_build_file_missing_cpu_type() {
+        let mut init_builds = HashMap::new();
+        init_builds.insert("x86_64".to_string(), "database/build/Dockerfile.init".to_string());
+        let init_build = InitBuild {
+            build_files: init_builds,
+            build_root: "database".to_string(),
+            build_lock: None
+        };
+
+        assert_eq!(get_build_file(&init_build.build_files, "x86_64", "database").unwrap(), "database/build/Dockerfile.init");
+        let error = get_build_file(&init_build.build_files, "aarch64", "database").unwrap_err();
+        assert!(matches!(&error, WedpError::MissingBuildFile { cpu_type, dependency } if cpu_type == "aarch64" && dependency == "database"));
+        assert_eq!(error.to_string(), "No build file for CPU type: aarch64 and no default in the wedding invite of database");
+    }
+
     #[test]
     fn test_delete_init_build_file() {
         let mut normal_builds = HashMap::new();
@@ -349,7 +845,7 @@ mod local_data_tests {
         });
 
         let mut mock_handle = MockCoreFileHandle::new();
-        let to_path = Path::new("./tests/test_repo/database/Dockerfile");
+        let to_path = Path::new("tests/test_repo/database/Dockerfile");
 
         mock_handle.expect_remove()
             .with(eq(to_path))
@@ -357,7 +853,7 @@ mod local_data_tests {
                 Ok(())
             });
         let result = wedding_invite.delete_init_build_file(
-            &"./tests/".to_string(), &"test_repo".to_string(), 
+            "./tests/", "test_repo", 
             &mut mock_handle);
         assert!(result.is_ok());
         mock_handle.checkpoint(); 
@@ -366,8 +862,50 @@ mod local_data_tests {
     #[test]
     fn test_get_docker_compose_files() {
         let wedding_invite = WeddingInvite::from_file("./tests/test_repo/wedding_invite.yml".to_string()).unwrap();
-        let docker_compose_files = wedding_invite.get_docker_compose_files(&"./tests/".to_string(), &"test_repo".to_string());
-        let expected_files = "-f ./tests/test_repo/runner_files/base.yml -f ./tests/test_repo/runner_files/database.yml ".to_string();
+        let docker_compose_files = wedding_invite.get_docker_compose_files("./tests/", "test_repo");
+        let expected_files = vec![
+            ComposeFileRef::new("test_repo", "tests/test_repo/runner_files/base.yml".to_string()),
+            ComposeFileRef::new("test_repo", "tests/test_repo/runner_files/database.yml".to_string()),
+        ];
         assert_eq!(docker_compose_files, expected_files);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_docker_compose_command_with_spaces_in_venue() {
+        let wedding_invite = WeddingInvite::from_file("./tests/test_repo/wedding_invite.yml".to_string()).unwrap();
+        let docker_compose_files = wedding_invite.get_docker_compose_files("./my venue/", "test_repo");
+        assert_eq!(
+            compose_args("docker-compose", &docker_compose_files).arg_refs(),
+            vec!["-f", "my venue/test_repo/runner_files/base.yml", "-f", "my venue/test_repo/runner_files/database.yml"]
+        );
+
+        let docker_compose_files = wedding_invite.get_docker_compose_files("./$HOME venue/", "test_repo");
+        let compose = compose_args("docker-compose", &docker_compose_files);
+        assert_eq!(
+            compose.arg_refs(),
+            vec!["-f", "$HOME venue/test_repo/runner_files/base.yml", "-f", "$HOME venue/test_repo/runner_files/database.yml"]
+        );
+        assert_eq!(
+            compose.to_string(),
+            "docker-compose -f '$HOME venue/test_repo/runner_files/base.yml' -f '$HOME venue/test_repo/runner_files/database.yml'"
+        );
+    }
+
+    #[test]
+    fn test_get_remote_compose_files() {
+        let mut wedding_invite = WeddingInvite::from_file("./tests/test_repo/wedding_invite.yml".to_string()).unwrap();
+        wedding_invite.remote_runner_files = Some(vec![RunnerFile::from("runner_files/remote.yml")]);
+        let remote_compose_files = wedding_invite.get_remote_compose_files("./tests/", "test_repo");
+        assert_eq!(remote_compose_files, Ok(vec![
+            ComposeFileRef::new("test_repo", "tests/test_repo/runner_files/remote.yml".to_string())
+        ]));
+    }
+
+    #[test]
+    fn test_get_remote_compose_files_missing() {
+        let mut wedding_invite = WeddingInvite::from_file("./tests/test_repo/wedding_invite.yml".to_string()).unwrap();
+        wedding_invite.remote_runner_files = None;
+        let remote_compose_files = wedding_invite.get_remote_compose_files("./tests/", "test_repo");
+        assert_eq!(remote_compose_files, Err("test_repo has no remote_runner_files".to_string()));
+    }
+}
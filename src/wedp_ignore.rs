@@ -0,0 +1,290 @@
+//! Support for the ```.wedpignore``` file in the working directory. The ```.wedpignore``` file uses gitignore
+//! style patterns to define paths in the working directory that wedp must never write into or watch.
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::file_handler::CoreFileHandle;
+
+
+/// The name of the ignore file in the working directory.
+pub const WEDP_IGNORE_FILE: &str = ".wedpignore";
+
+
+/// The ignore rules for the working directory.
+///
+/// # Fields
+/// * `root` - The working directory the patterns are relative to
+/// * `gitignore` - The compiled patterns of the ```.wedpignore``` file
+pub struct WedpIgnore {
+    pub root: PathBuf,
+    gitignore: Gitignore,
+}
+
+impl WedpIgnore {
+
+    /// Loads the ```.wedpignore``` file from the working directory. If there is no ```.wedpignore``` file
+    /// no paths are ignored.
+    ///
+    /// # Arguments
+    /// * `working_directory` - The path to the working directory
+    ///
+    /// # Returns
+    /// * `Result<WedpIgnore, String>` - The ignore rules or an error if the ```.wedpignore``` file is invalid
+    pub fn from_directory(working_directory: &str) -> Result<WedpIgnore, String> {
+        let ignore_path = Path::new(working_directory).join(WEDP_IGNORE_FILE);
+        let contents = match ignore_path.exists() {
+            true => match fs::read_to_string(&ignore_path) {
+                Ok(contents) => contents,
+                Err(error) => return Err(format!("Could not read {}: {}", ignore_path.to_string_lossy(), error))
+            },
+            false => String::new()
+        };
+        let patterns: Vec<&str> = contents.lines().collect();
+        WedpIgnore::from_patterns(working_directory, &patterns)
+    }
+
+    /// Creates the ignore rules from a list of patterns.
+    ///
+    /// # Arguments
+    /// * `working_directory` - The path to the working directory the patterns are relative to
+    /// * `patterns` - The gitignore style patterns
+    ///
+    /// # Returns
+    /// * `Result<WedpIgnore, String>` - The ignore rules or an error if a pattern is invalid
+    pub fn from_patterns(working_directory: &str, patterns: &[&str]) -> Result<WedpIgnore, String> {
+        let root = normalise(Path::new(working_directory));
+        let mut builder = GitignoreBuilder::new(&root);
+
+        for pattern in patterns {
+            if let Err(error) = builder.add_line(None, pattern) {
+                return Err(format!("Invalid {} pattern {}: {}", WEDP_IGNORE_FILE, pattern, error))
+            }
+        }
+        match builder.build() {
+            Ok(gitignore) => Ok(WedpIgnore { root, gitignore }),
+            Err(error) => Err(format!("Could not build {} patterns: {}", WEDP_IGNORE_FILE, error))
+        }
+    }
+
+    /// Checks if a path or any of its parent directories are ignored. Paths outside of the working
+    /// directory are never ignored.
+    ///
+    /// # Arguments
+    /// * `path` - The path to check
+    ///
+    /// # Returns
+    /// * `bool` - True if wedp must not write into or watch the path
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let path = normalise(path);
+        if !path.starts_with(&self.root) {
+            return false
+        }
+        let is_dir = path.is_dir();
+        self.gitignore.matched_path_or_any_parents(&path, is_dir).is_ignore()
+    }
+}
+
+
+/// A file handle that refuses to write into paths ignored by the ```.wedpignore``` file and passes
+/// everything else through to the wrapped file handle.
+///
+/// # Fields
+/// * `handle` - The file handle performing the file operations
+/// * `ignore` - The ignore rules of the working directory
+pub struct IgnoreFileHandle<'a> {
+    pub handle: &'a dyn CoreFileHandle,
+    pub ignore: WedpIgnore,
+}
+
+impl<'a> IgnoreFileHandle<'a> {
+
+    /// Creates a new IgnoreFileHandle struct.
+    ///
+    /// # Arguments
+    /// * `handle` - The file handle performing the file operations
+    /// * `ignore` - The ignore rules of the working directory
+    ///
+    /// # Returns
+    /// A new IgnoreFileHandle struct
+    pub fn new(handle: &'a dyn CoreFileHandle, ignore: WedpIgnore) -> Self {
+        IgnoreFileHandle { handle, ignore }
+    }
+
+    /// Returns an error if the path is ignored by the ```.wedpignore``` file.
+    ///
+    /// # Arguments
+    /// * `path` - The path that is about to be written to
+    ///
+    /// # Returns
+    /// * `Result<(), std::io::Error>` - A permission denied error if the path is ignored
+    fn check_writable(&self, path: &Path) -> Result<(), std::io::Error> {
+        match self.ignore.is_ignored(path) {
+            true => Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!("{} is ignored by {}", path.to_string_lossy(), WEDP_IGNORE_FILE)
+            )),
+            false => Ok(())
+        }
+    }
+}
+
+impl<'a> CoreFileHandle for IgnoreFileHandle<'a> {
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<u64, std::io::Error> {
+        self.check_writable(to)?;
+        self.handle.copy(from, to)
+    }
+
+    fn remove(&self, path: &Path) -> Result<(), std::io::Error> {
+        self.check_writable(path)?;
+        self.handle.remove(path)
+    }
+
+    fn remove_directory(&self, path: &Path) -> Result<(), std::io::Error> {
+        self.check_writable(path)?;
+        self.handle.remove_directory(path)
+    }
+
+    fn create_directory_if_not_exists(&self, path: &Path) -> Result<(), std::io::Error> {
+        self.check_writable(path)?;
+        self.handle.create_directory_if_not_exists(path)
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String, std::io::Error> {
+        self.handle.read_to_string(path)
+    }
+
+    fn write(&self, path: &Path, contents: &str) -> Result<(), std::io::Error> {
+        self.check_writable(path)?;
+        self.handle.write(path, contents)
+    }
+
+    fn append(&self, path: &Path, contents: &str) -> Result<(), std::io::Error> {
+        self.check_writable(path)?;
+        self.handle.append(path, contents)
+    }
+
+    fn modified(&self, path: &Path) -> Result<SystemTime, std::io::Error> {
+        self.handle.modified(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.handle.exists(path)
+    }
+
+    fn exists_no_follow(&self, path: &Path) -> bool {
+        self.handle.exists_no_follow(path)
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf, std::io::Error> {
+        self.handle.read_link(path)
+    }
+
+    fn dir_size(&self, path: &Path) -> Result<u64, std::io::Error> {
+        self.handle.dir_size(path)
+    }
+
+    fn symlink(&self, target: &Path, link: &Path) -> Result<(), std::io::Error> {
+        self.check_writable(link)?;
+        self.handle.symlink(target, link)
+    }
+
+    fn copy_directory(&self, from: &Path, to: &Path) -> Result<(), std::io::Error> {
+        self.check_writable(to)?;
+        self.handle.copy_directory(from, to)
+    }
+}
+
+
+/// Removes the ```.``` components from a path so paths like ```/work/./Dockerfile``` match the patterns.
+///
+/// # Arguments
+/// * `path` - The path to normalise
+///
+/// # Returns
+/// * `PathBuf` - The path without ```.``` components
+fn normalise(path: &Path) -> PathBuf {
+    path.components().filter(|component| *component != Component::CurDir).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::file_handler::MockCoreFileHandle;
+
+    static ROOT: &str = "/work";
+
+    fn test_ignore() -> WedpIgnore {
+        WedpIgnore::from_patterns(ROOT, &[
+            "secrets/",
+            "**/generated/",
+            "docker/*.env",
+            "!docker/example.env",
+        ]).unwrap()
+    }
+
+    #[test]
+    fn test_is_ignored_nested_patterns() {
+        let ignore = test_ignore();
+        assert!(ignore.is_ignored(Path::new("/work/secrets/Dockerfile")));
+        assert!(ignore.is_ignored(Path::new("/work/secrets/nested/Dockerfile")));
+        assert!(ignore.is_ignored(Path::new("/work/generated/Dockerfile")));
+        assert!(ignore.is_ignored(Path::new("/work/builds/api/generated/Dockerfile")));
+        assert!(!ignore.is_ignored(Path::new("/work/builds/api/Dockerfile")));
+        assert!(!ignore.is_ignored(Path::new("/work/./Dockerfile")));
+    }
+
+    #[test]
+    fn test_is_ignored_negation() {
+        let ignore = test_ignore();
+        assert!(ignore.is_ignored(Path::new("/work/docker/prod.env")));
+        assert!(!ignore.is_ignored(Path::new("/work/docker/example.env")));
+        assert!(!ignore.is_ignored(Path::new("/work/prod.env")));
+    }
+
+    #[test]
+    fn test_is_ignored_outside_working_directory() {
+        let ignore = test_ignore();
+        assert!(!ignore.is_ignored(Path::new("/other/secrets/Dockerfile")));
+    }
+
+    #[test]
+    fn test_from_directory_without_ignore_file() {
+        let ignore = WedpIgnore::from_directory("./tests/missing_working_directory").unwrap();
+        assert!(!ignore.is_ignored(Path::new("./tests/missing_working_directory/secrets/Dockerfile")));
+    }
+
+    #[test]
+    fn test_from_patterns_skips_comments() {
+        let ignore = WedpIgnore::from_patterns(ROOT, &["# generated output", "", "dist/"]).unwrap();
+        assert!(ignore.is_ignored(Path::new("/work/dist/Dockerfile")));
+        assert!(!ignore.is_ignored(Path::new("/work/# generated output")));
+    }
+
+    #[test]
+    fn test_copy_refuses_ignored_path() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_copy().times(0);
+        let handle = IgnoreFileHandle::new(&mock_handle, test_ignore());
+
+        let error = handle.copy(Path::new("/work/builds/Dockerfile"), Path::new("/work/secrets/Dockerfile")).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::PermissionDenied);
+        assert_eq!(error.to_string(), "/work/secrets/Dockerfile is ignored by .wedpignore");
+        mock_handle.checkpoint();
+    }
+
+    #[test]
+    fn test_copy_allowed_path() {
+        let mut mock_handle = MockCoreFileHandle::new();
+        mock_handle.expect_copy().times(1).returning(|_, _| Ok(10));
+        let handle = IgnoreFileHandle::new(&mock_handle, test_ignore());
+
+        let result = handle.copy(Path::new("/work/builds/Dockerfile"), Path::new("/work/./Dockerfile"));
+        assert_eq!(result.unwrap(), 10);
+        mock_handle.checkpoint();
+    }
+}
@@ -0,0 +1,286 @@
+//! Turns the errors from parsing the seating plan and the wedding invites into messages that point at the
+//! problem. The seating plan, the attendees, the wedding invites, and their init builds refuse keys they do not
+//! know, so a misspelled key such as ```attendes``` is reported with the line it is on and the key it was most
+//! likely meant to be instead of being silently ignored.
+//!
+//! ```validate``` lists every unknown key of a file rather than only the first, each unknown key is left out and
+//! the file is parsed again until no unknown keys are left.
+use serde::de::DeserializeOwned;
+
+use crate::error::WedpError;
+use crate::remote_refs::edit_distance;
+
+
+/// The start of the error serde gives for a key the struct does not have.
+const UNKNOWN_FIELD: &str = "unknown field `";
+
+
+/// Describes a YAML error, naming the undefined anchor when an alias points at an anchor that does not exist
+/// and suggesting the known key closest to an unknown key.
+///
+/// # Arguments
+/// * `error` - The error from parsing the YAML
+/// * `contents` - The YAML that was parsed
+///
+/// # Returns
+/// * `String` - The description of the error
+pub fn describe_yaml_error(error: &serde_yaml::Error, contents: &str) -> String {
+    let message = error.to_string();
+    if message.contains(UNKNOWN_FIELD) {
+        return describe_unknown_field(error, contents)
+    }
+    let location = match error.location() {
+        Some(location) if message.starts_with("unknown anchor") => location,
+        _ => return message
+    };
+    let anchor: String = contents.get(location.index()..).unwrap_or("")
+        .trim_start_matches('*')
+        .chars()
+        .take_while(|c| !c.is_whitespace() && !",[]{}".contains(*c))
+        .collect();
+    format!(
+        "alias *{} at line {} column {} refers to an undefined anchor &{}",
+        anchor, location.line(), location.column(), anchor
+    )
+}
+
+
+/// Leaves out every key the struct does not have, so the rest of a file can still be checked.
+///
+/// # Arguments
+/// * `contents` - The YAML to parse
+///
+/// # Returns
+/// * `(String, Vec<String>)` - The YAML with each unknown key and its value commented out, and the description
+///   of every unknown key
+pub fn strip_unknown_fields<T: DeserializeOwned>(contents: &str) -> (String, Vec<String>) {
+    let mut lines: Vec<String> = contents.lines().map(String::from).collect();
+    let mut problems = Vec::new();
+    for _ in 0..lines.len() {
+        let current = lines.join("\n");
+        let error = match serde_yaml::from_str::<T>(&current) {
+            Err(error) if error.to_string().contains(UNKNOWN_FIELD) => error,
+            _ => break
+        };
+        problems.push(describe_unknown_field(&error, &current));
+        let message = error.to_string();
+        let field = message.split(UNKNOWN_FIELD).nth(1).and_then(|rest| rest.split('`').next()).unwrap_or_default();
+        let location = error.location().map(|location| (location.line(), location.column())).or_else(|| find_key(&current, field));
+        match location {
+            Some((line, column)) if line > 0 && column > 0 && line <= lines.len() => comment_out_key(&mut lines, line - 1, column - 1),
+            _ => break
+        }
+    }
+    let mut stripped = lines.join("\n");
+    if contents.ends_with('\n') {
+        stripped.push('\n');
+    }
+    (stripped, problems)
+}
+
+
+/// Adds the unknown keys that were left out of a file to the error parsing the rest of it, as leaving out a 
+/// misspelled key that is required makes the file fail with a missing key.
+///
+/// # Arguments
+/// * `error` - The error parsing the file without the unknown keys
+/// * `unknown` - The description of every unknown key that was left out
+///
+/// # Returns
+/// * `WedpError` - The error with the unknown keys in its message
+pub fn with_unknown_fields(error: WedpError, unknown: &[String]) -> WedpError {
+    match error {
+        WedpError::ConfigParse { path, message } if !unknown.is_empty() => WedpError::ConfigParse {
+            path,
+            message: format!("{}; {}", unknown.join("; "), message)
+        },
+        error => error
+    }
+}
+
+
+/// Comments out a key and the lines of its value. A key that starts a list item keeps the ```-``` so the
+/// other keys of the item stay in it.
+///
+/// # Arguments
+/// * `lines` - The lines of the YAML
+/// * `index` - The index of the line the key is on
+/// * `column` - The index of the first character of the key on the line
+fn comment_out_key(lines: &mut [String], index: usize, column: usize) {
+    let indent = |line: &str| line.len() - line.trim_start().len();
+    let prefix = lines[index].get(..column).unwrap_or_default().to_string();
+    lines[index] = match prefix.trim_end().ends_with('-') {
+        true => prefix.trim_end().to_string(),
+        false => format!("{}# {}", prefix, lines[index].get(column..).unwrap_or_default())
+    };
+    for line in lines[index + 1..].iter_mut() {
+        if !line.trim().is_empty() && indent(line) <= column {
+            break
+        }
+        if !line.trim().is_empty() {
+            *line = format!("# {}", line);
+        }
+    }
+}
+
+
+/// Describes a key the struct does not have with the line it is on and the closest known key. Serde only
+/// knows the line of keys below the top level, the line of a top level key is looked up in the contents.
+///
+/// # Arguments
+/// * `error` - The unknown field error from parsing the YAML
+/// * `contents` - The YAML that was parsed
+///
+/// # Returns
+/// * `String` - The description of the error
+fn describe_unknown_field(error: &serde_yaml::Error, contents: &str) -> String {
+    let message = error.to_string();
+    let message = match error.location() {
+        Some(location) => message.trim_end_matches(&format!(" at line {} column {}", location.line(), location.column())).to_string(),
+        None => message
+    };
+    let start = message.find(UNKNOWN_FIELD).unwrap_or_default();
+    let (prefix, rest) = message.split_at(start);
+    let rest = &rest[UNKNOWN_FIELD.len()..];
+    let (field, expected) = rest.split_once('`').unwrap_or((rest, ""));
+    let expected = expected.trim_start_matches(", ");
+    let known: Vec<&str> = expected.split('`').skip(1).step_by(2).collect();
+
+    let location = error.location().map(|location| (location.line(), location.column())).or_else(|| find_key(contents, field));
+    let mut described = format!("{}unknown field `{}`", prefix, field);
+    if let Some((line, column)) = location {
+        described.push_str(&format!(" at line {} column {}", line, column));
+    }
+    match closest_key(field, &known) {
+        Some(closest) => described.push_str(&format!(", did you mean `{}`? Expected {}", closest, expected.trim_start_matches("expected "))),
+        None if !expected.is_empty() => described.push_str(&format!(", {}", expected)),
+        None => {}
+    }
+    described
+}
+
+
+/// Finds the first line a key is written on.
+///
+/// # Arguments
+/// * `contents` - The YAML the key is in
+/// * `key` - The key to look for
+///
+/// # Returns
+/// * `Option<(usize, usize)>` - The line and column of the key counting from one, ```None``` if it is not found
+fn find_key(contents: &str, key: &str) -> Option<(usize, usize)> {
+    contents.lines().enumerate().find_map(|(index, line)| {
+        let trimmed = line.trim_start();
+        let unlisted = trimmed.strip_prefix("- ").unwrap_or(trimmed).trim_start();
+        match unlisted.strip_prefix(key).is_some_and(|rest| rest.trim_start().starts_with(':')) {
+            true => Some((index + 1, line.len() - unlisted.len() + 1)),
+            false => None
+        }
+    })
+}
+
+
+/// Picks the known key closest to an unknown key, if one is within a few edits of it.
+///
+/// # Arguments
+/// * `field` - The unknown key
+/// * `known` - The keys the struct has
+///
+/// # Returns
+/// * `Option<&str>` - The closest known key within a third of the length of the unknown key and at least two edits
+fn closest_key<'a>(field: &str, known: &[&'a str]) -> Option<&'a str> {
+    let limit = (field.chars().count() / 3).max(2);
+    known.iter()
+         .map(|key| (edit_distance(field, key), *key))
+         .filter(|(distance, _)| *distance <= limit)
+         .min()
+         .map(|(_, key)| key)
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::seating_plan::SeatingPlan;
+    use crate::wedding_invite::WeddingInvite;
+
+    fn plan_error(contents: &str) -> String {
+        let error = serde_yaml::from_str::<SeatingPlan>(contents).unwrap_err();
+        describe_yaml_error(&error, contents)
+    }
+
+    #[test]
+    fn test_unknown_top_level_key() {
+        assert_eq!(
+            plan_error("attendes: []\nvenue: ./venue\n"),
+            "unknown field `attendes` at line 1 column 1, did you mean `attendees`? Expected one of `attendees`, `venue`, \
+             `command_defaults`, `restart_policy`, `compose_command`, `engine`, `max_docker_ops`, `max_git_ops`, `clone_depth`, \
+             `auth`, `git_protocol`"
+        );
+        assert!(plan_error("attendees: []\nvenu: ./venue\n").starts_with("unknown field `venu` at line 2 column 1, did you mean `venue`?"));
+    }
+
+    #[test]
+    fn test_unknown_attendee_key() {
+        let contents = "attendees:\n  - name: auth\n    url: https://github.com/org/auth\n    brnch: main\nvenue: ./venue\n";
+        assert!(
+            plan_error(contents).starts_with("attendees[0]: unknown field `brnch` at line 4 column 5, did you mean `branch`?"),
+            "{}", plan_error(contents)
+        );
+    }
+
+    #[test]
+    fn test_unknown_key_without_suggestion() {
+        let error = plan_error("attendees: []\nvenue: ./venue\ncolour: blue\n");
+        assert!(error.starts_with("unknown field `colour` at line 3 column 1, expected one of `attendees`"), "{}", error);
+    }
+
+    #[test]
+    fn test_unknown_invite_key() {
+        let contents = "build_root: .\nrunner_file:\n  - runner_files/base.yml\n";
+        let error = serde_yaml::from_str::<WeddingInvite>(contents).unwrap_err();
+        assert!(
+            describe_yaml_error(&error, contents).starts_with("unknown field `runner_file` at line 2 column 1, did you mean `runner_files`?"),
+            "{}", describe_yaml_error(&error, contents)
+        );
+    }
+
+    #[test]
+    fn test_strip_unknown_fields() {
+        let contents = "attendes: []\nattendees:\n  - brnch: main\n    name: auth\n    url: https://github.com/org/auth\n    branch: main\n    extra:\n      nested: true\n  - name: billing\n    url: https://github.com/org/billing\n    branch: main\nvenue: ./venue\n";
+        let (stripped, problems) = strip_unknown_fields::<SeatingPlan>(contents);
+        assert_eq!(problems.len(), 3, "{:?}", problems);
+        assert!(problems[0].starts_with("unknown field `attendes` at line 1 column 1, did you mean `attendees`?"), "{}", problems[0]);
+        assert!(problems[1].starts_with("attendees[0]: unknown field `brnch` at line 3 column 5, did you mean `branch`?"), "{}", problems[1]);
+        assert!(problems[2].starts_with("attendees[0]: unknown field `extra` at line 7 column 5"), "{}", problems[2]);
+
+        let seating_plan: SeatingPlan = serde_yaml::from_str(&stripped).unwrap();
+        assert_eq!(seating_plan.attendees.len(), 2);
+        assert_eq!(seating_plan.attendees[0].name, "auth");
+        assert_eq!(stripped.lines().count(), contents.lines().count());
+
+        let contents = "attendees: []\nvenue: ./venue\n";
+        assert_eq!(strip_unknown_fields::<SeatingPlan>(contents), (contents.to_string(), Vec::new()));
+    }
+
+    #[test]
+    fn test_with_unknown_fields() {
+        let contents = "build_root: \".\"\nruner_files:\n  - runner_files/base.yml\n";
+        let (stripped, unknown) = strip_unknown_fields::<WeddingInvite>(contents);
+        let error = serde_yaml::from_str::<WeddingInvite>(&stripped).unwrap_err();
+        let error = with_unknown_fields(WedpError::ConfigParse { path: "wedding_invite.yml".to_string(), message: error.to_string() }, &unknown);
+        let message = error.to_string();
+        assert!(message.starts_with("Could not parse file: unknown field `runer_files` at line 2 column 1, did you mean `runner_files`?"), "{}", message);
+        assert!(message.contains("; missing field `runner_files`"), "{}", message);
+        assert_eq!(with_unknown_fields(WedpError::InvalidConfig("bad".to_string()), &unknown).to_string(), "bad");
+    }
+
+    #[test]
+    fn test_find_key() {
+        assert_eq!(find_key("venue: ./venue\n", "venue"), Some((1, 1)));
+        assert_eq!(find_key("attendees:\n  - nmae: auth\n", "nmae"), Some((2, 5)));
+        assert_eq!(find_key("venues: ./venue\nvenue : ./venue\n", "venue"), Some((2, 1)));
+        assert_eq!(find_key("venue: ./venue\n", "attendes"), None);
+    }
+}
@@ -0,0 +1,105 @@
+//! Runs the demo end to end through the binary: generates it, installs it from the local git repos and again 
+//! from a vendored archive, checks it, and removes it again. Nothing in this test needs docker.
+use assert_cmd::Command;
+use predicates::str::contains;
+use std::path::PathBuf;
+
+
+fn demo_directory() -> PathBuf {
+    let directory = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("wedp-demo");
+    if directory.exists() {
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+    directory
+}
+
+
+#[test]
+fn test_demo_end_to_end() {
+    let directory = demo_directory();
+    let seating_plan = directory.join("wedding_planner.yml");
+
+    Command::cargo_bin("wedp").unwrap()
+        .args(["demo", directory.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(contains("wedp demo --cleanup"));
+    assert!(directory.join("repos/greeter/.git").is_dir());
+    assert!(directory.join("repos/listener/.git").is_dir());
+
+    Command::cargo_bin("wedp").unwrap()
+        .args(["setup", "-f", seating_plan.to_str().unwrap()])
+        .assert()
+        .success();
+
+    Command::cargo_bin("wedp").unwrap()
+        .args(["install", "-f", seating_plan.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(contains("2 installed\n"));
+    assert!(directory.join("venue/greeter/wedding_invite.yml").is_file());
+    assert!(directory.join("venue/listener/Dockerfile").is_file());
+
+    let local_change = directory.join("venue/greeter/notes.txt");
+    std::fs::write(&local_change, "kept between installs").unwrap();
+    Command::cargo_bin("wedp").unwrap()
+        .args(["install", "-f", seating_plan.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(contains("greeter: fetched into"))
+        .stdout(contains("2 installed\n"));
+    assert!(local_change.is_file());
+
+    let archive = directory.join("vendor.tar.gz");
+    Command::cargo_bin("wedp").unwrap()
+        .args(["vendor", "-o", archive.to_str().unwrap(), "-f", seating_plan.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(contains("Vendored 2 dependencies into"));
+    let manifest = std::fs::read_to_string(directory.join("venue/wedp-vendor-manifest.json")).unwrap();
+    let greeter_commit = std::process::Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(directory.join("venue/greeter"))
+        .output()
+        .unwrap();
+    assert!(manifest.contains(&format!("\"commit\": \"{}\"", String::from_utf8_lossy(&greeter_commit.stdout).trim())));
+    std::fs::remove_dir_all(directory.join("venue")).unwrap();
+    Command::cargo_bin("wedp").unwrap()
+        .args(["install", "--archive", archive.to_str().unwrap(), "-f", seating_plan.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(contains("greeter: extracted from"))
+        .stdout(contains("2 installed\n"));
+    assert!(local_change.is_file());
+    assert!(directory.join("venue/listener/wedding_invite.yml").is_file());
+    assert!(!directory.join("venue/listener/.git").exists());
+
+    Command::cargo_bin("wedp").unwrap()
+        .args(["validate", "-f", seating_plan.to_str().unwrap()])
+        .assert()
+        .success();
+
+    Command::cargo_bin("wedp").unwrap()
+        .args(["compose-files", "-f", seating_plan.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(contains("venue/greeter/runner_files/base.yml"))
+        .stdout(contains("venue/listener/runner_files/base.yml"));
+
+    Command::cargo_bin("wedp").unwrap()
+        .args(["demo", "--cleanup", directory.to_str().unwrap()])
+        .assert()
+        .success();
+    assert!(!directory.exists());
+}
+
+
+#[test]
+fn test_demo_cleanup_refuses_other_directories() {
+    Command::cargo_bin("wedp").unwrap()
+        .args(["demo", "--cleanup", "tests/valid_repo"])
+        .assert()
+        .failure()
+        .stderr(contains("is not a wedp demo, not removing it"));
+    assert!(PathBuf::from("tests/valid_repo/wedding_invite.yml").is_file());
+}
@@ -0,0 +1,591 @@
+//! Runs the top level run, remoterun, and logs commands through the binary with ```--dry-run``` to check the
+//! docker-compose commands they would run. Nothing in this test needs docker.
+use assert_cmd::Command;
+use predicates::prelude::PredicateBooleanExt;
+use predicates::str::contains;
+
+
+static COMPOSE: &str = "docker-compose -f tests/test_repo/runner_files/base.yml -f tests/test_repo/runner_files/database.yml";
+
+
+fn wedp(args: &[&str]) -> Command {
+    let mut command = Command::cargo_bin("wedp").unwrap();
+    command.env("WEDP_COMPOSE", "docker-compose")
+           .args(["--dry-run", "-f", "tests/run_seating_plan.yml"])
+           .args(args);
+    command
+}
+
+
+#[test]
+fn test_run_foreground() {
+    wedp(&["run"])
+        .assert()
+        .success()
+        .stdout(contains(format!("DRY-RUN: {} up\n", COMPOSE)))
+        .stdout(contains(" up -d").not());
+}
+
+
+#[test]
+fn test_run_abort_on_exit_invalid_service() {
+    wedp(&["run", "--abort-on-exit=-f"])
+        .assert()
+        .code(1)
+        .stdout(contains("not a valid service name").not())
+        .stderr(contains("-f is not a valid service name"));
+}
+
+
+#[test]
+fn test_run_detach() {
+    for flag in ["-d", "--detach"] {
+        wedp(&["run", flag])
+            .assert()
+            .success()
+            .stdout(contains(format!("DRY-RUN: {} up -d\n", COMPOSE)))
+            .stdout(contains(format!("DRY-RUN: {} ps\n", COMPOSE)))
+            .stdout(contains("Follow the logs with: wedp logs\n"));
+    }
+}
+
+
+#[test]
+fn test_remoterun_detach() {
+    wedp(&["remoterun", "-d"])
+        .assert()
+        .success()
+        .stdout(contains(" up -d\n"))
+        .stdout(contains("Follow the logs with: wedp logs --remote\n"));
+}
+
+
+#[test]
+fn test_legacy_detach_commands() {
+    wedp(&["run-d"])
+        .assert()
+        .success()
+        .stdout(contains(format!("DRY-RUN: {} up -d\n", COMPOSE)))
+        .stderr(contains("Warning: run-d is deprecated, use wedp run -d instead\n"));
+    wedp(&["remoterun-d", "--wait"])
+        .assert()
+        .success()
+        .stdout(contains(" up -d --wait\n"))
+        .stderr(contains("Warning: remoterun-d is deprecated, use wedp remoterun -d instead\n"));
+}
+
+
+#[test]
+fn test_detach_wait() {
+    wedp(&["run", "-d", "--wait"])
+        .assert()
+        .success()
+        .stdout(contains(format!("DRY-RUN: {} up -d --wait\n", COMPOSE)))
+        .stdout(contains("DRY-RUN: write tests/.wedp/run.json\n"));
+    wedp(&["remoterun", "--detach", "--wait"])
+        .assert()
+        .success()
+        .stdout(contains(" up -d --wait\n"))
+        .stdout(contains("DRY-RUN: write tests/.wedp/run.json\n"))
+        .stdout(contains("Follow the logs with: wedp logs --remote\n"));
+}
+
+
+#[test]
+fn test_logs() {
+    wedp(&["logs", "auth_db", "auth"])
+        .assert()
+        .success()
+        .stdout(contains(format!("DRY-RUN: {} logs -f --tail=100 auth_db auth\n", COMPOSE)));
+    wedp(&["logs", "--tail", "20", "auth"])
+        .assert()
+        .success()
+        .stdout(contains(format!("DRY-RUN: {} logs -f --tail=20 auth\n", COMPOSE)));
+    wedp(&["logs", "--no-follow", "--tail", "5"])
+        .assert()
+        .success()
+        .stdout(contains(format!("DRY-RUN: {} logs --tail=5\n", COMPOSE)));
+    wedp(&["logs", "--tail", "many"])
+        .assert()
+        .failure()
+        .stderr(contains("many is not a whole number of lines"));
+    wedp(&["logs", "--no-follow"])
+        .assert()
+        .success()
+        .stdout(contains(format!("DRY-RUN: {} logs\n", COMPOSE)));
+    wedp(&["logs", "--", "-f"])
+        .assert()
+        .code(1)
+        .stderr(contains("-f is not a valid service name"));
+}
+
+
+#[test]
+fn test_dry_run_changes_nothing() {
+    let cache_dir = std::env::temp_dir().join("wedp_run_tests").join("dry_run_cache");
+    let _ = std::fs::remove_dir_all(&cache_dir);
+    for (args, expected) in [
+        (vec!["build"], format!("DRY-RUN: {} build\n", COMPOSE)),
+        (vec!["run", "-d"], format!("DRY-RUN: {} up -d\n", COMPOSE)),
+        (vec!["install"], "DRY-RUN: cd ".to_string()),
+        (vec!["teardown"], format!("DRY-RUN: {} down\n", COMPOSE)),
+    ] {
+        wedp(&args)
+            .env("WEDP_CACHE_DIR", &cache_dir)
+            .assert()
+            .success()
+            .stdout(contains(expected))
+            .stdout(contains(format!("DRY-RUN: write {}", cache_dir.to_string_lossy())));
+    }
+    assert!(!cache_dir.exists());
+}
+
+
+#[test]
+fn test_only_and_except() {
+    wedp(&["build", "--only", "test_repo"])
+        .assert()
+        .success()
+        .stdout(contains(format!("DRY-RUN: {} build\n", COMPOSE)));
+    wedp(&["build", "--except", "test_repo"])
+        .assert()
+        .success()
+        .stdout(contains("DRY-RUN: docker-compose build\n"));
+    wedp(&["teardown", "--except", "test_repo"])
+        .assert()
+        .success()
+        .stdout(contains("DRY-RUN: docker-compose down\n"));
+    wedp(&["install", "--only", "test_repo,auth"])
+        .assert()
+        .code(65)
+        .stderr(contains("auth not in the seating plan, valid names are test_repo"));
+    wedp(&["run", "--only", "test_repo", "--except", "auth"])
+        .assert()
+        .failure();
+}
+
+
+#[test]
+fn test_update() {
+    wedp(&["update"])
+        .assert()
+        .success()
+        .stdout(contains("tests/test_repo && git status --porcelain\n"))
+        .stdout(contains("tests/test_repo && git remote set-branches origin '*'\n"))
+        .stdout(contains("tests/test_repo && git fetch --tags\n"))
+        .stdout(contains("tests/test_repo && git checkout master\n"))
+        .stdout(contains("tests/test_repo && git pull --ff-only\n"))
+        .stdout(contains("git clone").not());
+    wedp(&["install", "--no-clobber"])
+        .assert()
+        .success()
+        .stdout(contains("tests/test_repo && git pull --ff-only\n"))
+        .stdout(contains("wedding_planner.lock").not());
+    wedp(&["install", "--no-clobber", "--force"])
+        .assert()
+        .failure();
+}
+
+
+#[test]
+fn test_run_detach_then_logs() {
+    wedp(&["run", "-d", "--then-logs"])
+        .assert()
+        .success()
+        .stdout(contains(format!("DRY-RUN: {} up -d\n", COMPOSE)))
+        .stdout(contains("DRY-RUN: write tests/.wedp/run.json\n"))
+        .stdout(contains(format!("DRY-RUN: {} logs -f --tail=100\n", COMPOSE)))
+        .stdout(contains("the dependencies are still running, stop them with: wedp teardown\n"));
+    wedp(&["run", "--then-logs"])
+        .assert()
+        .failure()
+        .stderr(contains("--then-logs needs --detach"));
+}
+
+
+#[test]
+fn test_compose_override() {
+    wedp(&["run"])
+        .env("WEDP_COMPOSE", "docker compose")
+        .assert()
+        .success()
+        .stdout(contains("DRY-RUN: docker compose -f tests/test_repo/runner_files/base.yml"));
+    wedp(&["run"])
+        .env("WEDP_COMPOSE", "docker-compose; id")
+        .assert()
+        .code(65)
+        .stderr(contains("compose_command docker-compose; id is not a valid command in WEDP_COMPOSE"))
+        .stdout(contains(" up").not());
+}
+
+
+#[test]
+fn test_compose_cmd_flag() {
+    wedp(&["--compose-cmd", "docker compose", "run"])
+        .assert()
+        .success()
+        .stdout(contains("DRY-RUN: docker compose -f tests/test_repo/runner_files/base.yml"));
+    wedp(&["--compose-cmd", "docker-compose; id", "run"])
+        .assert()
+        .failure()
+        .stderr(contains("compose_command docker-compose; id is not a valid command"));
+}
+
+
+#[test]
+fn test_engine_flag() {
+    wedp(&["--engine", "docker-compose-v2", "run"])
+        .env_remove("WEDP_COMPOSE")
+        .assert()
+        .success()
+        .stdout(contains("DRY-RUN: docker compose -f tests/test_repo/runner_files/base.yml"));
+    wedp(&["--engine", "containerd", "run"])
+        .assert()
+        .failure()
+        .stderr(contains("engine containerd is not one of docker, docker-compose-v2, podman"));
+}
+
+
+#[test]
+fn test_conditional_runner_files() {
+    let conditional = |debug: Option<&str>| {
+        let mut command = Command::cargo_bin("wedp").unwrap();
+        command.env("WEDP_COMPOSE", "docker-compose")
+               .env_remove("WEDP_TEST_DEBUG")
+               .args(["--dry-run", "-f", "tests/conditional_seating_plan.yml", "run"]);
+        if let Some(debug) = debug {
+            command.env("WEDP_TEST_DEBUG", debug);
+        }
+        command
+    };
+    conditional(Some("1"))
+        .assert()
+        .success()
+        .stdout(contains(
+            "DRY-RUN: docker-compose -f tests/conditional_repo/runner_files/base.yml \
+             -f tests/conditional_repo/runner_files/debug.yml up\n"
+        ));
+    for debug in [None, Some("0")] {
+        conditional(debug)
+            .assert()
+            .success()
+            .stdout(contains("DRY-RUN: docker-compose -f tests/conditional_repo/runner_files/base.yml up\n"))
+            .stdout(contains("debug.yml").not());
+    }
+}
+
+
+#[test]
+fn test_check() {
+    wedp(&["check"])
+        .assert()
+        .success()
+        .stdout(contains(format!("DRY-RUN: {} config -q\n", COMPOSE)));
+    wedp(&["check", "--no-daemon"])
+        .assert()
+        .failure()
+        .stdout(contains("tests/test_repo/runner_files/base.yml: could not read: "))
+        .stderr(contains("2 problems found in the compose files of "));
+}
+
+
+#[test]
+fn test_teardown_remove_venue() {
+    wedp(&["teardown", "--remove-venue"])
+        .assert()
+        .success()
+        .stdout(contains(format!("DRY-RUN: {} down\nDRY-RUN: rm -r tests/test_repo\n", COMPOSE)));
+    wedp(&["teardown"])
+        .assert()
+        .success()
+        .stdout(contains("rm -r").not());
+}
+
+
+#[test]
+fn test_ps_raw() {
+    wedp(&["ps", "--raw"])
+        .assert()
+        .success()
+        .stdout(contains(format!("DRY-RUN: {} ps\n", COMPOSE)));
+}
+
+
+#[test]
+fn test_config_exit_codes() {
+    let plan = |path: &str| {
+        let mut command = Command::cargo_bin("wedp").unwrap();
+        command.args(["--dry-run", "-f", path, "build"]);
+        command
+    };
+    plan("tests/missing_seating_plan.yml")
+        .assert()
+        .code(66)
+        .stderr(contains("tests/missing_seating_plan.yml does not exist"));
+    plan("tests/seating_plan_branch_and_rev.yml")
+        .assert()
+        .code(65)
+        .stderr(contains("a rev can only be given on its own"));
+    plan("tests/wedding_invite.yml")
+        .assert()
+        .code(65)
+        .stderr(contains("Could not parse file: unknown field"));
+}
+
+
+#[test]
+fn test_merged_seating_plans() {
+    let mut command = Command::cargo_bin("wedp").unwrap();
+    command.args(["--dry-run", "-f", "tests/merge_core_seating_plan.yml", "-f", "tests/merge_data_seating_plan.yml", "status"])
+        .assert()
+        .success()
+        .stdout(contains("auth ").and(contains("billing ")).and(contains("postgres ")).and(contains("redis ")));
+    let mut command = Command::cargo_bin("wedp").unwrap();
+    command.args(["--dry-run", "-f", "tests/merge_core_seating_plan.yml,tests/merge_core_seating_plan.yml", "status"])
+        .assert()
+        .code(65)
+        .stderr(contains("attendee auth is already in another seating plan"));
+}
+
+
+#[test]
+fn test_clean() {
+    wedp(&["clean"])
+        .assert()
+        .success()
+        .stdout(contains("DRY-RUN: rm -r tests/test_repo\n").and(contains("rm -r tests\n").not()));
+    wedp(&["clean", "--all"])
+        .assert()
+        .success()
+        .stdout(contains("DRY-RUN: rm -r tests/test_repo\n").and(contains("DRY-RUN: rm -r tests\n")));
+}
+
+
+#[test]
+fn test_clean_all_from_subdirectory() {
+    let directory = std::env::temp_dir().join(format!("wedp_clean_subdirectory_{}", std::process::id()));
+    std::fs::create_dir_all(directory.join("venue").join("auth")).unwrap();
+    std::fs::create_dir_all(directory.join("docs")).unwrap();
+    std::fs::write(directory.join("wedding_planner.yml"), "attendees: []\nvenue: ./venue\n").unwrap();
+    let directory = directory.canonicalize().unwrap();
+    let clean_all = |from: &str| {
+        let mut command = Command::cargo_bin("wedp").unwrap();
+        command.env_remove("WEDP_PLAN_FILE").current_dir(directory.join(from)).args(["--dry-run", "clean", "--all"]);
+        command
+    };
+
+    clean_all("docs")
+        .assert()
+        .success()
+        .stdout(contains(format!("DRY-RUN: rm -r {}\n", directory.join("venue").to_string_lossy())));
+    clean_all("venue/auth")
+        .assert()
+        .failure()
+        .stderr(contains(format!("resolves to {}, refusing to remove it", directory.join("venue").to_string_lossy())));
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+
+#[test]
+fn test_interpolated_seating_plan() {
+    let status = |args: &[&str]| {
+        let mut command = Command::cargo_bin("wedp").unwrap();
+        command.env_remove("WEDP_TEST_GIT_HOST")
+               .env_remove("WEDP_TEST_AUTH_BRANCH")
+               .args(["--dry-run", "-f", "tests/interpolated_seating_plan.yml"])
+               .args(args)
+               .arg("status");
+        command
+    };
+    status(&[])
+        .env("WEDP_TEST_GIT_HOST", "git.example.com")
+        .assert()
+        .success()
+        .stdout(contains("develop"));
+    status(&[])
+        .assert()
+        .code(65)
+        .stderr(contains("WEDP_TEST_GIT_HOST is not set for url at line 3 of "));
+    status(&["--no-interpolate"])
+        .assert()
+        .success()
+        .stdout(contains("${WEDP_TEST_AUTH_BRANCH:-develop}"));
+}
+
+
+#[test]
+fn test_validate() {
+    let validate = |path: &str| {
+        let mut command = Command::cargo_bin("wedp").unwrap();
+        command.args(["-f", path, "validate", "--no-verify-refs"]);
+        command
+    };
+    validate("tests/validate/seating_plan.yml")
+        .assert()
+        .code(1)
+        .stdout(contains("unknown field `brnch` at line 4 column 5, did you mean `branch`?"))
+        .stdout(contains("unknown field `restart_polcy` at line 12 column 1, did you mean `restart_policy`?"))
+        .stdout(contains("attendee typo_repo is listed more than once"))
+        .stdout(contains("url billing service of billing does not look like a git remote"))
+        .stdout(contains("unknown field `remote_runer_files` at line 4 column 1"))
+        .stderr(contains("6 problems found in"));
+    validate("tests/live_test.yml")
+        .assert()
+        .success()
+        .stdout(contains("is valid"));
+}
+
+
+#[test]
+fn test_trace_script() {
+    let script = std::env::temp_dir().join(format!("wedp_trace_{}.sh", std::process::id()));
+    let _ = std::fs::remove_file(&script);
+    wedp(&["--trace-script", script.to_str().unwrap(), "run", "-d"])
+        .assert()
+        .success();
+    let contents = std::fs::read_to_string(&script).unwrap();
+    std::fs::remove_file(&script).unwrap();
+    assert!(contents.starts_with("#!/bin/sh\n"), "{}", contents);
+    assert!(contents.contains(&format!("{} up -d)\n", COMPOSE)), "{}", contents);
+}
+
+
+#[test]
+fn test_run_before_install() {
+    for args in [&["run"][..], &["remoterun", "-d"][..]] {
+        let mut command = Command::cargo_bin("wedp").unwrap();
+        command.env("WEDP_COMPOSE", "docker-compose")
+               .args(["-f", "tests/live_test.yml"])
+               .args(args)
+               .assert()
+               .failure()
+               .stderr(contains("venue ./sandbox/services/ does not exist, run `wedp setup && wedp install` first\n"))
+               .stderr(contains("missing attendees: institution\n"));
+    }
+    assert!(!std::path::Path::new("sandbox").exists());
+}
+
+
+#[test]
+fn test_init() {
+    let directory = std::env::temp_dir().join(format!("wedp_init_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    let init = |args: &[&str]| {
+        let mut command = Command::cargo_bin("wedp").unwrap();
+        command.current_dir(&directory).args(args);
+        command
+    };
+    init(&["--dry-run", "init", "invite"]).assert().success();
+    assert!(!directory.join("wedding_invite.yml").exists());
+
+    init(&["init", "invite"]).assert().success().stdout(contains("wedding_invite.yml"));
+    init(&["init", "plan"]).assert().success();
+    init(&["init", "plan"])
+        .assert()
+        .failure()
+        .stderr(contains("wedding_planner.yml already exists, pass --force to overwrite it"));
+    init(&["init", "plan", "--force"]).assert().success();
+    let contents = std::fs::read_to_string(directory.join("wedding_planner.yml")).unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+    assert!(contents.contains("venue: ./sandbox/services\n"), "{}", contents);
+}
+
+
+#[test]
+fn test_doctor() {
+    let directory = std::env::temp_dir().join(format!("wedp_doctor_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("wedding_planner.yml"), "attendees: []\nvenue: ./services\n").unwrap();
+    let doctor = |args: &[&str]| {
+        let mut command = Command::cargo_bin("wedp").unwrap();
+        command.current_dir(&directory).args(args);
+        command
+    };
+    doctor(&["doctor"])
+        .assert()
+        .failure()
+        .stdout(contains("venue services does not exist (fixable)\n"))
+        .stderr(contains("2 problems found in").and(contains("2 can be fixed with --fix")));
+
+    doctor(&["doctor", "--fix"])
+        .assert()
+        .success()
+        .stdout(contains("fixed: venue services does not exist\n"))
+        .stdout(contains("fixed: venue services has no .gitignore"))
+        .stdout(contains("No problems found"));
+    assert!(directory.join("services/.gitignore").exists());
+
+    std::fs::write(directory.join("services/wedp_restart_override.yml"), "services: {}\n").unwrap();
+    doctor(&["doctor", "--fix"])
+        .assert()
+        .success()
+        .stdout(contains("fixed: services/wedp_restart_override.yml is left over"));
+    let leftover = directory.join("services/wedp_restart_override.yml").exists();
+    std::fs::remove_dir_all(&directory).unwrap();
+    assert!(!leftover);
+}
+
+
+#[test]
+fn test_log_levels() {
+    wedp(&["--quiet", "run", "-d"])
+        .assert()
+        .success()
+        .stdout(contains(format!("DRY-RUN: {} up -d\n", COMPOSE)))
+        .stdout(contains("Follow the logs with").not())
+        .stderr(contains("Running run").not());
+    wedp(&["--verbose", "run", "-d"])
+        .assert()
+        .success()
+        .stdout(contains("Follow the logs with: wedp logs\n"))
+        .stderr(contains("+ docker ps -a --filter label=com.docker.compose.project"));
+    wedp(&["--verbose", "--quiet", "run"])
+        .assert()
+        .failure()
+        .stderr(contains("cannot be used with"));
+}
+
+
+#[test]
+fn test_lock() {
+    wedp(&["lock"])
+        .assert()
+        .success()
+        .stdout(contains("tests/test_repo && git rev-parse HEAD\n"))
+        .stdout(contains("git fetch").not())
+        .stdout(contains("/tests/wedding_planner.lock\n"));
+    wedp(&["install", "--locked"])
+        .assert()
+        .failure()
+        .stderr(contains("/tests/wedding_planner.lock"))
+        .stderr(contains("run wedp lock to create it"));
+}
+
+
+#[test]
+fn test_seating_plan_not_found() {
+    Command::cargo_bin("wedp").unwrap()
+        .env("WEDP_PLAN_FILE", "wedp_run_test_missing_plan.yml")
+        .current_dir(std::env::temp_dir())
+        .args(["--dry-run", "validate"])
+        .assert()
+        .failure()
+        .stderr(contains("No wedp_run_test_missing_plan.yml found in "))
+        .stderr(contains("pass -f to name the seating plan"));
+}
+
+
+#[test]
+fn test_prepare_diff_fails_when_a_dockerfile_can_not_be_read() {
+    wedp(&["prepare", "--diff"])
+        .assert()
+        .code(1)
+        .stderr(contains("test_repo: could not read "))
+        .stdout(contains("could not read").not());
+    wedp(&["prepare", "--diff", "--only", "missing"])
+        .assert()
+        .failure()
+        .stderr(contains("missing not in the seating plan"));
+}